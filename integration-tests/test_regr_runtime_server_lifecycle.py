@@ -17,11 +17,15 @@ re-starts it on the same port. The cycle repeats several times. PASS = no
 returns a fresh server id (not "Error: ...").
 """
 import json
+import threading
+import time
+import urllib.error
+import urllib.request
 
 import pytest
 
 
-def _start_payload(host: str, port: int) -> str:
+def _start_payload(host: str, port: int, main_service_path: str = "main.ts") -> str:
     """Minimal server config JSON. We don't need a real main_service —
     we never actually hit it, we only care that the server binds the
     port, listens, and then releases it cleanly on shutdown."""
@@ -31,7 +35,7 @@ def _start_payload(host: str, port: int) -> str:
         # main.ts under cwd; the start path normalizes it. The server
         # tolerates a non-existent path enough to bind and listen for the
         # short window before we call stop.
-        "main_service_path": "main.ts",
+        "main_service_path": main_service_path,
         "no_module_cache": True,
         # Short request_idle_timeout / graceful exit so stop returns fast.
         "graceful_exit_deadline_sec": 5,
@@ -130,3 +134,364 @@ def test_runtime_stop_releases_port_for_external_bind(node_factory):
         s.bind((host, port))
     finally:
         s.close()
+
+
+SLOW_MAIN_SERVICE = """
+Deno.serve(async (req: Request) => {
+  if (new URL(req.url).pathname === "/slow") {
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    return new Response("done");
+  }
+  return new Response("ok");
+});
+"""
+
+
+def _get(url: str, timeout: float) -> str:
+    with urllib.request.urlopen(url, timeout=timeout) as resp:
+        return resp.read().decode()
+
+
+def test_runtime_stop_drains_in_flight_request(node_factory, tmp_path):
+    """stop waits for a request that is already running instead of
+    dropping it, and reports the shutdown as graceful."""
+    main = tmp_path / "index.ts"
+    main.write_text(SLOW_MAIN_SERVICE)
+
+    node = node_factory(load_db=False, load_trexas=True)
+
+    port = 28293
+    host = "127.0.0.1"
+    cfg_json = _start_payload(host, port, str(main))
+
+    started = node.execute(
+        f"SELECT trex_runtime_start_with_config('{cfg_json}')", timeout=120
+    )
+    msg = started[0][0]
+    assert "started" in msg.lower(), msg
+    server_id = msg.split("Trex server started:", 1)[1].strip()
+
+    base_url = f"http://{host}:{port}"
+    deadline = time.time() + 30
+    while True:
+        try:
+            assert _get(f"{base_url}/", timeout=5) == "ok"
+            break
+        except Exception:
+            if time.time() > deadline:
+                raise
+            time.sleep(0.5)
+
+    result = {}
+
+    def slow_request():
+        try:
+            result["body"] = _get(f"{base_url}/slow", timeout=30)
+        except Exception as e:
+            result["error"] = e
+
+    request = threading.Thread(target=slow_request)
+    request.start()
+    time.sleep(0.5)
+
+    stopped = node.execute(
+        f"SELECT trex_runtime_stop('{server_id}')", timeout=60
+    )
+    request.join(timeout=30)
+
+    assert result.get("body") == "done", result
+    stop_msg = stopped[0][0]
+    assert "stopped" in stop_msg.lower(), stop_msg
+    assert "graceful" in stop_msg.lower(), stop_msg
+
+
+def test_runtime_stop_forces_request_past_deadline(node_factory, tmp_path):
+    """A request still running when graceful_exit_deadline_sec runs out is
+    cut off: stop returns shortly after the deadline, the client never gets
+    a response, and the port is free again. Whether the runtime or
+    stop_server ends the drain first is a race, so the reported outcome is
+    not checked."""
+    main = tmp_path / "index.ts"
+    main.write_text(SLOW_MAIN_SERVICE)
+
+    node = node_factory(load_db=False, load_trexas=True)
+
+    port = 28299
+    host = "127.0.0.1"
+    cfg = json.loads(_start_payload(host, port, str(main)))
+    # Shorter than the 2s the slow handler takes.
+    cfg["graceful_exit_deadline_sec"] = 1
+    cfg_json = json.dumps(cfg).replace("'", "''")
+
+    started = node.execute(
+        f"SELECT trex_runtime_start_with_config('{cfg_json}')", timeout=120
+    )
+    msg = started[0][0]
+    assert "started" in msg.lower(), msg
+    server_id = msg.split("Trex server started:", 1)[1].strip()
+
+    base_url = f"http://{host}:{port}"
+    deadline = time.time() + 30
+    while True:
+        try:
+            assert _get(f"{base_url}/", timeout=5) == "ok"
+            break
+        except Exception:
+            if time.time() > deadline:
+                raise
+            time.sleep(0.5)
+
+    result = {}
+
+    def slow_request():
+        try:
+            result["body"] = _get(f"{base_url}/slow", timeout=30)
+        except Exception as e:
+            result["error"] = e
+
+    request = threading.Thread(target=slow_request)
+    request.start()
+    time.sleep(0.5)
+
+    begin = time.time()
+    stopped = node.execute(
+        f"SELECT trex_runtime_stop('{server_id}')", timeout=60
+    )
+    elapsed = time.time() - begin
+    request.join(timeout=30)
+
+    stop_msg = stopped[0][0]
+    assert "stopped" in stop_msg.lower(), stop_msg
+    assert elapsed < 10, f"stop took {elapsed:.1f}s"
+    assert "body" not in result, result
+
+    import socket
+
+    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as s:
+        s.setsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR, 1)
+        s.bind((host, port))
+
+
+def test_runtime_start_rejects_bad_import_map(node_factory, tmp_path):
+    """A missing or non-JSON import map fails the start instead of the
+    first request."""
+    node = node_factory(load_db=False, load_trexas=True)
+
+    bad_json = tmp_path / "import_map.json"
+    bad_json.write_text("{ imports: ")
+    for path in (tmp_path / "missing.json", bad_json):
+        cfg = json.loads(_start_payload("127.0.0.1", 28294))
+        cfg["import_map_path"] = str(path)
+        cfg_json = json.dumps(cfg).replace("'", "''")
+        started = node.execute(
+            f"SELECT trex_runtime_start_with_config('{cfg_json}')", timeout=60
+        )
+        msg = started[0][0]
+        assert msg.startswith("Error converting config"), msg
+        assert "import map" in msg.lower(), msg
+
+
+def _serve_text(body: str) -> str:
+    return f'Deno.serve(() => new Response("{body}"));\n'
+
+
+def _wait_for_body(url: str, expected: str, timeout: float = 30) -> str:
+    deadline = time.time() + timeout
+    body = None
+    while time.time() < deadline:
+        try:
+            body = _get(url, timeout=5)
+            if body == expected:
+                return body
+        except Exception:
+            pass
+        time.sleep(0.5)
+    return body
+
+
+def test_runtime_watch_reloads_on_change(node_factory, tmp_path):
+    """With watch on, editing the main service swaps in the new code, and
+    an edit that does not build leaves the running server alone."""
+    main = tmp_path / "index.ts"
+    main.write_text(_serve_text("v1"))
+
+    node = node_factory(load_db=False, load_trexas=True)
+
+    port = 28295
+    host = "127.0.0.1"
+    cfg = json.loads(_start_payload(host, port, str(main)))
+    cfg["watch"] = True
+    cfg_json = json.dumps(cfg).replace("'", "''")
+
+    started = node.execute(
+        f"SELECT trex_runtime_start_with_config('{cfg_json}')", timeout=120
+    )
+    msg = started[0][0]
+    assert "started" in msg.lower(), msg
+    server_id = msg.split("Trex server started:", 1)[1].strip()
+
+    url = f"http://{host}:{port}/"
+    assert _wait_for_body(url, "v1") == "v1"
+
+    main.write_text(_serve_text("v2"))
+    assert _wait_for_body(url, "v2", timeout=60) == "v2"
+
+    listing = node.execute(
+        "SELECT server_id, last_reload FROM trex_runtime_list()", timeout=30
+    )
+    row = next(r for r in listing if r[0] == server_id)
+    assert row[1] != "never", row
+
+    main.write_text("Deno.serve(() => new Response(")
+    time.sleep(5)
+    assert _get(url, timeout=5) == "v2"
+
+    node.execute(f"SELECT trex_runtime_stop('{server_id}')", timeout=60)
+
+
+def test_runtime_watch_reload_keeps_serving(node_factory, tmp_path):
+    """A reload hands the listening socket to the new code: requests made
+    while it swaps are all answered, by either version."""
+    main = tmp_path / "index.ts"
+    main.write_text(_serve_text("v1"))
+
+    node = node_factory(load_db=False, load_trexas=True)
+
+    port = 28297
+    host = "127.0.0.1"
+    cfg = json.loads(_start_payload(host, port, str(main)))
+    cfg["watch"] = True
+    cfg_json = json.dumps(cfg).replace("'", "''")
+
+    started = node.execute(
+        f"SELECT trex_runtime_start_with_config('{cfg_json}')", timeout=120
+    )
+    msg = started[0][0]
+    assert "started" in msg.lower(), msg
+    server_id = msg.split("Trex server started:", 1)[1].strip()
+
+    url = f"http://{host}:{port}/"
+    assert _wait_for_body(url, "v1") == "v1"
+
+    bodies = []
+    failures = []
+    done = threading.Event()
+
+    def poll():
+        while not done.is_set():
+            try:
+                bodies.append(_get(url, timeout=10))
+            except Exception as e:
+                failures.append(repr(e))
+            time.sleep(0.05)
+
+    poller = threading.Thread(target=poll)
+    poller.start()
+    try:
+        main.write_text(_serve_text("v2"))
+        assert _wait_for_body(url, "v2", timeout=60) == "v2"
+        time.sleep(1)
+    finally:
+        done.set()
+        poller.join()
+
+    assert not failures, failures[:5]
+    assert set(bodies) <= {"v1", "v2"}, set(bodies)
+
+    node.execute(f"SELECT trex_runtime_stop('{server_id}')", timeout=60)
+
+
+def test_runtime_server_metrics_count_and_reset(node_factory, tmp_path):
+    """trex_server_metrics reports per-server request counts and latency
+    percentiles, and trex_reset_server_metrics zeroes them."""
+    main = tmp_path / "index.ts"
+    main.write_text(
+        "Deno.serve((req) => new URL(req.url).pathname === '/missing'\n"
+        "  ? new Response('no', { status: 404 })\n"
+        "  : new Response('ok'));\n"
+    )
+
+    node = node_factory(load_db=False, load_trexas=True)
+
+    port = 28296
+    host = "127.0.0.1"
+    cfg_json = _start_payload(host, port, str(main))
+    started = node.execute(
+        f"SELECT trex_runtime_start_with_config('{cfg_json}')", timeout=120
+    )
+    msg = started[0][0]
+    assert "started" in msg.lower(), msg
+    server_id = msg.split("Trex server started:", 1)[1].strip()
+
+    base_url = f"http://{host}:{port}"
+    assert _wait_for_body(f"{base_url}/", "ok") == "ok"
+    node.execute(f"SELECT trex_reset_server_metrics('{server_id}')", timeout=30)
+
+    for _ in range(5):
+        assert _get(f"{base_url}/", timeout=5) == "ok"
+    for _ in range(2):
+        with pytest.raises(urllib.error.HTTPError) as err:
+            _get(f"{base_url}/missing", timeout=5)
+        assert err.value.code == 404
+
+    metrics_sql = (
+        "SELECT total_requests, status_2xx, status_4xx, status_5xx, "
+        "p50_ms, p95_ms, p99_ms "
+        f"FROM trex_server_metrics('{server_id}')"
+    )
+    rows = node.execute(metrics_sql, timeout=30)
+    assert len(rows) == 1, rows
+    total, ok, client_err, server_err, p50, p95, p99 = rows[0]
+    assert (total, ok, client_err, server_err) == (7, 5, 2, 0), rows[0]
+    assert 0 <= p50 <= p95 <= p99, rows[0]
+
+    reset = node.execute(
+        f"SELECT trex_reset_server_metrics('{server_id}')", timeout=30
+    )
+    assert "reset" in reset[0][0].lower(), reset
+    rows = node.execute(metrics_sql, timeout=30)
+    assert rows[0][0] == 0 and rows[0][4] is None, rows[0]
+
+    assert node.execute(
+        "SELECT * FROM trex_server_metrics('trex_no_such_server')", timeout=30
+    ) == []
+
+    node.execute(f"SELECT trex_runtime_stop('{server_id}')", timeout=60)
+
+
+def test_runtime_watched_server_metrics(node_factory, tmp_path):
+    """A watched server is reached through the front that keeps its socket
+    across reloads; its requests are still counted once each."""
+    main = tmp_path / "index.ts"
+    main.write_text(_serve_text("v1"))
+
+    node = node_factory(load_db=False, load_trexas=True)
+
+    port = 28298
+    host = "127.0.0.1"
+    cfg = json.loads(_start_payload(host, port, str(main)))
+    cfg["watch"] = True
+    cfg_json = json.dumps(cfg).replace("'", "''")
+
+    started = node.execute(
+        f"SELECT trex_runtime_start_with_config('{cfg_json}')", timeout=120
+    )
+    msg = started[0][0]
+    assert "started" in msg.lower(), msg
+    server_id = msg.split("Trex server started:", 1)[1].strip()
+
+    url = f"http://{host}:{port}/"
+    assert _wait_for_body(url, "v1") == "v1"
+    node.execute(f"SELECT trex_reset_server_metrics('{server_id}')", timeout=30)
+
+    for _ in range(3):
+        assert _get(url, timeout=5) == "v1"
+
+    rows = node.execute(
+        "SELECT total_requests, status_2xx "
+        f"FROM trex_server_metrics('{server_id}')",
+        timeout=30,
+    )
+    assert rows == [(3, 3)], rows
+
+    node.execute(f"SELECT trex_runtime_stop('{server_id}')", timeout=60)
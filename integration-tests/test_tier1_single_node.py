@@ -58,6 +58,23 @@ def test_swarm_self_discovery(node_factory):
     assert len(nodes) >= 1, f"Expected at least 1 node, got {len(nodes)}"
 
 
+def test_swarm_node_advertises_resources(node_factory):
+    """The resource sampler publishes cpu_pct and free_mem_mb via gossip."""
+    node = node_factory(load_db=True)
+
+    node.execute(f"SELECT trex_db_start('0.0.0.0', {node.gossip_port}, 'test-cluster')")
+
+    rows = wait_for(
+        node,
+        "SELECT cpu_pct, free_mem_mb FROM trex_db_nodes()",
+        lambda rows: len(rows) >= 1 and rows[0][0] is not None,
+        timeout=15,
+    )
+    cpu_pct, free_mem_mb = rows[0]
+    assert 0.0 <= cpu_pct <= 100.0, f"cpu_pct out of range: {cpu_pct}"
+    assert free_mem_mb > 0, f"Expected free memory, got {free_mem_mb}"
+
+
 def test_swarm_tables_single_node(node_factory):
     """Single-node trex_db_tables() shows local table."""
     node = node_factory(load_db=True)
@@ -109,3 +109,64 @@ def test_distributed_query_regions(node_factory):
     assert "EU" in region_counts, f"Missing EU region in {region_counts}"
     assert region_counts["US"] == 1000, f"US count {region_counts['US']} != 1000"
     assert region_counts["EU"] == 1000, f"EU count {region_counts['EU']} != 1000"
+
+
+def test_cluster_config_flags_drift(node_factory):
+    """trex_db_cluster_config() lists both nodes' config and marks keys
+    whose values differ between them."""
+    node_a, node_b = _setup_two_nodes(node_factory)
+
+    node_a.execute("SELECT trex_db_set('replication_factor', '2')")
+    node_b.execute("SELECT trex_db_set('replication_factor', '3')")
+    node_a.execute("SELECT trex_db_set('default_priority', 'batch')")
+    node_b.execute("SELECT trex_db_set('default_priority', 'batch')")
+
+    rows = wait_for(
+        node_a,
+        "SELECT node_name, value, consistent FROM trex_db_cluster_config() "
+        "WHERE key = 'replication_factor' ORDER BY value",
+        lambda rows: len(rows) == 2,
+        timeout=15,
+    )
+    assert [r[1] for r in rows] == ["2", "3"], rows
+    assert all(r[2] is False for r in rows), rows
+
+    rows = wait_for(
+        node_a,
+        "SELECT DISTINCT consistent FROM trex_db_cluster_config() "
+        "WHERE key = 'default_priority'",
+        lambda rows: rows == [(True,)],
+        timeout=15,
+    )
+    assert rows == [(True,)], rows
+
+
+def test_kill_node_evicts_peer(node_factory):
+    """trex_db_kill_node() drops a peer from the cluster view, is idempotent,
+    and refuses to kill the local node."""
+    node_a, node_b = _setup_two_nodes(node_factory)
+
+    wait_for(
+        node_a,
+        "SELECT * FROM trex_db_nodes()",
+        lambda rows: len(rows) >= 2,
+        timeout=15,
+    )
+    self_name = node_a.execute(
+        "SELECT value FROM trex_db_config() WHERE key = 'node_name'"
+    )[0][0]
+    peer_name = node_a.execute(
+        f"SELECT node_name FROM trex_db_nodes() WHERE node_name != '{self_name}'"
+    )[0][0]
+
+    result = node_a.execute(f"SELECT trex_db_kill_node('{peer_name}')")[0][0]
+    assert result.startswith(f"Killed node '{peer_name}'"), result
+
+    names = [r[0] for r in node_a.execute("SELECT node_name FROM trex_db_nodes()")]
+    assert peer_name not in names, names
+
+    again = node_a.execute(f"SELECT trex_db_kill_node('{peer_name}')")[0][0]
+    assert "already removed" in again, again
+
+    local = node_a.execute(f"SELECT trex_db_kill_node('{self_name}')")[0][0]
+    assert local.startswith("Error:") and "trex_db_stop" in local, local
@@ -317,6 +317,62 @@ def test_distributed_sharded_count(node_factory):
     assert int(result[0][0]) == 2000
 
 
+def test_distributed_query_profile_per_shard(node_factory):
+    """trex_db_query_profiled times each shard scan and keeps the profile."""
+    scheduler, _ = _setup_two_nodes_sharded(node_factory)
+
+    profile = wait_for(
+        scheduler,
+        "SELECT * FROM trex_db_query_profiled('SELECT id FROM orders')",
+        lambda rows: any(r[1] == "query" for r in rows),
+        timeout=15,
+    )
+    # Columns: query_id, fragment, node_name, rows, bytes_shuffled, wall_ms.
+    scans = [r for r in profile if r[1].startswith("scan orders[")]
+    assert len(scans) == 2, f"Expected one scan per shard, got {profile}"
+    assert sum(r[3] for r in scans) == 2000
+    assert all(r[5] >= 0.0 for r in profile)
+    total = next(r for r in profile if r[1] == "query")
+    assert total[3] == 2000
+
+    stored = scheduler.execute(
+        f"SELECT fragment, rows FROM trex_db_query_profile('{total[0]}')"
+    )
+    assert sorted(stored) == sorted((r[1], r[3]) for r in profile)
+
+
+def test_distributed_export_parquet_directory(node_factory, tmp_path):
+    """trex_db_export writes a sharded query result to Parquet files."""
+    scheduler, _ = _setup_two_nodes_sharded(node_factory)
+    out_dir = f"{tmp_path}/orders/"
+
+    result = scheduler.execute(
+        f"SELECT trex_db_export('SELECT id, region FROM orders', '{out_dir}', 'parquet')"
+    )
+    message = result[0][0]
+    assert message.startswith("Exported 2000 row(s)"), message
+
+    files = list((tmp_path / "orders").glob("*.parquet"))
+    assert files, "no Parquet files written"
+    assert f"to {len(files)} file(s)" in message
+    count = scheduler.execute(f"SELECT COUNT(*) FROM read_parquet('{out_dir}*.parquet')")
+    assert int(count[0][0]) == 2000
+
+
+def test_distributed_export_single_csv(node_factory, tmp_path):
+    """A path without a trailing slash gets a single CSV file."""
+    scheduler, _ = _setup_two_nodes_sharded(node_factory)
+    out_file = tmp_path / "eu.csv"
+
+    query = "SELECT id FROM orders WHERE region = ''EU''"
+    result = scheduler.execute(
+        f"SELECT trex_db_export('{query}', '{out_file}', 'csv')"
+    )
+    assert result[0][0] == f"Exported 1000 row(s) to 1 file(s) at {out_file}"
+    count = scheduler.execute(f"SELECT COUNT(*) FROM read_csv('{out_file}')")
+    assert int(count[0][0]) == 1000
+
+
 def test_distributed_sharded_aggregation(node_factory):
     """SUM, MIN, MAX, AVG across two sharded partitions."""
     scheduler, _ = _setup_two_nodes_sharded(node_factory)
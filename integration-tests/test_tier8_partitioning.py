@@ -1,7 +1,7 @@
 """Tier 8: Table partitioning integration tests.
 
-Verifies trex_db_partition_table, trex_db_create_table, trex_db_repartition_table,
-and trex_db_partitions across multi-node clusters.
+Verifies trex_db_partition_table, trex_db_create_table, trex_db_attach_table,
+trex_db_repartition_table, and trex_db_partitions across multi-node clusters.
 """
 
 import json
@@ -274,6 +274,96 @@ def test_trex_db_create_table(node_factory):
     assert total == 40, f"Expected 40 total rows, got {total}"
 
 
+def test_trex_db_create_table_rolls_back_on_failure(node_factory):
+    """A failed trex_db_create_table leaves the table on no node."""
+    node_a, node_b = _setup_two_node_cluster(node_factory)
+
+    config = json.dumps({
+        "strategy": "hash",
+        "column": "missing_column",
+        "partitions": 2,
+    })
+    create_sql = "CREATE TABLE doomed AS SELECT i as id FROM range(10) t(i)"
+    result = node_a.execute(
+        f"SELECT trex_db_create_table('{create_sql}', '{config}')"
+    )
+    result_str = result[0][0]
+    assert "Rolled back creation of table 'doomed'" in result_str, result_str
+
+    for node in (node_a, node_b):
+        tables = [r[0] for r in node.execute("SHOW TABLES")]
+        assert not any(t.startswith("doomed") for t in tables), tables
+    partitions = node_a.execute("SELECT * FROM trex_db_partitions()")
+    assert not any(r[0] == "doomed" for r in partitions)
+
+
+def test_trex_db_attach_table(node_factory, tmp_path):
+    """trex_db_attach_table serves existing DuckDB files as the shards of a table."""
+    node_a, node_b = _setup_two_node_cluster(node_factory)
+
+    nodes = []
+    for i, node in enumerate((node_a, node_b)):
+        path = tmp_path / f"events_{i}.duckdb"
+        _write_duckdb_file(
+            node, path,
+            f"CREATE TABLE export_db.raw_events AS "
+            f"SELECT i as id, {i} as source FROM range({i * 10}, {i * 10 + 10}) t(i)",
+        )
+        nodes.append({"node": _node_name(node), "path": str(path), "table": "raw_events"})
+
+    config = json.dumps({"nodes": nodes})
+    result = node_a.execute(f"SELECT trex_db_attach_table('events', '{config}')")
+    result_str = result[0][0]
+    assert "Error" not in result_str, f"Attach failed: {result_str}"
+
+    wait_for(
+        node_a,
+        "SELECT * FROM trex_db_tables()",
+        lambda rows: sum(1 for r in rows if r[1] == "events") >= 2,
+        timeout=40,
+    )
+
+    assert _flight_count(node_a, "events") == 10
+    assert _flight_count(node_b, "events") == 10
+
+    result = wait_for(
+        node_a,
+        "SELECT * FROM trex_db_query('SELECT COUNT(*) as cnt FROM events')",
+        lambda rows: len(rows) >= 1 and rows[0][0] is not None,
+        timeout=10,
+    )
+    total = sum(int(r[0]) for r in result)
+    assert total == 20, f"Expected 20 rows across attached files, got {total}"
+
+
+def test_trex_db_attach_table_rejects_schema_mismatch(node_factory, tmp_path):
+    """Files whose schemas differ are detected and detached at attach time."""
+    node_a, node_b = _setup_two_node_cluster(node_factory)
+
+    path_a = tmp_path / "a.duckdb"
+    path_b = tmp_path / "b.duckdb"
+    _write_duckdb_file(
+        node_a, path_a,
+        "CREATE TABLE export_db.raw_events AS SELECT i as id FROM range(5) t(i)",
+    )
+    _write_duckdb_file(
+        node_b, path_b,
+        "CREATE TABLE export_db.raw_events AS SELECT i::VARCHAR as id FROM range(5) t(i)",
+    )
+
+    config = json.dumps({"nodes": [
+        {"node": _node_name(node_a), "path": str(path_a), "table": "raw_events"},
+        {"node": _node_name(node_b), "path": str(path_b), "table": "raw_events"},
+    ]})
+    result = node_a.execute(f"SELECT trex_db_attach_table('events', '{config}')")
+    result_str = result[0][0]
+    assert "Schema mismatch for table 'events'" in result_str, result_str
+
+    for node in (node_a, node_b):
+        tables = [r[0] for r in node.execute("SHOW TABLES")]
+        assert "events" not in tables, tables
+
+
 def test_query_partitioned_table_via_trex_db_query(node_factory):
     """Partitioned table can be queried via trex_db_query for correct results."""
     node_a, node_b = _setup_two_node_cluster(
@@ -391,6 +481,22 @@ def test_repartition_from_two_to_three_nodes(node_factory):
 # Helpers
 # ---------------------------------------------------------------------------
 
+def _node_name(node):
+    """Gossip name of `node`, looked up by its gossip port."""
+    rows = node.execute("SELECT node_name, gossip_addr FROM trex_db_nodes()")
+    for name, addr in rows:
+        if addr.endswith(f":{node.gossip_port}"):
+            return name
+    raise AssertionError(f"Node on gossip port {node.gossip_port} not found: {rows}")
+
+
+def _write_duckdb_file(node, path, create_sql):
+    """Create a DuckDB file at `path` holding the table `create_sql` makes."""
+    node.execute(f"ATTACH '{path}' AS export_db")
+    node.execute(create_sql)
+    node.execute("DETACH export_db")
+
+
 def _flight_count(node, table_name):
     """Query local row count for a table; returns 0 if table doesn't exist."""
     try:
@@ -75,3 +75,39 @@ def test_trexas_health_endpoint(node_factory):
 
     assert resp_data is not None, "Health endpoint did not respond within 10s"
     assert resp_data.get("message") == "ok"
+
+
+def test_trexas_create_bundle_tree_shake(node_factory, tmp_path):
+    """tree_shake leaves out modules the entrypoint never imports, keeps
+    dynamically imported ones, and reports the bundle size."""
+    (tmp_path / "lib").mkdir()
+    (tmp_path / "main.ts").write_text(
+        "import { greet } from './lib/greet.ts';\n"
+        "const { late } = await import('./lib/late.ts');\n"
+        "Deno.serve(() => new Response(greet() + late()));\n"
+    )
+    (tmp_path / "lib" / "greet.ts").write_text(
+        "export function greet() { return 'hi'; }\n"
+        "export function unused() { return 'never'; }\n"
+    )
+    (tmp_path / "lib" / "late.ts").write_text(
+        "export function late() { return '!'; }\n"
+        "export function alsoLate() { return '?'; }\n"
+    )
+    (tmp_path / "lib" / "orphan.ts").write_text(
+        "export function orphan() { return 'never'; }\n"
+    )
+    output = tmp_path / "out.eszip"
+
+    node = node_factory(load_trexas=True, load_flight=False, load_swarm=False)
+    options = json.dumps({"root": str(tmp_path), "tree_shake": True})
+    result = node.execute(
+        f"SELECT trex_create_bundle('main.ts', '{output}', '{options}')",
+        timeout=120,
+    )
+    msg = result[0][0]
+    assert msg.startswith("Bundle created successfully"), msg
+    assert f"({output.stat().st_size} bytes," in msg, msg
+    assert "3 module(s) kept" in msg, msg
+    assert "1 unreachable module(s) pruned" in msg, msg
+    assert "1 dynamic import(s) kept" in msg, msg
@@ -1,16 +1,23 @@
 //! Query admission control with priority queuing, per-user concurrency limits,
 //! and memory estimation.
-
-use std::collections::{BinaryHeap, HashMap};
+//!
+//! The controller also accounts for what each user's admitted queries
+//! consume on this node: how many ran, the bytes their scans returned, and
+//! CPU time. The process CPU time used between two admission events is
+//! split evenly among the queries running at the time, so a query running
+//! alone is charged exactly and concurrent ones share what they used.
+
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::cmp::Ordering as CmpOrdering;
 use std::sync::{Mutex, OnceLock};
 use std::sync::atomic::{AtomicU8, Ordering};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::catalog;
 use crate::gossip::GossipRegistry;
 use crate::logging::SwarmLogger;
 use crate::metrics;
+use crate::resources;
 
 /// Query priority levels. Higher numeric value = higher priority.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -117,6 +124,8 @@ pub struct AdmissionConfig {
     pub max_memory_utilization_pct: f64,
     pub max_queue_size: usize,
     pub timeout_secs: u64,
+    /// Finished queries kept for `trex_db_query_history`; 0 disables it.
+    pub query_history_size: usize,
 }
 
 impl Default for AdmissionConfig {
@@ -126,6 +135,7 @@ impl Default for AdmissionConfig {
             max_memory_utilization_pct: 85.0,
             max_queue_size: 100,
             timeout_secs: 300, // 5 minutes
+            query_history_size: 100,
         }
     }
 }
@@ -133,12 +143,55 @@ impl Default for AdmissionConfig {
 struct UserState {
     active_count: usize,
     max_concurrent: usize,
+    /// Usage of the user's finished queries since the last reset.
+    usage: Usage,
+}
+
+impl UserState {
+    fn new(max_concurrent: usize) -> Self {
+        UserState {
+            active_count: 0,
+            max_concurrent,
+            usage: Usage::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Usage {
+    total_queries: u64,
+    bytes_returned: u64,
+    cpu: Duration,
 }
 
 struct ActiveQuery {
     _query_id: String,
+    sql: String,
     user_id: String,
     pub started_at: Instant,
+    bytes_returned: u64,
+    cpu: Duration,
+}
+
+/// How an admitted query finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryOutcome {
+    Completed { rows: u64 },
+    /// Completed without some partitions; `warning` says which.
+    Partial { rows: u64, warning: String },
+    Failed(String),
+}
+
+/// A finished query retained in the bounded history.
+#[derive(Debug, Clone)]
+pub struct QueryHistoryEntry {
+    pub query_id: String,
+    pub sql: String,
+    pub user_id: String,
+    pub duration_ms: u64,
+    pub rows: u64,
+    pub status: String,
+    pub error: String,
 }
 
 pub struct ClusterStatus {
@@ -148,6 +201,18 @@ pub struct ClusterStatus {
     pub memory_utilization_pct: f64,
 }
 
+/// What one user's queries consumed on this node, finished and running.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserUsage {
+    pub user_id: String,
+    pub active_queries: usize,
+    pub max_concurrent: usize,
+    /// Queries admitted since the last reset.
+    pub total_queries: u64,
+    pub bytes_returned: u64,
+    pub cpu_ms: f64,
+}
+
 pub struct QueryInfo {
     pub query_id: String,
     pub user_id: String,
@@ -163,6 +228,11 @@ pub struct AdmissionController {
     user_state: HashMap<String, UserState>,
     config: AdmissionConfig,
     submitted_times: HashMap<String, Instant>,
+    history: VecDeque<QueryHistoryEntry>,
+    /// Reads the process CPU time; replaced in tests.
+    cpu_clock: fn() -> Option<Duration>,
+    /// Process CPU time when it was last split among running queries.
+    last_cpu: Option<Duration>,
 }
 
 impl AdmissionController {
@@ -173,6 +243,9 @@ impl AdmissionController {
             user_state: HashMap::new(),
             config,
             submitted_times: HashMap::new(),
+            history: VecDeque::new(),
+            cpu_clock: resources::process_cpu_time,
+            last_cpu: None,
         }
     }
 
@@ -188,6 +261,7 @@ impl AdmissionController {
         let now = Instant::now();
 
         metrics::instance().record_query_submitted();
+        self.charge_cpu();
 
         let estimated_memory = estimate_query_memory_from_sql(sql);
         let mem_pct = self.current_memory_utilization_pct();
@@ -210,10 +284,7 @@ impl AdmissionController {
         let user = self
             .user_state
             .entry(user_id.to_string())
-            .or_insert_with(|| UserState {
-                active_count: 0,
-                max_concurrent: self.config.default_max_concurrent,
-            });
+            .or_insert_with(|| UserState::new(self.config.default_max_concurrent));
 
         if user.active_count >= user.max_concurrent {
             if self.queue.len() >= self.config.max_queue_size {
@@ -263,12 +334,16 @@ impl AdmissionController {
         }
 
         user.active_count += 1;
+        user.usage.total_queries += 1;
         self.active_queries.insert(
             query_id.clone(),
             ActiveQuery {
                 _query_id: query_id.clone(),
+                sql: sql.to_string(),
                 user_id: user_id.to_string(),
                 started_at: now,
+                bytes_returned: 0,
+                cpu: Duration::ZERO,
             },
         );
         self.submitted_times.insert(query_id.clone(), now);
@@ -289,26 +364,69 @@ impl AdmissionController {
     }
 
     pub fn complete_query(&mut self, query_id: &str) {
+        self.finish_query(query_id, QueryOutcome::Completed { rows: 0 });
+    }
+
+    /// Release an active query and record how it ended in the history.
+    pub fn finish_query(&mut self, query_id: &str, outcome: QueryOutcome) {
+        self.charge_cpu();
         if let Some(active) = self.active_queries.remove(query_id) {
-            let duration_secs = active.started_at.elapsed().as_secs_f64();
-            if let Some(user) = self.user_state.get_mut(&active.user_id) {
-                user.active_count = user.active_count.saturating_sub(1);
-            }
-            metrics::instance().record_query_completed(duration_secs);
-            SwarmLogger::debug(
-                "admission",
-                &format!("Query {} completed ({:.3}s)", query_id, duration_secs),
-            );
+            let elapsed = active.started_at.elapsed();
+            let duration_secs = elapsed.as_secs_f64();
+            self.release(&active);
+            let (status, rows, error) = match outcome {
+                QueryOutcome::Completed { rows } => {
+                    metrics::instance().record_query_completed(duration_secs);
+                    SwarmLogger::debug(
+                        "admission",
+                        &format!("Query {} completed ({:.3}s)", query_id, duration_secs),
+                    );
+                    ("completed", rows, String::new())
+                }
+                QueryOutcome::Partial { rows, warning } => {
+                    metrics::instance().record_query_completed(duration_secs);
+                    SwarmLogger::debug(
+                        "admission",
+                        &format!("Query {} completed ({:.3}s): {}", query_id, duration_secs, warning),
+                    );
+                    ("partial", rows, warning)
+                }
+                QueryOutcome::Failed(error) => {
+                    metrics::instance().record_query_failed();
+                    SwarmLogger::debug(
+                        "admission",
+                        &format!("Query {} failed ({:.3}s): {}", query_id, duration_secs, error),
+                    );
+                    ("failed", 0, error)
+                }
+            };
+            self.record_history(QueryHistoryEntry {
+                query_id: query_id.to_string(),
+                sql: active.sql,
+                user_id: active.user_id,
+                duration_ms: elapsed.as_millis() as u64,
+                rows,
+                status: status.to_string(),
+                error,
+            });
         }
         self.submitted_times.remove(query_id);
         self.update_gauges();
     }
 
     pub fn cancel_query(&mut self, query_id: &str) -> Result<QueryStatus, String> {
+        self.charge_cpu();
         if let Some(active) = self.active_queries.remove(query_id) {
-            if let Some(user) = self.user_state.get_mut(&active.user_id) {
-                user.active_count = user.active_count.saturating_sub(1);
-            }
+            self.release(&active);
+            self.record_history(QueryHistoryEntry {
+                query_id: query_id.to_string(),
+                sql: active.sql,
+                user_id: active.user_id,
+                duration_ms: active.started_at.elapsed().as_millis() as u64,
+                rows: 0,
+                status: "cancelled".to_string(),
+                error: String::new(),
+            });
             self.submitted_times.remove(query_id);
             self.update_gauges();
             return Ok(QueryStatus::Cancelled);
@@ -366,10 +484,7 @@ impl AdmissionController {
         let user = self
             .user_state
             .entry(user_id.to_string())
-            .or_insert_with(|| UserState {
-                active_count: 0,
-                max_concurrent: self.config.default_max_concurrent,
-            });
+            .or_insert_with(|| UserState::new(self.config.default_max_concurrent));
         user.max_concurrent = max_concurrent;
 
         SwarmLogger::info(
@@ -381,6 +496,96 @@ impl AdmissionController {
         );
     }
 
+    /// Add `bytes` returned by a scan to the running query `query_id`.
+    /// Queries that were not admitted here are not accounted.
+    pub fn record_bytes_returned(&mut self, query_id: &str, bytes: u64) {
+        if let Some(active) = self.active_queries.get_mut(query_id) {
+            active.bytes_returned += bytes;
+        }
+    }
+
+    /// Usage of every user this node has admitted queries for or set a
+    /// quota on, running queries included, by user id.
+    pub fn get_user_usage(&mut self) -> Vec<UserUsage> {
+        self.charge_cpu();
+        let mut usage: Vec<UserUsage> = self
+            .user_state
+            .iter()
+            .map(|(user_id, state)| {
+                let mut totals = state.usage;
+                for active in self.active_queries.values().filter(|a| &a.user_id == user_id) {
+                    totals.bytes_returned += active.bytes_returned;
+                    totals.cpu += active.cpu;
+                }
+                UserUsage {
+                    user_id: user_id.clone(),
+                    active_queries: state.active_count,
+                    max_concurrent: state.max_concurrent,
+                    total_queries: totals.total_queries,
+                    bytes_returned: totals.bytes_returned,
+                    cpu_ms: totals.cpu.as_secs_f64() * 1000.0,
+                }
+            })
+            .collect();
+        usage.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+        usage
+    }
+
+    /// Zero the usage of `user_id`, or of every user when `None`. Running
+    /// queries keep running and are charged from now on; quotas and active
+    /// counts are unchanged.
+    pub fn reset_user_usage(&mut self, user_id: Option<&str>) {
+        self.charge_cpu();
+        let matches = |id: &str| user_id.is_none_or(|u| u == id);
+        for (id, state) in self.user_state.iter_mut() {
+            if matches(id) {
+                state.usage = Usage::default();
+            }
+        }
+        for active in self.active_queries.values_mut() {
+            if matches(&active.user_id) {
+                active.bytes_returned = 0;
+                active.cpu = Duration::ZERO;
+            }
+        }
+        SwarmLogger::info(
+            "admission",
+            &match user_id {
+                Some(id) => format!("Usage of user '{}' reset", id),
+                None => "Usage of every user reset".to_string(),
+            },
+        );
+    }
+
+    /// Split the process CPU time used since the last call evenly among the
+    /// running queries.
+    fn charge_cpu(&mut self) {
+        let Some(now) = (self.cpu_clock)() else {
+            return;
+        };
+        let Some(last) = self.last_cpu.replace(now) else {
+            return;
+        };
+        let running = self.active_queries.len() as u32;
+        if running == 0 {
+            return;
+        }
+        let share = now.saturating_sub(last) / running;
+        for active in self.active_queries.values_mut() {
+            active.cpu += share;
+        }
+    }
+
+    /// Free the concurrency slot of a query that left `active_queries` and
+    /// add what it used to its user's totals.
+    fn release(&mut self, active: &ActiveQuery) {
+        if let Some(user) = self.user_state.get_mut(&active.user_id) {
+            user.active_count = user.active_count.saturating_sub(1);
+            user.usage.bytes_returned += active.bytes_returned;
+            user.usage.cpu += active.cpu;
+        }
+    }
+
     pub fn get_all_query_info(&self) -> Vec<QueryInfo> {
         let mut infos = Vec::new();
 
@@ -411,6 +616,28 @@ impl AdmissionController {
         infos
     }
 
+    /// Finished queries, most recent first.
+    pub fn get_query_history(&self) -> Vec<QueryHistoryEntry> {
+        self.history.iter().rev().cloned().collect()
+    }
+
+    pub fn set_query_history_size(&mut self, size: usize) {
+        self.config.query_history_size = size;
+        while self.history.len() > size {
+            self.history.pop_front();
+        }
+    }
+
+    fn record_history(&mut self, entry: QueryHistoryEntry) {
+        if self.config.query_history_size == 0 {
+            return;
+        }
+        while self.history.len() >= self.config.query_history_size {
+            self.history.pop_front();
+        }
+        self.history.push_back(entry);
+    }
+
     fn update_gauges(&self) {
         metrics::instance().set_active_queries(self.active_queries.len() as u64);
         metrics::instance().set_queued_queries(self.queue.len() as u64);
@@ -520,6 +747,29 @@ pub fn complete(query_id: &str) -> Result<(), String> {
     Ok(())
 }
 
+pub fn finish(query_id: &str, outcome: QueryOutcome) -> Result<(), String> {
+    let mut ctrl = admission_lock()
+        .lock()
+        .map_err(|_| "Admission controller lock poisoned".to_string())?;
+    ctrl.finish_query(query_id, outcome);
+    Ok(())
+}
+
+pub fn get_query_history() -> Result<Vec<QueryHistoryEntry>, String> {
+    let ctrl = admission_lock()
+        .lock()
+        .map_err(|_| "Admission controller lock poisoned".to_string())?;
+    Ok(ctrl.get_query_history())
+}
+
+pub fn set_query_history_size(size: usize) -> Result<(), String> {
+    let mut ctrl = admission_lock()
+        .lock()
+        .map_err(|_| "Admission controller lock poisoned".to_string())?;
+    ctrl.set_query_history_size(size);
+    Ok(())
+}
+
 pub fn get_all_query_info() -> Result<Vec<QueryInfo>, String> {
     let ctrl = admission_lock()
         .lock()
@@ -556,6 +806,32 @@ pub fn set_user_quota(user_id: &str, max_concurrent: usize) -> Result<(), String
     Ok(())
 }
 
+/// Charge `bytes` returned by a scan to the query `query_id`, if this node
+/// admitted it.
+pub fn record_bytes_returned(query_id: Option<&str>, bytes: u64) {
+    let Some(query_id) = query_id else {
+        return;
+    };
+    if let Ok(mut ctrl) = admission_lock().lock() {
+        ctrl.record_bytes_returned(query_id, bytes);
+    }
+}
+
+pub fn get_user_usage() -> Result<Vec<UserUsage>, String> {
+    let mut ctrl = admission_lock()
+        .lock()
+        .map_err(|_| "Admission controller lock poisoned".to_string())?;
+    Ok(ctrl.get_user_usage())
+}
+
+pub fn reset_user_usage(user_id: Option<&str>) -> Result<(), String> {
+    let mut ctrl = admission_lock()
+        .lock()
+        .map_err(|_| "Admission controller lock poisoned".to_string())?;
+    ctrl.reset_user_usage(user_id);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -567,6 +843,7 @@ mod tests {
             max_memory_utilization_pct: 85.0,
             max_queue_size,
             timeout_secs: 300,
+            query_history_size: 100,
         })
     }
 
@@ -627,6 +904,62 @@ mod tests {
         assert_eq!(ctrl.user_state["user-a"].active_count, 0);
     }
 
+    #[test]
+    fn finished_queries_are_kept_in_history() {
+        let mut ctrl = make_controller(5, 100);
+        let (_, ok_id) = ctrl.submit_query("SELECT 1", "user-a", Priority::Interactive).unwrap();
+        let (_, bad_id) = ctrl.submit_query("SELECT x", "user-b", Priority::Interactive).unwrap();
+        ctrl.finish_query(&ok_id, QueryOutcome::Completed { rows: 3 });
+        ctrl.finish_query(&bad_id, QueryOutcome::Failed("no column x".into()));
+
+        let history = ctrl.get_query_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].query_id, bad_id);
+        assert_eq!(history[0].status, "failed");
+        assert_eq!(history[0].error, "no column x");
+        assert_eq!(history[0].user_id, "user-b");
+        assert_eq!(history[1].sql, "SELECT 1");
+        assert_eq!(history[1].rows, 3);
+        assert_eq!(history[1].status, "completed");
+        assert!(ctrl.get_all_query_info().is_empty());
+    }
+
+    #[test]
+    fn partial_queries_keep_their_warning() {
+        let mut ctrl = make_controller(5, 100);
+        let (_, qid) = ctrl.submit_query("SELECT 2", "user-a", Priority::Interactive).unwrap();
+        ctrl.finish_query(
+            &qid,
+            QueryOutcome::Partial { rows: 1, warning: "1 partition(s) missing".into() },
+        );
+
+        let history = ctrl.get_query_history();
+        assert_eq!(history[0].status, "partial");
+        assert_eq!(history[0].rows, 1);
+        assert_eq!(history[0].error, "1 partition(s) missing");
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut ctrl = make_controller(10, 100);
+        ctrl.set_query_history_size(2);
+        for i in 0..3 {
+            let (_, id) = ctrl
+                .submit_query(&format!("SELECT {}", i), "user-a", Priority::Interactive)
+                .unwrap();
+            ctrl.complete_query(&id);
+        }
+        let history = ctrl.get_query_history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].sql, "SELECT 2");
+        assert_eq!(history[1].sql, "SELECT 1");
+
+        ctrl.set_query_history_size(1);
+        assert_eq!(ctrl.get_query_history().len(), 1);
+        ctrl.set_query_history_size(0);
+        assert!(ctrl.get_query_history().is_empty());
+    }
+
     #[test]
     fn cancel_active_query() {
         let mut ctrl = make_controller(5, 100);
@@ -908,6 +1241,7 @@ mod tests {
             max_memory_utilization_pct: 85.0,
             max_queue_size: 100,
             timeout_secs: 0,
+            query_history_size: 100,
         });
         ctrl.submit_query("SELECT 1", "user-a", Priority::Interactive).unwrap();
         let timed_out = ctrl.check_timeouts();
@@ -945,6 +1279,72 @@ mod tests {
         assert!(after > before);
     }
 
+    thread_local! {
+        static FAKE_CPU_MS: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+    }
+
+    fn fake_cpu() -> Option<Duration> {
+        Some(Duration::from_millis(FAKE_CPU_MS.with(|c| c.get())))
+    }
+
+    fn advance_cpu(ms: u64) {
+        FAKE_CPU_MS.with(|c| c.set(c.get() + ms));
+    }
+
+    #[test]
+    fn usage_is_attributed_to_the_submitting_user() {
+        let mut ctrl = make_controller(10, 100);
+        ctrl.cpu_clock = fake_cpu;
+        let (_, a) = ctrl.submit_query("SELECT 1", "user-a", Priority::Interactive).unwrap();
+        advance_cpu(100);
+        let (_, b) = ctrl.submit_query("SELECT 2", "user-b", Priority::Interactive).unwrap();
+        ctrl.record_bytes_returned(&a, 1000);
+        ctrl.record_bytes_returned(&b, 10);
+        advance_cpu(40);
+        ctrl.finish_query(&a, QueryOutcome::Completed { rows: 1 });
+        ctrl.record_bytes_returned(&a, 5);
+
+        let usage = ctrl.get_user_usage();
+        assert_eq!(usage.len(), 2);
+        let (user_a, user_b) = (&usage[0], &usage[1]);
+        assert_eq!(user_a.user_id, "user-a");
+        assert_eq!((user_a.active_queries, user_a.total_queries), (0, 1));
+        assert_eq!(user_a.bytes_returned, 1000);
+        // Alone for 100ms, then half of the 40ms shared with user-b's query.
+        assert_eq!(user_a.cpu_ms, 120.0);
+        assert_eq!((user_b.active_queries, user_b.total_queries), (1, 1));
+        assert_eq!(user_b.bytes_returned, 10);
+        assert_eq!(user_b.cpu_ms, 20.0);
+    }
+
+    #[test]
+    fn usage_survives_queries_until_reset() {
+        let mut ctrl = make_controller(10, 100);
+        ctrl.cpu_clock = fake_cpu;
+        for _ in 0..3 {
+            let (_, qid) = ctrl.submit_query("SELECT 1", "user-a", Priority::Interactive).unwrap();
+            ctrl.record_bytes_returned(&qid, 100);
+            ctrl.complete_query(&qid);
+        }
+        let (_, running) = ctrl.submit_query("SELECT 2", "user-b", Priority::Interactive).unwrap();
+        ctrl.record_bytes_returned(&running, 7);
+        let usage = ctrl.get_user_usage();
+        assert_eq!((usage[0].total_queries, usage[0].bytes_returned), (3, 300));
+
+        ctrl.reset_user_usage(Some("user-a"));
+        let usage = ctrl.get_user_usage();
+        assert_eq!((usage[0].total_queries, usage[0].bytes_returned), (0, 0));
+        assert_eq!(usage[1].bytes_returned, 7);
+
+        ctrl.reset_user_usage(None);
+        advance_cpu(10);
+        ctrl.record_bytes_returned(&running, 3);
+        ctrl.complete_query(&running);
+        let usage = ctrl.get_user_usage();
+        assert_eq!((usage[1].total_queries, usage[1].bytes_returned), (0, 3));
+        assert_eq!(usage[1].cpu_ms, 10.0);
+    }
+
     #[test]
     fn cancel_query_convenience_not_found() {
         // Uses global singleton but is read-only (query doesn't exist).
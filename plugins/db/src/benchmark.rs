@@ -0,0 +1,132 @@
+//! Latency of one query run repeatedly, for `trex_db_benchmark`.
+//!
+//! Each iteration goes through admission control like any other query, so a
+//! benchmark holds one slot at a time and real queries get in between runs.
+
+use std::time::Instant;
+
+use crate::logging::SwarmLogger;
+use crate::metrics::percentile;
+
+/// Most iterations one benchmark may run.
+pub const MAX_ITERATIONS: i64 = 10_000;
+
+/// Timings of the measured iterations, in milliseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkSummary {
+    pub iterations: usize,
+    /// Whether an extra first run was made and left out of the timings.
+    pub warmup: bool,
+    /// Rows returned by the last iteration.
+    pub rows: u64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+/// Summarize `latencies_ms`; `None` when there are none.
+pub fn summarize(latencies_ms: &[f64], rows: u64, warmup: bool) -> Option<BenchmarkSummary> {
+    if latencies_ms.is_empty() {
+        return None;
+    }
+    let mut sorted = latencies_ms.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    Some(BenchmarkSummary {
+        iterations: sorted.len(),
+        warmup,
+        rows,
+        min_ms: sorted[0],
+        max_ms: sorted[sorted.len() - 1],
+        mean_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+        p50_ms: percentile(&sorted, 50.0),
+        p95_ms: percentile(&sorted, 95.0),
+    })
+}
+
+/// Time `iterations` calls of `run_once`, which runs the query and returns
+/// its row count, after one untimed call when `warmup` is set. Stops at the
+/// first failing iteration.
+pub fn run<E>(
+    iterations: i64,
+    warmup: bool,
+    mut run_once: impl FnMut() -> Result<u64, E>,
+) -> Result<BenchmarkSummary, E>
+where
+    E: From<String>,
+{
+    if !(1..=MAX_ITERATIONS).contains(&iterations) {
+        return Err(E::from(format!(
+            "iterations must be between 1 and {MAX_ITERATIONS}, got {iterations}"
+        )));
+    }
+    if warmup {
+        run_once()?;
+    }
+    let mut latencies_ms = Vec::with_capacity(iterations as usize);
+    let mut rows = 0;
+    for i in 0..iterations {
+        let started = Instant::now();
+        rows = run_once()?;
+        let elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+        SwarmLogger::debug(
+            "benchmark",
+            &format!("Iteration {} of {iterations} took {elapsed_ms:.3}ms", i + 1),
+        );
+        latencies_ms.push(elapsed_ms);
+    }
+    summarize(&latencies_ms, rows, warmup)
+        .ok_or_else(|| E::from("Benchmark ran no iterations".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarizes_latencies() {
+        let summary = summarize(&[40.0, 10.0, 20.0, 30.0, 100.0], 7, true).unwrap();
+        assert_eq!(summary.iterations, 5);
+        assert_eq!(summary.rows, 7);
+        assert!(summary.warmup);
+        assert_eq!(summary.min_ms, 10.0);
+        assert_eq!(summary.max_ms, 100.0);
+        assert_eq!(summary.mean_ms, 40.0);
+        assert_eq!(summary.p50_ms, 30.0);
+        assert_eq!(summary.p95_ms, 100.0);
+        assert_eq!(summarize(&[], 0, false), None);
+    }
+
+    #[test]
+    fn warmup_run_is_not_timed() {
+        let mut calls = 0;
+        let summary = run::<String>(3, true, || {
+            calls += 1;
+            Ok(calls)
+        })
+        .unwrap();
+        assert_eq!(calls, 4);
+        assert_eq!(summary.iterations, 3);
+        assert_eq!(summary.rows, 4);
+    }
+
+    #[test]
+    fn stops_at_the_first_failure() {
+        let mut calls = 0;
+        let err = run::<String>(5, false, || {
+            calls += 1;
+            if calls == 2 {
+                Err("Query queued at position 1".to_string())
+            } else {
+                Ok(1)
+            }
+        })
+        .unwrap_err();
+        assert_eq!(err, "Query queued at position 1");
+        assert_eq!(calls, 2);
+
+        assert!(run::<String>(0, false, || Ok(1)).is_err());
+        assert!(run::<String>(MAX_ITERATIONS + 1, false, || Ok(1)).is_err());
+    }
+}
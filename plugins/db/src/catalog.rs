@@ -2,9 +2,9 @@
 
 use duckdb::arrow::array::Array as _;
 use duckdb::arrow::array::RecordBatch as DuckRecordBatch;
-use serde::Deserialize;
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicBool, Ordering};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, OnceLock};
 
 use crate::gossip::{GossipRegistry, NodeKeyValueInfo};
@@ -36,6 +36,339 @@ pub struct CatalogEntry {
 struct CatalogValue {
     rows: u64,
     schema_hash: u64,
+    #[serde(default)]
+    stats: BTreeMap<String, ColumnStats>,
+    /// Advertisement pass on the node that collected `stats`; 0 from nodes
+    /// that predate it.
+    #[serde(default)]
+    stats_version: u64,
+    #[serde(default)]
+    hot_keys: BTreeMap<String, Vec<HotKey>>,
+    #[serde(default)]
+    bytes: u64,
+    #[serde(default)]
+    partitions: BTreeMap<usize, PartitionSize>,
+}
+
+/// Rows and Arrow bytes of one partition in one node's copy of a table,
+/// gossiped in the `partitions` field of its `catalog:` value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartitionSize {
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+/// Arrow bytes of a node's copy of a table and, for a partitioned table,
+/// of each partition in it.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct CopySizes {
+    bytes: u64,
+    partitions: BTreeMap<usize, PartitionSize>,
+}
+
+/// Min/max of one column in one node's copy of a table, gossiped in the
+/// `stats` field of its `catalog:` value. Dates and timestamps are stored as
+/// microseconds since the epoch so they compare as integers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnStats {
+    pub min: StatValue,
+    pub max: StatValue,
+    /// Absent in values gossiped by nodes that predate it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub null_count: Option<u64>,
+}
+
+/// A column value holding a large share of one node's copy of a table,
+/// gossiped in the `hot_keys` field of its `catalog:` value. Shuffle joins on
+/// the column salt these values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotKey {
+    pub value: StatValue,
+    pub rows: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StatValue {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl PartialOrd for StatValue {
+    /// Integers and floats compare numerically, text bytewise (DuckDB's
+    /// default collation); mixed kinds don't compare.
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        match (self, other) {
+            (StatValue::Int(a), StatValue::Int(b)) => Some(a.cmp(b)),
+            (StatValue::Text(a), StatValue::Text(b)) => Some(a.cmp(b)),
+            (StatValue::Int(a), StatValue::Float(b)) => (*a as f64).partial_cmp(b),
+            (StatValue::Float(a), StatValue::Int(b)) => a.partial_cmp(&(*b as f64)),
+            (StatValue::Float(a), StatValue::Float(b)) => a.partial_cmp(b),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for StatValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatValue::Int(v) => write!(f, "{v}"),
+            StatValue::Float(v) => write!(f, "{v}"),
+            StatValue::Text(v) => f.write_str(v),
+        }
+    }
+}
+
+/// Columns per table that get min/max stats; keeps catalog values small.
+const MAX_STATS_COLUMNS: usize = 32;
+/// Text min/max longer than this are dropped rather than truncated, since a
+/// truncated max would understate the column's range.
+const MAX_TEXT_STAT_LEN: usize = 64;
+
+/// Tables smaller than this are broadcast rather than shuffled, so their
+/// hot keys are not collected.
+const HOT_KEY_MIN_TABLE_ROWS: u64 = 100_000;
+/// A value is hot when it holds at least 1/HOT_KEY_SHARE_DIVISOR of the rows.
+const HOT_KEY_SHARE_DIVISOR: u64 = 100;
+/// Columns per table and values per column that get hot-key stats.
+const MAX_HOT_KEY_COLUMNS: usize = 8;
+const MAX_HOT_KEYS: usize = 8;
+
+#[derive(Clone, Copy)]
+enum StatKind {
+    Int,
+    Float,
+    Temporal,
+    Text,
+}
+
+fn stat_kind(data_type: &duckdb::arrow::datatypes::DataType) -> Option<StatKind> {
+    use duckdb::arrow::datatypes::DataType;
+    match data_type {
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32 => Some(StatKind::Int),
+        DataType::Float32 | DataType::Float64 => Some(StatKind::Float),
+        DataType::Date32 | DataType::Date64 | DataType::Timestamp(_, None) => {
+            Some(StatKind::Temporal)
+        }
+        DataType::Utf8 | DataType::LargeUtf8 => Some(StatKind::Text),
+        _ => None,
+    }
+}
+
+fn parse_stat(kind: StatKind, text: &str) -> Option<StatValue> {
+    match kind {
+        StatKind::Int | StatKind::Temporal => text.parse().ok().map(StatValue::Int),
+        StatKind::Float => text
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .map(StatValue::Float),
+        StatKind::Text => {
+            (text.len() <= MAX_TEXT_STAT_LEN).then(|| StatValue::Text(text.to_string()))
+        }
+    }
+}
+
+/// Min/max and null count per column of `table` in one aggregate query.
+/// Failures only cost pruning, so they are logged and yield no stats.
+fn collect_column_stats(
+    conn: &duckdb::Connection,
+    table: &str,
+    schema: &duckdb::arrow::datatypes::Schema,
+) -> BTreeMap<String, ColumnStats> {
+    let columns: Vec<(String, StatKind)> = schema
+        .fields()
+        .iter()
+        .filter_map(|f| stat_kind(f.data_type()).map(|k| (f.name().clone(), k)))
+        .take(MAX_STATS_COLUMNS)
+        .collect();
+    if columns.is_empty() {
+        return BTreeMap::new();
+    }
+
+    let select: Vec<String> = columns
+        .iter()
+        .map(|(name, kind)| {
+            let col = format!("\"{}\"", escape_identifier(name));
+            let min_max = match kind {
+                StatKind::Temporal => format!(
+                    "epoch_us(CAST(min({col}) AS TIMESTAMP)), epoch_us(CAST(max({col}) AS TIMESTAMP))"
+                ),
+                _ => format!("min({col}), max({col})"),
+            };
+            format!("{min_max}, count(*) - count({col})")
+        })
+        .collect();
+    let sql = format!(
+        "SELECT {} FROM \"{}\"",
+        select.join(", "),
+        escape_identifier(table)
+    );
+
+    let batches: Vec<DuckRecordBatch> = match conn
+        .prepare(&sql)
+        .and_then(|mut stmt| stmt.query_arrow([]).map(|rows| rows.collect()))
+    {
+        Ok(batches) => batches,
+        Err(e) => {
+            SwarmLogger::warn(
+                "catalog",
+                &format!("Failed to collect column stats for '{}': {e}", table),
+            );
+            return BTreeMap::new();
+        }
+    };
+    let Some(batch) = batches.iter().find(|b| b.num_rows() > 0) else {
+        return BTreeMap::new();
+    };
+
+    let value_at = |i: usize| -> Option<String> {
+        let col = batch.column(i);
+        if col.is_null(0) {
+            return None;
+        }
+        duckdb::arrow::util::display::array_value_to_string(col, 0).ok()
+    };
+
+    let mut stats = BTreeMap::new();
+    for (i, (name, kind)) in columns.iter().enumerate() {
+        let min = value_at(3 * i).and_then(|v| parse_stat(*kind, &v));
+        let max = value_at(3 * i + 1).and_then(|v| parse_stat(*kind, &v));
+        let null_count = value_at(3 * i + 2).and_then(|v| v.parse().ok());
+        if let (Some(min), Some(max)) = (min, max) {
+            stats.insert(name.clone(), ColumnStats { min, max, null_count });
+        }
+    }
+    stats
+}
+
+/// Most frequent values of the integer and text columns of `table`, kept
+/// when they hold at least 1/HOT_KEY_SHARE_DIVISOR of its rows. Like
+/// [`collect_column_stats`], failures are logged and yield no hot keys.
+fn collect_hot_keys(
+    conn: &duckdb::Connection,
+    table: &str,
+    schema: &duckdb::arrow::datatypes::Schema,
+    row_count: u64,
+) -> BTreeMap<String, Vec<HotKey>> {
+    let mut hot_keys = BTreeMap::new();
+    if row_count < HOT_KEY_MIN_TABLE_ROWS {
+        return hot_keys;
+    }
+    let min_rows = row_count / HOT_KEY_SHARE_DIVISOR;
+
+    let columns = schema
+        .fields()
+        .iter()
+        .filter_map(|f| match stat_kind(f.data_type()) {
+            Some(kind @ (StatKind::Int | StatKind::Text)) => Some((f.name().clone(), kind)),
+            _ => None,
+        })
+        .take(MAX_HOT_KEY_COLUMNS);
+
+    for (name, kind) in columns {
+        let col = format!("\"{}\"", escape_identifier(&name));
+        let sql = format!(
+            "SELECT CAST({col} AS VARCHAR), COUNT(*) FROM \"{}\" WHERE {col} IS NOT NULL \
+             GROUP BY {col} HAVING COUNT(*) >= {min_rows} ORDER BY 2 DESC LIMIT {MAX_HOT_KEYS}",
+            escape_identifier(table)
+        );
+        let batches: Vec<DuckRecordBatch> = match conn
+            .prepare(&sql)
+            .and_then(|mut stmt| stmt.query_arrow([]).map(|rows| rows.collect()))
+        {
+            Ok(batches) => batches,
+            Err(e) => {
+                SwarmLogger::warn(
+                    "catalog",
+                    &format!("Failed to collect hot keys for '{}.{}': {e}", table, name),
+                );
+                continue;
+            }
+        };
+
+        let mut keys = Vec::new();
+        for batch in &batches {
+            for row in 0..batch.num_rows() {
+                let text = duckdb::arrow::util::display::array_value_to_string(batch.column(0), row);
+                let count = duckdb::arrow::util::display::array_value_to_string(batch.column(1), row);
+                if let (Some(value), Some(rows)) = (
+                    text.ok().and_then(|t| parse_stat(kind, &t)),
+                    count.ok().and_then(|c| c.parse().ok()),
+                ) {
+                    keys.push(HotKey { value, rows });
+                }
+            }
+        }
+        if !keys.is_empty() {
+            hot_keys.insert(name, keys);
+        }
+    }
+    hot_keys
+}
+
+/// Sizes of the node's copy of `table`, split by `strategy` when it is
+/// partitioned. Reads the whole table a batch at a time; failures are logged
+/// and yield what was measured so far.
+fn collect_sizes(
+    conn: &duckdb::Connection,
+    table: &str,
+    strategy: Option<&crate::partition::PartitionStrategy>,
+) -> CopySizes {
+    let mut sizes = CopySizes::default();
+    let sql = format!("SELECT * FROM \"{}\"", escape_identifier(table));
+    let mut stmt = match conn.prepare(&sql) {
+        Ok(stmt) => stmt,
+        Err(e) => {
+            SwarmLogger::warn("catalog", &format!("Failed to measure '{}': {e}", table));
+            return sizes;
+        }
+    };
+    let batches = match stmt.query_arrow([]) {
+        Ok(batches) => batches,
+        Err(e) => {
+            SwarmLogger::warn("catalog", &format!("Failed to measure '{}': {e}", table));
+            return sizes;
+        }
+    };
+    let schema = batches.get_schema();
+    let mut strategy = strategy;
+    for batch in batches {
+        sizes.bytes += batch.get_array_memory_size() as u64;
+        let Some(by) = strategy else {
+            continue;
+        };
+        match crate::partition::partition_by_strategy(by, &schema, std::slice::from_ref(&batch)) {
+            Ok(parts) => {
+                for (partition_id, part) in parts.iter().enumerate() {
+                    let rows: usize = part.iter().map(|b| b.num_rows()).sum();
+                    if rows == 0 {
+                        continue;
+                    }
+                    let size = sizes.partitions.entry(partition_id).or_default();
+                    let bytes: usize = part.iter().map(|b| b.get_array_memory_size()).sum();
+                    size.rows += rows as u64;
+                    size.bytes += bytes as u64;
+                }
+            }
+            Err(e) => {
+                SwarmLogger::warn(
+                    "catalog",
+                    &format!("Failed to measure the partitions of '{}': {e}", table),
+                );
+                sizes.partitions.clear();
+                strategy = None;
+            }
+        }
+    }
+    sizes
 }
 
 #[derive(Debug, Deserialize)]
@@ -154,6 +487,36 @@ fn get_all_tables_from_states(nodes: &[NodeKeyValueInfo]) -> Vec<CatalogEntry> {
     entries
 }
 
+/// Hot keys per table and column, with row counts summed over the nodes
+/// that hold the table.
+fn hot_keys_from_states(
+    nodes: &[NodeKeyValueInfo],
+) -> HashMap<String, BTreeMap<String, Vec<HotKey>>> {
+    let mut merged: HashMap<String, BTreeMap<String, Vec<HotKey>>> = HashMap::new();
+    for node in nodes {
+        for (key, value) in &node.key_values {
+            let Some(table_name) = key.strip_prefix("catalog:").filter(|n| !n.is_empty()) else {
+                continue;
+            };
+            let Some(catalog_val) = parse_catalog_value(value) else {
+                continue;
+            };
+            let table = merged.entry(table_name.to_string()).or_default();
+            for (column, keys) in catalog_val.hot_keys {
+                let column_keys = table.entry(column).or_default();
+                for key in keys {
+                    match column_keys.iter_mut().find(|k| k.value == key.value) {
+                        Some(existing) => existing.rows += key.rows,
+                        None => column_keys.push(key),
+                    }
+                }
+            }
+        }
+    }
+    merged.retain(|_, columns| !columns.is_empty());
+    merged
+}
+
 fn list_table_names_from_states(nodes: &[NodeKeyValueInfo]) -> Vec<String> {
     let mut names: Vec<String> = nodes
         .iter()
@@ -171,6 +534,143 @@ fn fetch_node_key_values() -> Result<Vec<NodeKeyValueInfo>, String> {
     GossipRegistry::instance().get_node_key_values()
 }
 
+/// Gossiped statistics of one node's copy of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardStats {
+    pub node_name: String,
+    /// Partitions whose primary copy this is, with their sizes; empty for
+    /// unpartitioned tables. Zero for nodes that predate partition sizes.
+    pub partitions: BTreeMap<usize, PartitionSize>,
+    pub row_count: u64,
+    /// Arrow bytes of the copy; 0 from nodes that predate it.
+    pub byte_size: u64,
+    pub column_stats: BTreeMap<String, ColumnStats>,
+}
+
+fn table_stats_from_states(nodes: &[NodeKeyValueInfo], table_name: &str) -> Vec<ShardStats> {
+    let key = format!("catalog:{table_name}");
+    let metadata = crate::partition::find_partition_metadata(nodes, table_name)
+        .ok()
+        .flatten();
+    let mut shards: Vec<ShardStats> = nodes
+        .iter()
+        .filter_map(|node| {
+            let (_, value) = node.key_values.iter().find(|(k, _)| k == &key)?;
+            let catalog_val = parse_catalog_value(value)?;
+            let partitions = metadata
+                .iter()
+                .flat_map(|m| &m.assignments)
+                .filter(|a| a.node_name == node.node_name)
+                .map(|a| {
+                    let size = catalog_val.partitions.get(&a.partition_id).copied();
+                    (a.partition_id, size.unwrap_or_default())
+                })
+                .collect();
+            Some(ShardStats {
+                node_name: node.node_name.clone(),
+                partitions,
+                row_count: catalog_val.rows,
+                byte_size: catalog_val.bytes,
+                column_stats: catalog_val.stats,
+            })
+        })
+        .collect();
+    shards.sort_by(|a, b| a.node_name.cmp(&b.node_name));
+    shards
+}
+
+/// Row counts, sizes and column stats of every node's copy of `table_name`.
+pub fn table_stats(table_name: &str) -> Result<Vec<ShardStats>, String> {
+    let nodes = fetch_node_key_values()?;
+    Ok(table_stats_from_states(&nodes, table_name))
+}
+
+/// Gossip config key choosing what `trex_db_query` does with a table whose
+/// copies have diverging schemas: `warn` (the default) or `refuse`.
+pub const SCHEMA_CONFLICT_ACTION_KEY: &str = "schema_conflict_action";
+
+/// One node's copy of a table whose copies do not all share a schema hash.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaConflict {
+    pub table_name: String,
+    pub node_name: String,
+    pub schema_hash: u64,
+    /// Copies of the table, on any node, with this same hash.
+    pub copies_with_hash: usize,
+}
+
+/// Every copy of each table that has more than one schema hash across the
+/// cluster, by table, then by hash with the most common first. Hash 0 means
+/// the node could not read the schema and is not counted as a conflict.
+fn schema_conflicts_from_entries(entries: &[CatalogEntry]) -> Vec<SchemaConflict> {
+    let mut by_table: BTreeMap<&str, Vec<&CatalogEntry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|e| e.schema_hash != 0) {
+        by_table.entry(&entry.table_name).or_default().push(entry);
+    }
+
+    let mut conflicts = Vec::new();
+    for (table_name, copies) in by_table {
+        let mut counts: HashMap<u64, usize> = HashMap::new();
+        for copy in &copies {
+            *counts.entry(copy.schema_hash).or_default() += 1;
+        }
+        if counts.len() < 2 {
+            continue;
+        }
+        let mut rows: Vec<SchemaConflict> = copies
+            .iter()
+            .map(|copy| SchemaConflict {
+                table_name: table_name.to_string(),
+                node_name: copy.node_name.clone(),
+                schema_hash: copy.schema_hash,
+                copies_with_hash: counts[&copy.schema_hash],
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.copies_with_hash
+                .cmp(&a.copies_with_hash)
+                .then(a.schema_hash.cmp(&b.schema_hash))
+                .then_with(|| a.node_name.cmp(&b.node_name))
+        });
+        conflicts.extend(rows);
+    }
+    conflicts
+}
+
+/// Copies of tables whose schemas diverge across the cluster.
+pub fn schema_conflicts() -> Result<Vec<SchemaConflict>, String> {
+    let nodes = fetch_node_key_values()?;
+    Ok(schema_conflicts_from_entries(&get_all_tables_from_states(&nodes)))
+}
+
+/// One line per conflicted table naming each node and its schema hash.
+pub fn describe_schema_conflicts(conflicts: &[SchemaConflict]) -> String {
+    let mut by_table: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for conflict in conflicts {
+        by_table
+            .entry(&conflict.table_name)
+            .or_default()
+            .push(format!("'{}' 0x{:X}", conflict.node_name, conflict.schema_hash));
+    }
+    by_table
+        .into_iter()
+        .map(|(table, nodes)| {
+            format!(
+                "Table '{}' has diverging schemas across nodes: {}",
+                table,
+                nodes.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Hot join-key values per table and column across the cluster.
+pub fn get_hot_keys() -> Result<HashMap<String, BTreeMap<String, Vec<HotKey>>>, String> {
+    let nodes = fetch_node_key_values()?;
+    Ok(hot_keys_from_states(&nodes))
+}
+
 /// Resolve a table name to the set of nodes that hold it.
 pub fn resolve_table(table_name: &str) -> Result<Vec<CatalogEntry>, String> {
     let nodes = fetch_node_key_values()?;
@@ -227,6 +727,22 @@ pub fn get_all_tables() -> Result<Vec<CatalogEntry>, String> {
     Ok(entries)
 }
 
+/// Flight endpoint of every node running a Flight server, keyed by node name.
+pub fn flight_endpoints_by_node() -> Result<HashMap<String, String>, String> {
+    let nodes = fetch_node_key_values()?;
+    Ok(nodes
+        .iter()
+        .filter_map(|node| {
+            let endpoint = node
+                .key_values
+                .iter()
+                .find(|(k, _)| k == "service:flight")
+                .and_then(|(_, v)| parse_flight_endpoint(v))?;
+            Some((node.node_name.clone(), endpoint))
+        })
+        .collect())
+}
+
 /// Return sorted, deduplicated table names across the cluster.
 pub fn list_tables() -> Result<Vec<String>, String> {
     let nodes = fetch_node_key_values()?;
@@ -243,7 +759,7 @@ pub fn tables_by_name() -> Result<HashMap<String, Vec<CatalogEntry>>, String> {
 }
 
 /// FNV-1a hash of field names and types. Stable across processes (unlike DefaultHasher).
-fn compute_schema_hash(schema: &arrow::datatypes::SchemaRef) -> u64 {
+pub fn compute_schema_hash(schema: &arrow::datatypes::SchemaRef) -> u64 {
     let mut hash: u64 = 0xcbf29ce484222325;
     for field in schema.fields() {
         for byte in field.name().bytes() {
@@ -274,9 +790,83 @@ fn compute_schema_hash_duckdb(schema: &duckdb::arrow::datatypes::SchemaRef) -> u
     hash
 }
 
+/// What a local table was last advertised with.
+#[derive(Debug, Clone, PartialEq)]
+struct Advertised {
+    row_count: u64,
+    schema_hash: u64,
+    stats: BTreeMap<String, ColumnStats>,
+    hot_keys: BTreeMap<String, Vec<HotKey>>,
+    sizes: CopySizes,
+    /// Partition strategy `sizes` were split by, as JSON.
+    strategy: Option<String>,
+}
+
+impl Advertised {
+    /// Whether re-advertising the table would publish the same values.
+    fn is_current(
+        &self,
+        row_count: u64,
+        schema_hash: u64,
+        stats: &BTreeMap<String, ColumnStats>,
+    ) -> bool {
+        self.row_count == row_count && self.schema_hash == schema_hash && &self.stats == stats
+    }
+}
+
+fn advertised_tables() -> &'static std::sync::Mutex<HashMap<String, Advertised>> {
+    static ADVERTISED: OnceLock<std::sync::Mutex<HashMap<String, Advertised>>> = OnceLock::new();
+    ADVERTISED.get_or_init(|| std::sync::Mutex::new(HashMap::new()))
+}
+
+/// Gossip key a local table is advertised under.
+fn advertised_key(table: &str) -> String {
+    match crate::partition::parse_replica_table_name(table) {
+        Some((base, partition_id)) => format!("replica:{}:{}", base, partition_id),
+        None => format!("catalog:{}", table),
+    }
+}
+
+/// Advertised tables missing from `local`, in name order.
+fn dropped_tables<V>(advertised: &HashMap<String, V>, local: &[String]) -> Vec<String> {
+    let mut dropped: Vec<String> = advertised
+        .keys()
+        .filter(|t| !local.contains(t))
+        .cloned()
+        .collect();
+    dropped.sort();
+    dropped
+}
+
 /// Publish `catalog:{table}` gossip keys for all local tables.
 pub fn advertise_local_tables() -> Result<usize, String> {
-    let table_data = crate::local_connections::with_connection(|conn| {
+    advertise_tables(true)
+}
+
+/// Re-publish only the local tables whose row count, schema hash or column
+/// stats changed since they were last advertised, and withdraw the ones
+/// that were dropped.
+pub fn refresh_local_tables() -> Result<usize, String> {
+    advertise_tables(false)
+}
+
+/// Publish gossip keys for local tables, skipping unchanged ones unless
+/// `full`, and delete the keys of advertised tables that no longer exist.
+fn advertise_tables(full: bool) -> Result<usize, String> {
+    let version = next_stats_version();
+    // Without gossip there is no partition metadata, and sizes are per copy.
+    let strategies: HashMap<String, crate::partition::PartitionStrategy> =
+        crate::partition::get_all_partition_metadata()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(table, metadata)| (table, metadata.strategy))
+            .collect();
+    let previous = advertised_tables()
+        .lock()
+        .map_err(|e| format!("Failed to lock advertised tables: {e}"))?
+        .clone();
+
+    let (names, table_data) = crate::local_connections::with_connection(|conn| {
         let mut stmt = conn
             .prepare("SHOW TABLES")
             .map_err(|e| format!("Failed to prepare SHOW TABLES: {e}"))?;
@@ -297,14 +887,18 @@ pub fn advertise_local_tables() -> Result<usize, String> {
                 .downcast_ref::<duckdb::arrow::array::StringArray>()
                 .ok_or_else(|| "SHOW TABLES did not return string column".to_string())?;
             for i in 0..string_array.len() {
-                if !string_array.is_null(i) {
+                // Staging tables of an unfinished create are not part of
+                // any table until the create commits them.
+                if !string_array.is_null(i)
+                    && !crate::partition::is_pending_table_name(string_array.value(i))
+                {
                     names.push(string_array.value(i).to_string());
                 }
             }
         }
 
         if names.is_empty() {
-            return Ok(vec![]);
+            return Ok((names, vec![]));
         }
 
         let mut table_info = Vec::new();
@@ -341,7 +935,7 @@ pub fn advertise_local_tables() -> Result<usize, String> {
                 }
             };
 
-            let schema_hash: u64 = {
+            let schema = {
                 let schema_sql = format!("SELECT * FROM \"{}\" LIMIT 0", escape_identifier(table));
                 let mut stmt = conn
                     .prepare(&schema_sql)
@@ -352,31 +946,128 @@ pub fn advertise_local_tables() -> Result<usize, String> {
                     .map_err(|e| format!("Failed to execute schema query for '{}': {e}", table))?
                     .collect();
 
-                if let Some(batch) = batches.first() {
-                    compute_schema_hash_duckdb(&batch.schema())
-                } else {
-                    0
-                }
+                batches.first().map(|batch| batch.schema())
+            };
+            let schema_hash = schema.as_ref().map(compute_schema_hash_duckdb).unwrap_or(0);
+
+            // Replicas are read only as stand-ins for a primary shard, which
+            // carries the stats used for pruning and skew handling. Column
+            // stats are recollected on every refresh, since an UPDATE can
+            // move them without changing the row count and pruning on stale
+            // bounds would skip rows.
+            let primary_schema = schema
+                .as_ref()
+                .filter(|_| crate::partition::parse_replica_table_name(table).is_none());
+            let stats = primary_schema
+                .map(|schema| collect_column_stats(conn, table, schema))
+                .unwrap_or_default();
+            let strategy = strategies
+                .get(table.as_str())
+                .filter(|_| primary_schema.is_some());
+            let strategy_json = strategy.and_then(|s| serde_json::to_string(s).ok());
+            // A repartition can leave the row count and stats as they were
+            // while moving rows between partitions.
+            let unchanged = previous.get(table).filter(|p| {
+                p.is_current(row_count, schema_hash, &stats) && p.strategy == strategy_json
+            });
+            if unchanged.is_some() && !full {
+                continue;
+            }
+            // Hot keys take a GROUP BY per column and sizes a full read, so
+            // a table that has not changed keeps the ones it was last
+            // advertised with.
+            let (hot_keys, sizes) = match unchanged {
+                Some(p) => (p.hot_keys.clone(), p.sizes.clone()),
+                None => match primary_schema {
+                    Some(schema) => (
+                        collect_hot_keys(conn, table, schema, row_count),
+                        collect_sizes(conn, table, strategy),
+                    ),
+                    None => Default::default(),
+                },
             };
 
-            table_info.push((table.clone(), row_count, schema_hash));
+            table_info.push((
+                table.clone(),
+                Advertised {
+                    row_count,
+                    schema_hash,
+                    stats,
+                    hot_keys,
+                    sizes,
+                    strategy: strategy_json,
+                },
+            ));
         }
 
-        Ok(table_info)
+        Ok((names, table_info))
     })?;
 
+    let gossip = GossipRegistry::instance();
+    let mut advertised = advertised_tables()
+        .lock()
+        .map_err(|e| format!("Failed to lock advertised tables: {e}"))?;
+
+    for table in dropped_tables(&advertised, &names) {
+        match gossip.delete_key(&advertised_key(&table)) {
+            Ok(()) => {
+                advertised.remove(&table);
+                SwarmLogger::info(
+                    "catalog",
+                    &format!("Withdrew dropped table '{}' from the catalog", table),
+                );
+            }
+            Err(e) => SwarmLogger::warn(
+                "catalog",
+                &format!("Failed to withdraw dropped table '{}': {}", table, e),
+            ),
+        }
+    }
+
     if table_data.is_empty() {
-        SwarmLogger::debug("catalog", "No local tables to advertise");
+        SwarmLogger::debug("catalog", "No changed local tables to advertise");
         return Ok(0);
     }
 
-    let gossip = GossipRegistry::instance();
     let mut count = 0;
 
-    for (table, row_count, schema_hash) in &table_data {
-
-        let key = format!("catalog:{}", table);
-        let value = format!(r#"{{"rows": {}, "schema_hash": {}}}"#, row_count, schema_hash);
+    for (table, entry) in table_data {
+        // Replica tables are advertised under their own key so they never
+        // appear as a separate table or as an extra shard of the primary.
+        let key = advertised_key(&table);
+        let value = match crate::partition::parse_replica_table_name(&table) {
+            Some(_) => format!(
+                r#"{{"rows": {}, "schema_hash": {}, "role": "replica"}}"#,
+                entry.row_count, entry.schema_hash
+            ),
+            None => {
+                let mut value = format!(
+                    r#"{{"rows": {}, "schema_hash": {}, "role": "primary", "stats_version": {}, "bytes": {}"#,
+                    entry.row_count, entry.schema_hash, version, entry.sizes.bytes
+                );
+                if !entry.stats.is_empty() {
+                    value.push_str(&format!(
+                        r#", "stats": {}"#,
+                        serde_json::to_string(&entry.stats).unwrap_or_else(|_| "{}".to_string())
+                    ));
+                }
+                if !entry.hot_keys.is_empty() {
+                    value.push_str(&format!(
+                        r#", "hot_keys": {}"#,
+                        serde_json::to_string(&entry.hot_keys).unwrap_or_else(|_| "{}".to_string())
+                    ));
+                }
+                if !entry.sizes.partitions.is_empty() {
+                    value.push_str(&format!(
+                        r#", "partitions": {}"#,
+                        serde_json::to_string(&entry.sizes.partitions)
+                            .unwrap_or_else(|_| "{}".to_string())
+                    ));
+                }
+                value.push('}');
+                value
+            }
+        };
 
         match gossip.set_key(&key, &value) {
             Ok(()) => {
@@ -384,9 +1075,10 @@ pub fn advertise_local_tables() -> Result<usize, String> {
                     "catalog",
                     &format!(
                         "Advertised table '{}': rows={}, schema_hash=0x{:X}",
-                        table, row_count, schema_hash,
+                        table, entry.row_count, entry.schema_hash,
                     ),
                 );
+                advertised.insert(table, entry);
                 count += 1;
             }
             Err(e) => {
@@ -406,12 +1098,16 @@ pub fn advertise_local_tables() -> Result<usize, String> {
     Ok(count)
 }
 
-/// Remove all `catalog:*` gossip keys from this node.
+/// Remove all `catalog:*` and `replica:*` gossip keys from this node.
 pub fn remove_catalog_keys() -> Result<usize, String> {
     let gossip = GossipRegistry::instance();
 
-    let keys = gossip.list_keys_with_prefix("catalog:")?;
+    let mut keys = gossip.list_keys_with_prefix("catalog:")?;
+    keys.extend(gossip.list_keys_with_prefix("replica:")?);
     let count = keys.len();
+    if let Ok(mut advertised) = advertised_tables().lock() {
+        advertised.clear();
+    }
 
     for key in &keys {
         if let Err(e) = gossip.delete_key(key) {
@@ -443,7 +1139,12 @@ fn catalog_refresh_lock() -> &'static std::sync::Mutex<Option<CatalogRefreshHand
     CATALOG_REFRESH.get_or_init(|| std::sync::Mutex::new(None))
 }
 
-/// Spawn a background thread that re-advertises local tables every `SWARM_CATALOG_INTERVAL` seconds (default 30). No-op if already running.
+/// Refreshes between full re-advertisements. The others skip tables whose
+/// row count, schema and column stats are unchanged. Full ones re-publish
+/// every table but still reuse the hot keys of unchanged ones.
+const FULL_REFRESH_EVERY: u64 = 10;
+
+/// Spawn a background thread that refreshes the advertised local tables every `SWARM_CATALOG_INTERVAL` seconds (default 30). No-op if already running.
 pub fn start_catalog_refresh() -> Result<(), String> {
     let mut guard = catalog_refresh_lock()
         .lock()
@@ -473,6 +1174,7 @@ pub fn start_catalog_refresh() -> Result<(), String> {
                 ),
             );
 
+            let mut refreshes: u64 = 0;
             loop {
                 std::thread::sleep(std::time::Duration::from_secs(interval_secs));
 
@@ -480,7 +1182,13 @@ pub fn start_catalog_refresh() -> Result<(), String> {
                     break;
                 }
 
-                match advertise_local_tables() {
+                refreshes += 1;
+                let result = if refreshes.is_multiple_of(FULL_REFRESH_EVERY) {
+                    advertise_local_tables()
+                } else {
+                    refresh_local_tables()
+                };
+                match result {
                     Ok(n) => {
                         SwarmLogger::debug(
                             "catalog",
@@ -722,15 +1430,177 @@ pub fn get_self_node_id() -> Option<String> {
         .map(|(_, v)| v)
 }
 
+pub fn get_self_node_name() -> Option<String> {
+    let config = GossipRegistry::instance().get_self_config().ok()?;
+    config
+        .into_iter()
+        .find(|(k, _)| k == "node_name")
+        .map(|(_, v)| v)
+}
+
 /// One shard of a distributed table.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ShardInfo {
     pub node_name: String,
     pub flight_endpoint: String,
+    /// Replica copies of this shard's partitions on other nodes. When
+    /// non-empty, every partition of the shard has at least one entry, so
+    /// scanning one copy per partition returns the same rows as the shard.
+    pub replicas: Vec<ReplicaInfo>,
+    /// Per-column min/max of the shard, used to skip it for range filters.
+    pub column_stats: BTreeMap<String, ColumnStats>,
 }
 
-/// How a table is routed in the distributed session.
+/// A replica of one partition, stored in its own table on `node_name`.
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaInfo {
+    pub partition_id: usize,
+    pub node_name: String,
+    pub flight_endpoint: String,
+    pub table_name: String,
+}
+
+/// Column stats the node `node_id` gossips for its copy of `table_name`.
+/// Empty while they predate a write this node made to that copy, so the
+/// shard is not pruned on bounds the write may have moved.
+fn shard_column_stats_from_states(
+    nodes: &[NodeKeyValueInfo],
+    table_name: &str,
+    node_id: &str,
+) -> BTreeMap<String, ColumnStats> {
+    let key = format!("catalog:{table_name}");
+    let Some(node) = nodes.iter().find(|n| n.node_id == node_id) else {
+        return BTreeMap::new();
+    };
+    node.key_values
+        .iter()
+        .find(|(k, _)| k == &key)
+        .and_then(|(_, v)| parse_catalog_value(v))
+        .filter(|v| stats_are_current(table_name, &node.node_name, v.stats_version))
+        .map(|v| v.stats)
+        .unwrap_or_default()
+}
+
+/// Stamped into the catalog values of each advertisement pass.
+static STATS_VERSION: AtomicU64 = AtomicU64::new(0);
+
+/// Version for a new advertisement pass: above every earlier one, and
+/// seeded from the clock so it keeps growing across restarts.
+fn next_stats_version() -> u64 {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_micros() as u64);
+    let previous = STATS_VERSION
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| Some((v + 1).max(now)))
+        .unwrap_or_default();
+    (previous + 1).max(now)
+}
+
+/// Version of the latest advertisement pass on this node, so stats
+/// gossiped at it or later were collected after everything it had written.
+pub fn stats_version() -> u64 {
+    STATS_VERSION.load(Ordering::SeqCst)
+}
+
+/// Stats version each (table, node) copy must reach before its gossiped
+/// stats may prune it again, recorded by the writes this node makes.
+fn pending_stats() -> &'static std::sync::Mutex<HashMap<(String, String), u64>> {
+    static PENDING: OnceLock<std::sync::Mutex<HashMap<(String, String), u64>>> = OnceLock::new();
+    PENDING.get_or_init(|| std::sync::Mutex::new(HashMap::new()))
+}
+
+/// Note that this node wrote to `node_name`'s copy of `table_name`, whose
+/// stats only reflect the write from version `version` on.
+pub fn expect_stats_version(table_name: &str, node_name: &str, version: u64) {
+    if let Ok(mut pending) = pending_stats().lock() {
+        let wanted = pending
+            .entry((table_name.to_string(), node_name.to_string()))
+            .or_default();
+        *wanted = (*wanted).max(version);
+    }
+}
+
+/// Whether stats of `version` that `node_name` gossips for `table_name`
+/// postdate every write this node made to that copy.
+fn stats_are_current(table_name: &str, node_name: &str, version: u64) -> bool {
+    let Ok(mut pending) = pending_stats().lock() else {
+        return false;
+    };
+    let key = (table_name.to_string(), node_name.to_string());
+    match pending.get(&key) {
+        Some(&wanted) if version < wanted => false,
+        Some(_) => {
+            pending.remove(&key);
+            true
+        }
+        None => true,
+    }
+}
+
+/// The stats version `node_name` currently gossips for `table_name`, 0 when
+/// it advertises none.
+pub fn gossiped_stats_version(table_name: &str, node_name: &str) -> u64 {
+    let key = format!("catalog:{table_name}");
+    fetch_node_key_values()
+        .unwrap_or_default()
+        .iter()
+        .filter(|n| n.node_name == node_name)
+        .flat_map(|n| n.key_values.iter())
+        .find(|(k, _)| k == &key)
+        .and_then(|(_, v)| parse_catalog_value(v))
+        .map_or(0, |v| v.stats_version)
+}
+
+/// Collect advertised replicas of the partitions whose primary is
+/// `primary_node`. Returns nothing unless every such partition has a replica
+/// reachable over Flight, since a partial set cannot stand in for the shard.
+fn shard_replicas_from_states(
+    nodes: &[NodeKeyValueInfo],
+    table_name: &str,
+    primary_node: &str,
+) -> Vec<ReplicaInfo> {
+    let metadata = match crate::partition::find_partition_metadata(nodes, table_name) {
+        Ok(Some(m)) => m,
+        _ => return Vec::new(),
+    };
+
+    let mut replicas = Vec::new();
+    for assignment in metadata.assignments.iter().filter(|a| a.node_name == primary_node) {
+        let key = format!("replica:{}:{}", table_name, assignment.partition_id);
+        // Only replicas the metadata lists: one being loaded or dropped is
+        // advertised by its node but must not be read.
+        let copies: Vec<ReplicaInfo> = nodes
+            .iter()
+            .filter(|n| n.node_name != primary_node)
+            .filter(|n| assignment.replicas.iter().any(|r| r.node_name == n.node_name))
+            .filter(|n| n.key_values.iter().any(|(k, _)| k == &key))
+            .filter_map(|n| {
+                let endpoint = n
+                    .key_values
+                    .iter()
+                    .find(|(k, _)| k == "service:flight")
+                    .and_then(|(_, v)| parse_flight_endpoint(v))?;
+                Some(ReplicaInfo {
+                    partition_id: assignment.partition_id,
+                    node_name: n.node_name.clone(),
+                    flight_endpoint: endpoint,
+                    table_name: crate::partition::replica_table_name(
+                        table_name,
+                        assignment.partition_id,
+                    ),
+                })
+            })
+            .collect();
+        if copies.is_empty() {
+            return Vec::new();
+        }
+        replicas.extend(copies);
+    }
+    replicas
+}
+
+/// How a table is routed in the distributed session.
+#[derive(Debug, Clone, PartialEq)]
 pub enum TableClassification {
     Local,
     RemoteUnique {
@@ -788,6 +1658,8 @@ pub fn classify_tables_from_states(
                     e.flight_endpoint.as_ref().map(|ep| ShardInfo {
                         node_name: e.node_name.clone(),
                         flight_endpoint: ep.clone(),
+                        replicas: shard_replicas_from_states(nodes, &table_name, &e.node_name),
+                        column_stats: shard_column_stats_from_states(nodes, &table_name, &e.node_id),
                     })
                 })
                 .collect();
@@ -1213,6 +2085,47 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn dropped_tables_are_the_advertised_ones_no_longer_local() {
+        let advertised: HashMap<String, (u64, u64)> = [
+            ("orders".to_string(), (10, 1)),
+            ("orders__replica_2".to_string(), (5, 1)),
+            ("items".to_string(), (3, 2)),
+        ]
+        .into_iter()
+        .collect();
+        let local = vec!["items".to_string(), "new_table".to_string()];
+        assert_eq!(
+            dropped_tables(&advertised, &local),
+            vec!["orders", "orders__replica_2"]
+        );
+        assert_eq!(advertised_key("orders"), "catalog:orders");
+        assert_eq!(advertised_key("orders__replica_2"), "replica:orders:2");
+    }
+
+    #[test]
+    fn tables_are_readvertised_when_their_stats_move() {
+        let stats = |max: i64| {
+            BTreeMap::from([(
+                "id".to_string(),
+                ColumnStats { min: StatValue::Int(1), max: StatValue::Int(max), null_count: Some(0) },
+            )])
+        };
+        let advertised = Advertised {
+            row_count: 10,
+            schema_hash: 7,
+            stats: stats(100),
+            hot_keys: BTreeMap::new(),
+            sizes: CopySizes::default(),
+            strategy: None,
+        };
+        assert!(advertised.is_current(10, 7, &stats(100)));
+        // An UPDATE that keeps the row count but raises the max.
+        assert!(!advertised.is_current(10, 7, &stats(150)));
+        assert!(!advertised.is_current(11, 7, &stats(100)));
+        assert!(!advertised.is_current(10, 8, &stats(100)));
+    }
+
     #[test]
     fn remove_catalog_keys_without_gossip() {
         let result = remove_catalog_keys();
@@ -1415,6 +2328,261 @@ mod tests {
         }
     }
 
+    #[test]
+    fn classify_sharded_table_attaches_column_stats() {
+        let with_stats = r#"{"rows": 500, "schema_hash": 42, "role": "primary",
+            "stats": {"id": {"min": 1, "max": 500}, "price": {"min": 0.5, "max": 9.5},
+                      "region": {"min": "eu", "max": "us"}}}"#;
+        let cat_b = catalog_json(500, 42);
+        let flt_a = flight_json("10.0.0.1", 8815, "running");
+        let flt_b = flight_json("10.0.0.2", 8815, "running");
+
+        let nodes = vec![
+            make_node(
+                "id-a",
+                "node-a",
+                vec![("catalog:orders", with_stats), ("service:flight", &flt_a)],
+            ),
+            make_node(
+                "id-b",
+                "node-b",
+                vec![("catalog:orders", &cat_b), ("service:flight", &flt_b)],
+            ),
+        ];
+
+        let Some(TableClassification::Sharded { shards }) =
+            classify_tables_from_states(&nodes, Some("id-a")).remove("orders")
+        else {
+            panic!("expected a sharded table");
+        };
+        let a = shards.iter().find(|s| s.node_name == "node-a").unwrap();
+        let b = shards.iter().find(|s| s.node_name == "node-b").unwrap();
+        assert_eq!(a.column_stats["id"].max, StatValue::Int(500));
+        assert_eq!(a.column_stats["price"].min, StatValue::Float(0.5));
+        assert_eq!(a.column_stats["region"].min, StatValue::Text("eu".to_string()));
+        assert!(b.column_stats.is_empty());
+    }
+
+    #[test]
+    fn stats_from_before_a_write_do_not_prune() {
+        let gossiped = |max: u64, version: u64| {
+            format!(
+                r#"{{"rows": 10, "schema_hash": 42, "role": "primary", "stats_version": {version},
+                    "stats": {{"id": {{"min": 1, "max": {max}}}}}}}"#
+            )
+        };
+        let flt_a = flight_json("10.0.0.1", 8815, "running");
+        let flt_b = flight_json("10.0.0.2", 8815, "running");
+        let stats_of_a = |catalog_a: &str| {
+            let nodes = vec![
+                make_node(
+                    "id-a",
+                    "node-a",
+                    vec![("catalog:written_orders", catalog_a), ("service:flight", &flt_a)],
+                ),
+                make_node(
+                    "id-b",
+                    "node-b",
+                    vec![("catalog:written_orders", &catalog_json(10, 42)), ("service:flight", &flt_b)],
+                ),
+            ];
+            let Some(TableClassification::Sharded { shards }) =
+                classify_tables_from_states(&nodes, None).remove("written_orders")
+            else {
+                panic!("expected a sharded table");
+            };
+            shards.into_iter().find(|s| s.node_name == "node-a").unwrap().column_stats
+        };
+
+        assert_eq!(stats_of_a(&gossiped(100, 5))["id"].max, StatValue::Int(100));
+
+        // A row with id 150 was just appended to node-a, whose refresh
+        // reports version 6: until it is gossiped, id > 100 must still scan
+        // node-a.
+        expect_stats_version("written_orders", "node-a", 6);
+        assert!(stats_of_a(&gossiped(100, 5)).is_empty());
+        assert_eq!(stats_of_a(&gossiped(150, 6))["id"].max, StatValue::Int(150));
+    }
+
+    #[test]
+    fn stats_versions_grow() {
+        let first = next_stats_version();
+        assert!(next_stats_version() > first);
+        assert!(stats_version() > first);
+    }
+
+    #[test]
+    fn hot_keys_are_summed_across_nodes() {
+        let cat_a = r#"{"rows": 1000, "schema_hash": 42, "role": "primary",
+            "hot_keys": {"customer_id": [{"value": 7, "rows": 400}, {"value": 9, "rows": 20}]}}"#;
+        let cat_b = r#"{"rows": 1000, "schema_hash": 42, "role": "primary",
+            "hot_keys": {"customer_id": [{"value": 7, "rows": 300}],
+                         "region": [{"value": "eu", "rows": 900}]}}"#;
+        let plain = catalog_json(10, 1);
+        let nodes = vec![
+            make_node("id-a", "node-a", vec![("catalog:orders", cat_a), ("catalog:dim", &plain)]),
+            make_node("id-b", "node-b", vec![("catalog:orders", cat_b)]),
+        ];
+
+        let hot = hot_keys_from_states(&nodes);
+        assert!(!hot.contains_key("dim"));
+        let customer = &hot["orders"]["customer_id"];
+        assert_eq!(
+            customer,
+            &vec![
+                HotKey { value: StatValue::Int(7), rows: 700 },
+                HotKey { value: StatValue::Int(9), rows: 20 },
+            ]
+        );
+        assert_eq!(hot["orders"]["region"][0].value, StatValue::Text("eu".to_string()));
+    }
+
+    #[test]
+    fn table_stats_lists_each_shard_with_its_partitions() {
+        let cat_a = r#"{"rows": 700, "schema_hash": 42, "role": "primary", "bytes": 7000,
+            "stats": {"id": {"min": 1, "max": 500, "null_count": 3}},
+            "partitions": {"0": {"rows": 400, "bytes": 4000}, "2": {"rows": 300, "bytes": 3000}}}"#;
+        let cat_b = r#"{"rows": 300, "schema_hash": 42, "role": "primary",
+            "stats": {"id": {"min": 501, "max": 900}}}"#;
+        let meta = r#"{"strategy":{"hash":{"column":"id","num_partitions":3}},"assignments":[
+            {"partition_id":0,"node_name":"node-a","flight_endpoint":"http://10.0.0.1:8815"},
+            {"partition_id":1,"node_name":"node-b","flight_endpoint":"http://10.0.0.2:8815"},
+            {"partition_id":2,"node_name":"node-a","flight_endpoint":"http://10.0.0.1:8815"}
+        ],"create_sql":"x"}"#;
+        let nodes = vec![
+            make_node("id-b", "node-b", vec![("catalog:orders", cat_b)]),
+            make_node(
+                "id-a",
+                "node-a",
+                vec![("catalog:orders", cat_a), ("partition:orders", meta)],
+            ),
+            make_node("id-c", "node-c", vec![("catalog:other", cat_b)]),
+        ];
+
+        let stats = table_stats_from_states(&nodes, "orders");
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].node_name, "node-a");
+        assert_eq!(
+            stats[0].partitions.iter().map(|(id, s)| (*id, s.rows, s.bytes)).collect::<Vec<_>>(),
+            vec![(0, 400, 4000), (2, 300, 3000)]
+        );
+        assert_eq!((stats[0].row_count, stats[0].byte_size), (700, 7000));
+        assert_eq!(stats[0].column_stats["id"].null_count, Some(3));
+        // A node that predates sizes still lists its partitions.
+        assert_eq!(stats[1].partitions[&1], PartitionSize::default());
+        assert_eq!(stats[1].byte_size, 0);
+        assert_eq!(stats[1].column_stats["id"].min, StatValue::Int(501));
+        assert_eq!(stats[1].column_stats["id"].null_count, None);
+        assert!(table_stats_from_states(&nodes, "missing").is_empty());
+    }
+
+    #[test]
+    fn schema_conflicts_list_every_copy_of_diverging_tables() {
+        let nodes = vec![
+            make_node(
+                "id-a",
+                "node-a",
+                vec![("catalog:orders", &catalog_json(10, 0xA)), ("catalog:items", &catalog_json(5, 7))],
+            ),
+            make_node(
+                "id-b",
+                "node-b",
+                vec![("catalog:orders", &catalog_json(20, 0xB)), ("catalog:items", &catalog_json(5, 7))],
+            ),
+            make_node(
+                "id-c",
+                "node-c",
+                vec![("catalog:orders", &catalog_json(30, 0xA)), ("catalog:items", &catalog_json(5, 0))],
+            ),
+        ];
+
+        let conflicts = schema_conflicts_from_entries(&get_all_tables_from_states(&nodes));
+        let rows: Vec<(&str, &str, u64, usize)> = conflicts
+            .iter()
+            .map(|c| (c.table_name.as_str(), c.node_name.as_str(), c.schema_hash, c.copies_with_hash))
+            .collect();
+        // An unreadable schema (hash 0) on node-c is not a conflict for items.
+        assert_eq!(
+            rows,
+            vec![
+                ("orders", "node-a", 0xA, 2),
+                ("orders", "node-c", 0xA, 2),
+                ("orders", "node-b", 0xB, 1),
+            ]
+        );
+        assert_eq!(
+            describe_schema_conflicts(&conflicts),
+            "Table 'orders' has diverging schemas across nodes: 'node-a' 0xA, 'node-c' 0xA, 'node-b' 0xB"
+        );
+    }
+
+    #[test]
+    fn stat_values_compare_within_kind() {
+        assert!(StatValue::Int(2) < StatValue::Int(10));
+        assert!(StatValue::Int(2) < StatValue::Float(2.5));
+        assert!(StatValue::Text("b".into()) > StatValue::Text("aa".into()));
+        assert_eq!(StatValue::Int(1).partial_cmp(&StatValue::Text("1".into())), None);
+
+        assert_eq!(parse_stat(StatKind::Float, "NaN"), None);
+        assert_eq!(parse_stat(StatKind::Temporal, "1704067200000000"), Some(StatValue::Int(1_704_067_200_000_000)));
+        assert_eq!(parse_stat(StatKind::Text, &"x".repeat(MAX_TEXT_STAT_LEN + 1)), None);
+    }
+
+    #[test]
+    fn classify_sharded_table_attaches_complete_replicas() {
+        let cat = catalog_json(500, 42);
+        let replica = r#"{"rows": 250, "schema_hash": 42, "role": "replica"}"#;
+        let flt_a = flight_json("10.0.0.1", 8815, "running");
+        let flt_b = flight_json("10.0.0.2", 8815, "running");
+        let meta = r#"{"strategy":{"hash":{"column":"id","num_partitions":2}},"assignments":[
+            {"partition_id":0,"node_name":"node-a","flight_endpoint":"http://10.0.0.1:8815","replicas":[{"node_name":"node-b","flight_endpoint":"http://10.0.0.2:8815"}]},
+            {"partition_id":1,"node_name":"node-b","flight_endpoint":"http://10.0.0.2:8815","replicas":[{"node_name":"node-a","flight_endpoint":"http://10.0.0.1:8815"}]}
+        ],"create_sql":"x"}"#;
+
+        let nodes = vec![
+            make_node(
+                "id-a",
+                "node-a",
+                vec![
+                    ("catalog:orders", &cat),
+                    ("service:flight", &flt_a),
+                    ("partition:orders", meta),
+                ],
+            ),
+            make_node(
+                "id-b",
+                "node-b",
+                vec![
+                    ("catalog:orders", &cat),
+                    ("replica:orders:0", replica),
+                    ("service:flight", &flt_b),
+                ],
+            ),
+            // Advertised, but not in the metadata: a replica being dropped.
+            make_node(
+                "id-c",
+                "node-c",
+                vec![
+                    ("replica:orders:0", replica),
+                    ("service:flight", &flight_json("10.0.0.3", 8815, "running")),
+                ],
+            ),
+        ];
+
+        let result = classify_tables_from_states(&nodes, Some("id-a"));
+        let Some(TableClassification::Sharded { shards }) = result.get("orders") else {
+            panic!("Expected Sharded, got {:?}", result.get("orders"));
+        };
+        let shard_a = shards.iter().find(|s| s.node_name == "node-a").unwrap();
+        assert_eq!(shard_a.replicas.len(), 1);
+        assert_eq!(shard_a.replicas[0].node_name, "node-b");
+        assert_eq!(shard_a.replicas[0].table_name, "orders__replica_0");
+        // Partition 1's replica on node-a is not advertised, so node-b has no failover.
+        let shard_b = shards.iter().find(|s| s.node_name == "node-b").unwrap();
+        assert!(shard_b.replicas.is_empty());
+        assert!(!result.contains_key("orders__replica_0"));
+    }
+
     #[test]
     fn classify_mixed_tables() {
         let cat = catalog_json(100, 1);
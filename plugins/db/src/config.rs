@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 
 /// Cluster configuration parsed from the `SWARM_CONFIG` env var.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +14,19 @@ pub struct ClusterConfig {
     pub distributed_engine: bool,
     #[serde(default)]
     pub admission: Option<AdmissionConfig>,
+    #[serde(default)]
+    pub logging: Option<crate::logging::LoggingConfig>,
+    #[serde(default)]
+    pub flight_pool: Option<FlightPoolConfig>,
+    /// Files co-located on data nodes: a path as queries write it, mapped to
+    /// its local path on each node holding part of the files. Scans of a
+    /// mapped path run on those nodes.
+    #[serde(default)]
+    pub file_mappings: crate::file_scan::FileMappings,
+    /// Shared secret authenticating gossip between nodes; falls back to
+    /// `SWARM_CLUSTER_SECRET`. Without one, gossip is unauthenticated.
+    #[serde(default)]
+    pub cluster_secret: Option<String>,
     pub nodes: HashMap<String, NodeConfig>,
 }
 
@@ -40,6 +53,51 @@ fn default_priority() -> String {
     "interactive".to_string()
 }
 
+/// `flight_pool` section of `SWARM_CONFIG`: reuse of Flight connections
+/// between nodes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightPoolConfig {
+    #[serde(default = "default_pool_max_connections")]
+    pub max_connections_per_endpoint: usize,
+    /// Idle connections are closed after this many seconds.
+    #[serde(default = "default_pool_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    #[serde(default = "default_pool_keepalive_secs")]
+    pub keepalive_interval_secs: u64,
+}
+
+fn default_pool_max_connections() -> usize {
+    crate::flight_pool::PoolSettings::default().max_connections_per_endpoint
+}
+
+fn default_pool_idle_timeout_secs() -> u64 {
+    crate::flight_pool::PoolSettings::default().idle_timeout.as_secs()
+}
+
+fn default_pool_keepalive_secs() -> u64 {
+    crate::flight_pool::PoolSettings::default().keepalive_interval.as_secs()
+}
+
+impl FlightPoolConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_connections_per_endpoint == 0 {
+            return Err("flight_pool.max_connections_per_endpoint must be at least 1".to_string());
+        }
+        if self.keepalive_interval_secs == 0 {
+            return Err("flight_pool.keepalive_interval_secs must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self) {
+        crate::flight_pool::configure(crate::flight_pool::PoolSettings {
+            max_connections_per_endpoint: self.max_connections_per_endpoint,
+            idle_timeout: std::time::Duration::from_secs(self.idle_timeout_secs),
+            keepalive_interval: std::time::Duration::from_secs(self.keepalive_interval_secs),
+        });
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TlsConfig {
     pub ca_cert: String,
@@ -106,6 +164,23 @@ impl ClusterConfig {
             return Err("cluster_id must be non-empty".to_string());
         }
 
+        if let Some(logging) = &self.logging {
+            logging.validate()?;
+        }
+
+        if let Some(flight_pool) = &self.flight_pool {
+            flight_pool.validate()?;
+        }
+
+        for (path, nodes) in &self.file_mappings {
+            if nodes.is_empty() {
+                return Err(format!("file_mappings '{path}': no nodes listed"));
+            }
+            if let Some(node) = nodes.keys().find(|n| !self.nodes.contains_key(*n)) {
+                return Err(format!("file_mappings '{path}': unknown node '{node}'"));
+            }
+        }
+
         let mut seen_addrs: HashSet<SocketAddr> = HashSet::new();
 
         for (name, node) in &self.nodes {
@@ -163,6 +238,97 @@ impl ClusterConfig {
     }
 }
 
+impl ClusterConfig {
+    /// The gossip secret: `cluster_secret`, else `SWARM_CLUSTER_SECRET`.
+    pub fn gossip_secret(&self) -> Option<String> {
+        self.cluster_secret
+            .clone()
+            .filter(|s| !s.is_empty())
+            .or_else(cluster_secret_from_env)
+    }
+}
+
+/// `SWARM_CLUSTER_SECRET`, when set and non-empty.
+pub fn cluster_secret_from_env() -> Option<String> {
+    env::var("SWARM_CLUSTER_SECRET").ok().filter(|s| !s.is_empty())
+}
+
+/// Longest `cluster_id` accepted by `trex_db_start`.
+pub const MAX_CLUSTER_ID_LEN: usize = 64;
+
+/// Check a `cluster_id` given to `trex_db_start`: non-empty, and at most
+/// [`MAX_CLUSTER_ID_LEN`] ASCII letters, digits, `-`, `_` and `.`. An empty
+/// or mistyped id would let unrelated nodes join each other, or keep
+/// related ones apart.
+pub fn validate_cluster_id(cluster_id: &str) -> Result<(), String> {
+    if cluster_id.is_empty() {
+        return Err("cluster_id must be non-empty".to_string());
+    }
+    if cluster_id.len() > MAX_CLUSTER_ID_LEN {
+        return Err(format!(
+            "cluster_id must be at most {MAX_CLUSTER_ID_LEN} characters, got {}",
+            cluster_id.len()
+        ));
+    }
+    if let Some(c) = cluster_id
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        return Err(format!(
+            "cluster_id '{cluster_id}' contains '{c}'; use letters, digits, '-', '_' and '.'"
+        ));
+    }
+    Ok(())
+}
+
+/// The address to gossip on for `host`, an IP address (IPv6 optionally in
+/// brackets) or a hostname, which is resolved now so a typo fails the start
+/// instead of leaving a node nobody can reach.
+pub fn resolve_gossip_addr(host: &str, port: u16) -> Result<SocketAddr, String> {
+    let trimmed = host.trim();
+    if trimmed.is_empty() {
+        return Err("host must be non-empty".to_string());
+    }
+    let unbracketed = trimmed
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(trimmed);
+    if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+    (trimmed, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("host '{host}' is not an IP address or resolvable hostname: {e}"))?
+        .next()
+        .ok_or_else(|| format!("host '{host}' resolved to no addresses"))
+}
+
+/// Check that `seed` is `host:port` with an IP address or a hostname. A
+/// hostname need not resolve yet: seeds that are not up are retried after
+/// the node starts.
+pub fn validate_seed_addr(seed: &str) -> Result<(), String> {
+    if seed.parse::<SocketAddr>().is_ok() {
+        return Ok(());
+    }
+    let (host, port) = seed
+        .rsplit_once(':')
+        .ok_or_else(|| format!("seed '{seed}' must be host:port"))?;
+    port.parse::<u16>()
+        .map_err(|_| format!("seed '{seed}' has an invalid port '{port}'"))?;
+    let valid_host = !host.is_empty()
+        && host
+            .split('.')
+            .all(|label| {
+                !label.is_empty()
+                    && !label.starts_with('-')
+                    && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+    if !valid_host {
+        return Err(format!("seed '{seed}' is not an IP address or hostname with a port"));
+    }
+    Ok(())
+}
+
 pub fn get_node_name() -> Result<String, String> {
     env::var("SWARM_NODE")
         .map_err(|_| "SWARM_NODE environment variable is not set".to_string())
@@ -182,6 +348,167 @@ pub fn get_node_config_by_name<'a>(config: &'a ClusterConfig, name: &str) -> Opt
         .map(|(k, v)| (k.as_str(), v))
 }
 
+/// Shape a value set through `trex_db_set` must have.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigValueKind {
+    Bool,
+    Int { min: i64, max: i64 },
+    Float { min: f64, max: f64 },
+    Enum(&'static [&'static str]),
+    /// An existing directory this process can write to.
+    Directory,
+}
+
+/// A gossip config key with a known meaning.
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigKeySpec {
+    pub key: &'static str,
+    pub kind: ConfigValueKind,
+}
+
+pub const KNOWN_CONFIG_KEYS: &[ConfigKeySpec] = &[
+    ConfigKeySpec { key: "data_node", kind: ConfigValueKind::Bool },
+    ConfigKeySpec { key: "replication_factor", kind: ConfigValueKind::Int { min: 1, max: 64 } },
+    ConfigKeySpec {
+        key: "query_memory_limit_mb",
+        kind: ConfigValueKind::Int { min: 1, max: 16 * 1024 * 1024 },
+    },
+    ConfigKeySpec {
+        key: "default_max_concurrent",
+        kind: ConfigValueKind::Int { min: 1, max: 100_000 },
+    },
+    ConfigKeySpec {
+        key: "memory_rejection_threshold_pct",
+        kind: ConfigValueKind::Float { min: 0.0, max: 100.0 },
+    },
+    ConfigKeySpec {
+        key: "shuffle_buffer_mb",
+        kind: ConfigValueKind::Int { min: 1, max: 1024 * 1024 },
+    },
+    ConfigKeySpec {
+        key: "default_priority",
+        kind: ConfigValueKind::Enum(&["batch", "interactive", "system"]),
+    },
+    ConfigKeySpec {
+        key: "schema_conflict_action",
+        kind: ConfigValueKind::Enum(&["warn", "refuse"]),
+    },
+    ConfigKeySpec {
+        key: "scheduler_load_policy",
+        kind: ConfigValueKind::Enum(&["round_robin", "least_loaded", "locality"]),
+    },
+    ConfigKeySpec {
+        key: "node_query_threads",
+        kind: ConfigValueKind::Int { min: 1, max: 1024 },
+    },
+    ConfigKeySpec { key: "spill_dir", kind: ConfigValueKind::Directory },
+];
+
+pub fn config_key_spec(key: &str) -> Option<&'static ConfigKeySpec> {
+    KNOWN_CONFIG_KEYS.iter().find(|spec| spec.key == key)
+}
+
+/// Check `value` against the schema of `key` and return it in canonical
+/// form (`true`/`false`, plain integers, lowercase enum members, trimmed
+/// paths).
+/// `Ok(None)` means the key is not a known one and `value` is passed on
+/// unchanged.
+pub fn validate_config_value(key: &str, value: &str) -> Result<Option<String>, String> {
+    let Some(spec) = config_key_spec(key) else {
+        return Ok(None);
+    };
+    let trimmed = value.trim();
+    let canonical = match spec.kind {
+        ConfigValueKind::Bool => match trimmed.to_ascii_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => "true".to_string(),
+            "false" | "0" | "no" | "off" => "false".to_string(),
+            _ => return Err(format!("{key} must be true or false, got '{value}'")),
+        },
+        ConfigValueKind::Int { min, max } => {
+            let n: i64 = trimmed
+                .parse()
+                .map_err(|_| format!("{key} must be an integer, got '{value}'"))?;
+            if !(min..=max).contains(&n) {
+                return Err(format!("{key} must be between {min} and {max}, got {n}"));
+            }
+            n.to_string()
+        }
+        ConfigValueKind::Float { min, max } => {
+            let x: f64 = trimmed
+                .parse()
+                .ok()
+                .filter(|x: &f64| x.is_finite())
+                .ok_or_else(|| format!("{key} must be a number, got '{value}'"))?;
+            if !(min..=max).contains(&x) {
+                return Err(format!("{key} must be between {min} and {max}, got {x}"));
+            }
+            x.to_string()
+        }
+        ConfigValueKind::Enum(members) => {
+            let lower = trimmed.to_ascii_lowercase();
+            if !members.contains(&lower.as_str()) {
+                return Err(format!(
+                    "{key} must be one of {}, got '{value}'",
+                    members.join(", ")
+                ));
+            }
+            lower
+        }
+        ConfigValueKind::Directory => {
+            crate::spill::validate_spill_dir(std::path::Path::new(trimmed))?;
+            trimmed.to_string()
+        }
+    };
+    Ok(Some(canonical))
+}
+
+/// Gossip key prefixes that advertise tables and services rather than
+/// configuration; `trex_db_tables` and `trex_db_services` list those.
+const ADVERTISEMENT_PREFIXES: &[&str] = &["catalog:", "service:"];
+
+/// One node's value for one key in `trex_db_cluster_config`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterConfigRow {
+    pub node_name: String,
+    pub key: String,
+    pub value: String,
+    /// Every node publishes this key with the same value.
+    pub consistent: bool,
+}
+
+/// Flatten each node's published config into rows sorted by key, then
+/// node. A key missing on some node counts as inconsistent. Resource
+/// readings (`cpu_pct`, `free_mem_mb`) change with every sample and are
+/// skipped along with advertisements.
+pub fn compare_node_configs(nodes: &[(String, Vec<(String, String)>)]) -> Vec<ClusterConfigRow> {
+    let mut by_key: BTreeMap<&str, Vec<(&str, &str)>> = BTreeMap::new();
+    for (node_name, key_values) in nodes {
+        for (key, value) in key_values {
+            if ADVERTISEMENT_PREFIXES.iter().any(|p| key.starts_with(p))
+                || key == crate::resources::CPU_PCT_KEY
+                || key == crate::resources::FREE_MEM_MB_KEY
+            {
+                continue;
+            }
+            by_key.entry(key).or_default().push((node_name, value));
+        }
+    }
+
+    let mut rows = Vec::new();
+    for (key, mut values) in by_key {
+        values.sort();
+        let consistent =
+            values.len() == nodes.len() && values.windows(2).all(|w| w[0].1 == w[1].1);
+        rows.extend(values.into_iter().map(|(node_name, value)| ClusterConfigRow {
+            node_name: node_name.to_string(),
+            key: key.to_string(),
+            value: value.to_string(),
+            consistent,
+        }));
+    }
+    rows
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,6 +549,70 @@ mod tests {
         assert!(b.extensions.is_empty());
     }
 
+    #[test]
+    fn parse_cluster_secret() {
+        let json = r#"{ "cluster_id": "c", "cluster_secret": "s3cret", "nodes": {} }"#;
+        let cfg = ClusterConfig::from_json(json).unwrap();
+        assert_eq!(cfg.gossip_secret().as_deref(), Some("s3cret"));
+        assert_eq!(ClusterConfig::from_json(sample_json()).unwrap().cluster_secret, None);
+    }
+
+    #[test]
+    fn parse_logging_section() {
+        let json = r#"{
+            "cluster_id": "c",
+            "logging": { "level": "debug", "format": "json" },
+            "nodes": {}
+        }"#;
+        let cfg = ClusterConfig::from_json(json).unwrap();
+        let logging = cfg.logging.unwrap();
+        assert_eq!(logging.level.as_deref(), Some("debug"));
+        assert_eq!(logging.format.as_deref(), Some("json"));
+
+        let bad = r#"{ "cluster_id": "c", "logging": { "format": "xml" }, "nodes": {} }"#;
+        assert!(ClusterConfig::from_json(bad).unwrap_err().contains("logging.format"));
+    }
+
+    #[test]
+    fn parse_flight_pool_section() {
+        let json = r#"{
+            "cluster_id": "c",
+            "flight_pool": { "max_connections_per_endpoint": 8, "idle_timeout_secs": 30 },
+            "nodes": {}
+        }"#;
+        let cfg = ClusterConfig::from_json(json).unwrap();
+        let pool = cfg.flight_pool.unwrap();
+        assert_eq!(pool.max_connections_per_endpoint, 8);
+        assert_eq!(pool.idle_timeout_secs, 30);
+        assert_eq!(pool.keepalive_interval_secs, 30);
+
+        let bad = r#"{ "cluster_id": "c", "flight_pool": { "max_connections_per_endpoint": 0 }, "nodes": {} }"#;
+        assert!(ClusterConfig::from_json(bad)
+            .unwrap_err()
+            .contains("max_connections_per_endpoint"));
+    }
+
+    #[test]
+    fn parse_file_mappings_section() {
+        let json = r#"{
+            "cluster_id": "c",
+            "file_mappings": {
+                "/data/events/*.parquet": { "n1": "/mnt/events/*.parquet" }
+            },
+            "nodes": { "n1": { "gossip_addr": "10.0.0.1:7100" } }
+        }"#;
+        let cfg = ClusterConfig::from_json(json).unwrap();
+        assert_eq!(
+            cfg.file_mappings["/data/events/*.parquet"]["n1"],
+            "/mnt/events/*.parquet"
+        );
+
+        let unknown = json.replace(r#""n1": "/mnt"#, r#""n2": "/mnt"#);
+        assert!(ClusterConfig::from_json(&unknown)
+            .unwrap_err()
+            .contains("unknown node 'n2'"));
+    }
+
     #[test]
     fn parse_with_tls() {
         let json = r#"{
@@ -248,6 +639,36 @@ mod tests {
         assert!(err.contains("cluster_id"), "error was: {err}");
     }
 
+    #[test]
+    fn start_cluster_ids_are_validated() {
+        assert!(validate_cluster_id("prod-eu_1.a").is_ok());
+        assert!(validate_cluster_id("").unwrap_err().contains("non-empty"));
+        assert!(validate_cluster_id("pro d").unwrap_err().contains("' '"));
+        assert!(validate_cluster_id("prod@v2").is_err());
+        assert!(validate_cluster_id(&"a".repeat(MAX_CLUSTER_ID_LEN + 1)).is_err());
+    }
+
+    #[test]
+    fn start_hosts_are_resolved_and_seeds_checked() {
+        assert_eq!(
+            resolve_gossip_addr("127.0.0.1", 7000).unwrap(),
+            "127.0.0.1:7000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            resolve_gossip_addr("[::1]", 7000).unwrap(),
+            "[::1]:7000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(resolve_gossip_addr("localhost", 7000).unwrap().port(), 7000);
+        assert!(resolve_gossip_addr(" ", 7000).is_err());
+
+        assert!(validate_seed_addr("10.0.0.2:7001").is_ok());
+        assert!(validate_seed_addr("[::1]:7001").is_ok());
+        assert!(validate_seed_addr("seed-0.trex.svc:7001").is_ok());
+        assert!(validate_seed_addr("10.0.0.2").is_err());
+        assert!(validate_seed_addr("seed_0:7001").is_err());
+        assert!(validate_seed_addr("seed-0:70000").is_err());
+    }
+
     #[test]
     fn invalid_gossip_addr_rejected() {
         let json = r#"{
@@ -363,4 +784,76 @@ mod tests {
         }"#;
         assert!(ClusterConfig::from_json(json).is_ok());
     }
+
+    #[test]
+    fn known_config_values_are_canonicalized() {
+        assert_eq!(validate_config_value("data_node", " TRUE ").unwrap().as_deref(), Some("true"));
+        assert_eq!(validate_config_value("data_node", "off").unwrap().as_deref(), Some("false"));
+        assert_eq!(validate_config_value("replication_factor", "3").unwrap().as_deref(), Some("3"));
+        assert_eq!(
+            validate_config_value("memory_rejection_threshold_pct", "85.5").unwrap().as_deref(),
+            Some("85.5")
+        );
+        assert_eq!(
+            validate_config_value("default_priority", "Batch").unwrap().as_deref(),
+            Some("batch")
+        );
+    }
+
+    #[test]
+    fn invalid_config_values_rejected() {
+        let err = validate_config_value("replication_factor", "three").unwrap_err();
+        assert!(err.contains("integer"), "error was: {err}");
+        let err = validate_config_value("replication_factor", "0").unwrap_err();
+        assert!(err.contains("between 1 and 64"), "error was: {err}");
+        let err = validate_config_value("query_memory_limit_mb", "-5").unwrap_err();
+        assert!(err.contains("query_memory_limit_mb"), "error was: {err}");
+        let err = validate_config_value("shuffle_buffer_mb", "0").unwrap_err();
+        assert!(err.contains("shuffle_buffer_mb"), "error was: {err}");
+        let err = validate_config_value("data_node", "maybe").unwrap_err();
+        assert!(err.contains("true or false"), "error was: {err}");
+        let err = validate_config_value("memory_rejection_threshold_pct", "NaN").unwrap_err();
+        assert!(err.contains("number"), "error was: {err}");
+        let err = validate_config_value("default_priority", "urgent").unwrap_err();
+        assert!(err.contains("batch, interactive, system"), "error was: {err}");
+        let err = validate_config_value("spill_dir", "/nonexistent/trex-spill").unwrap_err();
+        assert!(err.contains("spill_dir"), "error was: {err}");
+    }
+
+    #[test]
+    fn unknown_config_keys_pass_through() {
+        assert_eq!(validate_config_value("my_custom_key", "anything").unwrap(), None);
+    }
+
+    #[test]
+    fn compare_node_configs_flags_drift() {
+        let kv = |pairs: &[(&str, &str)]| {
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<Vec<_>>()
+        };
+        let nodes = vec![
+            (
+                "b".to_string(),
+                kv(&[("replication_factor", "2"), ("data_node", "true"), ("catalog:t", "{}"), ("cpu_pct", "12.5")]),
+            ),
+            (
+                "a".to_string(),
+                kv(&[("replication_factor", "3"), ("data_node", "true"), ("only_a", "x")]),
+            ),
+        ];
+        let rows = compare_node_configs(&nodes);
+        let summary: Vec<(&str, &str, &str, bool)> = rows
+            .iter()
+            .map(|r| (r.key.as_str(), r.node_name.as_str(), r.value.as_str(), r.consistent))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("data_node", "a", "true", true),
+                ("data_node", "b", "true", true),
+                ("only_a", "a", "x", false),
+                ("replication_factor", "a", "3", false),
+                ("replication_factor", "b", "2", false),
+            ]
+        );
+    }
 }
@@ -4,13 +4,15 @@
 use std::sync::Arc;
 use std::time::Instant;
 
-use arrow::array::RecordBatch;
+use arrow::array::{ArrayRef, RecordBatch};
 use arrow::compute::concat_batches;
 use arrow::datatypes::SchemaRef;
 use uuid::Uuid;
 
 use crate::aggregation::{self, DecomposedQuery};
 use crate::catalog;
+use crate::error::{SwarmError, SwarmResult};
+use crate::file_scan::FileScanPlan;
 use crate::flight_client;
 use crate::logging::{LogLevel, SwarmLogger};
 
@@ -24,14 +26,15 @@ pub struct QueryResult {
 pub fn execute_distributed_query(
     sql: &str,
     partial_results: bool,
-) -> Result<QueryResult, String> {
-    let query_id = Uuid::new_v4();
+) -> SwarmResult<QueryResult> {
+    let query_id = crate::logging::current_query_id()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
     let start = Instant::now();
 
     SwarmLogger::log_with_context(
         LogLevel::Info,
         "coordinator",
-        &[("query_id", &query_id.to_string())],
+        &[("query_id", &query_id)],
         &format!("Received query: {sql}"),
     );
 
@@ -41,27 +44,27 @@ pub fn execute_distributed_query(
             SwarmLogger::log_with_context(
                 LogLevel::Debug,
                 "coordinator",
-                &[("query_id", &query_id.to_string())],
+                &[("query_id", &query_id)],
                 "No table in query, executing locally",
             );
             return execute_local_query(sql);
         }
-        Err(e) => return Err(e),
+        Err(e) => return Err(SwarmError::SqlExecution(e)),
     };
 
     SwarmLogger::log_with_context(
         LogLevel::Debug,
         "coordinator",
-        &[("query_id", &query_id.to_string())],
+        &[("query_id", &query_id)],
         &format!("Extracted table name: {table_name}"),
     );
 
     let catalog_entries = catalog::resolve_table(&table_name)?;
 
     if catalog_entries.is_empty() {
-        return Err(format!(
+        return Err(SwarmError::Internal(format!(
             "No data nodes found for table '{table_name}'"
-        ));
+        )));
     }
 
     let target_nodes: Vec<String> = catalog_entries
@@ -70,17 +73,17 @@ pub fn execute_distributed_query(
         .collect();
 
     if target_nodes.is_empty() {
-        return Err(format!(
+        return Err(SwarmError::Internal(format!(
             "No Flight endpoints available for table '{table_name}' (found {} node(s) but none have Flight running)",
             catalog_entries.len(),
-        ));
+        )));
     }
 
     let node_list = target_nodes.join(", ");
     SwarmLogger::log_with_context(
         LogLevel::Info,
         "coordinator",
-        &[("query_id", &query_id.to_string())],
+        &[("query_id", &query_id)],
         &format!(
             "Resolved {} target node(s) for table '{}': [{}]",
             target_nodes.len(),
@@ -89,18 +92,69 @@ pub fn execute_distributed_query(
         ),
     );
 
-    let decomposed = aggregation::decompose_query(sql)?;
+    let decomposed = aggregation::decompose_query(sql).map_err(SwarmError::SqlExecution)?;
 
     SwarmLogger::log_with_context(
         LogLevel::Debug,
         "coordinator",
-        &[("query_id", &query_id.to_string())],
+        &[("query_id", &query_id)],
         &format!(
             "Decomposed query: has_aggregations={}, node_sql=\"{}\", merge_sql=\"{}\"",
             decomposed.has_aggregations, decomposed.node_sql, decomposed.merge_sql,
         ),
     );
 
+    let tasks = target_nodes
+        .iter()
+        .map(|endpoint| (endpoint.clone(), decomposed.node_sql.clone()))
+        .collect();
+    run_on_nodes(&query_id, tasks, &decomposed, partial_results, start)
+}
+
+/// Run a [`FileScanPlan`] on the nodes holding its files and merge their
+/// results as for a sharded table.
+pub fn execute_file_scan(plan: &FileScanPlan, partial_results: bool) -> SwarmResult<QueryResult> {
+    let query_id = crate::logging::current_query_id()
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let start = Instant::now();
+
+    let endpoints = catalog::flight_endpoints_by_node()?;
+    let tasks = plan
+        .scans
+        .iter()
+        .map(|scan| {
+            let endpoint = endpoints.get(&scan.node_name).ok_or_else(|| {
+                SwarmError::Internal(format!(
+                    "No Flight endpoint for node '{}', which holds files of '{}'",
+                    scan.node_name, plan.logical_path,
+                ))
+            })?;
+            Ok((endpoint.clone(), scan.sql.clone()))
+        })
+        .collect::<SwarmResult<Vec<_>>>()?;
+
+    SwarmLogger::log_with_context(
+        LogLevel::Info,
+        "coordinator",
+        &[("query_id", &query_id)],
+        &format!(
+            "Pushing scan of '{}' down to {} node(s)",
+            plan.logical_path,
+            tasks.len(),
+        ),
+    );
+
+    run_on_nodes(&query_id, tasks, &plan.decomposed, partial_results, start)
+}
+
+/// Run each `(endpoint, sql)` task, then merge the results per `decomposed`.
+fn run_on_nodes(
+    query_id: &str,
+    tasks: Vec<(String, String)>,
+    decomposed: &DecomposedQuery,
+    partial_results: bool,
+    start: Instant,
+) -> SwarmResult<QueryResult> {
     let rt = tokio::runtime::Builder::new_multi_thread()
         .worker_threads(2)
         .enable_all()
@@ -109,14 +163,14 @@ pub fn execute_distributed_query(
 
     let fan_out_start = Instant::now();
     let (all_node_batches, errors) = rt.block_on(async {
-        let mut handles = Vec::with_capacity(target_nodes.len());
+        let mut handles = Vec::with_capacity(tasks.len());
 
-        for endpoint in &target_nodes {
-            let ep = endpoint.clone();
-            let node_sql = decomposed.node_sql.clone();
+        for (ep, node_sql) in tasks {
             let qid = query_id.to_string();
 
-            handles.push(tokio::spawn(async move {
+            let task_qid = Some(qid.clone());
+
+            handles.push(tokio::spawn(crate::logging::with_query_id(task_qid, async move {
                 let node_start = Instant::now();
                 let result = flight_client::query_node(&ep, &node_sql).await;
                 let elapsed_ms = node_start.elapsed().as_millis();
@@ -147,57 +201,74 @@ pub fn execute_distributed_query(
                 }
 
                 (ep, result)
-            }));
+            })));
         }
 
-        let mut all_node_batches: Vec<Vec<RecordBatch>> = Vec::with_capacity(target_nodes.len());
-        let mut errors: Vec<String> = Vec::new();
+        let mut all_node_batches: Vec<Vec<RecordBatch>> = Vec::with_capacity(handles.len());
+        let mut errors: Vec<SwarmError> = Vec::new();
+        let mut skipped: Vec<SwarmError> = Vec::new();
 
         for handle in handles {
             match handle.await {
                 Ok((endpoint, result)) => match result {
                     Ok(batches) => {
+                        crate::admission::record_bytes_returned(
+                            Some(query_id),
+                            batches.iter().map(|b| b.get_array_memory_size() as u64).sum(),
+                        );
                         all_node_batches.push(batches);
                     }
                     Err(e) => {
-                        let msg = format!("Node {endpoint} failed: {e}");
                         if partial_results {
                             SwarmLogger::log_with_context(
                                 LogLevel::Warn,
                                 "coordinator",
-                                &[("query_id", &query_id.to_string())],
+                                &[("query_id", query_id)],
                                 &format!(
                                     "Partial results mode: ignoring failure from {endpoint}: {e}"
                                 ),
                             );
+                            crate::partial_results::record_missing(
+                                Some(query_id),
+                                format!("node {endpoint}"),
+                            );
+                            skipped.push(e.map_message(|m| format!("Node {endpoint} failed: {m}")));
                         } else {
+                            let err = e.map_message(|m| format!("Node {endpoint} failed: {m}"));
                             SwarmLogger::log_with_context(
                                 LogLevel::Error,
                                 "coordinator",
-                                &[("query_id", &query_id.to_string())],
-                                &msg,
+                                &[("query_id", query_id)],
+                                err.message(),
                             );
-                            errors.push(msg);
+                            errors.push(err);
                         }
                     }
                 },
                 Err(e) => {
-                    errors.push(format!("Task join error: {e}"));
+                    errors.push(SwarmError::Internal(format!("Task join error: {e}")));
                 }
             }
         }
 
+        // A partial result still needs one node to have answered.
+        if all_node_batches.is_empty() {
+            errors.append(&mut skipped);
+        }
+
         (all_node_batches, errors)
     });
 
     let fan_out_ms = fan_out_start.elapsed().as_millis();
 
-    if !errors.is_empty() {
-        return Err(format!(
+    // The combined error takes the kind of the first failure.
+    if let Some(first) = errors.first() {
+        let combined = format!(
             "Distributed query failed on {} node(s): {}",
             errors.len(),
-            errors.join("; "),
-        ));
+            errors.iter().map(|e| e.message()).collect::<Vec<_>>().join("; "),
+        );
+        return Err(first.clone().map_message(|_| combined));
     }
 
     if all_node_batches.is_empty()
@@ -206,7 +277,7 @@ pub fn execute_distributed_query(
         SwarmLogger::log_with_context(
             LogLevel::Info,
             "coordinator",
-            &[("query_id", &query_id.to_string())],
+            &[("query_id", query_id)],
             &format!(
                 "Query returned no results from any node (fan-out took {}ms)",
                 fan_out_ms,
@@ -227,7 +298,7 @@ pub fn execute_distributed_query(
     }
 
     let merge_start = Instant::now();
-    let result = merge_batches(all_node_batches, &decomposed)?;
+    let result = merge_batches(all_node_batches, decomposed)?;
     let merge_ms = merge_start.elapsed().as_millis();
 
     let total_rows: usize = result.batches.iter().map(|b| b.num_rows()).sum();
@@ -236,7 +307,7 @@ pub fn execute_distributed_query(
     SwarmLogger::log_with_context(
         LogLevel::Info,
         "coordinator",
-        &[("query_id", &query_id.to_string())],
+        &[("query_id", query_id)],
         &format!(
             "Query complete: {} row(s), fan-out={}ms, merge={}ms, total={}ms",
             total_rows, fan_out_ms, merge_ms, total_ms,
@@ -247,8 +318,8 @@ pub fn execute_distributed_query(
 }
 
 /// Execute locally for queries without a FROM clause.
-fn execute_local_query(sql: &str) -> Result<QueryResult, String> {
-    let (_schema, batches) = crate::pool::read_arrow(sql)?;
+fn execute_local_query(sql: &str) -> SwarmResult<QueryResult> {
+    let (_schema, batches) = crate::pool::read_arrow(sql).map_err(SwarmError::SqlExecution)?;
 
     let schema = if let Some(first) = batches.first() {
         first.schema()
@@ -333,12 +404,33 @@ fn extract_table_from_factor(factor: &sqlparser::ast::TableFactor) -> Option<Str
     }
 }
 
+/// `array[row]` as `trex_db_query` returns it, in a VARCHAR column. Binary
+/// values render as hex, and control characters other than tab and line
+/// breaks are escaped as `\u{XXXX}`, so the text never holds a NUL byte
+/// and can always be passed to DuckDB as a C string.
+pub fn render_value(array: &ArrayRef, row: usize) -> String {
+    let value = arrow::util::display::array_value_to_string(array, row).unwrap_or_default();
+    let escaped = |c: char| c.is_control() && !matches!(c, '\t' | '\n' | '\r');
+    if !value.chars().any(escaped) {
+        return value;
+    }
+    let mut rendered = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        if escaped(c) {
+            rendered.push_str(&format!("\\u{{{:04x}}}", c as u32));
+        } else {
+            rendered.push(c);
+        }
+    }
+    rendered
+}
+
 /// Merge per-node batches: concatenate for non-aggregates, or load into
 /// DuckDB and run merge SQL for aggregates.
 pub fn merge_batches(
     node_batches: Vec<Vec<RecordBatch>>,
     decomposed: &DecomposedQuery,
-) -> Result<QueryResult, String> {
+) -> SwarmResult<QueryResult> {
     let all_batches: Vec<RecordBatch> = node_batches
         .into_iter()
         .flat_map(|nb| nb.into_iter())
@@ -371,11 +463,11 @@ fn merge_with_duckdb(
     schema: &SchemaRef,
     batches: Vec<RecordBatch>,
     merge_sql: &str,
-) -> Result<QueryResult, String> {
+) -> SwarmResult<QueryResult> {
     use duckdb::vtab::arrow::{arrow_recordbatch_to_query_params, ArrowVTab};
 
     let merged_batch = concat_batches(schema, &batches)
-        .map_err(|e| format!("Failed to concatenate record batches: {e}"))?;
+        .map_err(|e| SwarmError::Internal(format!("Failed to concatenate record batches: {e}")))?;
 
     let rewritten_sql = merge_sql.replace(
         "FROM _merged",
@@ -407,12 +499,29 @@ fn merge_with_duckdb(
             batches: result_batches,
         })
     })
+    .map_err(SwarmError::SqlExecution)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn render_value_escapes_nul_and_control_characters() {
+        use arrow::array::{BinaryArray, StringArray};
+
+        let strings: ArrayRef =
+            Arc::new(StringArray::from(vec!["a\0b", "tab\there\n", "bell\u{7}", "ünï"]));
+        assert_eq!(render_value(&strings, 0), "a\\u{0000}b");
+        assert_eq!(render_value(&strings, 1), "tab\there\n");
+        assert_eq!(render_value(&strings, 2), "bell\\u{0007}");
+        assert_eq!(render_value(&strings, 3), "ünï");
+
+        let binary: ArrayRef = Arc::new(BinaryArray::from(vec![&b"\0\xffA"[..]]));
+        assert_eq!(render_value(&binary, 0), "00ff41");
+        assert!(std::ffi::CString::new(render_value(&strings, 0)).is_ok());
+    }
+
     #[test]
     fn extract_simple_table() {
         let result = extract_table_name("SELECT * FROM orders").unwrap();
@@ -7,10 +7,13 @@ use std::sync::{Arc, Mutex, OnceLock};
 
 use arrow::array::RecordBatch;
 use arrow::datatypes::SchemaRef;
+use datafusion::execution::session_state::SessionStateBuilder;
 use datafusion::prelude::SessionContext;
 
 use crate::catalog;
+use crate::error::{SwarmError, SwarmResult};
 use crate::logging::SwarmLogger;
+use crate::spill;
 
 pub struct SchedulerConfig {
     pub bind_addr: String,
@@ -37,13 +40,13 @@ fn scheduler_lock() -> &'static Mutex<Option<SchedulerHandle>> {
     SCHEDULER.get_or_init(|| Mutex::new(None))
 }
 
-pub fn start_scheduler(config: SchedulerConfig) -> Result<(), String> {
+pub fn start_scheduler(config: SchedulerConfig) -> SwarmResult<()> {
     let mut guard = scheduler_lock()
         .lock()
         .map_err(|_| "Scheduler lock poisoned".to_string())?;
 
     if guard.is_some() {
-        return Err("Scheduler is already running".to_string());
+        return Err(SwarmError::Internal("Scheduler is already running".to_string()));
     }
 
     // Create the Tokio runtime on a separate thread to avoid "Cannot start a
@@ -110,7 +113,7 @@ pub fn start_scheduler(config: SchedulerConfig) -> Result<(), String> {
     Ok(())
 }
 
-pub fn stop_scheduler() -> Result<(), String> {
+pub fn stop_scheduler() -> SwarmResult<()> {
     let active = {
         let guard = scheduler_lock()
             .lock()
@@ -121,13 +124,7 @@ pub fn stop_scheduler() -> Result<(), String> {
         Arc::clone(&handle.active_queries)
     };
 
-    // Wait up to 5 seconds for active queries to drain.
-    for _ in 0..50 {
-        if active.load(AtomicOrdering::SeqCst) == 0 {
-            break;
-        }
-        std::thread::sleep(std::time::Duration::from_millis(100));
-    }
+    wait_until_idle(&active, std::time::Duration::from_secs(5));
 
     let mut guard = scheduler_lock()
         .lock()
@@ -145,8 +142,35 @@ pub fn stop_scheduler() -> Result<(), String> {
     Ok(())
 }
 
+/// Poll `active` until no query is running or `timeout` passes. Returns
+/// whether it drained.
+fn wait_until_idle(active: &AtomicUsize, timeout: std::time::Duration) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    while active.load(AtomicOrdering::SeqCst) > 0 {
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    true
+}
+
+/// Wait up to `timeout` for the queries running on this node's scheduler to
+/// finish, e.g. before dropping tables they may read. True when none are
+/// left, including when no scheduler runs here.
+pub fn wait_for_active_queries(timeout: std::time::Duration) -> bool {
+    let active = match scheduler_lock().lock() {
+        Ok(guard) => match guard.as_ref() {
+            Some(handle) => Arc::clone(&handle.active_queries),
+            None => return true,
+        },
+        Err(_) => return false,
+    };
+    wait_until_idle(&active, timeout)
+}
+
 /// Rebuild session context from catalog to pick up cluster topology changes.
-pub fn refresh_session() -> Result<(), String> {
+pub fn refresh_session() -> SwarmResult<()> {
     let (rt_handle, ctx_lock) = {
         let guard = scheduler_lock()
             .lock()
@@ -215,7 +239,7 @@ pub fn is_scheduler_running() -> bool {
         .unwrap_or(false)
 }
 
-pub fn submit_query(sql: &str) -> Result<(SchemaRef, Vec<RecordBatch>), String> {
+pub fn submit_query(sql: &str) -> SwarmResult<(SchemaRef, Vec<RecordBatch>)> {
     // Release the lock before block_on to avoid holding it across await points.
     let (rt_handle, ctx, active) = {
         let guard = scheduler_lock()
@@ -231,23 +255,33 @@ pub fn submit_query(sql: &str) -> Result<(SchemaRef, Vec<RecordBatch>), String>
     let _guard = QueryGuard(active);
 
     let sql = sql.to_string();
+    let query_id = crate::logging::current_query_id();
     // Run block_on in a separate thread to avoid nested-runtime panic when
     // called from a DuckDB function that is inside a tokio context.
     let (schema, batches) = std::thread::spawn(move || {
-        rt_handle.block_on(async {
+        rt_handle.block_on(crate::logging::with_query_id(query_id.clone(), async {
             let ctx_read = ctx.read().await;
-            let df = ctx_read
+            SwarmLogger::debug("scheduler", &format!("Planning query: {sql}"));
+            let (session, _spill_dir) = query_session(&ctx_read, query_id.as_deref())?;
+            let df = session
                 .sql(&sql)
                 .await
-                .map_err(|e| format!("Distributed SQL planning failed: {e}"))?;
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed SQL planning failed"))?;
             // Capture schema from the DataFrame before collect() so we have
             // column metadata even when the result set is empty.
             let schema: SchemaRef = Arc::new(df.schema().as_arrow().clone());
             let batches = df.collect()
                 .await
-                .map_err(|e| format!("Distributed query execution failed: {e}"))?;
-            Ok::<_, String>((schema, batches))
-        })
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed query execution failed"))?;
+            SwarmLogger::debug(
+                "scheduler",
+                &format!(
+                    "Query returned {} row(s)",
+                    batches.iter().map(|b| b.num_rows()).sum::<usize>()
+                ),
+            );
+            Ok::<_, SwarmError>((schema, batches))
+        }))
     })
     .join()
     .map_err(|_| "Query execution thread panicked".to_string())??;
@@ -255,8 +289,457 @@ pub fn submit_query(sql: &str) -> Result<(SchemaRef, Vec<RecordBatch>), String>
     Ok((schema, batches))
 }
 
+/// File format written by [`export_query`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Parquet,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Option<Self> {
+        match format.to_ascii_lowercase().as_str() {
+            "parquet" => Some(ExportFormat::Parquet),
+            "csv" => Some(ExportFormat::Csv),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Parquet => "parquet",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub rows: u64,
+    pub files: usize,
+}
+
+/// Whether an export to `output_path` writes a directory of files, one per
+/// writer, rather than a single file.
+pub fn is_directory_export(output_path: &str) -> bool {
+    output_path.ends_with('/')
+}
+
+/// Run `sql` and write its result to `output_path` as it streams out of
+/// the plan, so the result is never held in memory whole.
+///
+/// A path ending in `/` is a directory that the result is written into in
+/// parallel, several files at a time; it must be empty or not exist yet, so
+/// the file count covers only this export. Any other path gets one file.
+pub fn export_query(
+    sql: &str,
+    output_path: &str,
+    format: ExportFormat,
+) -> SwarmResult<ExportSummary> {
+    let directory = is_directory_export(output_path);
+    if directory {
+        if let Ok(mut entries) = std::fs::read_dir(output_path) {
+            if entries.next().is_some() {
+                return Err(SwarmError::Internal(format!(
+                    "Export directory '{output_path}' is not empty"
+                )));
+            }
+        }
+    }
+
+    let (rt_handle, ctx, active) = {
+        let guard = scheduler_lock()
+            .lock()
+            .map_err(|_| "Scheduler lock poisoned".to_string())?;
+        let handle = guard
+            .as_ref()
+            .ok_or_else(|| "Scheduler is not running".to_string())?;
+        (handle.runtime.handle().clone(), Arc::clone(&handle.ctx), Arc::clone(&handle.active_queries))
+    };
+
+    active.fetch_add(1, AtomicOrdering::SeqCst);
+    let _guard = QueryGuard(active);
+
+    let sql = sql.to_string();
+    let path = output_path.to_string();
+    let query_id = crate::logging::current_query_id();
+    let counts = std::thread::spawn(move || {
+        rt_handle.block_on(crate::logging::with_query_id(query_id.clone(), async {
+            use datafusion::dataframe::DataFrameWriteOptions;
+
+            let ctx_read = ctx.read().await;
+            SwarmLogger::debug("scheduler", &format!("Planning export to {path}: {sql}"));
+            let (session, _spill_dir) = query_session(&ctx_read, query_id.as_deref())?;
+            let df = session
+                .sql(&sql)
+                .await
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed SQL planning failed"))?;
+            let options = DataFrameWriteOptions::new().with_single_file_output(!directory);
+            let written = match format {
+                ExportFormat::Parquet => df.write_parquet(&path, options, None).await,
+                ExportFormat::Csv => df.write_csv(&path, options, None).await,
+            };
+            written.map_err(|e| SwarmError::from_datafusion(&e, "Distributed export failed"))
+        }))
+    })
+    .join()
+    .map_err(|_| "Export thread panicked".to_string())??;
+
+    let rows = counts
+        .iter()
+        .filter_map(|batch| {
+            batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<arrow::array::UInt64Array>()
+        })
+        .flat_map(|counts| counts.iter().flatten())
+        .sum();
+    let files = if directory {
+        count_export_files(output_path, format)
+    } else {
+        1
+    };
+
+    SwarmLogger::info(
+        "scheduler",
+        &format!("Exported {rows} row(s) to {files} file(s) at {output_path}"),
+    );
+    Ok(ExportSummary { rows, files })
+}
+
+fn count_export_files(dir: &str, format: ExportFormat) -> usize {
+    std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter(|e| {
+                    e.path()
+                        .extension()
+                        .is_some_and(|ext| ext == format.extension())
+                })
+                .count()
+        })
+        .unwrap_or(0)
+}
+
+/// `ctx` with the query id attached to its config, so operators can recover
+/// it from their `TaskContext` even on tasks DataFusion spawns itself, and
+/// with its spill files in a directory of the query's own. The directory
+/// is removed when the returned [`spill::QuerySpillDir`] is dropped.
+fn query_session(
+    ctx: &SessionContext,
+    query_id: Option<&str>,
+) -> SwarmResult<(SessionContext, spill::QuerySpillDir)> {
+    let state = ctx.state();
+    let spill_id = query_id
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let (runtime_env, spill_dir) = spill::query_runtime(state.runtime_env(), &spill_id)?;
+    let mut config = state.config().clone();
+    if let Some(query_id) = query_id {
+        config = config.with_extension(Arc::new(crate::logging::QueryLogId(query_id.to_string())));
+    }
+    let session = SessionContext::new_with_state(
+        SessionStateBuilder::new_from_existing(state)
+            .with_config(config)
+            .with_runtime_env(runtime_env)
+            .build(),
+    );
+    Ok((session, spill_dir))
+}
+
+/// Optimized logical plan of `sql` in the distributed session, as indented
+/// text, followed by the shard pruning of each sharded scan and the node
+/// each of its shards is read from under the load policy. Planning only:
+/// nothing is executed and no shuffles are set up.
+pub fn explain_query(sql: &str) -> SwarmResult<String> {
+    let (rt_handle, ctx) = {
+        let guard = scheduler_lock()
+            .lock()
+            .map_err(|_| "Scheduler lock poisoned".to_string())?;
+        let handle = guard
+            .as_ref()
+            .ok_or_else(|| "Scheduler is not running".to_string())?;
+        (handle.runtime.handle().clone(), Arc::clone(&handle.ctx))
+    };
+
+    let sql = sql.to_string();
+    std::thread::spawn(move || {
+        rt_handle.block_on(async {
+            let ctx_read = ctx.read().await;
+            let plan = ctx_read
+                .sql(&sql)
+                .await
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed SQL planning failed"))?
+                .into_optimized_plan()
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed SQL optimization failed"))?;
+            let mut text = plan.display_indent().to_string();
+            let pruning = describe_shard_pruning(&plan);
+            if !pruning.is_empty() {
+                text.push_str("\n\nShard pruning:\n");
+                for line in pruning {
+                    text.push_str("  ");
+                    text.push_str(&line);
+                    text.push('\n');
+                }
+            }
+            let policy = LoadPolicy::current();
+            let selection = describe_node_selection(&plan, policy);
+            if !selection.is_empty() {
+                text.push_str(&format!("\nNode selection ({}):\n", policy.as_str()));
+                for line in selection {
+                    text.push_str("  ");
+                    text.push_str(&line);
+                    text.push('\n');
+                }
+            }
+            Ok::<_, SwarmError>(text)
+        })
+    })
+    .join()
+    .map_err(|_| SwarmError::Internal("Explain thread panicked".to_string()))?
+}
+
+/// One operator of an executed physical plan with what it measured.
+/// Operators are numbered in plan order, parents before children, so the
+/// same plan always gives the same ids.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorActuals {
+    pub operator_id: usize,
+    pub parent_id: Option<usize>,
+    pub depth: usize,
+    pub operator: String,
+    /// `None` for operators that report no metrics.
+    pub rows: Option<u64>,
+    pub time_ms: Option<f64>,
+    /// Set for shuffle writers only.
+    pub bytes_shuffled: Option<u64>,
+}
+
+/// Run `sql` in the distributed session, discarding its result, and return
+/// the physical plan it ran with the rows, compute time and shuffled bytes
+/// each operator recorded, summed over its partitions. Scans report the
+/// rows their shards returned and the time until each shard answered.
+pub fn explain_analyze_query(sql: &str) -> SwarmResult<Vec<OperatorActuals>> {
+    let (rt_handle, ctx, active) = {
+        let guard = scheduler_lock()
+            .lock()
+            .map_err(|_| "Scheduler lock poisoned".to_string())?;
+        let handle = guard
+            .as_ref()
+            .ok_or_else(|| "Scheduler is not running".to_string())?;
+        (handle.runtime.handle().clone(), Arc::clone(&handle.ctx), Arc::clone(&handle.active_queries))
+    };
+
+    active.fetch_add(1, AtomicOrdering::SeqCst);
+    let _guard = QueryGuard(active);
+
+    let sql = sql.to_string();
+    let query_id = crate::logging::current_query_id();
+    std::thread::spawn(move || {
+        rt_handle.block_on(crate::logging::with_query_id(query_id.clone(), async {
+            let ctx_read = ctx.read().await;
+            SwarmLogger::debug("scheduler", &format!("Planning analyzed query: {sql}"));
+            let (session, _spill_dir) = query_session(&ctx_read, query_id.as_deref())?;
+            let plan = session
+                .sql(&sql)
+                .await
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed SQL planning failed"))?
+                .create_physical_plan()
+                .await
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed SQL planning failed"))?;
+            datafusion::physical_plan::collect(Arc::clone(&plan), session.task_ctx())
+                .await
+                .map_err(|e| SwarmError::from_datafusion(&e, "Distributed query execution failed"))?;
+            Ok::<_, SwarmError>(operator_actuals(&plan))
+        }))
+    })
+    .join()
+    .map_err(|_| SwarmError::Internal("Explain analyze thread panicked".to_string()))?
+}
+
+/// The operators of an executed `plan` with their metrics, in plan order.
+fn operator_actuals(
+    plan: &Arc<dyn datafusion::physical_plan::ExecutionPlan>,
+) -> Vec<OperatorActuals> {
+    fn visit(
+        plan: &Arc<dyn datafusion::physical_plan::ExecutionPlan>,
+        parent_id: Option<usize>,
+        depth: usize,
+        out: &mut Vec<OperatorActuals>,
+    ) {
+        let operator_id = out.len();
+        let metrics = plan.metrics().map(|m| m.aggregate_by_name());
+        let operator = datafusion::physical_plan::displayable(plan.as_ref())
+            .one_line()
+            .to_string();
+        out.push(OperatorActuals {
+            operator_id,
+            parent_id,
+            depth,
+            operator: operator.trim_end().to_string(),
+            rows: metrics.as_ref().and_then(|m| m.output_rows()).map(|n| n as u64),
+            time_ms: metrics
+                .as_ref()
+                .and_then(|m| m.elapsed_compute())
+                .map(|ns| ns as f64 / 1_000_000.0),
+            bytes_shuffled: metrics
+                .as_ref()
+                .and_then(|m| m.sum_by_name(crate::shuffle_writer::BYTES_SHUFFLED_METRIC))
+                .map(|v| v.as_usize() as u64),
+        });
+        for child in plan.children() {
+            visit(child, Some(operator_id), depth + 1, out);
+        }
+    }
+
+    let mut out = Vec::new();
+    visit(plan, None, 0, &mut out);
+    out
+}
+
+/// One line per sharded scan in `plan`, saying which shards its pushed-down
+/// filters leave to read.
+fn describe_shard_pruning(plan: &datafusion::logical_expr::LogicalPlan) -> Vec<String> {
+    use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion};
+    use datafusion::logical_expr::LogicalPlan;
+
+    let mut lines = Vec::new();
+    let _ = plan.apply(|node| {
+        if let LogicalPlan::TableScan(scan) = node {
+            if let Ok(provider) = datafusion::datasource::source_as_provider(&scan.source) {
+                if let Some(dist) = provider
+                    .as_any()
+                    .downcast_ref::<crate::distributed_table_provider::DistributedTableProvider>()
+                {
+                    lines.push(dist.describe_pruning(&scan.filters));
+                }
+            }
+        }
+        Ok(TreeNodeRecursion::Continue)
+    });
+    lines
+}
+
+/// One line per shard of each sharded scan in `plan`, naming the node
+/// `policy` reads it from first.
+fn describe_node_selection(
+    plan: &datafusion::logical_expr::LogicalPlan,
+    policy: LoadPolicy,
+) -> Vec<String> {
+    use datafusion::common::tree_node::{TreeNode, TreeNodeRecursion};
+    use datafusion::logical_expr::LogicalPlan;
+
+    let scores = node_load_scores();
+    let local_node = catalog::get_self_node_name();
+    let unreachable = crate::gossip::GossipRegistry::instance().unreachable_nodes();
+    let mut lines = Vec::new();
+    let _ = plan.apply(|node| {
+        if let LogicalPlan::TableScan(scan) = node {
+            if let Ok(provider) = datafusion::datasource::source_as_provider(&scan.source) {
+                if let Some(dist) = provider
+                    .as_any()
+                    .downcast_ref::<crate::distributed_table_provider::DistributedTableProvider>()
+                {
+                    lines.extend(dist.describe_node_selection(
+                        &scan.filters,
+                        policy,
+                        &scores,
+                        local_node.as_deref(),
+                        &unreachable,
+                    ));
+                }
+            }
+        }
+        Ok(TreeNodeRecursion::Continue)
+    });
+    lines
+}
+
+/// Free memory below which a node counts as under memory pressure.
+const LOW_MEMORY_MB: u64 = 1024;
+
+/// Load score of a node from its advertised resources; lower is less
+/// loaded. CPU utilisation contributes 0..1, and memory pressure adds up to
+/// 1 more as free memory falls below `LOW_MEMORY_MB`. A value the node has
+/// not advertised counts as half-busy CPU and no memory pressure, so such
+/// nodes are neither favoured nor avoided.
+pub fn load_score(cpu_pct: Option<f64>, free_mem_mb: Option<u64>) -> f64 {
+    let cpu = cpu_pct.map_or(0.5, |pct| (pct / 100.0).clamp(0.0, 1.0));
+    let memory = free_mem_mb.map_or(0.0, |free| {
+        LOW_MEMORY_MB.saturating_sub(free) as f64 / LOW_MEMORY_MB as f64
+    });
+    cpu + memory
+}
+
+/// Load score of every node known to gossip, keyed by node name. Empty when
+/// gossip is not running.
+pub fn node_load_scores() -> HashMap<String, f64> {
+    crate::gossip::GossipRegistry::instance()
+        .get_node_states()
+        .map(|nodes| {
+            nodes
+                .into_iter()
+                .map(|n| (n.node_name, load_score(n.cpu_pct, n.free_mem_mb)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub const LOAD_POLICY_KEY: &str = "scheduler_load_policy";
+
+/// How a scan picks among the nodes that can each serve it: the copies of a
+/// replicated shard, or the nodes holding every table of a query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadPolicy {
+    /// Take turns, whatever the load.
+    RoundRobin,
+    /// The node with the lowest [`load_score`], taking turns among equally
+    /// loaded ones.
+    #[default]
+    LeastLoaded,
+    /// This node when it holds a copy, else the node the partition is
+    /// assigned to; replicas only when those fail.
+    Locality,
+}
+
+impl LoadPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "round_robin" => Some(Self::RoundRobin),
+            "least_loaded" => Some(Self::LeastLoaded),
+            "locality" => Some(Self::Locality),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::RoundRobin => "round_robin",
+            Self::LeastLoaded => "least_loaded",
+            Self::Locality => "locality",
+        }
+    }
+
+    /// `scheduler_load_policy` as set on this node through `trex_db_set`,
+    /// `least_loaded` by default. Read for every scan, so a change applies
+    /// to the next query.
+    pub fn current() -> Self {
+        crate::gossip::GossipRegistry::instance()
+            .get_self_config()
+            .ok()
+            .and_then(|config| config.into_iter().find(|(k, _)| k == LOAD_POLICY_KEY))
+            .and_then(|(_, v)| Self::parse(&v))
+            .unwrap_or_default()
+    }
+}
+
+/// Rotates the co-located node chosen under [`LoadPolicy::RoundRobin`].
+static NEXT_COLOCATED: AtomicUsize = AtomicUsize::new(0);
+
 /// Returns `Some(flight_endpoint)` if all tables are co-located, `None` if distributed.
-pub fn check_colocation(table_names: &[String]) -> Result<Option<String>, String> {
+pub fn check_colocation(table_names: &[String]) -> SwarmResult<Option<String>> {
     if table_names.is_empty() {
         return Ok(None);
     }
@@ -301,18 +784,66 @@ pub fn check_colocation(table_names: &[String]) -> Result<Option<String>, String
     }
 
     if let Some(candidates) = candidate_nodes {
-        for (_node_id, endpoint) in &candidates {
-            if let Some(ep) = endpoint {
-                SwarmLogger::debug(
-                    "scheduler",
-                    &format!(
-                        "Co-location check: all {} table(s) co-located at {}",
-                        table_names.len(),
-                        ep,
-                    ),
-                );
-                return Ok(Some(ep.to_string()));
+        // Several nodes may hold every table (replicas); the load policy
+        // picks one.
+        let node_names: HashMap<&str, &str> = all_entries
+            .iter()
+            .map(|e| (e.node_id.as_str(), e.node_name.as_str()))
+            .collect();
+        let policy = LoadPolicy::current();
+        let scores = match policy {
+            LoadPolicy::RoundRobin => HashMap::new(),
+            _ => node_load_scores(),
+        };
+        let self_id = catalog::get_self_node_id();
+        // A suspect or dead node would only stall the query until its
+        // failure is confirmed.
+        let unreachable = crate::gossip::GossipRegistry::instance().unreachable_nodes();
+        let mut reachable: Vec<(&str, &str)> = candidates
+            .iter()
+            .filter(|(node_id, _)| {
+                node_names
+                    .get(*node_id)
+                    .is_none_or(|name| !unreachable.contains(*name))
+            })
+            .filter_map(|(node_id, endpoint)| endpoint.map(|ep| (*node_id, ep)))
+            .collect();
+        reachable.sort_by_key(|&(_, ep)| ep);
+        let score_of = |node_id: &str| {
+            node_names
+                .get(node_id)
+                .and_then(|name| scores.get(*name))
+                .copied()
+                .unwrap_or_else(|| load_score(None, None))
+        };
+        let least_loaded = reachable
+            .iter()
+            .map(|&(node_id, ep)| (score_of(node_id), ep))
+            .min_by(|a, b| a.0.total_cmp(&b.0).then_with(|| a.1.cmp(b.1)))
+            .map(|(_, ep)| ep);
+        let best = match policy {
+            LoadPolicy::RoundRobin if !reachable.is_empty() => {
+                let next = NEXT_COLOCATED.fetch_add(1, AtomicOrdering::Relaxed);
+                Some(reachable[next % reachable.len()].1)
             }
+            LoadPolicy::Locality => reachable
+                .iter()
+                .find(|(node_id, _)| Some(*node_id) == self_id.as_deref())
+                .map(|&(_, ep)| ep)
+                .or(least_loaded),
+            _ => least_loaded,
+        };
+        if let Some(ep) = best {
+            SwarmLogger::debug(
+                "scheduler",
+                &format!(
+                    "Co-location check: all {} table(s) co-located at {} ({})",
+                    table_names.len(),
+                    ep,
+                    policy.as_str(),
+                ),
+            );
+            return Ok(Some(ep.to_string()));
         }
     }
 
@@ -409,7 +940,7 @@ fn collect_table_names_from_table_factor(
     }
 }
 
-pub fn check_colocation_for_sql(sql: &str) -> Result<Option<String>, String> {
+pub fn check_colocation_for_sql(sql: &str) -> SwarmResult<Option<String>> {
     let table_names = extract_table_names_from_sql(sql);
     check_colocation(&table_names)
 }
@@ -427,7 +958,68 @@ mod tests {
     fn submit_query_without_scheduler_returns_error() {
         let result = submit_query("SELECT 1");
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not running"));
+        let err = result.unwrap_err();
+        assert!(matches!(err, SwarmError::Internal(_)), "unexpected error: {err:?}");
+        assert!(err.to_string().contains("not running"));
+    }
+
+    #[test]
+    fn operator_actuals_number_operators_in_plan_order() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let actuals = rt.block_on(async {
+            let ctx = SessionContext::new();
+            let plan = ctx
+                .sql("SELECT x FROM (VALUES (1), (2), (3)) AS t(x) WHERE x > 1")
+                .await
+                .unwrap()
+                .create_physical_plan()
+                .await
+                .unwrap();
+            datafusion::physical_plan::collect(Arc::clone(&plan), ctx.task_ctx())
+                .await
+                .unwrap();
+            operator_actuals(&plan)
+        });
+
+        assert_eq!(actuals[0].operator_id, 0);
+        assert_eq!(actuals[0].parent_id, None);
+        assert_eq!(actuals[0].rows, Some(2));
+        for (i, op) in actuals.iter().enumerate().skip(1) {
+            assert_eq!(op.operator_id, i);
+            let parent = op.parent_id.expect("non-root operators have a parent");
+            assert!(parent < i);
+            assert_eq!(op.depth, actuals[parent].depth + 1);
+        }
+        let filter = actuals
+            .iter()
+            .find(|op| op.operator.starts_with("FilterExec"))
+            .expect("plan filters");
+        assert_eq!(filter.rows, Some(2));
+        assert!(filter.time_ms.is_some());
+        assert!(actuals.iter().all(|op| op.bytes_shuffled.is_none()));
+    }
+
+    #[test]
+    fn export_format_parses_case_insensitively() {
+        assert_eq!(ExportFormat::parse("parquet"), Some(ExportFormat::Parquet));
+        assert_eq!(ExportFormat::parse("CSV"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::parse("json"), None);
+        assert!(is_directory_export("/exports/orders/"));
+        assert!(!is_directory_export("/exports/orders.parquet"));
+    }
+
+    #[test]
+    fn export_into_non_empty_directory_is_rejected() {
+        let dir = std::env::temp_dir().join(format!("trex-export-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("old.parquet"), b"").unwrap();
+        let path = format!("{}/", dir.display());
+
+        let err = export_query("SELECT 1", &path, ExportFormat::Parquet).unwrap_err();
+        assert!(err.to_string().contains("is not empty"), "unexpected error: {err}");
+        assert_eq!(count_export_files(&path, ExportFormat::Parquet), 1);
+        assert_eq!(count_export_files(&path, ExportFormat::Csv), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 
     #[test]
@@ -436,11 +1028,34 @@ mod tests {
         assert_eq!(result.unwrap(), None);
     }
 
+    #[test]
+    fn load_score_prefers_idle_nodes_with_free_memory() {
+        let idle = load_score(Some(10.0), Some(8192));
+        let busy = load_score(Some(90.0), Some(8192));
+        let low_memory = load_score(Some(10.0), Some(256));
+        let unknown = load_score(None, None);
+        assert!(idle < unknown && unknown < busy);
+        assert!(idle < low_memory);
+        assert_eq!(load_score(Some(250.0), Some(0)), 2.0);
+    }
+
+    #[test]
+    fn load_policy_parses_config_values() {
+        for policy in [LoadPolicy::RoundRobin, LoadPolicy::LeastLoaded, LoadPolicy::Locality] {
+            assert_eq!(LoadPolicy::parse(policy.as_str()), Some(policy));
+        }
+        assert_eq!(LoadPolicy::parse(" Locality "), Some(LoadPolicy::Locality));
+        assert_eq!(LoadPolicy::parse("random"), None);
+        assert_eq!(LoadPolicy::default(), LoadPolicy::LeastLoaded);
+    }
+
     #[test]
     fn stop_scheduler_when_not_running_returns_error() {
         let result = stop_scheduler();
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("not running"));
+        let err = result.unwrap_err();
+        assert!(matches!(err, SwarmError::Internal(_)), "unexpected error: {err:?}");
+        assert!(err.to_string().contains("not running"));
     }
 
     #[test]
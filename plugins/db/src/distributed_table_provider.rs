@@ -1,19 +1,25 @@
 //! Sharded table provider that fans out scans to all shards via Arrow Flight.
 
 use std::any::Any;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use arrow::array::RecordBatch;
 use arrow::datatypes::SchemaRef;
 use datafusion::catalog::Session;
 use datafusion::common::Result as DFResult;
+use datafusion::common::ScalarValue;
 use datafusion::datasource::TableProvider;
 use datafusion::datasource::TableType;
 use datafusion::error::DataFusionError;
 use datafusion::execution::{SendableRecordBatchStream, TaskContext};
 use datafusion::logical_expr::TableProviderFilterPushDown;
 use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricBuilder, MetricsSet};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
@@ -23,9 +29,14 @@ use datafusion::sql::unparser::expr_to_sql;
 use futures::stream;
 use futures::StreamExt;
 
-use crate::catalog::ShardInfo;
+use crate::admission;
+use crate::catalog::{ColumnStats, ShardInfo, StatValue};
+use crate::distributed_scheduler::{self, LoadPolicy};
+use crate::error::{SwarmError, SwarmResult};
+use crate::partial_results;
 use crate::flight_client;
-use crate::logging::SwarmLogger;
+use crate::logging::{self, SwarmLogger};
+use crate::query_profile::{self, FragmentProfile};
 
 /// TableProvider that fans out scans to all shards via Arrow Flight.
 #[derive(Debug)]
@@ -80,7 +91,19 @@ impl DistributedTableProvider {
         filters: &[Expr],
         limit: Option<usize>,
     ) -> String {
-        let escaped = crate::catalog::escape_identifier(&self.table_name);
+        self.build_scan_sql(&self.table_name, projection, filters, limit)
+    }
+
+    /// Same as [`Self::build_shard_sql`] but reading from `table`, which
+    /// differs from the logical table for replica copies.
+    fn build_scan_sql(
+        &self,
+        table: &str,
+        projection: Option<&Vec<usize>>,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> String {
+        let escaped = crate::catalog::escape_identifier(table);
 
         let columns = match projection {
             Some(indices) if !indices.is_empty() => {
@@ -124,6 +147,162 @@ impl DistributedTableProvider {
 
         sql
     }
+
+    /// Split the shards into those `filters` may match and the names of the
+    /// nodes whose column stats rule every filter out for them.
+    pub fn prune_shards(&self, filters: &[Expr]) -> (Vec<ShardInfo>, Vec<String>) {
+        let mut kept = Vec::new();
+        let mut pruned = Vec::new();
+        for shard in &self.shards {
+            if filters.iter().all(|f| may_match(f, &shard.column_stats)) {
+                kept.push(shard.clone());
+            } else {
+                pruned.push(shard.node_name.clone());
+            }
+        }
+        (kept, pruned)
+    }
+
+    /// One line for `trex_db_explain` describing the shard pruning of a scan.
+    pub fn describe_pruning(&self, filters: &[Expr]) -> String {
+        let (kept, pruned) = self.prune_shards(filters);
+        let mut line = format!(
+            "{}: scanning {} of {} shard(s)",
+            self.table_name,
+            kept.len(),
+            self.shards.len()
+        );
+        if !pruned.is_empty() {
+            line.push_str(&format!(", pruned {}", pruned.join(", ")));
+        }
+        line
+    }
+
+    /// Lines for `trex_db_explain` naming the node each scanned shard is read
+    /// from first under `policy`, and why.
+    pub fn describe_node_selection(
+        &self,
+        filters: &[Expr],
+        policy: LoadPolicy,
+        node_scores: &HashMap<String, f64>,
+        local_node: Option<&str>,
+        unreachable: &HashSet<String>,
+    ) -> Vec<String> {
+        let (kept, _) = self.prune_shards(filters);
+        // Each partition's scan takes the next turn of the rotation.
+        let start = NEXT_ROUTE.load(Ordering::Relaxed);
+        kept.iter()
+            .enumerate()
+            .map(|(partition, shard)| {
+                let routes = shard_routes(shard, &self.table_name);
+                let routes: Vec<Vec<&ScanTarget>> =
+                    routes.iter().map(|r| r.iter().collect()).collect();
+                let mut order =
+                    policy_route_order(policy, &routes, node_scores, local_node, start + partition);
+                demote_unreachable(&mut order, &routes, unreachable);
+                let first = &routes[order[0]];
+                let mut nodes: Vec<&str> = first.iter().map(|t| t.node_name.as_str()).collect();
+                nodes.dedup();
+                format!(
+                    "{}[{}]: {} ({})",
+                    self.table_name,
+                    partition,
+                    nodes.join(","),
+                    route_choice_reason(policy, &routes, order[0], node_scores, local_node)
+                )
+            })
+            .collect()
+    }
+}
+
+/// Whether `filter` can hold for some row of a shard with `stats`. Anything
+/// not understood (other operators, casts, missing stats) may match.
+fn may_match(filter: &Expr, stats: &BTreeMap<String, ColumnStats>) -> bool {
+    use datafusion::logical_expr::{Between, BinaryExpr, Operator};
+
+    match filter {
+        Expr::BinaryExpr(BinaryExpr { left, op: Operator::And, right }) => {
+            may_match(left, stats) && may_match(right, stats)
+        }
+        Expr::BinaryExpr(BinaryExpr { left, op: Operator::Or, right }) => {
+            may_match(left, stats) || may_match(right, stats)
+        }
+        Expr::BinaryExpr(BinaryExpr { left, op, right }) => {
+            let (column, op, value) = match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(c), Expr::Literal(v, _)) => (c, *op, v),
+                (Expr::Literal(v, _), Expr::Column(c)) => match op.swap() {
+                    Some(swapped) => (c, swapped, v),
+                    None => return true,
+                },
+                _ => return true,
+            };
+            let (Some(col), Some(value)) = (stats.get(&column.name), literal_stat(value)) else {
+                return true;
+            };
+            let (lo, hi) = (col.min.partial_cmp(&value), col.max.partial_cmp(&value));
+            let (Some(lo), Some(hi)) = (lo, hi) else {
+                return true;
+            };
+            match op {
+                Operator::Eq => lo.is_le() && hi.is_ge(),
+                Operator::NotEq => !(lo.is_eq() && hi.is_eq()),
+                Operator::Lt => lo.is_lt(),
+                Operator::LtEq => lo.is_le(),
+                Operator::Gt => hi.is_gt(),
+                Operator::GtEq => hi.is_ge(),
+                _ => true,
+            }
+        }
+        Expr::Between(Between { expr, negated: false, low, high }) => {
+            let as_range = Expr::BinaryExpr(BinaryExpr::new(
+                Box::new(Expr::BinaryExpr(BinaryExpr::new(
+                    expr.clone(),
+                    Operator::GtEq,
+                    low.clone(),
+                ))),
+                Operator::And,
+                Box::new(Expr::BinaryExpr(BinaryExpr::new(
+                    expr.clone(),
+                    Operator::LtEq,
+                    high.clone(),
+                ))),
+            ));
+            may_match(&as_range, stats)
+        }
+        _ => true,
+    }
+}
+
+/// A filter literal in the form the catalog stores column stats.
+fn literal_stat(value: &ScalarValue) -> Option<StatValue> {
+    const US_PER_DAY: i64 = 86_400_000_000;
+    let int = |v: Option<i64>| v.map(StatValue::Int);
+    match value {
+        ScalarValue::Int8(v) => int(v.map(i64::from)),
+        ScalarValue::Int16(v) => int(v.map(i64::from)),
+        ScalarValue::Int32(v) => int(v.map(i64::from)),
+        ScalarValue::Int64(v) => int(*v),
+        ScalarValue::UInt8(v) => int(v.map(i64::from)),
+        ScalarValue::UInt16(v) => int(v.map(i64::from)),
+        ScalarValue::UInt32(v) => int(v.map(i64::from)),
+        ScalarValue::UInt64(v) => int(v.and_then(|v| i64::try_from(v).ok())),
+        ScalarValue::Float32(v) => v.map(|v| StatValue::Float(v.into())),
+        ScalarValue::Float64(v) => v.map(StatValue::Float),
+        ScalarValue::Utf8(v) | ScalarValue::LargeUtf8(v) | ScalarValue::Utf8View(v) => {
+            v.clone().map(StatValue::Text)
+        }
+        ScalarValue::Date32(v) => int(v.and_then(|d| i64::from(d).checked_mul(US_PER_DAY))),
+        ScalarValue::Date64(v) => int(v.and_then(|ms| ms.checked_mul(1_000))),
+        ScalarValue::TimestampSecond(v, None) => int(v.and_then(|s| s.checked_mul(1_000_000))),
+        ScalarValue::TimestampMillisecond(v, None) => int(v.and_then(|ms| ms.checked_mul(1_000))),
+        ScalarValue::TimestampMicrosecond(v, None) => int(*v),
+        // Only whole microseconds: rounding would make `<` comparisons prune
+        // shards that can still match.
+        ScalarValue::TimestampNanosecond(v, None) => {
+            int(v.filter(|ns| ns % 1_000 == 0).map(|ns| ns / 1_000))
+        }
+        _ => None,
+    }
 }
 
 #[async_trait::async_trait]
@@ -148,13 +327,15 @@ impl TableProvider for DistributedTableProvider {
         limit: Option<usize>,
     ) -> DFResult<Arc<dyn ExecutionPlan>> {
         let shard_sql = self.build_shard_sql(projection, filters, limit);
+        let (shards, pruned) = self.prune_shards(filters);
 
         SwarmLogger::debug(
             "distributed-table",
             &format!(
-                "Shard scan for '{}' across {} shard(s): {}",
+                "Shard scan for '{}' across {} shard(s), {} pruned: {}",
                 self.table_name,
-                self.shards.len(),
+                shards.len(),
+                pruned.len(),
                 shard_sql,
             ),
         );
@@ -170,13 +351,44 @@ impl TableProvider for DistributedTableProvider {
             None => self.schema.clone(),
         };
 
-        Ok(Arc::new(DistributedExec::new(
-            self.table_name.clone(),
-            output_schema,
-            self.shards.clone(),
-            shard_sql,
-            self.runtime_handle.clone(),
-        )))
+        if shards.is_empty() {
+            return Ok(Arc::new(EmptyExec::new(output_schema)));
+        }
+
+        let replica_sql: HashMap<String, String> = shards
+            .iter()
+            .flat_map(|s| s.replicas.iter())
+            .map(|r| {
+                let sql = self.build_scan_sql(&r.table_name, projection, filters, limit);
+                (r.table_name.clone(), sql)
+            })
+            .collect();
+
+        // Load only matters when a shard has more than one copy to pick from.
+        let policy = LoadPolicy::current();
+        let node_scores = if replica_sql.is_empty() || policy != LoadPolicy::LeastLoaded {
+            HashMap::new()
+        } else {
+            distributed_scheduler::node_load_scores()
+        };
+        let local_node = match policy {
+            LoadPolicy::Locality => crate::catalog::get_self_node_name(),
+            _ => None,
+        };
+
+        Ok(Arc::new(
+            DistributedExec::new(
+                self.table_name.clone(),
+                output_schema,
+                shards,
+                shard_sql,
+                self.runtime_handle.clone(),
+            )
+            .with_replica_sql(replica_sql)
+            .with_node_scores(node_scores)
+            .with_load_policy(policy, local_node)
+            .with_unreachable_nodes(crate::gossip::GossipRegistry::instance().unreachable_nodes()),
+        ))
     }
 
     fn supports_filters_pushdown(
@@ -188,6 +400,150 @@ impl TableProvider for DistributedTableProvider {
     }
 }
 
+/// One Flight query that reads part or all of a shard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ScanTarget {
+    pub(crate) node_name: String,
+    pub(crate) flight_endpoint: String,
+    pub(crate) table_name: String,
+}
+
+/// Rotates the first route tried so shard scans spread over their copies.
+static NEXT_ROUTE: AtomicUsize = AtomicUsize::new(0);
+
+/// A route is as loaded as its busiest node, rounded to a tenth so small
+/// fluctuations do not defeat the rotation between similarly idle copies.
+fn route_score<'a>(
+    route: impl IntoIterator<Item = &'a ScanTarget>,
+    node_scores: &HashMap<String, f64>,
+) -> u64 {
+    let score = route
+        .into_iter()
+        .map(|t| {
+            node_scores
+                .get(&t.node_name)
+                .copied()
+                .unwrap_or_else(|| distributed_scheduler::load_score(None, None))
+        })
+        .fold(0.0, f64::max);
+    (score * 10.0).round() as u64
+}
+
+/// Indices of routes in the order to try them: least loaded first, ties in
+/// rotation order starting at `start`.
+fn route_order(route_scores: &[u64], start: usize) -> Vec<usize> {
+    let n = route_scores.len();
+    let mut order: Vec<usize> = (0..n).map(|i| (start + i) % n).collect();
+    order.sort_by_key(|&i| route_scores[i]);
+    order
+}
+
+/// Whether this node serves every scan of `route`.
+fn is_local(route: &[&ScanTarget], local_node: Option<&str>) -> bool {
+    local_node.is_some_and(|local| route.iter().all(|t| t.node_name == local))
+}
+
+/// Indices of routes in the order `policy` tries them, `start` being the
+/// scan's turn in the rotation. Route 0 reads the primary table, as
+/// [`shard_routes`] builds them.
+pub(crate) fn policy_route_order(
+    policy: LoadPolicy,
+    routes: &[Vec<&ScanTarget>],
+    node_scores: &HashMap<String, f64>,
+    local_node: Option<&str>,
+    start: usize,
+) -> Vec<usize> {
+    let n = routes.len();
+    match policy {
+        LoadPolicy::RoundRobin => (0..n).map(|i| (start + i) % n).collect(),
+        LoadPolicy::LeastLoaded => {
+            let scores: Vec<u64> = routes
+                .iter()
+                .map(|route| route_score(route.iter().copied(), node_scores))
+                .collect();
+            route_order(&scores, start)
+        }
+        // The sort is stable: after a local copy comes the primary, then the
+        // replicas in order.
+        LoadPolicy::Locality => {
+            let mut order: Vec<usize> = (0..n).collect();
+            order.sort_by_key(|&i| !is_local(&routes[i], local_node));
+            order
+        }
+    }
+}
+
+/// Move the routes that read from a node in `unreachable` behind all the
+/// others, keeping their order. They stay as a last resort: a suspect node
+/// may still answer, and without them a shard could go unread.
+fn demote_unreachable(
+    order: &mut [usize],
+    routes: &[Vec<&ScanTarget>],
+    unreachable: &HashSet<String>,
+) {
+    order.sort_by_key(|&i| routes[i].iter().any(|t| unreachable.contains(&t.node_name)));
+}
+
+/// Why `policy` tries route `first` first.
+pub(crate) fn route_choice_reason(
+    policy: LoadPolicy,
+    routes: &[Vec<&ScanTarget>],
+    first: usize,
+    node_scores: &HashMap<String, f64>,
+    local_node: Option<&str>,
+) -> String {
+    if routes.len() == 1 {
+        return "only copy".to_string();
+    }
+    match policy {
+        LoadPolicy::RoundRobin => format!("round_robin: next of {} copies", routes.len()),
+        LoadPolicy::LeastLoaded => format!(
+            "least_loaded: load {:.1}, lowest of {} copies",
+            route_score(routes[first].iter().copied(), node_scores) as f64 / 10.0,
+            routes.len()
+        ),
+        LoadPolicy::Locality if is_local(&routes[first], local_node) => {
+            "locality: copy on this node".to_string()
+        }
+        LoadPolicy::Locality => "locality: node the partition is assigned to".to_string(),
+    }
+}
+
+/// Alternative ways to read all rows of `shard`: the primary table first,
+/// then one route per replica copy, each reading one replica per partition.
+fn shard_routes(shard: &ShardInfo, table_name: &str) -> Vec<Vec<ScanTarget>> {
+    let mut routes = vec![vec![ScanTarget {
+        node_name: shard.node_name.clone(),
+        flight_endpoint: shard.flight_endpoint.clone(),
+        table_name: table_name.to_string(),
+    }]];
+
+    let mut copies: BTreeMap<usize, Vec<&crate::catalog::ReplicaInfo>> = BTreeMap::new();
+    for replica in &shard.replicas {
+        copies.entry(replica.partition_id).or_default().push(replica);
+    }
+    let max_copies = copies.values().map(|c| c.len()).max().unwrap_or(0);
+
+    for copy in 0..max_copies {
+        let route: Vec<ScanTarget> = copies
+            .values()
+            .map(|c| {
+                let r = c[copy % c.len()];
+                ScanTarget {
+                    node_name: r.node_name.clone(),
+                    flight_endpoint: r.flight_endpoint.clone(),
+                    table_name: r.table_name.clone(),
+                }
+            })
+            .collect();
+        if !routes.contains(&route) {
+            routes.push(route);
+        }
+    }
+
+    routes
+}
+
 /// ExecutionPlan that queries all shards in parallel via Arrow Flight.
 #[derive(Debug)]
 pub struct DistributedExec {
@@ -195,8 +551,20 @@ pub struct DistributedExec {
     schema: SchemaRef,
     shards: Vec<ShardInfo>,
     shard_sql: String,
+    /// Scan SQL per replica table, used by replica routes.
+    replica_sql: HashMap<String, String>,
+    /// Load score per node name, used to prefer less-loaded copies.
+    node_scores: HashMap<String, f64>,
+    /// How to pick among a shard's copies.
+    policy: LoadPolicy,
+    /// This node's name, which [`LoadPolicy::Locality`] prefers.
+    local_node: Option<String>,
+    /// Nodes SWIM holds suspect or dead, tried only after every other copy.
+    unreachable: HashSet<String>,
     runtime_handle: tokio::runtime::Handle,
     properties: PlanProperties,
+    /// Rows and time of each shard's scan, for `trex_db_explain_analyze`.
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl DistributedExec {
@@ -219,10 +587,37 @@ impl DistributedExec {
             schema,
             shards,
             shard_sql,
+            replica_sql: HashMap::new(),
+            node_scores: HashMap::new(),
+            policy: LoadPolicy::default(),
+            local_node: None,
+            unreachable: HashSet::new(),
             runtime_handle,
             properties,
+            metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    fn with_replica_sql(mut self, replica_sql: HashMap<String, String>) -> Self {
+        self.replica_sql = replica_sql;
+        self
+    }
+
+    fn with_node_scores(mut self, node_scores: HashMap<String, f64>) -> Self {
+        self.node_scores = node_scores;
+        self
+    }
+
+    fn with_load_policy(mut self, policy: LoadPolicy, local_node: Option<String>) -> Self {
+        self.policy = policy;
+        self.local_node = local_node;
+        self
+    }
+
+    fn with_unreachable_nodes(mut self, unreachable: HashSet<String>) -> Self {
+        self.unreachable = unreachable;
+        self
+    }
 }
 
 impl DisplayAs for DistributedExec {
@@ -263,7 +658,7 @@ impl ExecutionPlan for DistributedExec {
     fn execute(
         &self,
         partition: usize,
-        _context: Arc<TaskContext>,
+        context: Arc<TaskContext>,
     ) -> DFResult<SendableRecordBatchStream> {
         if partition >= self.shards.len() {
             return Err(DataFusionError::Internal(format!(
@@ -274,39 +669,116 @@ impl ExecutionPlan for DistributedExec {
         }
 
         let shard = self.shards[partition].clone();
-        let sql = self.shard_sql.clone();
         let schema = self.schema.clone();
         let table_name = self.table_name.clone();
 
-        // Spawn the Flight query as a tokio task (non-blocking). Returning a
-        // lazy stream avoids blocking tokio worker threads — which would
-        // deadlock the runtime when multiple partitions execute concurrently.
-        let join_handle = self.runtime_handle.spawn(async move {
-            SwarmLogger::debug(
-                "distributed-exec",
-                &format!(
-                    "Querying shard {} ({}) for '{}' [partition {}]",
-                    shard.node_name, shard.flight_endpoint, table_name, partition
-                ),
-            );
-            let batches = flight_client::query_node(&shard.flight_endpoint, &sql)
-                .await
-                .map_err(|e| {
-                    format!(
-                        "Distributed scan for '{}' failed on shard {} ({}): {}",
-                        table_name, shard.node_name, shard.flight_endpoint, e
-                    )
-                })?;
-            let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+        // Pair every scan with its SQL; a route whose replica SQL is missing
+        // cannot be served and is dropped.
+        let routes: Vec<Vec<(ScanTarget, String)>> = shard_routes(&shard, &table_name)
+            .into_iter()
+            .filter_map(|route| {
+                route
+                    .into_iter()
+                    .map(|target| {
+                        let sql = if target.table_name == table_name {
+                            Some(self.shard_sql.clone())
+                        } else {
+                            self.replica_sql.get(&target.table_name).cloned()
+                        }?;
+                        Some((target, sql))
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect();
+        let targets: Vec<Vec<&ScanTarget>> = routes
+            .iter()
+            .map(|route| route.iter().map(|(t, _)| t).collect())
+            .collect();
+        let local_node = self.local_node.as_deref();
+        let mut order = policy_route_order(
+            self.policy,
+            &targets,
+            &self.node_scores,
+            local_node,
+            NEXT_ROUTE.fetch_add(1, Ordering::Relaxed),
+        );
+        demote_unreachable(&mut order, &targets, &self.unreachable);
+        if let Some(&first) = order.first() {
             SwarmLogger::debug(
                 "distributed-exec",
                 &format!(
-                    "Shard {} ({}) returned {} row(s) [partition {}]",
-                    shard.node_name, shard.flight_endpoint, rows, partition
+                    "Scan of '{}' [partition {}] goes to {} first ({})",
+                    table_name,
+                    partition,
+                    targets[first][0].node_name,
+                    route_choice_reason(self.policy, &targets, first, &self.node_scores, local_node),
                 ),
             );
-            Ok::<Vec<RecordBatch>, String>(batches)
-        });
+        }
+        let query_id = logging::query_id_for_task(&context);
+        let profile_id = query_id.clone();
+        let output_rows = MetricBuilder::new(&self.metrics).output_rows(partition);
+        let elapsed_compute = MetricBuilder::new(&self.metrics).elapsed_compute(partition);
+
+        // Spawn the Flight query as a tokio task (non-blocking). Returning a
+        // lazy stream avoids blocking tokio worker threads — which would
+        // deadlock the runtime when multiple partitions execute concurrently.
+        let join_handle = self.runtime_handle.spawn(logging::with_query_id(query_id, async move {
+            let started = Instant::now();
+            let mut last_err = SwarmError::Internal(format!("No route to scan '{}'", table_name));
+            for (attempt, &index) in order.iter().enumerate() {
+                let route = &routes[index];
+                match scan_route(route, &table_name, partition).await {
+                    Ok(batches) => {
+                        let mut nodes: Vec<&str> =
+                            route.iter().map(|(t, _)| t.node_name.as_str()).collect();
+                        nodes.dedup();
+                        admission::record_bytes_returned(
+                            profile_id.as_deref(),
+                            batches.iter().map(|b| b.get_array_memory_size() as u64).sum(),
+                        );
+                        output_rows.add(batches.iter().map(|b| b.num_rows()).sum());
+                        elapsed_compute.add_duration(started.elapsed());
+                        query_profile::record_fragment(
+                            profile_id.as_deref(),
+                            FragmentProfile {
+                                fragment: format!("scan {}[{}]", table_name, partition),
+                                node_name: nodes.join(","),
+                                rows: batches.iter().map(|b| b.num_rows() as u64).sum(),
+                                bytes_shuffled: 0,
+                                wall_ms: started.elapsed().as_secs_f64() * 1000.0,
+                            },
+                        );
+                        return Ok(batches);
+                    }
+                    // Another copy only helps when this one could not be
+                    // reached; a failing query fails the same way elsewhere.
+                    Err(e) if e.is_retryable() && attempt + 1 < routes.len() => {
+                        SwarmLogger::warn(
+                            "distributed-exec",
+                            &format!("{}; retrying on another copy", e),
+                        );
+                        last_err = e;
+                    }
+                    Err(e) => {
+                        last_err = e;
+                        break;
+                    }
+                }
+            }
+            // Queries run with partial results skip a shard no copy could serve.
+            if partial_results::record_missing(
+                profile_id.as_deref(),
+                format!("{}[{}]", table_name, partition),
+            ) {
+                SwarmLogger::warn(
+                    "distributed-exec",
+                    &format!("{}; returning partial results without it", last_err),
+                );
+                return Ok(Vec::new());
+            }
+            Err::<Vec<RecordBatch>, SwarmError>(last_err)
+        }));
 
         let empty_projection = schema.fields().is_empty();
         let out_schema = schema.clone();
@@ -350,6 +822,48 @@ impl ExecutionPlan for DistributedExec {
             result_stream,
         )))
     }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+}
+
+/// Run every scan of one route and concatenate the results.
+async fn scan_route(
+    route: &[(ScanTarget, String)],
+    table_name: &str,
+    partition: usize,
+) -> SwarmResult<Vec<RecordBatch>> {
+    let mut batches = Vec::new();
+    for (target, sql) in route {
+        SwarmLogger::debug(
+            "distributed-exec",
+            &format!(
+                "Querying shard {} ({}) for '{}' [partition {}]",
+                target.node_name, target.flight_endpoint, target.table_name, partition
+            ),
+        );
+        let part = flight_client::query_node(&target.flight_endpoint, sql)
+            .await
+            .map_err(|e| {
+                e.map_message(|m| {
+                    format!(
+                        "Distributed scan for '{}' failed on shard {} ({}): {}",
+                        table_name, target.node_name, target.flight_endpoint, m
+                    )
+                })
+            })?;
+        let rows: usize = part.iter().map(|b| b.num_rows()).sum();
+        SwarmLogger::debug(
+            "distributed-exec",
+            &format!(
+                "Shard {} ({}) returned {} row(s) [partition {}]",
+                target.node_name, target.flight_endpoint, rows, partition
+            ),
+        );
+        batches.extend(part);
+    }
+    Ok(batches)
 }
 
 #[cfg(test)]
@@ -370,10 +884,14 @@ mod tests {
             ShardInfo {
                 node_name: "node-a".to_string(),
                 flight_endpoint: "http://10.0.0.1:8815".to_string(),
+                replicas: vec![],
+                column_stats: BTreeMap::new(),
             },
             ShardInfo {
                 node_name: "node-b".to_string(),
                 flight_endpoint: "http://10.0.0.2:8815".to_string(),
+                replicas: vec![],
+                column_stats: BTreeMap::new(),
             },
         ]
     }
@@ -400,14 +918,20 @@ mod tests {
             ShardInfo {
                 node_name: "a".to_string(),
                 flight_endpoint: "http://a:8815".to_string(),
+                replicas: vec![],
+                column_stats: BTreeMap::new(),
             },
             ShardInfo {
                 node_name: "b".to_string(),
                 flight_endpoint: "http://b:8815".to_string(),
+                replicas: vec![],
+                column_stats: BTreeMap::new(),
             },
             ShardInfo {
                 node_name: "c".to_string(),
                 flight_endpoint: "http://c:8815".to_string(),
+                replicas: vec![],
+                column_stats: BTreeMap::new(),
             },
         ];
         let exec = DistributedExec::new(
@@ -539,4 +1063,188 @@ mod tests {
         let result = provider.supports_filters_pushdown(&[&expr]).unwrap();
         assert_eq!(result, vec![TableProviderFilterPushDown::Exact]);
     }
+
+    fn replica(partition_id: usize, node: &str) -> crate::catalog::ReplicaInfo {
+        crate::catalog::ReplicaInfo {
+            partition_id,
+            node_name: node.to_string(),
+            flight_endpoint: format!("http://{}:8815", node),
+            table_name: format!("orders__replica_{}", partition_id),
+        }
+    }
+
+    fn stats(min: i64, max: i64) -> BTreeMap<String, ColumnStats> {
+        BTreeMap::from([(
+            "ts".to_string(),
+            ColumnStats {
+                min: StatValue::Int(min),
+                max: StatValue::Int(max),
+                null_count: None,
+            },
+        )])
+    }
+
+    fn ts() -> Expr {
+        datafusion::prelude::col("ts")
+    }
+
+    fn lit_i64(v: i64) -> Expr {
+        datafusion::prelude::lit(v)
+    }
+
+    #[test]
+    fn range_filters_use_min_max() {
+        let s = stats(10, 20);
+        assert!(may_match(&ts().gt(lit_i64(15)), &s));
+        assert!(!may_match(&ts().gt(lit_i64(20)), &s));
+        assert!(may_match(&ts().gt_eq(lit_i64(20)), &s));
+        assert!(!may_match(&ts().lt(lit_i64(10)), &s));
+        assert!(!may_match(&ts().eq(lit_i64(25)), &s));
+        assert!(!may_match(&lit_i64(5).gt(ts()), &s));
+        assert!(!may_match(&ts().between(lit_i64(21), lit_i64(30)), &s));
+        assert!(may_match(&ts().gt(lit_i64(30)).or(ts().eq(lit_i64(12))), &s));
+        assert!(!may_match(&ts().gt(lit_i64(15)).and(ts().lt(lit_i64(5))), &s));
+    }
+
+    #[test]
+    fn unknown_filters_may_match() {
+        let s = stats(10, 20);
+        assert!(may_match(&datafusion::prelude::col("other").gt(lit_i64(99)), &s));
+        assert!(may_match(&ts().gt(datafusion::prelude::lit("x")), &s));
+        assert!(may_match(&ts().is_null(), &s));
+        assert!(may_match(&ts().gt(lit_i64(99)), &BTreeMap::new()));
+
+        // Timestamps are compared in microseconds.
+        let day = 86_400_000_000;
+        let date = Expr::Literal(ScalarValue::Date32(Some(2)), None);
+        assert!(!may_match(&ts().gt_eq(date.clone()), &stats(0, day)));
+        assert!(may_match(&ts().gt_eq(date), &stats(0, 2 * day)));
+        let odd_ns = Expr::Literal(ScalarValue::TimestampNanosecond(Some(1_500), None), None);
+        assert!(may_match(&ts().lt(odd_ns), &stats(2, 5)));
+    }
+
+    #[test]
+    fn prune_shards_drops_shards_outside_the_range() {
+        let mut shards = test_shards();
+        shards[0].column_stats = stats(0, 99);
+        shards[1].column_stats = stats(100, 199);
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let provider = DistributedTableProvider {
+            table_name: "orders".to_string(),
+            schema: test_schema(),
+            shards,
+            runtime_handle: rt.handle().clone(),
+        };
+
+        let (kept, pruned) = provider.prune_shards(&[ts().gt_eq(lit_i64(150))]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].node_name, "node-b");
+        assert_eq!(pruned, vec!["node-a".to_string()]);
+        assert_eq!(
+            provider.describe_pruning(&[ts().gt_eq(lit_i64(150))]),
+            "orders: scanning 1 of 2 shard(s), pruned node-a"
+        );
+        assert_eq!(
+            provider.describe_pruning(&[]),
+            "orders: scanning 2 of 2 shard(s)"
+        );
+    }
+
+    #[test]
+    fn shard_routes_without_replicas_is_primary_only() {
+        let routes = shard_routes(&test_shards()[0], "orders");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(routes[0][0].node_name, "node-a");
+        assert_eq!(routes[0][0].table_name, "orders");
+    }
+
+    #[test]
+    fn route_order_prefers_less_loaded_copies() {
+        let mut shard = test_shards()[0].clone();
+        shard.replicas = vec![replica(0, "b"), replica(0, "c")];
+        let routes = shard_routes(&shard, "orders");
+        let scores: HashMap<String, f64> = [
+            ("node-a".to_string(), 1.4),
+            ("b".to_string(), 0.2),
+            ("c".to_string(), 0.23),
+        ]
+        .into_iter()
+        .collect();
+        let route_scores: Vec<u64> = routes.iter().map(|r| route_score(r, &scores)).collect();
+        assert_eq!(route_scores, vec![14, 2, 2]);
+        assert_eq!(route_order(&route_scores, 0), vec![1, 2, 0]);
+        assert_eq!(route_order(&route_scores, 2), vec![2, 1, 0]);
+        // Without load information the rotation alone decides.
+        let unknown: Vec<u64> = routes.iter().map(|r| route_score(r, &HashMap::new())).collect();
+        assert_eq!(route_order(&unknown, 1), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn load_policies_order_routes() {
+        let mut shard = test_shards()[0].clone();
+        shard.replicas = vec![replica(0, "b"), replica(0, "c")];
+        let routes = shard_routes(&shard, "orders");
+        let routes: Vec<Vec<&ScanTarget>> = routes.iter().map(|r| r.iter().collect()).collect();
+        let scores: HashMap<String, f64> = [
+            ("node-a".to_string(), 1.4),
+            ("b".to_string(), 0.9),
+            ("c".to_string(), 0.2),
+        ]
+        .into_iter()
+        .collect();
+        let order = |policy, local, start| policy_route_order(policy, &routes, &scores, local, start);
+
+        assert_eq!(order(LoadPolicy::RoundRobin, None, 1), vec![1, 2, 0]);
+        assert_eq!(order(LoadPolicy::LeastLoaded, None, 1), vec![2, 1, 0]);
+        assert_eq!(order(LoadPolicy::Locality, Some("c"), 1), vec![2, 0, 1]);
+        assert_eq!(order(LoadPolicy::Locality, Some("node-z"), 1), vec![0, 1, 2]);
+
+        let reason = |policy, local, first| route_choice_reason(policy, &routes, first, &scores, local);
+        assert_eq!(
+            reason(LoadPolicy::LeastLoaded, None, 2),
+            "least_loaded: load 0.2, lowest of 3 copies"
+        );
+        assert_eq!(reason(LoadPolicy::Locality, Some("c"), 2), "locality: copy on this node");
+        assert_eq!(
+            reason(LoadPolicy::Locality, None, 0),
+            "locality: node the partition is assigned to"
+        );
+        assert_eq!(
+            route_choice_reason(LoadPolicy::RoundRobin, &routes[..1], 0, &scores, None),
+            "only copy"
+        );
+    }
+
+    #[test]
+    fn unreachable_copies_are_tried_last() {
+        let mut shard = test_shards()[0].clone();
+        shard.replicas = vec![replica(0, "b"), replica(0, "c")];
+        let routes = shard_routes(&shard, "orders");
+        let routes: Vec<Vec<&ScanTarget>> = routes.iter().map(|r| r.iter().collect()).collect();
+        let unreachable: HashSet<String> = ["node-a".to_string(), "c".to_string()].into();
+
+        let mut order = policy_route_order(LoadPolicy::RoundRobin, &routes, &HashMap::new(), None, 2);
+        assert_eq!(order, vec![2, 0, 1]);
+        demote_unreachable(&mut order, &routes, &unreachable);
+        assert_eq!(order, vec![1, 2, 0]);
+
+        // With every copy unreachable the policy's order stands.
+        let mut order = vec![2, 0, 1];
+        let all: HashSet<String> = ["node-a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        demote_unreachable(&mut order, &routes, &all);
+        assert_eq!(order, vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn shard_routes_read_one_copy_per_partition() {
+        let mut shard = test_shards()[0].clone();
+        shard.replicas = vec![replica(0, "b"), replica(2, "b"), replica(0, "c"), replica(2, "c")];
+        let routes = shard_routes(&shard, "orders");
+        assert_eq!(routes.len(), 3);
+        for (route, node) in routes[1..].iter().zip(["b", "c"]) {
+            let tables: Vec<&str> = route.iter().map(|t| t.table_name.as_str()).collect();
+            assert_eq!(tables, vec!["orders__replica_0", "orders__replica_2"]);
+            assert!(route.iter().all(|t| t.node_name == node));
+        }
+    }
 }
@@ -1,7 +1,11 @@
 //! [`SQLExecutor`] implementation backed by the local trexsql instance for
 //! datafusion-federation SQL pushdown.
+//!
+//! Fragments run with DuckDB's `threads` setting taken from this node's
+//! `node_query_threads` config key, so operators can leave cores to gossip
+//! and Flight. Without the key DuckDB picks the thread count itself.
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use arrow::array::{Array, RecordBatch};
 use arrow::datatypes::SchemaRef;
@@ -79,7 +83,59 @@ impl SQLExecutor for DuckDBSQLExecutor {
     }
 }
 
+pub const QUERY_THREADS_KEY: &str = "node_query_threads";
+
+/// The `threads` value last set from `node_query_threads`; `None` while
+/// DuckDB still uses its own default.
+static APPLIED_THREADS: Mutex<Option<i64>> = Mutex::new(None);
+
+/// `node_query_threads` as set on this node through `trex_db_set`.
+pub fn configured_query_threads() -> Option<i64> {
+    crate::gossip::GossipRegistry::instance()
+        .get_self_config()
+        .ok()
+        .and_then(|config| config.into_iter().find(|(k, _)| k == QUERY_THREADS_KEY))
+        .and_then(|(_, v)| v.trim().parse().ok())
+        .filter(|n| *n > 0)
+}
+
+/// The statement moving DuckDB from `applied` threads to `configured`, or
+/// `None` when nothing changes. Dropping the key goes back to DuckDB's
+/// default; a thread count set by hand is left alone until the key is set.
+fn threads_statement(applied: Option<i64>, configured: Option<i64>) -> Option<String> {
+    match (applied, configured) {
+        (a, c) if a == c => None,
+        (_, Some(n)) => Some(format!("SET threads = {n}")),
+        (Some(_), None) => Some("RESET threads".to_string()),
+        (None, None) => None,
+    }
+}
+
+/// Bring DuckDB's thread count in line with `node_query_threads`. Called
+/// before every fragment, so a change through `trex_db_set` applies to the
+/// next one.
+pub fn apply_query_threads() -> Result<(), String> {
+    let configured = configured_query_threads();
+    let mut applied = APPLIED_THREADS
+        .lock()
+        .map_err(|_| "query threads lock poisoned".to_string())?;
+    let Some(statement) = threads_statement(*applied, configured) else {
+        return Ok(());
+    };
+    crate::pool::execute(&statement)
+        .map_err(|e| format!("Failed to apply {QUERY_THREADS_KEY}: {e}"))?;
+    crate::logging::SwarmLogger::info(
+        "executor",
+        &format!("Applied {QUERY_THREADS_KEY}: {statement}"),
+    );
+    *applied = configured;
+    Ok(())
+}
+
 fn execute_on_duckdb(sql: &str) -> DFResult<Vec<RecordBatch>> {
+    if let Err(e) = apply_query_threads() {
+        crate::logging::SwarmLogger::warn("executor", &e);
+    }
     let (_schema, batches) = crate::pool::read_arrow(sql)
         .map_err(|e| datafusion::error::DataFusionError::Execution(e))?;
     Ok(batches)
@@ -109,6 +165,15 @@ mod tests {
         let _dialect_ref: &dyn Dialect = dialect.as_ref();
     }
 
+    #[test]
+    fn test_threads_statement_only_on_change() {
+        assert_eq!(threads_statement(None, None), None);
+        assert_eq!(threads_statement(Some(4), Some(4)), None);
+        assert_eq!(threads_statement(None, Some(4)).as_deref(), Some("SET threads = 4"));
+        assert_eq!(threads_statement(Some(4), Some(2)).as_deref(), Some("SET threads = 2"));
+        assert_eq!(threads_statement(Some(4), None).as_deref(), Some("RESET threads"));
+    }
+
     #[test]
     fn test_execute_without_connection_returns_error() {
         let executor = DuckDBSQLExecutor;
@@ -0,0 +1,178 @@
+//! Structured errors for the distributed query path, so callers can tell a
+//! node that could not be reached from a query that failed on it.
+//!
+//! Scalar and table functions still report errors as text: a [`SwarmError`]
+//! displays as the message the string errors used to carry, and converts
+//! into `String` so functions returning `Result<_, String>` can keep using
+//! `?` on it.
+
+use std::fmt;
+
+use datafusion::error::DataFusionError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SwarmError {
+    /// The node at `endpoint` could not be connected to or dropped the
+    /// connection. Running the request on another copy may succeed.
+    NodeUnreachable { endpoint: String, message: String },
+    /// A query was rejected or failed while planning or executing.
+    SqlExecution(String),
+    /// Shuffle data could not be moved between nodes.
+    ShuffleFailed(String),
+    /// Admission control rejected or queued the query.
+    AdmissionRejected(String),
+    /// A node or operation did not answer in time.
+    Timeout(String),
+    /// Scheduler state, configuration and other local failures.
+    Internal(String),
+}
+
+pub type SwarmResult<T> = Result<T, SwarmError>;
+
+impl SwarmError {
+    pub fn message(&self) -> &str {
+        match self {
+            SwarmError::NodeUnreachable { message, .. }
+            | SwarmError::SqlExecution(message)
+            | SwarmError::ShuffleFailed(message)
+            | SwarmError::AdmissionRejected(message)
+            | SwarmError::Timeout(message)
+            | SwarmError::Internal(message) => message,
+        }
+    }
+
+    /// Whether the same request may succeed against another node.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SwarmError::NodeUnreachable { .. })
+    }
+
+    /// The same kind of error with its message rewritten, to add context.
+    pub fn map_message(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            SwarmError::NodeUnreachable { endpoint, message } => SwarmError::NodeUnreachable {
+                endpoint,
+                message: f(message),
+            },
+            SwarmError::SqlExecution(message) => SwarmError::SqlExecution(f(message)),
+            SwarmError::ShuffleFailed(message) => SwarmError::ShuffleFailed(f(message)),
+            SwarmError::AdmissionRejected(message) => SwarmError::AdmissionRejected(f(message)),
+            SwarmError::Timeout(message) => SwarmError::Timeout(f(message)),
+            SwarmError::Internal(message) => SwarmError::Internal(f(message)),
+        }
+    }
+
+    /// Classify a failed Flight call to `endpoint` by its gRPC status.
+    /// `message` is the full text to report.
+    pub fn from_status(endpoint: &str, status: &tonic::Status, message: String) -> Self {
+        match status.code() {
+            tonic::Code::Unavailable => SwarmError::NodeUnreachable {
+                endpoint: endpoint.to_string(),
+                message,
+            },
+            // Connections that break mid-call surface as a transport error.
+            tonic::Code::Unknown if status.message().contains("transport error") => {
+                SwarmError::NodeUnreachable {
+                    endpoint: endpoint.to_string(),
+                    message,
+                }
+            }
+            tonic::Code::DeadlineExceeded => SwarmError::Timeout(message),
+            _ => SwarmError::SqlExecution(message),
+        }
+    }
+
+    /// The error an operator raised inside `err`, with `context` prefixed,
+    /// or `SqlExecution` when DataFusion failed on its own.
+    pub fn from_datafusion(err: &DataFusionError, context: &str) -> Self {
+        let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+        while let Some(e) = source {
+            if let Some(swarm) = e.downcast_ref::<SwarmError>() {
+                return swarm.clone().map_message(|m| format!("{context}: {m}"));
+            }
+            source = e.source();
+        }
+        SwarmError::SqlExecution(format!("{context}: {err}"))
+    }
+}
+
+impl fmt::Display for SwarmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for SwarmError {}
+
+impl From<SwarmError> for String {
+    fn from(err: SwarmError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Errors from helpers that still report plain strings count as internal.
+impl From<String> for SwarmError {
+    fn from(message: String) -> Self {
+        SwarmError::Internal(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes_map_to_kinds() {
+        let err = |status: tonic::Status| {
+            SwarmError::from_status("http://10.0.0.1:8815", &status, "failed".to_string())
+        };
+        assert_eq!(
+            err(tonic::Status::unavailable("connection refused")),
+            SwarmError::NodeUnreachable {
+                endpoint: "http://10.0.0.1:8815".to_string(),
+                message: "failed".to_string(),
+            }
+        );
+        assert!(err(tonic::Status::unknown("transport error")).is_retryable());
+        assert_eq!(
+            err(tonic::Status::deadline_exceeded("slow")),
+            SwarmError::Timeout("failed".to_string())
+        );
+        assert_eq!(
+            err(tonic::Status::internal("Catalog Error")),
+            SwarmError::SqlExecution("failed".to_string())
+        );
+    }
+
+    #[test]
+    fn only_unreachable_nodes_are_retryable() {
+        assert!(!SwarmError::SqlExecution("x".to_string()).is_retryable());
+        assert!(!SwarmError::Timeout("x".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn datafusion_errors_keep_the_operator_error() {
+        let shuffle = SwarmError::ShuffleFailed("partition 1 lost".to_string());
+        let wrapped = DataFusionError::Context(
+            "join".to_string(),
+            Box::new(DataFusionError::External(Box::new(shuffle))),
+        );
+        assert_eq!(
+            SwarmError::from_datafusion(&wrapped, "Distributed query execution failed"),
+            SwarmError::ShuffleFailed(
+                "Distributed query execution failed: partition 1 lost".to_string()
+            )
+        );
+
+        let plain = DataFusionError::Plan("no such column".to_string());
+        assert!(matches!(
+            SwarmError::from_datafusion(&plain, "planning"),
+            SwarmError::SqlExecution(m) if m.starts_with("planning: ")
+        ));
+    }
+
+    #[test]
+    fn converts_to_its_message() {
+        let text: String = SwarmError::AdmissionRejected("Query rejected: busy".to_string()).into();
+        assert_eq!(text, "Query rejected: busy");
+    }
+}
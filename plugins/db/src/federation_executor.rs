@@ -4,7 +4,7 @@ use std::sync::{Arc, Mutex, OnceLock};
 
 use arrow::datatypes::SchemaRef;
 use datafusion::error::Result as DFResult;
-use datafusion::execution::runtime_env::RuntimeEnv;
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
 use datafusion::execution::SessionStateBuilder;
 use datafusion::optimizer::Optimizer;
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
@@ -20,7 +20,7 @@ use futures::StreamExt;
 
 use crate::catalog;
 use crate::flight_client;
-use crate::logging::SwarmLogger;
+use crate::logging::{self, SwarmLogger};
 
 pub fn wrap_executor_error(
     operation: &str,
@@ -135,9 +135,10 @@ impl SQLExecutor for FlightSQLExecutor {
         // Spawn as a tokio task (non-blocking). Returning a lazy stream avoids
         // blocking tokio worker threads — which would deadlock the runtime when
         // multiple federation queries execute concurrently.
-        let join_handle = self.runtime_handle.spawn(async move {
+        let query_id = logging::current_query_id();
+        let join_handle = self.runtime_handle.spawn(logging::with_query_id(query_id, async move {
             flight_client::query_node(&endpoint, &sql).await
-        });
+        }));
 
         let result_stream = futures::stream::once(async move {
             let batches = join_handle
@@ -206,6 +207,7 @@ pub async fn create_duckdb_session() -> Result<SessionContext, String> {
         .ok_or("Default catalog not found")?
         .register_schema("public", schema_provider)
         .map_err(|e| format!("Schema registration failed: {e}"))?;
+    crate::postgres_federation::register_postgres_catalogs(&ctx).await;
 
     SwarmLogger::info("distributed", "DataFusion session with trexsql federation ready");
     Ok(ctx)
@@ -308,6 +310,10 @@ pub async fn create_distributed_session_with_classifications(
         .map(|i| i + 1)
         .unwrap_or(rules.len());
     rules.insert(pos, Arc::new(FederationOptimizerRule::new()));
+    rules.insert(
+        pos,
+        Arc::new(crate::shuffle_optimizer::JoinReorderRule::new(Arc::clone(&catalog_stats))),
+    );
 
     // Physical optimizer: start with DataFusion defaults (EnforceDistribution,
     // EnforceSorting, CoalesceBatches, etc.), then append ShuffleInsertionRule.
@@ -323,9 +329,24 @@ pub async fn create_distributed_session_with_classifications(
         Arc::new(crate::shuffle_optimizer::ShuffleInsertionRule::new(catalog_stats)),
     );
 
+    // With SWARM_MEMORY_LIMIT (bytes) set, sorts and aggregations spill to
+    // disk instead of growing past the limit; unset keeps an unbounded pool.
+    // Each query gets its own spill directory (see `spill::query_runtime`).
+    let runtime_env = match std::env::var("SWARM_MEMORY_LIMIT")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+    {
+        Some(limit) => RuntimeEnvBuilder::new()
+            .with_memory_limit(limit, 1.0)
+            .build_arc()
+            .map_err(|e| format!("Runtime environment failed: {e}"))?,
+        None => Arc::new(RuntimeEnv::default()),
+    };
+
     let state = SessionStateBuilder::new()
         .with_default_features()
         .with_config(session_config)
+        .with_runtime_env(runtime_env)
         .with_optimizer_rules(rules)
         .with_physical_optimizer_rules(physical_optimizer_rules)
         .with_query_planner(Arc::new(FederatedQueryPlanner::new()))
@@ -389,6 +410,7 @@ pub async fn create_distributed_session_with_classifications(
             .register_schema("public", multi_schema)
             .map_err(|e| format!("Schema registration failed: {e}"))?;
     }
+    crate::postgres_federation::register_postgres_catalogs(&ctx).await;
 
     SwarmLogger::info(
         "distributed",
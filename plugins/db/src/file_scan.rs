@@ -0,0 +1,249 @@
+//! Pushdown of `read_parquet`/`read_csv` scans to the nodes holding the
+//! files, so co-located files are scanned in parallel where they live
+//! instead of being read through the coordinator.
+//!
+//! `file_mappings` in `SWARM_CONFIG` maps a path as queries write it to the
+//! local path on each node that holds part of the data. A query whose only
+//! relation is a scan of a mapped path runs on every mapped node with the
+//! path swapped for that node's own, and the per-node results are merged as
+//! for a sharded table.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ops::ControlFlow;
+use std::sync::{OnceLock, RwLock};
+
+use sqlparser::ast::{
+    Expr, FunctionArg, FunctionArgExpr, Statement, TableFactor, Value, Visit, VisitMut, Visitor,
+    VisitorMut,
+};
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+
+use crate::aggregation::{self, DecomposedQuery};
+
+/// Table functions whose first argument is the path of the files to read.
+const FILE_FUNCTIONS: &[&str] = &["read_parquet", "parquet_scan", "read_csv", "read_csv_auto"];
+
+/// Logical path to the local path on each node holding its files, keyed by
+/// node name.
+pub type FileMappings = HashMap<String, BTreeMap<String, String>>;
+
+/// The statement one node runs for a pushed-down scan.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeScan {
+    pub node_name: String,
+    pub sql: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileScanPlan {
+    pub logical_path: String,
+    pub decomposed: DecomposedQuery,
+    pub scans: Vec<NodeScan>,
+}
+
+fn mappings_lock() -> &'static RwLock<FileMappings> {
+    static MAPPINGS: OnceLock<RwLock<FileMappings>> = OnceLock::new();
+    MAPPINGS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Replace the file mappings used to plan later queries.
+pub fn configure(mappings: FileMappings) {
+    if let Ok(mut current) = mappings_lock().write() {
+        *current = mappings;
+    }
+}
+
+/// The pushdown plan for `sql`, or `None` when it should run as usual:
+/// its only relation must be one scan of a mapped path.
+pub fn plan(sql: &str) -> Option<FileScanPlan> {
+    let mappings = mappings_lock().read().ok()?;
+    plan_with(sql, &mappings)
+}
+
+fn plan_with(sql: &str, mappings: &FileMappings) -> Option<FileScanPlan> {
+    if mappings.is_empty() {
+        return None;
+    }
+    let statements = Parser::parse_sql(&GenericDialect {}, sql).ok()?;
+    let [Statement::Query(query)] = statements.as_slice() else {
+        return None;
+    };
+
+    let mut finder = ScanFinder {
+        mappings,
+        paths: Vec::new(),
+        other_relations: 0,
+    };
+    let _ = query.visit(&mut finder);
+    if finder.other_relations > 0 || finder.paths.len() != 1 {
+        return None;
+    }
+    let logical_path = finder.paths.remove(0);
+
+    let decomposed = aggregation::decompose_query(sql).ok()?;
+    let scans = mappings[&logical_path]
+        .iter()
+        .map(|(node_name, local_path)| {
+            Some(NodeScan {
+                node_name: node_name.clone(),
+                sql: rewrite_path(&decomposed.node_sql, &logical_path, local_path)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(FileScanPlan {
+        logical_path,
+        decomposed,
+        scans,
+    })
+}
+
+/// The path a file-reading table function is called on, when it is a plain
+/// string literal.
+fn scanned_path(factor: &TableFactor) -> Option<&String> {
+    let TableFactor::Table {
+        name,
+        args: Some(args),
+        ..
+    } = factor
+    else {
+        return None;
+    };
+    let function = name.0.last()?.value.to_lowercase();
+    if !FILE_FUNCTIONS.contains(&function.as_str()) {
+        return None;
+    }
+    match args.args.first()? {
+        FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Value(Value::SingleQuotedString(
+            path,
+        )))) => Some(path),
+        _ => None,
+    }
+}
+
+/// Collects the mapped paths a query scans and counts every other relation.
+struct ScanFinder<'a> {
+    mappings: &'a FileMappings,
+    paths: Vec<String>,
+    other_relations: usize,
+}
+
+impl Visitor for ScanFinder<'_> {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, factor: &TableFactor) -> ControlFlow<()> {
+        match factor {
+            // Containers: what they hold is visited on its own.
+            TableFactor::Derived { .. } | TableFactor::NestedJoin { .. } => {}
+            _ => match scanned_path(factor) {
+                Some(path) if self.mappings.contains_key(path) => self.paths.push(path.clone()),
+                _ => self.other_relations += 1,
+            },
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+struct PathRewriter<'a> {
+    from: &'a str,
+    to: &'a str,
+}
+
+impl VisitorMut for PathRewriter<'_> {
+    type Break = ();
+
+    fn pre_visit_table_factor(&mut self, factor: &mut TableFactor) -> ControlFlow<()> {
+        if scanned_path(factor).is_some_and(|path| path == self.from) {
+            if let TableFactor::Table { args: Some(args), .. } = factor {
+                args.args[0] = FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Value(
+                    Value::SingleQuotedString(self.to.to_string()),
+                )));
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+/// `sql` with scans of `from` reading `to` instead.
+fn rewrite_path(sql: &str, from: &str, to: &str) -> Option<String> {
+    let mut statements = Parser::parse_sql(&GenericDialect {}, sql).ok()?;
+    let _ = VisitMut::visit(&mut statements, &mut PathRewriter { from, to });
+    Some(
+        statements
+            .iter()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>()
+            .join("; "),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mappings() -> FileMappings {
+        let mut nodes = BTreeMap::new();
+        nodes.insert("node-a".to_string(), "/mnt/a/events/*.parquet".to_string());
+        nodes.insert("node-b".to_string(), "/mnt/b/events/*.parquet".to_string());
+        HashMap::from([("/data/events/*.parquet".to_string(), nodes)])
+    }
+
+    #[test]
+    fn scan_of_mapped_path_runs_on_each_node() {
+        let plan = plan_with(
+            "SELECT id FROM read_parquet('/data/events/*.parquet') WHERE id > 1",
+            &mappings(),
+        )
+        .unwrap();
+        assert_eq!(plan.logical_path, "/data/events/*.parquet");
+        assert!(!plan.decomposed.has_aggregations);
+        assert_eq!(
+            plan.scans,
+            vec![
+                NodeScan {
+                    node_name: "node-a".to_string(),
+                    sql: "SELECT id FROM read_parquet('/mnt/a/events/*.parquet') WHERE id > 1"
+                        .to_string(),
+                },
+                NodeScan {
+                    node_name: "node-b".to_string(),
+                    sql: "SELECT id FROM read_parquet('/mnt/b/events/*.parquet') WHERE id > 1"
+                        .to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn aggregates_are_decomposed() {
+        let plan = plan_with(
+            "SELECT kind, COUNT(*) FROM read_csv('/data/events/*.parquet', header = true) GROUP BY kind",
+            &mappings(),
+        )
+        .unwrap();
+        assert!(plan.decomposed.has_aggregations);
+        assert!(plan.scans[0]
+            .sql
+            .contains("read_csv('/mnt/a/events/*.parquet', header = true)"));
+        assert!(plan.decomposed.merge_sql.contains("FROM _merged"));
+    }
+
+    #[test]
+    fn other_queries_are_not_pushed_down() {
+        for sql in [
+            "SELECT * FROM read_parquet('/data/other/*.parquet')",
+            "SELECT * FROM read_parquet('/data/events/*.parquet') e JOIN users u ON e.id = u.id",
+            "SELECT * FROM read_parquet('/data/events/*.parquet') UNION ALL SELECT * FROM read_parquet('/data/events/*.parquet')",
+            "SELECT * FROM events",
+            "SELECT 1",
+            "not sql",
+        ] {
+            assert_eq!(plan_with(sql, &mappings()), None, "{sql}");
+        }
+        assert_eq!(
+            plan_with("SELECT * FROM read_parquet('/data/events/*.parquet')", &HashMap::new()),
+            None
+        );
+    }
+}
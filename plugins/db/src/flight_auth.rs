@@ -0,0 +1,240 @@
+//! Bearer-token authentication of Flight calls.
+//!
+//! A Flight server accepts a call only when its `authorization` header
+//! carries `Bearer <token>` with a token it knows: the cluster token, which
+//! every node derives from the cluster secret, or one of the per-client
+//! tokens in `SWARM_FLIGHT_TOKENS`. Nodes send the cluster token on their
+//! own calls, or `SWARM_FLIGHT_TOKEN` when it is set.
+//!
+//! A server with neither a secret nor client tokens, or one started with
+//! `SWARM_FLIGHT_AUTH=none`, accepts every call, for local development.
+
+use std::env;
+use std::sync::RwLock;
+
+use ring::hmac;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::{Request, Status};
+
+use crate::config;
+use crate::gossip_auth;
+use crate::logging::SwarmLogger;
+
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+
+/// Domain separator, so the token is not the gossip HMAC key itself.
+const CLUSTER_TOKEN_CONTEXT: &[u8] = b"trex-flight-token";
+
+/// `cluster_secret` from `SWARM_CONFIG`; takes precedence over the
+/// environment.
+static CONFIGURED_SECRET: RwLock<Option<String>> = RwLock::new(None);
+
+/// Record the cluster secret loaded from `SWARM_CONFIG`, before the Flight
+/// server starts.
+pub fn set_cluster_secret(secret: Option<String>) {
+    if let Ok(mut slot) = CONFIGURED_SECRET.write() {
+        *slot = secret.filter(|s| !s.is_empty());
+    }
+}
+
+fn cluster_secret() -> Option<String> {
+    CONFIGURED_SECRET
+        .read()
+        .ok()
+        .and_then(|slot| slot.clone())
+        .or_else(config::cluster_secret_from_env)
+}
+
+/// Token derived from the cluster secret: hex HMAC-SHA256 of a fixed
+/// context string.
+pub fn cluster_token(secret: &str) -> String {
+    let tag = hmac::sign(&gossip_auth::key(secret), CLUSTER_TOKEN_CONTEXT);
+    tag.as_ref().iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// The token this node sends: `SWARM_FLIGHT_TOKEN`, else the cluster token.
+pub fn client_token() -> Option<String> {
+    env::var("SWARM_FLIGHT_TOKEN")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .or_else(|| cluster_secret().map(|secret| cluster_token(&secret)))
+}
+
+fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.trim().split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The tokens a Flight server accepts. Empty means unauthenticated.
+#[derive(Clone, Default)]
+pub struct FlightAuth {
+    tokens: Vec<String>,
+}
+
+impl FlightAuth {
+    pub fn new(cluster_secret: Option<&str>, client_tokens: &[String]) -> Self {
+        let tokens = cluster_secret
+            .map(cluster_token)
+            .into_iter()
+            .chain(client_tokens.iter().map(|t| t.trim().to_string()))
+            .filter(|t| !t.is_empty())
+            .collect();
+        Self { tokens }
+    }
+
+    /// The server configuration: the cluster secret and `SWARM_FLIGHT_TOKENS`
+    /// (comma-separated), unless `SWARM_FLIGHT_AUTH=none`.
+    pub fn from_env() -> Self {
+        let disabled = env::var("SWARM_FLIGHT_AUTH")
+            .map(|v| v.eq_ignore_ascii_case("none"))
+            .unwrap_or(false);
+        if disabled {
+            return Self::default();
+        }
+        let client_tokens: Vec<String> = env::var("SWARM_FLIGHT_TOKENS")
+            .map(|v| v.split(',').map(str::to_string).collect())
+            .unwrap_or_default();
+        Self::new(cluster_secret().as_deref(), &client_tokens)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty()
+    }
+
+    pub fn accepts(&self, token: &str) -> bool {
+        self.tokens
+            .iter()
+            .any(|known| constant_time_eq(known.as_bytes(), token.as_bytes()))
+    }
+
+    /// Admit `request` to the `call` RPC, or reject it as unauthenticated.
+    pub fn check<T>(&self, call: &str, request: &Request<T>) -> Result<(), Status> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        let header = request
+            .metadata()
+            .get(AUTHORIZATION_HEADER)
+            .and_then(|v| v.to_str().ok());
+        let result = match header.and_then(bearer_token) {
+            None => Err(Status::unauthenticated("Missing bearer token")),
+            Some(token) if self.accepts(token) => Ok(()),
+            Some(_) => Err(Status::unauthenticated("Invalid bearer token")),
+        };
+        if let Err(status) = &result {
+            let peer = request
+                .remote_addr()
+                .map(|a| a.to_string())
+                .unwrap_or_else(|| "unknown peer".to_string());
+            SwarmLogger::warn(
+                "flight-auth",
+                &format!("Rejected {call} from {peer}: {}", status.message()),
+            );
+        }
+        result
+    }
+}
+
+/// Client interceptor adding `authorization: Bearer <token>` to every call.
+#[derive(Clone, Default)]
+pub struct BearerToken {
+    value: Option<MetadataValue<Ascii>>,
+}
+
+impl std::fmt::Debug for BearerToken {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let token = if self.value.is_some() { "<redacted>" } else { "<none>" };
+        f.debug_struct("BearerToken").field("value", &token).finish()
+    }
+}
+
+impl BearerToken {
+    pub fn new(token: Option<&str>) -> Self {
+        Self {
+            value: token.and_then(|t| {
+                let mut value: MetadataValue<Ascii> = format!("Bearer {t}").parse().ok()?;
+                value.set_sensitive(true);
+                Some(value)
+            }),
+        }
+    }
+
+    /// The token from [`client_token`].
+    pub fn from_env() -> Self {
+        Self::new(client_token().as_deref())
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(value) = &self.value {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_HEADER, value.clone());
+        }
+        Ok(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_with(header: Option<&str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(header) = header {
+            request
+                .metadata_mut()
+                .insert(AUTHORIZATION_HEADER, header.parse().unwrap());
+        }
+        request
+    }
+
+    #[test]
+    fn cluster_token_is_stable_and_secret_dependent() {
+        assert_eq!(cluster_token("s3cret"), cluster_token("s3cret"));
+        assert_ne!(cluster_token("s3cret"), cluster_token("other"));
+        assert_eq!(cluster_token("s3cret").len(), 64);
+        assert_ne!(cluster_token("s3cret"), "s3cret");
+    }
+
+    #[test]
+    fn without_tokens_every_call_is_admitted() {
+        let auth = FlightAuth::new(None, &[]);
+        assert!(!auth.is_enabled());
+        assert!(auth.check("DoGet", &request_with(None)).is_ok());
+    }
+
+    #[test]
+    fn cluster_and_client_tokens_are_accepted() {
+        let auth = FlightAuth::new(Some("s3cret"), &["client-a".to_string()]);
+        let cluster = format!("Bearer {}", cluster_token("s3cret"));
+        assert!(auth.check("DoGet", &request_with(Some(&cluster))).is_ok());
+        assert!(auth.check("DoAction", &request_with(Some("bearer client-a"))).is_ok());
+    }
+
+    #[test]
+    fn missing_and_wrong_tokens_are_rejected() {
+        let auth = FlightAuth::new(Some("s3cret"), &[]);
+        for header in [None, Some("Bearer nope"), Some("Basic s3cret"), Some("s3cret")] {
+            let status = auth.check("DoGet", &request_with(header)).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::Unauthenticated);
+        }
+    }
+
+    #[test]
+    fn interceptor_attaches_the_token() {
+        let mut interceptor = BearerToken::new(Some("client-a"));
+        let request = interceptor.call(Request::new(())).unwrap();
+        let auth = FlightAuth::new(None, &["client-a".to_string()]);
+        assert!(auth.check("DoGet", &request).is_ok());
+
+        let request = BearerToken::new(None).call(Request::new(())).unwrap();
+        assert!(request.metadata().get(AUTHORIZATION_HEADER).is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use std::sync::Once;
+use std::time::{Duration, Instant};
 
 use arrow::array::RecordBatch;
 use arrow::datatypes::SchemaRef;
@@ -6,9 +7,13 @@ use arrow_flight::decode::FlightRecordBatchStream;
 use arrow_flight::flight_service_client::FlightServiceClient;
 use arrow_flight::{Action, Ticket};
 use futures::TryStreamExt;
+use tonic::codegen::InterceptedService;
 use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
 
-use crate::logging::SwarmLogger;
+use crate::error::{SwarmError, SwarmResult};
+use crate::flight_auth::BearerToken;
+use crate::flight_pool::{self, Lease};
+use crate::logging::{self, SwarmLogger};
 
 /// rustls 0.23+ requires a CryptoProvider be installed before any TLS handshake.
 /// Both `ring` and `aws-lc-rs` features can be linked transitively, so the
@@ -23,15 +28,20 @@ fn ensure_crypto_provider() {
     });
 }
 
-/// Arrow Flight gRPC client for executing SQL queries via DoGet.
+/// Flight client whose calls carry this node's bearer token.
+pub type AuthenticatedClient = FlightServiceClient<InterceptedService<Channel, BearerToken>>;
+
+/// Arrow Flight gRPC client for executing SQL queries via DoGet. Its
+/// connection comes from [`flight_pool`] and goes back when it is dropped.
 #[derive(Debug)]
 pub struct FlightClient {
     endpoint: String,
-    client: FlightServiceClient<Channel>,
+    client: AuthenticatedClient,
+    lease: Lease,
 }
 
 impl FlightClient {
-    pub async fn connect(endpoint: &str) -> Result<Self, String> {
+    pub async fn connect(endpoint: &str) -> SwarmResult<Self> {
         SwarmLogger::debug(
             "flight-client",
             &format!("Connecting to Flight server at {endpoint}"),
@@ -39,13 +49,15 @@ impl FlightClient {
 
         ensure_crypto_provider();
 
-        let channel = Endpoint::from_shared(endpoint.to_string())
-            .map_err(|e| format!("Failed to connect to {endpoint}: invalid URI: {e}"))?
-            .connect()
+        let target = Endpoint::from_shared(endpoint.to_string()).map_err(|e| {
+            SwarmError::Internal(format!("Failed to connect to {endpoint}: invalid URI: {e}"))
+        })?;
+        let lease = flight_pool::checkout(endpoint, target)
             .await
-            .map_err(|e| format!("Failed to connect to {endpoint}: {e}"))?;
+            .map_err(|e| node_unreachable(endpoint, format!("Failed to connect to {endpoint}: {e}")))?;
 
-        let client = FlightServiceClient::new(channel);
+        let client =
+            FlightServiceClient::with_interceptor(lease.channel(), BearerToken::from_env());
 
         SwarmLogger::debug(
             "flight-client",
@@ -55,6 +67,7 @@ impl FlightClient {
         Ok(Self {
             endpoint: endpoint.to_string(),
             client,
+            lease,
         })
     }
 
@@ -64,7 +77,7 @@ impl FlightClient {
         cert_path: &str,
         key_path: &str,
         ca_cert_path: &str,
-    ) -> Result<Self, String> {
+    ) -> SwarmResult<Self> {
         SwarmLogger::debug(
             "flight-client",
             &format!("Connecting to Flight server at {endpoint} with mTLS"),
@@ -86,15 +99,19 @@ impl FlightClient {
             .identity(identity)
             .ca_certificate(ca);
 
-        let channel = Endpoint::from_shared(endpoint.to_string())
+        let target = Endpoint::from_shared(endpoint.to_string())
             .map_err(|e| format!("Failed to connect to {endpoint}: invalid URI: {e}"))?
             .tls_config(tls_config)
-            .map_err(|e| format!("Failed to configure TLS for {endpoint}: {e}"))?
-            .connect()
+            .map_err(|e| format!("Failed to configure TLS for {endpoint}: {e}"))?;
+        let pool_key = format!("{endpoint} tls:{cert_path}:{key_path}:{ca_cert_path}");
+        let lease = flight_pool::checkout(&pool_key, target)
             .await
-            .map_err(|e| format!("Failed to connect to {endpoint} with TLS: {e}"))?;
+            .map_err(|e| {
+                node_unreachable(endpoint, format!("Failed to connect to {endpoint} with TLS: {e}"))
+            })?;
 
-        let client = FlightServiceClient::new(channel);
+        let client =
+            FlightServiceClient::with_interceptor(lease.channel(), BearerToken::from_env());
 
         SwarmLogger::debug(
             "flight-client",
@@ -104,6 +121,7 @@ impl FlightClient {
         Ok(Self {
             endpoint: endpoint.to_string(),
             client,
+            lease,
         })
     }
 
@@ -111,7 +129,15 @@ impl FlightClient {
     pub async fn execute_query(
         &mut self,
         sql: &str,
-    ) -> Result<(SchemaRef, Vec<RecordBatch>), String> {
+    ) -> SwarmResult<(SchemaRef, Vec<RecordBatch>)> {
+        let result = self.execute_query_inner(sql).await;
+        self.evict_if_broken(result)
+    }
+
+    async fn execute_query_inner(
+        &mut self,
+        sql: &str,
+    ) -> SwarmResult<(SchemaRef, Vec<RecordBatch>)> {
         SwarmLogger::debug(
             "flight-client",
             &format!("Executing query on {}: {sql}", self.endpoint),
@@ -120,11 +146,17 @@ impl FlightClient {
         let ticket_payload = serde_json::json!({ "query": sql }).to_string();
         let ticket = Ticket::new(ticket_payload.into_bytes());
 
+        let mut request = tonic::Request::new(ticket);
+        logging::tag_request(&mut request);
+
         let response = self
             .client
-            .do_get(ticket)
+            .do_get(request)
             .await
-            .map_err(|e| format!("Flight query failed on {}: {e}", self.endpoint))?;
+            .map_err(|e| {
+                let message = format!("Flight query failed on {}: {e}", self.endpoint);
+                SwarmError::from_status(&self.endpoint, &e, message)
+            })?;
 
         let flight_stream = FlightRecordBatchStream::new_from_flight_data(
             response
@@ -137,10 +169,16 @@ impl FlightClient {
         futures::pin_mut!(flight_stream);
 
         while let Some(batch) = flight_stream.try_next().await.map_err(|e| {
-            format!(
+            let message = format!(
                 "Failed to decode Flight response from {}: {e}",
                 self.endpoint
-            )
+            );
+            match &e {
+                arrow_flight::error::FlightError::Tonic(status) => {
+                    SwarmError::from_status(&self.endpoint, status, message)
+                }
+                _ => SwarmError::SqlExecution(message),
+            }
         })? {
             batches.push(batch);
         }
@@ -167,7 +205,12 @@ impl FlightClient {
     }
 
     /// Execute a Flight action (e.g. DDL/DML via "query") and return the response body.
-    pub async fn do_action(&mut self, action_type: &str, body: &str) -> Result<String, String> {
+    pub async fn do_action(&mut self, action_type: &str, body: &str) -> SwarmResult<String> {
+        let result = self.do_action_inner(action_type, body).await;
+        self.evict_if_broken(result)
+    }
+
+    async fn do_action_inner(&mut self, action_type: &str, body: &str) -> SwarmResult<String> {
         SwarmLogger::debug(
             "flight-client",
             &format!("DoAction '{}' on {}: {body}", action_type, self.endpoint),
@@ -178,17 +221,26 @@ impl FlightClient {
             body: body.as_bytes().to_vec().into(),
         };
 
+        let mut request = tonic::Request::new(action);
+        logging::tag_request(&mut request);
+
         let mut response = self
             .client
-            .do_action(action)
+            .do_action(request)
             .await
-            .map_err(|e| format!("DoAction '{}' failed on {}: {e}", action_type, self.endpoint))?
+            .map_err(|e| {
+                let message = format!("DoAction '{}' failed on {}: {e}", action_type, self.endpoint);
+                SwarmError::from_status(&self.endpoint, &e, message)
+            })?
             .into_inner();
 
         let result_body = if let Some(result) = response
             .message()
             .await
-            .map_err(|e| format!("Failed to read DoAction response from {}: {e}", self.endpoint))?
+            .map_err(|e| {
+                let message = format!("Failed to read DoAction response from {}: {e}", self.endpoint);
+                SwarmError::from_status(&self.endpoint, &e, message)
+            })?
         {
             String::from_utf8(result.body.to_vec())
                 .unwrap_or_else(|_| "<non-utf8 response>".to_string())
@@ -203,25 +255,105 @@ impl FlightClient {
 
         Ok(result_body)
     }
+
+    /// The raw Flight client on this connection, for calls without a
+    /// wrapper here such as DoExchange.
+    pub fn service_client(&mut self) -> &mut AuthenticatedClient {
+        &mut self.client
+    }
+
+    /// Take this connection out of the pool when `result` shows it broke,
+    /// so no later call is handed the same dead channel.
+    pub fn evict_if_broken<T>(&self, result: SwarmResult<T>) -> SwarmResult<T> {
+        if let Err(e) = &result {
+            if e.is_retryable() {
+                flight_pool::evict(&self.lease);
+            }
+        }
+        result
+    }
 }
 
 /// One-shot: connect, execute SQL remotely via DoAction("query"). Must be called within tokio.
-pub async fn execute_remote_sql(endpoint: &str, sql: &str) -> Result<(), String> {
+pub async fn execute_remote_sql(endpoint: &str, sql: &str) -> SwarmResult<()> {
     let mut client = FlightClient::connect(endpoint).await?;
     let body = serde_json::json!({ "query": sql }).to_string();
     client.do_action("query", &body).await?;
     Ok(())
 }
 
-/// One-shot: trigger catalog refresh on a remote node.
-pub async fn refresh_remote_catalog(endpoint: &str) -> Result<(), String> {
+/// One-shot: trigger catalog refresh on a remote node. Returns the stats
+/// version it advertised, 0 from nodes that do not report one.
+pub async fn refresh_remote_catalog(endpoint: &str) -> SwarmResult<u64> {
     let mut client = FlightClient::connect(endpoint).await?;
-    client.do_action("refresh_catalog", "{}").await?;
-    Ok(())
+    let reply = client.do_action("refresh_catalog", "{}").await?;
+    Ok(serde_json::from_str::<serde_json::Value>(&reply)
+        .ok()
+        .and_then(|v| v.get("stats_version")?.as_u64())
+        .unwrap_or(0))
+}
+
+/// One-shot: have a remote node stop routing `table_name` by metadata older
+/// than `routing_version` and wait up to `timeout` for the queries planned on
+/// it. False when that did not finish in time.
+pub async fn drain_remote_routes(
+    endpoint: &str,
+    table_name: &str,
+    routing_version: u64,
+    timeout: Duration,
+) -> SwarmResult<bool> {
+    let mut client = FlightClient::connect(endpoint).await?;
+    let body = serde_json::json!({
+        "table": table_name,
+        "routing_version": routing_version,
+        "timeout_ms": timeout.as_millis() as u64,
+    })
+    .to_string();
+    let reply = client.do_action("drain_routes", &body).await?;
+    Ok(serde_json::from_str::<serde_json::Value>(&reply)
+        .ok()
+        .and_then(|v| v.get("drained")?.as_bool())
+        .unwrap_or(false))
+}
+
+/// One-shot: compact the local copies of a table on a remote node, returning
+/// its JSON vacuum report.
+pub async fn vacuum_remote_table(endpoint: &str, table_name: &str) -> SwarmResult<String> {
+    let mut client = FlightClient::connect(endpoint).await?;
+    let body = serde_json::json!({ "table": table_name }).to_string();
+    client.do_action("vacuum_table", &body).await
+}
+
+/// One-shot: the last `lines` log lines a remote node kept, oldest first.
+pub async fn fetch_remote_logs(endpoint: &str, lines: usize) -> SwarmResult<Vec<String>> {
+    let mut client = FlightClient::connect(endpoint).await?;
+    let body = serde_json::json!({ "lines": lines }).to_string();
+    let reply = client.do_action("logs", &body).await?;
+    serde_json::from_str(&reply).map_err(|e| {
+        SwarmError::Internal(format!("Invalid logs reply from {endpoint}: {e}"))
+    })
+}
+
+/// One-shot: time a "ping" DoAction round trip to a remote node. Connecting
+/// and the call together give up after `timeout`, so a dead node that never
+/// answers fails instead of hanging.
+pub async fn ping_node(endpoint: &str, timeout: Duration) -> SwarmResult<Duration> {
+    let ping = async {
+        let mut client = FlightClient::connect(endpoint).await?;
+        let started = Instant::now();
+        client.do_action("ping", "{}").await?;
+        Ok(started.elapsed())
+    };
+    tokio::time::timeout(timeout, ping).await.unwrap_or_else(|_| {
+        Err(SwarmError::Timeout(format!(
+            "Ping to {endpoint} timed out after {}ms",
+            timeout.as_millis()
+        )))
+    })
 }
 
 /// One-shot: connect, execute, return batches. Must be called within tokio.
-pub async fn query_node(endpoint: &str, sql: &str) -> Result<Vec<RecordBatch>, String> {
+pub async fn query_node(endpoint: &str, sql: &str) -> SwarmResult<Vec<RecordBatch>> {
     let mut client = FlightClient::connect(endpoint).await?;
     let (_schema, batches) = client.execute_query(sql).await?;
     Ok(batches)
@@ -231,11 +363,18 @@ pub async fn query_node(endpoint: &str, sql: &str) -> Result<Vec<RecordBatch>, S
 pub async fn query_node_with_schema(
     endpoint: &str,
     sql: &str,
-) -> Result<(SchemaRef, Vec<RecordBatch>), String> {
+) -> SwarmResult<(SchemaRef, Vec<RecordBatch>)> {
     let mut client = FlightClient::connect(endpoint).await?;
     client.execute_query(sql).await
 }
 
+fn node_unreachable(endpoint: &str, message: String) -> SwarmError {
+    SwarmError::NodeUnreachable {
+        endpoint: endpoint.to_string(),
+        message,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -245,8 +384,9 @@ mod tests {
         let result = FlightClient::connect("http://127.0.0.1:1").await;
         assert!(result.is_err());
         let err = result.unwrap_err();
+        assert!(err.is_retryable(), "unexpected error: {err:?}");
         assert!(
-            err.starts_with("Failed to connect to http://127.0.0.1:1:"),
+            err.to_string().starts_with("Failed to connect to http://127.0.0.1:1:"),
             "unexpected error: {err}"
         );
     }
@@ -256,19 +396,42 @@ mod tests {
         let result = FlightClient::connect("not a uri").await;
         assert!(result.is_err());
         let err = result.unwrap_err();
+        assert!(matches!(err, SwarmError::Internal(_)), "unexpected error: {err:?}");
         assert!(
-            err.contains("Failed to connect to not a uri"),
+            err.to_string().contains("Failed to connect to not a uri"),
             "unexpected error: {err}"
         );
     }
 
+    #[tokio::test]
+    async fn ping_node_times_out_on_a_silent_peer() {
+        // Accepts TCP connections but never answers the HTTP/2 handshake.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+
+        let started = Instant::now();
+        let err = ping_node(&endpoint, Duration::from_millis(200)).await.unwrap_err();
+        assert!(matches!(err, SwarmError::Timeout(_)), "unexpected error: {err:?}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn ping_node_connection_error() {
+        let err = ping_node("http://127.0.0.1:1", Duration::from_secs(5)).await.unwrap_err();
+        assert!(err.is_retryable(), "unexpected error: {err:?}");
+    }
+
     #[tokio::test]
     async fn query_node_connection_error() {
         let result = query_node("http://127.0.0.1:1", "SELECT 1").await;
         assert!(result.is_err());
         let err = result.unwrap_err();
         assert!(
-            err.contains("Failed to connect to http://127.0.0.1:1"),
+            matches!(&err, SwarmError::NodeUnreachable { endpoint, .. } if endpoint == "http://127.0.0.1:1"),
+            "unexpected error: {err:?}"
+        );
+        assert!(
+            err.to_string().contains("Failed to connect to http://127.0.0.1:1"),
             "unexpected error: {err}"
         );
     }
@@ -0,0 +1,260 @@
+//! Per-endpoint pool of Flight channels, so repeated calls to a node reuse
+//! an established HTTP/2 connection instead of paying for a new handshake.
+//!
+//! A tonic [`Channel`] multiplexes concurrent calls, so the pool only opens
+//! another connection to an endpoint when every pooled one is busy, up to
+//! [`PoolSettings::max_connections_per_endpoint`]. HTTP/2 keepalive pings
+//! find dead peers on idle connections, and callers [`evict`] a channel
+//! whose call failed at the transport level so it is never handed out again.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use tonic::transport::{Channel, Endpoint};
+
+use crate::logging::SwarmLogger;
+
+/// How long a keepalive ping may go unanswered before the connection is
+/// considered broken.
+const KEEPALIVE_TIMEOUT: Duration = Duration::from_secs(20);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolSettings {
+    pub max_connections_per_endpoint: usize,
+    /// Idle connections older than this are closed.
+    pub idle_timeout: Duration,
+    pub keepalive_interval: Duration,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections_per_endpoint: 4,
+            idle_timeout: Duration::from_secs(300),
+            keepalive_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+struct PooledChannel {
+    id: u64,
+    channel: Channel,
+    in_flight: Arc<AtomicUsize>,
+    last_used: Instant,
+}
+
+/// A channel checked out of the pool. It counts as busy until dropped.
+pub struct Lease {
+    key: String,
+    id: u64,
+    channel: Channel,
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Lease {
+    pub fn channel(&self) -> Channel {
+        self.channel.clone()
+    }
+}
+
+impl std::fmt::Debug for Lease {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lease")
+            .field("key", &self.key)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Default)]
+struct ChannelPool {
+    settings: RwLock<PoolSettings>,
+    channels: Mutex<HashMap<String, Vec<PooledChannel>>>,
+    next_id: AtomicU64,
+}
+
+fn pool() -> &'static ChannelPool {
+    static POOL: OnceLock<ChannelPool> = OnceLock::new();
+    POOL.get_or_init(ChannelPool::default)
+}
+
+fn settings() -> PoolSettings {
+    pool().settings.read().map(|s| *s).unwrap_or_default()
+}
+
+/// Replace the pool settings. Connections already open stay pooled; the
+/// new limits apply from the next checkout.
+pub fn configure(settings: PoolSettings) {
+    if let Ok(mut current) = pool().settings.write() {
+        *current = settings;
+    }
+}
+
+/// Drop idle connections past `idle_timeout`, then pick the least busy
+/// channel, or `None` when a new connection should be opened instead: there
+/// are none, or all are busy and the endpoint is below its limit.
+fn pick(channels: &mut Vec<PooledChannel>, now: Instant, settings: &PoolSettings) -> Option<usize> {
+    channels.retain(|c| {
+        c.in_flight.load(Ordering::Relaxed) > 0
+            || now.saturating_duration_since(c.last_used) < settings.idle_timeout
+    });
+    let (index, busy) = channels
+        .iter()
+        .enumerate()
+        .map(|(i, c)| (i, c.in_flight.load(Ordering::Relaxed)))
+        .min_by_key(|&(_, busy)| busy)?;
+    if busy > 0 && channels.len() < settings.max_connections_per_endpoint {
+        return None;
+    }
+    Some(index)
+}
+
+fn lease(key: &str, channel: &mut PooledChannel, now: Instant) -> Lease {
+    channel.in_flight.fetch_add(1, Ordering::Relaxed);
+    channel.last_used = now;
+    Lease {
+        key: key.to_string(),
+        id: channel.id,
+        channel: channel.channel.clone(),
+        in_flight: channel.in_flight.clone(),
+    }
+}
+
+/// A channel to `endpoint`, pooled under `key`. `key` must tell apart
+/// endpoints whose transport settings differ, e.g. TLS identities.
+pub async fn checkout(key: &str, endpoint: Endpoint) -> Result<Lease, tonic::transport::Error> {
+    let settings = settings();
+    if let Ok(mut channels) = pool().channels.lock() {
+        let entry = channels.entry(key.to_string()).or_default();
+        if let Some(index) = pick(entry, Instant::now(), &settings) {
+            crate::metrics::instance().record_flight_connection_reused();
+            return Ok(lease(key, &mut entry[index], Instant::now()));
+        }
+    }
+
+    let channel = endpoint
+        .http2_keep_alive_interval(settings.keepalive_interval)
+        .keep_alive_timeout(KEEPALIVE_TIMEOUT)
+        .keep_alive_while_idle(true)
+        .connect()
+        .await?;
+    crate::metrics::instance().record_flight_connection_opened();
+
+    let mut pooled = PooledChannel {
+        id: pool().next_id.fetch_add(1, Ordering::Relaxed),
+        channel,
+        in_flight: Arc::new(AtomicUsize::new(0)),
+        last_used: Instant::now(),
+    };
+    let leased = lease(key, &mut pooled, Instant::now());
+    // Concurrent checkouts may all have opened a connection; the ones past
+    // the limit serve their own call and close when it ends.
+    if let Ok(mut channels) = pool().channels.lock() {
+        let entry = channels.entry(key.to_string()).or_default();
+        if entry.len() < settings.max_connections_per_endpoint {
+            entry.push(pooled);
+        }
+    }
+    Ok(leased)
+}
+
+/// Remove the channel behind `lease` from the pool after a transport
+/// failure, so later calls open a fresh connection.
+pub fn evict(lease: &Lease) {
+    if let Ok(mut channels) = pool().channels.lock() {
+        if let Some(entry) = channels.get_mut(&lease.key) {
+            let before = entry.len();
+            entry.retain(|c| c.id != lease.id);
+            if entry.len() < before {
+                SwarmLogger::debug(
+                    "flight-pool",
+                    &format!("Evicted broken connection to {}", lease.key),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(id: u64, in_flight: usize, last_used: Instant) -> PooledChannel {
+        PooledChannel {
+            id,
+            channel: Endpoint::from_static("http://127.0.0.1:1").connect_lazy(),
+            in_flight: Arc::new(AtomicUsize::new(in_flight)),
+            last_used,
+        }
+    }
+
+    fn settings(max: usize) -> PoolSettings {
+        PoolSettings {
+            max_connections_per_endpoint: max,
+            ..PoolSettings::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn reuses_idle_channel_before_opening_more() {
+        let now = Instant::now();
+        let mut channels = vec![channel(1, 2, now), channel(2, 0, now)];
+        assert_eq!(pick(&mut channels, now, &settings(4)), Some(1));
+
+        let mut empty = Vec::new();
+        assert_eq!(pick(&mut empty, now, &settings(4)), None);
+    }
+
+    #[tokio::test]
+    async fn opens_new_channel_only_below_limit() {
+        let now = Instant::now();
+        let mut channels = vec![channel(1, 1, now), channel(2, 3, now)];
+        assert_eq!(pick(&mut channels, now, &settings(4)), None);
+        assert_eq!(pick(&mut channels, now, &settings(2)), Some(0));
+    }
+
+    #[tokio::test]
+    async fn drops_channels_idle_past_timeout() {
+        let now = Instant::now();
+        let old = now - Duration::from_secs(600);
+        let mut channels = vec![channel(1, 0, old), channel(2, 1, old), channel(3, 0, now)];
+        assert_eq!(pick(&mut channels, now, &settings(4)), Some(1));
+        let ids: Vec<u64> = channels.iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[tokio::test]
+    async fn lease_counts_as_busy_until_dropped() {
+        let now = Instant::now();
+        let mut pooled = channel(7, 0, now);
+        let leased = lease("http://node:1", &mut pooled, now);
+        assert_eq!(pooled.in_flight.load(Ordering::Relaxed), 1);
+        drop(leased);
+        assert_eq!(pooled.in_flight.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn evict_removes_only_the_broken_channel() {
+        let key = "http://evict-test:1";
+        let now = Instant::now();
+        let mut broken = channel(1001, 0, now);
+        let leased = lease(key, &mut broken, now);
+        pool()
+            .channels
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), vec![broken, channel(1002, 0, now)]);
+
+        evict(&leased);
+        let ids: Vec<u64> = pool().channels.lock().unwrap()[key].iter().map(|c| c.id).collect();
+        assert_eq!(ids, vec![1002]);
+    }
+}
@@ -31,11 +31,33 @@ use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
 use tonic::{Request, Response, Status, Streaming};
 use tokio::sync::oneshot;
 
-use crate::logging::SwarmLogger;
+use crate::error::SwarmError;
+use crate::flight_auth::{FlightAuth, AUTHORIZATION_HEADER};
+use crate::logging::{self, SwarmLogger};
 use crate::server_registry::ServerRegistry;
 use crate::shuffle_descriptor::ShuffleDescriptor;
 use crate::shuffle_registry;
 
+/// A DoGet ticket. With `distributed` the query runs across the cluster as
+/// `trex_db_query` runs it, through admission control; otherwise on this
+/// node's database alone, as the fragments other nodes send are.
+#[derive(Debug, PartialEq)]
+struct TicketQuery {
+    sql: String,
+    distributed: bool,
+}
+
+/// The gRPC status a client sees for a distributed query failing with `e`.
+fn distributed_query_status(e: SwarmError) -> Status {
+    let message = e.to_string();
+    match e {
+        SwarmError::AdmissionRejected(_) => Status::resource_exhausted(message),
+        SwarmError::Timeout(_) => Status::deadline_exceeded(message),
+        SwarmError::NodeUnreachable { .. } => Status::unavailable(message),
+        _ => Status::internal(message),
+    }
+}
+
 fn escape_identifier(name: &str) -> String {
     format!("\"{}\"", name.replace('"', "\"\""))
 }
@@ -45,19 +67,21 @@ fn escape_identifier(name: &str) -> String {
 pub struct DuckDBFlightService {
     host: String,
     port: u16,
+    auth: Arc<FlightAuth>,
 }
 
 impl DuckDBFlightService {
-    pub fn new(host: String, port: u16) -> Self {
+    pub fn new(host: String, port: u16, auth: FlightAuth) -> Self {
         Self {
             host,
             port,
+            auth: Arc::new(auth),
         }
     }
 
-    /// Extract the SQL query string from a JSON-encoded ticket.
-    /// Expected format: `{"query": "SELECT ..."}`
-    fn parse_ticket_query(ticket: &Ticket) -> Result<String, Status> {
+    /// Extract the SQL query from a JSON-encoded ticket.
+    /// Expected format: `{"query": "SELECT ...", "distributed": false}`
+    fn parse_ticket_query(ticket: &Ticket) -> Result<TicketQuery, Status> {
         let value: serde_json::Value = serde_json::from_slice(ticket.ticket.as_ref())
             .map_err(|e| {
                 Status::invalid_argument(format!(
@@ -66,7 +90,7 @@ impl DuckDBFlightService {
                 ))
             })?;
 
-        value
+        let sql = value
             .get("query")
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
@@ -74,12 +98,22 @@ impl DuckDBFlightService {
                 Status::invalid_argument(
                     "Ticket JSON must contain a \"query\" field with a string value",
                 )
-            })
+            })?;
+        let distributed = match value.get("distributed") {
+            None | Some(serde_json::Value::Null) => false,
+            Some(v) => v.as_bool().ok_or_else(|| {
+                Status::invalid_argument("Ticket field \"distributed\" must be a boolean")
+            })?,
+        };
+        Ok(TicketQuery { sql, distributed })
     }
 
     fn execute_query_pooled(
         sql: &str,
     ) -> Result<(arrow::datatypes::SchemaRef, Vec<arrow::array::RecordBatch>), Status> {
+        if let Err(e) = crate::duckdb_sql_executor::apply_query_threads() {
+            SwarmLogger::warn("flight", &e);
+        }
         crate::pool::read_arrow(sql).map_err(|e| {
             Status::internal(format!("Failed to execute '{}': {}", sql, e))
         })
@@ -136,27 +170,63 @@ impl FlightService for DuckDBFlightService {
     type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
     type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
 
-    /// Return the node address as a handshake acknowledgement.
+    /// Check the client's token and return the node address as a handshake
+    /// acknowledgement. The token may come in the `authorization` header or
+    /// as the first request's payload; it is echoed back in the response's
+    /// `authorization` header for clients that take it from there.
     async fn handshake(
         &self,
-        _request: Request<Streaming<HandshakeRequest>>,
+        request: Request<Streaming<HandshakeRequest>>,
     ) -> Result<Response<Self::HandshakeStream>, Status> {
         let node_id = format!("{}:{}", self.host, self.port);
         SwarmLogger::debug("handshake", &format!("Handshake request from client, node_id={node_id}"));
+
+        let mut bearer = None;
+        if self.auth.is_enabled() {
+            let header = request
+                .metadata()
+                .get(AUTHORIZATION_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            let checked = match header {
+                Some(header) => self.auth.check("handshake", &request).map(|_| header),
+                None => {
+                    let mut inbound = request.into_inner();
+                    let payload = inbound
+                        .message()
+                        .await?
+                        .map(|m| String::from_utf8_lossy(&m.payload).into_owned())
+                        .unwrap_or_default();
+                    if self.auth.accepts(payload.trim()) {
+                        Ok(format!("Bearer {}", payload.trim()))
+                    } else {
+                        SwarmLogger::warn("flight-auth", "Rejected handshake: invalid token payload");
+                        Err(Status::unauthenticated("Invalid handshake token"))
+                    }
+                }
+            };
+            bearer = Some(checked?);
+        }
+
         let response = HandshakeResponse {
             protocol_version: 1,
             payload: node_id.into_bytes().into(),
         };
 
         let output = stream::once(async { Ok(response) }).boxed();
-        Ok(Response::new(output))
+        let mut response = Response::new(output);
+        if let Some(value) = bearer.and_then(|b| b.parse().ok()) {
+            response.metadata_mut().insert(AUTHORIZATION_HEADER, value);
+        }
+        Ok(response)
     }
 
     /// Return one FlightInfo per trexsql table with schema and endpoint.
     async fn list_flights(
         &self,
-        _request: Request<Criteria>,
+        request: Request<Criteria>,
     ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        self.auth.check("ListFlights", &request)?;
         SwarmLogger::debug("list_flights", &format!("Listing tables on {}:{}", self.host, self.port));
         let host = self.host.clone();
         let port = self.port;
@@ -247,6 +317,7 @@ impl FlightService for DuckDBFlightService {
         &self,
         request: Request<FlightDescriptor>,
     ) -> Result<Response<FlightInfo>, Status> {
+        self.auth.check("GetFlightInfo", &request)?;
         let descriptor = request.into_inner();
         let sql = Self::descriptor_to_query(&descriptor)?;
         let host = self.host.clone();
@@ -286,6 +357,7 @@ impl FlightService for DuckDBFlightService {
         &self,
         request: Request<FlightDescriptor>,
     ) -> Result<Response<SchemaResult>, Status> {
+        self.auth.check("GetSchema", &request)?;
         let descriptor = request.into_inner();
         let sql = Self::descriptor_to_query(&descriptor)?;
 
@@ -321,38 +393,53 @@ impl FlightService for DuckDBFlightService {
         &self,
         request: Request<Ticket>,
     ) -> Result<Response<Self::DoGetStream>, Status> {
-        let ticket = request.into_inner();
-        let sql = Self::parse_ticket_query(&ticket)?;
-        SwarmLogger::info("do_get", &format!("Executing query on {}:{}", self.host, self.port));
-        SwarmLogger::debug("do_get", &format!("SQL: {sql}"));
-
-        let (schema, batches) =
-            tokio::task::spawn_blocking(move || Self::execute_query(&sql))
-                .await
-                .map_err(|e| Status::internal(format!("Task join error: {}", e)))??;
+        self.auth.check("DoGet", &request)?;
+        let query_id = logging::query_id_from_metadata(request.metadata());
+        logging::with_query_id(query_id.clone(), async move {
+            let ticket = request.into_inner();
+            let TicketQuery { sql, distributed } = Self::parse_ticket_query(&ticket)?;
+            let scope = if distributed { "distributed query" } else { "query" };
+            SwarmLogger::info("do_get", &format!("Executing {scope} on {}:{}", self.host, self.port));
+            SwarmLogger::debug("do_get", &format!("SQL: {sql}"));
+
+            let (schema, batches) = tokio::task::spawn_blocking(move || {
+                let _log_guard = query_id.as_deref().map(logging::enter_query);
+                if distributed {
+                    crate::run_db_query(&sql, false, false)
+                        .map(|(result, _)| (result.schema, result.batches))
+                        .map_err(distributed_query_status)
+                } else {
+                    Self::execute_query(&sql)
+                }
+            })
+            .await
+            .map_err(|e| Status::internal(format!("Task join error: {}", e)))??;
 
-        SwarmLogger::debug(
-            "do_get",
-            &format!("Query returned {} batches, {} total rows",
-                batches.len(),
-                batches.iter().map(|b| b.num_rows()).sum::<usize>()),
-        );
+            SwarmLogger::debug(
+                "do_get",
+                &format!("Query returned {} batches, {} total rows",
+                    batches.len(),
+                    batches.iter().map(|b| b.num_rows()).sum::<usize>()),
+            );
 
-        let batch_stream = stream::iter(batches.into_iter().map(Ok));
+            let batch_stream = stream::iter(batches.into_iter().map(Ok));
 
-        let flight_data_stream = FlightDataEncoderBuilder::new()
-            .with_schema(schema)
-            .build(batch_stream)
-            .map_err(|e| Status::internal(format!("Flight encoding error: {}", e)));
+            let flight_data_stream = FlightDataEncoderBuilder::new()
+                .with_schema(schema)
+                .build(batch_stream)
+                .map_err(|e| Status::internal(format!("Flight encoding error: {}", e)));
 
-        Ok(Response::new(flight_data_stream.boxed()))
+            Ok(Response::new(flight_data_stream.boxed()))
+        })
+        .await
     }
 
     /// Not supported; use SQL via DoGet or DoAction.
     async fn do_put(
         &self,
-        _request: Request<Streaming<FlightData>>,
+        request: Request<Streaming<FlightData>>,
     ) -> Result<Response<Self::DoPutStream>, Status> {
+        self.auth.check("DoPut", &request)?;
         Err(Status::unimplemented(
             "DoPut is not supported; use SQL statements via DoGet or DoAction",
         ))
@@ -363,6 +450,7 @@ impl FlightService for DuckDBFlightService {
         &self,
         request: Request<Action>,
     ) -> Result<Response<Self::DoActionStream>, Status> {
+        self.auth.check("DoAction", &request)?;
         let action = request.into_inner();
 
         match action.r#type.as_str() {
@@ -395,15 +483,112 @@ impl FlightService for DuckDBFlightService {
                 let output = stream::once(async { Ok(result) }).boxed();
                 Ok(Response::new(output))
             }
+            // Answered without touching the database, so its round trip
+            // measures the network and the Flight stack only.
+            "ping" => {
+                let result = arrow_flight::Result {
+                    body: r#"{"status":"ok"}"#.as_bytes().to_vec().into(),
+                };
+                let output = stream::once(async { Ok(result) }).boxed();
+                Ok(Response::new(output))
+            }
+            "logs" => {
+                let body: serde_json::Value =
+                    serde_json::from_slice(&action.body).map_err(|e| {
+                        Status::invalid_argument(format!("Invalid JSON action body: {}", e))
+                    })?;
+                let lines = body
+                    .get("lines")
+                    .and_then(|v| v.as_u64())
+                    .ok_or_else(|| {
+                        Status::invalid_argument("Action body must contain a \"lines\" count")
+                    })?;
+                let lines = crate::logging::recent_lines(usize::try_from(lines).unwrap_or(usize::MAX));
+                let body = serde_json::to_string(&lines)
+                    .map_err(|e| Status::internal(format!("Failed to encode log lines: {}", e)))?;
+
+                let result = arrow_flight::Result {
+                    body: body.into_bytes().into(),
+                };
+                let output = stream::once(async { Ok(result) }).boxed();
+                Ok(Response::new(output))
+            }
             "refresh_catalog" => {
-                tokio::task::spawn_blocking(|| {
+                let version = tokio::task::spawn_blocking(|| {
                     let _ = crate::catalog::advertise_local_tables();
+                    crate::catalog::stats_version()
                 })
                 .await
                 .map_err(|e| Status::internal(format!("Task join error: {}", e)))?;
 
+                // The caller waits for stats of this version before pruning
+                // on them again.
+                let body = serde_json::json!({ "status": "ok", "stats_version": version });
                 let result = arrow_flight::Result {
-                    body: r#"{"status":"ok"}"#.as_bytes().to_vec().into(),
+                    body: body.to_string().into_bytes().into(),
+                };
+                let output = stream::once(async { Ok(result) }).boxed();
+                Ok(Response::new(output))
+            }
+            "drain_routes" => {
+                let body: serde_json::Value =
+                    serde_json::from_slice(&action.body).map_err(|e| {
+                        Status::invalid_argument(format!("Invalid JSON action body: {}", e))
+                    })?;
+                let (Some(table), Some(version), Some(timeout_ms)) = (
+                    body.get("table").and_then(|v| v.as_str()).map(str::to_string),
+                    body.get("routing_version").and_then(|v| v.as_u64()),
+                    body.get("timeout_ms").and_then(|v| v.as_u64()),
+                ) else {
+                    return Err(Status::invalid_argument(
+                        "Action body must contain \"table\", \"routing_version\" and \"timeout_ms\"",
+                    ));
+                };
+
+                let drained = tokio::task::spawn_blocking(move || {
+                    crate::partition::drain_routes(
+                        &table,
+                        version,
+                        std::time::Duration::from_millis(timeout_ms),
+                    )
+                })
+                .await
+                .map_err(|e| Status::internal(format!("Task join error: {}", e)))?;
+
+                let body = serde_json::json!({ "status": "ok", "drained": drained });
+                let result = arrow_flight::Result {
+                    body: body.to_string().into_bytes().into(),
+                };
+                let output = stream::once(async { Ok(result) }).boxed();
+                Ok(Response::new(output))
+            }
+            "vacuum_table" => {
+                let body: serde_json::Value =
+                    serde_json::from_slice(&action.body).map_err(|e| {
+                        Status::invalid_argument(format!("Invalid JSON action body: {}", e))
+                    })?;
+                let table = body
+                    .get("table")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        Status::invalid_argument("Action body must contain a \"table\" field")
+                    })?
+                    .to_string();
+
+                let report = tokio::task::spawn_blocking(move || -> Result<String, Status> {
+                    let report = crate::partition::vacuum_local_table(&table)
+                        .map_err(|e| Status::internal(format!("Vacuum failed: {}", e)))?;
+                    // Row counts are unchanged, but stats are recollected
+                    // from the rewritten tables.
+                    let _ = crate::catalog::advertise_local_tables();
+                    serde_json::to_string(&report)
+                        .map_err(|e| Status::internal(format!("Failed to encode report: {}", e)))
+                })
+                .await
+                .map_err(|e| Status::internal(format!("Task join error: {}", e)))??;
+
+                let result = arrow_flight::Result {
+                    body: report.into_bytes().into(),
                 };
                 let output = stream::once(async { Ok(result) }).boxed();
                 Ok(Response::new(output))
@@ -417,12 +602,24 @@ impl FlightService for DuckDBFlightService {
 
     async fn list_actions(
         &self,
-        _request: Request<Empty>,
+        request: Request<Empty>,
     ) -> Result<Response<Self::ListActionsStream>, Status> {
-        let actions = vec![ActionType {
-            r#type: "query".to_string(),
-            description: "Execute a SQL statement (DDL/DML) against trexsql".to_string(),
-        }];
+        self.auth.check("ListActions", &request)?;
+        let actions = vec![
+            ActionType {
+                r#type: "query".to_string(),
+                description: "Execute a SQL statement (DDL/DML) against trexsql".to_string(),
+            },
+            ActionType {
+                r#type: "ping".to_string(),
+                description: "Answer immediately, for measuring round-trip latency".to_string(),
+            },
+            ActionType {
+                r#type: "logs".to_string(),
+                description: "Return the last \"lines\" log lines this node kept in memory"
+                    .to_string(),
+            },
+        ];
 
         let output = stream::iter(actions.into_iter().map(Ok)).boxed();
         Ok(Response::new(output))
@@ -438,6 +635,7 @@ impl FlightService for DuckDBFlightService {
         &self,
         request: Request<Streaming<FlightData>>,
     ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        self.auth.check("DoExchange", &request)?;
         let mut inbound = request.into_inner();
 
         let first_msg = inbound
@@ -472,46 +670,80 @@ impl FlightService for DuckDBFlightService {
                 Status::invalid_argument("Descriptor path must contain partition_id")
             })?;
 
-        SwarmLogger::debug(
-            "flight-do-exchange",
-            &format!(
-                "DoExchange: receiving shuffle '{}' partition {} from stream",
-                desc.shuffle_id, partition_id,
-            ),
-        );
+        logging::with_query_id(desc.query_id.clone(), async move {
+            SwarmLogger::debug(
+                "flight-do-exchange",
+                &format!(
+                    "DoExchange: receiving shuffle '{}' partition {} from stream",
+                    desc.shuffle_id, partition_id,
+                ),
+            );
 
-        // Reconstruct stream including the first message (may contain data)
-        let first_stream = stream::once(async { Ok(first_msg) });
-        let rest_stream = inbound.map_err(|e| arrow_flight::error::FlightError::Tonic(Box::new(e)));
-        let full_stream = first_stream
-            .map(|r| r.map_err(|e: Status| arrow_flight::error::FlightError::Tonic(Box::new(e))))
-            .chain(rest_stream);
+            // Reconstruct stream including the first message (may contain data)
+            let first_stream = stream::once(async { Ok(first_msg) });
+            let rest_stream = inbound.map_err(|e| arrow_flight::error::FlightError::Tonic(Box::new(e)));
+            let full_stream = first_stream
+                .map(|r| r.map_err(|e: Status| arrow_flight::error::FlightError::Tonic(Box::new(e))))
+                .chain(rest_stream);
+
+            let flight_stream = FlightRecordBatchStream::new_from_flight_data(full_stream);
+            futures::pin_mut!(flight_stream);
+
+            let Some(ref target_table) = desc.target_table else {
+                // Hand each batch to the registry as it is decoded. While the
+                // partition is over its buffer budget the push waits and the
+                // inbound stream is not read, which throttles the sender.
+                let mut batch_count = 0usize;
+                let mut rows = 0usize;
+                while let Some(batch) = flight_stream
+                    .try_next()
+                    .await
+                    .map_err(|e| Status::internal(format!("Failed to decode batch: {e}")))?
+                {
+                    batch_count += 1;
+                    rows += batch.num_rows();
+                    shuffle_registry::push_batches(&desc.shuffle_id, partition_id, vec![batch])
+                        .await
+                        .map_err(Status::cancelled)?;
+                }
+                shuffle_registry::finish_source(&desc.shuffle_id, partition_id);
+
+                SwarmLogger::debug(
+                    "flight-do-exchange",
+                    &format!(
+                        "DoExchange: received {} batch(es), {} row(s) for shuffle '{}' partition {}",
+                        batch_count,
+                        rows,
+                        desc.shuffle_id,
+                        partition_id,
+                    ),
+                );
 
-        let flight_stream = FlightRecordBatchStream::new_from_flight_data(full_stream);
-        futures::pin_mut!(flight_stream);
+                let ack = stream::empty().boxed();
+                return Ok(Response::new(ack));
+            };
 
-        let mut batches: Vec<RecordBatch> = Vec::new();
-        while let Some(batch) = flight_stream
-            .try_next()
-            .await
-            .map_err(|e| Status::internal(format!("Failed to decode batch: {e}")))?
-        {
-            batches.push(batch);
-        }
+            let mut batches: Vec<RecordBatch> = Vec::new();
+            while let Some(batch) = flight_stream
+                .try_next()
+                .await
+                .map_err(|e| Status::internal(format!("Failed to decode batch: {e}")))?
+            {
+                batches.push(batch);
+            }
 
-        let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
-        SwarmLogger::debug(
-            "flight-do-exchange",
-            &format!(
-                "DoExchange: received {} batch(es), {} row(s) for shuffle '{}' partition {}",
-                batches.len(),
-                rows,
-                desc.shuffle_id,
-                partition_id,
-            ),
-        );
+            let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+            SwarmLogger::debug(
+                "flight-do-exchange",
+                &format!(
+                    "DoExchange: received {} batch(es), {} row(s) for shuffle '{}' partition {}",
+                    batches.len(),
+                    rows,
+                    desc.shuffle_id,
+                    partition_id,
+                ),
+            );
 
-        if let Some(ref target_table) = desc.target_table {
             let table_name = target_table.clone();
             let batch_count = batches.len();
 
@@ -547,18 +779,18 @@ impl FlightService for DuckDBFlightService {
                     batch_count, rows, target_table,
                 ),
             );
-        } else {
-            shuffle_registry::submit_partition(&desc.shuffle_id, partition_id, batches);
-        }
 
-        let ack = stream::empty().boxed();
-        Ok(Response::new(ack))
+            let ack = stream::empty().boxed();
+            Ok(Response::new(ack))
+        })
+        .await
     }
 
     async fn poll_flight_info(
         &self,
-        _request: Request<FlightDescriptor>,
+        request: Request<FlightDescriptor>,
     ) -> Result<Response<PollInfo>, Status> {
+        self.auth.check("PollFlightInfo", &request)?;
         Err(Status::unimplemented("PollFlightInfo is not supported"))
     }
 }
@@ -595,6 +827,8 @@ pub fn start_flight_server(
 
     ensure_crypto_provider();
 
+    let auth = server_auth(&host, port);
+
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
     ServerRegistry::instance().reserve(&host, port, shutdown_tx, tls_enabled)?;
@@ -626,6 +860,7 @@ pub fn start_flight_server(
                 let service = DuckDBFlightService::new(
                     server_host.clone(),
                     server_port,
+                    auth,
                 );
 
                 let addr = format!("{}:{}", server_host, server_port)
@@ -696,6 +931,8 @@ pub fn start_flight_server_with_tls(
         .identity(identity)
         .client_ca_root(client_ca);
 
+    let auth = server_auth(&host, port);
+
     let (shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
 
     ServerRegistry::instance().reserve(&host, port, shutdown_tx, true)?;
@@ -726,6 +963,7 @@ pub fn start_flight_server_with_tls(
                 let service = DuckDBFlightService::new(
                     server_host.clone(),
                     server_port,
+                    auth,
                 );
 
                 let addr = format!("{}:{}", server_host, server_port)
@@ -776,6 +1014,20 @@ pub fn start_flight_server_with_tls(
     ))
 }
 
+/// The server's token configuration, logging whether calls need a token.
+fn server_auth(host: &str, port: u16) -> FlightAuth {
+    let auth = FlightAuth::from_env();
+    if auth.is_enabled() {
+        SwarmLogger::info("server", &format!("Flight server on {host}:{port} requires a bearer token"));
+    } else {
+        SwarmLogger::warn(
+            "server",
+            &format!("Flight server on {host}:{port} accepts unauthenticated calls"),
+        );
+    }
+    auth
+}
+
 fn validate_pem(data: &[u8], label: &str) -> Result<(), String> {
     let s = std::str::from_utf8(data)
         .map_err(|_| format!("{label} file is not valid UTF-8"))?;
@@ -794,3 +1046,44 @@ pub fn stop_flight_server(host: &str, port: u16) -> Result<String, String> {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(json: &str) -> Ticket {
+        Ticket {
+            ticket: json.as_bytes().to_vec().into(),
+        }
+    }
+
+    #[test]
+    fn tickets_run_locally_unless_distributed() {
+        let local = DuckDBFlightService::parse_ticket_query(&ticket(r#"{"query": "SELECT 1"}"#));
+        assert_eq!(
+            local.unwrap(),
+            TicketQuery { sql: "SELECT 1".into(), distributed: false }
+        );
+        let distributed = DuckDBFlightService::parse_ticket_query(&ticket(
+            r#"{"query": "SELECT 1", "distributed": true}"#,
+        ));
+        assert!(distributed.unwrap().distributed);
+
+        for bad in [
+            r#"{"distributed": true}"#,
+            r#"{"query": "SELECT 1", "distributed": "yes"}"#,
+            "SELECT 1",
+        ] {
+            let status = DuckDBFlightService::parse_ticket_query(&ticket(bad)).unwrap_err();
+            assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        }
+    }
+
+    #[test]
+    fn admission_rejections_are_resource_exhausted() {
+        let status = distributed_query_status(SwarmError::AdmissionRejected("Query queued".into()));
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+        let status = distributed_query_status(SwarmError::SqlExecution("bad".into()));
+        assert_eq!(status.code(), tonic::Code::Internal);
+    }
+}
@@ -1,18 +1,140 @@
 use chitchat::transport::UdpTransport;
-use chitchat::{spawn_chitchat, ChitchatConfig, ChitchatHandle, ChitchatId, FailureDetectorConfig};
+use chitchat::{
+    spawn_chitchat, Chitchat, ChitchatConfig, ChitchatHandle, ChitchatId, FailureDetectorConfig,
+};
+use std::collections::{HashMap, HashSet};
 use std::net::SocketAddr;
 use std::sync::{Arc, Mutex, OnceLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use crate::gossip_auth::AuthenticatedUdpTransport;
+use crate::gossip_protocol::{self, ProtocolVersion};
 use crate::logging::SwarmLogger;
+use crate::resources::{self, ResourceSampler};
+use crate::swim::{self, Liveness, ProbeAction, SwimConfig, SwimDetector};
+
+const GOSSIP_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the protocol version spoken with live peers is renegotiated.
+const NEGOTIATION_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The `status` a node started with seeds advertises until it reaches one.
+pub const JOINING_STATUS: &str = "joining";
+
+/// Wait before the first retry of the seeds; it doubles up to
+/// `JOIN_MAX_BACKOFF`.
+const JOIN_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const JOIN_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How long seeds are retried when `SWARM_JOIN_TIMEOUT_SECS` is not set.
+const DEFAULT_JOIN_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// How long a node keeps contacting its seeds before it settles for
+/// chitchat's own occasional seed gossip: `SWARM_JOIN_TIMEOUT_SECS`, or
+/// five minutes.
+pub fn join_timeout() -> Result<Duration, String> {
+    match std::env::var("SWARM_JOIN_TIMEOUT_SECS").ok().filter(|v| !v.is_empty()) {
+        None => Ok(DEFAULT_JOIN_TIMEOUT),
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Duration::from_secs)
+            .map_err(|_| format!("Invalid SWARM_JOIN_TIMEOUT_SECS '{value}', expected seconds")),
+    }
+}
+
+/// Wait after the `attempt`th (from 0) contact of the seeds.
+fn join_backoff(attempt: u32) -> Duration {
+    JOIN_INITIAL_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(JOIN_MAX_BACKOFF)
+}
+
+/// Prefix of the keys recording nodes removed with `trex_db_kill_node`:
+/// `killed:<node_id>` = node name. Every node drops the named node from its
+/// view of the cluster, and copies the marker into its own state so it
+/// outlives the node that wrote it.
+pub const KILLED_PREFIX: &str = "killed:";
+
+/// Node ids marked killed by any node, with the name each was killed under.
+fn killed_nodes(cc: &Chitchat) -> HashMap<String, String> {
+    killed_in(cc.node_states().values().map(|state| state.key_values()))
+}
+
+/// Node ids marked killed in any of the key-value sets in `states`.
+fn killed_in<'a, S>(states: impl IntoIterator<Item = S>) -> HashMap<String, String>
+where
+    S: IntoIterator<Item = (&'a str, &'a str)>,
+{
+    let mut killed = HashMap::new();
+    for state in states {
+        for (key, value) in state {
+            if let Some(node_id) = key.strip_prefix(KILLED_PREFIX) {
+                killed.insert(node_id.to_string(), value.to_string());
+            }
+        }
+    }
+    killed
+}
+
+/// Markers in `killed` that the key-values `own` do not hold yet, as the
+/// `(key, node name)` pairs to set.
+fn unadopted_kills<'a>(
+    killed: &HashMap<String, String>,
+    own: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> Vec<(String, String)> {
+    let own: HashSet<&str> = own.into_iter().map(|(key, _)| key).collect();
+    let mut missing: Vec<(String, String)> = killed
+        .iter()
+        .map(|(id, name)| (format!("{KILLED_PREFIX}{id}"), name.clone()))
+        .filter(|(key, _)| !own.contains(key.as_str()))
+        .collect();
+    missing.sort();
+    missing
+}
+
+/// Node ids to mark when killing `node_name`, given `(node_id, node_name)`
+/// of every known node. Empty when the node was already killed.
+fn plan_kill(
+    node_name: &str,
+    self_id: &str,
+    nodes: &[(String, String)],
+    killed: &HashMap<String, String>,
+) -> Result<Vec<String>, String> {
+    let matching: Vec<&String> = nodes
+        .iter()
+        .filter(|(_, name)| name == node_name)
+        .map(|(id, _)| id)
+        .collect();
+    if matching.iter().any(|id| *id == self_id) {
+        return Err(format!(
+            "Cannot kill the local node '{node_name}'; use trex_db_stop instead"
+        ));
+    }
+    let to_kill: Vec<String> = matching
+        .into_iter()
+        .filter(|id| !killed.contains_key(*id))
+        .cloned()
+        .collect();
+    if to_kill.is_empty() && !killed.values().any(|name| name == node_name) {
+        return Err(format!("Node '{node_name}' is not known to the cluster"));
+    }
+    Ok(to_kill)
+}
 
 pub struct NodeInfo {
     pub node_id: String,
     pub node_name: String,
     pub gossip_addr: String,
     pub data_node: String,
+    /// The node's advertised status (`active`, `draining`), replaced by
+    /// `suspect` or `dead` while the local failure detector doubts it.
     pub status: String,
+    /// Advertised CPU utilisation; `None` until the node has sampled it.
+    pub cpu_pct: Option<f64>,
+    /// Advertised available memory; `None` until the node has sampled it.
+    pub free_mem_mb: Option<u64>,
 }
 
 /// All key-value pairs for a node. Used by the catalog module to resolve table locations.
@@ -24,9 +146,10 @@ pub struct NodeKeyValueInfo {
 }
 
 struct GossipHandle {
-    chitchat_handle: ChitchatHandle,
+    chitchat_handle: Arc<ChitchatHandle>,
     runtime: tokio::runtime::Runtime,
     node_id: String,
+    swim: Arc<Mutex<SwimDetector>>,
 }
 
 /// Execute `future` on the gossip runtime via `Handle::spawn()` + a blocking
@@ -82,12 +205,12 @@ impl GossipRegistry {
     /// Start the gossip layer. Returns the generated UUID node-id on success.
     pub fn start(
         &self,
-        host: &str,
-        port: u16,
+        gossip_addr: SocketAddr,
         cluster_id: &str,
         node_name: &str,
         data_node: &str,
         seeds: Vec<String>,
+        secret: Option<&str>,
     ) -> Result<String, String> {
         let mut guard = self.handle.lock().map_err(|_| "Gossip lock poisoned".to_string())?;
         if guard.is_some() {
@@ -101,52 +224,81 @@ impl GossipRegistry {
             .build()
             .map_err(|e| format!("Failed to create tokio runtime: {e}"))?;
 
+        let protocol = gossip_protocol::local_version()?;
+        let join_timeout = join_timeout()?;
+        let spill_dir = crate::spill::spill_dir_from_env();
+        let cleared = crate::spill::prepare_node_spill_dir(
+            spill_dir.as_deref().unwrap_or(&std::env::temp_dir()),
+            node_name,
+        )?;
+        if cleared > 0 {
+            SwarmLogger::info(
+                "spill",
+                &format!("Removed spill files of {cleared} query(ies) left by an earlier run"),
+            );
+        }
         let node_id = Uuid::new_v4().to_string();
 
-        let gossip_addr: SocketAddr = format!("{host}:{port}")
-            .parse()
-            .map_err(|e| format!("Invalid gossip address {host}:{port}: {e}"))?;
-
         // Skip our own address -- chitchat does not need to seed itself.
-        let seed_addrs: Vec<SocketAddr> = seeds
-            .iter()
-            .filter_map(|s| {
-                let addr: SocketAddr = s.parse().ok()?;
-                if addr == gossip_addr {
-                    None
-                } else {
-                    Some(addr)
-                }
-            })
+        // Hostnames are kept as given: their seeds may not be up, or even
+        // resolvable, yet.
+        let seed_nodes: Vec<String> = seeds
+            .into_iter()
+            .filter(|s| s.parse::<SocketAddr>().ok() != Some(gossip_addr))
             .collect();
 
         let chitchat_id = ChitchatId::new(node_id.clone(), 0, gossip_addr);
 
         let config = ChitchatConfig {
             chitchat_id,
-            cluster_id: cluster_id.to_string(),
-            gossip_interval: Duration::from_millis(500),
+            cluster_id: gossip_protocol::wire_cluster_id(cluster_id, protocol.major),
+            gossip_interval: GOSSIP_INTERVAL,
             listen_addr: gossip_addr,
-            seed_nodes: seed_addrs.iter().map(|a| a.to_string()).collect(),
+            seed_nodes: seed_nodes.clone(),
             failure_detector_config: FailureDetectorConfig::default(),
             marked_for_deletion_grace_period: Duration::from_secs(60),
             catchup_callback: None,
             extra_liveness_predicate: None,
         };
 
-        let initial_kv: Vec<(String, String)> = vec![
+        let mut initial_kv: Vec<(String, String)> = vec![
             ("node_name".to_string(), node_name.to_string()),
             ("data_node".to_string(), data_node.to_string()),
-            ("status".to_string(), "active".to_string()),
+            ("status".to_string(), initial_status(&seed_nodes).to_string()),
+            (gossip_protocol::PROTOCOL_VERSION_KEY.to_string(), protocol.to_string()),
         ];
+        if let Some(dir) = &spill_dir {
+            initial_kv.push((crate::spill::SPILL_DIR_KEY.to_string(), dir.display().to_string()));
+        }
 
-        let transport = UdpTransport;
-
+        // With a cluster secret, datagrams that fail authentication are
+        // dropped, so nodes without it can't join or inject state.
         let chitchat_handle = runtime.block_on(async {
-            spawn_chitchat(config, initial_kv, &transport)
-                .await
-                .map_err(|e| format!("Failed to spawn chitchat: {e}"))
+            match secret {
+                Some(secret) => {
+                    let transport = AuthenticatedUdpTransport::new(secret);
+                    spawn_chitchat(config, initial_kv, &transport).await
+                }
+                None => spawn_chitchat(config, initial_kv, &UdpTransport).await,
+            }
+            .map_err(|e| format!("Failed to spawn chitchat: {e}"))
         })?;
+        let chitchat_handle = Arc::new(chitchat_handle);
+
+        if !seed_nodes.is_empty() {
+            runtime.spawn(run_seed_join(
+                chitchat_handle.clone(),
+                seed_nodes.clone(),
+                gossip_addr,
+                join_timeout,
+            ));
+        }
+
+        let swim = Arc::new(Mutex::new(SwimDetector::new(SwimConfig::default())));
+        runtime.spawn(run_swim_probes(chitchat_handle.chitchat(), swim.clone()));
+        runtime.spawn(run_resource_sampler(chitchat_handle.chitchat()));
+        runtime.spawn(run_protocol_negotiation(chitchat_handle.chitchat(), protocol));
+        runtime.spawn(run_kill_propagation(chitchat_handle.chitchat()));
 
         SwarmLogger::log_with_context(
             crate::logging::LogLevel::Info,
@@ -157,6 +309,9 @@ impl GossipRegistry {
                 ("name", node_name),
                 ("addr", &gossip_addr.to_string()),
                 ("cluster", cluster_id),
+                ("protocol", &protocol.to_string()),
+                ("authenticated", if secret.is_some() { "true" } else { "false" }),
+                ("seeds", &seed_nodes.len().to_string()),
             ],
             "Gossip started",
         );
@@ -165,6 +320,7 @@ impl GossipRegistry {
             chitchat_handle,
             runtime,
             node_id: node_id.clone(),
+            swim,
         });
 
         Ok(node_id)
@@ -239,7 +395,7 @@ impl GossipRegistry {
 
     /// Return the state of every node known to the gossip layer.
     pub fn get_node_states(&self) -> Result<Vec<NodeInfo>, String> {
-        let (handle, chitchat, node_id) = {
+        let (handle, chitchat, node_id, swim) = {
             let guard = self.handle.lock().map_err(|_| "Gossip lock poisoned".to_string())?;
             let gossip = guard
                 .as_ref()
@@ -248,13 +404,16 @@ impl GossipRegistry {
                 gossip.runtime.handle().clone(),
                 gossip.chitchat_handle.chitchat(),
                 gossip.node_id.clone(),
+                gossip.swim.clone(),
             )
         };
 
         let nodes = exec_on_runtime(&handle, async move {
             let cc = chitchat.lock().await;
+            let killed = killed_nodes(&cc);
             cc.node_states()
                 .iter()
+                .filter(|(id, _)| !killed.contains_key(&id.node_id))
                 .map(|(id, state)| {
                     let node_name = state
                         .get("node_name")
@@ -264,10 +423,22 @@ impl GossipRegistry {
                         .get("data_node")
                         .unwrap_or("false")
                         .to_string();
-                    let status = state
-                        .get("status")
-                        .unwrap_or("unknown")
-                        .to_string();
+                    let advertised = state.get("status").unwrap_or("unknown");
+                    let liveness = swim
+                        .lock()
+                        .map(|d| d.liveness(&id.node_id))
+                        .unwrap_or(Liveness::Alive);
+                    let status = match liveness {
+                        Liveness::Alive => advertised.to_string(),
+                        other if advertised != "draining" => other.as_str().to_string(),
+                        _ => advertised.to_string(),
+                    };
+                    let cpu_pct = state
+                        .get(resources::CPU_PCT_KEY)
+                        .and_then(|v| v.parse().ok());
+                    let free_mem_mb = state
+                        .get(resources::FREE_MEM_MB_KEY)
+                        .and_then(|v| v.parse().ok());
 
                     NodeInfo {
                         node_id: id.node_id.clone(),
@@ -275,6 +446,8 @@ impl GossipRegistry {
                         gossip_addr: id.gossip_advertise_addr.to_string(),
                         data_node,
                         status,
+                        cpu_pct,
+                        free_mem_mb,
                     }
                 })
                 .collect::<Vec<_>>()
@@ -294,6 +467,91 @@ impl GossipRegistry {
         Ok(nodes)
     }
 
+    /// Names of the nodes SWIM holds suspect or dead, whatever they
+    /// advertise. Routing, co-location and placement pass over them; empty
+    /// when gossip is not running.
+    pub fn unreachable_nodes(&self) -> HashSet<String> {
+        let (handle, chitchat, swim) = {
+            let Ok(guard) = self.handle.lock() else {
+                return HashSet::new();
+            };
+            let Some(gossip) = guard.as_ref() else {
+                return HashSet::new();
+            };
+            (
+                gossip.runtime.handle().clone(),
+                gossip.chitchat_handle.chitchat(),
+                gossip.swim.clone(),
+            )
+        };
+        let ids = swim.lock().map(|d| d.unreachable()).unwrap_or_default();
+        if ids.is_empty() {
+            return HashSet::new();
+        }
+
+        exec_on_runtime(&handle, async move {
+            let cc = chitchat.lock().await;
+            cc.node_states()
+                .iter()
+                .filter(|(id, _)| ids.contains(&id.node_id))
+                .filter_map(|(_, state)| state.get("node_name").map(str::to_string))
+                .collect()
+        })
+    }
+
+    /// Permanently remove the node named `node_name` from every node's view of
+    /// the cluster. Returns false when it had already been removed. The local
+    /// node cannot be killed.
+    pub fn kill_node(&self, node_name: &str) -> Result<bool, String> {
+        let (handle, chitchat, node_id) = {
+            let guard = self.handle.lock().map_err(|_| "Gossip lock poisoned".to_string())?;
+            let gossip = guard
+                .as_ref()
+                .ok_or_else(|| "Gossip is not running".to_string())?;
+            (
+                gossip.runtime.handle().clone(),
+                gossip.chitchat_handle.chitchat(),
+                gossip.node_id.clone(),
+            )
+        };
+
+        let name_owned = node_name.to_string();
+        let killed_ids = exec_on_runtime(&handle, async move {
+            let mut cc = chitchat.lock().await;
+            let self_id = cc.self_chitchat_id().node_id.clone();
+            let nodes: Vec<(String, String)> = cc
+                .node_states()
+                .iter()
+                .map(|(id, state)| {
+                    let name = state.get("node_name").unwrap_or("").to_string();
+                    (id.node_id.clone(), name)
+                })
+                .collect();
+            let to_kill = plan_kill(&name_owned, &self_id, &nodes, &killed_nodes(&cc))?;
+            for id in &to_kill {
+                cc.self_node_state()
+                    .set(format!("{KILLED_PREFIX}{id}"), &name_owned);
+            }
+            Ok::<_, String>(to_kill)
+        })?;
+
+        if !killed_ids.is_empty() {
+            SwarmLogger::log_with_context(
+                crate::logging::LogLevel::Warn,
+                "gossip",
+                &[
+                    ("node_id", &node_id),
+                    ("operation", "kill_node"),
+                    ("name", node_name),
+                    ("killed", &killed_ids.join(",")),
+                ],
+                &format!("Removed node '{node_name}' from the cluster"),
+            );
+        }
+
+        Ok(!killed_ids.is_empty())
+    }
+
     /// Return this node's current gossip configuration as key-value pairs.
     pub fn get_self_config(&self) -> Result<Vec<(String, String)>, String> {
         let (handle, chitchat) = {
@@ -420,8 +678,10 @@ impl GossipRegistry {
 
         let nodes = exec_on_runtime(&handle, async move {
             let cc = chitchat.lock().await;
+            let killed = killed_nodes(&cc);
             cc.node_states()
                 .iter()
+                .filter(|(id, _)| !killed.contains_key(&id.node_id))
                 .map(|(id, state)| {
                     let node_name = state
                         .get("node_name")
@@ -459,3 +719,338 @@ impl GossipRegistry {
         Ok(nodes)
     }
 }
+
+/// `joining` for a node with seeds to reach, else `active`.
+fn initial_status(seed_nodes: &[String]) -> &'static str {
+    if seed_nodes.is_empty() {
+        "active"
+    } else {
+        JOINING_STATUS
+    }
+}
+
+/// The addresses `seeds` resolve to now, without `self_addr`. Seeds that do
+/// not resolve are skipped until the next attempt.
+async fn resolve_seeds(seeds: &[String], self_addr: SocketAddr) -> HashSet<SocketAddr> {
+    let mut addrs = HashSet::new();
+    for seed in seeds {
+        match tokio::net::lookup_host(seed.as_str()).await {
+            Ok(resolved) => addrs.extend(resolved.filter(|a| *a != self_addr)),
+            Err(e) => SwarmLogger::debug("gossip", &format!("Seed {seed} does not resolve yet: {e}")),
+        }
+    }
+    addrs
+}
+
+/// Background join: gossip to every seed, backing off exponentially, until
+/// a peer is live, then advertise `active` instead of `joining`. Hostnames
+/// are resolved again on every attempt, so seeds that start after this node
+/// are found. After `timeout` the seeds are only contacted by chitchat's own
+/// gossip rounds, and the node turns `active` once one of them answers.
+async fn run_seed_join(
+    handle: Arc<ChitchatHandle>,
+    seeds: Vec<String>,
+    self_addr: SocketAddr,
+    timeout: Duration,
+) {
+    let chitchat = handle.chitchat();
+    let started = Instant::now();
+    let mut attempt: u32 = 0;
+    let mut gave_up = false;
+    loop {
+        {
+            let mut cc = chitchat.lock().await;
+            let self_id = cc.self_chitchat_id().clone();
+            if cc.live_nodes().any(|id| *id != self_id) {
+                if cc.self_node_state().get("status") == Some(JOINING_STATUS) {
+                    cc.self_node_state().set("status", "active");
+                }
+                drop(cc);
+                SwarmLogger::info(
+                    "gossip",
+                    &format!(
+                        "Joined the cluster after {:.1}s ({} seed contact attempt(s))",
+                        started.elapsed().as_secs_f64(),
+                        attempt
+                    ),
+                );
+                return;
+            }
+        }
+
+        if !gave_up && started.elapsed() >= timeout {
+            gave_up = true;
+            SwarmLogger::warn(
+                "gossip",
+                &format!(
+                    "No seed answered within {}s; running on its own until one does",
+                    timeout.as_secs()
+                ),
+            );
+        }
+        if gave_up {
+            tokio::time::sleep(JOIN_MAX_BACKOFF).await;
+            continue;
+        }
+
+        let addrs = resolve_seeds(&seeds, self_addr).await;
+        for addr in &addrs {
+            if let Err(e) = handle.gossip(*addr) {
+                SwarmLogger::debug("gossip", &format!("Failed to contact seed {addr}: {e}"));
+            }
+        }
+        let wait = join_backoff(attempt);
+        SwarmLogger::debug(
+            "gossip",
+            &format!(
+                "Contacted {} of {} seed(s); checking again in {}ms",
+                addrs.len(),
+                seeds.len(),
+                wait.as_millis()
+            ),
+        );
+        attempt += 1;
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Background resource sampler: every `SAMPLE_INTERVAL`, publish this node's
+/// CPU utilisation and available memory and mirror them into the metrics
+/// gauges. Values the platform does not report are left unset.
+async fn run_resource_sampler(chitchat: Arc<tokio::sync::Mutex<Chitchat>>) {
+    let mut sampler = ResourceSampler::new();
+    // Prime the CPU counters so the first published value covers an interval.
+    sampler.sample();
+    loop {
+        tokio::time::sleep(resources::SAMPLE_INTERVAL).await;
+
+        let sample = sampler.sample();
+        crate::metrics::instance().set_node_resources(sample);
+
+        let mut cc = chitchat.lock().await;
+        let self_state = cc.self_node_state();
+        if let Some(cpu_pct) = sample.cpu_pct {
+            self_state.set(resources::CPU_PCT_KEY, format!("{cpu_pct:.1}"));
+        }
+        if let Some(free_mem_mb) = sample.free_mem_mb {
+            self_state.set(resources::FREE_MEM_MB_KEY, free_mem_mb.to_string());
+        }
+    }
+}
+
+/// Background negotiation, every `NEGOTIATION_INTERVAL`: settle on the
+/// protocol version every live peer speaks and log when it changes.
+async fn run_protocol_negotiation(
+    chitchat: Arc<tokio::sync::Mutex<Chitchat>>,
+    local: ProtocolVersion,
+) {
+    loop {
+        let peers: Vec<(String, Option<String>)> = {
+            let cc = chitchat.lock().await;
+            let self_id = cc.self_chitchat_id().node_id.clone();
+            let live: HashSet<String> = cc.live_nodes().map(|id| id.node_id.clone()).collect();
+            cc.node_states()
+                .iter()
+                .filter(|(id, _)| id.node_id != self_id && live.contains(&id.node_id))
+                .map(|(id, state)| {
+                    let name = state.get("node_name").unwrap_or(&id.node_id).to_string();
+                    let version = state
+                        .get(gossip_protocol::PROTOCOL_VERSION_KEY)
+                        .map(str::to_string);
+                    (name, version)
+                })
+                .collect()
+        };
+        gossip_protocol::record(local, gossip_protocol::negotiate(local, &peers));
+
+        tokio::time::sleep(NEGOTIATION_INTERVAL).await;
+    }
+}
+
+/// Background copy, once per gossip interval, of every kill marker another
+/// node holds into this node's state. A node's key-values are collected
+/// some time after it leaves, so without the copy a killed node would
+/// reappear once the node that killed it is gone.
+async fn run_kill_propagation(chitchat: Arc<tokio::sync::Mutex<Chitchat>>) {
+    loop {
+        tokio::time::sleep(GOSSIP_INTERVAL).await;
+
+        let mut cc = chitchat.lock().await;
+        let killed = killed_nodes(&cc);
+        let self_state = cc.self_node_state();
+        for (key, name) in unadopted_kills(&killed, self_state.key_values()) {
+            self_state.set(key, name);
+        }
+    }
+}
+
+/// Background SWIM round, once per gossip interval: answer probe requests
+/// addressed to this node, then update suspicion for every peer and publish
+/// or withdraw this node's own probe requests.
+async fn run_swim_probes(
+    chitchat: Arc<tokio::sync::Mutex<Chitchat>>,
+    detector: Arc<Mutex<SwimDetector>>,
+) {
+    loop {
+        tokio::time::sleep(GOSSIP_INTERVAL).await;
+
+        let mut cc = chitchat.lock().await;
+        let self_id = cc.self_chitchat_id().node_id.clone();
+        let live: HashSet<String> = cc.live_nodes().map(|id| id.node_id.clone()).collect();
+
+        let mut known: Vec<String> = Vec::new();
+        let mut to_ack: HashSet<String> = HashSet::new();
+        let mut acks: HashMap<String, HashSet<String>> = HashMap::new();
+        for (id, state) in cc.node_states() {
+            if id.node_id == self_id {
+                continue;
+            }
+            known.push(id.node_id.clone());
+            if !live.contains(&id.node_id) {
+                continue;
+            }
+            for (key, value) in state.key_values() {
+                if let Some(suspect) = key.strip_prefix(swim::PROBE_REQUEST_PREFIX) {
+                    if swim::is_helper(value, &self_id) && live.contains(suspect) {
+                        to_ack.insert(suspect.to_string());
+                    }
+                } else if let Some(suspect) = key.strip_prefix(swim::PROBE_ACK_PREFIX) {
+                    acks.entry(suspect.to_string())
+                        .or_default()
+                        .insert(id.node_id.clone());
+                }
+            }
+        }
+
+        let candidates: Vec<String> = live.iter().filter(|n| **n != self_id).cloned().collect();
+        let now = Instant::now();
+        let actions: Vec<(String, ProbeAction)> = match detector.lock() {
+            Ok(mut d) => {
+                let actions = known
+                    .iter()
+                    .map(|node| {
+                        let acked = acks
+                            .get(node)
+                            .is_some_and(|from| d.helpers(node).iter().any(|h| from.contains(h)));
+                        let seed = Uuid::new_v4().as_u128() as u64;
+                        let action =
+                            d.observe(now, node, live.contains(node), acked, &candidates, seed);
+                        (node.clone(), action)
+                    })
+                    .collect();
+                d.retain(|id| known.iter().any(|k| k == id));
+                actions
+            }
+            Err(_) => continue,
+        };
+
+        let self_state = cc.self_node_state();
+        let current_acks: Vec<String> = self_state
+            .key_values()
+            .filter_map(|(k, _)| k.strip_prefix(swim::PROBE_ACK_PREFIX).map(str::to_string))
+            .collect();
+        for suspect in &current_acks {
+            if !to_ack.contains(suspect) {
+                self_state.delete(&format!("{}{}", swim::PROBE_ACK_PREFIX, suspect));
+            }
+        }
+        for suspect in &to_ack {
+            if !current_acks.contains(suspect) {
+                self_state.set(format!("{}{}", swim::PROBE_ACK_PREFIX, suspect), "1");
+            }
+        }
+
+        for (node, action) in actions {
+            let key = format!("{}{}", swim::PROBE_REQUEST_PREFIX, node);
+            match action {
+                ProbeAction::Request(helpers) => {
+                    SwarmLogger::debug(
+                        "gossip",
+                        &format!("Node {} is suspect; asking {} peer(s) to probe", node, helpers.len()),
+                    );
+                    self_state.set(&key, swim::encode_helpers(&helpers));
+                }
+                ProbeAction::Withdraw => self_state.delete(&key),
+                ProbeAction::None => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nodes() -> Vec<(String, String)> {
+        vec![
+            ("id-a".to_string(), "node-a".to_string()),
+            ("id-b".to_string(), "node-b".to_string()),
+        ]
+    }
+
+    #[test]
+    fn join_backoff_doubles_up_to_the_cap() {
+        assert_eq!(join_backoff(0), Duration::from_millis(500));
+        assert_eq!(join_backoff(1), Duration::from_secs(1));
+        assert_eq!(join_backoff(3), Duration::from_secs(4));
+        assert_eq!(join_backoff(6), JOIN_MAX_BACKOFF);
+        assert_eq!(join_backoff(u32::MAX), JOIN_MAX_BACKOFF);
+    }
+
+    #[test]
+    fn nodes_with_seeds_start_joining() {
+        assert_eq!(initial_status(&[]), "active");
+        assert_eq!(initial_status(&["seed-0:7946".to_string()]), JOINING_STATUS);
+    }
+
+    #[tokio::test]
+    async fn unresolvable_seeds_are_skipped() {
+        let self_addr: SocketAddr = "127.0.0.1:7946".parse().unwrap();
+        let seeds = vec![
+            "127.0.0.1:7946".to_string(),
+            "127.0.0.1:7947".to_string(),
+            "no-such-port".to_string(),
+        ];
+        let addrs = resolve_seeds(&seeds, self_addr).await;
+        assert_eq!(addrs, HashSet::from(["127.0.0.1:7947".parse().unwrap()]));
+    }
+
+    #[test]
+    fn kill_marks_matching_node() {
+        let to_kill = plan_kill("node-b", "id-a", &nodes(), &HashMap::new()).unwrap();
+        assert_eq!(to_kill, vec!["id-b".to_string()]);
+    }
+
+    #[test]
+    fn kill_is_idempotent() {
+        let killed = HashMap::from([("id-b".to_string(), "node-b".to_string())]);
+        assert!(plan_kill("node-b", "id-a", &nodes(), &killed).unwrap().is_empty());
+        // Still idempotent once the dead node's state has been collected.
+        assert!(plan_kill("node-b", "id-a", &nodes()[..1], &killed).unwrap().is_empty());
+    }
+
+    #[test]
+    fn kill_markers_outlive_the_killing_node() {
+        let own = vec![("node_name", "node-a")];
+        let killer = vec![("node_name", "node-c"), ("killed:id-b", "node-b")];
+        let killed = killed_in([own.clone(), killer]);
+        let adopted = unadopted_kills(&killed, own.clone());
+        assert_eq!(adopted, vec![("killed:id-b".to_string(), "node-b".to_string())]);
+
+        // The killer's state is gone; the copy still filters node-b out.
+        let mut own = own;
+        own.extend(adopted.iter().map(|(key, name)| (key.as_str(), name.as_str())));
+        let killed = killed_in([own.clone()]);
+        assert_eq!(killed.get("id-b").map(String::as_str), Some("node-b"));
+        assert!(plan_kill("node-b", "id-a", &nodes(), &killed).unwrap().is_empty());
+        assert!(unadopted_kills(&killed, own).is_empty());
+    }
+
+    #[test]
+    fn kill_refuses_local_and_unknown_nodes() {
+        let err = plan_kill("node-a", "id-a", &nodes(), &HashMap::new()).unwrap_err();
+        assert!(err.contains("trex_db_stop"));
+        let err = plan_kill("node-z", "id-a", &nodes(), &HashMap::new()).unwrap_err();
+        assert!(err.contains("not known"));
+    }
+}
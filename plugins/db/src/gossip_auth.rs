@@ -0,0 +1,512 @@
+//! HMAC authentication of gossip datagrams for clusters with a shared
+//! secret.
+//!
+//! Every datagram carries an HMAC-SHA256 tag over its contents, keyed with
+//! the cluster secret. Datagrams whose tag does not verify, including the
+//! plain chitchat messages of nodes started without the secret, are dropped
+//! before chitchat sees them. Nodes without a secret keep using chitchat's
+//! plain UDP transport.
+//!
+//! chitchat fills datagrams up to the UDP payload limit, so a message that
+//! no longer fits once tagged is sent as two halves, each tagged on its own,
+//! and put back together by the receiver.
+//!
+//! A valid datagram captured off the wire would still verify when sent
+//! again, so every message also carries the id of the socket that sent it,
+//! a counter, and its send time. Messages sent more than
+//! [`MAX_CLOCK_SKEW_MS`] from the receiver's clock are dropped, as are
+//! counters already seen from the same sender.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use chitchat::transport::{Socket, Transport};
+use chitchat::{ChitchatMessage, Deserializable, Serializable};
+use ring::hmac;
+
+use crate::logging::SwarmLogger;
+
+/// Largest UDP payload over IPv4; the same bound chitchat sizes messages to.
+const MAX_DATAGRAM: usize = 65_507;
+const TAG_LEN: usize = 32;
+
+const WHOLE: u8 = 1;
+const FIRST_HALF: u8 = 2;
+const SECOND_HALF: u8 = 3;
+/// Kind byte, then the sender id, the counter and the send time, which
+/// both halves of a message share.
+const HEADER_LEN: usize = 1 + 8 + 8 + 8;
+
+/// Halves waiting for their other half; the oldest are dropped first.
+const MAX_PENDING_HALVES: usize = 16;
+
+/// How far a message's send time may be from the receiver's clock. Node
+/// clocks must agree to within this.
+pub const MAX_CLOCK_SKEW_MS: u64 = 30_000;
+/// Counters this far below a sender's highest are too old to tell apart
+/// from replays and are dropped.
+const REPLAY_WINDOW: u64 = 64;
+
+pub fn key(secret: &str) -> hmac::Key {
+    hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes())
+}
+
+fn tagged(key: &hmac::Key, mut datagram: Vec<u8>) -> Vec<u8> {
+    let tag = hmac::sign(key, &datagram);
+    datagram.extend_from_slice(tag.as_ref());
+    datagram
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// What identifies a message: the sending socket, its counter there, and
+/// when it was sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Header {
+    sender: u64,
+    counter: u64,
+    sent_at_ms: u64,
+}
+
+impl Header {
+    fn write(&self, kind: u8, datagram: &mut Vec<u8>) {
+        datagram.push(kind);
+        datagram.extend_from_slice(&self.sender.to_be_bytes());
+        datagram.extend_from_slice(&self.counter.to_be_bytes());
+        datagram.extend_from_slice(&self.sent_at_ms.to_be_bytes());
+    }
+
+    fn read(body: &[u8]) -> Self {
+        let field = |at: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&body[at..at + 8]);
+            u64::from_be_bytes(bytes)
+        };
+        Self {
+            sender: field(1),
+            counter: field(9),
+            sent_at_ms: field(17),
+        }
+    }
+}
+
+/// The datagrams that carry `payload`: one when it fits with its tag, two
+/// halves sharing `header` otherwise.
+fn seal(key: &hmac::Key, header: Header, payload: &[u8]) -> Vec<Vec<u8>> {
+    if HEADER_LEN + payload.len() + TAG_LEN <= MAX_DATAGRAM {
+        let mut datagram = Vec::with_capacity(HEADER_LEN + payload.len() + TAG_LEN);
+        header.write(WHOLE, &mut datagram);
+        datagram.extend_from_slice(payload);
+        return vec![tagged(key, datagram)];
+    }
+    let (first, second) = payload.split_at(payload.len() / 2);
+    [(FIRST_HALF, first), (SECOND_HALF, second)]
+        .into_iter()
+        .map(|(kind, chunk)| {
+            let mut datagram = Vec::with_capacity(HEADER_LEN + chunk.len() + TAG_LEN);
+            header.write(kind, &mut datagram);
+            datagram.extend_from_slice(chunk);
+            tagged(key, datagram)
+        })
+        .collect()
+}
+
+struct PendingHalf {
+    header: Header,
+    kind: u8,
+    chunk: Vec<u8>,
+}
+
+/// The counters seen from one sender: the highest, and a bit for each of
+/// the [`REPLAY_WINDOW`] below it.
+struct SeenCounters {
+    highest: u64,
+    below: u64,
+    last_seen_ms: u64,
+}
+
+impl SeenCounters {
+    fn is_new(&self, counter: u64) -> bool {
+        counter > self.highest
+            || (self.highest - counter < REPLAY_WINDOW
+                && self.below & (1 << (self.highest - counter)) == 0)
+    }
+
+    fn record(&mut self, counter: u64, now_ms: u64) {
+        if counter > self.highest {
+            let shift = counter - self.highest;
+            self.below = if shift < REPLAY_WINDOW {
+                self.below << shift
+            } else {
+                0
+            };
+            self.highest = counter;
+        }
+        self.below |= 1 << (self.highest - counter);
+        self.last_seen_ms = now_ms;
+    }
+}
+
+/// Verifies incoming datagrams, drops replays and joins halves back into
+/// messages.
+struct Opener {
+    key: hmac::Key,
+    pending: VecDeque<PendingHalf>,
+    seen: HashMap<u64, SeenCounters>,
+}
+
+impl Opener {
+    fn new(key: hmac::Key) -> Self {
+        Self {
+            key,
+            pending: VecDeque::new(),
+            seen: HashMap::new(),
+        }
+    }
+
+    fn is_new(&self, header: &Header) -> bool {
+        self.seen
+            .get(&header.sender)
+            .is_none_or(|seen| seen.is_new(header.counter))
+    }
+
+    /// Note a completed message. A sender unheard of for twice the skew
+    /// allowance is forgotten: anything it sent before then is stale.
+    fn record(&mut self, header: &Header, now_ms: u64) {
+        if !self.seen.contains_key(&header.sender) {
+            let horizon = now_ms.saturating_sub(2 * MAX_CLOCK_SKEW_MS);
+            self.seen.retain(|_, seen| seen.last_seen_ms >= horizon);
+        }
+        self.seen
+            .entry(header.sender)
+            .or_insert(SeenCounters {
+                highest: header.counter,
+                below: 0,
+                last_seen_ms: now_ms,
+            })
+            .record(header.counter, now_ms);
+    }
+
+    /// The message payload completed by `datagram`, if any. Datagrams that
+    /// fail authentication, are stale, or replay a message already opened
+    /// return `Err`.
+    fn open(&mut self, datagram: &[u8], now_ms: u64) -> Result<Option<Vec<u8>>, ()> {
+        if datagram.len() < HEADER_LEN + TAG_LEN {
+            return Err(());
+        }
+        let (body, tag) = datagram.split_at(datagram.len() - TAG_LEN);
+        hmac::verify(&self.key, body, tag).map_err(|_| ())?;
+
+        let header = Header::read(body);
+        if header.sent_at_ms.abs_diff(now_ms) > MAX_CLOCK_SKEW_MS || !self.is_new(&header) {
+            return Err(());
+        }
+        let chunk = &body[HEADER_LEN..];
+        let payload = match body[0] {
+            WHOLE => chunk.to_vec(),
+            kind @ (FIRST_HALF | SECOND_HALF) => {
+                let other = self
+                    .pending
+                    .iter()
+                    .position(|p| p.header == header && p.kind != kind);
+                match other.and_then(|i| self.pending.remove(i)) {
+                    Some(other) => {
+                        let (first, second) = if kind == FIRST_HALF {
+                            (chunk, other.chunk.as_slice())
+                        } else {
+                            (other.chunk.as_slice(), chunk)
+                        };
+                        [first, second].concat()
+                    }
+                    None => {
+                        if self.pending.len() >= MAX_PENDING_HALVES {
+                            self.pending.pop_front();
+                        }
+                        self.pending.push_back(PendingHalf {
+                            header,
+                            kind,
+                            chunk: chunk.to_vec(),
+                        });
+                        return Ok(None);
+                    }
+                }
+            }
+            _ => return Err(()),
+        };
+        self.record(&header, now_ms);
+        Ok(Some(payload))
+    }
+}
+
+/// chitchat transport that tags outgoing and verifies incoming datagrams.
+pub struct AuthenticatedUdpTransport {
+    key: hmac::Key,
+}
+
+impl AuthenticatedUdpTransport {
+    pub fn new(secret: &str) -> Self {
+        Self { key: key(secret) }
+    }
+}
+
+#[async_trait]
+impl Transport for AuthenticatedUdpTransport {
+    async fn open(&self, listen_addr: SocketAddr) -> anyhow::Result<Box<dyn Socket>> {
+        Ok(Box::new(
+            AuthenticatedUdpSocket::bind(listen_addr, self.key.clone()).await?,
+        ))
+    }
+}
+
+/// Sender id of a new socket, so counters restart from zero after a
+/// restart without looking like replays.
+fn rand_sender_id() -> u64 {
+    let bytes = uuid::Uuid::new_v4().into_bytes();
+    u64::from_be_bytes(bytes[..8].try_into().unwrap_or_default())
+}
+
+struct AuthenticatedUdpSocket {
+    socket: tokio::net::UdpSocket,
+    key: hmac::Key,
+    opener: Opener,
+    sender: u64,
+    next_counter: u64,
+    buf_recv: Box<[u8]>,
+}
+
+impl AuthenticatedUdpSocket {
+    async fn bind(listen_addr: SocketAddr, key: hmac::Key) -> anyhow::Result<Self> {
+        let socket = tokio::net::UdpSocket::bind(listen_addr)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to bind to {listen_addr}/UDP for gossip: {e}"))?;
+        Ok(Self {
+            socket,
+            opener: Opener::new(key.clone()),
+            key,
+            sender: rand_sender_id(),
+            next_counter: 0,
+            buf_recv: vec![0u8; MAX_DATAGRAM].into_boxed_slice(),
+        })
+    }
+}
+
+#[async_trait]
+impl Socket for AuthenticatedUdpSocket {
+    async fn send(&mut self, to: SocketAddr, msg: ChitchatMessage) -> anyhow::Result<()> {
+        let payload = msg.serialize_to_vec();
+        let header = Header {
+            sender: self.sender,
+            counter: self.next_counter,
+            sent_at_ms: now_ms(),
+        };
+        self.next_counter += 1;
+        for datagram in seal(&self.key, header, &payload) {
+            self.socket
+                .send_to(&datagram, to)
+                .await
+                .map_err(|e| anyhow::anyhow!("failed to send gossip message to {to}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    async fn recv(&mut self) -> anyhow::Result<(SocketAddr, ChitchatMessage)> {
+        loop {
+            let (len, from) = match self.socket.recv_from(&mut self.buf_recv).await {
+                Ok(received) => received,
+                // Sends to a stopped peer surface here on some platforms.
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionRefused
+                    ) =>
+                {
+                    continue
+                }
+                Err(e) => return Err(anyhow::anyhow!("fatal gossip recv error: {e}")),
+            };
+            let payload = match self.opener.open(&self.buf_recv[..len], now_ms()) {
+                Ok(Some(payload)) => payload,
+                Ok(None) => continue,
+                Err(()) => {
+                    SwarmLogger::debug(
+                        "gossip",
+                        &format!("Dropped unauthenticated or replayed gossip datagram from {from}"),
+                    );
+                    continue;
+                }
+            };
+            match ChitchatMessage::deserialize(&mut payload.as_slice()) {
+                Ok(msg) => return Ok((from, msg)),
+                Err(e) => SwarmLogger::debug(
+                    "gossip",
+                    &format!("Dropped invalid gossip message from {from}: {e}"),
+                ),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000_000;
+
+    fn header(sender: u64, counter: u64) -> Header {
+        Header {
+            sender,
+            counter,
+            sent_at_ms: NOW,
+        }
+    }
+
+    #[test]
+    fn small_messages_round_trip_in_one_datagram() {
+        let datagrams = seal(&key("s3cret"), header(1, 7), b"hello");
+        assert_eq!(datagrams.len(), 1);
+        let mut opener = Opener::new(key("s3cret"));
+        assert_eq!(opener.open(&datagrams[0], NOW), Ok(Some(b"hello".to_vec())));
+    }
+
+    #[test]
+    fn wrong_secret_and_tampering_are_rejected() {
+        let datagrams = seal(&key("s3cret"), header(1, 7), b"hello");
+        assert_eq!(Opener::new(key("other")).open(&datagrams[0], NOW), Err(()));
+
+        let mut tampered = datagrams[0].clone();
+        tampered[HEADER_LEN] ^= 1;
+        assert_eq!(Opener::new(key("s3cret")).open(&tampered, NOW), Err(()));
+        // A plaintext chitchat message has no tag to verify.
+        let plain = ChitchatMessage::BadCluster.serialize_to_vec();
+        assert_eq!(Opener::new(key("s3cret")).open(&plain, NOW), Err(()));
+    }
+
+    #[test]
+    fn full_size_messages_are_split_and_joined() {
+        let payload: Vec<u8> = (0..MAX_DATAGRAM).map(|i| (i % 251) as u8).collect();
+        let datagrams = seal(&key("s3cret"), header(1, 42), &payload);
+        assert_eq!(datagrams.len(), 2);
+        assert!(datagrams.iter().all(|d| d.len() <= MAX_DATAGRAM));
+        let other = seal(&key("s3cret"), header(2, 42), &payload);
+
+        // Halves may arrive in either order, interleaved with other senders.
+        let mut opener = Opener::new(key("s3cret"));
+        assert_eq!(opener.open(&datagrams[1], NOW), Ok(None));
+        assert_eq!(opener.open(&other[0], NOW), Ok(None));
+        assert_eq!(opener.open(&datagrams[0], NOW), Ok(Some(payload)));
+    }
+
+    #[test]
+    fn replayed_messages_are_rejected() {
+        let key = key("s3cret");
+        let mut opener = Opener::new(key.clone());
+        let first = seal(&key, header(1, 5), b"a");
+        assert_eq!(opener.open(&first[0], NOW), Ok(Some(b"a".to_vec())));
+        assert_eq!(opener.open(&first[0], NOW + 1), Err(()));
+
+        // Counters may arrive out of order, but each opens once.
+        let later = seal(&key, header(1, 9), b"b");
+        let earlier = seal(&key, header(1, 7), b"c");
+        assert_eq!(opener.open(&later[0], NOW), Ok(Some(b"b".to_vec())));
+        assert_eq!(opener.open(&earlier[0], NOW), Ok(Some(b"c".to_vec())));
+        assert_eq!(opener.open(&earlier[0], NOW), Err(()));
+        assert_eq!(opener.open(&first[0], NOW), Err(()));
+
+        // Another socket counts on its own.
+        let restarted = seal(&key, header(2, 5), b"d");
+        assert_eq!(opener.open(&restarted[0], NOW), Ok(Some(b"d".to_vec())));
+
+        // Too far behind the highest counter to know whether it was seen.
+        let ahead = seal(&key, header(1, 9 + REPLAY_WINDOW), b"e");
+        assert_eq!(opener.open(&ahead[0], NOW), Ok(Some(b"e".to_vec())));
+        let behind = seal(&key, header(1, 8), b"f");
+        assert_eq!(opener.open(&behind[0], NOW), Err(()));
+    }
+
+    #[test]
+    fn replayed_halves_are_rejected() {
+        let key = key("s3cret");
+        let payload: Vec<u8> = (0..MAX_DATAGRAM).map(|i| (i % 251) as u8).collect();
+        let datagrams = seal(&key, header(1, 3), &payload);
+        let mut opener = Opener::new(key);
+        assert_eq!(opener.open(&datagrams[0], NOW), Ok(None));
+        assert_eq!(opener.open(&datagrams[1], NOW), Ok(Some(payload)));
+        assert_eq!(opener.open(&datagrams[0], NOW), Err(()));
+        assert_eq!(opener.open(&datagrams[1], NOW), Err(()));
+    }
+
+    #[test]
+    fn messages_outside_the_clock_window_are_rejected() {
+        let key = key("s3cret");
+        let mut opener = Opener::new(key.clone());
+        let sent = seal(&key, header(1, 1), b"a");
+        assert_eq!(opener.open(&sent[0], NOW + MAX_CLOCK_SKEW_MS + 1), Err(()));
+        assert_eq!(opener.open(&sent[0], NOW - MAX_CLOCK_SKEW_MS - 1), Err(()));
+        assert_eq!(
+            opener.open(&sent[0], NOW + MAX_CLOCK_SKEW_MS),
+            Ok(Some(b"a".to_vec()))
+        );
+    }
+
+    #[test]
+    fn quiet_senders_are_forgotten() {
+        let key = key("s3cret");
+        let mut opener = Opener::new(key.clone());
+        opener
+            .open(&seal(&key, header(1, 1), b"a")[0], NOW)
+            .unwrap();
+        let later = NOW + 2 * MAX_CLOCK_SKEW_MS + 1;
+        let mut fresh = header(2, 1);
+        fresh.sent_at_ms = later;
+        opener.open(&seal(&key, fresh, b"b")[0], later).unwrap();
+        assert_eq!(opener.seen.keys().collect::<Vec<_>>(), vec![&2]);
+    }
+
+    #[tokio::test]
+    async fn sockets_with_the_same_secret_exchange_messages() {
+        let any_port: SocketAddr = ([127, 0, 0, 1], 0).into();
+        let mut sender = AuthenticatedUdpSocket::bind(any_port, key("s3cret"))
+            .await
+            .unwrap();
+        let mut receiver = AuthenticatedUdpSocket::bind(any_port, key("s3cret"))
+            .await
+            .unwrap();
+        let a = sender.socket.local_addr().unwrap();
+        let b = receiver.socket.local_addr().unwrap();
+
+        // A datagram from a node without the secret is dropped.
+        let plain = tokio::net::UdpSocket::bind(any_port).await.unwrap();
+        plain
+            .send_to(&ChitchatMessage::BadCluster.serialize_to_vec(), b)
+            .await
+            .unwrap();
+        sender.send(b, ChitchatMessage::BadCluster).await.unwrap();
+
+        let (from, msg) = receiver.recv().await.unwrap();
+        assert_eq!(from, a);
+        assert_eq!(msg, ChitchatMessage::BadCluster);
+    }
+
+    #[tokio::test]
+    async fn sockets_bind_ipv6_addresses() {
+        let any_port: SocketAddr = "[::1]:0".parse().unwrap();
+        let Ok(mut sender) = AuthenticatedUdpSocket::bind(any_port, key("s3cret")).await else {
+            // No IPv6 loopback in this environment.
+            return;
+        };
+        let mut receiver = AuthenticatedUdpSocket::bind(any_port, key("s3cret"))
+            .await
+            .unwrap();
+        let b = receiver.socket.local_addr().unwrap();
+        sender.send(b, ChitchatMessage::BadCluster).await.unwrap();
+        let (from, _) = receiver.recv().await.unwrap();
+        assert_eq!(from, sender.socket.local_addr().unwrap());
+    }
+}
@@ -0,0 +1,240 @@
+//! Gossip protocol version and its negotiation across the cluster.
+//!
+//! Every node gossips the highest version it speaks under
+//! `protocol_version`, as `major.minor`; nodes that predate the key speak
+//! 1.0. Within a major version the cluster speaks the lowest minor version
+//! any live node advertises, the highest they all support, and nodes warn
+//! while that is below their own. New gossip formats introduced in a minor
+//! version are only used once [`negotiated_version`] reaches it.
+//!
+//! Nodes of different major versions never join each other: from major 2
+//! on, the major version is part of chitchat's cluster id, so peers reject
+//! each other's messages as coming from another cluster instead of merging
+//! state they can't read.
+
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::logging::SwarmLogger;
+
+pub const PROTOCOL_VERSION_KEY: &str = "protocol_version";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ProtocolVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+/// The highest version this build speaks.
+pub const CURRENT: ProtocolVersion = ProtocolVersion { major: 1, minor: 0 };
+
+/// The version of nodes that gossip no `protocol_version`.
+const UNVERSIONED: ProtocolVersion = ProtocolVersion { major: 1, minor: 0 };
+
+impl ProtocolVersion {
+    /// `major.minor`, or a bare `major` meaning minor 0.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (major, minor) = value.split_once('.').unwrap_or((value, "0"));
+        Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for ProtocolVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// The version this node advertises: [`CURRENT`], or the lower minor
+/// version set in `SWARM_GOSSIP_PROTOCOL` to hold a cluster back during a
+/// rolling upgrade.
+pub fn local_version() -> Result<ProtocolVersion, String> {
+    let Some(value) = std::env::var("SWARM_GOSSIP_PROTOCOL").ok().filter(|v| !v.is_empty()) else {
+        return Ok(CURRENT);
+    };
+    let version = ProtocolVersion::parse(&value)
+        .ok_or_else(|| format!("Invalid SWARM_GOSSIP_PROTOCOL '{value}', expected major.minor"))?;
+    if version.major != CURRENT.major || version > CURRENT {
+        return Err(format!(
+            "SWARM_GOSSIP_PROTOCOL {version} is not supported; this node speaks {}.0 to {CURRENT}",
+            CURRENT.major
+        ));
+    }
+    Ok(version)
+}
+
+/// chitchat cluster id for `cluster_id` at `major`. Major 1 keeps the plain
+/// id that nodes predating versioning use.
+pub fn wire_cluster_id(cluster_id: &str, major: u32) -> String {
+    if major <= 1 {
+        cluster_id.to_string()
+    } else {
+        format!("{cluster_id}@v{major}")
+    }
+}
+
+/// What this node speaks with its live peers, given each peer's name and
+/// advertised `protocol_version`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Negotiation {
+    pub version: ProtocolVersion,
+    /// Peers below the local version, which hold the cluster down to theirs.
+    pub down_level: Vec<(String, ProtocolVersion)>,
+    /// Peers of another major version, or with an unreadable version. They
+    /// are not counted.
+    pub incompatible: Vec<(String, String)>,
+}
+
+pub fn negotiate(local: ProtocolVersion, peers: &[(String, Option<String>)]) -> Negotiation {
+    let mut negotiation = Negotiation {
+        version: local,
+        down_level: Vec::new(),
+        incompatible: Vec::new(),
+    };
+    for (name, advertised) in peers {
+        let version = match advertised {
+            None => Some(UNVERSIONED),
+            Some(value) => ProtocolVersion::parse(value),
+        };
+        match version {
+            Some(version) if version.major == local.major => {
+                if version < local {
+                    negotiation.down_level.push((name.clone(), version));
+                }
+                negotiation.version = negotiation.version.min(version);
+            }
+            _ => negotiation.incompatible.push((
+                name.clone(),
+                advertised.clone().unwrap_or_default(),
+            )),
+        }
+    }
+    negotiation.down_level.sort();
+    negotiation.incompatible.sort();
+    negotiation
+}
+
+static NEGOTIATED: Mutex<Option<Negotiation>> = Mutex::new(None);
+
+/// The version the cluster currently speaks; [`CURRENT`] before the first
+/// negotiation.
+pub fn negotiated_version() -> ProtocolVersion {
+    NEGOTIATED
+        .lock()
+        .ok()
+        .and_then(|n| n.as_ref().map(|n| n.version))
+        .unwrap_or(CURRENT)
+}
+
+/// Record `negotiation`, logging when it differs from the previous one.
+pub fn record(local: ProtocolVersion, negotiation: Negotiation) {
+    let Ok(mut slot) = NEGOTIATED.lock() else {
+        return;
+    };
+    if slot.as_ref() == Some(&negotiation) {
+        return;
+    }
+
+    if !negotiation.down_level.is_empty() {
+        let peers: Vec<String> = negotiation
+            .down_level
+            .iter()
+            .map(|(name, version)| format!("'{name}' ({version})"))
+            .collect();
+        SwarmLogger::warn(
+            "gossip",
+            &format!(
+                "Speaking gossip protocol {} instead of {local}: down-level peer(s) {}",
+                negotiation.version,
+                peers.join(", ")
+            ),
+        );
+    } else if slot.as_ref().is_some_and(|previous| previous.version < negotiation.version) {
+        SwarmLogger::info(
+            "gossip",
+            &format!("Speaking gossip protocol {} with every peer", negotiation.version),
+        );
+    }
+    if !negotiation.incompatible.is_empty() {
+        let peers: Vec<String> = negotiation
+            .incompatible
+            .iter()
+            .map(|(name, version)| format!("'{name}' ({version})"))
+            .collect();
+        SwarmLogger::warn(
+            "gossip",
+            &format!(
+                "Ignoring peer(s) with an incompatible gossip protocol: {}",
+                peers.join(", ")
+            ),
+        );
+    }
+    *slot = Some(negotiation);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(major: u32, minor: u32) -> ProtocolVersion {
+        ProtocolVersion { major, minor }
+    }
+
+    fn peer(name: &str, version: Option<&str>) -> (String, Option<String>) {
+        (name.to_string(), version.map(str::to_string))
+    }
+
+    #[test]
+    fn parses_major_and_minor() {
+        assert_eq!(ProtocolVersion::parse("1.3"), Some(v(1, 3)));
+        assert_eq!(ProtocolVersion::parse(" 2 "), Some(v(2, 0)));
+        assert_eq!(ProtocolVersion::parse("1.x"), None);
+        assert_eq!(ProtocolVersion::parse(""), None);
+        assert_eq!(v(1, 10).to_string(), "1.10");
+        assert!(v(1, 2) < v(1, 10));
+    }
+
+    #[test]
+    fn negotiates_the_lowest_minor_of_the_same_major() {
+        let peers = vec![
+            peer("node-b", Some("1.1")),
+            peer("node-c", Some("1.3")),
+            peer("node-d", None),
+        ];
+        let negotiation = negotiate(v(1, 2), &peers);
+        assert_eq!(negotiation.version, v(1, 0));
+        assert_eq!(
+            negotiation.down_level,
+            vec![("node-b".to_string(), v(1, 1)), ("node-d".to_string(), v(1, 0))]
+        );
+        assert!(negotiation.incompatible.is_empty());
+
+        let up_to_date = negotiate(v(1, 2), &[peer("node-c", Some("1.3"))]);
+        assert_eq!(up_to_date.version, v(1, 2));
+        assert!(up_to_date.down_level.is_empty());
+    }
+
+    #[test]
+    fn other_majors_are_not_negotiated_with() {
+        let peers = vec![peer("node-b", Some("2.0")), peer("node-c", Some("garbage"))];
+        let negotiation = negotiate(v(1, 1), &peers);
+        assert_eq!(negotiation.version, v(1, 1));
+        assert_eq!(
+            negotiation.incompatible,
+            vec![
+                ("node-b".to_string(), "2.0".to_string()),
+                ("node-c".to_string(), "garbage".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn major_versions_after_the_first_get_their_own_cluster_id() {
+        assert_eq!(wire_cluster_id("prod", 1), "prod");
+        assert_eq!(wire_cluster_id("prod", 2), "prod@v2");
+    }
+}
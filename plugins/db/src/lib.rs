@@ -5,11 +5,20 @@ extern crate libduckdb_sys;
 pub mod local_connections;
 pub mod logging;
 pub mod config;
+pub mod error;
 pub mod gossip;
+pub mod gossip_auth;
+pub mod gossip_protocol;
+pub mod flight_auth;
+pub mod swim;
+pub mod resources;
 pub mod catalog;
 pub mod flight_client;
+pub mod flight_pool;
 pub mod aggregation;
+pub mod benchmark;
 pub mod coordinator;
+pub mod file_scan;
 pub mod duckdb_final_pass;
 pub mod duckdb_sql_executor;
 pub mod duckdb_table_provider;
@@ -21,6 +30,8 @@ pub mod orchestrator;
 pub mod service_functions;
 pub mod admission;
 pub mod metrics;
+pub mod query_profile;
+pub mod partial_results;
 pub mod shuffle_descriptor;
 pub mod shuffle_partition;
 pub mod shuffle_registry;
@@ -28,15 +39,22 @@ pub mod shuffle_transport;
 pub mod shuffle_writer;
 pub mod shuffle_reader;
 pub mod shuffle_optimizer;
+pub mod spill;
 pub mod flight_server;
 pub mod flight_functions;
 pub mod server_registry;
 pub mod partition;
+pub mod node_logs;
+pub mod ping;
 pub mod pool;
+pub mod postgres_federation;
 
 use duckdb::{
     core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
-    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab, arrow::WritableVector},
+    vtab::{
+        BindInfo, InitInfo, TableFunctionInfo, VTab,
+        arrow::{record_batch_to_duckdb_data_chunk, to_duckdb_logical_type, WritableVector},
+    },
     vscalar::{VScalar, ScalarFunctionSignature},
     Connection, Result,
 };
@@ -45,6 +63,7 @@ use std::{
     error::Error,
     ffi::CString,
     sync::{atomic::{AtomicBool, Ordering}, Mutex},
+    time::Instant,
 };
 
 use gossip::GossipRegistry;
@@ -91,16 +110,18 @@ impl VScalar for DbStartScalar {
         let cluster_id = duckdb::types::DuckString::new(&mut { cluster_slice[0] })
             .as_str()
             .to_string();
+        config::validate_cluster_id(&cluster_id)?;
+        let gossip_addr = config::resolve_gossip_addr(&host, port)?;
 
         let node_name = format!("node-{}:{}", host, port);
 
         let response = match GossipRegistry::instance().start(
-            &host,
-            port,
+            gossip_addr,
             &cluster_id,
             &node_name,
             "true",
             vec![],
+            config::cluster_secret_from_env().as_deref(),
         ) {
             Ok(_node_id) => format!(
                 "Swarm started on {}:{} (cluster: {})",
@@ -167,23 +188,26 @@ impl VScalar for DbStartWithSeedsScalar {
         let seeds_str = duckdb::types::DuckString::new(&mut { seeds_slice[0] })
             .as_str()
             .to_string();
+        config::validate_cluster_id(&cluster_id)?;
+        let gossip_addr = config::resolve_gossip_addr(&host, port)?;
 
         let seeds: Vec<String> = seeds_str
             .split(',')
-            .map(|s| s.trim().to_string())
+            .map(str::trim)
             .filter(|s| !s.is_empty())
-            .collect();
+            .map(|s| config::validate_seed_addr(s).map(|()| s.to_string()))
+            .collect::<Result<_, _>>()?;
 
         let seed_count = seeds.len();
         let node_name = format!("node-{}:{}", host, port);
 
         let response = match GossipRegistry::instance().start(
-            &host,
-            port,
+            gossip_addr,
             &cluster_id,
             &node_name,
             "true",
             seeds,
+            config::cluster_secret_from_env().as_deref(),
         ) {
             Ok(_node_id) => format!(
                 "Swarm started on {}:{} (cluster: {}, seeds: {})",
@@ -271,6 +295,16 @@ impl VScalar for DbSetScalar {
             .as_str()
             .to_string();
 
+        let (value, warning) = match config::validate_config_value(&key, &value) {
+            Ok(Some(canonical)) => (canonical, None),
+            Ok(None) => (value, Some(format!("; warning: '{}' is not a known config key", key))),
+            Err(err) => {
+                let flat_vector = output.flat_vector();
+                flat_vector.insert(0, &format!("Error: {}", err));
+                return Ok(());
+            }
+        };
+
         let response = match GossipRegistry::instance().set_key(&key, &value) {
             Ok(()) => {
                 if key == "data_node" {
@@ -282,7 +316,17 @@ impl VScalar for DbSetScalar {
                         let _ = catalog::remove_catalog_keys();
                     }
                 }
-                format!("Set {} = {} (propagating to cluster)", key, value)
+                if key == duckdb_sql_executor::QUERY_THREADS_KEY {
+                    if let Err(e) = duckdb_sql_executor::apply_query_threads() {
+                        logging::SwarmLogger::warn("executor", &e);
+                    }
+                }
+                format!(
+                    "Set {} = {} (propagating to cluster){}",
+                    key,
+                    value,
+                    warning.unwrap_or_default()
+                )
             }
             Err(err) => format!("Error: {}", err),
         };
@@ -326,6 +370,8 @@ impl VTab for DbNodesTable {
         );
         bind.add_result_column("data_node", LogicalTypeHandle::from(LogicalTypeId::Varchar));
         bind.add_result_column("status", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("cpu_pct", LogicalTypeHandle::from(LogicalTypeId::Double));
+        bind.add_result_column("free_mem_mb", LogicalTypeHandle::from(LogicalTypeId::Bigint));
         Ok(DbNodesBindData {})
     }
 
@@ -365,6 +411,8 @@ impl VTab for DbNodesTable {
         let gossip_addr_vec = output.flat_vector(2);
         let data_node_vec = output.flat_vector(3);
         let status_vec = output.flat_vector(4);
+        let mut cpu_vec = output.flat_vector(5);
+        let mut free_mem_vec = output.flat_vector(6);
 
         for (i, node) in nodes.iter().enumerate() {
             node_id_vec.insert(i, CString::new(node.node_id.clone())?);
@@ -372,6 +420,14 @@ impl VTab for DbNodesTable {
             gossip_addr_vec.insert(i, CString::new(node.gossip_addr.clone())?);
             data_node_vec.insert(i, CString::new(node.data_node.clone())?);
             status_vec.insert(i, CString::new(node.status.clone())?);
+            match node.cpu_pct {
+                Some(cpu_pct) => cpu_vec.as_mut_slice::<f64>()[i] = cpu_pct,
+                None => cpu_vec.set_null(i),
+            }
+            match node.free_mem_mb {
+                Some(free_mem_mb) => free_mem_vec.as_mut_slice::<i64>()[i] = free_mem_mb as i64,
+                None => free_mem_vec.set_null(i),
+            }
         }
 
         output.set_len(chunk_size);
@@ -451,6 +507,84 @@ impl VTab for DbConfigTable {
     }
 }
 
+struct DbClusterConfigTable;
+
+#[repr(C)]
+struct DbClusterConfigBindData {}
+
+#[repr(C)]
+struct DbClusterConfigInitData {
+    rows: Vec<config::ClusterConfigRow>,
+    cursor: Mutex<usize>,
+}
+
+impl VTab for DbClusterConfigTable {
+    type InitData = DbClusterConfigInitData;
+    type BindData = DbClusterConfigBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("key", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("value", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("consistent", LogicalTypeHandle::from(LogicalTypeId::Boolean));
+        Ok(DbClusterConfigBindData {})
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        let nodes: Vec<(String, Vec<(String, String)>)> = GossipRegistry::instance()
+            .get_node_key_values()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|node| {
+                let name = if node.node_name.is_empty() { node.node_id } else { node.node_name };
+                (name, node.key_values)
+            })
+            .collect();
+        Ok(DbClusterConfigInitData {
+            rows: config::compare_node_configs(&nodes),
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let mut cursor = init_data
+            .cursor
+            .lock()
+            .map_err(|_| "cluster config cursor lock poisoned")?;
+
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(init_data.rows.len());
+        if start >= end {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let node_vec = output.flat_vector(0);
+        let key_vec = output.flat_vector(1);
+        let value_vec = output.flat_vector(2);
+        let mut consistent_vec = output.flat_vector(3);
+
+        for (i, row) in init_data.rows[start..end].iter().enumerate() {
+            node_vec.insert(i, CString::new(row.node_name.clone())?);
+            key_vec.insert(i, CString::new(row.key.clone())?);
+            value_vec.insert(i, CString::new(row.value.clone())?);
+            consistent_vec.as_mut_slice::<bool>()[i] = row.consistent;
+        }
+
+        *cursor = end;
+        output.set_len(end - start);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        None
+    }
+}
+
 struct DbTablesTable;
 
 #[repr(C)]
@@ -479,6 +613,7 @@ impl VTab for DbTablesTable {
             "schema_hash",
             LogicalTypeHandle::from(LogicalTypeId::Varchar),
         );
+        bind.add_result_column("source", LogicalTypeHandle::from(LogicalTypeId::Varchar));
         Ok(DbTablesBindData {})
     }
 
@@ -499,33 +634,54 @@ impl VTab for DbTablesTable {
             return Ok(());
         }
 
-        let entries = match catalog::get_all_tables() {
-            Ok(entries) => entries,
-            Err(_) => {
-                output.set_len(0);
-                return Ok(());
+        let mut rows: Vec<(String, String, String, String, String)> = catalog::get_all_tables()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.node_name,
+                    entry.table_name,
+                    entry.approx_rows.to_string(),
+                    format!("0x{:X}", entry.schema_hash),
+                    "native".to_string(),
+                )
+            })
+            .collect();
+
+        // Federated sources live on this node only and carry no row estimate.
+        let local_node = catalog::get_self_node_id().unwrap_or_else(|| "local".to_string());
+        for source in postgres_federation::sources() {
+            for (schema, table) in &source.tables {
+                rows.push((
+                    local_node.clone(),
+                    format!("{}.{}.{}", source.name, schema, table),
+                    String::new(),
+                    String::new(),
+                    format!("postgres:{}", source.name),
+                ));
             }
-        };
+        }
 
-        if entries.is_empty() {
+        if rows.is_empty() {
             output.set_len(0);
             return Ok(());
         }
 
-        let chunk_size = entries.len();
+        let chunk_size = rows.len();
         let node_name_vec = output.flat_vector(0);
         let table_name_vec = output.flat_vector(1);
         let approx_rows_vec = output.flat_vector(2);
         let schema_hash_vec = output.flat_vector(3);
-
-        for (i, entry) in entries.iter().enumerate() {
-            node_name_vec.insert(i, CString::new(entry.node_name.clone())?);
-            table_name_vec.insert(i, CString::new(entry.table_name.clone())?);
-            approx_rows_vec.insert(i, CString::new(entry.approx_rows.to_string())?);
-            schema_hash_vec.insert(
-                i,
-                CString::new(format!("0x{:X}", entry.schema_hash))?,
-            );
+        let source_vec = output.flat_vector(4);
+
+        for (i, (node_name, table_name, approx_rows, schema_hash, source)) in
+            rows.into_iter().enumerate()
+        {
+            node_name_vec.insert(i, CString::new(node_name)?);
+            table_name_vec.insert(i, CString::new(table_name)?);
+            approx_rows_vec.insert(i, CString::new(approx_rows)?);
+            schema_hash_vec.insert(i, CString::new(schema_hash)?);
+            source_vec.insert(i, CString::new(source)?);
         }
 
         output.set_len(chunk_size);
@@ -607,7 +763,294 @@ impl VScalar for DbSetDistributedScalar {
     }
 }
 
-struct DbQueryTable;
+/// Run `sql` across the cluster, through admission control and DataFusion
+/// when distributed mode is on, otherwise through the legacy coordinator.
+fn execute_db_query(sql: &str, partial: bool) -> error::SwarmResult<coordinator::QueryResult> {
+    if let Some(insert) = partition::parse_insert_select(sql) {
+        if let Some(metadata) = partition::get_partition_metadata(&insert.table_name)
+            .ok()
+            .flatten()
+        {
+            return run_db_insert(&insert, &metadata);
+        }
+    }
+    run_db_query(sql, false, partial).map(|(result, _)| result)
+}
+
+/// `INSERT INTO <partitioned table> <query>`: run the query across the
+/// cluster, then route its rows to the table's partitions. Returns the row
+/// count in a `Count` column, as DuckDB does for an insert.
+fn run_db_insert(
+    insert: &partition::InsertSelect,
+    metadata: &partition::PartitionMetadata,
+) -> error::SwarmResult<coordinator::QueryResult> {
+    use arrow::array::Int64Array;
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    // A partial result would insert some rows and silently lose others.
+    let (result, _) = run_db_query(&insert.source_sql, false, false)?;
+    let rows = partition::swarm_insert_impl(insert, metadata, &result.schema, &result.batches)
+        .map_err(error::SwarmError::SqlExecution)?;
+
+    let schema = std::sync::Arc::new(Schema::new(vec![Field::new("Count", DataType::Int64, false)]));
+    let batch = arrow::array::RecordBatch::try_new(
+        schema.clone(),
+        vec![std::sync::Arc::new(Int64Array::from(vec![rows as i64]))],
+    )
+    .map_err(|e| error::SwarmError::Internal(format!("Failed to build insert result: {e}")))?;
+    Ok(coordinator::QueryResult {
+        schema,
+        batches: vec![batch],
+    })
+}
+
+/// `trex_db_ctas`: run `sql` as `trex_db_query` does and create the
+/// distributed table `table_name` from its result.
+fn run_db_ctas(table_name: &str, sql: &str, config_json: &str) -> error::SwarmResult<String> {
+    let (result, _) = run_db_query(sql, false, false)?;
+    partition::swarm_create_table_as_impl(table_name, &result.schema, &result.batches, config_json)
+        .map_err(error::SwarmError::SqlExecution)
+}
+
+/// Pass `sql` through admission control, returning its admission query id.
+/// Queries that are rejected or would have to queue are turned away.
+fn admit_query(sql: &str) -> error::SwarmResult<Option<String>> {
+    let priority = admission::get_session_priority();
+    let (status, qid) = admission::submit_or_check(sql, "default", priority)
+        .map_err(|e| error::SwarmError::Internal(format!("Admission error: {}", e)))?;
+    match status {
+        admission::QueryStatus::Rejected(reason) => {
+            // Rejected queries are not enqueued, but cancel defensively
+            // in case future admission logic admits-then-rejects on a
+            // post-admission policy check. cancel_query is a no-op if
+            // the qid is not tracked.
+            let _ = admission::cancel_query(&qid);
+            Err(error::SwarmError::AdmissionRejected(format!(
+                "Query rejected: {}",
+                reason
+            )))
+        }
+        admission::QueryStatus::Queued { position } => {
+            // The caller bails out without ever transitioning this qid
+            // to Running/Completed, so without an explicit cancel the
+            // queue would accumulate orphaned entries.
+            let _ = admission::cancel_query(&qid);
+            Err(error::SwarmError::AdmissionRejected(format!(
+                "Query queued at position {}",
+                position
+            )))
+        }
+        _ => Ok(Some(qid)),
+    }
+}
+
+/// Warn about a query reading tables whose copies have diverging schemas,
+/// or reject it when this node has `schema_conflict_action` set to
+/// `refuse`. Without gossip there is no catalog and nothing to check.
+fn check_schema_conflicts(sql: &str) -> error::SwarmResult<()> {
+    let tables = distributed_scheduler::extract_table_names_from_sql(sql);
+    if tables.is_empty() {
+        return Ok(());
+    }
+    let conflicts: Vec<catalog::SchemaConflict> = catalog::schema_conflicts()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| tables.iter().any(|t| t.eq_ignore_ascii_case(&c.table_name)))
+        .collect();
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    let message = catalog::describe_schema_conflicts(&conflicts);
+    let refuse = GossipRegistry::instance()
+        .get_self_config()
+        .ok()
+        .and_then(|config| {
+            config
+                .into_iter()
+                .find(|(k, _)| k == catalog::SCHEMA_CONFLICT_ACTION_KEY)
+        })
+        .is_some_and(|(_, v)| v == "refuse");
+    if refuse {
+        return Err(error::SwarmError::SqlExecution(format!("Query refused: {message}")));
+    }
+    logging::SwarmLogger::warn("db", &message);
+    Ok(())
+}
+
+/// Run `sql` as `trex_db_query` does and return its query id with the
+/// result. With `profile`, the query's fragments are collected under that id,
+/// followed by a `query` fragment timing the whole query on this node. With
+/// `partial`, partitions that can't be read are skipped with a warning
+/// instead of failing the query, and the result ends with a
+/// `_missing_partitions` column counting them.
+pub(crate) fn run_db_query(
+    sql: &str,
+    profile: bool,
+    partial: bool,
+) -> error::SwarmResult<(coordinator::QueryResult, String)> {
+    check_schema_conflicts(sql)?;
+
+    // Capture the flag once to avoid TOCTOU between check and query submission.
+    let distributed = is_distributed_enabled();
+
+    let admission_query_id = if distributed { admit_query(sql)? } else { None };
+
+    // Everything logged for this query from here on, on this node and on the
+    // nodes it reaches, carries the same query id.
+    let query_id = admission_query_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let _log_guard = logging::enter_query(&query_id);
+    if profile {
+        query_profile::begin(&query_id);
+    }
+    let started = Instant::now();
+    let partial_query = partial.then(|| partial_results::begin(&query_id));
+    let partial_warning = || {
+        let missing = partial_query.as_ref()?.missing();
+        (!missing.is_empty()).then(|| partial_results::warning(&missing))
+    };
+
+    let file_scan = file_scan::plan(sql);
+    let result = if distributed {
+        let query_result = match &file_scan {
+            Some(plan) => coordinator::execute_file_scan(plan, partial)
+                .map(|result| (result.schema, result.batches)),
+            None => distributed_scheduler::submit_query(sql),
+        };
+        // Complete admission tracking regardless of query outcome.
+        if let Some(qid) = &admission_query_id {
+            let outcome = match &query_result {
+                Ok((_, batches)) => {
+                    let rows = batches.iter().map(|b| b.num_rows() as u64).sum();
+                    match partial_warning() {
+                        Some(warning) => admission::QueryOutcome::Partial { rows, warning },
+                        None => admission::QueryOutcome::Completed { rows },
+                    }
+                }
+                Err(e) => admission::QueryOutcome::Failed(e.to_string()),
+            };
+            let _ = admission::finish(qid, outcome);
+        }
+        let (schema, batches) = query_result
+            .map_err(|e| e.map_message(|m| format!("Distributed query error: {m}")))?;
+        coordinator::QueryResult { schema, batches }
+    } else {
+        match &file_scan {
+            Some(plan) => coordinator::execute_file_scan(plan, partial),
+            None => coordinator::execute_distributed_query(sql, partial),
+        }
+        .map_err(|e| e.map_message(|m| format!("Distributed query error: {m}")))?
+    };
+
+    if let Some(warning) = partial_warning() {
+        logging::SwarmLogger::warn("db", &warning);
+    }
+    let result = match &partial_query {
+        Some(query) => {
+            let (schema, batches) = partial_results::append_missing_column(
+                &result.schema,
+                &result.batches,
+                query.missing().len(),
+            )
+            .map_err(|e| {
+                error::SwarmError::Internal(format!("Failed to build partial result: {e}"))
+            })?;
+            coordinator::QueryResult { schema, batches }
+        }
+        None => result,
+    };
+
+    if profile {
+        query_profile::record_fragment(
+            Some(&query_id),
+            query_profile::FragmentProfile {
+                fragment: "query".to_string(),
+                node_name: query_profile::local_node_name(),
+                rows: result.batches.iter().map(|b| b.num_rows() as u64).sum(),
+                bytes_shuffled: 0,
+                wall_ms: started.elapsed().as_secs_f64() * 1000.0,
+            },
+        );
+    }
+    Ok((result, query_id))
+}
+
+/// Run `sql` through admission as `trex_db_query` does, but write its result
+/// to `output_path` instead of returning it.
+fn run_db_export(sql: &str, output_path: &str, format: &str) -> error::SwarmResult<String> {
+    let format = distributed_scheduler::ExportFormat::parse(format).ok_or_else(|| {
+        error::SwarmError::Internal(format!(
+            "Unknown export format '{}', expected 'parquet' or 'csv'",
+            format
+        ))
+    })?;
+    // Exports stream out of the DataFusion plan, which the legacy
+    // coordinator does not have.
+    if !is_distributed_enabled() {
+        return Err(error::SwarmError::Internal(
+            "trex_db_export requires distributed mode (trex_db_set_distributed(true))".to_string(),
+        ));
+    }
+
+    let admission_query_id = admit_query(sql)?;
+    let query_id = admission_query_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let _log_guard = logging::enter_query(&query_id);
+
+    let result = distributed_scheduler::export_query(sql, output_path, format);
+    if let Some(qid) = &admission_query_id {
+        let outcome = match &result {
+            Ok(summary) => admission::QueryOutcome::Completed { rows: summary.rows },
+            Err(e) => admission::QueryOutcome::Failed(e.to_string()),
+        };
+        let _ = admission::finish(qid, outcome);
+    }
+    let summary =
+        result.map_err(|e| e.map_message(|m| format!("Distributed export error: {m}")))?;
+    Ok(format!(
+        "Exported {} row(s) to {} file(s) at {}",
+        summary.rows, summary.files, output_path
+    ))
+}
+
+/// Run `sql` through admission as `trex_db_query` does and return the plan
+/// it ran with each operator's measured rows and time instead of its rows.
+fn run_db_explain_analyze(
+    sql: &str,
+) -> error::SwarmResult<Vec<distributed_scheduler::OperatorActuals>> {
+    // The actuals come from the DataFusion plan, which the legacy
+    // coordinator does not have.
+    if !is_distributed_enabled() {
+        return Err(error::SwarmError::Internal(
+            "trex_db_explain_analyze requires distributed mode (trex_db_set_distributed(true))"
+                .to_string(),
+        ));
+    }
+
+    let admission_query_id = admit_query(sql)?;
+    let query_id = admission_query_id
+        .clone()
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let _log_guard = logging::enter_query(&query_id);
+
+    let result = distributed_scheduler::explain_analyze_query(sql);
+    if let Some(qid) = &admission_query_id {
+        let outcome = match &result {
+            Ok(operators) => admission::QueryOutcome::Completed {
+                rows: operators.first().and_then(|root| root.rows).unwrap_or(0),
+            },
+            Err(e) => admission::QueryOutcome::Failed(e.to_string()),
+        };
+        let _ = admission::finish(qid, outcome);
+    }
+    result.map_err(|e| e.map_message(|m| format!("Distributed query error: {m}")))
+}
+
+/// `trex_db_query`, or `trex_db_query_partial` when `PARTIAL` is set.
+struct DbQueryTable<const PARTIAL: bool>;
 
 #[repr(C)]
 struct DbQueryBindData {
@@ -621,56 +1064,13 @@ struct DbQueryInitData {
     done: AtomicBool,
 }
 
-impl VTab for DbQueryTable {
+impl<const PARTIAL: bool> VTab for DbQueryTable<PARTIAL> {
     type InitData = DbQueryInitData;
     type BindData = DbQueryBindData;
 
     fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
         let sql = bind.get_parameter(0).to_string();
-
-        // Capture the flag once to avoid TOCTOU between check and query submission.
-        let distributed = is_distributed_enabled();
-
-        let mut admission_query_id: Option<String> = None;
-        if distributed {
-            let priority = admission::get_session_priority();
-            let (status, qid) = admission::submit_or_check(&sql, "default", priority)
-                .map_err(|e| format!("Admission error: {}", e))?;
-            match status {
-                admission::QueryStatus::Rejected(reason) => {
-                    // Rejected queries are not enqueued, but cancel defensively
-                    // in case future admission logic admits-then-rejects on a
-                    // post-admission policy check. cancel_query is a no-op if
-                    // the qid is not tracked.
-                    let _ = admission::cancel_query(&qid);
-                    return Err(format!("Query rejected: {}", reason).into());
-                }
-                admission::QueryStatus::Queued { position } => {
-                    // The caller bails out without ever transitioning this qid
-                    // to Running/Completed, so without an explicit cancel the
-                    // queue would accumulate orphaned entries.
-                    let _ = admission::cancel_query(&qid);
-                    return Err(format!("Query queued at position {}", position).into());
-                }
-                _ => {
-                    admission_query_id = Some(qid);
-                }
-            }
-        }
-
-        let result = if distributed {
-            let query_result = distributed_scheduler::submit_query(&sql);
-            // Complete admission tracking regardless of query outcome.
-            if let Some(qid) = &admission_query_id {
-                let _ = admission::complete(qid);
-            }
-            let (schema, batches) = query_result
-                .map_err(|e| format!("Distributed query error: {e}"))?;
-            coordinator::QueryResult { schema, batches }
-        } else {
-            coordinator::execute_distributed_query(&sql, false)
-                .map_err(|e| format!("Distributed query error: {e}"))?
-        };
+        let result = execute_db_query(&sql, PARTIAL)?;
 
         // No final pass for the DataFusion path — DataFusion handles the
         // full SQL (ORDER BY, LIMIT, aggregation, joins, etc.) natively.
@@ -685,7 +1085,7 @@ impl VTab for DbQueryTable {
 
         Ok(DbQueryBindData {
             sql,
-            partial_results: false,
+            partial_results: PARTIAL,
             cached_result: Mutex::new(Some(result)),
         })
     }
@@ -727,9 +1127,7 @@ impl VTab for DbQueryTable {
         for batch in &result.batches {
             for row in 0..batch.num_rows() {
                 for col in 0..num_cols {
-                    let col_array = batch.column(col);
-                    let value = arrow::util::display::array_value_to_string(col_array, row)
-                        .unwrap_or_default();
+                    let value = coordinator::render_value(batch.column(col), row);
                     let vec = output.flat_vector(col);
                     vec.insert(row_count, CString::new(value)?);
                 }
@@ -746,6 +1144,133 @@ impl VTab for DbQueryTable {
     }
 }
 
+/// `trex_db_query` with native column types instead of all-VARCHAR, so the
+/// result can feed arithmetic and aggregates directly. Arrow types DuckDB
+/// has no equivalent for are still returned as VARCHAR.
+struct DbQueryTypedTable;
+
+/// Rows per output chunk; DuckDB's STANDARD_VECTOR_SIZE.
+const BATCH_SIZE: usize = 2048;
+
+#[repr(C)]
+struct DbQueryTypedBindData {
+    result: coordinator::QueryResult,
+    /// Per column: true when it is stringified instead of copied natively.
+    as_varchar: Vec<bool>,
+}
+
+#[repr(C)]
+struct DbQueryTypedInitData {
+    /// Next (batch index, row offset) to emit.
+    cursor: Mutex<(usize, usize)>,
+}
+
+/// Replace the flagged columns of `batch` with their string rendering,
+/// keeping SQL NULLs as nulls.
+fn stringify_columns(
+    batch: arrow::array::RecordBatch,
+    as_varchar: &[bool],
+) -> Result<arrow::array::RecordBatch, Box<dyn std::error::Error>> {
+    use arrow::array::{Array, ArrayRef, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    if !as_varchar.iter().any(|v| *v) {
+        return Ok(batch);
+    }
+    let mut fields = Vec::with_capacity(batch.num_columns());
+    let mut columns: Vec<ArrayRef> = Vec::with_capacity(batch.num_columns());
+    for (idx, (field, col)) in batch.schema().fields().iter().zip(batch.columns()).enumerate() {
+        if as_varchar[idx] {
+            let strings: StringArray = (0..col.len())
+                .map(|row| {
+                    if col.is_null(row) {
+                        Ok(None)
+                    } else {
+                        arrow::util::display::array_value_to_string(col, row).map(Some)
+                    }
+                })
+                .collect::<Result<_, _>>()?;
+            fields.push(Field::new(field.name(), DataType::Utf8, true));
+            columns.push(std::sync::Arc::new(strings));
+        } else {
+            fields.push(field.as_ref().clone());
+            columns.push(col.clone());
+        }
+    }
+    Ok(arrow::array::RecordBatch::try_new(
+        std::sync::Arc::new(Schema::new(fields)),
+        columns,
+    )?)
+}
+
+impl VTab for DbQueryTypedTable {
+    type InitData = DbQueryTypedInitData;
+    type BindData = DbQueryTypedBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let sql = bind.get_parameter(0).to_string();
+        let result = execute_db_query(&sql, false)?;
+
+        let mut as_varchar = Vec::with_capacity(result.schema.fields().len());
+        for field in result.schema.fields() {
+            match to_duckdb_logical_type(field.data_type()) {
+                Ok(logical_type) => {
+                    bind.add_result_column(field.name(), logical_type);
+                    as_varchar.push(false);
+                }
+                Err(_) => {
+                    bind.add_result_column(
+                        field.name(),
+                        LogicalTypeHandle::from(LogicalTypeId::Varchar),
+                    );
+                    as_varchar.push(true);
+                }
+            }
+        }
+
+        Ok(DbQueryTypedBindData { result, as_varchar })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbQueryTypedInitData {
+            cursor: Mutex::new((0, 0)),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let bind_data = func.get_bind_data();
+        let mut cursor = init_data
+            .cursor
+            .lock()
+            .map_err(|e| format!("Lock error: {e}"))?;
+
+        loop {
+            let (batch_idx, offset) = *cursor;
+            let Some(batch) = bind_data.result.batches.get(batch_idx) else {
+                output.set_len(0);
+                return Ok(());
+            };
+            if offset >= batch.num_rows() {
+                *cursor = (batch_idx + 1, 0);
+                continue;
+            }
+            let len = (batch.num_rows() - offset).min(BATCH_SIZE);
+            let chunk = stringify_columns(batch.slice(offset, len), &bind_data.as_varchar)?;
+            record_batch_to_duckdb_data_chunk(&chunk, output)?;
+            *cursor = (batch_idx, offset + len);
+            return Ok(());
+        }
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![LogicalTypeId::Varchar.into()])
+    }
+}
+
 struct DbQueryStatusTable;
 
 #[repr(C)]
@@ -823,34 +1348,36 @@ impl VTab for DbQueryStatusTable {
     }
 }
 
-struct DbClusterStatusTable;
+struct DbQueryHistoryTable;
 
 #[repr(C)]
-struct DbClusterStatusBindData {}
+struct DbQueryHistoryBindData {}
 
 #[repr(C)]
-struct DbClusterStatusInitData {
-    done: AtomicBool,
+struct DbQueryHistoryInitData {
+    entries: Vec<admission::QueryHistoryEntry>,
+    cursor: Mutex<usize>,
 }
 
-impl VTab for DbClusterStatusTable {
-    type InitData = DbClusterStatusInitData;
-    type BindData = DbClusterStatusBindData;
+impl VTab for DbQueryHistoryTable {
+    type InitData = DbQueryHistoryInitData;
+    type BindData = DbQueryHistoryBindData;
 
     fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
-        bind.add_result_column("total_nodes", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("active_queries", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("queued_queries", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column(
-            "memory_utilization_pct",
-            LogicalTypeHandle::from(LogicalTypeId::Varchar),
-        );
-        Ok(DbClusterStatusBindData {})
+        bind.add_result_column("query_id", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("sql", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("user_id", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("duration_ms", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("rows", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("status", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("error", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        Ok(DbQueryHistoryBindData {})
     }
 
     fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
-        Ok(DbClusterStatusInitData {
-            done: AtomicBool::new(false),
+        Ok(DbQueryHistoryInitData {
+            entries: admission::get_query_history().unwrap_or_default(),
+            cursor: Mutex::new(0),
         })
     }
 
@@ -859,34 +1386,38 @@ impl VTab for DbClusterStatusTable {
         output: &mut DataChunkHandle,
     ) -> Result<(), Box<dyn std::error::Error>> {
         let init_data = func.get_init_data();
+        let mut cursor = init_data
+            .cursor
+            .lock()
+            .map_err(|_| "query history cursor lock poisoned")?;
 
-        if init_data.done.swap(true, Ordering::Relaxed) {
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(init_data.entries.len());
+        if start >= end {
             output.set_len(0);
             return Ok(());
         }
 
-        let status = match admission::get_cluster_status() {
-            Ok(s) => s,
-            Err(_) => {
-                output.set_len(0);
-                return Ok(());
-            }
-        };
-
-        let total_nodes_vec = output.flat_vector(0);
-        let active_vec = output.flat_vector(1);
-        let queued_vec = output.flat_vector(2);
-        let mem_vec = output.flat_vector(3);
-
-        total_nodes_vec.insert(0, CString::new(status.total_nodes.to_string())?);
-        active_vec.insert(0, CString::new(status.active_queries.to_string())?);
-        queued_vec.insert(0, CString::new(status.queued_queries.to_string())?);
-        mem_vec.insert(
-            0,
-            CString::new(format!("{:.1}", status.memory_utilization_pct))?,
-        );
+        let query_id_vec = output.flat_vector(0);
+        let sql_vec = output.flat_vector(1);
+        let user_id_vec = output.flat_vector(2);
+        let mut duration_vec = output.flat_vector(3);
+        let mut rows_vec = output.flat_vector(4);
+        let status_vec = output.flat_vector(5);
+        let error_vec = output.flat_vector(6);
+
+        for (i, entry) in init_data.entries[start..end].iter().enumerate() {
+            query_id_vec.insert(i, CString::new(entry.query_id.clone())?);
+            sql_vec.insert(i, CString::new(entry.sql.clone())?);
+            user_id_vec.insert(i, CString::new(entry.user_id.clone())?);
+            duration_vec.as_mut_slice::<i64>()[i] = entry.duration_ms as i64;
+            rows_vec.as_mut_slice::<i64>()[i] = entry.rows as i64;
+            status_vec.insert(i, CString::new(entry.status.clone())?);
+            error_vec.insert(i, CString::new(entry.error.clone())?);
+        }
 
-        output.set_len(1);
+        *cursor = end;
+        output.set_len(end - start);
         Ok(())
     }
 
@@ -895,37 +1426,1258 @@ impl VTab for DbClusterStatusTable {
     }
 }
 
-struct DbSetPriorityScalar;
+struct DbUserUsageTable;
 
-impl VScalar for DbSetPriorityScalar {
-    type State = ();
+#[repr(C)]
+struct DbUserUsageBindData {}
 
-    unsafe fn invoke(
-        _state: &Self::State,
-        input: &mut DataChunkHandle,
-        output: &mut dyn WritableVector,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if input.len() == 0 {
-            return Err("No input provided".into());
-        }
+#[repr(C)]
+struct DbUserUsageInitData {
+    usage: Vec<admission::UserUsage>,
+    cursor: Mutex<usize>,
+}
 
-        let priority_vector = input.flat_vector(0);
-        let priority_slice =
-            priority_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
-        let priority_str = duckdb::types::DuckString::new(&mut { priority_slice[0] })
-            .as_str()
-            .to_string();
+impl VTab for DbUserUsageTable {
+    type InitData = DbUserUsageInitData;
+    type BindData = DbUserUsageBindData;
 
-        let response = match admission::Priority::from_str(&priority_str) {
-            Some(priority) => {
-                admission::set_session_priority(priority);
-                format!("Session priority set to '{}'", priority.as_str())
-            }
-            None => format!(
-                "Invalid priority '{}'. Valid values: batch, interactive, system",
-                priority_str
-            ),
-        };
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        bind.add_result_column("user_id", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("active_queries", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("max_concurrent", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("total_queries", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("bytes_returned", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("cpu_ms", LogicalTypeHandle::from(LogicalTypeId::Double));
+        Ok(DbUserUsageBindData {})
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbUserUsageInitData {
+            usage: admission::get_user_usage().unwrap_or_default(),
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let mut cursor = init_data
+            .cursor
+            .lock()
+            .map_err(|_| "user usage cursor lock poisoned")?;
+
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(init_data.usage.len());
+        if start >= end {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let user_id_vec = output.flat_vector(0);
+        let mut active_vec = output.flat_vector(1);
+        let mut max_vec = output.flat_vector(2);
+        let mut total_vec = output.flat_vector(3);
+        let mut bytes_vec = output.flat_vector(4);
+        let mut cpu_vec = output.flat_vector(5);
+
+        for (i, usage) in init_data.usage[start..end].iter().enumerate() {
+            user_id_vec.insert(i, CString::new(usage.user_id.clone())?);
+            active_vec.as_mut_slice::<i64>()[i] = usage.active_queries as i64;
+            max_vec.as_mut_slice::<i64>()[i] = usage.max_concurrent as i64;
+            total_vec.as_mut_slice::<i64>()[i] = usage.total_queries as i64;
+            bytes_vec.as_mut_slice::<i64>()[i] = usage.bytes_returned as i64;
+            cpu_vec.as_mut_slice::<f64>()[i] = usage.cpu_ms;
+        }
+
+        *cursor = end;
+        output.set_len(end - start);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        None
+    }
+}
+
+/// Columns shared by `trex_db_query_profiled` and `trex_db_query_profile`.
+fn bind_profile_columns(bind: &BindInfo) {
+    bind.add_result_column("query_id", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+    bind.add_result_column("fragment", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+    bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+    bind.add_result_column("rows", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+    bind.add_result_column("bytes_shuffled", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+    bind.add_result_column("wall_ms", LogicalTypeHandle::from(LogicalTypeId::Double));
+}
+
+/// Emit the next `BATCH_SIZE` fragments of a profile from `cursor`.
+fn write_profile_rows(
+    query_id: &str,
+    fragments: &[query_profile::FragmentProfile],
+    cursor: &Mutex<usize>,
+    output: &mut DataChunkHandle,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut cursor = cursor.lock().map_err(|_| "query profile cursor lock poisoned")?;
+
+    let start = *cursor;
+    let end = (start + BATCH_SIZE).min(fragments.len());
+    if start >= end {
+        output.set_len(0);
+        return Ok(());
+    }
+
+    let query_id_vec = output.flat_vector(0);
+    let fragment_vec = output.flat_vector(1);
+    let node_vec = output.flat_vector(2);
+    let mut rows_vec = output.flat_vector(3);
+    let mut bytes_vec = output.flat_vector(4);
+    let mut wall_vec = output.flat_vector(5);
+
+    for (i, fragment) in fragments[start..end].iter().enumerate() {
+        query_id_vec.insert(i, CString::new(query_id)?);
+        fragment_vec.insert(i, CString::new(fragment.fragment.clone())?);
+        node_vec.insert(i, CString::new(fragment.node_name.clone())?);
+        rows_vec.as_mut_slice::<i64>()[i] = fragment.rows as i64;
+        bytes_vec.as_mut_slice::<i64>()[i] = fragment.bytes_shuffled as i64;
+        wall_vec.as_mut_slice::<f64>()[i] = fragment.wall_ms;
+    }
+
+    *cursor = end;
+    output.set_len(end - start);
+    Ok(())
+}
+
+/// Runs a distributed query and returns its per-fragment profile instead of
+/// its rows. The profile stays available to `trex_db_query_profile`.
+struct DbQueryProfiledTable;
+
+#[repr(C)]
+struct DbQueryProfileBindData {
+    query_id: String,
+    fragments: Vec<query_profile::FragmentProfile>,
+}
+
+#[repr(C)]
+struct DbQueryProfileInitData {
+    cursor: Mutex<usize>,
+}
+
+impl VTab for DbQueryProfiledTable {
+    type InitData = DbQueryProfileInitData;
+    type BindData = DbQueryProfileBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let sql = bind.get_parameter(0).to_string();
+        let (_, query_id) = run_db_query(&sql, true, false)?;
+        bind_profile_columns(bind);
+        let fragments = query_profile::get_profile(&query_id).unwrap_or_default();
+        Ok(DbQueryProfileBindData { query_id, fragments })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbQueryProfileInitData {
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bind_data = func.get_bind_data();
+        let init_data = func.get_init_data();
+        write_profile_rows(&bind_data.query_id, &bind_data.fragments, &init_data.cursor, output)
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![LogicalTypeId::Varchar.into()])
+    }
+}
+
+/// Profile recorded by an earlier `trex_db_query_profiled` call.
+struct DbQueryProfileTable;
+
+impl VTab for DbQueryProfileTable {
+    type InitData = DbQueryProfileInitData;
+    type BindData = DbQueryProfileBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let query_id = bind.get_parameter(0).to_string();
+        let fragments = query_profile::get_profile(&query_id)
+            .ok_or_else(|| format!("No profile recorded for query '{query_id}'"))?;
+        bind_profile_columns(bind);
+        Ok(DbQueryProfileBindData { query_id, fragments })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbQueryProfileInitData {
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bind_data = func.get_bind_data();
+        let init_data = func.get_init_data();
+        write_profile_rows(&bind_data.query_id, &bind_data.fragments, &init_data.cursor, output)
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![LogicalTypeId::Varchar.into()])
+    }
+}
+
+/// Runs a distributed query and returns its physical plan, one operator per
+/// row, with the rows and time each operator measured.
+struct DbExplainAnalyzeTable;
+
+#[repr(C)]
+struct DbExplainAnalyzeBindData {
+    operators: Vec<distributed_scheduler::OperatorActuals>,
+}
+
+#[repr(C)]
+struct DbExplainAnalyzeInitData {
+    cursor: Mutex<usize>,
+}
+
+impl VTab for DbExplainAnalyzeTable {
+    type InitData = DbExplainAnalyzeInitData;
+    type BindData = DbExplainAnalyzeBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let sql = bind.get_parameter(0).to_string();
+        let operators = run_db_explain_analyze(&sql)?;
+        bind.add_result_column("operator_id", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("parent_id", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("operator", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("actual_rows", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("actual_ms", LogicalTypeHandle::from(LogicalTypeId::Double));
+        bind.add_result_column("bytes_shuffled", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        Ok(DbExplainAnalyzeBindData { operators })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbExplainAnalyzeInitData {
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let operators = &func.get_bind_data().operators;
+        let mut cursor = func
+            .get_init_data()
+            .cursor
+            .lock()
+            .map_err(|_| "explain analyze cursor lock poisoned")?;
+
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(operators.len());
+        if start >= end {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let mut id_vec = output.flat_vector(0);
+        let mut parent_vec = output.flat_vector(1);
+        let operator_vec = output.flat_vector(2);
+        let mut rows_vec = output.flat_vector(3);
+        let mut ms_vec = output.flat_vector(4);
+        let mut bytes_vec = output.flat_vector(5);
+
+        for (i, op) in operators[start..end].iter().enumerate() {
+            id_vec.as_mut_slice::<i64>()[i] = op.operator_id as i64;
+            match op.parent_id {
+                Some(parent_id) => parent_vec.as_mut_slice::<i64>()[i] = parent_id as i64,
+                None => parent_vec.set_null(i),
+            }
+            let indented = format!("{}{}", "  ".repeat(op.depth), op.operator);
+            operator_vec.insert(i, CString::new(indented)?);
+            match op.rows {
+                Some(rows) => rows_vec.as_mut_slice::<i64>()[i] = rows as i64,
+                None => rows_vec.set_null(i),
+            }
+            match op.time_ms {
+                Some(ms) => ms_vec.as_mut_slice::<f64>()[i] = ms,
+                None => ms_vec.set_null(i),
+            }
+            match op.bytes_shuffled {
+                Some(bytes) => bytes_vec.as_mut_slice::<i64>()[i] = bytes as i64,
+                None => bytes_vec.set_null(i),
+            }
+        }
+
+        *cursor = end;
+        output.set_len(end - start);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![LogicalTypeId::Varchar.into()])
+    }
+}
+
+/// `trex_db_benchmark(sql, iterations)`: run a query repeatedly through
+/// `trex_db_query`'s path and summarize its latency in one row.
+struct DbBenchmarkTable;
+
+#[repr(C)]
+struct DbBenchmarkBindData {
+    summary: benchmark::BenchmarkSummary,
+}
+
+#[repr(C)]
+struct DbBenchmarkInitData {
+    done: AtomicBool,
+}
+
+impl VTab for DbBenchmarkTable {
+    type InitData = DbBenchmarkInitData;
+    type BindData = DbBenchmarkBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let sql = bind.get_parameter(0).to_string();
+        let iterations = bind.get_parameter(1).to_int64();
+        let warmup = bind
+            .get_named_parameter("warmup")
+            .map(|value| value.to_string().eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        let summary = benchmark::run(iterations, warmup, || {
+            run_db_query(&sql, false, false).map(|(result, _)| {
+                result.batches.iter().map(|b| b.num_rows() as u64).sum()
+            })
+        })?;
+
+        bind.add_result_column("iterations", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("warmup", LogicalTypeHandle::from(LogicalTypeId::Boolean));
+        bind.add_result_column("rows", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        for column in ["min_ms", "max_ms", "mean_ms", "p50_ms", "p95_ms"] {
+            bind.add_result_column(column, LogicalTypeHandle::from(LogicalTypeId::Double));
+        }
+        Ok(DbBenchmarkBindData { summary })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbBenchmarkInitData {
+            done: AtomicBool::new(false),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if func.get_init_data().done.swap(true, Ordering::Relaxed) {
+            output.set_len(0);
+            return Ok(());
+        }
+        let summary = &func.get_bind_data().summary;
+
+        output.flat_vector(0).as_mut_slice::<i64>()[0] = summary.iterations as i64;
+        output.flat_vector(1).as_mut_slice::<bool>()[0] = summary.warmup;
+        output.flat_vector(2).as_mut_slice::<i64>()[0] = summary.rows as i64;
+        let timings = [
+            summary.min_ms,
+            summary.max_ms,
+            summary.mean_ms,
+            summary.p50_ms,
+            summary.p95_ms,
+        ];
+        for (i, ms) in timings.into_iter().enumerate() {
+            output.flat_vector(3 + i).as_mut_slice::<f64>()[0] = ms;
+        }
+
+        output.set_len(1);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            LogicalTypeHandle::from(LogicalTypeId::Bigint),
+        ])
+    }
+
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        Some(vec![(
+            "warmup".to_string(),
+            LogicalTypeHandle::from(LogicalTypeId::Boolean),
+        )])
+    }
+}
+
+struct DbClusterStatusTable;
+
+#[repr(C)]
+struct DbClusterStatusBindData {}
+
+#[repr(C)]
+struct DbClusterStatusInitData {
+    done: AtomicBool,
+}
+
+impl VTab for DbClusterStatusTable {
+    type InitData = DbClusterStatusInitData;
+    type BindData = DbClusterStatusBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        bind.add_result_column("total_nodes", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("active_queries", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("queued_queries", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "memory_utilization_pct",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
+        Ok(DbClusterStatusBindData {})
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbClusterStatusInitData {
+            done: AtomicBool::new(false),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+
+        if init_data.done.swap(true, Ordering::Relaxed) {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let status = match admission::get_cluster_status() {
+            Ok(s) => s,
+            Err(_) => {
+                output.set_len(0);
+                return Ok(());
+            }
+        };
+
+        let total_nodes_vec = output.flat_vector(0);
+        let active_vec = output.flat_vector(1);
+        let queued_vec = output.flat_vector(2);
+        let mem_vec = output.flat_vector(3);
+
+        total_nodes_vec.insert(0, CString::new(status.total_nodes.to_string())?);
+        active_vec.insert(0, CString::new(status.active_queries.to_string())?);
+        queued_vec.insert(0, CString::new(status.queued_queries.to_string())?);
+        mem_vec.insert(
+            0,
+            CString::new(format!("{:.1}", status.memory_utilization_pct))?,
+        );
+
+        output.set_len(1);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        None
+    }
+}
+
+struct DbSetPriorityScalar;
+
+impl VScalar for DbSetPriorityScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.len() == 0 {
+            return Err("No input provided".into());
+        }
+
+        let priority_vector = input.flat_vector(0);
+        let priority_slice =
+            priority_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let priority_str = duckdb::types::DuckString::new(&mut { priority_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match admission::Priority::from_str(&priority_str) {
+            Some(priority) => {
+                admission::set_session_priority(priority);
+                format!("Session priority set to '{}'", priority.as_str())
+            }
+            None => format!(
+                "Invalid priority '{}'. Valid values: batch, interactive, system",
+                priority_str
+            ),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbSetUserQuotaScalar;
+
+impl VScalar for DbSetUserQuotaScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.len() == 0 {
+            return Err("No input provided".into());
+        }
+
+        let user_vector = input.flat_vector(0);
+        let limit_vector = input.flat_vector(1);
+
+        let user_slice =
+            user_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let limit_slice = limit_vector.as_slice_with_len::<i32>(input.len());
+
+        let user_id = duckdb::types::DuckString::new(&mut { user_slice[0] })
+            .as_str()
+            .to_string();
+        let max_concurrent = limit_slice[0];
+
+        if max_concurrent < 1 {
+            let flat_vector = output.flat_vector();
+            flat_vector.insert(
+                0,
+                &format!("max_concurrent must be >= 1, got {}", max_concurrent),
+            );
+            return Ok(());
+        }
+
+        let response = match admission::set_user_quota(&user_id, max_concurrent as usize) {
+            Ok(()) => format!(
+                "User '{}' quota set to {} concurrent queries",
+                user_id, max_concurrent
+            ),
+            Err(e) => format!("Error setting quota: {}", e),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Integer.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbSetQueryHistorySizeScalar;
+
+impl VScalar for DbSetQueryHistorySizeScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let size_vector = input.flat_vector(0);
+        let size = size_vector.as_slice_with_len::<i32>(input.len())[0];
+
+        if size < 0 {
+            let flat_vector = output.flat_vector();
+            flat_vector.insert(0, &format!("history size must be >= 0, got {}", size));
+            return Ok(());
+        }
+
+        let response = match admission::set_query_history_size(size as usize) {
+            Ok(()) => format!("Query history size set to {}", size),
+            Err(e) => format!("Error setting query history size: {}", e),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Integer.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbResetUserUsageScalar;
+
+impl VScalar for DbResetUserUsageScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let user_vector = input.flat_vector(0);
+        let user_slice =
+            user_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let user_id = duckdb::types::DuckString::new(&mut { user_slice[0] })
+            .as_str()
+            .to_string();
+
+        // An empty user id resets every user.
+        let target = (!user_id.is_empty()).then_some(user_id.as_str());
+        let response = match admission::reset_user_usage(target) {
+            Ok(()) => match target {
+                Some(id) => format!("Usage of user '{}' reset", id),
+                None => "Usage of every user reset".to_string(),
+            },
+            Err(e) => format!("Error resetting usage: {}", e),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbMetricsTable;
+
+#[repr(C)]
+struct DbMetricsBindData {}
+
+#[repr(C)]
+struct DbMetricsInitData {
+    done: AtomicBool,
+}
+
+impl VTab for DbMetricsTable {
+    type InitData = DbMetricsInitData;
+    type BindData = DbMetricsBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        bind.add_result_column("metric_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("metric_type", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("value", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("labels", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        Ok(DbMetricsBindData {})
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbMetricsInitData {
+            done: AtomicBool::new(false),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+
+        if init_data.done.swap(true, Ordering::Relaxed) {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let entries = metrics::instance().get_all_metrics();
+
+        if entries.is_empty() {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let chunk_size = entries.len();
+        let name_vec = output.flat_vector(0);
+        let type_vec = output.flat_vector(1);
+        let value_vec = output.flat_vector(2);
+        let labels_vec = output.flat_vector(3);
+
+        for (i, entry) in entries.iter().enumerate() {
+            name_vec.insert(i, CString::new(entry.name.clone())?);
+            type_vec.insert(i, CString::new(entry.metric_type.clone())?);
+            value_vec.insert(i, CString::new(entry.value.clone())?);
+            labels_vec.insert(i, CString::new(entry.labels.clone())?);
+        }
+
+        output.set_len(chunk_size);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        None
+    }
+}
+
+struct DbCancelQueryScalar;
+
+impl VScalar for DbCancelQueryScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.len() == 0 {
+            return Err("No input provided".into());
+        }
+
+        let id_vector = input.flat_vector(0);
+        let id_slice =
+            id_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let query_id = duckdb::types::DuckString::new(&mut { id_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match admission::cancel_query(&query_id) {
+            Ok(_status) => format!("Query {} cancelled", query_id),
+            Err(e) => format!("Query {} not found: {}", query_id, e),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbPartitionTableScalar;
+
+impl VScalar for DbPartitionTableScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.len() == 0 {
+            return Err("No input provided".into());
+        }
+
+        let table_vector = input.flat_vector(0);
+        let config_vector = input.flat_vector(1);
+
+        let table_slice =
+            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let config_slice =
+            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+
+        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
+            .as_str()
+            .to_string();
+        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match partition::swarm_partition_table_impl(&table_name, &config_json) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbCreateTableScalar;
+
+impl VScalar for DbCreateTableScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.len() == 0 {
+            return Err("No input provided".into());
+        }
+
+        let sql_vector = input.flat_vector(0);
+        let config_vector = input.flat_vector(1);
+
+        let sql_slice =
+            sql_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let config_slice =
+            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+
+        let create_sql = duckdb::types::DuckString::new(&mut { sql_slice[0] })
+            .as_str()
+            .to_string();
+        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match partition::swarm_create_table_impl(&create_sql, &config_json) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbCtasScalar;
+
+impl VScalar for DbCtasScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let table_vector = input.flat_vector(0);
+        let sql_vector = input.flat_vector(1);
+        let config_vector = input.flat_vector(2);
+
+        let table_slice =
+            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let sql_slice =
+            sql_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let config_slice =
+            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+
+        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
+            .as_str()
+            .to_string();
+        let sql = duckdb::types::DuckString::new(&mut { sql_slice[0] })
+            .as_str()
+            .to_string();
+        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match run_db_ctas(&table_name, &sql, &config_json) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbAttachTableScalar;
+
+impl VScalar for DbAttachTableScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let table_vector = input.flat_vector(0);
+        let config_vector = input.flat_vector(1);
+
+        let table_slice =
+            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let config_slice =
+            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+
+        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
+            .as_str()
+            .to_string();
+        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match partition::swarm_attach_table_impl(&table_name, &config_json) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbRepartitionTableScalar;
+
+impl VScalar for DbRepartitionTableScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.len() == 0 {
+            return Err("No input provided".into());
+        }
+
+        let table_vector = input.flat_vector(0);
+        let config_vector = input.flat_vector(1);
+
+        let table_slice =
+            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let config_slice =
+            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+
+        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
+            .as_str()
+            .to_string();
+        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match partition::swarm_repartition_table_impl(&table_name, &config_json) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbFederatePostgresScalar;
+
+impl VScalar for DbFederatePostgresScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let name_vector = input.flat_vector(0);
+        let conn_vector = input.flat_vector(1);
+
+        let name_slice =
+            name_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let conn_slice =
+            conn_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+
+        let name = duckdb::types::DuckString::new(&mut { name_slice[0] })
+            .as_str()
+            .to_string();
+        let connection_string = duckdb::types::DuckString::new(&mut { conn_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response =
+            match postgres_federation::federate_postgres_impl(&name, &connection_string) {
+                Ok(msg) => msg,
+                Err(err) => format!("Error: {}", err),
+            };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbRebalanceTableScalar;
+
+impl VScalar for DbRebalanceTableScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let table_vector = input.flat_vector(0);
+        let table_slice =
+            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match partition::swarm_rebalance_table_impl(&table_name) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbSetReplicationFactorScalar;
+
+impl VScalar for DbSetReplicationFactorScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let table_vector = input.flat_vector(0);
+        let table_slice =
+            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
+            .as_str()
+            .to_string();
+        let factor = input.flat_vector(1).as_slice_with_len::<i32>(input.len())[0];
+
+        let response = if factor < 1 {
+            format!("Error: replication factor must be at least 1, got {}", factor)
+        } else {
+            match partition::swarm_set_replication_factor_impl(&table_name, factor as usize) {
+                Ok(msg) => msg,
+                Err(err) => format!("Error: {}", err),
+            }
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Integer.into(),
+            ],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbKillNodeScalar;
+
+impl VScalar for DbKillNodeScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let node_vector = input.flat_vector(0);
+        let node_slice =
+            node_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let node_name = duckdb::types::DuckString::new(&mut { node_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match partition::swarm_kill_node_impl(&node_name) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbVacuumTableScalar;
+
+impl VScalar for DbVacuumTableScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let table_vector = input.flat_vector(0);
+        let table_slice =
+            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match partition::swarm_vacuum_table_impl(&table_name) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::Varchar.into(),
+        )]
+    }
+}
+
+struct DbExplainScalar;
+
+impl VScalar for DbExplainScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.is_empty() {
+            return Err("No input provided".into());
+        }
+
+        let sql_vector = input.flat_vector(0);
+        let sql_slice =
+            sql_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let sql = duckdb::types::DuckString::new(&mut { sql_slice[0] })
+            .as_str()
+            .to_string();
+
+        let response = match distributed_scheduler::explain_query(&sql) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
 
         let flat_vector = output.flat_vector();
         flat_vector.insert(0, &response);
@@ -940,9 +2692,9 @@ impl VScalar for DbSetPriorityScalar {
     }
 }
 
-struct DbSetUserQuotaScalar;
+struct DbExportScalar;
 
-impl VScalar for DbSetUserQuotaScalar {
+impl VScalar for DbExportScalar {
     type State = ();
 
     unsafe fn invoke(
@@ -950,37 +2702,34 @@ impl VScalar for DbSetUserQuotaScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        if input.len() == 0 {
+        if input.is_empty() {
             return Err("No input provided".into());
         }
 
-        let user_vector = input.flat_vector(0);
-        let limit_vector = input.flat_vector(1);
+        let sql_vector = input.flat_vector(0);
+        let path_vector = input.flat_vector(1);
+        let format_vector = input.flat_vector(2);
 
-        let user_slice =
-            user_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
-        let limit_slice = limit_vector.as_slice_with_len::<i32>(input.len());
+        let sql_slice =
+            sql_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let path_slice =
+            path_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let format_slice =
+            format_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
 
-        let user_id = duckdb::types::DuckString::new(&mut { user_slice[0] })
+        let sql = duckdb::types::DuckString::new(&mut { sql_slice[0] })
+            .as_str()
+            .to_string();
+        let output_path = duckdb::types::DuckString::new(&mut { path_slice[0] })
+            .as_str()
+            .to_string();
+        let format = duckdb::types::DuckString::new(&mut { format_slice[0] })
             .as_str()
             .to_string();
-        let max_concurrent = limit_slice[0];
-
-        if max_concurrent < 1 {
-            let flat_vector = output.flat_vector();
-            flat_vector.insert(
-                0,
-                &format!("max_concurrent must be >= 1, got {}", max_concurrent),
-            );
-            return Ok(());
-        }
 
-        let response = match admission::set_user_quota(&user_id, max_concurrent as usize) {
-            Ok(()) => format!(
-                "User '{}' quota set to {} concurrent queries",
-                user_id, max_concurrent
-            ),
-            Err(e) => format!("Error setting quota: {}", e),
+        let response = match run_db_export(&sql, &output_path, &format) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
         };
 
         let flat_vector = output.flat_vector();
@@ -992,37 +2741,47 @@ impl VScalar for DbSetUserQuotaScalar {
         vec![ScalarFunctionSignature::exact(
             vec![
                 LogicalTypeId::Varchar.into(),
-                LogicalTypeId::Integer.into(),
+                LogicalTypeId::Varchar.into(),
+                LogicalTypeId::Varchar.into(),
             ],
             LogicalTypeId::Varchar.into(),
         )]
     }
 }
 
-struct DbMetricsTable;
+struct DbPartitionsTable;
 
 #[repr(C)]
-struct DbMetricsBindData {}
+struct DbPartitionsBindData {}
 
 #[repr(C)]
-struct DbMetricsInitData {
+struct DbPartitionsInitData {
     done: AtomicBool,
 }
 
-impl VTab for DbMetricsTable {
-    type InitData = DbMetricsInitData;
-    type BindData = DbMetricsBindData;
+impl VTab for DbPartitionsTable {
+    type InitData = DbPartitionsInitData;
+    type BindData = DbPartitionsBindData;
 
     fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
-        bind.add_result_column("metric_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("metric_type", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("value", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("labels", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        Ok(DbMetricsBindData {})
+        bind.add_result_column("table_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("strategy", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("column", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "partition_id",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
+        bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "flight_endpoint",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
+        bind.add_result_column("role", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        Ok(DbPartitionsBindData {})
     }
 
     fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
-        Ok(DbMetricsInitData {
+        Ok(DbPartitionsInitData {
             done: AtomicBool::new(false),
         })
     }
@@ -1038,27 +2797,72 @@ impl VTab for DbMetricsTable {
             return Ok(());
         }
 
-        let entries = metrics::instance().get_all_metrics();
+        let all_metadata = match partition::get_all_partition_metadata() {
+            Ok(m) => m,
+            Err(_) => {
+                output.set_len(0);
+                return Ok(());
+            }
+        };
 
-        if entries.is_empty() {
+        if all_metadata.is_empty() {
             output.set_len(0);
             return Ok(());
         }
 
-        let chunk_size = entries.len();
-        let name_vec = output.flat_vector(0);
-        let type_vec = output.flat_vector(1);
-        let value_vec = output.flat_vector(2);
-        let labels_vec = output.flat_vector(3);
+        let mut rows: Vec<(String, String, String, usize, String, String, &str)> = Vec::new();
+        for (table_name, meta) in &all_metadata {
+            let strategy_str = partition::strategy_label(&meta.strategy);
+            let column_str = partition::strategy_column(&meta.strategy).to_string();
 
-        for (i, entry) in entries.iter().enumerate() {
-            name_vec.insert(i, CString::new(entry.name.clone())?);
-            type_vec.insert(i, CString::new(entry.metric_type.clone())?);
-            value_vec.insert(i, CString::new(entry.value.clone())?);
-            labels_vec.insert(i, CString::new(entry.labels.clone())?);
+            for assignment in &meta.assignments {
+                rows.push((
+                    table_name.clone(),
+                    strategy_str.clone(),
+                    column_str.clone(),
+                    assignment.partition_id,
+                    assignment.node_name.clone(),
+                    assignment.flight_endpoint.clone(),
+                    "primary",
+                ));
+                for replica in &assignment.replicas {
+                    rows.push((
+                        table_name.clone(),
+                        strategy_str.clone(),
+                        column_str.clone(),
+                        assignment.partition_id,
+                        replica.node_name.clone(),
+                        replica.flight_endpoint.clone(),
+                        "replica",
+                    ));
+                }
+            }
         }
 
-        output.set_len(chunk_size);
+        if rows.is_empty() {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let table_name_vec = output.flat_vector(0);
+        let strategy_vec = output.flat_vector(1);
+        let column_vec = output.flat_vector(2);
+        let partition_id_vec = output.flat_vector(3);
+        let node_name_vec = output.flat_vector(4);
+        let flight_endpoint_vec = output.flat_vector(5);
+        let role_vec = output.flat_vector(6);
+
+        for (i, (table, strategy, column, pid, node, endpoint, role)) in rows.iter().enumerate() {
+            table_name_vec.insert(i, CString::new(table.clone())?);
+            strategy_vec.insert(i, CString::new(strategy.clone())?);
+            column_vec.insert(i, CString::new(column.clone())?);
+            partition_id_vec.insert(i, CString::new(pid.to_string())?);
+            node_name_vec.insert(i, CString::new(node.clone())?);
+            flight_endpoint_vec.insert(i, CString::new(endpoint.clone())?);
+            role_vec.insert(i, CString::new(*role)?);
+        }
+
+        output.set_len(rows.len());
         Ok(())
     }
 
@@ -1067,148 +2871,378 @@ impl VTab for DbMetricsTable {
     }
 }
 
-struct DbCancelQueryScalar;
+/// What `trex_db_partition_table` would do with a table: the rows
+/// `trex_db_partitions` would show for it, with estimated row counts.
+struct DbPartitionTablePreviewTable;
+
+/// One copy of one partition in a preview.
+struct PartitionPreviewRow {
+    partition_id: usize,
+    node_name: String,
+    flight_endpoint: String,
+    role: &'static str,
+    estimated_rows: u64,
+    node_skew: f64,
+}
 
-impl VScalar for DbCancelQueryScalar {
-    type State = ();
+#[repr(C)]
+struct DbPartitionTablePreviewBindData {
+    table_name: String,
+    strategy: String,
+    column: String,
+    rows: Vec<PartitionPreviewRow>,
+}
 
-    unsafe fn invoke(
-        _state: &Self::State,
-        input: &mut DataChunkHandle,
-        output: &mut dyn WritableVector,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if input.len() == 0 {
-            return Err("No input provided".into());
+#[repr(C)]
+struct DbPartitionTablePreviewInitData {
+    cursor: Mutex<usize>,
+}
+
+impl VTab for DbPartitionTablePreviewTable {
+    type InitData = DbPartitionTablePreviewInitData;
+    type BindData = DbPartitionTablePreviewBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let table_name = bind.get_parameter(0).to_string();
+        let config_json = bind.get_parameter(1).to_string();
+        let preview = partition::swarm_partition_table_preview_impl(&table_name, &config_json)?;
+
+        bind.add_result_column("table_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("strategy", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("column", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "partition_id",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
+        bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "flight_endpoint",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
+        bind.add_result_column("role", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "estimated_rows",
+            LogicalTypeHandle::from(LogicalTypeId::Bigint),
+        );
+        bind.add_result_column("node_skew", LogicalTypeHandle::from(LogicalTypeId::Double));
+
+        let mut rows = Vec::new();
+        for assignment in &preview.assignments {
+            let copies = std::iter::once((
+                &assignment.node_name,
+                &assignment.flight_endpoint,
+                "primary",
+            ))
+            .chain(
+                assignment
+                    .replicas
+                    .iter()
+                    .map(|r| (&r.node_name, &r.flight_endpoint, "replica")),
+            );
+            for (node_name, flight_endpoint, role) in copies {
+                rows.push(PartitionPreviewRow {
+                    partition_id: assignment.partition_id,
+                    node_name: node_name.clone(),
+                    flight_endpoint: flight_endpoint.clone(),
+                    role,
+                    estimated_rows: preview.estimated_rows[assignment.partition_id],
+                    node_skew: preview.node_skew(node_name),
+                });
+            }
         }
 
-        let id_vector = input.flat_vector(0);
-        let id_slice =
-            id_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
-        let query_id = duckdb::types::DuckString::new(&mut { id_slice[0] })
-            .as_str()
-            .to_string();
+        Ok(DbPartitionTablePreviewBindData {
+            table_name,
+            strategy: partition::strategy_label(&preview.strategy),
+            column: partition::strategy_column(&preview.strategy).to_string(),
+            rows,
+        })
+    }
 
-        let response = match admission::cancel_query(&query_id) {
-            Ok(_status) => format!("Query {} cancelled", query_id),
-            Err(e) => format!("Query {} not found: {}", query_id, e),
-        };
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbPartitionTablePreviewInitData {
+            cursor: Mutex::new(0),
+        })
+    }
 
-        let flat_vector = output.flat_vector();
-        flat_vector.insert(0, &response);
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bind_data = func.get_bind_data();
+        let rows = &bind_data.rows;
+        let mut cursor = func
+            .get_init_data()
+            .cursor
+            .lock()
+            .map_err(|_| "partition preview cursor lock poisoned")?;
+
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(rows.len());
+        if start >= end {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let table_name_vec = output.flat_vector(0);
+        let strategy_vec = output.flat_vector(1);
+        let column_vec = output.flat_vector(2);
+        let partition_id_vec = output.flat_vector(3);
+        let node_name_vec = output.flat_vector(4);
+        let flight_endpoint_vec = output.flat_vector(5);
+        let role_vec = output.flat_vector(6);
+        let mut estimated_rows_vec = output.flat_vector(7);
+        let mut node_skew_vec = output.flat_vector(8);
+
+        for (i, row) in rows[start..end].iter().enumerate() {
+            table_name_vec.insert(i, CString::new(bind_data.table_name.clone())?);
+            strategy_vec.insert(i, CString::new(bind_data.strategy.clone())?);
+            column_vec.insert(i, CString::new(bind_data.column.clone())?);
+            partition_id_vec.insert(i, CString::new(row.partition_id.to_string())?);
+            node_name_vec.insert(i, CString::new(row.node_name.clone())?);
+            flight_endpoint_vec.insert(i, CString::new(row.flight_endpoint.clone())?);
+            role_vec.insert(i, CString::new(row.role)?);
+            estimated_rows_vec.as_mut_slice::<i64>()[i] = row.estimated_rows as i64;
+            node_skew_vec.as_mut_slice::<f64>()[i] = row.node_skew;
+        }
+
+        *cursor = end;
+        output.set_len(end - start);
         Ok(())
     }
 
-    fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![LogicalTypeId::Varchar.into()],
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
             LogicalTypeId::Varchar.into(),
-        )]
+            LogicalTypeId::Varchar.into(),
+        ])
     }
 }
 
-struct DbPartitionTableScalar;
+/// Gossiped sizes and column stats of each partition of a table, or of each
+/// node's copy of an unpartitioned one, one row per partition and column.
+struct DbTableStatsTable;
+
+/// One output row; `column` is `None` for shards that gossip no column stats.
+struct TableStatsRow {
+    partition_id: Option<usize>,
+    node_name: String,
+    row_count: u64,
+    byte_size: u64,
+    column: Option<(String, catalog::ColumnStats)>,
+}
 
-impl VScalar for DbPartitionTableScalar {
-    type State = ();
+#[repr(C)]
+struct DbTableStatsBindData {
+    rows: Vec<TableStatsRow>,
+}
 
-    unsafe fn invoke(
-        _state: &Self::State,
-        input: &mut DataChunkHandle,
-        output: &mut dyn WritableVector,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if input.len() == 0 {
-            return Err("No input provided".into());
+#[repr(C)]
+struct DbTableStatsInitData {
+    cursor: Mutex<usize>,
+}
+
+impl VTab for DbTableStatsTable {
+    type InitData = DbTableStatsInitData;
+    type BindData = DbTableStatsBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let table_name = bind.get_parameter(0).to_string();
+        let shards = catalog::table_stats(&table_name)?;
+        if shards.is_empty() {
+            return Err(format!("Table '{table_name}' is not in the cluster catalog").into());
         }
 
-        let table_vector = input.flat_vector(0);
-        let config_vector = input.flat_vector(1);
+        bind.add_result_column("partition_id", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("row_count", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("byte_size", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("column_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("min_value", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("max_value", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("null_count", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+
+        // Column stats are gossiped per copy, so every partition on a node
+        // repeats its node's.
+        let mut rows = Vec::new();
+        for shard in &shards {
+            let copies: Vec<(Option<usize>, u64, u64)> = if shard.partitions.is_empty() {
+                vec![(None, shard.row_count, shard.byte_size)]
+            } else {
+                shard
+                    .partitions
+                    .iter()
+                    .map(|(id, size)| (Some(*id), size.rows, size.bytes))
+                    .collect()
+            };
+            for (partition_id, row_count, byte_size) in copies {
+                let row = |column| TableStatsRow {
+                    partition_id,
+                    node_name: shard.node_name.clone(),
+                    row_count,
+                    byte_size,
+                    column,
+                };
+                if shard.column_stats.is_empty() {
+                    rows.push(row(None));
+                }
+                for (name, stats) in &shard.column_stats {
+                    rows.push(row(Some((name.clone(), stats.clone()))));
+                }
+            }
+        }
+        rows.sort_by_key(|r| r.partition_id);
+        Ok(DbTableStatsBindData { rows })
+    }
 
-        let table_slice =
-            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
-        let config_slice =
-            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbTableStatsInitData {
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let rows = &func.get_bind_data().rows;
+        let mut cursor = func
+            .get_init_data()
+            .cursor
+            .lock()
+            .map_err(|_| "table stats cursor lock poisoned")?;
 
-        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
-            .as_str()
-            .to_string();
-        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
-            .as_str()
-            .to_string();
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(rows.len());
+        if start >= end {
+            output.set_len(0);
+            return Ok(());
+        }
 
-        let response = match partition::swarm_partition_table_impl(&table_name, &config_json) {
-            Ok(msg) => msg,
-            Err(err) => format!("Error: {}", err),
-        };
+        let mut partition_id_vec = output.flat_vector(0);
+        let node_vec = output.flat_vector(1);
+        let mut row_count_vec = output.flat_vector(2);
+        let mut byte_size_vec = output.flat_vector(3);
+        let mut column_vec = output.flat_vector(4);
+        let mut min_vec = output.flat_vector(5);
+        let mut max_vec = output.flat_vector(6);
+        let mut null_count_vec = output.flat_vector(7);
+
+        for (i, row) in rows[start..end].iter().enumerate() {
+            match row.partition_id {
+                Some(id) => partition_id_vec.as_mut_slice::<i64>()[i] = id as i64,
+                None => partition_id_vec.set_null(i),
+            }
+            node_vec.insert(i, CString::new(row.node_name.clone())?);
+            row_count_vec.as_mut_slice::<i64>()[i] = row.row_count as i64;
+            byte_size_vec.as_mut_slice::<i64>()[i] = row.byte_size as i64;
+            match &row.column {
+                Some((name, stats)) => {
+                    column_vec.insert(i, CString::new(name.clone())?);
+                    min_vec.insert(i, CString::new(stats.min.to_string())?);
+                    max_vec.insert(i, CString::new(stats.max.to_string())?);
+                    match stats.null_count {
+                        Some(n) => null_count_vec.as_mut_slice::<i64>()[i] = n as i64,
+                        None => null_count_vec.set_null(i),
+                    }
+                }
+                None => {
+                    column_vec.set_null(i);
+                    min_vec.set_null(i);
+                    max_vec.set_null(i);
+                    null_count_vec.set_null(i);
+                }
+            }
+        }
 
-        let flat_vector = output.flat_vector();
-        flat_vector.insert(0, &response);
+        *cursor = end;
+        output.set_len(end - start);
         Ok(())
     }
 
-    fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![
-                LogicalTypeId::Varchar.into(),
-                LogicalTypeId::Varchar.into(),
-            ],
-            LogicalTypeId::Varchar.into(),
-        )]
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![LogicalTypeId::Varchar.into()])
     }
 }
 
-struct DbCreateTableScalar;
+/// Copies of tables whose schema hash differs from other copies of the same
+/// table, one row per copy of each conflicted table.
+struct DbSchemaConflictsTable;
 
-impl VScalar for DbCreateTableScalar {
-    type State = ();
+#[repr(C)]
+struct DbSchemaConflictsBindData {
+    conflicts: Vec<catalog::SchemaConflict>,
+}
 
-    unsafe fn invoke(
-        _state: &Self::State,
-        input: &mut DataChunkHandle,
-        output: &mut dyn WritableVector,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if input.len() == 0 {
-            return Err("No input provided".into());
-        }
+#[repr(C)]
+struct DbSchemaConflictsInitData {
+    cursor: Mutex<usize>,
+}
 
-        let sql_vector = input.flat_vector(0);
-        let config_vector = input.flat_vector(1);
+impl VTab for DbSchemaConflictsTable {
+    type InitData = DbSchemaConflictsInitData;
+    type BindData = DbSchemaConflictsBindData;
 
-        let sql_slice =
-            sql_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
-        let config_slice =
-            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        bind.add_result_column("table_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("schema_hash", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("copies_with_hash", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        Ok(DbSchemaConflictsBindData {
+            conflicts: catalog::schema_conflicts()?,
+        })
+    }
 
-        let create_sql = duckdb::types::DuckString::new(&mut { sql_slice[0] })
-            .as_str()
-            .to_string();
-        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
-            .as_str()
-            .to_string();
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbSchemaConflictsInitData {
+            cursor: Mutex::new(0),
+        })
+    }
 
-        let response = match partition::swarm_create_table_impl(&create_sql, &config_json) {
-            Ok(msg) => msg,
-            Err(err) => format!("Error: {}", err),
-        };
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let conflicts = &func.get_bind_data().conflicts;
+        let mut cursor = func
+            .get_init_data()
+            .cursor
+            .lock()
+            .map_err(|_| "schema conflicts cursor lock poisoned")?;
 
-        let flat_vector = output.flat_vector();
-        flat_vector.insert(0, &response);
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(conflicts.len());
+        if start >= end {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let table_vec = output.flat_vector(0);
+        let node_vec = output.flat_vector(1);
+        let hash_vec = output.flat_vector(2);
+        let mut copies_vec = output.flat_vector(3);
+
+        for (i, conflict) in conflicts[start..end].iter().enumerate() {
+            table_vec.insert(i, CString::new(conflict.table_name.clone())?);
+            node_vec.insert(i, CString::new(conflict.node_name.clone())?);
+            hash_vec.insert(i, CString::new(format!("0x{:X}", conflict.schema_hash))?);
+            copies_vec.as_mut_slice::<i64>()[i] = conflict.copies_with_hash as i64;
+        }
+
+        *cursor = end;
+        output.set_len(end - start);
         Ok(())
     }
 
-    fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![
-                LogicalTypeId::Varchar.into(),
-                LogicalTypeId::Varchar.into(),
-            ],
-            LogicalTypeId::Varchar.into(),
-        )]
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        None
     }
 }
 
-struct DbRepartitionTableScalar;
+/// Round-trip time to a node in milliseconds; fails when it is unreachable.
+struct DbPingScalar;
 
-impl VScalar for DbRepartitionTableScalar {
+impl VScalar for DbPingScalar {
     type State = ();
 
     unsafe fn invoke(
@@ -1216,79 +3250,62 @@ impl VScalar for DbRepartitionTableScalar {
         input: &mut DataChunkHandle,
         output: &mut dyn WritableVector,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        if input.len() == 0 {
+        if input.is_empty() {
             return Err("No input provided".into());
         }
 
-        let table_vector = input.flat_vector(0);
-        let config_vector = input.flat_vector(1);
-
-        let table_slice =
-            table_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
-        let config_slice =
-            config_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
-
-        let table_name = duckdb::types::DuckString::new(&mut { table_slice[0] })
-            .as_str()
-            .to_string();
-        let config_json = duckdb::types::DuckString::new(&mut { config_slice[0] })
+        let node_vector = input.flat_vector(0);
+        let node_slice =
+            node_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let node_name = duckdb::types::DuckString::new(&mut { node_slice[0] })
             .as_str()
             .to_string();
 
-        let response = match partition::swarm_repartition_table_impl(&table_name, &config_json) {
-            Ok(msg) => msg,
-            Err(err) => format!("Error: {}", err),
-        };
+        let rtt_ms = ping::ping(&node_name)?;
 
-        let flat_vector = output.flat_vector();
-        flat_vector.insert(0, &response);
+        let mut flat_vector = output.flat_vector();
+        flat_vector.as_mut_slice::<f64>()[0] = rtt_ms;
         Ok(())
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
         vec![ScalarFunctionSignature::exact(
-            vec![
-                LogicalTypeId::Varchar.into(),
-                LogicalTypeId::Varchar.into(),
-            ],
-            LogicalTypeId::Varchar.into(),
+            vec![LogicalTypeId::Varchar.into()],
+            LogicalTypeId::Double.into(),
         )]
     }
 }
 
-struct DbPartitionsTable;
+/// Round-trip time to every peer running a Flight server, one row per peer.
+struct DbPingAllTable;
 
 #[repr(C)]
-struct DbPartitionsBindData {}
+struct DbPingAllBindData {
+    results: Vec<ping::PingResult>,
+}
 
 #[repr(C)]
-struct DbPartitionsInitData {
-    done: AtomicBool,
+struct DbPingAllInitData {
+    cursor: Mutex<usize>,
 }
 
-impl VTab for DbPartitionsTable {
-    type InitData = DbPartitionsInitData;
-    type BindData = DbPartitionsBindData;
+impl VTab for DbPingAllTable {
+    type InitData = DbPingAllInitData;
+    type BindData = DbPingAllBindData;
 
     fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
-        bind.add_result_column("table_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("strategy", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("column", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column(
-            "partition_id",
-            LogicalTypeHandle::from(LogicalTypeId::Varchar),
-        );
         bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column(
-            "flight_endpoint",
-            LogicalTypeHandle::from(LogicalTypeId::Varchar),
-        );
-        Ok(DbPartitionsBindData {})
+        bind.add_result_column("flight_endpoint", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("rtt_ms", LogicalTypeHandle::from(LogicalTypeId::Double));
+        bind.add_result_column("error", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        Ok(DbPingAllBindData {
+            results: ping::ping_all()?,
+        })
     }
 
     fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
-        Ok(DbPartitionsInitData {
-            done: AtomicBool::new(false),
+        Ok(DbPingAllInitData {
+            cursor: Mutex::new(0),
         })
     }
 
@@ -1296,76 +3313,125 @@ impl VTab for DbPartitionsTable {
         func: &TableFunctionInfo<Self>,
         output: &mut DataChunkHandle,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let init_data = func.get_init_data();
-
-        if init_data.done.swap(true, Ordering::Relaxed) {
-            output.set_len(0);
-            return Ok(());
-        }
-
-        let all_metadata = match partition::get_all_partition_metadata() {
-            Ok(m) => m,
-            Err(_) => {
-                output.set_len(0);
-                return Ok(());
-            }
-        };
+        let results = &func.get_bind_data().results;
+        let mut cursor = func
+            .get_init_data()
+            .cursor
+            .lock()
+            .map_err(|_| "ping cursor lock poisoned")?;
 
-        if all_metadata.is_empty() {
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(results.len());
+        if start >= end {
             output.set_len(0);
             return Ok(());
         }
 
-        let mut rows: Vec<(String, String, String, usize, String, String)> = Vec::new();
-        for (table_name, meta) in &all_metadata {
-            let (strategy_str, column_str) = match &meta.strategy {
-                partition::PartitionStrategy::Hash { column, num_partitions } => {
-                    (format!("hash({})", num_partitions), column.clone())
+        let node_vec = output.flat_vector(0);
+        let endpoint_vec = output.flat_vector(1);
+        let mut rtt_vec = output.flat_vector(2);
+        let mut error_vec = output.flat_vector(3);
+
+        for (i, result) in results[start..end].iter().enumerate() {
+            node_vec.insert(i, CString::new(result.node_name.clone())?);
+            endpoint_vec.insert(i, CString::new(result.flight_endpoint.clone())?);
+            match &result.rtt_ms {
+                Ok(rtt_ms) => {
+                    rtt_vec.as_mut_slice::<f64>()[i] = *rtt_ms;
+                    error_vec.set_null(i);
                 }
-                partition::PartitionStrategy::Range { column, ranges } => {
-                    (format!("range({})", ranges.len()), column.clone())
+                Err(e) => {
+                    rtt_vec.set_null(i);
+                    error_vec.insert(i, CString::new(e.clone())?);
                 }
-            };
-
-            for assignment in &meta.assignments {
-                rows.push((
-                    table_name.clone(),
-                    strategy_str.clone(),
-                    column_str.clone(),
-                    assignment.partition_id,
-                    assignment.node_name.clone(),
-                    assignment.flight_endpoint.clone(),
-                ));
             }
         }
 
-        if rows.is_empty() {
+        *cursor = end;
+        output.set_len(end - start);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        None
+    }
+}
+
+/// The last log lines a node kept in memory, one row per line, oldest first.
+struct DbNodeLogsTable;
+
+#[repr(C)]
+struct DbNodeLogsBindData {
+    node_name: String,
+    lines: Vec<String>,
+}
+
+#[repr(C)]
+struct DbNodeLogsInitData {
+    cursor: Mutex<usize>,
+}
+
+impl VTab for DbNodeLogsTable {
+    type InitData = DbNodeLogsInitData;
+    type BindData = DbNodeLogsBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        let node_name = bind.get_parameter(0).to_string();
+        let lines = bind.get_parameter(1).to_int64();
+        bind.add_result_column("node_name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("line_number", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+        bind.add_result_column("line", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        Ok(DbNodeLogsBindData {
+            lines: node_logs::node_logs(&node_name, lines)?,
+            node_name,
+        })
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(DbNodeLogsInitData {
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let bind_data = func.get_bind_data();
+        let lines = &bind_data.lines;
+        let mut cursor = func
+            .get_init_data()
+            .cursor
+            .lock()
+            .map_err(|_| "node logs cursor lock poisoned")?;
+
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(lines.len());
+        if start >= end {
             output.set_len(0);
             return Ok(());
         }
 
-        let table_name_vec = output.flat_vector(0);
-        let strategy_vec = output.flat_vector(1);
-        let column_vec = output.flat_vector(2);
-        let partition_id_vec = output.flat_vector(3);
-        let node_name_vec = output.flat_vector(4);
-        let flight_endpoint_vec = output.flat_vector(5);
+        let node_vec = output.flat_vector(0);
+        let mut number_vec = output.flat_vector(1);
+        let line_vec = output.flat_vector(2);
 
-        for (i, (table, strategy, column, pid, node, endpoint)) in rows.iter().enumerate() {
-            table_name_vec.insert(i, CString::new(table.clone())?);
-            strategy_vec.insert(i, CString::new(strategy.clone())?);
-            column_vec.insert(i, CString::new(column.clone())?);
-            partition_id_vec.insert(i, CString::new(pid.to_string())?);
-            node_name_vec.insert(i, CString::new(node.clone())?);
-            flight_endpoint_vec.insert(i, CString::new(endpoint.clone())?);
+        for (i, line) in lines[start..end].iter().enumerate() {
+            node_vec.insert(i, CString::new(bind_data.node_name.clone())?);
+            number_vec.as_mut_slice::<i64>()[i] = (start + i + 1) as i64;
+            line_vec.insert(i, CString::new(line.replace('\0', ""))?);
         }
 
-        output.set_len(rows.len());
+        *cursor = end;
+        output.set_len(end - start);
         Ok(())
     }
 
     fn parameters() -> Option<Vec<LogicalTypeHandle>> {
-        None
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            LogicalTypeHandle::from(LogicalTypeId::Bigint),
+        ])
     }
 }
 
@@ -1396,6 +3462,9 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
     con.register_table_function::<DbConfigTable>("trex_db_config")
         .expect("Failed to register trex_db_config function");
 
+    con.register_table_function::<DbClusterConfigTable>("trex_db_cluster_config")
+        .expect("Failed to register trex_db_cluster_config function");
+
     con.register_scalar_function::<DbSetScalar>("trex_db_set")
         .expect("Failed to register trex_db_set function");
 
@@ -1405,9 +3474,15 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
     con.register_scalar_function::<DbSetDistributedScalar>("trex_db_set_distributed")
         .expect("Failed to register trex_db_set_distributed function");
 
-    con.register_table_function::<DbQueryTable>("trex_db_query")
+    con.register_table_function::<DbQueryTable<false>>("trex_db_query")
         .expect("Failed to register trex_db_query function");
 
+    con.register_table_function::<DbQueryTable<true>>("trex_db_query_partial")
+        .expect("Failed to register trex_db_query_partial function");
+
+    con.register_table_function::<DbQueryTypedTable>("trex_db_query_typed")
+        .expect("Failed to register trex_db_query_typed function");
+
     con.register_table_function::<service_functions::SwarmServicesTable>("trex_db_services")
         .expect("Failed to register trex_db_services function");
 
@@ -1432,6 +3507,18 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
     con.register_table_function::<DbQueryStatusTable>("trex_db_query_status")
         .expect("Failed to register trex_db_query_status function");
 
+    con.register_table_function::<DbQueryHistoryTable>("trex_db_query_history")
+        .expect("Failed to register trex_db_query_history function");
+
+    con.register_table_function::<DbQueryProfiledTable>("trex_db_query_profiled")
+        .expect("Failed to register trex_db_query_profiled function");
+
+    con.register_table_function::<DbQueryProfileTable>("trex_db_query_profile")
+        .expect("Failed to register trex_db_query_profile function");
+
+    con.register_table_function::<DbBenchmarkTable>("trex_db_benchmark")
+        .expect("Failed to register trex_db_benchmark function");
+
     con.register_table_function::<DbClusterStatusTable>("trex_db_cluster_status")
         .expect("Failed to register trex_db_cluster_status function");
 
@@ -1441,6 +3528,15 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
     con.register_scalar_function::<DbSetUserQuotaScalar>("trex_db_set_user_quota")
         .expect("Failed to register trex_db_set_user_quota function");
 
+    con.register_scalar_function::<DbSetQueryHistorySizeScalar>("trex_db_set_query_history_size")
+        .expect("Failed to register trex_db_set_query_history_size function");
+
+    con.register_table_function::<DbUserUsageTable>("trex_db_user_usage")
+        .expect("Failed to register trex_db_user_usage function");
+
+    con.register_scalar_function::<DbResetUserUsageScalar>("trex_db_reset_user_usage")
+        .expect("Failed to register trex_db_reset_user_usage function");
+
     con.register_table_function::<DbMetricsTable>("trex_db_metrics")
         .expect("Failed to register trex_db_metrics function");
 
@@ -1453,12 +3549,59 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
     con.register_scalar_function::<DbCreateTableScalar>("trex_db_create_table")
         .expect("Failed to register trex_db_create_table function");
 
+    con.register_scalar_function::<DbCtasScalar>("trex_db_ctas")
+        .expect("Failed to register trex_db_ctas function");
+
+    con.register_scalar_function::<DbAttachTableScalar>("trex_db_attach_table")
+        .expect("Failed to register trex_db_attach_table function");
+
     con.register_scalar_function::<DbRepartitionTableScalar>("trex_db_repartition_table")
         .expect("Failed to register trex_db_repartition_table function");
 
+    con.register_scalar_function::<DbRebalanceTableScalar>("trex_db_rebalance_table")
+        .expect("Failed to register trex_db_rebalance_table function");
+
+    con.register_scalar_function::<DbVacuumTableScalar>("trex_db_vacuum_table")
+        .expect("Failed to register trex_db_vacuum_table function");
+    con.register_scalar_function::<DbSetReplicationFactorScalar>("trex_db_set_replication_factor")
+        .expect("Failed to register trex_db_set_replication_factor function");
+
+    con.register_scalar_function::<DbKillNodeScalar>("trex_db_kill_node")
+        .expect("Failed to register trex_db_kill_node function");
+
+    con.register_scalar_function::<DbExplainScalar>("trex_db_explain")
+        .expect("Failed to register trex_db_explain function");
+
+    con.register_table_function::<DbExplainAnalyzeTable>("trex_db_explain_analyze")
+        .expect("Failed to register trex_db_explain_analyze function");
+
+    con.register_scalar_function::<DbExportScalar>("trex_db_export")
+        .expect("Failed to register trex_db_export function");
+
+    con.register_scalar_function::<DbFederatePostgresScalar>("trex_db_federate_postgres")
+        .expect("Failed to register trex_db_federate_postgres function");
+
     con.register_table_function::<DbPartitionsTable>("trex_db_partitions")
         .expect("Failed to register trex_db_partitions function");
 
+    con.register_table_function::<DbPartitionTablePreviewTable>("trex_db_partition_table_preview")
+        .expect("Failed to register trex_db_partition_table_preview function");
+
+    con.register_table_function::<DbTableStatsTable>("trex_db_table_stats")
+        .expect("Failed to register trex_db_table_stats function");
+
+    con.register_table_function::<DbSchemaConflictsTable>("trex_db_schema_conflicts")
+        .expect("Failed to register trex_db_schema_conflicts function");
+
+    con.register_scalar_function::<DbPingScalar>("trex_db_ping")
+        .expect("Failed to register trex_db_ping function");
+
+    con.register_table_function::<DbPingAllTable>("trex_db_ping_all")
+        .expect("Failed to register trex_db_ping_all function");
+
+    con.register_table_function::<DbNodeLogsTable>("trex_db_node_logs")
+        .expect("Failed to register trex_db_node_logs function");
+
     // Flight server functions (merged from flight extension)
     con.register_scalar_function::<flight_functions::StartFlightServerScalar>("trex_db_flight_start")
         .expect("Failed to register trex_db_flight_start function");
@@ -1476,6 +3619,13 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
         .expect("Failed to register trex_db_flight_status function");
 
     if let Ok(config) = config::ClusterConfig::from_env() {
+        if let Some(logging) = &config.logging {
+            logging.apply();
+        }
+        if let Some(flight_pool) = &config.flight_pool {
+            flight_pool.apply();
+        }
+        file_scan::configure(config.file_mappings.clone());
         if let Some((node_name, node_cfg)) = config::get_this_node_config(&config) {
             let addr: std::net::SocketAddr = match node_cfg.gossip_addr.parse() {
                 Ok(a) => a,
@@ -1491,13 +3641,15 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
 
             let data_node = if node_cfg.data_node { "true" } else { "false" };
 
+            flight_auth::set_cluster_secret(config.gossip_secret());
+
             let _ = GossipRegistry::instance().start(
-                &addr.ip().to_string(),
-                addr.port(),
+                addr,
                 &config.cluster_id,
                 node_name,
                 data_node,
                 seeds,
+                config.gossip_secret().as_deref(),
             );
 
             if !node_cfg.extensions.is_empty() {
@@ -1519,3 +3671,39 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Array, Int32Array, Int64Array, RecordBatch, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use std::sync::Arc;
+
+    #[test]
+    fn stringify_columns_rewrites_only_flagged_columns_and_keeps_nulls() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+                Arc::new(Int64Array::from(vec![Some(10), None, Some(30)])),
+            ],
+        )
+        .unwrap();
+
+        let out = stringify_columns(batch.clone(), &[false, true]).unwrap();
+        assert_eq!(out.schema().field(0), batch.schema().field(0));
+        assert_eq!(out.column(0).as_ref(), batch.column(0).as_ref());
+        assert_eq!(out.schema().field(1).data_type(), &DataType::Utf8);
+        let strings = out.column(1).as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(strings.value(0), "10");
+        assert!(strings.is_null(1));
+        assert_eq!(strings.value(2), "30");
+
+        let untouched = stringify_columns(batch.clone(), &[false, false]).unwrap();
+        assert_eq!(untouched, batch);
+    }
+}
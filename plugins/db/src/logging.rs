@@ -1,6 +1,40 @@
+//! Leveled stderr logging with optional JSON output and a per-query id.
+//!
+//! The query id of the distributed query being run is carried as a tokio
+//! task-local (for async code) or a thread-local (for the synchronous DuckDB
+//! entry points) and attached to every line logged while it is set, so one
+//! query's lifecycle can be followed across the scheduler, shuffle and Flight
+//! modules. Remote nodes pick it up from the `x-trex-query-id` Flight header
+//! or the shuffle descriptor.
+//!
+//! The last [`RECENT_LINES_CAPACITY`] lines written are also kept in memory,
+//! so `trex_db_node_logs` can tail a node's log over Flight.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
 use std::env;
+use std::future::Future;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use serde::{Deserialize, Serialize};
+
+/// gRPC metadata key carrying the query id on Flight calls.
+pub const QUERY_ID_HEADER: &str = "x-trex-query-id";
+
+/// 0 means "not configured": fall back to the environment.
+static LEVEL_OVERRIDE: AtomicU8 = AtomicU8::new(0);
+static FORMAT_OVERRIDE: AtomicU8 = AtomicU8::new(0);
+
+tokio::task_local! {
+    static TASK_QUERY_ID: String;
+}
+
+thread_local! {
+    static THREAD_QUERY_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum LogLevel {
     Error = 1,
@@ -23,6 +57,10 @@ impl LogLevel {
     }
 
     pub fn current() -> LogLevel {
+        let configured = LEVEL_OVERRIDE.load(Ordering::Relaxed);
+        if configured != 0 {
+            return LogLevel::from_u8(configured);
+        }
         env::var("SWARM_LOG_LEVEL")
             .map(|s| LogLevel::from_str(&s))
             .unwrap_or(LogLevel::Info)
@@ -37,6 +75,186 @@ impl LogLevel {
             LogLevel::Trace => "TRACE",
         }
     }
+
+    fn from_u8(v: u8) -> LogLevel {
+        match v {
+            1 => LogLevel::Error,
+            2 => LogLevel::Warn,
+            4 => LogLevel::Debug,
+            5 => LogLevel::Trace,
+            _ => LogLevel::Info,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text = 1,
+    Json = 2,
+}
+
+impl LogFormat {
+    pub fn parse(s: &str) -> Option<LogFormat> {
+        match s.to_lowercase().as_str() {
+            "text" => Some(LogFormat::Text),
+            "json" => Some(LogFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn current() -> LogFormat {
+        match FORMAT_OVERRIDE.load(Ordering::Relaxed) {
+            1 => LogFormat::Text,
+            2 => LogFormat::Json,
+            _ => env::var("SWARM_LOG_FORMAT")
+                .ok()
+                .and_then(|s| LogFormat::parse(&s))
+                .unwrap_or(LogFormat::Text),
+        }
+    }
+}
+
+/// `logging` section of `SWARM_CONFIG`. Unset fields keep the
+/// `SWARM_LOG_LEVEL` / `SWARM_LOG_FORMAT` environment defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    #[serde(default)]
+    pub level: Option<String>,
+    /// `text` or `json`.
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+impl LoggingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(format) = &self.format {
+            if LogFormat::parse(format).is_none() {
+                return Err(format!(
+                    "logging.format must be 'text' or 'json', got '{format}'"
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn apply(&self) {
+        if let Some(level) = &self.level {
+            LEVEL_OVERRIDE.store(LogLevel::from_str(level) as u8, Ordering::Relaxed);
+        }
+        if let Some(format) = self.format.as_deref().and_then(LogFormat::parse) {
+            FORMAT_OVERRIDE.store(format as u8, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Query id of the work running on this task or thread, if any.
+pub fn current_query_id() -> Option<String> {
+    if let Ok(Some(id)) = TASK_QUERY_ID.try_with(|id| (!id.is_empty()).then(|| id.clone())) {
+        return Some(id);
+    }
+    THREAD_QUERY_ID.with(|id| id.borrow().clone())
+}
+
+/// Run `fut` with `query_id` attached to its log lines. Spawned tasks do not
+/// inherit task-locals, so capture [`current_query_id`] before spawning and
+/// wrap the spawned future in this.
+pub fn with_query_id<F: Future>(
+    query_id: Option<String>,
+    fut: F,
+) -> impl Future<Output = F::Output> {
+    TASK_QUERY_ID.scope(query_id.unwrap_or_default(), fut)
+}
+
+/// Session config extension carrying the query id into `ExecutionPlan::execute`,
+/// which DataFusion may call from tasks it spawns itself.
+#[derive(Debug)]
+pub struct QueryLogId(pub String);
+
+/// Query id for an operator running under `task`: the session's
+/// [`QueryLogId`] if set, otherwise whatever is current.
+pub fn query_id_for_task(task: &datafusion::execution::TaskContext) -> Option<String> {
+    task.session_config()
+        .get_extension::<QueryLogId>()
+        .map(|id| id.0.clone())
+        .or_else(current_query_id)
+}
+
+/// Forward the current query id to a remote node as a Flight header.
+pub fn tag_request<T>(request: &mut tonic::Request<T>) {
+    if let Some(id) = current_query_id() {
+        if let Ok(value) = id.parse() {
+            request.metadata_mut().insert(QUERY_ID_HEADER, value);
+        }
+    }
+}
+
+pub fn query_id_from_metadata(metadata: &tonic::metadata::MetadataMap) -> Option<String> {
+    metadata
+        .get(QUERY_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Attaches a query id to log lines on this thread until dropped.
+pub struct QueryIdGuard {
+    previous: Option<String>,
+}
+
+pub fn enter_query(query_id: &str) -> QueryIdGuard {
+    let previous = THREAD_QUERY_ID.with(|id| id.replace(Some(query_id.to_string())));
+    QueryIdGuard { previous }
+}
+
+impl Drop for QueryIdGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        THREAD_QUERY_ID.with(|id| *id.borrow_mut() = previous);
+    }
+}
+
+/// How many of the most recent log lines each node keeps for tailing.
+pub const RECENT_LINES_CAPACITY: usize = 1000;
+
+/// The newest `capacity` lines, dropping the oldest to make room.
+struct LineRing {
+    capacity: usize,
+    lines: VecDeque<String>,
+}
+
+impl LineRing {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: VecDeque::new(),
+        }
+    }
+
+    fn push(&mut self, line: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.lines.len() >= self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// The last `n` lines, oldest first.
+    fn tail(&self, n: usize) -> Vec<String> {
+        let skip = self.lines.len().saturating_sub(n);
+        self.lines.iter().skip(skip).cloned().collect()
+    }
+}
+
+static RECENT_LINES: Mutex<LineRing> = Mutex::new(LineRing::new(RECENT_LINES_CAPACITY));
+
+/// The last `n` lines this node logged, oldest first, as they were written
+/// to stderr.
+pub fn recent_lines(n: usize) -> Vec<String> {
+    RECENT_LINES
+        .lock()
+        .map(|ring| ring.tail(n))
+        .unwrap_or_default()
 }
 
 pub struct SwarmLogger;
@@ -100,18 +318,7 @@ impl SwarmLogger {
     }
 
     pub fn log(level: LogLevel, category: &str, message: &str) {
-        if level > LogLevel::current() {
-            return;
-        }
-        let timestamp = Self::timestamp();
-        let sanitized = Self::sanitize(message);
-        eprintln!(
-            "[{}] [{}] [{}] {}",
-            timestamp,
-            level.as_str(),
-            category,
-            sanitized
-        );
+        Self::log_with_context(level, category, &[], message);
     }
 
     pub fn log_with_context(
@@ -123,22 +330,64 @@ impl SwarmLogger {
         if level > LogLevel::current() {
             return;
         }
+        let query_id = current_query_id()
+            .filter(|_| !context.iter().any(|(k, _)| *k == "query_id"));
+        let mut fields: Vec<(&str, &str)> = Vec::with_capacity(context.len() + 1);
+        if let Some(id) = &query_id {
+            fields.push(("query_id", id));
+        }
+        fields.extend_from_slice(context);
+        let line = Self::format_line(LogFormat::current(), level, category, &fields, message);
+        eprintln!("{}", line);
+        if let Ok(mut ring) = RECENT_LINES.lock() {
+            ring.push(line);
+        }
+    }
+
+    fn format_line(
+        format: LogFormat,
+        level: LogLevel,
+        category: &str,
+        fields: &[(&str, &str)],
+        message: &str,
+    ) -> String {
         let timestamp = Self::timestamp();
-        let ctx_str = context
-            .iter()
-            .map(|(k, v)| format!("{}={}", k, v))
-            .collect::<Vec<_>>()
-            .join(" ");
-        let sanitized_ctx = Self::sanitize(&ctx_str);
         let sanitized_msg = Self::sanitize(message);
-        eprintln!(
-            "[{}] [{}] [{}] [{}] {}",
-            timestamp,
-            level.as_str(),
-            category,
-            sanitized_ctx,
-            sanitized_msg
-        );
+        match format {
+            LogFormat::Json => {
+                let mut obj = serde_json::Map::new();
+                obj.insert("ts".into(), timestamp.into());
+                obj.insert("level".into(), level.as_str().into());
+                obj.insert("category".into(), category.into());
+                for (k, v) in fields {
+                    obj.insert((*k).to_string(), Self::sanitize(v).into());
+                }
+                obj.insert("message".into(), sanitized_msg.into());
+                serde_json::Value::Object(obj).to_string()
+            }
+            LogFormat::Text if fields.is_empty() => format!(
+                "[{}] [{}] [{}] {}",
+                timestamp,
+                level.as_str(),
+                category,
+                sanitized_msg
+            ),
+            LogFormat::Text => {
+                let ctx_str = fields
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", k, v))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                format!(
+                    "[{}] [{}] [{}] [{}] {}",
+                    timestamp,
+                    level.as_str(),
+                    category,
+                    Self::sanitize(&ctx_str),
+                    sanitized_msg
+                )
+            }
+        }
     }
 
     pub fn error(category: &str, message: &str) {
@@ -196,3 +445,93 @@ macro_rules! swarm_trace {
         $crate::logging::SwarmLogger::trace($category, &format!($($arg)*))
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_query_id_is_restored_on_drop() {
+        assert_eq!(current_query_id(), None);
+        {
+            let _outer = enter_query("q-1");
+            {
+                let _inner = enter_query("q-2");
+                assert_eq!(current_query_id().as_deref(), Some("q-2"));
+            }
+            assert_eq!(current_query_id().as_deref(), Some("q-1"));
+        }
+        assert_eq!(current_query_id(), None);
+    }
+
+    #[test]
+    fn task_query_id_wins_over_thread() {
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let _guard = enter_query("thread");
+        let seen = rt.block_on(with_query_id(Some("task".into()), async {
+            current_query_id()
+        }));
+        assert_eq!(seen.as_deref(), Some("task"));
+        let seen = rt.block_on(with_query_id(None, async { current_query_id() }));
+        assert_eq!(seen.as_deref(), Some("thread"));
+    }
+
+    #[test]
+    fn json_lines_carry_fields() {
+        let line = SwarmLogger::format_line(
+            LogFormat::Json,
+            LogLevel::Info,
+            "scheduler",
+            &[("query_id", "q-7"), ("node", "n1")],
+            "token=abc started",
+        );
+        let v: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(v["level"], "INFO");
+        assert_eq!(v["category"], "scheduler");
+        assert_eq!(v["query_id"], "q-7");
+        assert_eq!(v["node"], "n1");
+        assert_eq!(v["message"], "token=[REDACTED] started");
+
+        let text = SwarmLogger::format_line(
+            LogFormat::Text,
+            LogLevel::Warn,
+            "scheduler",
+            &[("query_id", "q-7")],
+            "slow",
+        );
+        assert!(text.ends_with("[WARN] [scheduler] [query_id=q-7] slow"));
+    }
+
+    #[test]
+    fn line_ring_keeps_the_newest_lines() {
+        let mut ring = LineRing::new(3);
+        for i in 0..5 {
+            ring.push(format!("line {i}"));
+        }
+        assert_eq!(ring.tail(10), vec!["line 2", "line 3", "line 4"]);
+        assert_eq!(ring.tail(2), vec!["line 3", "line 4"]);
+        assert!(ring.tail(0).is_empty());
+
+        let mut disabled = LineRing::new(0);
+        disabled.push("dropped".into());
+        assert!(disabled.tail(1).is_empty());
+    }
+
+    #[test]
+    fn logged_lines_are_kept_for_tailing() {
+        SwarmLogger::error("logging-test", "kept for tailing");
+        assert!(recent_lines(RECENT_LINES_CAPACITY)
+            .iter()
+            .any(|line| line.contains("kept for tailing")));
+    }
+
+    #[test]
+    fn logging_config_rejects_unknown_format() {
+        let cfg = LoggingConfig {
+            level: None,
+            format: Some("xml".into()),
+        };
+        assert!(cfg.validate().is_err());
+        assert_eq!(LogFormat::parse("JSON"), Some(LogFormat::Json));
+    }
+}
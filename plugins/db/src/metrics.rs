@@ -4,6 +4,8 @@ use std::collections::VecDeque;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Mutex, OnceLock};
 
+use crate::resources::ResourceSample;
+
 const MAX_QUERY_TIMES: usize = 1000;
 
 /// A single metric entry returned by [`SwarmMetrics::get_all_metrics`].
@@ -44,6 +46,13 @@ pub struct SwarmMetrics {
     pub active_queries: AtomicU64,
     pub queued_queries: AtomicU64,
 
+    pub flight_connections_opened: AtomicU64,
+    pub flight_connections_reused: AtomicU64,
+
+    /// Last sampled CPU utilisation, stored as `f64` bits.
+    node_cpu_pct: AtomicU64,
+    pub node_free_mem_mb: AtomicU64,
+
     query_times: Mutex<VecDeque<f64>>,
 }
 
@@ -56,6 +65,10 @@ impl SwarmMetrics {
             queries_rejected: AtomicU64::new(0),
             active_queries: AtomicU64::new(0),
             queued_queries: AtomicU64::new(0),
+            flight_connections_opened: AtomicU64::new(0),
+            flight_connections_reused: AtomicU64::new(0),
+            node_cpu_pct: AtomicU64::new(0f64.to_bits()),
+            node_free_mem_mb: AtomicU64::new(0),
             query_times: Mutex::new(VecDeque::new()),
         }
     }
@@ -83,6 +96,14 @@ impl SwarmMetrics {
         self.queries_rejected.fetch_add(1, Ordering::Relaxed);
     }
 
+    pub fn record_flight_connection_opened(&self) {
+        self.flight_connections_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_flight_connection_reused(&self) {
+        self.flight_connections_reused.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn set_active_queries(&self, n: u64) {
         self.active_queries.store(n, Ordering::Relaxed);
     }
@@ -91,6 +112,21 @@ impl SwarmMetrics {
         self.queued_queries.store(n, Ordering::Relaxed);
     }
 
+    /// Record the resource sampler's latest reading; unsampled values keep
+    /// their previous gauge value.
+    pub fn set_node_resources(&self, sample: ResourceSample) {
+        if let Some(cpu_pct) = sample.cpu_pct {
+            self.node_cpu_pct.store(cpu_pct.to_bits(), Ordering::Relaxed);
+        }
+        if let Some(free_mem_mb) = sample.free_mem_mb {
+            self.node_free_mem_mb.store(free_mem_mb, Ordering::Relaxed);
+        }
+    }
+
+    pub fn node_cpu_pct(&self) -> f64 {
+        f64::from_bits(self.node_cpu_pct.load(Ordering::Relaxed))
+    }
+
     pub fn get_all_metrics(&self) -> Vec<MetricEntry> {
         let mut entries = Vec::new();
 
@@ -119,6 +155,19 @@ impl SwarmMetrics {
             labels: String::new(),
         });
 
+        entries.push(MetricEntry {
+            name: "flight_connections_opened".to_string(),
+            metric_type: "counter".to_string(),
+            value: self.flight_connections_opened.load(Ordering::Relaxed).to_string(),
+            labels: String::new(),
+        });
+        entries.push(MetricEntry {
+            name: "flight_connections_reused".to_string(),
+            metric_type: "counter".to_string(),
+            value: self.flight_connections_reused.load(Ordering::Relaxed).to_string(),
+            labels: String::new(),
+        });
+
         entries.push(MetricEntry {
             name: "active_queries".to_string(),
             metric_type: "gauge".to_string(),
@@ -131,6 +180,18 @@ impl SwarmMetrics {
             value: self.queued_queries.load(Ordering::Relaxed).to_string(),
             labels: String::new(),
         });
+        entries.push(MetricEntry {
+            name: "node_cpu_pct".to_string(),
+            metric_type: "gauge".to_string(),
+            value: format!("{:.1}", self.node_cpu_pct()),
+            labels: String::new(),
+        });
+        entries.push(MetricEntry {
+            name: "node_free_mem_mb".to_string(),
+            metric_type: "gauge".to_string(),
+            value: self.node_free_mem_mb.load(Ordering::Relaxed).to_string(),
+            labels: String::new(),
+        });
 
         let histogram = self.get_query_time_histogram();
         entries.push(MetricEntry {
@@ -188,7 +249,7 @@ impl SwarmMetrics {
 }
 
 /// Nearest-rank percentile from a sorted slice.
-fn percentile(sorted: &[f64], pct: f64) -> f64 {
+pub(crate) fn percentile(sorted: &[f64], pct: f64) -> f64 {
     if sorted.is_empty() {
         return 0.0;
     }
@@ -275,6 +336,21 @@ mod tests {
         assert_eq!(m.queued_queries.load(Ordering::Relaxed), 10);
     }
 
+    #[test]
+    fn set_node_resources_keeps_unsampled_values() {
+        let m = SwarmMetrics::new();
+        m.set_node_resources(ResourceSample {
+            cpu_pct: Some(42.5),
+            free_mem_mb: Some(2048),
+        });
+        m.set_node_resources(ResourceSample {
+            cpu_pct: None,
+            free_mem_mb: Some(1024),
+        });
+        assert_eq!(m.node_cpu_pct(), 42.5);
+        assert_eq!(m.node_free_mem_mb.load(Ordering::Relaxed), 1024);
+    }
+
     #[test]
     fn histogram_empty() {
         let m = SwarmMetrics::new();
@@ -336,24 +412,29 @@ mod tests {
         m.set_active_queries(2);
 
         let entries = m.get_all_metrics();
-        assert_eq!(entries.len(), 7);
+        assert_eq!(entries.len(), 11);
 
         let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
         assert!(names.contains(&"queries_submitted"));
         assert!(names.contains(&"queries_completed"));
         assert!(names.contains(&"queries_failed"));
         assert!(names.contains(&"queries_rejected"));
+        assert!(names.contains(&"flight_connections_opened"));
+        assert!(names.contains(&"flight_connections_reused"));
         assert!(names.contains(&"active_queries"));
         assert!(names.contains(&"queued_queries"));
+        assert!(names.contains(&"node_cpu_pct"));
+        assert!(names.contains(&"node_free_mem_mb"));
         assert!(names.contains(&"query_execution_time_seconds"));
 
         for e in &entries {
             match e.name.as_str() {
                 "queries_submitted" | "queries_completed" | "queries_failed"
-                | "queries_rejected" => {
+                | "queries_rejected" | "flight_connections_opened"
+                | "flight_connections_reused" => {
                     assert_eq!(e.metric_type, "counter");
                 }
-                "active_queries" | "queued_queries" => {
+                "active_queries" | "queued_queries" | "node_cpu_pct" | "node_free_mem_mb" => {
                     assert_eq!(e.metric_type, "gauge");
                 }
                 "query_execution_time_seconds" => {
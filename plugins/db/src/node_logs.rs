@@ -0,0 +1,42 @@
+//! Tail another node's log: the lines its logger kept in memory, fetched
+//! with the Flight "logs" action.
+
+use std::time::Duration;
+
+use crate::catalog;
+use crate::flight_client;
+use crate::logging::{self, RECENT_LINES_CAPACITY};
+
+/// How long fetching a node's log may take.
+pub const LOGS_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The last `lines` log lines of `node_name`, oldest first. This node's own
+/// lines are read directly; nodes keep at most [`RECENT_LINES_CAPACITY`].
+pub fn node_logs(node_name: &str, lines: i64) -> Result<Vec<String>, String> {
+    let lines = usize::try_from(lines)
+        .map_err(|_| format!("Line count must not be negative, got {lines}"))?
+        .min(RECENT_LINES_CAPACITY);
+    if catalog::get_self_node_name().as_deref() == Some(node_name) {
+        return Ok(logging::recent_lines(lines));
+    }
+
+    let endpoints = catalog::flight_endpoints_by_node()?;
+    let endpoint = endpoints
+        .get(node_name)
+        .ok_or_else(|| format!("Node '{node_name}' is not running a Flight server"))?;
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create tokio runtime: {e}"))?;
+    rt.block_on(async {
+        tokio::time::timeout(LOGS_TIMEOUT, flight_client::fetch_remote_logs(endpoint, lines))
+            .await
+            .map_err(|_| {
+                format!(
+                    "Fetching logs from {node_name} timed out after {}s",
+                    LOGS_TIMEOUT.as_secs()
+                )
+            })?
+            .map_err(String::from)
+    })
+}
@@ -0,0 +1,158 @@
+//! Queries that return what they could read instead of failing when a node
+//! or shard is unreachable.
+//!
+//! `trex_db_query_partial` calls [`begin`] before running its query. A scan
+//! whose shard fails on every copy, or a node whose fan-out query fails,
+//! reports itself through [`record_missing`] and contributes no rows. The
+//! guard returned by [`begin`] lists what was skipped once the query is done,
+//! and [`append_missing_column`] puts the count in the result the caller
+//! gets.
+
+use arrow::array::{ArrayRef, Int64Array, RecordBatch};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// The column appended to every `trex_db_query_partial` result.
+pub const MISSING_COLUMN: &str = "_missing_partitions";
+
+fn queries() -> &'static Mutex<HashMap<String, Vec<String>>> {
+    static QUERIES: OnceLock<Mutex<HashMap<String, Vec<String>>>> = OnceLock::new();
+    QUERIES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Tolerates missing partitions for `query_id` until dropped.
+pub struct PartialQuery {
+    query_id: String,
+}
+
+impl PartialQuery {
+    /// The partitions skipped so far, in the order they failed.
+    pub fn missing(&self) -> Vec<String> {
+        queries()
+            .lock()
+            .ok()
+            .and_then(|q| q.get(&self.query_id).cloned())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for PartialQuery {
+    fn drop(&mut self) {
+        if let Ok(mut q) = queries().lock() {
+            q.remove(&self.query_id);
+        }
+    }
+}
+
+pub fn begin(query_id: &str) -> PartialQuery {
+    if let Ok(mut q) = queries().lock() {
+        q.insert(query_id.to_string(), Vec::new());
+    }
+    PartialQuery {
+        query_id: query_id.to_string(),
+    }
+}
+
+/// Note that `partition` of `query_id` could not be read. Returns false
+/// when the query does not tolerate missing partitions, in which case the
+/// caller fails it as usual.
+pub fn record_missing(query_id: Option<&str>, partition: String) -> bool {
+    let Some(query_id) = query_id else {
+        return false;
+    };
+    let Ok(mut q) = queries().lock() else {
+        return false;
+    };
+    match q.get_mut(query_id) {
+        Some(missing) => {
+            missing.push(partition);
+            true
+        }
+        None => false,
+    }
+}
+
+/// The warning attached to a partial result with `missing` partitions.
+pub fn warning(missing: &[String]) -> String {
+    format!(
+        "Partial results: {} partition(s) missing ({})",
+        missing.len(),
+        missing.join(", ")
+    )
+}
+
+/// `batches` with a trailing [`MISSING_COLUMN`] holding `missing` on every
+/// row, 0 when the result is complete.
+pub fn append_missing_column(
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    missing: usize,
+) -> Result<(SchemaRef, Vec<RecordBatch>), ArrowError> {
+    let mut fields: Vec<Field> = schema.fields().iter().map(|f| f.as_ref().clone()).collect();
+    fields.push(Field::new(MISSING_COLUMN, DataType::Int64, false));
+    let schema = Arc::new(Schema::new(fields));
+    let batches = batches
+        .iter()
+        .map(|batch| {
+            let mut columns = batch.columns().to_vec();
+            let count: ArrayRef =
+                Arc::new(Int64Array::from(vec![missing as i64; batch.num_rows()]));
+            columns.push(count);
+            RecordBatch::try_new(schema.clone(), columns)
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((schema, batches))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_partitions_are_tracked_per_query() {
+        let query = begin("partial-test-1");
+        assert!(record_missing(Some("partial-test-1"), "orders[1]".into()));
+        assert!(record_missing(Some("partial-test-1"), "orders[3]".into()));
+        assert!(!record_missing(Some("partial-test-other"), "orders[0]".into()));
+        assert!(!record_missing(None, "orders[0]".into()));
+        assert_eq!(query.missing(), vec!["orders[1]", "orders[3]"]);
+        assert_eq!(
+            warning(&query.missing()),
+            "Partial results: 2 partition(s) missing (orders[1], orders[3])"
+        );
+
+        drop(query);
+        assert!(!record_missing(Some("partial-test-1"), "orders[2]".into()));
+    }
+
+    #[test]
+    fn partial_results_carry_the_missing_count() {
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "region",
+            DataType::Utf8,
+            true,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(arrow::array::StringArray::from(vec!["eu", "us"]))],
+        )
+        .unwrap();
+
+        let (schema, batches) = append_missing_column(&schema, &[batch], 2).unwrap();
+        assert_eq!(schema.fields().len(), 2);
+        assert_eq!(schema.field(1).name(), MISSING_COLUMN);
+        let counts = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(counts.values().to_vec(), vec![2, 2]);
+
+        let (empty_schema, empty) =
+            append_missing_column(&Arc::new(Schema::empty()), &[], 0).unwrap();
+        assert_eq!(empty_schema.field(0).name(), MISSING_COLUMN);
+        assert!(empty.is_empty());
+    }
+}
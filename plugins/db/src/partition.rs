@@ -1,6 +1,6 @@
 //! Table partitioning: distribute tables across cluster nodes.
 
-use arrow::array::RecordBatch;
+use arrow::array::{Array, RecordBatch};
 use arrow::compute::take;
 use arrow::datatypes::{DataType, SchemaRef};
 use serde::{Deserialize, Serialize};
@@ -8,7 +8,7 @@ use std::sync::Arc;
 
 use crate::catalog;
 use crate::flight_client;
-use crate::gossip::GossipRegistry;
+use crate::gossip::{GossipRegistry, NodeKeyValueInfo};
 use crate::logging::SwarmLogger;
 use crate::shuffle_descriptor::{ShuffleDescriptor, ShuffleTarget};
 use crate::shuffle_partition;
@@ -25,6 +25,13 @@ pub enum PartitionStrategy {
         column: String,
         ranges: Vec<RangeBound>,
     },
+    /// Each entry maps a partition label to the discrete column values it
+    /// holds. Values in no list go to a trailing default partition, so there
+    /// are `lists.len() + 1` partitions.
+    List {
+        column: String,
+        lists: Vec<(String, Vec<String>)>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,11 +42,51 @@ pub struct RangeBound {
     pub upper: Option<serde_json::Value>,
 }
 
+/// Where a partition lives. `node_name`/`flight_endpoint` name the primary,
+/// whose rows sit in the node's copy of the table; each replica holds the
+/// same rows in its own [`replica_table_name`] table.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PartitionAssignment {
     pub partition_id: usize,
     pub node_name: String,
     pub flight_endpoint: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replicas: Vec<ReplicaAssignment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicaAssignment {
+    pub node_name: String,
+    pub flight_endpoint: String,
+}
+
+/// Physical table holding a replica of one partition. Replicas stay out of
+/// the node's primary table so a scan of that table never sees a row twice.
+pub fn replica_table_name(table_name: &str, partition_id: usize) -> String {
+    format!("{}__replica_{}", table_name, partition_id)
+}
+
+/// Inverse of [`replica_table_name`].
+pub fn parse_replica_table_name(name: &str) -> Option<(&str, usize)> {
+    let (base, id) = name.rsplit_once("__replica_")?;
+    if base.is_empty() {
+        return None;
+    }
+    Some((base, id.parse().ok()?))
+}
+
+const PENDING_SUFFIX: &str = "__pending";
+
+/// Staging table a node loads its copy of `table_name` into before the
+/// create commits it under its real name. The name is fixed per table, so a
+/// retry after a failed create replaces whatever the failed attempt left.
+pub fn pending_table_name(table_name: &str) -> String {
+    format!("{}{}", table_name, PENDING_SUFFIX)
+}
+
+/// Whether `name` is a [`pending_table_name`] staging table.
+pub fn is_pending_table_name(name: &str) -> bool {
+    name.len() > PENDING_SUFFIX.len() && name.ends_with(PENDING_SUFFIX)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +94,14 @@ pub struct PartitionMetadata {
     pub strategy: PartitionStrategy,
     pub assignments: Vec<PartitionAssignment>,
     pub create_sql: String,
+    /// Copies of each partition, counting the primary; 0 in metadata that
+    /// predates it.
+    #[serde(default)]
+    pub replication_factor: usize,
+    /// Bumped when copies are removed from the assignments, so a node can
+    /// tell whether it routes by the change yet; see [`drain_routes`].
+    #[serde(default)]
+    pub routing_version: u64,
 }
 
 /// User-facing JSON config parsed from the second argument.
@@ -59,7 +114,33 @@ pub struct PartitionConfig {
     #[serde(default)]
     pub ranges: Option<Vec<RangeBound>>,
     #[serde(default)]
+    pub lists: Option<Vec<ListPartition>>,
+    #[serde(default)]
     pub nodes: Option<Vec<String>>,
+    /// Copies of each partition, counting the primary. Defaults to 1.
+    #[serde(default)]
+    pub replication_factor: Option<usize>,
+}
+
+/// One entry of a list strategy config: `{"name": "eu", "values": ["de", "fr"]}`.
+/// Non-string values are matched by their JSON text, so `[1, 2]` matches an
+/// integer column.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListPartition {
+    pub name: String,
+    pub values: Vec<serde_json::Value>,
+}
+
+impl ListPartition {
+    fn value_strings(&self) -> Vec<String> {
+        self.values
+            .iter()
+            .map(|v| match v {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            })
+            .collect()
+    }
 }
 
 pub fn publish_partition_metadata(
@@ -74,8 +155,16 @@ pub fn publish_partition_metadata(
 
 pub fn get_partition_metadata(table_name: &str) -> Result<Option<PartitionMetadata>, String> {
     let nodes = GossipRegistry::instance().get_node_key_values()?;
+    find_partition_metadata(&nodes, table_name)
+}
+
+/// Look up partition metadata in an already fetched gossip snapshot.
+pub fn find_partition_metadata(
+    nodes: &[NodeKeyValueInfo],
+    table_name: &str,
+) -> Result<Option<PartitionMetadata>, String> {
     let key = format!("partition:{}", table_name);
-    for node in &nodes {
+    for node in nodes {
         for (k, v) in &node.key_values {
             if k == &key {
                 let meta: PartitionMetadata = serde_json::from_str(v)
@@ -119,12 +208,18 @@ pub struct TargetNode {
     pub flight_endpoint: String,
 }
 
-/// Discover active data nodes with Flight endpoints from gossip.
+/// Discover active data nodes with Flight endpoints from gossip. Nodes SWIM
+/// holds suspect or dead are left out, so no partition is placed on them.
 pub fn discover_target_nodes() -> Result<Vec<TargetNode>, String> {
-    let nodes = GossipRegistry::instance().get_node_key_values()?;
+    let registry = GossipRegistry::instance();
+    let nodes = registry.get_node_key_values()?;
+    let unreachable = registry.unreachable_nodes();
     let mut targets = Vec::new();
 
     for node in &nodes {
+        if unreachable.contains(&node.node_name) {
+            continue;
+        }
         let is_data_node = node
             .key_values
             .iter()
@@ -166,6 +261,67 @@ pub fn assign_partitions(
     available_nodes: &[TargetNode],
     explicit_nodes: Option<&[String]>,
 ) -> Result<Vec<PartitionAssignment>, String> {
+    let target_nodes = select_target_nodes(available_nodes, explicit_nodes)?;
+
+    let mut assignments = Vec::with_capacity(num_partitions);
+    for partition_id in 0..num_partitions {
+        let node = &target_nodes[partition_id % target_nodes.len()];
+        assignments.push(PartitionAssignment {
+            partition_id,
+            node_name: node.node_name.clone(),
+            flight_endpoint: node.flight_endpoint.clone(),
+            replicas: Vec::new(),
+        });
+    }
+
+    Ok(assignments)
+}
+
+/// Give every partition `replication_factor - 1` replicas on the nodes that
+/// follow its primary in the target list, so no node holds two copies.
+pub fn assign_replicas(
+    assignments: &mut [PartitionAssignment],
+    available_nodes: &[TargetNode],
+    explicit_nodes: Option<&[String]>,
+    replication_factor: usize,
+) -> Result<(), String> {
+    if replication_factor == 0 {
+        return Err("replication_factor must be at least 1".to_string());
+    }
+    let target_nodes = select_target_nodes(available_nodes, explicit_nodes)?;
+    if replication_factor > target_nodes.len() {
+        return Err(format!(
+            "replication_factor {} exceeds the {} available node(s)",
+            replication_factor,
+            target_nodes.len()
+        ));
+    }
+
+    for assignment in assignments.iter_mut() {
+        let primary = target_nodes
+            .iter()
+            .position(|n| n.node_name == assignment.node_name)
+            .ok_or_else(|| {
+                format!("Primary node '{}' is not a target node", assignment.node_name)
+            })?;
+        assignment.replicas = (1..replication_factor)
+            .map(|offset| {
+                let node = target_nodes[(primary + offset) % target_nodes.len()];
+                ReplicaAssignment {
+                    node_name: node.node_name.clone(),
+                    flight_endpoint: node.flight_endpoint.clone(),
+                }
+            })
+            .collect();
+    }
+
+    Ok(())
+}
+
+fn select_target_nodes<'a>(
+    available_nodes: &'a [TargetNode],
+    explicit_nodes: Option<&[String]>,
+) -> Result<Vec<&'a TargetNode>, String> {
     if available_nodes.is_empty() {
         return Err("No target nodes available for partitioning".to_string());
     }
@@ -188,17 +344,7 @@ pub fn assign_partitions(
         return Err("No target nodes matched for partitioning".to_string());
     }
 
-    let mut assignments = Vec::with_capacity(num_partitions);
-    for partition_id in 0..num_partitions {
-        let node = &target_nodes[partition_id % target_nodes.len()];
-        assignments.push(PartitionAssignment {
-            partition_id,
-            node_name: node.node_name.clone(),
-            flight_endpoint: node.flight_endpoint.clone(),
-        });
-    }
-
-    Ok(assignments)
+    Ok(target_nodes)
 }
 
 pub fn generate_create_table_sql(table_name: &str, schema: &SchemaRef) -> String {
@@ -294,63 +440,262 @@ pub fn range_partition_batches(
         for row in 0..num_rows {
             let value_str =
                 arrow::util::display::array_value_to_string(col, row).unwrap_or_default();
-            let value_f64: Option<f64> = value_str.parse().ok();
-
-            let mut assigned = false;
-            for (part_idx, range) in ranges.iter().enumerate() {
-                let above_lower = match &range.lower {
-                    None => true,
-                    Some(bound) => match (value_f64, bound.as_f64()) {
-                        (Some(v), Some(b)) => v >= b,
-                        _ => value_str >= bound.to_string(),
-                    },
-                };
+            partition_indices[range_partition_of(&value_str, ranges)].push(row as u32);
+        }
 
-                let below_upper = match &range.upper {
-                    None => true,
-                    Some(bound) => match (value_f64, bound.as_f64()) {
-                        (Some(v), Some(b)) => v < b,
-                        _ => value_str < bound.to_string(),
-                    },
-                };
+        take_partitions(batch, &partition_indices, &mut result)?;
+    }
 
-                if above_lower && below_upper {
-                    partition_indices[part_idx].push(row as u32);
-                    assigned = true;
-                    break;
-                }
-            }
+    Ok(result)
+}
+
+/// The range a column value, in its display form, falls in: the first whose
+/// `[lower, upper)` holds it, else the last. Values compare numerically when
+/// both sides are numbers and as text otherwise.
+fn range_partition_of(value_str: &str, ranges: &[RangeBound]) -> usize {
+    let value_f64: Option<f64> = value_str.parse().ok();
+    ranges
+        .iter()
+        .position(|range| {
+            let above_lower = match &range.lower {
+                None => true,
+                Some(bound) => match (value_f64, bound.as_f64()) {
+                    (Some(v), Some(b)) => v >= b,
+                    _ => value_str >= bound.to_string().as_str(),
+                },
+            };
+
+            let below_upper = match &range.upper {
+                None => true,
+                Some(bound) => match (value_f64, bound.as_f64()) {
+                    (Some(v), Some(b)) => v < b,
+                    _ => value_str < bound.to_string().as_str(),
+                },
+            };
+
+            above_lower && below_upper
+        })
+        .unwrap_or(ranges.len() - 1)
+}
 
-            if !assigned {
-                partition_indices[num_partitions - 1].push(row as u32);
+/// Partition of each listed value, rejecting values listed twice.
+fn list_value_partitions(
+    lists: &[(String, Vec<String>)],
+) -> Result<std::collections::HashMap<&str, usize>, String> {
+    let mut value_to_partition = std::collections::HashMap::new();
+    for (part_idx, (label, values)) in lists.iter().enumerate() {
+        for value in values {
+            if value_to_partition.insert(value.as_str(), part_idx).is_some() {
+                return Err(format!(
+                    "Value '{}' appears in more than one list (last in '{}')",
+                    value, label
+                ));
             }
         }
+    }
+    Ok(value_to_partition)
+}
+
+/// Partition batches by exact value match on a single column.
+///
+/// Partition `i` receives the rows whose value is in `lists[i]`; rows with a
+/// NULL or unlisted value go to the default partition at `lists.len()`.
+pub fn list_partition_batches(
+    batches: &[RecordBatch],
+    column_name: &str,
+    lists: &[(String, Vec<String>)],
+) -> Result<Vec<Vec<RecordBatch>>, String> {
+    if lists.is_empty() {
+        return Err("At least one list is required".to_string());
+    }
+
+    let value_to_partition = list_value_partitions(lists)?;
+
+    let num_partitions = lists.len() + 1;
+    let default_partition = lists.len();
+    let mut result: Vec<Vec<RecordBatch>> = vec![Vec::new(); num_partitions];
+
+    for batch in batches {
+        if batch.num_rows() == 0 {
+            continue;
+        }
 
-        let schema = batch.schema();
-        for (part_idx, indices) in partition_indices.iter().enumerate() {
-            if indices.is_empty() {
-                continue;
-            }
+        let col_idx = batch
+            .schema()
+            .index_of(column_name)
+            .map_err(|_| format!("Column '{}' not found in schema", column_name))?;
 
-            let indices_array = arrow::array::UInt32Array::from(indices.clone());
-            let columns: Vec<_> = batch
-                .columns()
-                .iter()
-                .map(|col_arr| {
-                    take(col_arr.as_ref(), &indices_array, None)
-                        .map_err(|e| format!("Arrow take error: {e}"))
-                })
-                .collect::<Result<_, _>>()?;
+        let col = batch.column(col_idx);
+        let mut partition_indices: Vec<Vec<u32>> = vec![Vec::new(); num_partitions];
 
-            let part_batch = RecordBatch::try_new(schema.clone(), columns)
-                .map_err(|e| format!("Failed to create partitioned batch: {e}"))?;
-            result[part_idx].push(part_batch);
+        for row in 0..batch.num_rows() {
+            let part_idx = if col.is_null(row) {
+                default_partition
+            } else {
+                let value =
+                    arrow::util::display::array_value_to_string(col, row).unwrap_or_default();
+                value_to_partition
+                    .get(value.as_str())
+                    .copied()
+                    .unwrap_or(default_partition)
+            };
+            partition_indices[part_idx].push(row as u32);
         }
+
+        take_partitions(batch, &partition_indices, &mut result)?;
     }
 
     Ok(result)
 }
 
+/// Append the rows of `batch` selected by `partition_indices[i]` to
+/// `result[i]`, skipping partitions with no rows.
+fn take_partitions(
+    batch: &RecordBatch,
+    partition_indices: &[Vec<u32>],
+    result: &mut [Vec<RecordBatch>],
+) -> Result<(), String> {
+    let schema = batch.schema();
+    for (part_idx, indices) in partition_indices.iter().enumerate() {
+        if indices.is_empty() {
+            continue;
+        }
+
+        let indices_array = arrow::array::UInt32Array::from(indices.clone());
+        let columns: Vec<_> = batch
+            .columns()
+            .iter()
+            .map(|col_arr| {
+                take(col_arr.as_ref(), &indices_array, None)
+                    .map_err(|e| format!("Arrow take error: {e}"))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let part_batch = RecordBatch::try_new(schema.clone(), columns)
+            .map_err(|e| format!("Failed to create partitioned batch: {e}"))?;
+        result[part_idx].push(part_batch);
+    }
+    Ok(())
+}
+
+/// One line per assignment naming the partition's node and any replicas.
+fn summarize_assignments(assignments: &[PartitionAssignment]) -> Vec<String> {
+    assignments
+        .iter()
+        .map(|a| {
+            if a.replicas.is_empty() {
+                format!("  partition {} -> {}", a.partition_id, a.node_name)
+            } else {
+                let replicas: Vec<&str> = a.replicas.iter().map(|r| r.node_name.as_str()).collect();
+                format!(
+                    "  partition {} -> {} (replicas: {})",
+                    a.partition_id,
+                    a.node_name,
+                    replicas.join(", ")
+                )
+            }
+        })
+        .collect()
+}
+
+/// Split `batches` according to `config`, returning the strategy recorded in
+/// the partition metadata along with one batch list per partition.
+fn partition_by_config(
+    config: &PartitionConfig,
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+) -> Result<(PartitionStrategy, Vec<Vec<RecordBatch>>), String> {
+    let strategy = strategy_from_config(config)?;
+    let partitioned = partition_by_strategy(&strategy, schema, batches)?;
+    Ok((strategy, partitioned))
+}
+
+fn strategy_from_config(config: &PartitionConfig) -> Result<PartitionStrategy, String> {
+    Ok(match config.strategy.as_str() {
+        "hash" => {
+            let num_partitions = config
+                .partitions
+                .ok_or("Hash strategy requires 'partitions' field")?;
+            if num_partitions == 0 {
+                return Err("Number of partitions must be > 0".to_string());
+            }
+            PartitionStrategy::Hash {
+                column: config.column.clone(),
+                num_partitions,
+            }
+        }
+        "range" => {
+            let ranges = config
+                .ranges
+                .as_ref()
+                .ok_or("Range strategy requires 'ranges' field")?;
+            if ranges.is_empty() {
+                return Err("At least one range is required".to_string());
+            }
+            PartitionStrategy::Range {
+                column: config.column.clone(),
+                ranges: ranges.clone(),
+            }
+        }
+        "list" => {
+            let lists: Vec<(String, Vec<String>)> = config
+                .lists
+                .as_ref()
+                .ok_or("List strategy requires 'lists' field")?
+                .iter()
+                .map(|l| (l.name.clone(), l.value_strings()))
+                .collect();
+            if lists.is_empty() {
+                return Err("At least one list is required".to_string());
+            }
+            list_value_partitions(&lists)?;
+            PartitionStrategy::List {
+                column: config.column.clone(),
+                lists,
+            }
+        }
+        other => return Err(format!("Unknown partition strategy: '{}'", other)),
+    })
+}
+
+/// Split `batches` into the partitions described by `strategy`. Hashing is
+/// deterministic, so re-running this on a node's rows recovers exactly the
+/// partitions that were sent there.
+pub fn partition_by_strategy(
+    strategy: &PartitionStrategy,
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+) -> Result<Vec<Vec<RecordBatch>>, String> {
+    match strategy {
+        PartitionStrategy::Hash {
+            column,
+            num_partitions,
+        } => {
+            let key_indices =
+                shuffle_partition::resolve_key_indices(schema, std::slice::from_ref(column))
+                    .map_err(|e| format!("Failed to resolve partition column: {e}"))?;
+
+            let mut all_partitions: Vec<Vec<RecordBatch>> = vec![Vec::new(); *num_partitions];
+            for batch in batches {
+                let parts = shuffle_partition::partition_batch(batch, &key_indices, *num_partitions)
+                    .map_err(|e| format!("Hash partitioning failed: {e}"))?;
+                for (i, part) in parts.into_iter().enumerate() {
+                    if part.num_rows() > 0 {
+                        all_partitions[i].push(part);
+                    }
+                }
+            }
+            Ok(all_partitions)
+        }
+        PartitionStrategy::Range { column, ranges } => {
+            range_partition_batches(batches, column, ranges)
+        }
+        PartitionStrategy::List { column, lists } => {
+            list_partition_batches(batches, column, lists)
+        }
+    }
+}
+
 fn read_local_table(table_name: &str) -> Result<(SchemaRef, Vec<RecordBatch>), String> {
     let sql = format!(
         "SELECT * FROM \"{}\"",
@@ -404,113 +749,376 @@ pub fn swarm_partition_table_impl(
         .index_of(&config.column)
         .map_err(|_| format!("Column '{}' not found in table '{}'", config.column, table_name))?;
 
-    let available_nodes = discover_target_nodes()?;
-    if available_nodes.is_empty() {
-        return Err("No active data nodes with Flight endpoints found in cluster".to_string());
+    let metadata = partition_and_distribute(table_name, &config, &schema, &batches)?;
+
+    let local_ep = get_local_flight_endpoint();
+    let coordinator_is_target = local_ep.as_ref().map_or(false, |ep| {
+        metadata.assignments.iter().any(|a| a.flight_endpoint == *ep)
+    });
+    if !coordinator_is_target {
+        drop_local_table(table_name)?;
     }
 
-    let (strategy, partitioned_data) = match config.strategy.as_str() {
-        "hash" => {
-            let num_partitions = config
-                .partitions
-                .ok_or("Hash strategy requires 'partitions' field")?;
-            if num_partitions == 0 {
-                return Err("Number of partitions must be > 0".to_string());
-            }
+    publish_partition_metadata(table_name, &metadata)?;
 
-            let key_indices = shuffle_partition::resolve_key_indices(
-                &schema,
-                &[config.column.clone()],
-            )
-            .map_err(|e| format!("Failed to resolve partition column: {e}"))?;
+    let _ = catalog::advertise_local_tables();
 
-            let mut all_partitions: Vec<Vec<RecordBatch>> = vec![Vec::new(); num_partitions];
-            for batch in &batches {
-                let parts = shuffle_partition::partition_batch(batch, &key_indices, num_partitions)
-                    .map_err(|e| format!("Hash partitioning failed: {e}"))?;
-                for (i, part) in parts.into_iter().enumerate() {
-                    if part.num_rows() > 0 {
-                        all_partitions[i].push(part);
-                    }
-                }
-            }
+    let partition_summary = summarize_assignments(&metadata.assignments);
 
-            let strategy = PartitionStrategy::Hash {
-                column: config.column.clone(),
-                num_partitions,
-            };
-            (strategy, all_partitions)
-        }
-        "range" => {
-            let ranges = config
-                .ranges
-                .as_ref()
-                .ok_or("Range strategy requires 'ranges' field")?;
-            if ranges.is_empty() {
-                return Err("At least one range is required".to_string());
-            }
+    Ok(format!(
+        "Partitioned table '{}' ({} rows) into {} partition(s):\n{}",
+        table_name,
+        total_rows,
+        metadata.assignments.len(),
+        partition_summary.join("\n")
+    ))
+}
 
-            let partitioned = range_partition_batches(&batches, &config.column, ranges)?;
+/// Split `batches` by `config`, assign the partitions to data nodes and
+/// distribute them, returning the metadata to publish once the caller has
+/// cleaned up after itself.
+fn partition_and_distribute(
+    table_name: &str,
+    config: &PartitionConfig,
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+) -> Result<PartitionMetadata, String> {
+    let available_nodes = discover_target_nodes()?;
+    if available_nodes.is_empty() {
+        return Err("No active data nodes with Flight endpoints found in cluster".to_string());
+    }
 
-            let strategy = PartitionStrategy::Range {
-                column: config.column.clone(),
-                ranges: ranges.clone(),
-            };
-            (strategy, partitioned)
-        }
-        other => return Err(format!("Unknown partition strategy: '{}'", other)),
-    };
+    let (strategy, partitioned_data) = partition_by_config(config, schema, batches)?;
 
     let num_partitions = partitioned_data.len();
 
-    let assignments = assign_partitions(
+    let mut assignments = assign_partitions(
         num_partitions,
         &available_nodes,
         config.nodes.as_deref(),
     )?;
+    assign_replicas(
+        &mut assignments,
+        &available_nodes,
+        config.nodes.as_deref(),
+        config.replication_factor.unwrap_or(1),
+    )?;
 
-    let create_sql = generate_create_table_sql(table_name, &schema);
+    let create_sql = generate_create_table_sql(table_name, schema);
 
     with_runtime(|rt| {
         rt.block_on(async {
-            distribute_partitions(
-                table_name,
-                &schema,
-                &create_sql,
-                &assignments,
-                partitioned_data,
-            )
-            .await
+            distribute_partitions(table_name, schema, &assignments, partitioned_data).await
         })
     })?;
 
-    let local_ep = get_local_flight_endpoint();
-    let coordinator_is_target = local_ep.as_ref().map_or(false, |ep| {
-        assignments.iter().any(|a| a.flight_endpoint == *ep)
-    });
-    if !coordinator_is_target {
-        drop_local_table(table_name)?;
-    }
-
-    let metadata = PartitionMetadata {
+    Ok(PartitionMetadata {
         strategy,
-        assignments: assignments.clone(),
+        assignments,
         create_sql,
-    };
-    publish_partition_metadata(table_name, &metadata)?;
+        replication_factor: config.replication_factor.unwrap_or(1),
+        routing_version: 0,
+    })
+}
 
-    let _ = catalog::advertise_local_tables();
+/// What `trex_db_partition_table` would do with a table, estimated from
+/// catalog stats without reading or moving any of its rows.
+#[derive(Debug, Clone)]
+pub struct PartitionPreview {
+    pub strategy: PartitionStrategy,
+    pub assignments: Vec<PartitionAssignment>,
+    /// Estimated rows of each partition, by partition id.
+    pub estimated_rows: Vec<u64>,
+    /// Nodes that partitions may be assigned to, whether or not any are.
+    pub target_nodes: Vec<String>,
+}
+
+impl PartitionPreview {
+    /// Estimated rows `node_name` would hold, counting replicas.
+    pub fn node_rows(&self, node_name: &str) -> u64 {
+        self.assignments
+            .iter()
+            .filter(|a| {
+                a.node_name == node_name || a.replicas.iter().any(|r| r.node_name == node_name)
+            })
+            .map(|a| self.estimated_rows[a.partition_id])
+            .sum()
+    }
+
+    /// Estimated rows of `node_name` over the mean of all target nodes, so
+    /// 1.0 is an even spread and 2.0 a node with twice its share. An empty
+    /// table counts as even.
+    pub fn node_skew(&self, node_name: &str) -> f64 {
+        let total: u64 = self.target_nodes.iter().map(|n| self.node_rows(n)).sum();
+        if total == 0 {
+            return 1.0;
+        }
+        let mean = total as f64 / self.target_nodes.len() as f64;
+        self.node_rows(node_name) as f64 / mean
+    }
+}
+
+/// `trex_db_partition_table_preview`: the partitions `trex_db_partition_table`
+/// would create for the local table `table_name` with `config_json`, the
+/// nodes they would go to and their estimated row counts. The estimate uses
+/// the row count, null count, min/max and hot keys this node gossips for the
+/// table; nothing is moved and no metadata is published.
+pub fn swarm_partition_table_preview_impl(
+    table_name: &str,
+    config_json: &str,
+) -> Result<PartitionPreview, String> {
+    let config: PartitionConfig = serde_json::from_str(config_json)
+        .map_err(|e| format!("Invalid partition config JSON: {e}"))?;
+    let strategy = strategy_from_config(&config)?;
+
+    let (schema, _) = crate::pool::read_arrow(&format!(
+        "SELECT * FROM \"{}\" LIMIT 0",
+        table_name.replace('"', "\"\"")
+    ))?;
+    let field = schema
+        .field_with_name(&config.column)
+        .map_err(|_| format!("Column '{}' not found in table '{}'", config.column, table_name))?;
+
+    let self_node = catalog::get_self_node_name();
+    let shard = catalog::table_stats(table_name)?
+        .into_iter()
+        .find(|s| Some(&s.node_name) == self_node.as_ref())
+        .ok_or_else(|| {
+            format!(
+                "No catalog stats for table '{}' on this node yet; they are gossiped on the next catalog refresh",
+                table_name
+            )
+        })?;
+    let hot_keys = catalog::get_hot_keys()?
+        .remove(table_name)
+        .and_then(|mut columns| columns.remove(&config.column))
+        .unwrap_or_default();
+    let estimated_rows = estimate_partition_rows(
+        &strategy,
+        field.data_type(),
+        shard.row_count,
+        shard.column_stats.get(&config.column),
+        &hot_keys,
+    )?;
 
-    let partition_summary: Vec<String> = assignments
+    let available_nodes = discover_target_nodes()?;
+    let mut assignments = assign_partitions(
+        estimated_rows.len(),
+        &available_nodes,
+        config.nodes.as_deref(),
+    )?;
+    assign_replicas(
+        &mut assignments,
+        &available_nodes,
+        config.nodes.as_deref(),
+        config.replication_factor.unwrap_or(1),
+    )?;
+    let target_nodes = select_target_nodes(&available_nodes, config.nodes.as_deref())?
         .iter()
-        .map(|a| format!("  partition {} -> {}", a.partition_id, a.node_name))
+        .map(|n| n.node_name.clone())
         .collect();
 
+    SwarmLogger::debug(
+        "partition",
+        &format!(
+            "Previewed partitioning of '{}' ({} rows) into {} partition(s)",
+            table_name,
+            shard.row_count,
+            assignments.len()
+        ),
+    );
+
+    Ok(PartitionPreview {
+        strategy,
+        assignments,
+        estimated_rows,
+        target_nodes,
+    })
+}
+
+fn strategy_partition_count(strategy: &PartitionStrategy) -> usize {
+    match strategy {
+        PartitionStrategy::Hash { num_partitions, .. } => *num_partitions,
+        PartitionStrategy::Range { ranges, .. } => ranges.len(),
+        PartitionStrategy::List { lists, .. } => lists.len() + 1,
+    }
+}
+
+/// The partition a single value of the partition column, given in its
+/// display form with `None` for NULL, is routed to.
+fn partition_of_value(
+    strategy: &PartitionStrategy,
+    data_type: &DataType,
+    value: Option<&str>,
+) -> Result<usize, String> {
+    match strategy {
+        PartitionStrategy::Hash { num_partitions, .. } => {
+            let text: arrow::array::ArrayRef = Arc::new(arrow::array::StringArray::from(vec![value]));
+            let key = arrow::compute::cast(&text, data_type).map_err(|e| {
+                format!("Failed to cast '{}' to {data_type}: {e}", value.unwrap_or("NULL"))
+            })?;
+            let schema = Arc::new(arrow::datatypes::Schema::new(vec![
+                arrow::datatypes::Field::new("key", data_type.clone(), true),
+            ]));
+            let batch = RecordBatch::try_new(schema, vec![key])
+                .map_err(|e| format!("Failed to build key batch: {e}"))?;
+            let parts = shuffle_partition::partition_batch(&batch, &[0], *num_partitions)
+                .map_err(|e| format!("Hash partitioning failed: {e}"))?;
+            Ok(parts.iter().position(|p| p.num_rows() > 0).unwrap_or(0))
+        }
+        // A NULL displays as the empty string, as range_partition_batches sees it.
+        PartitionStrategy::Range { ranges, .. } => {
+            Ok(range_partition_of(value.unwrap_or_default(), ranges))
+        }
+        PartitionStrategy::List { lists, .. } => {
+            let value_to_partition = list_value_partitions(lists)?;
+            Ok(value
+                .and_then(|v| value_to_partition.get(v).copied())
+                .unwrap_or(lists.len()))
+        }
+    }
+}
+
+fn stat_f64(value: &catalog::StatValue) -> Option<f64> {
+    match value {
+        catalog::StatValue::Int(v) => Some(*v as f64),
+        catalog::StatValue::Float(v) => Some(*v),
+        catalog::StatValue::Text(_) => None,
+    }
+}
+
+/// Estimated rows per partition of a table with `row_count` rows whose
+/// partition column has type `data_type`. NULLs and `hot_keys` go to the
+/// partition they route to. The other rows are spread over the ranges of a
+/// range strategy by how much of the column's min/max span each covers,
+/// assuming uniform values, and evenly otherwise: stats say nothing of how
+/// a hash or a value list splits them.
+fn estimate_partition_rows(
+    strategy: &PartitionStrategy,
+    data_type: &DataType,
+    row_count: u64,
+    stats: Option<&catalog::ColumnStats>,
+    hot_keys: &[catalog::HotKey],
+) -> Result<Vec<u64>, String> {
+    let mut rows = vec![0u64; strategy_partition_count(strategy)];
+    let mut remaining = row_count;
+
+    let nulls = stats.and_then(|s| s.null_count).unwrap_or(0).min(remaining);
+    if nulls > 0 {
+        rows[partition_of_value(strategy, data_type, None)?] += nulls;
+        remaining -= nulls;
+    }
+    for key in hot_keys {
+        let key_rows = key.rows.min(remaining);
+        let value = key.value.to_string();
+        rows[partition_of_value(strategy, data_type, Some(&value))?] += key_rows;
+        remaining -= key_rows;
+    }
+
+    // Round the running total, so the estimates add up to `row_count`.
+    let weights = spread_weights(strategy, data_type, stats)?;
+    let mut cumulative = 0.0;
+    let mut spread = 0u64;
+    for (partition_rows, weight) in rows.iter_mut().zip(weights) {
+        cumulative += weight;
+        let upto = ((remaining as f64 * cumulative).round() as u64).min(remaining);
+        *partition_rows += upto - spread;
+        spread = upto;
+    }
+    if let Some(last) = rows.last_mut() {
+        *last += remaining - spread;
+    }
+    Ok(rows)
+}
+
+/// Share of the non-NULL, non-hot rows each partition gets; sums to 1.
+fn spread_weights(
+    strategy: &PartitionStrategy,
+    data_type: &DataType,
+    stats: Option<&catalog::ColumnStats>,
+) -> Result<Vec<f64>, String> {
+    let num_partitions = strategy_partition_count(strategy);
+    let even = vec![1.0 / num_partitions as f64; num_partitions];
+    let (PartitionStrategy::Range { ranges, .. }, Some(stats)) = (strategy, stats) else {
+        return Ok(even);
+    };
+    let (Some(min), Some(max)) = (stat_f64(&stats.min), stat_f64(&stats.max)) else {
+        return Ok(even);
+    };
+    if max <= min {
+        let mut weights = vec![0.0; num_partitions];
+        weights[partition_of_value(strategy, data_type, Some(&stats.min.to_string()))?] = 1.0;
+        return Ok(weights);
+    }
+
+    let mut weights = Vec::with_capacity(num_partitions);
+    for range in ranges {
+        let bound = |bound: &Option<serde_json::Value>, unbounded: f64| match bound {
+            None => Some(unbounded),
+            Some(value) => value.as_f64(),
+        };
+        let (Some(lower), Some(upper)) = (
+            bound(&range.lower, f64::NEG_INFINITY),
+            bound(&range.upper, f64::INFINITY),
+        ) else {
+            return Ok(even);
+        };
+        weights.push((upper.min(max) - lower.max(min)).max(0.0) / (max - min));
+    }
+    // Values in no range go to the last one, as they do when partitioning.
+    let covered: f64 = weights.iter().sum();
+    if let Some(last) = weights.last_mut() {
+        *last += (1.0 - covered).max(0.0);
+    }
+    let total: f64 = weights.iter().sum();
+    Ok(weights.into_iter().map(|w| w / total).collect())
+}
+
+/// `trex_db_ctas`: create the distributed table `table_name` from the
+/// result of a query, partitioned by `config_json` as
+/// `trex_db_partition_table` would. The table must not exist yet.
+pub fn swarm_create_table_as_impl(
+    table_name: &str,
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+    config_json: &str,
+) -> Result<String, String> {
+    let config: PartitionConfig = serde_json::from_str(config_json)
+        .map_err(|e| format!("Invalid partition config JSON: {e}"))?;
+
+    if get_partition_metadata(table_name)?.is_some()
+        || catalog::get_all_tables()?
+            .iter()
+            .any(|e| e.table_name.eq_ignore_ascii_case(table_name))
+    {
+        return Err(format!("Table '{}' already exists in the cluster", table_name));
+    }
+    if schema.fields().is_empty() {
+        return Err("The query returns no columns".to_string());
+    }
+    schema
+        .index_of(&config.column)
+        .map_err(|_| format!("Column '{}' not found in the query result", config.column))?;
+
+    SwarmLogger::info(
+        "partition",
+        &format!(
+            "Creating table '{}' from a query with strategy '{}'",
+            table_name, config.strategy
+        ),
+    );
+
+    let total_rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+    let metadata = partition_and_distribute(table_name, &config, schema, batches)?;
+    publish_partition_metadata(table_name, &metadata)?;
+
+    let partition_summary = summarize_assignments(&metadata.assignments);
+
     Ok(format!(
-        "Partitioned table '{}' ({} rows) into {} partition(s):\n{}",
+        "Created table '{}' ({} rows) in {} partition(s):\n{}",
         table_name,
         total_rows,
-        num_partitions,
+        metadata.assignments.len(),
         partition_summary.join("\n")
     ))
 }
@@ -529,195 +1137,1706 @@ pub fn swarm_create_table_impl(
     let table_name = extract_table_name(create_sql)
         .ok_or_else(|| "Could not extract table name from CREATE SQL".to_string())?;
 
+    // Metadata is only published once every node has committed, so on
+    // failure the coordinator's copy is the last thing left to drop.
     match swarm_partition_table_impl(&table_name, config_json) {
-        Ok(msg) => Ok(msg),
+        Ok(msg) => Ok(format!("Created table '{}' on all target nodes. {}", table_name, msg)),
         Err(e) => {
             let _ = drop_local_table(&table_name);  // rollback
-            Err(e)
+            Err(format!("Rolled back creation of table '{}': {}", table_name, e))
         }
     }
 }
 
-pub fn swarm_repartition_table_impl(
-    table_name: &str,
-    config_json: &str,
-) -> Result<String, String> {
-    SwarmLogger::info(
-        "partition",
-        &format!("Repartitioning table '{}'", table_name),
-    );
+/// An `INSERT INTO <table> [(columns)] <query>` statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InsertSelect {
+    pub table_name: String,
+    /// Target columns; empty means all of them, in table order.
+    pub columns: Vec<String>,
+    /// The query producing the rows.
+    pub source_sql: String,
+}
 
-    let entries = catalog::resolve_table(table_name)?;
-    if entries.is_empty() {
-        return Err(format!("Table '{}' not found in cluster catalog", table_name));
+/// `sql` as a single plain INSERT with a source query (`SELECT` or
+/// `VALUES`). Anything else, including inserts with `ON CONFLICT` or
+/// `RETURNING`, is `None` and runs as an ordinary query.
+pub fn parse_insert_select(sql: &str) -> Option<InsertSelect> {
+    use sqlparser::ast::Statement;
+    use sqlparser::dialect::GenericDialect;
+    use sqlparser::parser::Parser;
+
+    let mut statements = Parser::parse_sql(&GenericDialect {}, sql).ok()?;
+    if statements.len() != 1 {
+        return None;
+    }
+    let Statement::Insert(insert) = statements.pop()? else {
+        return None;
+    };
+    if insert.on.is_some()
+        || insert.returning.is_some()
+        || insert.or.is_some()
+        || insert.overwrite
+        || insert.replace_into
+    {
+        return None;
+    }
+    Some(InsertSelect {
+        table_name: insert.table_name.0.last()?.value.clone(),
+        columns: insert.columns.iter().map(|c| c.value.clone()).collect(),
+        source_sql: insert.source?.to_string(),
+    })
+}
+
+/// How `trex_db_partitions` shows a strategy: its kind and partition count.
+pub fn strategy_label(strategy: &PartitionStrategy) -> String {
+    match strategy {
+        PartitionStrategy::Hash { num_partitions, .. } => format!("hash({})", num_partitions),
+        PartitionStrategy::Range { ranges, .. } => format!("range({})", ranges.len()),
+        PartitionStrategy::List { lists, .. } => format!("list({})", lists.len()),
     }
+}
 
-    let shard_endpoints: Vec<(String, String)> = entries
-        .iter()
-        .filter_map(|e| {
-            e.flight_endpoint
-                .as_ref()
-                .map(|ep| (ep.clone(), e.node_name.clone()))
-        })
-        .collect();
+pub fn strategy_column(strategy: &PartitionStrategy) -> &str {
+    match strategy {
+        PartitionStrategy::Hash { column, .. }
+        | PartitionStrategy::Range { column, .. }
+        | PartitionStrategy::List { column, .. } => column,
+    }
+}
 
-    if shard_endpoints.is_empty() {
+/// Rename and cast the columns of a query result to the table columns they
+/// are inserted into, `columns` or all of them in order, so rows hash and
+/// compare as the table's own values do when they are routed.
+fn align_to_table(
+    table_name: &str,
+    table_schema: &SchemaRef,
+    columns: &[String],
+    source_schema: &SchemaRef,
+    batches: &[RecordBatch],
+) -> Result<(SchemaRef, Vec<RecordBatch>), String> {
+    let fields: Vec<arrow::datatypes::Field> = if columns.is_empty() {
+        table_schema.fields().iter().map(|f| f.as_ref().clone()).collect()
+    } else {
+        columns
+            .iter()
+            .map(|c| {
+                table_schema
+                    .fields()
+                    .iter()
+                    .find(|f| f.name().eq_ignore_ascii_case(c))
+                    .map(|f| f.as_ref().clone())
+                    .ok_or_else(|| format!("Column '{}' not found in table '{}'", c, table_name))
+            })
+            .collect::<Result<_, _>>()?
+    };
+    if fields.len() != source_schema.fields().len() {
         return Err(format!(
-            "No Flight endpoints found for table '{}' — cannot gather data",
-            table_name
+            "Table '{}' has {} target column(s) but the query returns {}",
+            table_name,
+            fields.len(),
+            source_schema.fields().len()
         ));
     }
 
-    let (schema, all_batches) = with_runtime(|rt| {
-        rt.block_on(async {
-            gather_table_from_shards(table_name, &shard_endpoints).await
+    let schema: SchemaRef = Arc::new(arrow::datatypes::Schema::new(
+        fields
+            .into_iter()
+            .map(|f| f.with_nullable(true))
+            .collect::<Vec<_>>(),
+    ));
+    let aligned = batches
+        .iter()
+        .map(|batch| {
+            let columns = batch
+                .columns()
+                .iter()
+                .zip(schema.fields())
+                .map(|(column, field)| {
+                    arrow::compute::cast(column, field.data_type()).map_err(|e| {
+                        format!("Cannot insert into column '{}': {e}", field.name())
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            RecordBatch::try_new(schema.clone(), columns)
+                .map_err(|e| format!("Failed to build insert batch: {e}"))
         })
-    })?;
+        .collect::<Result<_, _>>()?;
+    Ok((schema, aligned))
+}
 
-    let total_rows: usize = all_batches.iter().map(|b| b.num_rows()).sum();
-    SwarmLogger::info(
-        "partition",
-        &format!(
-            "Gathered {} rows from {} shard(s) for table '{}'",
-            total_rows,
-            shard_endpoints.len(),
-            table_name,
-        ),
-    );
+/// Rows an insert appends to one table on one node: a primary's copy of the
+/// table or a replica table.
+#[derive(Debug)]
+struct InsertTarget {
+    node_name: String,
+    flight_endpoint: String,
+    table: String,
+    batches: Vec<RecordBatch>,
+}
 
-    with_runtime(|rt| {
-        rt.block_on(async {
-            let mut drop_failures: Vec<String> = Vec::new();
-            for (endpoint, node_name) in &shard_endpoints {
-                let drop_sql = format!(
-                    "DROP TABLE IF EXISTS \"{}\"",
-                    table_name.replace('"', "\"\"")
-                );
-                if let Err(e) = flight_client::execute_remote_sql(endpoint, &drop_sql).await {
-                    drop_failures.push(format!("node '{}': {}", node_name, e));
-                }
-            }
-            if !drop_failures.is_empty() {
-                return Err(format!(
-                    "Aborting repartition of '{}' — failed to drop old shards: {}",
-                    table_name,
-                    drop_failures.join("; ")
-                ));
-            }
-            Ok(())
-        })
-    })?;
+/// Group the rows of each partition by the tables holding a copy of it.
+/// A node that is primary for several partitions gets all their rows in
+/// one target.
+fn insert_targets(
+    table_name: &str,
+    assignments: &[PartitionAssignment],
+    partitioned: Vec<Vec<RecordBatch>>,
+) -> Result<Vec<InsertTarget>, String> {
+    let mut targets: std::collections::BTreeMap<(String, String), InsertTarget> =
+        std::collections::BTreeMap::new();
+    for (partition_id, batches) in partitioned.into_iter().enumerate() {
+        if batches.iter().all(|b| b.num_rows() == 0) {
+            continue;
+        }
+        let assignment = assignments
+            .iter()
+            .find(|a| a.partition_id == partition_id)
+            .ok_or_else(|| {
+                format!("Partition {} of '{}' has no assigned node", partition_id, table_name)
+            })?;
+        let copies = std::iter::once((
+            &assignment.node_name,
+            &assignment.flight_endpoint,
+            table_name.to_string(),
+        ))
+        .chain(assignment.replicas.iter().map(|r| {
+            (
+                &r.node_name,
+                &r.flight_endpoint,
+                replica_table_name(table_name, partition_id),
+            )
+        }));
+        for (node_name, flight_endpoint, table) in copies {
+            targets
+                .entry((flight_endpoint.clone(), table.clone()))
+                .or_insert_with(|| InsertTarget {
+                    node_name: node_name.clone(),
+                    flight_endpoint: flight_endpoint.clone(),
+                    table,
+                    batches: Vec::new(),
+                })
+                .batches
+                .extend(batches.iter().cloned());
+        }
+    }
+    Ok(targets.into_values().collect())
+}
 
-    let _ = remove_partition_metadata(table_name);
+/// Staging table an insert loads a node's rows into before appending them,
+/// hidden from the catalog like a [`pending_table_name`] table.
+fn insert_staging_table_name(table_name: &str) -> String {
+    pending_table_name(&format!("{}__insert", table_name))
+}
 
-    let config: PartitionConfig = serde_json::from_str(config_json)
-        .map_err(|e| format!("Invalid partition config JSON: {e}"))?;
+/// Append each target's rows in two phases, as [`distribute_partitions`]
+/// creates tables: every node first loads its rows into a staging table,
+/// and only once all have them does each append its staging table to the
+/// real one. A failure while loading leaves the table untouched.
+async fn append_to_targets(
+    table_name: &str,
+    schema: &SchemaRef,
+    targets: &[InsertTarget],
+) -> Result<(), String> {
+    let column_list: Vec<String> = schema
+        .fields()
+        .iter()
+        .map(|f| format!("\"{}\"", f.name().replace('"', "\"\"")))
+        .collect();
+    let mut staged: Vec<&InsertTarget> = Vec::new();
+
+    let prepared: Result<(), String> = async {
+        for target in targets {
+            let staging = insert_staging_table_name(&target.table);
+            flight_client::execute_remote_sql(
+                &target.flight_endpoint,
+                &generate_create_table_sql(&staging, schema),
+            )
+            .await?;
+            staged.push(target);
+            let descriptor = ShuffleDescriptor {
+                shuffle_id: format!("insert-{}", staging),
+                join_keys: vec![],
+                num_partitions: 1,
+                partition_targets: vec![ShuffleTarget {
+                    partition_id: 0,
+                    flight_endpoint: target.flight_endpoint.clone(),
+                    node_name: target.node_name.clone(),
+                }],
+                target_table: Some(staging),
+                query_id: None,
+                salt: None,
+            };
+            shuffle_transport::send_partition(
+                &target.flight_endpoint,
+                &descriptor,
+                0,
+                schema.clone(),
+                target.batches.clone(),
+            )
+            .await
+            .map_err(|e| format!("Failed to send rows to '{}': {}", target.node_name, e))?;
+        }
+        Ok(())
+    }
+    .await;
 
-    schema
-        .index_of(&config.column)
-        .map_err(|_| format!("Column '{}' not found in table '{}'", config.column, table_name))?;
+    let drop_staging = |target: &InsertTarget| {
+        let endpoint = target.flight_endpoint.clone();
+        let sql = drop_table_sql(&insert_staging_table_name(&target.table));
+        async move {
+            let _ = flight_client::execute_remote_sql(&endpoint, &sql).await;
+        }
+    };
 
-    let available_nodes = discover_target_nodes()?;
-    if available_nodes.is_empty() {
-        return Err("No active data nodes with Flight endpoints found in cluster".to_string());
+    if let Err(e) = prepared {
+        for target in &staged {
+            drop_staging(target).await;
+        }
+        return Err(format!(
+            "Failed to insert into '{}', rolled back on {} node(s): {}",
+            table_name,
+            staged.len(),
+            e
+        ));
     }
 
-    let (strategy, partitioned_data) = match config.strategy.as_str() {
-        "hash" => {
-            let num_partitions = config
-                .partitions
-                .ok_or("Hash strategy requires 'partitions' field")?;
-            if num_partitions == 0 {
-                return Err("Number of partitions must be > 0".to_string());
+    for (i, target) in targets.iter().enumerate() {
+        let append_sql = format!(
+            "INSERT INTO \"{}\" ({}) SELECT * FROM \"{}\"",
+            target.table.replace('"', "\"\""),
+            column_list.join(", "),
+            insert_staging_table_name(&target.table).replace('"', "\"\"")
+        );
+        if let Err(e) = flight_client::execute_remote_sql(&target.flight_endpoint, &append_sql).await {
+            for pending in &targets[i..] {
+                drop_staging(pending).await;
             }
+            return Err(format!(
+                "Insert into '{}' failed on node '{}' after appending to {} of {} table copies: {}",
+                table_name,
+                target.node_name,
+                i,
+                targets.len(),
+                e
+            ));
+        }
+        drop_staging(target).await;
+    }
 
-            let key_indices = shuffle_partition::resolve_key_indices(
-                &schema,
-                &[config.column.clone()],
-            )
-            .map_err(|e| format!("Failed to resolve partition column: {e}"))?;
+    refresh_node_catalogs(
+        Some(table_name),
+        targets
+            .iter()
+            .map(|t| (t.node_name.as_str(), t.flight_endpoint.as_str())),
+    )
+    .await;
+    Ok(())
+}
 
-            let mut all_partitions: Vec<Vec<RecordBatch>> = vec![Vec::new(); num_partitions];
-            for batch in &all_batches {
-                let parts = shuffle_partition::partition_batch(batch, &key_indices, num_partitions)
-                    .map_err(|e| format!("Hash partitioning failed: {e}"))?;
-                for (i, part) in parts.into_iter().enumerate() {
-                    if part.num_rows() > 0 {
-                        all_partitions[i].push(part);
-                    }
+/// Have each node re-advertise its tables now instead of after the next
+/// gossip round, up to 30s later. Nodes sharing an endpoint are refreshed
+/// once, and a node that can't be reached is logged and left to gossip.
+/// With `written`, this node just changed the nodes' copies of that table;
+/// see [`refresh_written_copy`].
+async fn refresh_node_catalogs<N: AsRef<str>, E: AsRef<str>>(
+    written: Option<&str>,
+    nodes: impl IntoIterator<Item = (N, E)>,
+) {
+    let mut refreshed = std::collections::HashSet::new();
+    for (node_name, endpoint) in nodes {
+        let (node_name, endpoint) = (node_name.as_ref(), endpoint.as_ref());
+        if !refreshed.insert(endpoint.to_string()) {
+            continue;
+        }
+        let result = match written {
+            Some(table_name) => refresh_written_copy(table_name, node_name, endpoint).await,
+            None => flight_client::refresh_remote_catalog(endpoint)
+                .await
+                .map(|_| ()),
+        };
+        if let Err(e) = result {
+            SwarmLogger::warn(
+                "partition",
+                &format!("Failed to trigger catalog refresh on node '{}': {}", node_name, e),
+            );
+        }
+    }
+}
+
+/// Have `node_name` re-advertise its tables after this node changed its
+/// copy of `table_name`. Until stats from that refresh reach gossip, the
+/// ones gossiped before the change no longer prune the copy: rows written
+/// past their bounds would otherwise go unread.
+async fn refresh_written_copy(
+    table_name: &str,
+    node_name: &str,
+    endpoint: &str,
+) -> crate::error::SwarmResult<()> {
+    let gossiped = catalog::gossiped_stats_version(table_name, node_name);
+    catalog::expect_stats_version(table_name, node_name, gossiped + 1);
+    let version = flight_client::refresh_remote_catalog(endpoint).await?;
+    catalog::expect_stats_version(table_name, node_name, version);
+    Ok(())
+}
+
+/// `INSERT INTO` a partitioned table: route every row of the query result
+/// to the one partition the table's strategy puts it in, and append it to
+/// that partition's primary and replicas. Returns the rows inserted.
+pub fn swarm_insert_impl(
+    insert: &InsertSelect,
+    metadata: &PartitionMetadata,
+    schema: &SchemaRef,
+    batches: &[RecordBatch],
+) -> Result<u64, String> {
+    let table_name = insert.table_name.as_str();
+    let endpoint = &metadata
+        .assignments
+        .first()
+        .ok_or_else(|| format!("Table '{}' has no partitions", table_name))?
+        .flight_endpoint;
+    let schema_sql = format!(
+        "SELECT * FROM \"{}\" LIMIT 1",
+        catalog::escape_identifier(table_name)
+    );
+
+    with_runtime(|rt| {
+        rt.block_on(async {
+            let (table_schema, _) = flight_client::query_node_with_schema(endpoint, &schema_sql)
+                .await
+                .map_err(|e| format!("Failed to fetch schema of '{}': {}", table_name, e))?;
+            let (schema, batches) =
+                align_to_table(table_name, &table_schema, &insert.columns, schema, batches)?;
+
+            let column = strategy_column(&metadata.strategy);
+            if schema.index_of(column).is_err() {
+                return Err(format!(
+                    "INSERT into '{}' must set its partition column '{}'",
+                    table_name, column
+                ));
+            }
+            let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+            let partitioned = partition_by_strategy(&metadata.strategy, &schema, &batches)?;
+            let targets = insert_targets(table_name, &metadata.assignments, partitioned)?;
+            append_to_targets(table_name, &schema, &targets).await?;
+
+            SwarmLogger::info(
+                "partition",
+                &format!(
+                    "Inserted {} row(s) into '{}' across {} table copies",
+                    rows,
+                    table_name,
+                    targets.len()
+                ),
+            );
+            Ok(rows as u64)
+        })
+    })
+}
+
+/// `trex_db_attach_table` config: which file and table backs the table on
+/// each node.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttachConfig {
+    pub nodes: Vec<AttachSource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttachSource {
+    pub node: String,
+    /// DuckDB file on that node's filesystem.
+    pub path: String,
+    /// Table inside the file.
+    pub table: String,
+}
+
+/// Catalog the source file of `table_name` is attached under on each node.
+pub fn attached_database_name(table_name: &str) -> String {
+    format!("{}__source", table_name)
+}
+
+/// Column list of `schema` in SQL types, as reported in schema mismatches.
+fn describe_schema(schema: &SchemaRef) -> String {
+    schema
+        .fields()
+        .iter()
+        .map(|f| format!("{} {}", f.name(), arrow_type_to_sql(f.data_type())))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The first node whose schema differs from the first node's, as an error
+/// naming both. `schemas` holds `(node_name, schema)`.
+fn check_attached_schemas(table_name: &str, schemas: &[(String, SchemaRef)]) -> Result<(), String> {
+    let Some((first_node, first)) = schemas.first() else {
+        return Ok(());
+    };
+    let expected = catalog::compute_schema_hash(first);
+    for (node, schema) in &schemas[1..] {
+        if catalog::compute_schema_hash(schema) != expected {
+            return Err(format!(
+                "Schema mismatch for table '{}': node '{}' has ({}) but node '{}' has ({})",
+                table_name,
+                node,
+                describe_schema(schema),
+                first_node,
+                describe_schema(first)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Expose tables in existing DuckDB files as the shards of `table_name`,
+/// without copying their rows.
+///
+/// Each listed node attaches its file read-only and gets a view named
+/// `table_name` over the table inside it, which the catalog advertises like
+/// any local table and Flight scans read in place. Every node's schema is
+/// checked against the first's before any view is created; a mismatch, or
+/// any other failure, detaches the files again on every node.
+pub fn swarm_attach_table_impl(table_name: &str, config_json: &str) -> Result<String, String> {
+    let config: AttachConfig = serde_json::from_str(config_json)
+        .map_err(|e| format!("Invalid attach config JSON: {e}"))?;
+    if config.nodes.is_empty() {
+        return Err("Attach config must list at least one node".to_string());
+    }
+
+    SwarmLogger::info(
+        "partition",
+        &format!(
+            "Attaching table '{}' from {} node(s)",
+            table_name,
+            config.nodes.len()
+        ),
+    );
+
+    let available_nodes = discover_target_nodes()?;
+    let mut seen = std::collections::HashSet::new();
+    let mut sources = Vec::with_capacity(config.nodes.len());
+    for source in &config.nodes {
+        if !seen.insert(source.node.as_str()) {
+            return Err(format!("Node '{}' is listed more than once", source.node));
+        }
+        let target = available_nodes
+            .iter()
+            .find(|n| n.node_name == source.node)
+            .ok_or_else(|| {
+                format!(
+                    "Node '{}' is not an active data node with a Flight endpoint",
+                    source.node
+                )
+            })?;
+        sources.push((source, target.flight_endpoint.clone()));
+    }
+
+    let database = attached_database_name(table_name).replace('"', "\"\"");
+    let detach_sql = format!("DETACH DATABASE IF EXISTS \"{}\"", database);
+
+    with_runtime(|rt| {
+        rt.block_on(async {
+            let result = async {
+                let mut schemas = Vec::with_capacity(sources.len());
+                for (source, endpoint) in &sources {
+                    let attach_sql = format!(
+                        "ATTACH IF NOT EXISTS '{}' AS \"{}\" (READ_ONLY)",
+                        source.path.replace('\'', "''"),
+                        database
+                    );
+                    flight_client::execute_remote_sql(endpoint, &attach_sql)
+                        .await
+                        .map_err(|e| {
+                            format!(
+                                "Failed to attach '{}' on node '{}': {}",
+                                source.path, source.node, e
+                            )
+                        })?;
+                    let schema_sql = format!(
+                        "SELECT * FROM \"{}\".\"{}\" LIMIT 0",
+                        database,
+                        source.table.replace('"', "\"\"")
+                    );
+                    let (schema, _) = flight_client::query_node_with_schema(endpoint, &schema_sql)
+                        .await
+                        .map_err(|e| {
+                            format!(
+                                "Failed to read table '{}' from '{}' on node '{}': {}",
+                                source.table, source.path, source.node, e
+                            )
+                        })?;
+                    schemas.push((source.node.clone(), schema));
+                }
+                check_attached_schemas(table_name, &schemas)?;
+
+                for (source, endpoint) in &sources {
+                    let view_sql = format!(
+                        "CREATE OR REPLACE VIEW \"{}\" AS SELECT * FROM \"{}\".\"{}\"",
+                        table_name.replace('"', "\"\""),
+                        database,
+                        source.table.replace('"', "\"\"")
+                    );
+                    flight_client::execute_remote_sql(endpoint, &view_sql)
+                        .await
+                        .map_err(|e| {
+                            format!(
+                                "Failed to create view '{}' on node '{}': {}",
+                                table_name, source.node, e
+                            )
+                        })?;
                 }
+                Ok::<(), String>(())
             }
+            .await;
 
-            let strategy = PartitionStrategy::Hash {
-                column: config.column.clone(),
+            if let Err(e) = result {
+                let drop_view_sql = format!(
+                    "DROP VIEW IF EXISTS \"{}\"",
+                    table_name.replace('"', "\"\"")
+                );
+                for (_, endpoint) in &sources {
+                    let _ = flight_client::execute_remote_sql(endpoint, &drop_view_sql).await;
+                    let _ = flight_client::execute_remote_sql(endpoint, &detach_sql).await;
+                }
+                return Err(e);
+            }
+
+            refresh_node_catalogs(
+                None,
+                sources
+                    .iter()
+                    .map(|(source, endpoint)| (source.node.as_str(), endpoint.as_str())),
+            )
+            .await;
+            Ok(())
+        })
+    })?;
+
+    let _ = catalog::advertise_local_tables();
+
+    let summary: Vec<String> = config
+        .nodes
+        .iter()
+        .map(|s| format!("  {} -> {} ({})", s.node, s.path, s.table))
+        .collect();
+    Ok(format!(
+        "Attached table '{}' on {} node(s):\n{}",
+        table_name,
+        config.nodes.len(),
+        summary.join("\n")
+    ))
+}
+
+pub fn swarm_repartition_table_impl(
+    table_name: &str,
+    config_json: &str,
+) -> Result<String, String> {
+    SwarmLogger::info(
+        "partition",
+        &format!("Repartitioning table '{}'", table_name),
+    );
+
+    let entries = catalog::resolve_table(table_name)?;
+    if entries.is_empty() {
+        return Err(format!("Table '{}' not found in cluster catalog", table_name));
+    }
+    let old_metadata = get_partition_metadata(table_name).ok().flatten();
+
+    let shard_endpoints: Vec<(String, String)> = entries
+        .iter()
+        .filter_map(|e| {
+            e.flight_endpoint
+                .as_ref()
+                .map(|ep| (ep.clone(), e.node_name.clone()))
+        })
+        .collect();
+
+    if shard_endpoints.is_empty() {
+        return Err(format!(
+            "No Flight endpoints found for table '{}' — cannot gather data",
+            table_name
+        ));
+    }
+
+    let (schema, all_batches) = with_runtime(|rt| {
+        rt.block_on(async {
+            gather_table_from_shards(table_name, &shard_endpoints).await
+        })
+    })?;
+
+    let total_rows: usize = all_batches.iter().map(|b| b.num_rows()).sum();
+    SwarmLogger::info(
+        "partition",
+        &format!(
+            "Gathered {} rows from {} shard(s) for table '{}'",
+            total_rows,
+            shard_endpoints.len(),
+            table_name,
+        ),
+    );
+
+    with_runtime(|rt| {
+        rt.block_on(async {
+            let mut drop_failures: Vec<String> = Vec::new();
+            for (endpoint, node_name) in &shard_endpoints {
+                let drop_sql = format!(
+                    "DROP TABLE IF EXISTS \"{}\"",
+                    table_name.replace('"', "\"\"")
+                );
+                if let Err(e) = flight_client::execute_remote_sql(endpoint, &drop_sql).await {
+                    drop_failures.push(format!("node '{}': {}", node_name, e));
+                }
+            }
+            if let Some(old) = &old_metadata {
+                for assignment in &old.assignments {
+                    for replica in &assignment.replicas {
+                        let drop_sql = format!(
+                            "DROP TABLE IF EXISTS \"{}\"",
+                            replica_table_name(table_name, assignment.partition_id)
+                                .replace('"', "\"\"")
+                        );
+                        if let Err(e) =
+                            flight_client::execute_remote_sql(&replica.flight_endpoint, &drop_sql).await
+                        {
+                            drop_failures.push(format!("node '{}': {}", replica.node_name, e));
+                        }
+                    }
+                }
+            }
+            if !drop_failures.is_empty() {
+                return Err(format!(
+                    "Aborting repartition of '{}' — failed to drop old shards: {}",
+                    table_name,
+                    drop_failures.join("; ")
+                ));
+            }
+            Ok(())
+        })
+    })?;
+
+    let _ = remove_partition_metadata(table_name);
+
+    let config: PartitionConfig = serde_json::from_str(config_json)
+        .map_err(|e| format!("Invalid partition config JSON: {e}"))?;
+
+    schema
+        .index_of(&config.column)
+        .map_err(|_| format!("Column '{}' not found in table '{}'", config.column, table_name))?;
+
+    let available_nodes = discover_target_nodes()?;
+    if available_nodes.is_empty() {
+        return Err("No active data nodes with Flight endpoints found in cluster".to_string());
+    }
+
+    let (strategy, partitioned_data) = partition_by_config(&config, &schema, &all_batches)?;
+
+    let num_partitions = partitioned_data.len();
+
+    let mut assignments = assign_partitions(
+        num_partitions,
+        &available_nodes,
+        config.nodes.as_deref(),
+    )?;
+    assign_replicas(
+        &mut assignments,
+        &available_nodes,
+        config.nodes.as_deref(),
+        config.replication_factor.unwrap_or(1),
+    )?;
+
+    let create_sql = generate_create_table_sql(table_name, &schema);
+
+    with_runtime(|rt| {
+        rt.block_on(async {
+            distribute_partitions(table_name, &schema, &assignments, partitioned_data).await
+        })
+    })?;
+
+    let metadata = PartitionMetadata {
+        strategy,
+        assignments: assignments.clone(),
+        create_sql,
+        replication_factor: config.replication_factor.unwrap_or(1),
+        routing_version: 0,
+    };
+    publish_partition_metadata(table_name, &metadata)?;
+
+    let _ = catalog::advertise_local_tables();
+
+    let partition_summary = summarize_assignments(&assignments);
+
+    Ok(format!(
+        "Repartitioned table '{}' ({} rows) into {} partition(s):\n{}",
+        table_name,
+        total_rows,
+        num_partitions,
+        partition_summary.join("\n")
+    ))
+}
+
+/// Nodes within this fraction of the mean load count as balanced.
+const REBALANCE_TOLERANCE: f64 = 0.1;
+
+/// A partition relocation chosen by [`plan_rebalance`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartitionMove {
+    pub partition_id: usize,
+    pub from_node: String,
+    pub to_node: String,
+}
+
+/// Choose partition moves that bring every node's load within `tolerance`
+/// of the mean. `partition_rows` holds `(partition_id, node_name, rows)` of
+/// each primary and `replicas` the `(partition_id, node_name)` of each
+/// replica; `nodes` lists every node that may receive data, including
+/// empty ones.
+///
+/// Replicas stay where they are but count towards their node's load, and a
+/// primary never moves onto a node holding one of its replicas, which would
+/// leave both copies on the same node. Each step moves a partition from the
+/// most loaded node that can shed one to the least loaded node that can
+/// take it, choosing the size that best halves the gap between them, and
+/// only while that strictly narrows the gap, so the plan terminates and
+/// stays small.
+pub fn plan_rebalance(
+    partition_rows: &[(usize, String, u64)],
+    replicas: &[(usize, String)],
+    nodes: &[String],
+    tolerance: f64,
+) -> Vec<PartitionMove> {
+    let mut location: std::collections::BTreeMap<usize, (String, u64)> = partition_rows
+        .iter()
+        .map(|(id, node, rows)| (*id, (node.clone(), *rows)))
+        .collect();
+    let original: std::collections::BTreeMap<usize, String> = location
+        .iter()
+        .map(|(id, (node, _))| (*id, node.clone()))
+        .collect();
+
+    let mut load: std::collections::BTreeMap<String, u64> =
+        nodes.iter().map(|n| (n.clone(), 0)).collect();
+    for (node, rows) in location.values() {
+        *load.entry(node.clone()).or_insert(0) += rows;
+    }
+    for (partition_id, node) in replicas {
+        let rows = location.get(partition_id).map_or(0, |(_, rows)| *rows);
+        *load.entry(node.clone()).or_insert(0) += rows;
+    }
+    if load.len() < 2 {
+        return Vec::new();
+    }
+    let holds_replica =
+        |partition_id: usize, node: &str| replicas.iter().any(|(id, n)| *id == partition_id && n == node);
+
+    let total: u64 = load.values().sum();
+    let limit = (total as f64 / load.len() as f64) * (1.0 + tolerance);
+
+    for _ in 0..location.len() * load.len() {
+        // Nodes over the limit, most loaded first, as sources, and every
+        // other node, least loaded first, as targets. A node loaded only
+        // with replicas has nothing to give, so the next source is tried.
+        let mut by_load: Vec<(String, u64)> = load.iter().map(|(n, l)| (n.clone(), *l)).collect();
+        by_load.sort_by_key(|(_, l)| std::cmp::Reverse(*l));
+        let step = by_load
+            .iter()
+            .take_while(|(_, l)| (*l as f64) > limit)
+            .find_map(|(max_node, max_load)| {
+                by_load.iter().rev().filter(|(n, _)| n != max_node).find_map(|(min_node, min_load)| {
+                    let gap = max_load - min_load;
+                    location
+                        .iter()
+                        .filter(|(id, (node, rows))| {
+                            node == max_node && *rows > 0 && *rows < gap && !holds_replica(**id, min_node)
+                        })
+                        .min_by_key(|(_, (_, rows))| (2 * *rows).abs_diff(gap))
+                        .map(|(id, (_, rows))| (*id, *rows, max_node.clone(), min_node.clone()))
+                })
+            });
+        let Some((partition_id, rows, max_node, min_node)) = step else {
+            break;
+        };
+
+        location.insert(partition_id, (min_node.clone(), rows));
+        *load.get_mut(&max_node).unwrap() -= rows;
+        *load.get_mut(&min_node).unwrap() += rows;
+    }
+
+    location
+        .into_iter()
+        .filter_map(|(partition_id, (node, _))| {
+            let from = &original[&partition_id];
+            (*from != node).then(|| PartitionMove {
+                partition_id,
+                from_node: from.clone(),
+                to_node: node,
+            })
+        })
+        .collect()
+}
+
+pub fn swarm_rebalance_table_impl(table_name: &str) -> Result<String, String> {
+    SwarmLogger::info(
+        "partition",
+        &format!("Rebalancing table '{}'", table_name),
+    );
+
+    let mut metadata = get_partition_metadata(table_name)?
+        .ok_or_else(|| format!("Table '{}' is not partitioned", table_name))?;
+
+    // Row counts are per node; split them evenly over that node's partitions.
+    let entries = catalog::resolve_table(table_name)?;
+    let mut partitions_per_node: std::collections::HashMap<&str, u64> =
+        std::collections::HashMap::new();
+    for a in &metadata.assignments {
+        *partitions_per_node.entry(a.node_name.as_str()).or_insert(0) += 1;
+    }
+    let partition_rows: Vec<(usize, String, u64)> = metadata
+        .assignments
+        .iter()
+        .map(|a| {
+            let node_rows = entries
+                .iter()
+                .find(|e| e.node_name == a.node_name)
+                .map(|e| e.approx_rows)
+                .unwrap_or(0);
+            let rows = node_rows / partitions_per_node[a.node_name.as_str()];
+            (a.partition_id, a.node_name.clone(), rows)
+        })
+        .collect();
+
+    let available_nodes = discover_target_nodes()?;
+    for a in &metadata.assignments {
+        if !available_nodes.iter().any(|n| n.node_name == a.node_name) {
+            return Err(format!(
+                "Node '{}' holding partition {} of '{}' is not available",
+                a.node_name, a.partition_id, table_name
+            ));
+        }
+    }
+    let node_names: Vec<String> = available_nodes.iter().map(|n| n.node_name.clone()).collect();
+
+    let replicas: Vec<(usize, String)> = metadata
+        .assignments
+        .iter()
+        .flat_map(|a| a.replicas.iter().map(|r| (a.partition_id, r.node_name.clone())))
+        .collect();
+
+    let moves = plan_rebalance(&partition_rows, &replicas, &node_names, REBALANCE_TOLERANCE);
+    if moves.is_empty() {
+        return Ok(format!("Table '{}' is already balanced", table_name));
+    }
+
+    let endpoint_of = |node: &str| {
+        available_nodes
+            .iter()
+            .find(|n| n.node_name == node)
+            .map(|n| n.flight_endpoint.clone())
+            .unwrap()
+    };
+
+    let mut source_nodes: Vec<&str> = moves.iter().map(|m| m.from_node.as_str()).collect();
+    source_nodes.sort_unstable();
+    source_nodes.dedup();
+
+    // Assignments are updated after each source node so later steps see
+    // partitions that already arrived on a node.
+    let mut done: Vec<&PartitionMove> = Vec::new();
+    let mut touched: Vec<String> = Vec::new();
+    let result = with_runtime(|rt| {
+        rt.block_on(async {
+            for source in &source_nodes {
+                let node_moves: Vec<&PartitionMove> =
+                    moves.iter().filter(|m| m.from_node == *source).collect();
+                rebalance_source_node(table_name, &metadata, source, &node_moves, &endpoint_of)
+                    .await?;
+
+                for m in node_moves {
+                    if let Some(a) = metadata
+                        .assignments
+                        .iter_mut()
+                        .find(|a| a.partition_id == m.partition_id)
+                    {
+                        a.node_name = m.to_node.clone();
+                        a.flight_endpoint = endpoint_of(&m.to_node);
+                    }
+                    for node in [&m.from_node, &m.to_node] {
+                        if !touched.contains(node) {
+                            touched.push(node.clone());
+                        }
+                    }
+                    done.push(m);
+                }
+            }
+            Ok(())
+        })
+    });
+
+    // Publish once, covering whatever moved even on failure, so the
+    // metadata always matches where the rows are.
+    if !done.is_empty() {
+        publish_partition_metadata(table_name, &metadata)?;
+    }
+
+    let _ = with_runtime(|rt| {
+        rt.block_on(async {
+            refresh_node_catalogs(
+                Some(table_name),
+                touched
+                    .iter()
+                    .map(|node| (node.as_str(), endpoint_of(node))),
+            )
+            .await;
+        });
+        Ok(())
+    });
+    let _ = catalog::advertise_local_tables();
+
+    let summary: Vec<String> = done
+        .iter()
+        .map(|m| format!("  partition {}: {} -> {}", m.partition_id, m.from_node, m.to_node))
+        .collect();
+
+    match result {
+        Ok(()) => Ok(format!(
+            "Rebalanced table '{}': moved {} partition(s):\n{}",
+            table_name,
+            done.len(),
+            summary.join("\n")
+        )),
+        Err(e) if done.is_empty() => Err(e),
+        Err(e) => Err(format!(
+            "{} (completed moves before the failure:\n{})",
+            e,
+            summary.join("\n")
+        )),
+    }
+}
+
+/// Move `node_moves` off `source`: read its rows, ship each moving partition
+/// to its target, then rewrite the source table with the partitions it keeps.
+async fn rebalance_source_node(
+    table_name: &str,
+    metadata: &PartitionMetadata,
+    source: &str,
+    node_moves: &[&PartitionMove],
+    endpoint_of: &impl Fn(&str) -> String,
+) -> Result<(), String> {
+    let source_endpoint = endpoint_of(source);
+    let (schema, batches) = gather_table_from_shards(
+        table_name,
+        &[(source_endpoint.clone(), source.to_string())],
+    )
+    .await?;
+    let mut partitioned = partition_by_strategy(&metadata.strategy, &schema, &batches)?;
+    let num_partitions = partitioned.len();
+
+    let send = |endpoint: String, node: String, partition_id: usize, data: Vec<RecordBatch>| {
+        let schema = schema.clone();
+        async move {
+            if data.iter().all(|b| b.num_rows() == 0) {
+                return Ok(());
+            }
+            let descriptor = ShuffleDescriptor {
+                shuffle_id: format!("rebalance-{}-{}", table_name, partition_id),
+                join_keys: vec![],
                 num_partitions,
+                partition_targets: vec![ShuffleTarget {
+                    partition_id,
+                    flight_endpoint: endpoint.clone(),
+                    node_name: node.clone(),
+                }],
+                target_table: Some(table_name.to_string()),
+                query_id: None,
+                salt: None,
             };
-            (strategy, all_partitions)
+            shuffle_transport::send_partition(&endpoint, &descriptor, partition_id, schema, data)
+                .await
+                .map_err(|e| format!("Failed to send partition {} to '{}': {}", partition_id, node, e))
         }
-        "range" => {
-            let ranges = config
-                .ranges
-                .as_ref()
-                .ok_or("Range strategy requires 'ranges' field")?;
-            if ranges.is_empty() {
-                return Err("At least one range is required".to_string());
+    };
+
+    for m in node_moves {
+        // create_sql replaces the table, so only run it where none exists yet.
+        let target_holds_table = metadata
+            .assignments
+            .iter()
+            .any(|a| a.node_name == m.to_node);
+        let target_endpoint = endpoint_of(&m.to_node);
+        if !target_holds_table {
+            let create_if_missing = metadata.create_sql.replacen(
+                "CREATE OR REPLACE TABLE",
+                "CREATE TABLE IF NOT EXISTS",
+                1,
+            );
+            flight_client::execute_remote_sql(&target_endpoint, &create_if_missing)
+                .await
+                .map_err(|e| {
+                    format!("Failed to create table '{}' on node '{}': {}", table_name, m.to_node, e)
+                })?;
+        }
+        let data = std::mem::take(partitioned.get_mut(m.partition_id).ok_or_else(|| {
+            format!("Partition {} out of range for '{}'", m.partition_id, table_name)
+        })?);
+        send(target_endpoint, m.to_node.clone(), m.partition_id, data).await?;
+    }
+
+    let kept: Vec<usize> = metadata
+        .assignments
+        .iter()
+        .filter(|a| a.node_name == source && !node_moves.iter().any(|m| m.partition_id == a.partition_id))
+        .map(|a| a.partition_id)
+        .collect();
+    if kept.is_empty() {
+        let drop_sql = format!(
+            "DROP TABLE IF EXISTS \"{}\"",
+            table_name.replace('"', "\"\"")
+        );
+        return flight_client::execute_remote_sql(&source_endpoint, &drop_sql)
+            .await
+            .map_err(String::from);
+    }
+
+    flight_client::execute_remote_sql(&source_endpoint, &metadata.create_sql)
+        .await
+        .map_err(|e| format!("Failed to rewrite table '{}' on node '{}': {}", table_name, source, e))?;
+    for partition_id in kept {
+        let data = std::mem::take(&mut partitioned[partition_id]);
+        send(source_endpoint.clone(), source.to_string(), partition_id, data).await?;
+    }
+    Ok(())
+}
+
+/// How one partition that had a copy on a killed node gets its copies back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaRepair {
+    pub partition_id: usize,
+    /// Node whose copy the rows are read from.
+    pub source_node: String,
+    /// Whether that copy is a replica table rather than the primary table.
+    pub source_is_replica: bool,
+    /// Replica that becomes the primary, when the killed node was primary.
+    pub promoted: Option<String>,
+    /// Node receiving a new replica; `None` when every node already holds
+    /// a copy and the partition stays under-replicated.
+    pub new_replica: Option<String>,
+}
+
+/// Plan the repairs of `assignments` after `killed` left the cluster.
+/// Partitions without replicas are skipped, as are partitions whose only
+/// copy was on the killed node. New replicas go to the first of `nodes`
+/// (rotated by partition id) that holds no copy yet.
+pub fn plan_rereplication(
+    assignments: &[PartitionAssignment],
+    killed: &str,
+    nodes: &[String],
+) -> Vec<ReplicaRepair> {
+    let mut repairs = Vec::new();
+    for a in assignments {
+        if a.replicas.is_empty() {
+            continue;
+        }
+        let survivors: Vec<&str> = a
+            .replicas
+            .iter()
+            .map(|r| r.node_name.as_str())
+            .filter(|n| *n != killed)
+            .collect();
+        let (primary, promoted) = if a.node_name == killed {
+            match survivors.first() {
+                Some(first) => (*first, Some(first.to_string())),
+                None => continue,
             }
+        } else if survivors.len() < a.replicas.len() {
+            (a.node_name.as_str(), None)
+        } else {
+            continue;
+        };
+
+        let (source_node, source_is_replica) = match (&promoted, survivors.first()) {
+            (Some(p), _) => (p.clone(), true),
+            (None, Some(replica)) => (replica.to_string(), true),
+            (None, None) => (primary.to_string(), false),
+        };
+
+        let holders: Vec<&str> = std::iter::once(primary)
+            .chain(survivors.iter().copied().filter(|n| Some(*n) != promoted.as_deref()))
+            .collect();
+        let new_replica = (0..nodes.len())
+            .map(|i| &nodes[(a.partition_id + i) % nodes.len()])
+            .find(|n| n.as_str() != killed && !holders.contains(&n.as_str()))
+            .cloned();
+
+        repairs.push(ReplicaRepair {
+            partition_id: a.partition_id,
+            source_node,
+            source_is_replica,
+            promoted,
+            new_replica,
+        });
+    }
+    repairs
+}
+
+/// Remove `node_name` from the cluster, then restore the copies of every
+/// replicated partition it held from the surviving ones.
+pub fn swarm_kill_node_impl(node_name: &str) -> Result<String, String> {
+    // The killed node's own gossip state, which may carry partition
+    // metadata, disappears from view with it, so read the metadata first.
+    let tables = get_all_partition_metadata()?;
+    if !GossipRegistry::instance().kill_node(node_name)? {
+        return Ok(format!("Node '{}' was already removed", node_name));
+    }
+    SwarmLogger::warn("partition", &format!("Node '{}' killed", node_name));
+
+    let available_nodes = discover_target_nodes()?;
+    let node_names: Vec<String> = available_nodes.iter().map(|n| n.node_name.clone()).collect();
+    let endpoint_of = |node: &str| {
+        available_nodes
+            .iter()
+            .find(|n| n.node_name == node)
+            .map(|n| n.flight_endpoint.clone())
+            .ok_or_else(|| format!("Node '{}' is not available", node))
+    };
+
+    let mut summary: Vec<String> = Vec::new();
+    let mut failures: Vec<String> = Vec::new();
+    let mut touched: Vec<String> = Vec::new();
+    for (table_name, mut metadata) in tables {
+        let references_node = metadata.assignments.iter().any(|a| {
+            a.node_name == node_name || a.replicas.iter().any(|r| r.node_name == node_name)
+        });
+        if !references_node {
+            continue;
+        }
+        let repairs = plan_rereplication(&metadata.assignments, node_name, &node_names);
+        for a in &metadata.assignments {
+            if a.node_name == node_name && !repairs.iter().any(|r| r.partition_id == a.partition_id) {
+                failures.push(format!(
+                    "  {}[{}]: no surviving copy",
+                    table_name, a.partition_id
+                ));
+            }
+        }
+
+        let mut repaired = 0;
+        for repair in &repairs {
+            let result = with_runtime(|rt| {
+                rt.block_on(repair_partition(&table_name, &metadata, repair, &endpoint_of))
+            });
+            match result {
+                Ok(()) => {
+                    apply_repair(&mut metadata, node_name, repair, &endpoint_of)?;
+                    for node in std::iter::once(&repair.source_node)
+                        .chain(repair.new_replica.iter())
+                    {
+                        if !touched.contains(node) {
+                            touched.push(node.clone());
+                        }
+                    }
+                    summary.push(describe_repair(&table_name, repair));
+                    repaired += 1;
+                }
+                Err(e) => failures.push(format!("  {}[{}]: {}", table_name, repair.partition_id, e)),
+            }
+        }
+        if repaired > 0 {
+            publish_partition_metadata(&table_name, &metadata)?;
+        }
+    }
+
+    let _ = with_runtime(|rt| {
+        rt.block_on(async {
+            refresh_node_catalogs(
+                None,
+                touched
+                    .iter()
+                    .filter_map(|node| Some((node.as_str(), endpoint_of(node).ok()?))),
+            )
+            .await;
+        });
+        Ok(())
+    });
+    let _ = catalog::advertise_local_tables();
+
+    let mut response = format!("Killed node '{}'", node_name);
+    if !summary.is_empty() {
+        response.push_str(&format!(
+            "; re-replicated {} partition(s):\n{}",
+            summary.len(),
+            summary.join("\n")
+        ));
+    }
+    if !failures.is_empty() {
+        response.push_str(&format!(
+            "\nCould not restore {} partition(s):\n{}",
+            failures.len(),
+            failures.join("\n")
+        ));
+    }
+    Ok(response)
+}
+
+fn describe_repair(table_name: &str, repair: &ReplicaRepair) -> String {
+    let mut line = format!("  {}[{}]:", table_name, repair.partition_id);
+    if let Some(promoted) = &repair.promoted {
+        line.push_str(&format!(" promoted '{}'", promoted));
+    }
+    match &repair.new_replica {
+        Some(node) => line.push_str(&format!(" new replica on '{}'", node)),
+        None => line.push_str(" no free node for a new replica"),
+    }
+    line
+}
+
+/// Copy the rows of one partition to where `repair` needs them: into the
+/// promoted node's primary table and into a new replica table.
+async fn repair_partition(
+    table_name: &str,
+    metadata: &PartitionMetadata,
+    repair: &ReplicaRepair,
+    endpoint_of: &impl Fn(&str) -> Result<String, String>,
+) -> Result<(), String> {
+    let partition_id = repair.partition_id;
+    let replica_table = replica_table_name(table_name, partition_id);
+    let source_endpoint = endpoint_of(&repair.source_node)?;
+    let (schema, data) = if repair.source_is_replica {
+        gather_table_from_shards(
+            &replica_table,
+            &[(source_endpoint.clone(), repair.source_node.clone())],
+        )
+        .await?
+    } else {
+        let (schema, batches) = gather_table_from_shards(
+            table_name,
+            &[(source_endpoint.clone(), repair.source_node.clone())],
+        )
+        .await?;
+        let mut partitioned = partition_by_strategy(&metadata.strategy, &schema, &batches)?;
+        if partition_id >= partitioned.len() {
+            return Err(format!("Partition {} out of range", partition_id));
+        }
+        (schema, std::mem::take(&mut partitioned[partition_id]))
+    };
+    let num_partitions = metadata.assignments.len();
+
+    let send = |endpoint: String, node: String, target_table: String, batches: Vec<RecordBatch>| {
+        let schema = schema.clone();
+        async move {
+            if batches.iter().all(|b| b.num_rows() == 0) {
+                return Ok(());
+            }
+            let descriptor = ShuffleDescriptor {
+                shuffle_id: format!("rereplicate-{}-{}", table_name, partition_id),
+                join_keys: vec![],
+                num_partitions,
+                partition_targets: vec![ShuffleTarget {
+                    partition_id,
+                    flight_endpoint: endpoint.clone(),
+                    node_name: node.clone(),
+                }],
+                target_table: Some(target_table),
+                query_id: None,
+                salt: None,
+            };
+            shuffle_transport::send_partition(&endpoint, &descriptor, partition_id, schema, batches)
+                .await
+                .map_err(|e| format!("Failed to send partition {} to '{}': {}", partition_id, node, e))
+        }
+    };
+
+    if let Some(promoted) = &repair.promoted {
+        let create_if_missing = metadata.create_sql.replacen(
+            "CREATE OR REPLACE TABLE",
+            "CREATE TABLE IF NOT EXISTS",
+            1,
+        );
+        flight_client::execute_remote_sql(&source_endpoint, &create_if_missing)
+            .await
+            .map_err(|e| format!("Failed to create table '{}' on node '{}': {}", table_name, promoted, e))?;
+        send(
+            source_endpoint.clone(),
+            promoted.clone(),
+            table_name.to_string(),
+            data.clone(),
+        )
+        .await?;
+        let drop_sql = format!(
+            "DROP TABLE IF EXISTS \"{}\"",
+            replica_table.replace('"', "\"\"")
+        );
+        flight_client::execute_remote_sql(&source_endpoint, &drop_sql).await?;
+    }
+
+    if let Some(target) = &repair.new_replica {
+        let target_endpoint = endpoint_of(target)?;
+        flight_client::execute_remote_sql(
+            &target_endpoint,
+            &generate_create_table_sql(&replica_table, &schema),
+        )
+        .await
+        .map_err(|e| format!("Failed to create '{}' on node '{}': {}", replica_table, target, e))?;
+        send(target_endpoint, target.clone(), replica_table.clone(), data).await?;
+    }
+    Ok(())
+}
+
+/// Record a completed repair in `metadata`.
+fn apply_repair(
+    metadata: &mut PartitionMetadata,
+    killed: &str,
+    repair: &ReplicaRepair,
+    endpoint_of: &impl Fn(&str) -> Result<String, String>,
+) -> Result<(), String> {
+    let Some(a) = metadata
+        .assignments
+        .iter_mut()
+        .find(|a| a.partition_id == repair.partition_id)
+    else {
+        return Ok(());
+    };
+    a.replicas.retain(|r| r.node_name != killed);
+    if let Some(promoted) = &repair.promoted {
+        a.replicas.retain(|r| &r.node_name != promoted);
+        a.node_name = promoted.clone();
+        a.flight_endpoint = endpoint_of(promoted)?;
+    }
+    if let Some(target) = &repair.new_replica {
+        a.replicas.push(ReplicaAssignment {
+            node_name: target.clone(),
+            flight_endpoint: endpoint_of(target)?,
+        });
+    }
+    Ok(())
+}
+
+/// Replica copies one partition gains and loses to reach a new replication
+/// factor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationChange {
+    pub partition_id: usize,
+    /// Nodes receiving a new replica.
+    pub added: Vec<String>,
+    /// Nodes whose replica is dropped.
+    pub removed: Vec<String>,
+}
+
+/// Plan the replicas that give every partition `replication_factor`
+/// copies, counting the primary. New replicas go to the nodes that follow
+/// the primary in `nodes`, as [`assign_replicas`] places them; surplus
+/// replicas are dropped newest first and the primary always stays.
+/// Partitions already at the factor are left out.
+pub fn plan_replication_change(
+    assignments: &[PartitionAssignment],
+    nodes: &[String],
+    replication_factor: usize,
+) -> Result<Vec<ReplicationChange>, String> {
+    if replication_factor == 0 {
+        return Err("replication_factor must be at least 1".to_string());
+    }
+    if replication_factor > nodes.len() {
+        return Err(format!(
+            "replication_factor {} exceeds the {} available node(s)",
+            replication_factor,
+            nodes.len()
+        ));
+    }
+
+    let wanted = replication_factor - 1;
+    let mut changes = Vec::new();
+    for a in assignments {
+        let (added, removed) = if a.replicas.len() < wanted {
+            let start = nodes
+                .iter()
+                .position(|n| *n == a.node_name)
+                .unwrap_or(a.partition_id);
+            let added = (1..=nodes.len())
+                .map(|offset| &nodes[(start + offset) % nodes.len()])
+                .filter(|n| {
+                    **n != a.node_name && !a.replicas.iter().any(|r| r.node_name == **n)
+                })
+                .take(wanted - a.replicas.len())
+                .cloned()
+                .collect();
+            (added, Vec::new())
+        } else if a.replicas.len() > wanted {
+            let removed = a.replicas[wanted..].iter().map(|r| r.node_name.clone()).collect();
+            (Vec::new(), removed)
+        } else {
+            continue;
+        };
+        changes.push(ReplicationChange {
+            partition_id: a.partition_id,
+            added,
+            removed,
+        });
+    }
+    Ok(changes)
+}
+
+/// Set the number of copies of every partition of `table_name`, counting
+/// the primary, copying partitions to new replicas over Flight or dropping
+/// surplus ones.
+///
+/// Queries only read replicas the partition metadata lists. A new replica
+/// is listed once its rows are in place; a dropped one is unlisted first,
+/// and its table goes only after the queries running here have finished.
+pub fn swarm_set_replication_factor_impl(
+    table_name: &str,
+    replication_factor: usize,
+) -> Result<String, String> {
+    SwarmLogger::info(
+        "partition",
+        &format!(
+            "Setting replication factor of '{}' to {}",
+            table_name, replication_factor
+        ),
+    );
+
+    let mut metadata = get_partition_metadata(table_name)?
+        .ok_or_else(|| format!("Table '{}' is not partitioned", table_name))?;
+    let available_nodes = discover_target_nodes()?;
+    let node_names: Vec<String> = available_nodes.iter().map(|n| n.node_name.clone()).collect();
+    let endpoint_of = |node: &str| {
+        available_nodes
+            .iter()
+            .find(|n| n.node_name == node)
+            .map(|n| n.flight_endpoint.clone())
+            .ok_or_else(|| format!("Node '{}' is not available", node))
+    };
 
-            let partitioned = range_partition_batches(&all_batches, &config.column, ranges)?;
+    let changes = plan_replication_change(&metadata.assignments, &node_names, replication_factor)?;
+    if changes.is_empty() {
+        return Ok(format!(
+            "Table '{}' already has replication factor {}",
+            table_name, replication_factor
+        ));
+    }
 
-            let strategy = PartitionStrategy::Range {
-                column: config.column.clone(),
-                ranges: ranges.clone(),
+    let mut summary: Vec<String> = Vec::new();
+    let mut failures: Vec<String> = Vec::new();
+    let mut touched: Vec<String> = Vec::new();
+    let mut drops: Vec<(String, usize)> = Vec::new();
+    for change in &changes {
+        let mut created: Vec<String> = Vec::new();
+        for node in &change.added {
+            let Some(a) = metadata
+                .assignments
+                .iter()
+                .find(|a| a.partition_id == change.partition_id)
+            else {
+                continue;
             };
-            (strategy, partitioned)
+            // A replica holds exactly the partition, so read one if it can.
+            let source_replica = a
+                .replicas
+                .iter()
+                .find(|r| node_names.contains(&r.node_name))
+                .map(|r| r.node_name.clone());
+            let repair = ReplicaRepair {
+                partition_id: change.partition_id,
+                source_node: source_replica.clone().unwrap_or_else(|| a.node_name.clone()),
+                source_is_replica: source_replica.is_some(),
+                promoted: None,
+                new_replica: Some(node.clone()),
+            };
+            let result = with_runtime(|rt| {
+                rt.block_on(repair_partition(table_name, &metadata, &repair, &endpoint_of))
+            });
+            match result {
+                Ok(()) => {
+                    let flight_endpoint = endpoint_of(node)?;
+                    if let Some(a) = metadata
+                        .assignments
+                        .iter_mut()
+                        .find(|a| a.partition_id == change.partition_id)
+                    {
+                        a.replicas.push(ReplicaAssignment {
+                            node_name: node.clone(),
+                            flight_endpoint,
+                        });
+                    }
+                    if !touched.contains(node) {
+                        touched.push(node.clone());
+                    }
+                    created.push(node.clone());
+                }
+                Err(e) => failures.push(format!(
+                    "  partition {}: replica on '{}': {}",
+                    change.partition_id, node, e
+                )),
+            }
         }
-        other => return Err(format!("Unknown partition strategy: '{}'", other)),
-    };
-
-    let num_partitions = partitioned_data.len();
 
-    let assignments = assign_partitions(
-        num_partitions,
-        &available_nodes,
-        config.nodes.as_deref(),
-    )?;
+        if let Some(a) = metadata
+            .assignments
+            .iter_mut()
+            .find(|a| a.partition_id == change.partition_id)
+        {
+            a.replicas.retain(|r| !change.removed.contains(&r.node_name));
+        }
+        drops.extend(change.removed.iter().map(|node| (node.clone(), change.partition_id)));
+        summary.push(describe_replication_change(
+            change.partition_id,
+            &created,
+            &change.removed,
+        ));
+    }
 
-    let create_sql = generate_create_table_sql(table_name, &schema);
+    metadata.replication_factor = replication_factor;
+    if !drops.is_empty() {
+        metadata.routing_version += 1;
+    }
+    publish_partition_metadata(table_name, &metadata)?;
+    if crate::distributed_scheduler::is_scheduler_running() {
+        if let Err(e) = crate::distributed_scheduler::refresh_session() {
+            SwarmLogger::warn("partition", &format!("Failed to refresh session: {}", e));
+        }
+    }
 
-    with_runtime(|rt| {
+    let _ = with_runtime(|rt| {
         rt.block_on(async {
-            distribute_partitions(
-                table_name,
-                &schema,
-                &create_sql,
-                &assignments,
-                partitioned_data,
+            // Any node may plan queries over the replicas, so each must stop
+            // routing to them and finish what it started before they go.
+            if !drops.is_empty() {
+                drain_cluster_routes(table_name, metadata.routing_version).await;
+            }
+            for (node, partition_id) in &drops {
+                let result = match endpoint_of(node) {
+                    Ok(endpoint) => flight_client::execute_remote_sql(
+                        &endpoint,
+                        &drop_table_sql(&replica_table_name(table_name, *partition_id)),
+                    )
+                    .await
+                    .map_err(String::from),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(_) => {
+                        if !touched.contains(node) {
+                            touched.push(node.clone());
+                        }
+                    }
+                    Err(e) => failures.push(format!(
+                        "  partition {}: dropping replica on '{}': {}",
+                        partition_id, node, e
+                    )),
+                }
+            }
+            refresh_node_catalogs(
+                None,
+                touched
+                    .iter()
+                    .filter_map(|node| Some((node.as_str(), endpoint_of(node).ok()?))),
             )
-            .await
-        })
-    })?;
+            .await;
+        });
+        Ok(())
+    });
+    let _ = catalog::advertise_local_tables();
 
-    let metadata = PartitionMetadata {
-        strategy,
-        assignments: assignments.clone(),
-        create_sql,
-    };
-    publish_partition_metadata(table_name, &metadata)?;
+    let mut response = format!(
+        "Set replication factor of '{}' to {}:\n{}",
+        table_name,
+        replication_factor,
+        summary.join("\n")
+    );
+    if !failures.is_empty() {
+        response.push_str(&format!(
+            "\n{} failure(s):\n{}",
+            failures.len(),
+            failures.join("\n")
+        ));
+    }
+    Ok(response)
+}
 
-    let _ = catalog::advertise_local_tables();
+/// How long dropping copies waits for every node to stop using them.
+const ROUTE_DRAIN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 
-    let partition_summary: Vec<String> = assignments
-        .iter()
-        .map(|a| format!("  partition {} -> {}", a.partition_id, a.node_name))
-        .collect();
+/// Have every node with a Flight server route `table_name` by metadata of
+/// at least `routing_version` and finish the queries it planned before.
+/// Nodes that don't answer in time are logged and not waited for.
+async fn drain_cluster_routes(table_name: &str, routing_version: u64) {
+    let endpoints = match catalog::flight_endpoints_by_node() {
+        Ok(endpoints) => endpoints,
+        Err(e) => {
+            SwarmLogger::warn("partition", &format!("Failed to list nodes to drain: {}", e));
+            return;
+        }
+    };
+    let drains = endpoints.into_iter().map(|(node, endpoint)| async move {
+        let drained = flight_client::drain_remote_routes(
+            &endpoint,
+            table_name,
+            routing_version,
+            ROUTE_DRAIN_TIMEOUT,
+        )
+        .await;
+        (node, drained)
+    });
+    for (node, drained) in futures::future::join_all(drains).await {
+        match drained {
+            Ok(true) => {}
+            Ok(false) => SwarmLogger::warn(
+                "partition",
+                &format!(
+                    "Node '{}' still ran queries on '{}' after {}s; dropping replicas anyway",
+                    node,
+                    table_name,
+                    ROUTE_DRAIN_TIMEOUT.as_secs()
+                ),
+            ),
+            Err(e) => SwarmLogger::warn(
+                "partition",
+                &format!("Failed to drain '{}' on node '{}': {}", table_name, node, e),
+            ),
+        }
+    }
+}
 
-    Ok(format!(
-        "Repartitioned table '{}' ({} rows) into {} partition(s):\n{}",
-        table_name,
-        total_rows,
-        num_partitions,
-        partition_summary.join("\n")
-    ))
+/// Wait until this node has seen metadata of `table_name` with at least
+/// `routing_version`, rebuild its session on it, and wait for the queries
+/// planned on the old one. Serves the Flight `drain_routes` action. True
+/// when all of it happened within `timeout`.
+pub fn drain_routes(table_name: &str, routing_version: u64, timeout: std::time::Duration) -> bool {
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        let seen = get_partition_metadata(table_name)
+            .ok()
+            .flatten()
+            .is_some_and(|m| m.routing_version >= routing_version);
+        if seen {
+            break;
+        }
+        if std::time::Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+    if crate::distributed_scheduler::is_scheduler_running() {
+        if let Err(e) = crate::distributed_scheduler::refresh_session() {
+            SwarmLogger::warn("partition", &format!("Failed to refresh session: {}", e));
+            return false;
+        }
+    }
+    crate::distributed_scheduler::wait_for_active_queries(
+        deadline.saturating_duration_since(std::time::Instant::now()),
+    )
+}
+
+fn describe_replication_change(partition_id: usize, created: &[String], removed: &[String]) -> String {
+    let count = |nodes: &[String]| {
+        if nodes.is_empty() {
+            "0".to_string()
+        } else {
+            format!("{} ({})", nodes.len(), nodes.join(", "))
+        }
+    };
+    format!(
+        "  partition {}: created {}, removed {}",
+        partition_id,
+        count(created),
+        count(removed)
+    )
 }
 
 /// Return the Flight endpoint of the local node, if available.
@@ -730,42 +2849,137 @@ fn get_local_flight_endpoint() -> Option<String> {
         .and_then(|e| e.flight_endpoint.clone())
 }
 
-/// Distribute partitioned data to remote nodes via Flight.
+/// One node's copy of a table being created: its primary table or one of
+/// its replica tables.
+struct StagedTable {
+    flight_endpoint: String,
+    node_name: String,
+    table: String,
+}
+
+fn drop_table_sql(table_name: &str) -> String {
+    format!("DROP TABLE IF EXISTS \"{}\"", table_name.replace('"', "\"\""))
+}
+
+/// Distribute partitioned data to remote nodes via Flight, in two phases so
+/// the table appears on every target node or on none.
+///
+/// Prepare loads each node's rows into [`pending_table_name`] staging
+/// tables, which the catalog does not advertise. Only once every node has
+/// its data does commit rename the staging tables to their real names. A
+/// failure in either phase drops everything this call created, and the
+/// error says how many nodes were rolled back.
 async fn distribute_partitions(
     table_name: &str,
     schema: &SchemaRef,
-    create_sql: &str,
     assignments: &[PartitionAssignment],
     partitioned_data: Vec<Vec<RecordBatch>>,
 ) -> Result<(), String> {
-    let mut created_on: Vec<String> = Vec::new(); // for rollback
-
-    let mut unique_endpoints: Vec<(String, String)> = Vec::new();
-    let mut seen_endpoints = std::collections::HashSet::new();
-    for assignment in assignments {
-        if seen_endpoints.insert(assignment.flight_endpoint.clone()) {
-            unique_endpoints.push((
-                assignment.flight_endpoint.clone(),
-                assignment.node_name.clone(),
-            ));
+    let mut staged: Vec<StagedTable> = Vec::new();
+
+    if let Err(e) =
+        prepare_partitions(table_name, schema, assignments, &partitioned_data, &mut staged).await
+    {
+        for table in &staged {
+            let _ = flight_client::execute_remote_sql(
+                &table.flight_endpoint,
+                &drop_table_sql(&pending_table_name(&table.table)),
+            )
+            .await;
         }
+        return Err(format!(
+            "Failed to distribute table '{}', rolled back on {} node(s): {}",
+            table_name,
+            count_nodes(&staged),
+            e
+        ));
     }
 
-    for (endpoint, node_name) in &unique_endpoints {
-        if let Err(e) = flight_client::execute_remote_sql(endpoint, create_sql).await {
-            for rollback_ep in &created_on {  // rollback
-                let drop_sql = format!(
-                    "DROP TABLE IF EXISTS \"{}\"",
-                    table_name.replace('"', "\"\"")
-                );
-                let _ = flight_client::execute_remote_sql(rollback_ep, &drop_sql).await;
+    for (i, table) in staged.iter().enumerate() {
+        if let Err(e) = commit_staged_table(table).await {
+            for committed in &staged[..i] {
+                let _ = flight_client::execute_remote_sql(
+                    &committed.flight_endpoint,
+                    &drop_table_sql(&committed.table),
+                )
+                .await;
+            }
+            for pending in &staged[i..] {
+                let _ = flight_client::execute_remote_sql(
+                    &pending.flight_endpoint,
+                    &drop_table_sql(&pending_table_name(&pending.table)),
+                )
+                .await;
             }
             return Err(format!(
-                "Failed to create table '{}' on node '{}': {}",
-                table_name, node_name, e
+                "Failed to commit table '{}', rolled back on {} node(s): commit of '{}' on node '{}' failed: {}",
+                table_name,
+                count_nodes(&staged),
+                table.table,
+                table.node_name,
+                e
             ));
         }
-        created_on.push(endpoint.clone());
+    }
+
+    SwarmLogger::debug(
+        "partition",
+        &format!(
+            "Committed table '{}' on {} node(s)",
+            table_name,
+            count_nodes(&staged)
+        ),
+    );
+
+    refresh_node_catalogs(
+        None,
+        staged
+            .iter()
+            .map(|t| (t.node_name.as_str(), t.flight_endpoint.as_str())),
+    )
+    .await;
+
+    Ok(())
+}
+
+fn count_nodes(staged: &[StagedTable]) -> usize {
+    staged
+        .iter()
+        .map(|t| t.flight_endpoint.as_str())
+        .collect::<std::collections::HashSet<_>>()
+        .len()
+}
+
+/// Prepare phase of [`distribute_partitions`]: create the staging tables
+/// and load every partition and replica into them. Each staging table is
+/// pushed to `staged` as soon as it exists, so the caller can drop it.
+async fn prepare_partitions(
+    table_name: &str,
+    schema: &SchemaRef,
+    assignments: &[PartitionAssignment],
+    partitioned_data: &[Vec<RecordBatch>],
+    staged: &mut Vec<StagedTable>,
+) -> Result<(), String> {
+    let pending_create_sql = generate_create_table_sql(&pending_table_name(table_name), schema);
+
+    let mut seen_endpoints = std::collections::HashSet::new();
+    for assignment in assignments {
+        if !seen_endpoints.insert(assignment.flight_endpoint.clone()) {
+            continue;
+        }
+        flight_client::execute_remote_sql(&assignment.flight_endpoint, &pending_create_sql)
+            .await
+            .map_err(|e| {
+                format!(
+                    "Failed to create table '{}' on node '{}': {}",
+                    table_name, assignment.node_name, e
+                )
+            })?;
+        staged.push(StagedTable {
+            flight_endpoint: assignment.flight_endpoint.clone(),
+            node_name: assignment.node_name.clone(),
+            table: table_name.to_string(),
+        });
     }
 
     for assignment in assignments {
@@ -790,10 +3004,12 @@ async fn distribute_partitions(
                 flight_endpoint: assignment.flight_endpoint.clone(),
                 node_name: assignment.node_name.clone(),
             }],
-            target_table: Some(table_name.to_string()),
+            target_table: Some(pending_table_name(table_name)),
+            query_id: None,
+            salt: None,
         };
 
-        if let Err(e) = shuffle_transport::send_partition(
+        shuffle_transport::send_partition(
             &assignment.flight_endpoint,
             &descriptor,
             partition_id,
@@ -801,80 +3017,353 @@ async fn distribute_partitions(
             partition_batches.clone(),
         )
         .await
-        {
-            // rollback
-            for rollback_ep in &created_on {
-                let drop_sql = format!(
-                    "DROP TABLE IF EXISTS \"{}\"",
-                    table_name.replace('"', "\"\"")
+        .map_err(|e| {
+            format!(
+                "Failed to send partition {} to '{}': {}",
+                partition_id, assignment.node_name, e
+            )
+        })?;
+
+        SwarmLogger::debug(
+            "partition",
+            &format!(
+                "Sent partition {} ({} batches) to {}",
+                partition_id,
+                partition_batches.len(),
+                assignment.node_name,
+            ),
+        );
+    }
+
+    for assignment in assignments {
+        let partition_id = assignment.partition_id;
+        if assignment.replicas.is_empty() || partition_id >= partitioned_data.len() {
+            continue;
+        }
+        let replica_table = replica_table_name(table_name, partition_id);
+        let pending_replica = pending_table_name(&replica_table);
+        let replica_create_sql = generate_create_table_sql(&pending_replica, schema);
+        let partition_batches = &partitioned_data[partition_id];
+
+        for replica in &assignment.replicas {
+            let result = async {
+                flight_client::execute_remote_sql(&replica.flight_endpoint, &replica_create_sql)
+                    .await?;
+                staged.push(StagedTable {
+                    flight_endpoint: replica.flight_endpoint.clone(),
+                    node_name: replica.node_name.clone(),
+                    table: replica_table.clone(),
+                });
+                if partition_batches.iter().all(|b| b.num_rows() == 0) {
+                    return Ok(());
+                }
+                let descriptor = ShuffleDescriptor {
+                    shuffle_id: format!("replica-{}-{}", table_name, partition_id),
+                    join_keys: vec![],
+                    num_partitions: partitioned_data.len(),
+                    partition_targets: vec![ShuffleTarget {
+                        partition_id,
+                        flight_endpoint: replica.flight_endpoint.clone(),
+                        node_name: replica.node_name.clone(),
+                    }],
+                    target_table: Some(pending_replica.clone()),
+                    query_id: None,
+                    salt: None,
+                };
+                shuffle_transport::send_partition(
+                    &replica.flight_endpoint,
+                    &descriptor,
+                    partition_id,
+                    schema.clone(),
+                    partition_batches.clone(),
+                )
+                .await
+            }
+            .await;
+
+            result.map_err(|e| {
+                format!(
+                    "Failed to replicate partition {} to '{}': {}",
+                    partition_id, replica.node_name, e
+                )
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Commit phase of [`distribute_partitions`] for one staging table: replace
+/// any table already under the real name, as `CREATE OR REPLACE` would.
+async fn commit_staged_table(table: &StagedTable) -> Result<(), String> {
+    flight_client::execute_remote_sql(&table.flight_endpoint, &drop_table_sql(&table.table))
+        .await?;
+    let rename_sql = format!(
+        "ALTER TABLE \"{}\" RENAME TO \"{}\"",
+        pending_table_name(&table.table).replace('"', "\"\""),
+        table.table.replace('"', "\"\"")
+    );
+    flight_client::execute_remote_sql(&table.flight_endpoint, &rename_sql).await?;
+    Ok(())
+}
+
+/// Gather all data for a table from multiple shards via DoGet.
+async fn gather_table_from_shards(
+    table_name: &str,
+    shard_endpoints: &[(String, String)],
+) -> Result<(SchemaRef, Vec<RecordBatch>), String> {
+    let sql = format!(
+        "SELECT * FROM \"{}\"",
+        table_name.replace('"', "\"\"")
+    );
+
+    let mut all_batches = Vec::new();
+    let mut schema: Option<SchemaRef> = None;
+
+    for (endpoint, node_name) in shard_endpoints {
+        let (shard_schema, shard_batches) =
+            flight_client::query_node_with_schema(endpoint, &sql).await.map_err(|e| {
+                format!(
+                    "Failed to gather data from node '{}' ({}): {}",
+                    node_name, endpoint, e
+                )
+            })?;
+
+        if schema.is_none() {
+            schema = Some(shard_schema);
+        }
+
+        all_batches.extend(shard_batches);
+    }
+
+    let schema = schema.unwrap_or_else(|| Arc::new(arrow::datatypes::Schema::empty()));
+
+    Ok((schema, all_batches))
+}
+
+/// What one node's `vacuum_table` action compacted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VacuumReport {
+    /// Local tables rewritten: the node's copy of the table and its replicas.
+    pub tables: Vec<String>,
+    pub row_groups_before: u64,
+    pub row_groups_after: u64,
+    /// Used database bytes before the rewrite and after the checkpoint.
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+impl VacuumReport {
+    pub fn reclaimed_bytes(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// Compact `table_name` on every data node, one node at a time, and return a
+/// line per node with the space it reclaimed.
+pub fn swarm_vacuum_table_impl(table_name: &str) -> Result<String, String> {
+    SwarmLogger::info("partition", &format!("Vacuuming table '{}'", table_name));
+
+    if catalog::resolve_table(table_name)?.is_empty() {
+        return Err(format!("Table '{}' not found in catalog", table_name));
+    }
+    let nodes = discover_target_nodes()?;
+    if nodes.is_empty() {
+        return Err("No data nodes available".to_string());
+    }
+
+    // Sequential, so the other copies of a replicated partition keep serving
+    // while one node rewrites its copy.
+    let results = with_runtime(|rt| {
+        Ok(rt.block_on(async {
+            let mut results = Vec::with_capacity(nodes.len());
+            for node in &nodes {
+                let report = flight_client::vacuum_remote_table(&node.flight_endpoint, table_name)
+                    .await
+                    .map_err(String::from)
+                    .and_then(|body| {
+                        serde_json::from_str::<VacuumReport>(&body)
+                            .map_err(|e| format!("Invalid vacuum report: {e}"))
+                    });
+                if let Err(e) = &report {
+                    SwarmLogger::warn(
+                        "partition",
+                        &format!("Vacuum of '{}' failed on {}: {e}", table_name, node.node_name),
+                    );
+                }
+                results.push((node.node_name.clone(), report));
+            }
+            results
+        }))
+    })?;
+
+    Ok(format_vacuum_summary(table_name, &results))
+}
+
+fn format_vacuum_summary(table_name: &str, results: &[(String, Result<VacuumReport, String>)]) -> String {
+    let compacted = results
+        .iter()
+        .filter(|(_, r)| matches!(r, Ok(report) if !report.tables.is_empty()))
+        .count();
+    let reclaimed: u64 = results
+        .iter()
+        .filter_map(|(_, r)| r.as_ref().ok())
+        .map(VacuumReport::reclaimed_bytes)
+        .sum();
+
+    let mut lines = vec![format!(
+        "Vacuumed '{}' on {} of {} node(s), reclaimed {} bytes",
+        table_name,
+        compacted,
+        results.len(),
+        reclaimed,
+    )];
+    for (node, result) in results {
+        lines.push(match result {
+            Ok(report) if report.tables.is_empty() => format!("{}: no local copy", node),
+            Ok(report) => format!(
+                "{}: {} table(s), {} -> {} row group(s), reclaimed {} bytes",
+                node,
+                report.tables.len(),
+                report.row_groups_before,
+                report.row_groups_after,
+                report.reclaimed_bytes(),
+            ),
+            Err(e) => format!("{}: error: {}", node, e),
+        });
+    }
+    lines.join("\n")
+}
+
+/// Local tables holding rows of `table_name`: the table and its replicas.
+fn vacuum_targets(table_name: &str, local_tables: &[String]) -> Vec<String> {
+    local_tables
+        .iter()
+        .filter(|name| {
+            name.as_str() == table_name
+                || parse_replica_table_name(name).is_some_and(|(base, _)| base == table_name)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Rewrite the local copies of `table_name` into fresh row groups and
+/// checkpoint so the freed blocks are reclaimed.
+///
+/// Each table is copied aside, emptied and refilled in one transaction that
+/// only commits when the row count matches, so readers keep seeing the old
+/// rows until the commit and a failure loses nothing.
+pub fn vacuum_local_table(table_name: &str) -> Result<VacuumReport, String> {
+    crate::local_connections::with_connection(|conn| {
+        let local_tables = query_strings(conn, "SHOW TABLES")?;
+        let targets = vacuum_targets(table_name, &local_tables);
+
+        let mut report = VacuumReport {
+            tables: targets.clone(),
+            bytes_before: used_database_bytes(conn)?,
+            ..Default::default()
+        };
+        for table in &targets {
+            report.row_groups_before += row_group_count(conn, table)?;
+            rewrite_table(conn, table)?;
+        }
+        if !targets.is_empty() {
+            // Blocks of the old row groups are freed at the next checkpoint;
+            // if another writer blocks it, the automatic one frees them later.
+            if let Err(e) = conn.execute_batch("CHECKPOINT") {
+                SwarmLogger::warn(
+                    "partition",
+                    &format!("Checkpoint after vacuuming '{}' failed: {e}", table_name),
                 );
-                let _ = flight_client::execute_remote_sql(rollback_ep, &drop_sql).await;
             }
-            return Err(format!(
-                "Failed to send partition {} to '{}': {}",
-                partition_id, assignment.node_name, e
-            ));
         }
+        for table in &targets {
+            report.row_groups_after += row_group_count(conn, table)?;
+        }
+        report.bytes_after = used_database_bytes(conn)?;
 
-        SwarmLogger::debug(
+        SwarmLogger::info(
             "partition",
             &format!(
-                "Sent partition {} ({} batches) to {}",
-                partition_id,
-                partition_batches.len(),
-                assignment.node_name,
+                "Vacuumed {} local table(s) of '{}': reclaimed {} bytes",
+                report.tables.len(),
+                table_name,
+                report.reclaimed_bytes(),
             ),
         );
-    }
-
-    // Eagerly refresh catalog (avoids 30s gossip delay)
-    for (endpoint, node_name) in &unique_endpoints {
-        if let Err(e) = flight_client::refresh_remote_catalog(endpoint).await {
-            SwarmLogger::warn(
-                "partition",
-                &format!(
-                    "Failed to trigger catalog refresh on node '{}': {}",
-                    node_name, e
-                ),
-            );
-        }
-    }
-
-    Ok(())
+        Ok(report)
+    })
 }
 
-/// Gather all data for a table from multiple shards via DoGet.
-async fn gather_table_from_shards(
-    table_name: &str,
-    shard_endpoints: &[(String, String)],
-) -> Result<(SchemaRef, Vec<RecordBatch>), String> {
-    let sql = format!(
-        "SELECT * FROM \"{}\"",
-        table_name.replace('"', "\"\"")
-    );
-
-    let mut all_batches = Vec::new();
-    let mut schema: Option<SchemaRef> = None;
-
-    for (endpoint, node_name) in shard_endpoints {
-        let (shard_schema, shard_batches) =
-            flight_client::query_node_with_schema(endpoint, &sql).await.map_err(|e| {
-                format!(
-                    "Failed to gather data from node '{}' ({}): {}",
-                    node_name, endpoint, e
-                )
-            })?;
+fn rewrite_table(conn: &duckdb::Connection, table: &str) -> Result<(), String> {
+    let quoted = format!("\"{}\"", catalog::escape_identifier(table));
+    let staging = "temp.\"__trex_vacuum\"";
 
-        if schema.is_none() {
-            schema = Some(shard_schema);
+    conn.execute_batch("BEGIN TRANSACTION")
+        .map_err(|e| format!("Failed to begin vacuum of '{}': {e}", table))?;
+    let result = (|| -> Result<(), String> {
+        let run = |sql: String| {
+            conn.execute_batch(&sql)
+                .map_err(|e| format!("Vacuum of '{}' failed: {e}", table))
+        };
+        run(format!("CREATE OR REPLACE TEMP TABLE \"__trex_vacuum\" AS SELECT * FROM {quoted}"))?;
+        let expected = query_u64(conn, &format!("SELECT COUNT(*) FROM {staging}"))?;
+        run(format!("DELETE FROM {quoted}"))?;
+        run(format!("INSERT INTO {quoted} SELECT * FROM {staging}"))?;
+        let actual = query_u64(conn, &format!("SELECT COUNT(*) FROM {quoted}"))?;
+        if actual != expected {
+            return Err(format!(
+                "Vacuum of '{}' rewrote {} of {} row(s)",
+                table, actual, expected
+            ));
         }
+        run(format!("DROP TABLE {staging}"))
+    })();
 
-        all_batches.extend(shard_batches);
+    match result {
+        Ok(()) => conn
+            .execute_batch("COMMIT")
+            .map_err(|e| format!("Failed to commit vacuum of '{}': {e}", table)),
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK");
+            Err(e)
+        }
     }
+}
 
-    let schema = schema.unwrap_or_else(|| Arc::new(arrow::datatypes::Schema::empty()));
+fn row_group_count(conn: &duckdb::Connection, table: &str) -> Result<u64, String> {
+    query_u64(
+        conn,
+        &format!(
+            "SELECT COUNT(DISTINCT row_group_id) FROM pragma_storage_info('{}')",
+            table.replace('\'', "''")
+        ),
+    )
+}
 
-    Ok((schema, all_batches))
+fn used_database_bytes(conn: &duckdb::Connection) -> Result<u64, String> {
+    query_u64(
+        conn,
+        "SELECT COALESCE(SUM(used_blocks * block_size), 0) FROM pragma_database_size() \
+         WHERE database_name = current_database()",
+    )
+}
+
+fn query_u64(conn: &duckdb::Connection, sql: &str) -> Result<u64, String> {
+    conn.query_row(sql, [], |row| row.get::<_, i64>(0))
+        .map(|v| v.max(0) as u64)
+        .map_err(|e| format!("Query '{}' failed: {e}", sql))
+}
+
+fn query_strings(conn: &duckdb::Connection, sql: &str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| format!("Failed to prepare '{}': {e}", sql))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to execute '{}': {e}", sql))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to read '{}': {e}", sql))
 }
 
 /// Extract table name from a CREATE TABLE statement.
@@ -1024,6 +3513,195 @@ mod tests {
         assert!(result.is_err());
     }
 
+    fn region_batch() -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("region", DataType::Utf8, true),
+        ]));
+        RecordBatch::try_new(
+            schema,
+            vec![
+                Arc::new(Int64Array::from(vec![1, 2, 3, 4, 5, 6])),
+                Arc::new(StringArray::from(vec![
+                    Some("de"),
+                    Some("us"),
+                    Some("fr"),
+                    Some("jp"),
+                    None,
+                    Some("us"),
+                ])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn list_partition_routes_by_exact_value() {
+        let lists = vec![
+            ("eu".to_string(), vec!["de".to_string(), "fr".to_string()]),
+            ("na".to_string(), vec!["us".to_string()]),
+        ];
+        let result = list_partition_batches(&[region_batch()], "region", &lists).unwrap();
+        assert_eq!(result.len(), 3);
+
+        let ids = |part: &[RecordBatch]| -> Vec<i64> {
+            part.iter()
+                .flat_map(|b| {
+                    b.column(0)
+                        .as_any()
+                        .downcast_ref::<Int64Array>()
+                        .unwrap()
+                        .values()
+                        .to_vec()
+                })
+                .collect()
+        };
+        assert_eq!(ids(&result[0]), vec![1, 3]);
+        assert_eq!(ids(&result[1]), vec![2, 6]);
+        // Unlisted ("jp") and NULL values land in the default partition.
+        assert_eq!(ids(&result[2]), vec![4, 5]);
+    }
+
+    #[test]
+    fn list_partition_rejects_duplicate_values() {
+        let lists = vec![
+            ("a".to_string(), vec!["de".to_string()]),
+            ("b".to_string(), vec!["de".to_string()]),
+        ];
+        assert!(list_partition_batches(&[region_batch()], "region", &lists).is_err());
+        assert!(list_partition_batches(&[region_batch()], "region", &[]).is_err());
+    }
+
+    #[test]
+    fn list_partition_matches_numeric_values() {
+        let json = r#"{"strategy":"list","column":"id","lists":[{"name":"low","values":[1,2]}]}"#;
+        let config: PartitionConfig = serde_json::from_str(json).unwrap();
+        let (strategy, partitioned) =
+            partition_by_config(&config, &region_batch().schema(), &[region_batch()]).unwrap();
+        match strategy {
+            PartitionStrategy::List { column, lists } => {
+                assert_eq!(column, "id");
+                assert_eq!(lists, vec![("low".to_string(), vec!["1".to_string(), "2".to_string()])]);
+            }
+            other => panic!("unexpected strategy {:?}", other),
+        }
+        let rows: Vec<usize> = partitioned
+            .iter()
+            .map(|p| p.iter().map(|b| b.num_rows()).sum())
+            .collect();
+        assert_eq!(rows, vec![2, 4]);
+    }
+
+    fn int_stats(min: i64, max: i64, null_count: u64) -> catalog::ColumnStats {
+        catalog::ColumnStats {
+            min: catalog::StatValue::Int(min),
+            max: catalog::StatValue::Int(max),
+            null_count: Some(null_count),
+        }
+    }
+
+    #[test]
+    fn preview_estimates_range_rows_from_min_max() {
+        let ranges: Vec<RangeBound> = serde_json::from_str(
+            r#"[{"upper": 25}, {"lower": 25, "upper": 100}, {"lower": 100}]"#,
+        )
+        .unwrap();
+        let strategy = PartitionStrategy::Range {
+            column: "id".to_string(),
+            ranges,
+        };
+        let rows = estimate_partition_rows(
+            &strategy,
+            &DataType::Int64,
+            1000,
+            Some(&int_stats(0, 100, 0)),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(rows, vec![250, 750, 0]);
+
+        // A hot key is counted whole in its own range.
+        let hot = [catalog::HotKey {
+            value: catalog::StatValue::Int(150),
+            rows: 200,
+        }];
+        let rows = estimate_partition_rows(
+            &strategy,
+            &DataType::Int64,
+            1000,
+            Some(&int_stats(0, 100, 0)),
+            &hot,
+        )
+        .unwrap();
+        assert_eq!(rows, vec![200, 600, 200]);
+    }
+
+    #[test]
+    fn preview_places_hot_keys_and_nulls_where_rows_would_go() {
+        let strategy = PartitionStrategy::Hash {
+            column: "id".to_string(),
+            num_partitions: 4,
+        };
+        let hot = [catalog::HotKey {
+            value: catalog::StatValue::Int(7),
+            rows: 600,
+        }];
+        let rows = estimate_partition_rows(
+            &strategy,
+            &DataType::Int32,
+            1000,
+            Some(&int_stats(1, 100, 0)),
+            &hot,
+        )
+        .unwrap();
+        assert_eq!(rows.iter().sum::<u64>(), 1000);
+
+        let schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(arrow::array::Int32Array::from(vec![7]))],
+        )
+        .unwrap();
+        let partitioned = partition_by_strategy(&strategy, &schema, &[batch]).unwrap();
+        let hot_partition = partitioned.iter().position(|p| !p.is_empty()).unwrap();
+        assert_eq!(rows[hot_partition], 700);
+        assert!(rows.iter().enumerate().all(|(i, r)| i == hot_partition || *r == 100));
+
+        let lists = vec![("eu".to_string(), vec!["de".to_string()])];
+        let strategy = PartitionStrategy::List {
+            column: "region".to_string(),
+            lists,
+        };
+        let stats = catalog::ColumnStats {
+            min: catalog::StatValue::Text("de".to_string()),
+            max: catalog::StatValue::Text("us".to_string()),
+            null_count: Some(40),
+        };
+        let rows =
+            estimate_partition_rows(&strategy, &DataType::Utf8, 100, Some(&stats), &[]).unwrap();
+        assert_eq!(rows, vec![30, 70]);
+    }
+
+    #[test]
+    fn preview_skew_counts_every_copy_on_a_node() {
+        let preview = PartitionPreview {
+            strategy: PartitionStrategy::Hash {
+                column: "id".to_string(),
+                num_partitions: 2,
+            },
+            assignments: vec![
+                replicated(0, "node-a", &["node-b"]),
+                replicated(1, "node-b", &[]),
+            ],
+            estimated_rows: vec![300, 100],
+            target_nodes: vec!["node-a".into(), "node-b".into(), "node-c".into()],
+        };
+        assert_eq!(preview.node_rows("node-a"), 300);
+        assert_eq!(preview.node_rows("node-b"), 400);
+        assert!((preview.node_skew("node-b") - 400.0 / (700.0 / 3.0)).abs() < 1e-9);
+        assert_eq!(preview.node_skew("node-c"), 0.0);
+    }
+
     #[test]
     fn assign_partitions_round_robin() {
         let nodes = vec![
@@ -1088,6 +3766,98 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn assign_replicas_follow_primary() {
+        let nodes: Vec<TargetNode> = ["node-a", "node-b", "node-c"]
+            .iter()
+            .map(|n| TargetNode {
+                node_name: n.to_string(),
+                flight_endpoint: format!("http://{}:8815", n),
+            })
+            .collect();
+
+        let mut assignments = assign_partitions(3, &nodes, None).unwrap();
+        assign_replicas(&mut assignments, &nodes, None, 2).unwrap();
+        let replicas: Vec<&str> = assignments
+            .iter()
+            .map(|a| a.replicas[0].node_name.as_str())
+            .collect();
+        assert_eq!(replicas, vec!["node-b", "node-c", "node-a"]);
+
+        assert!(assign_replicas(&mut assignments, &nodes, None, 4).is_err());
+        assert!(assign_replicas(&mut assignments, &nodes, None, 0).is_err());
+        assign_replicas(&mut assignments, &nodes, None, 1).unwrap();
+        assert!(assignments.iter().all(|a| a.replicas.is_empty()));
+    }
+
+    #[test]
+    fn replica_table_name_roundtrip() {
+        let name = replica_table_name("my__table", 12);
+        assert_eq!(name, "my__table__replica_12");
+        assert_eq!(parse_replica_table_name(&name), Some(("my__table", 12)));
+        assert_eq!(parse_replica_table_name("orders"), None);
+        assert_eq!(parse_replica_table_name("orders__replica_x"), None);
+        assert_eq!(parse_replica_table_name("__replica_1"), None);
+    }
+
+    #[test]
+    fn pending_table_names_are_recognised() {
+        assert_eq!(pending_table_name("orders"), "orders__pending");
+        assert!(is_pending_table_name(&pending_table_name("orders")));
+        assert!(is_pending_table_name(&pending_table_name(&replica_table_name("orders", 3))));
+        assert!(!is_pending_table_name("orders"));
+        assert!(!is_pending_table_name("__pending"));
+        assert_eq!(parse_replica_table_name(&pending_table_name(&replica_table_name("orders", 3))), None);
+    }
+
+    #[test]
+    fn attach_config_deserialize() {
+        let config: AttachConfig = serde_json::from_str(
+            r#"{"nodes": [{"node": "node-a", "path": "/data/a.duckdb", "table": "events"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.nodes.len(), 1);
+        assert_eq!(config.nodes[0].node, "node-a");
+        assert_eq!(config.nodes[0].path, "/data/a.duckdb");
+        assert_eq!(config.nodes[0].table, "events");
+        assert!(serde_json::from_str::<AttachConfig>(r#"{"nodes": [{"node": "a"}]}"#).is_err());
+    }
+
+    #[test]
+    fn attached_schemas_must_match() {
+        use arrow::datatypes::{Field, Schema};
+
+        let schema = |value_type: DataType| -> SchemaRef {
+            Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("value", value_type, true),
+            ]))
+        };
+        let matching = vec![
+            ("node-a".to_string(), schema(DataType::Utf8)),
+            ("node-b".to_string(), schema(DataType::Utf8)),
+        ];
+        assert!(check_attached_schemas("events", &matching).is_ok());
+
+        let mismatched = vec![
+            ("node-a".to_string(), schema(DataType::Utf8)),
+            ("node-b".to_string(), schema(DataType::Float64)),
+        ];
+        let err = check_attached_schemas("events", &mismatched).unwrap_err();
+        assert_eq!(
+            err,
+            "Schema mismatch for table 'events': node 'node-b' has (id BIGINT, value DOUBLE) \
+             but node 'node-a' has (id BIGINT, value VARCHAR)"
+        );
+    }
+
+    #[test]
+    fn partition_metadata_without_replicas_still_parses() {
+        let json = r#"{"strategy":{"hash":{"column":"id","num_partitions":1}},"assignments":[{"partition_id":0,"node_name":"a","flight_endpoint":"http://a:8815"}],"create_sql":"x"}"#;
+        let meta: PartitionMetadata = serde_json::from_str(json).unwrap();
+        assert!(meta.assignments[0].replicas.is_empty());
+    }
+
     #[test]
     fn extract_table_name_simple() {
         assert_eq!(
@@ -1150,14 +3920,18 @@ mod tests {
                     partition_id: 0,
                     node_name: "node-a".to_string(),
                     flight_endpoint: "http://a:8815".to_string(),
+                    replicas: vec![],
                 },
                 PartitionAssignment {
                     partition_id: 1,
                     node_name: "node-b".to_string(),
                     flight_endpoint: "http://b:8815".to_string(),
+                    replicas: vec![],
                 },
             ],
             create_sql: "CREATE TABLE orders (id INT)".to_string(),
+            replication_factor: 1,
+            routing_version: 3,
         };
 
         let json = serde_json::to_string(&meta).unwrap();
@@ -1165,6 +3939,7 @@ mod tests {
 
         assert_eq!(restored.assignments.len(), 2);
         assert_eq!(restored.create_sql, "CREATE TABLE orders (id INT)");
+        assert_eq!((restored.replication_factor, restored.routing_version), (1, 3));
     }
 
     #[test]
@@ -1236,4 +4011,340 @@ mod tests {
             "INTERVAL"
         );
     }
+
+    fn loads(rows: &[(usize, &str, u64)]) -> Vec<(usize, String, u64)> {
+        rows.iter().map(|(id, n, r)| (*id, n.to_string(), *r)).collect()
+    }
+
+    #[test]
+    fn plan_rebalance_moves_to_empty_node() {
+        let partitions = loads(&[(0, "a", 100), (1, "a", 100), (2, "b", 100), (3, "b", 100)]);
+        let nodes = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let moves = plan_rebalance(&partitions, &[], &nodes, 0.1);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].to_node, "c");
+    }
+
+    #[test]
+    fn plan_rebalance_balanced_table_has_no_moves() {
+        let partitions = loads(&[(0, "a", 100), (1, "b", 105), (2, "c", 95)]);
+        let nodes = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert!(plan_rebalance(&partitions, &[], &nodes, 0.1).is_empty());
+    }
+
+    #[test]
+    fn plan_rebalance_skips_moves_that_do_not_help() {
+        // A single huge partition cannot be split, so moving it only shifts the skew.
+        let partitions = loads(&[(0, "a", 1000), (1, "b", 10)]);
+        let nodes = vec!["a".to_string(), "b".to_string()];
+        assert!(plan_rebalance(&partitions, &[], &nodes, 0.1).is_empty());
+    }
+
+    #[test]
+    fn plan_rebalance_reports_original_owner() {
+        let partitions = loads(&[(0, "a", 300), (1, "a", 300), (2, "a", 300), (3, "b", 0)]);
+        let nodes = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let moves = plan_rebalance(&partitions, &[], &nodes, 0.1);
+        assert_eq!(moves.len(), 2);
+        assert!(moves.iter().all(|m| m.from_node == "a"));
+        let targets: std::collections::HashSet<_> = moves.iter().map(|m| m.to_node.as_str()).collect();
+        assert_eq!(targets, ["b", "c"].into_iter().collect());
+    }
+
+    #[test]
+    fn plan_rebalance_keeps_primaries_off_their_replica_nodes() {
+        // c is empty but holds the replicas of a's partitions, so they go to b.
+        let partitions = loads(&[(0, "a", 300), (1, "a", 300), (2, "b", 0)]);
+        let replicas = vec![(0, "c".to_string()), (1, "c".to_string())];
+        let nodes = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let moves = plan_rebalance(&partitions, &replicas, &nodes, 0.1);
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].to_node, "b");
+    }
+
+    #[test]
+    fn plan_rebalance_counts_replica_rows_in_node_load() {
+        // b's replica of partition 0 makes it as loaded as a, so nothing moves to it.
+        let partitions = loads(&[(0, "a", 300), (1, "a", 300), (2, "b", 300)]);
+        let replicas = vec![(0, "b".to_string())];
+        let nodes = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let moves = plan_rebalance(&partitions, &replicas, &nodes, 0.1);
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|m| m.to_node == "c"));
+    }
+
+    fn replicated(partition_id: usize, primary: &str, replicas: &[&str]) -> PartitionAssignment {
+        PartitionAssignment {
+            partition_id,
+            node_name: primary.to_string(),
+            flight_endpoint: format!("http://{}:50051", primary),
+            replicas: replicas
+                .iter()
+                .map(|n| ReplicaAssignment {
+                    node_name: n.to_string(),
+                    flight_endpoint: format!("http://{}:50051", n),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn rereplication_promotes_replica_of_killed_primary() {
+        let assignments = vec![replicated(0, "a", &["b"]), replicated(1, "b", &["c"])];
+        let nodes = vec!["b".to_string(), "c".to_string(), "d".to_string()];
+        let repairs = plan_rereplication(&assignments, "a", &nodes);
+        assert_eq!(
+            repairs,
+            vec![ReplicaRepair {
+                partition_id: 0,
+                source_node: "b".to_string(),
+                source_is_replica: true,
+                promoted: Some("b".to_string()),
+                new_replica: Some("c".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn rereplication_replaces_killed_replica() {
+        let assignments = vec![replicated(1, "a", &["b", "c"]), replicated(2, "a", &["b"])];
+        let nodes = vec!["a".to_string(), "c".to_string(), "d".to_string()];
+        let repairs = plan_rereplication(&assignments, "b", &nodes);
+        assert_eq!(repairs.len(), 2);
+        // A surviving replica is the source; it holds exactly the partition.
+        assert_eq!(repairs[0].source_node, "c");
+        assert!(repairs[0].source_is_replica);
+        assert_eq!(repairs[0].new_replica.as_deref(), Some("d"));
+        // Otherwise the primary's table is read and re-partitioned.
+        assert_eq!(repairs[1].source_node, "a");
+        assert!(!repairs[1].source_is_replica);
+        assert_eq!(repairs[1].new_replica.as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn rereplication_skips_unreplicated_and_lost_partitions() {
+        let assignments = vec![replicated(0, "a", &[]), replicated(1, "b", &["c"])];
+        let nodes = vec!["c".to_string()];
+        let repairs = plan_rereplication(&assignments, "a", &nodes);
+        assert!(repairs.is_empty());
+        // With every node already holding a copy there is nowhere to put one.
+        let repairs = plan_rereplication(&[replicated(0, "a", &["b"])], "a", &["b".to_string()]);
+        assert_eq!(repairs[0].new_replica, None);
+    }
+
+    #[test]
+    fn replication_change_adds_replicas_after_the_primary() {
+        let assignments = vec![replicated(0, "a", &[]), replicated(1, "c", &["a"])];
+        let nodes = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let changes = plan_replication_change(&assignments, &nodes, 3).unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                ReplicationChange {
+                    partition_id: 0,
+                    added: vec!["b".to_string(), "c".to_string()],
+                    removed: vec![],
+                },
+                ReplicationChange {
+                    partition_id: 1,
+                    added: vec!["b".to_string()],
+                    removed: vec![],
+                },
+            ]
+        );
+        assert!(plan_replication_change(&assignments, &nodes, 4).is_err());
+        assert!(plan_replication_change(&assignments, &nodes, 0).is_err());
+    }
+
+    #[test]
+    fn replication_change_drops_newest_replicas_and_keeps_the_primary() {
+        let assignments = vec![replicated(0, "a", &["b", "c"]), replicated(1, "b", &["c"])];
+        let nodes = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let changes = plan_replication_change(&assignments, &nodes, 2).unwrap();
+        assert_eq!(
+            changes,
+            vec![ReplicationChange {
+                partition_id: 0,
+                added: vec![],
+                removed: vec!["c".to_string()],
+            }]
+        );
+        let changes = plan_replication_change(&assignments, &nodes, 1).unwrap();
+        assert_eq!(changes[0].removed, vec!["b".to_string(), "c".to_string()]);
+        assert_eq!(changes[1].removed, vec!["c".to_string()]);
+    }
+
+    #[test]
+    fn replication_change_summary_counts_each_partition() {
+        assert_eq!(
+            describe_replication_change(3, &["b".to_string(), "c".to_string()], &[]),
+            "  partition 3: created 2 (b, c), removed 0"
+        );
+        assert_eq!(
+            describe_replication_change(0, &[], &["a".to_string()]),
+            "  partition 0: created 0, removed 1 (a)"
+        );
+    }
+
+    #[test]
+    fn vacuum_targets_include_replicas_only_of_the_table() {
+        let local: Vec<String> = [
+            "orders",
+            &replica_table_name("orders", 2),
+            &replica_table_name("orders_archive", 1),
+            "orders_archive",
+            "customers",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            vacuum_targets("orders", &local),
+            vec!["orders".to_string(), replica_table_name("orders", 2)]
+        );
+        assert!(vacuum_targets("missing", &local).is_empty());
+    }
+
+    #[test]
+    fn vacuum_summary_reports_each_node() {
+        let compacted = VacuumReport {
+            tables: vec!["orders".to_string()],
+            row_groups_before: 12,
+            row_groups_after: 3,
+            bytes_before: 4096,
+            bytes_after: 1024,
+        };
+        let results = vec![
+            ("node-a".to_string(), Ok(compacted)),
+            ("node-b".to_string(), Ok(VacuumReport::default())),
+            ("node-c".to_string(), Err("connection refused".to_string())),
+        ];
+        assert_eq!(
+            format_vacuum_summary("orders", &results),
+            "Vacuumed 'orders' on 1 of 3 node(s), reclaimed 3072 bytes\n\
+             node-a: 1 table(s), 12 -> 3 row group(s), reclaimed 3072 bytes\n\
+             node-b: no local copy\n\
+             node-c: error: connection refused"
+        );
+    }
+
+    #[test]
+    fn vacuum_report_never_reclaims_negative_space() {
+        let grown = VacuumReport {
+            bytes_before: 100,
+            bytes_after: 200,
+            ..Default::default()
+        };
+        assert_eq!(grown.reclaimed_bytes(), 0);
+    }
+
+    #[test]
+    fn parse_insert_select_reads_target_and_source() {
+        let insert = parse_insert_select(
+            "INSERT INTO orders (id, name) SELECT id, name FROM staging WHERE id > 3",
+        )
+        .unwrap();
+        assert_eq!(insert.table_name, "orders");
+        assert_eq!(insert.columns, vec!["id", "name"]);
+        assert_eq!(insert.source_sql, "SELECT id, name FROM staging WHERE id > 3");
+
+        let values = parse_insert_select("INSERT INTO main.orders VALUES (1, 'a', 2.5)").unwrap();
+        assert_eq!(values.table_name, "orders");
+        assert!(values.columns.is_empty());
+
+        assert!(parse_insert_select("SELECT * FROM orders").is_none());
+        assert!(parse_insert_select("INSERT INTO orders SELECT * FROM s RETURNING id").is_none());
+        assert!(parse_insert_select("INSERT INTO a SELECT 1; INSERT INTO b SELECT 2").is_none());
+    }
+
+    #[test]
+    fn insert_rows_are_cast_to_the_table_and_routed_once() {
+        use arrow::array::Int32Array;
+
+        // The query produces INT and unnamed columns; the table has BIGINT.
+        let source_schema = Arc::new(Schema::new(vec![
+            Field::new("x", DataType::Int32, false),
+            Field::new("y", DataType::Utf8, true),
+        ]));
+        let source = RecordBatch::try_new(
+            source_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from((1..=50).collect::<Vec<i32>>())),
+                Arc::new(StringArray::from(vec![Some("v"); 50])),
+            ],
+        )
+        .unwrap();
+        let columns = vec!["ID".to_string(), "name".to_string()];
+        let (schema, aligned) =
+            align_to_table("orders", &test_schema(), &columns, &source_schema, &[source]).unwrap();
+        assert_eq!(schema.field(0).name(), "id");
+        assert_eq!(schema.field(0).data_type(), &DataType::Int64);
+
+        let strategy = PartitionStrategy::Hash {
+            column: "id".to_string(),
+            num_partitions: 4,
+        };
+        let routed = partition_by_strategy(&strategy, &schema, &aligned).unwrap();
+        let rows: usize = routed.iter().flatten().map(|b| b.num_rows()).sum();
+        assert_eq!(rows, 50);
+
+        // Each row lands where the table's own BIGINT values of the same id do.
+        let native_schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int64, true)]));
+        let native = RecordBatch::try_new(
+            native_schema.clone(),
+            vec![Arc::new(Int64Array::from((1..=50).collect::<Vec<i64>>()))],
+        )
+        .unwrap();
+        let expected = partition_by_strategy(&strategy, &native_schema, &[native]).unwrap();
+        let ids = |parts: &Vec<Vec<RecordBatch>>| -> Vec<Vec<String>> {
+            parts
+                .iter()
+                .map(|part| {
+                    part.iter()
+                        .flat_map(|b| {
+                            (0..b.num_rows())
+                                .map(|r| arrow::util::display::array_value_to_string(b.column(0), r))
+                                .collect::<Result<Vec<_>, _>>()
+                                .unwrap()
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+        assert_eq!(ids(&routed), ids(&expected));
+    }
+
+    #[test]
+    fn align_to_table_rejects_mismatched_columns() {
+        assert!(align_to_table("orders", &test_schema(), &[], &test_schema(), &[]).is_ok());
+        let err = align_to_table("orders", &test_schema(), &["id".to_string()], &test_schema(), &[])
+            .unwrap_err();
+        assert!(err.contains("1 target column(s) but the query returns 3"), "{err}");
+        let err = align_to_table("orders", &test_schema(), &["nope".to_string()], &test_schema(), &[])
+            .unwrap_err();
+        assert!(err.contains("'nope' not found"), "{err}");
+    }
+
+    #[test]
+    fn insert_targets_group_rows_by_table_copy() {
+        let assignments = vec![
+            replicated(0, "a", &["b"]),
+            replicated(1, "b", &[]),
+            replicated(2, "a", &[]),
+        ];
+        let batch = test_batch();
+        let partitioned = vec![vec![batch.slice(0, 2)], vec![], vec![batch.slice(2, 3)]];
+
+        let targets = insert_targets("orders", &assignments, partitioned).unwrap();
+        let summary: Vec<(&str, &str, usize)> = targets
+            .iter()
+            .map(|t| {
+                let rows = t.batches.iter().map(|b| b.num_rows()).sum();
+                (t.node_name.as_str(), t.table.as_str(), rows)
+            })
+            .collect();
+        assert_eq!(summary, vec![("a", "orders", 5), ("b", "orders__replica_0", 2)]);
+        assert_eq!(insert_staging_table_name("orders"), "orders__insert__pending");
+        assert!(is_pending_table_name(&insert_staging_table_name("orders")));
+    }
 }
@@ -0,0 +1,79 @@
+//! Round-trip latency between this node and its peers, measured with the
+//! Flight "ping" action.
+
+use std::time::Duration;
+
+use crate::catalog;
+use crate::flight_client;
+use crate::logging::SwarmLogger;
+
+/// How long a ping may take before the node counts as unreachable.
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The round trip to one peer, or why it could not be measured.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingResult {
+    pub node_name: String,
+    pub flight_endpoint: String,
+    pub rtt_ms: Result<f64, String>,
+}
+
+fn millis(rtt: Duration) -> f64 {
+    rtt.as_secs_f64() * 1000.0
+}
+
+fn with_runtime<T>(f: impl std::future::Future<Output = T>) -> Result<T, String> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| format!("Failed to create tokio runtime: {e}"))?;
+    Ok(rt.block_on(f))
+}
+
+async fn ping_endpoint(endpoint: &str) -> Result<f64, String> {
+    flight_client::ping_node(endpoint, PING_TIMEOUT)
+        .await
+        .map(millis)
+        .map_err(String::from)
+}
+
+/// Round-trip time to `node_name` in milliseconds.
+pub fn ping(node_name: &str) -> Result<f64, String> {
+    let endpoints = catalog::flight_endpoints_by_node()?;
+    let endpoint = endpoints
+        .get(node_name)
+        .ok_or_else(|| format!("Node '{node_name}' is not running a Flight server"))?;
+    let rtt = with_runtime(ping_endpoint(endpoint))??;
+    SwarmLogger::debug("ping", &format!("Ping to {node_name} took {rtt:.3}ms"));
+    Ok(rtt)
+}
+
+/// Round-trip time to every other node running a Flight server, pinged
+/// concurrently, in node name order.
+pub fn ping_all() -> Result<Vec<PingResult>, String> {
+    let self_name = catalog::get_self_node_name();
+    let mut peers: Vec<(String, String)> = catalog::flight_endpoints_by_node()?
+        .into_iter()
+        .filter(|(name, _)| Some(name) != self_name.as_ref())
+        .collect();
+    peers.sort();
+
+    let rtts = with_runtime(futures::future::join_all(
+        peers.iter().map(|(_, endpoint)| ping_endpoint(endpoint)),
+    ))?;
+
+    Ok(peers
+        .into_iter()
+        .zip(rtts)
+        .map(|((node_name, flight_endpoint), rtt_ms)| {
+            if let Err(e) = &rtt_ms {
+                SwarmLogger::warn("ping", &format!("Ping to {node_name} failed: {e}"));
+            }
+            PingResult {
+                node_name,
+                flight_endpoint,
+                rtt_ms,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,341 @@
+//! External PostgreSQL sources federated into distributed queries.
+//!
+//! `trex_db_federate_postgres(name, connection_string)` attaches the database
+//! through DuckDB's `postgres` extension and exposes its tables to DataFusion
+//! as catalog `name` (`SELECT * FROM name.public.orders`). Scans run as DuckDB
+//! SQL against the attached catalog, and DuckDB forwards projections and
+//! simple filters to Postgres. Sources are registered per node: they join
+//! the session of the node that federated them.
+
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use arrow::array::{Array, StringArray};
+use arrow::datatypes::SchemaRef;
+use datafusion::catalog::{MemoryCatalogProvider, SchemaProvider};
+use datafusion::datasource::TableProvider;
+use datafusion::error::Result as DFResult;
+use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
+use datafusion::physical_plan::SendableRecordBatchStream;
+use datafusion::prelude::SessionContext;
+use datafusion::sql::unparser::dialect::Dialect;
+use datafusion::sql::TableReference;
+use datafusion_federation::sql::{RemoteTableRef, SQLExecutor, SQLFederationProvider, SQLTableSource};
+use datafusion_federation::FederatedTableProviderAdaptor;
+use futures::stream;
+
+use crate::federation_executor::wrap_executor_error;
+use crate::logging::SwarmLogger;
+
+/// A federated Postgres database and the `(schema, table)` pairs it exposes.
+#[derive(Debug, Clone)]
+pub struct PostgresSource {
+    pub name: String,
+    pub tables: Vec<(String, String)>,
+}
+
+static SOURCES: OnceLock<Mutex<BTreeMap<String, PostgresSource>>> = OnceLock::new();
+
+fn sources_lock() -> &'static Mutex<BTreeMap<String, PostgresSource>> {
+    SOURCES.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+/// All sources registered on this node.
+pub fn sources() -> Vec<PostgresSource> {
+    sources_lock()
+        .lock()
+        .map(|s| s.values().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Source names become DuckDB and DataFusion catalog names, so keep them to
+/// plain identifiers and away from the built-in catalogs.
+fn validate_source_name(name: &str) -> Result<(), String> {
+    let mut chars = name.chars();
+    let valid = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(format!(
+            "Invalid source name '{}': use letters, digits and '_' only",
+            name
+        ));
+    }
+    if matches!(
+        name.to_ascii_lowercase().as_str(),
+        "datafusion" | "memory" | "system" | "temp" | "main"
+    ) {
+        return Err(format!("Source name '{}' is reserved", name));
+    }
+    Ok(())
+}
+
+pub fn federate_postgres_impl(name: &str, connection_string: &str) -> Result<String, String> {
+    validate_source_name(name)?;
+
+    if crate::pool::execute("LOAD postgres").is_err() {
+        crate::pool::execute("INSTALL postgres")
+            .map_err(|e| format!("Failed to install the postgres extension: {e}"))?;
+        crate::pool::execute("LOAD postgres")
+            .map_err(|e| format!("Failed to load the postgres extension: {e}"))?;
+    }
+
+    crate::pool::execute(&format!("DETACH DATABASE IF EXISTS \"{}\"", name))
+        .map_err(|e| format!("Failed to detach previous source '{}': {e}", name))?;
+    crate::pool::execute(&format!(
+        "ATTACH '{}' AS \"{}\" (TYPE postgres, READ_ONLY)",
+        connection_string.replace('\'', "''"),
+        name
+    ))
+    .map_err(|e| format!("Failed to attach Postgres source '{}': {e}", name))?;
+
+    let tables = list_source_tables(name)?;
+    let count = tables.len();
+    sources_lock()
+        .lock()
+        .map_err(|_| "Federation registry lock poisoned".to_string())?
+        .insert(
+            name.to_string(),
+            PostgresSource {
+                name: name.to_string(),
+                tables,
+            },
+        );
+
+    SwarmLogger::info(
+        "federation",
+        &format!("Federated Postgres source '{}' with {} table(s)", name, count),
+    );
+
+    // Running sessions pick the catalog up on rebuild.
+    if crate::distributed_scheduler::is_scheduler_running() {
+        if let Err(e) = crate::distributed_scheduler::refresh_session() {
+            SwarmLogger::warn(
+                "federation",
+                &format!("Session refresh after federating '{}' failed: {}", name, e),
+            );
+        }
+    }
+
+    Ok(format!(
+        "Federated Postgres source '{}' with {} table(s)",
+        name, count
+    ))
+}
+
+fn list_source_tables(name: &str) -> Result<Vec<(String, String)>, String> {
+    let sql = format!(
+        "SELECT table_schema, table_name FROM information_schema.tables \
+         WHERE table_catalog = '{}' ORDER BY table_schema, table_name",
+        name
+    );
+    let (_schema, batches) = crate::pool::read_arrow(&sql)
+        .map_err(|e| format!("Failed to list tables of source '{}': {e}", name))?;
+
+    let mut tables = Vec::new();
+    for batch in &batches {
+        let (Some(schemas), Some(names)) = (
+            batch.column(0).as_any().downcast_ref::<StringArray>(),
+            batch.column(1).as_any().downcast_ref::<StringArray>(),
+        ) else {
+            return Err("information_schema.tables returned unexpected column types".to_string());
+        };
+        for i in 0..batch.num_rows() {
+            if !schemas.is_null(i) && !names.is_null(i) {
+                tables.push((schemas.value(i).to_string(), names.value(i).to_string()));
+            }
+        }
+    }
+    Ok(tables)
+}
+
+/// Runs federated fragments for one source on the local DuckDB connection,
+/// where the source is attached. Each source gets its own compute context,
+/// so a join with native tables is split at the source boundary.
+struct PostgresSQLExecutor {
+    source: String,
+}
+
+#[async_trait::async_trait]
+impl SQLExecutor for PostgresSQLExecutor {
+    fn name(&self) -> &str {
+        "postgres"
+    }
+
+    fn compute_context(&self) -> Option<String> {
+        Some(format!("postgres:{}", self.source))
+    }
+
+    fn dialect(&self) -> Arc<dyn Dialect> {
+        Arc::new(datafusion::sql::unparser::dialect::DuckDBDialect::new())
+    }
+
+    fn execute(&self, sql: &str, schema: SchemaRef) -> DFResult<SendableRecordBatchStream> {
+        SwarmLogger::debug(
+            "federation",
+            &format!("Postgres pushdown to '{}': {sql}", self.source),
+        );
+        let (_schema, batches) =
+            crate::pool::read_arrow(sql).map_err(|e| wrap_executor_error("postgres_execute", e))?;
+        let batch_stream = stream::iter(batches.into_iter().map(Ok));
+        Ok(Box::pin(RecordBatchStreamAdapter::new(schema, batch_stream)))
+    }
+
+    async fn table_names(&self) -> DFResult<Vec<String>> {
+        Ok(sources()
+            .into_iter()
+            .filter(|s| s.name == self.source)
+            .flat_map(|s| s.tables)
+            .map(|(schema, table)| format!("{}.{}.{}", self.source, schema, table))
+            .collect())
+    }
+
+    async fn get_table_schema(&self, table_name: &str) -> DFResult<SchemaRef> {
+        // `table_name` is already the quoted three-part reference.
+        let (schema, _) = crate::pool::read_arrow(&format!("SELECT * FROM {} LIMIT 1", table_name))
+            .map_err(|e| wrap_executor_error("postgres_get_table_schema", e))?;
+        Ok(schema)
+    }
+}
+
+/// One Postgres schema, keyed by bare table name so DataFusion resolves
+/// `source.schema.table` through the catalog while federation rewrites the
+/// scan to the full remote reference.
+#[derive(Debug)]
+struct FederatedSchemaProvider {
+    tables: HashMap<String, Arc<SQLTableSource>>,
+}
+
+impl FederatedSchemaProvider {
+    fn new(sources: Vec<Arc<SQLTableSource>>) -> Self {
+        let tables = sources
+            .into_iter()
+            .map(|s| (s.table_reference().table().to_string(), s))
+            .collect();
+        Self { tables }
+    }
+}
+
+#[async_trait::async_trait]
+impl SchemaProvider for FederatedSchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.tables.keys().cloned().collect()
+    }
+
+    async fn table(&self, name: &str) -> DFResult<Option<Arc<dyn TableProvider>>> {
+        Ok(self.tables.get(name).map(|source| {
+            Arc::new(FederatedTableProviderAdaptor::new(source.clone())) as Arc<dyn TableProvider>
+        }))
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.tables.contains_key(name)
+    }
+}
+
+/// Register every federated source as a DataFusion catalog on `ctx`. Tables
+/// whose schema cannot be read are skipped with a warning.
+pub async fn register_postgres_catalogs(ctx: &SessionContext) {
+    for source in sources() {
+        let executor = Arc::new(PostgresSQLExecutor {
+            source: source.name.clone(),
+        });
+        let provider = Arc::new(SQLFederationProvider::new(executor));
+
+        let mut by_schema: BTreeMap<String, Vec<Arc<SQLTableSource>>> = BTreeMap::new();
+        for (schema, table) in &source.tables {
+            let table_ref = TableReference::full(source.name.as_str(), schema.as_str(), table.as_str());
+            match SQLTableSource::new(provider.clone(), RemoteTableRef::from(table_ref)).await {
+                Ok(table_source) => by_schema
+                    .entry(schema.clone())
+                    .or_default()
+                    .push(Arc::new(table_source)),
+                Err(e) => SwarmLogger::warn(
+                    "federation",
+                    &format!(
+                        "Skipping {}.{}.{}: failed to read schema: {}",
+                        source.name, schema, table, e
+                    ),
+                ),
+            }
+        }
+
+        let catalog = MemoryCatalogProvider::new();
+        for (schema, tables) in by_schema {
+            let _ = datafusion::catalog::CatalogProvider::register_schema(
+                &catalog,
+                &schema,
+                Arc::new(FederatedSchemaProvider::new(tables)),
+            );
+        }
+        ctx.register_catalog(source.name.as_str(), Arc::new(catalog));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use datafusion::datasource::MemTable;
+
+    #[test]
+    fn source_names_must_be_identifiers() {
+        assert!(validate_source_name("crm").is_ok());
+        assert!(validate_source_name("_pg2").is_ok());
+        assert!(validate_source_name("").is_err());
+        assert!(validate_source_name("2pg").is_err());
+        assert!(validate_source_name("my-pg").is_err());
+        assert!(validate_source_name("a\"b").is_err());
+        assert!(validate_source_name("DataFusion").is_err());
+    }
+
+    #[tokio::test]
+    async fn join_between_federated_and_native_table_plans() {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, true),
+        ]));
+        let executor = Arc::new(PostgresSQLExecutor {
+            source: "crm".to_string(),
+        });
+        let provider = Arc::new(SQLFederationProvider::new(executor));
+        let customers = SQLTableSource::new_with_schema(
+            provider,
+            RemoteTableRef::from(TableReference::full("crm", "public", "customers")),
+            schema.clone(),
+        );
+
+        let state = datafusion::execution::SessionStateBuilder::new()
+            .with_default_features()
+            .with_optimizer_rules(vec![Arc::new(
+                datafusion_federation::FederationOptimizerRule::new(),
+            )])
+            .with_query_planner(Arc::new(datafusion_federation::FederatedQueryPlanner::new()))
+            .build();
+        let ctx = SessionContext::new_with_state(state);
+        let catalog = MemoryCatalogProvider::new();
+        datafusion::catalog::CatalogProvider::register_schema(
+            &catalog,
+            "public",
+            Arc::new(FederatedSchemaProvider::new(vec![Arc::new(customers)])),
+        )
+        .unwrap();
+        ctx.register_catalog("crm", Arc::new(catalog));
+        ctx.register_table("orders", Arc::new(MemTable::try_new(schema, vec![vec![]]).unwrap()))
+            .unwrap();
+
+        let plan = ctx
+            .sql("SELECT o.id, c.name FROM orders o JOIN crm.public.customers c ON o.id = c.id WHERE c.id > 5")
+            .await
+            .unwrap()
+            .into_optimized_plan()
+            .unwrap();
+        let text = format!("{}", plan.display_indent());
+        assert!(text.contains("Federated"), "expected a federated scan in:\n{text}");
+        assert!(text.contains("orders"), "expected the native table in:\n{text}");
+    }
+}
@@ -0,0 +1,158 @@
+//! Per-fragment runtime profiles of distributed queries: the measured
+//! counterpart to the static plan `trex_db_explain` prints.
+//!
+//! Profiling is opt-in per query. `trex_db_query_profiled` calls [`begin`]
+//! before running its query, and operators report each finished fragment
+//! through [`record_fragment`]. Fragments of queries nobody is profiling are
+//! dropped, so ordinary queries only pay for a map lookup.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+
+/// Profiles kept for later lookup; the oldest is evicted first.
+const MAX_PROFILES: usize = 64;
+
+/// Timing and volume of one piece of a distributed query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentProfile {
+    /// What the fragment did, e.g. `scan orders[0]` or `shuffle <id>[1]`.
+    pub fragment: String,
+    /// Node or nodes whose work the fragment measures.
+    pub node_name: String,
+    pub rows: u64,
+    /// Bytes sent to other nodes; zero for fragments that do not shuffle.
+    pub bytes_shuffled: u64,
+    pub wall_ms: f64,
+}
+
+#[derive(Default)]
+struct ProfileStore {
+    /// Query ids in the order their profiles were started.
+    order: VecDeque<String>,
+    profiles: HashMap<String, Vec<FragmentProfile>>,
+}
+
+impl ProfileStore {
+    fn begin(&mut self, query_id: &str) {
+        if self.profiles.insert(query_id.to_string(), Vec::new()).is_some() {
+            self.order.retain(|id| id != query_id);
+        }
+        self.order.push_back(query_id.to_string());
+        while self.order.len() > MAX_PROFILES {
+            if let Some(oldest) = self.order.pop_front() {
+                self.profiles.remove(&oldest);
+            }
+        }
+    }
+
+    fn record(&mut self, query_id: &str, fragment: FragmentProfile) {
+        if let Some(fragments) = self.profiles.get_mut(query_id) {
+            fragments.push(fragment);
+        }
+    }
+}
+
+fn store() -> &'static Mutex<ProfileStore> {
+    static STORE: OnceLock<Mutex<ProfileStore>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(ProfileStore::default()))
+}
+
+/// Start collecting fragments for `query_id`, replacing any earlier profile
+/// under the same id.
+pub fn begin(query_id: &str) {
+    if let Ok(mut store) = store().lock() {
+        store.begin(query_id);
+    }
+}
+
+/// Whether fragments of `query_id` are being collected. Lets operators skip
+/// work that only feeds the profile.
+pub fn is_profiling(query_id: Option<&str>) -> bool {
+    let Some(query_id) = query_id else {
+        return false;
+    };
+    store()
+        .lock()
+        .map(|s| s.profiles.contains_key(query_id))
+        .unwrap_or(false)
+}
+
+/// Add a finished fragment to the profile of `query_id`, if one was begun.
+pub fn record_fragment(query_id: Option<&str>, fragment: FragmentProfile) {
+    let Some(query_id) = query_id else {
+        return;
+    };
+    if let Ok(mut store) = store().lock() {
+        store.record(query_id, fragment);
+    }
+}
+
+/// Name this node gossips under, for fragments that run locally.
+pub fn local_node_name() -> String {
+    crate::gossip::GossipRegistry::instance()
+        .get_self_config()
+        .ok()
+        .and_then(|config| config.into_iter().find(|(k, _)| k == "node_name"))
+        .map(|(_, v)| v)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "local".to_string())
+}
+
+/// Fragments recorded for `query_id`, in completion order.
+pub fn get_profile(query_id: &str) -> Option<Vec<FragmentProfile>> {
+    store().lock().ok()?.profiles.get(query_id).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(name: &str) -> FragmentProfile {
+        FragmentProfile {
+            fragment: name.to_string(),
+            node_name: "node-a".to_string(),
+            rows: 10,
+            bytes_shuffled: 0,
+            wall_ms: 1.5,
+        }
+    }
+
+    #[test]
+    fn records_only_begun_queries() {
+        let mut store = ProfileStore::default();
+        store.record("q1", fragment("scan t[0]"));
+        assert!(store.profiles.is_empty());
+
+        store.begin("q1");
+        store.record("q1", fragment("scan t[0]"));
+        store.record("q1", fragment("scan t[1]"));
+        let names: Vec<&str> = store.profiles["q1"].iter().map(|f| f.fragment.as_str()).collect();
+        assert_eq!(names, vec!["scan t[0]", "scan t[1]"]);
+
+        store.begin("q1");
+        assert!(store.profiles["q1"].is_empty());
+        assert_eq!(store.order.len(), 1);
+    }
+
+    #[test]
+    fn evicts_oldest_profiles() {
+        let mut store = ProfileStore::default();
+        for i in 0..MAX_PROFILES + 2 {
+            store.begin(&format!("q{i}"));
+        }
+        assert_eq!(store.profiles.len(), MAX_PROFILES);
+        assert!(!store.profiles.contains_key("q0"));
+        assert!(!store.profiles.contains_key("q1"));
+        assert!(store.profiles.contains_key(&format!("q{}", MAX_PROFILES + 1)));
+    }
+
+    #[test]
+    fn global_profile_round_trip() {
+        assert!(!is_profiling(None));
+        begin("profile-round-trip");
+        assert!(is_profiling(Some("profile-round-trip")));
+        record_fragment(Some("profile-round-trip"), fragment("query"));
+        assert_eq!(get_profile("profile-round-trip"), Some(vec![fragment("query")]));
+        assert_eq!(get_profile("profile-never-begun"), None);
+    }
+}
@@ -0,0 +1,166 @@
+//! Host resource sampling: CPU load and free memory, advertised over gossip
+//! so the scheduler can steer reads toward less-loaded nodes.
+
+use std::time::Duration;
+
+/// Gossip key carrying this node's CPU utilisation (percent, one decimal).
+pub const CPU_PCT_KEY: &str = "cpu_pct";
+/// Gossip key carrying this node's available memory in MiB.
+pub const FREE_MEM_MB_KEY: &str = "free_mem_mb";
+
+/// How often the sampler refreshes the advertised values.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Aggregate jiffies from the `cpu` line of `/proc/stat`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+/// Parse the aggregate `cpu` line of `/proc/stat`. Idle and iowait count as
+/// idle time; everything else is busy.
+pub fn parse_cpu_times(stat: &str) -> Option<CpuTimes> {
+    let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+    let fields: Vec<u64> = line
+        .split_whitespace()
+        .skip(1)
+        .map(|f| f.parse().ok())
+        .collect::<Option<_>>()?;
+    if fields.len() < 4 {
+        return None;
+    }
+    // user nice system idle iowait irq softirq steal [guest guest_nice]:
+    // guest time is already included in user/nice.
+    let counted = &fields[..fields.len().min(8)];
+    let total: u64 = counted.iter().sum();
+    let idle = fields[3] + fields.get(4).copied().unwrap_or(0);
+    Some(CpuTimes {
+        busy: total.saturating_sub(idle),
+        total,
+    })
+}
+
+/// CPU utilisation between two samples, in percent.
+pub fn cpu_pct_between(prev: CpuTimes, now: CpuTimes) -> Option<f64> {
+    let total = now.total.checked_sub(prev.total)?;
+    let busy = now.busy.checked_sub(prev.busy)?;
+    if total == 0 {
+        return None;
+    }
+    Some((busy as f64 / total as f64 * 100.0).clamp(0.0, 100.0))
+}
+
+/// Clock ticks per second of the times in `/proc/<pid>/stat` (`USER_HZ`,
+/// 100 on every Linux architecture).
+const USER_HZ: u64 = 100;
+
+/// User plus system time, in clock ticks, from the contents of
+/// `/proc/self/stat`. The command name may contain spaces and parentheses,
+/// so fields are counted from the last `)`.
+pub fn parse_process_cpu_ticks(stat: &str) -> Option<u64> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = rest.split_whitespace().collect();
+    // Fields 14 and 15 of proc(5), counting from the state field (3).
+    let utime: u64 = fields.get(11)?.parse().ok()?;
+    let stime: u64 = fields.get(12)?.parse().ok()?;
+    Some(utime + stime)
+}
+
+/// CPU time this process has used across all its threads, or `None` where
+/// `/proc` is not available.
+pub fn process_cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    let ticks = parse_process_cpu_ticks(&stat)?;
+    Some(Duration::from_millis(ticks * 1000 / USER_HZ))
+}
+
+/// Available memory in MiB from `/proc/meminfo`, preferring `MemAvailable`
+/// over `MemFree` on kernels that report it.
+pub fn parse_free_mem_mb(meminfo: &str) -> Option<u64> {
+    let field = |name: &str| {
+        meminfo.lines().find_map(|l| {
+            let rest = l.strip_prefix(name)?.strip_prefix(':')?;
+            rest.split_whitespace().next()?.parse::<u64>().ok()
+        })
+    };
+    field("MemAvailable").or_else(|| field("MemFree")).map(|kb| kb / 1024)
+}
+
+/// One reading of the host's resources. Either value is `None` when the
+/// platform does not expose it (or, for CPU, on the first reading).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceSample {
+    pub cpu_pct: Option<f64>,
+    pub free_mem_mb: Option<u64>,
+}
+
+/// Keeps the previous CPU counters so successive samples yield utilisation
+/// over the interval between them.
+#[derive(Debug, Default)]
+pub struct ResourceSampler {
+    prev_cpu: Option<CpuTimes>,
+}
+
+impl ResourceSampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sample(&mut self) -> ResourceSample {
+        let now = std::fs::read_to_string("/proc/stat")
+            .ok()
+            .and_then(|s| parse_cpu_times(&s));
+        let cpu_pct = match (self.prev_cpu, now) {
+            (Some(prev), Some(now)) => cpu_pct_between(prev, now),
+            _ => None,
+        };
+        if now.is_some() {
+            self.prev_cpu = now;
+        }
+        let free_mem_mb = std::fs::read_to_string("/proc/meminfo")
+            .ok()
+            .and_then(|s| parse_free_mem_mb(&s));
+        ResourceSample {
+            cpu_pct,
+            free_mem_mb,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_aggregate_cpu_line() {
+        let stat = "cpu  100 0 50 800 50 0 0 0 0 0\ncpu0 50 0 25 400 25 0 0 0 0 0\n";
+        let times = parse_cpu_times(stat).unwrap();
+        assert_eq!(times, CpuTimes { busy: 150, total: 1000 });
+        assert_eq!(parse_cpu_times("intr 1 2 3\n"), None);
+    }
+
+    #[test]
+    fn cpu_pct_is_delta_over_interval() {
+        let prev = CpuTimes { busy: 100, total: 1000 };
+        let now = CpuTimes { busy: 175, total: 1100 };
+        assert_eq!(cpu_pct_between(prev, now), Some(75.0));
+        assert_eq!(cpu_pct_between(now, now), None);
+        assert_eq!(cpu_pct_between(now, prev), None);
+    }
+
+    #[test]
+    fn process_cpu_ticks_are_user_plus_system() {
+        let stat = "4242 (trex (worker) 1) S 1 4242 4242 0 -1 4194560 500 0 0 0 120 30 0 0 20 0 8 0";
+        assert_eq!(parse_process_cpu_ticks(stat), Some(150));
+        assert_eq!(parse_process_cpu_ticks("4242 (trex) S 1"), None);
+    }
+
+    #[test]
+    fn free_mem_prefers_mem_available() {
+        let meminfo = "MemTotal:       16384000 kB\nMemFree:         1024000 kB\nMemAvailable:    8192000 kB\n";
+        assert_eq!(parse_free_mem_mb(meminfo), Some(8000));
+        assert_eq!(parse_free_mem_mb("MemFree:         2048 kB\n"), Some(2));
+        assert_eq!(parse_free_mem_mb(""), None);
+    }
+}
@@ -18,6 +18,28 @@ pub struct ShuffleDescriptor {
     /// storing them in the shuffle registry.
     #[serde(default)]
     pub target_table: Option<String>,
+    /// Query this shuffle belongs to, so the receiving node logs under it.
+    #[serde(default)]
+    pub query_id: Option<String>,
+    /// Hot-key salting for a skewed join; `None` partitions purely by hash.
+    #[serde(default)]
+    pub salt: Option<SaltSpec>,
+}
+
+/// Spreads the rows of hot join keys over several partitions.
+///
+/// The probe side sends each hot-key row to one of `factor` partitions; the
+/// build side sends its hot-key rows to all of them, so every probe row
+/// still meets every matching build row exactly once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaltSpec {
+    /// Hot values of the first join key, as text.
+    pub hot_values: Vec<String>,
+    /// Number of partitions each hot key is spread over.
+    pub factor: usize,
+    /// Replicate hot rows to all `factor` partitions (build side) instead of
+    /// splitting them (probe side).
+    pub replicate: bool,
 }
 
 /// Target endpoint for a single shuffle partition.
@@ -73,6 +95,8 @@ mod tests {
                 },
             ],
             target_table: None,
+            query_id: None,
+            salt: None,
         }
     }
 
@@ -101,6 +125,22 @@ mod tests {
         assert!(desc.target_for_partition(99).is_none());
     }
 
+    #[test]
+    fn salt_roundtrip_and_default() {
+        let mut desc = sample_descriptor();
+        desc.salt = Some(SaltSpec {
+            hot_values: vec!["42".to_string()],
+            factor: 4,
+            replicate: true,
+        });
+        let restored = ShuffleDescriptor::from_json_bytes(&desc.to_json_bytes().unwrap()).unwrap();
+        assert_eq!(restored.salt, desc.salt);
+
+        // Descriptors from nodes that predate salting carry no salt field.
+        let legacy = br#"{"shuffle_id":"s","join_keys":["k"],"num_partitions":1,"partition_targets":[]}"#;
+        assert!(ShuffleDescriptor::from_json_bytes(legacy).unwrap().salt.is_none());
+    }
+
     #[test]
     fn deserialize_invalid_bytes() {
         let result = ShuffleDescriptor::from_json_bytes(b"not json");
@@ -115,6 +155,8 @@ mod tests {
             num_partitions: 3,
             partition_targets: vec![],
             target_table: None,
+            query_id: None,
+            salt: None,
         };
         let bytes = desc.to_json_bytes().unwrap();
         let restored = ShuffleDescriptor::from_json_bytes(&bytes).unwrap();
@@ -3,19 +3,44 @@
 //!
 //! Runs AFTER `FederationOptimizerRule` which pushes co-located scans to
 //! executors. This rule handles the remaining cross-context joins.
+//!
+//! Large-large joins above `SWARM_SORT_MERGE_THRESHOLD` rows per side are
+//! planned as a sort-merge join over the shuffled partitions, so neither side
+//! has to fit in a hash table; the per-partition sorts spill to disk when the
+//! session has a memory limit.
+//!
+//! Probe-side join keys the catalog reports as hot (above
+//! `SWARM_SKEW_THRESHOLD` of the probe rows) are salted over
+//! `SWARM_SALT_FACTOR` partitions, with the matching build rows replicated.
+//!
+//! [`JoinReorderRule`] is the logical counterpart: it picks the join order
+//! from the same catalog statistics before federation runs.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use datafusion::common::tree_node::{Transformed, TreeNode, TreeNodeRecursion};
+use datafusion::arrow::compute::SortOptions;
+use datafusion::common::NullEquality;
 use datafusion::config::ConfigOptions;
 use datafusion::error::Result as DFResult;
+use datafusion::logical_expr::utils::split_conjunction_owned;
+use datafusion::logical_expr::{Expr, JoinConstraint, JoinType, LogicalPlan, LogicalPlanBuilder};
+use datafusion::optimizer::{OptimizerConfig, OptimizerRule};
+use datafusion::physical_expr::expressions::Column;
+use datafusion::physical_expr::{LexOrdering, PhysicalExpr, PhysicalSortExpr};
 use datafusion::physical_optimizer::PhysicalOptimizerRule;
-use datafusion::physical_plan::joins::HashJoinExec;
+use datafusion::physical_plan::joins::{HashJoinExec, PartitionMode, SortMergeJoinExec};
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::sorts::sort::SortExec;
 use datafusion::physical_plan::ExecutionPlan;
 
-use crate::catalog;
-use crate::logging::SwarmLogger;
-use crate::shuffle_descriptor::{ShuffleDescriptor, ShuffleTarget};
+use crate::catalog::{self, HotKey, StatValue};
+use crate::distributed_scheduler::{self, LoadPolicy};
+use crate::distributed_table_provider::{policy_route_order, route_choice_reason, ScanTarget};
+use crate::logging::{self, SwarmLogger};
+use crate::shuffle_descriptor::{SaltSpec, ShuffleDescriptor, ShuffleTarget};
 use crate::shuffle_partition;
 use crate::shuffle_registry;
 use crate::shuffle_writer::ShuffleWriterExec;
@@ -24,15 +49,34 @@ use crate::shuffle_writer::ShuffleWriterExec;
 /// to the other side's node rather than hash-shuffled.
 const DEFAULT_BROADCAST_THRESHOLD: u64 = 100_000;
 
+/// Default sort-merge threshold: when both sides of a shuffled join have more
+/// rows than this, the join is sort-merged instead of hashed.
+const DEFAULT_SORT_MERGE_THRESHOLD: u64 = 10_000_000;
+
+/// Default salt factor: partitions a hot key's rows are spread over.
+const DEFAULT_SALT_FACTOR: usize = 4;
+
+/// Default skew threshold: keys holding at least this share of the probe
+/// side's rows are salted.
+const DEFAULT_SKEW_THRESHOLD: f64 = 0.1;
+
 /// Join strategy chosen based on table statistics and node topology.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum JoinStrategy {
     /// Both tables on the same node — no shuffle needed.
     CoLocated,
-    /// One table is small enough to broadcast to the other node.
-    Broadcast { small_side: BroadcastSide },
+    /// One table is small enough to broadcast to the other node. `target`
+    /// is the flight endpoint of the node holding the large side that the
+    /// load policy picked.
+    Broadcast {
+        small_side: BroadcastSide,
+        target: Option<String>,
+    },
     /// Both tables are large and on different nodes — hash-shuffle both.
     HashShuffle,
+    /// Both tables are too large to hash — shuffle both, sort each partition
+    /// by the join keys and merge.
+    SortMergeShuffle,
     /// No stats available — fall back to pull-to-coordinator (no change).
     PullToCoordinator,
 }
@@ -49,8 +93,25 @@ pub struct CatalogStats {
     pub table_stats: HashMap<String, (u64, Vec<String>)>,
     /// Broadcast threshold (rows). Tables below this are broadcast.
     pub broadcast_threshold: u64,
+    /// Sort-merge threshold (rows). Shuffled joins with both sides above this
+    /// are sort-merged.
+    pub sort_merge_threshold: u64,
+    /// table_name → column → hot values, for skew detection.
+    pub hot_keys: HashMap<String, BTreeMap<String, Vec<HotKey>>>,
+    /// Partitions a hot key is salted over; below 2 disables salting.
+    pub salt_factor: usize,
+    /// Share of the probe rows above which a key is salted.
+    pub skew_threshold: f64,
+    /// Undrained bytes each shuffle partition may buffer on this node.
+    pub shuffle_buffer_bytes: usize,
     /// The local node's flight endpoint.
     pub local_endpoint: Option<String>,
+    /// flight endpoint → node name, for every node in the catalog.
+    pub endpoint_nodes: HashMap<String, String>,
+    /// Picks the broadcast target among the nodes holding the large side.
+    pub load_policy: LoadPolicy,
+    /// Load score per node name; only filled for `LoadPolicy::LeastLoaded`.
+    pub node_scores: HashMap<String, f64>,
     /// Tokio runtime handle for spawning shuffle tasks.
     pub runtime_handle: tokio::runtime::Handle,
 }
@@ -60,6 +121,12 @@ impl std::fmt::Debug for CatalogStats {
         f.debug_struct("CatalogStats")
             .field("table_count", &self.table_stats.len())
             .field("broadcast_threshold", &self.broadcast_threshold)
+            .field("sort_merge_threshold", &self.sort_merge_threshold)
+            .field("hot_key_tables", &self.hot_keys.len())
+            .field("salt_factor", &self.salt_factor)
+            .field("skew_threshold", &self.skew_threshold)
+            .field("shuffle_buffer_bytes", &self.shuffle_buffer_bytes)
+            .field("load_policy", &self.load_policy)
             .finish()
     }
 }
@@ -73,17 +140,59 @@ impl CatalogStats {
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(DEFAULT_BROADCAST_THRESHOLD);
+        let sort_merge_threshold = std::env::var("SWARM_SORT_MERGE_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SORT_MERGE_THRESHOLD);
+        let salt_factor = std::env::var("SWARM_SALT_FACTOR")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SALT_FACTOR);
+        let skew_threshold = std::env::var("SWARM_SKEW_THRESHOLD")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_SKEW_THRESHOLD);
+        let load_policy = LoadPolicy::current();
+        let node_scores = match load_policy {
+            LoadPolicy::LeastLoaded => distributed_scheduler::node_load_scores(),
+            _ => HashMap::new(),
+        };
 
         Self {
             table_stats,
             broadcast_threshold,
+            sort_merge_threshold,
+            hot_keys: catalog::get_hot_keys().unwrap_or_default(),
+            salt_factor,
+            skew_threshold,
+            shuffle_buffer_bytes: Self::fetch_shuffle_buffer_mb() * 1024 * 1024,
             local_endpoint,
+            endpoint_nodes: Self::fetch_endpoint_nodes(),
+            load_policy,
+            node_scores,
             runtime_handle,
         }
     }
 
+    /// `shuffle_buffer_mb` as set on this node through `trex_db_set`.
+    fn fetch_shuffle_buffer_mb() -> usize {
+        crate::gossip::GossipRegistry::instance()
+            .get_self_config()
+            .ok()
+            .and_then(|config| {
+                config
+                    .into_iter()
+                    .find(|(k, _)| k == shuffle_registry::SHUFFLE_BUFFER_MB_KEY)
+            })
+            .and_then(|(_, v)| v.parse().ok())
+            .unwrap_or(shuffle_registry::DEFAULT_SHUFFLE_BUFFER_MB)
+    }
+
     fn fetch_table_stats() -> HashMap<String, (u64, Vec<String>)> {
         let entries = catalog::get_all_tables().unwrap_or_default();
+        // Suspect or dead nodes still count towards the row estimate but are
+        // not sent shuffle partitions.
+        let unreachable = crate::gossip::GossipRegistry::instance().unreachable_nodes();
         let mut stats: HashMap<String, (u64, Vec<String>)> = HashMap::new();
 
         for entry in entries {
@@ -91,6 +200,9 @@ impl CatalogStats {
                 .entry(entry.table_name.clone())
                 .or_insert((0, Vec::new()));
             stat.0 += entry.approx_rows;
+            if unreachable.contains(&entry.node_name) {
+                continue;
+            }
             if let Some(ep) = entry.flight_endpoint {
                 if !stat.1.contains(&ep) {
                     stat.1.push(ep);
@@ -101,6 +213,14 @@ impl CatalogStats {
         stats
     }
 
+    fn fetch_endpoint_nodes() -> HashMap<String, String> {
+        catalog::get_all_tables()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|e| e.flight_endpoint.map(|ep| (ep, e.node_name)))
+            .collect()
+    }
+
     fn fetch_local_endpoint() -> Option<String> {
         let self_id = catalog::get_self_node_id()?;
         let entries = catalog::get_all_tables().ok()?;
@@ -111,6 +231,9 @@ impl CatalogStats {
     }
 }
 
+/// Rotates the broadcast target chosen under [`LoadPolicy::RoundRobin`].
+static NEXT_BROADCAST: AtomicUsize = AtomicUsize::new(0);
+
 /// Optimizer rule that inserts shuffle boundaries for cross-node joins.
 #[derive(Debug)]
 pub struct ShuffleInsertionRule {
@@ -163,11 +286,15 @@ impl ShuffleInsertionRule {
                 if lr <= self.catalog_stats.broadcast_threshold {
                     JoinStrategy::Broadcast {
                         small_side: BroadcastSide::Left,
+                        target: self.broadcast_target(right_tables, &right_endpoints),
                     }
                 } else if rr <= self.catalog_stats.broadcast_threshold {
                     JoinStrategy::Broadcast {
                         small_side: BroadcastSide::Right,
+                        target: self.broadcast_target(left_tables, &left_endpoints),
                     }
+                } else if lr.min(rr) > self.catalog_stats.sort_merge_threshold {
+                    JoinStrategy::SortMergeShuffle
                 } else {
                     JoinStrategy::HashShuffle
                 }
@@ -176,6 +303,56 @@ impl ShuffleInsertionRule {
         }
     }
 
+    /// The node among `endpoints`, those holding the large side, the small
+    /// side is broadcast to, picked by the load policy the way a shard scan
+    /// picks among its copies.
+    fn broadcast_target(&self, large_tables: &[String], endpoints: &[String]) -> Option<String> {
+        let stats = &self.catalog_stats;
+        let table_name = large_tables.first()?;
+        let candidates: Vec<ScanTarget> = endpoints
+            .iter()
+            .map(|ep| ScanTarget {
+                node_name: stats.endpoint_nodes.get(ep).cloned().unwrap_or_else(|| ep.clone()),
+                flight_endpoint: ep.clone(),
+                table_name: table_name.clone(),
+            })
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        let routes: Vec<Vec<&ScanTarget>> = candidates.iter().map(|t| vec![t]).collect();
+        let local_node = stats
+            .local_endpoint
+            .as_ref()
+            .and_then(|ep| stats.endpoint_nodes.get(ep))
+            .map(String::as_str);
+        let start = NEXT_BROADCAST.fetch_add(1, Ordering::Relaxed);
+        let order = policy_route_order(
+            stats.load_policy,
+            &routes,
+            &stats.node_scores,
+            local_node,
+            start,
+        );
+        let chosen = &candidates[order[0]];
+        SwarmLogger::debug(
+            "shuffle-optimizer",
+            &format!(
+                "Broadcast target for {:?}: {} ({})",
+                large_tables,
+                chosen.node_name,
+                route_choice_reason(
+                    stats.load_policy,
+                    &routes,
+                    order[0],
+                    &stats.node_scores,
+                    local_node
+                ),
+            ),
+        );
+        Some(chosen.flight_endpoint.clone())
+    }
+
     /// Recursively walk the plan and insert shuffle boundaries at HashJoinExec nodes.
     fn optimize_node(
         &self,
@@ -193,7 +370,116 @@ impl ShuffleInsertionRule {
             plan
         };
 
-        let is_hash_shuffle = plan
+        // `swap_inputs` may wrap the join in a projection that restores the
+        // column order; the shuffle goes around the join itself.
+        if let Some(swapped) = self.swap_to_smaller_build_side(&plan)? {
+            if swapped.as_any().is::<HashJoinExec>() {
+                return self.plan_join(swapped);
+            }
+            let join = Arc::clone(swapped.children()[0]);
+            let planned = self.plan_join(join)?;
+            return swapped.with_new_children(vec![planned]);
+        }
+
+        self.plan_join(plan)
+    }
+
+    /// Estimated rows across `tables`, if every one is in the catalog.
+    fn estimated_rows(&self, tables: &[String]) -> Option<u64> {
+        if tables.is_empty() {
+            return None;
+        }
+        tables
+            .iter()
+            .map(|t| self.catalog_stats.table_stats.get(t).map(|(r, _)| *r))
+            .sum()
+    }
+
+    /// Values of `probe_column` that hold at least `skew_threshold` of the
+    /// probe side's rows, sorted. Empty when salting is disabled or would
+    /// change the result: build rows are replicated, so only join types
+    /// that never emit unmatched build rows can be salted.
+    fn skewed_values(
+        &self,
+        join_type: &JoinType,
+        probe_column: &str,
+        probe_tables: &[String],
+    ) -> Vec<String> {
+        if self.catalog_stats.salt_factor < 2
+            || !matches!(
+                join_type,
+                JoinType::Inner | JoinType::Right | JoinType::RightSemi | JoinType::RightAnti
+            )
+        {
+            return Vec::new();
+        }
+        let Some(probe_rows) = self.estimated_rows(probe_tables) else {
+            return Vec::new();
+        };
+
+        let mut rows_by_value: HashMap<String, u64> = HashMap::new();
+        for table in probe_tables {
+            let keys = self
+                .catalog_stats
+                .hot_keys
+                .get(table)
+                .and_then(|columns| columns.get(probe_column));
+            for key in keys.into_iter().flatten() {
+                *rows_by_value.entry(stat_value_text(&key.value)).or_default() += key.rows;
+            }
+        }
+
+        let min_rows = self.catalog_stats.skew_threshold * probe_rows as f64;
+        let mut values: Vec<String> = rows_by_value
+            .into_iter()
+            .filter(|(_, rows)| *rows as f64 >= min_rows)
+            .map(|(value, _)| value)
+            .collect();
+        values.sort();
+        values
+    }
+
+    /// Swap a partitioned inner hash join whose build (left) side is
+    /// estimated larger than its probe side. Collect-left joins are left
+    /// alone: their build side is already a single coalesced partition.
+    fn swap_to_smaller_build_side(
+        &self,
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> DFResult<Option<Arc<dyn ExecutionPlan>>> {
+        let Some(hash_join) = plan.as_any().downcast_ref::<HashJoinExec>() else {
+            return Ok(None);
+        };
+        if *hash_join.join_type() != datafusion::common::JoinType::Inner
+            || *hash_join.partition_mode() != PartitionMode::Partitioned
+        {
+            return Ok(None);
+        }
+        let left_tables = extract_table_names_from_plan(hash_join.left());
+        let right_tables = extract_table_names_from_plan(hash_join.right());
+        match (
+            self.estimated_rows(&left_tables),
+            self.estimated_rows(&right_tables),
+        ) {
+            (Some(left_rows), Some(right_rows)) if left_rows > right_rows => {
+                SwarmLogger::debug(
+                    "shuffle-optimizer",
+                    &format!(
+                        "Building join on {:?} ({} rows) instead of {:?} ({} rows)",
+                        right_tables, right_rows, left_tables, left_rows,
+                    ),
+                );
+                hash_join.swap_inputs(PartitionMode::Partitioned).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Pick the strategy for a join and insert a shuffle if it needs one.
+    fn plan_join(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> DFResult<Arc<dyn ExecutionPlan>> {
+        let strategy = plan
             .as_any()
             .downcast_ref::<HashJoinExec>()
             .map(|hash_join| {
@@ -209,21 +495,23 @@ impl ShuffleInsertionRule {
                     ),
                 );
 
-                strategy == JoinStrategy::HashShuffle
-            })
-            .unwrap_or(false);
+                strategy
+            });
 
-        if is_hash_shuffle {
-            return self.insert_shuffle(plan);
+        match strategy {
+            Some(JoinStrategy::HashShuffle) => self.insert_shuffle(plan, false),
+            Some(JoinStrategy::SortMergeShuffle) => self.insert_shuffle(plan, true),
+            _ => Ok(plan),
         }
-
-        Ok(plan)
     }
 
-    /// Insert ShuffleWriter/Reader around a HashJoinExec.
+    /// Insert ShuffleWriter/Reader around a HashJoinExec. With `sort_merge`
+    /// the join itself is replaced by a sort-merge join over the shuffled
+    /// partitions.
     fn insert_shuffle(
         &self,
         plan: Arc<dyn ExecutionPlan>,
+        sort_merge: bool,
     ) -> DFResult<Arc<dyn ExecutionPlan>> {
         let hash_join = plan
             .as_any()
@@ -290,17 +578,54 @@ impl ShuffleInsertionRule {
             .and_then(|local_ep| all_endpoints.iter().position(|ep| ep == local_ep))
             .unwrap_or(0);
 
+        // Salting keys on the first join key; the right side probes.
+        let hot_values = match on.first() {
+            Some((_, probe_key)) if num_partitions > 1 => probe_key
+                .as_any()
+                .downcast_ref::<Column>()
+                .map(|column| {
+                    self.skewed_values(hash_join.join_type(), column.name(), &right_tables)
+                })
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let salt = |replicate: bool| {
+            (!hot_values.is_empty()).then(|| SaltSpec {
+                hot_values: hot_values.clone(),
+                factor: self.catalog_stats.salt_factor,
+                replicate,
+            })
+        };
+        if !hot_values.is_empty() {
+            SwarmLogger::info(
+                "shuffle-optimizer",
+                &format!(
+                    "Salting {} hot key(s) of {:?} over {} partitions: {:?}",
+                    hot_values.len(),
+                    right_tables,
+                    self.catalog_stats.salt_factor.min(num_partitions),
+                    hot_values,
+                ),
+            );
+        }
+
         let left_desc = ShuffleDescriptor {
             shuffle_id: format!("{}-left", shuffle_id),
             join_keys: left_key_names.clone(),
             num_partitions,
             partition_targets: partition_targets.clone(),
             target_table: None,
+            query_id: logging::current_query_id(),
+            salt: salt(true),
         };
         let left_key_indices =
             shuffle_partition::resolve_key_indices(&left.schema(), &left_key_names)?;
 
-        shuffle_registry::register_shuffle(&left_desc.shuffle_id, num_partitions);
+        shuffle_registry::register_shuffle_with_buffer(
+            &left_desc.shuffle_id,
+            num_partitions,
+            self.catalog_stats.shuffle_buffer_bytes,
+        );
 
         let left_writer = Arc::new(ShuffleWriterExec::new(
             left,
@@ -316,11 +641,17 @@ impl ShuffleInsertionRule {
             num_partitions,
             partition_targets,
             target_table: None,
+            query_id: logging::current_query_id(),
+            salt: salt(false),
         };
         let right_key_indices =
             shuffle_partition::resolve_key_indices(&right.schema(), &right_key_names)?;
 
-        shuffle_registry::register_shuffle(&right_desc.shuffle_id, num_partitions);
+        shuffle_registry::register_shuffle_with_buffer(
+            &right_desc.shuffle_id,
+            num_partitions,
+            self.catalog_stats.shuffle_buffer_bytes,
+        );
 
         let right_writer = Arc::new(ShuffleWriterExec::new(
             right,
@@ -330,19 +661,26 @@ impl ShuffleInsertionRule {
             self.catalog_stats.runtime_handle.clone(),
         ));
 
-        // Reconstruct the HashJoinExec with shuffle writers as inputs.
+        // Reconstruct the join with shuffle writers as inputs.
         // The ShuffleWriterExec reads from its child, hash-partitions the data,
         // sends remote partitions via Flight, and yields the local partition.
-        let rebuilt = plan.with_new_children(vec![
-            left_writer as Arc<dyn ExecutionPlan>,
-            right_writer as Arc<dyn ExecutionPlan>,
-        ])?;
+        let rebuilt = if sort_merge {
+            sort_merge_join(hash_join, left_writer, right_writer)?
+        } else {
+            plan.with_new_children(vec![
+                left_writer as Arc<dyn ExecutionPlan>,
+                right_writer as Arc<dyn ExecutionPlan>,
+            ])?
+        };
 
         SwarmLogger::info(
             "shuffle-optimizer",
             &format!(
-                "Inserted hash shuffle for join: shuffle_id={}, partitions={}, local_partition={}",
-                shuffle_id, num_partitions, local_partition_id,
+                "Inserted {} shuffle for join: shuffle_id={}, partitions={}, local_partition={}",
+                if sort_merge { "sort-merge" } else { "hash" },
+                shuffle_id,
+                num_partitions,
+                local_partition_id,
             ),
         );
 
@@ -350,6 +688,71 @@ impl ShuffleInsertionRule {
     }
 }
 
+/// A hot-key stat value as the text the shuffle writer casts back to the
+/// key column's type.
+fn stat_value_text(value: &StatValue) -> String {
+    match value {
+        StatValue::Int(v) => v.to_string(),
+        StatValue::Float(v) => v.to_string(),
+        StatValue::Text(v) => v.clone(),
+    }
+}
+
+/// Rebuild `hash_join` as a sort-merge join over `left` and `right`, each
+/// sorted by its join keys within every partition.
+///
+/// Null keys never match under `NullEqualsNothing` (plain `=`), and runs of
+/// equal keys produce every left × right pair, as the hash join would.
+fn sort_merge_join(
+    hash_join: &HashJoinExec,
+    left: Arc<dyn ExecutionPlan>,
+    right: Arc<dyn ExecutionPlan>,
+) -> DFResult<Arc<dyn ExecutionPlan>> {
+    let on = hash_join.on().to_vec();
+    let left = sort_partitions(left, on.iter().map(|(l, _)| Arc::clone(l)))?;
+    let right = sort_partitions(right, on.iter().map(|(_, r)| Arc::clone(r)))?;
+    let sort_options = vec![SortOptions::default(); on.len()];
+
+    let join: Arc<dyn ExecutionPlan> = Arc::new(SortMergeJoinExec::try_new(
+        left,
+        right,
+        on,
+        hash_join.filter().cloned(),
+        *hash_join.join_type(),
+        sort_options,
+        hash_join.null_equality(),
+    )?);
+
+    // The sort-merge join has no embedded projection; apply the hash join's.
+    let Some(projection) = &hash_join.projection else {
+        return Ok(join);
+    };
+    let schema = join.schema();
+    let exprs: Vec<(Arc<dyn PhysicalExpr>, String)> = projection
+        .iter()
+        .map(|&i| {
+            let name = schema.field(i).name().clone();
+            (Arc::new(Column::new(&name, i)) as Arc<dyn PhysicalExpr>, name)
+        })
+        .collect();
+    Ok(Arc::new(ProjectionExec::try_new(exprs, join)?))
+}
+
+/// Sort each partition of `input` by `keys`. `SortExec` spills sorted runs to
+/// disk once the session's memory pool is exhausted.
+fn sort_partitions(
+    input: Arc<dyn ExecutionPlan>,
+    keys: impl Iterator<Item = Arc<dyn PhysicalExpr>>,
+) -> DFResult<Arc<dyn ExecutionPlan>> {
+    let ordering = LexOrdering::new(keys.map(|expr| PhysicalSortExpr::new(expr, SortOptions::default())))
+        .ok_or_else(|| {
+            datafusion::error::DataFusionError::Internal(
+                "sort-merge join needs at least one join key".to_string(),
+            )
+        })?;
+    Ok(Arc::new(SortExec::new(ordering, input).with_preserve_partitioning(true)))
+}
+
 impl PhysicalOptimizerRule for ShuffleInsertionRule {
     fn optimize(
         &self,
@@ -368,6 +771,219 @@ impl PhysicalOptimizerRule for ShuffleInsertionRule {
     }
 }
 
+/// Logical optimizer rule that reorders chains of three or more inner joins
+/// using catalog row counts.
+///
+/// Starting from the smallest input, it repeatedly joins the smallest input
+/// that shares a join predicate with what has been joined so far, so each
+/// join shuffles as little as the statistics allow. Ties break on the
+/// input's plan text, so the same stats always give the same order. Runs
+/// before `FederationOptimizerRule`, while the inputs are still plain table
+/// scans; chains with any input missing from the catalog keep their written
+/// order. Which side of each join builds is left to
+/// [`ShuffleInsertionRule`], which sees the physical join.
+#[derive(Debug)]
+pub struct JoinReorderRule {
+    catalog_stats: Arc<CatalogStats>,
+}
+
+/// Shape of a join tree over the flattened inputs of one chain.
+#[derive(Debug, PartialEq, Eq)]
+enum JoinShape {
+    Input(usize),
+    Join(Box<JoinShape>, Box<JoinShape>),
+}
+
+impl JoinReorderRule {
+    pub fn new(catalog_stats: Arc<CatalogStats>) -> Self {
+        Self { catalog_stats }
+    }
+
+    /// Estimated rows of a join input, or `None` if any of its tables is
+    /// unknown to the catalog.
+    fn input_rows(&self, input: &LogicalPlan) -> Option<u64> {
+        let mut tables = Vec::new();
+        let mut opaque = false;
+        input
+            .apply(|node| {
+                match node {
+                    LogicalPlan::TableScan(scan) => tables.push(scan.table_name.table().to_string()),
+                    LogicalPlan::Extension(_) => opaque = true,
+                    _ => {}
+                }
+                Ok(TreeNodeRecursion::Continue)
+            })
+            .ok()?;
+        if opaque || tables.is_empty() {
+            return None;
+        }
+        tables
+            .iter()
+            .map(|t| self.catalog_stats.table_stats.get(t).map(|(rows, _)| *rows))
+            .sum()
+    }
+
+    fn reorder(&self, plan: LogicalPlan) -> DFResult<Transformed<LogicalPlan>> {
+        let mut inputs = Vec::new();
+        let mut predicates = Vec::new();
+        let written = flatten_join_chain(plan.clone(), &mut inputs, &mut predicates);
+        if inputs.len() < 3 {
+            return Ok(Transformed::no(plan));
+        }
+
+        let Some(rows) = inputs
+            .iter()
+            .map(|input| self.input_rows(input))
+            .collect::<Option<Vec<u64>>>()
+        else {
+            return Ok(Transformed::no(plan));
+        };
+
+        // Which inputs each predicate references; a column no input provides
+        // (e.g. an outer reference) leaves the chain alone.
+        let mut predicate_inputs = Vec::with_capacity(predicates.len());
+        for predicate in &predicates {
+            let mut referenced = BTreeSet::new();
+            for column in predicate.column_refs() {
+                match inputs.iter().position(|input| input.schema().has_column(column)) {
+                    Some(i) => {
+                        referenced.insert(i);
+                    }
+                    None => return Ok(Transformed::no(plan)),
+                }
+            }
+            predicate_inputs.push(referenced);
+        }
+
+        let keys: Vec<String> = inputs
+            .iter()
+            .map(|input| input.display_indent().to_string())
+            .collect();
+        let order = choose_join_order(&rows, &keys, &predicate_inputs);
+
+        // Left-deep in `order`: this is also the shape `EliminateCrossJoin`
+        // rebuilds inner joins into, so later passes keep it.
+        let mut joined = BTreeSet::from([order[0]]);
+        let mut shape = JoinShape::Input(order[0]);
+        let mut current = inputs[order[0]].clone();
+        let mut used = vec![false; predicates.len()];
+
+        for &next in &order[1..] {
+            joined.insert(next);
+            let mut on = Vec::new();
+            for (i, predicate) in predicates.iter().enumerate() {
+                if !used[i] && predicate_inputs[i].is_subset(&joined) {
+                    used[i] = true;
+                    on.push(predicate.clone());
+                }
+            }
+
+            let builder = LogicalPlanBuilder::from(current);
+            current = if on.is_empty() {
+                builder.cross_join(inputs[next].clone())?.build()?
+            } else {
+                builder.join_on(inputs[next].clone(), JoinType::Inner, on)?.build()?
+            };
+            shape = JoinShape::Join(Box::new(shape), Box::new(JoinShape::Input(next)));
+        }
+
+        if shape == written {
+            return Ok(Transformed::no(plan));
+        }
+
+        SwarmLogger::debug(
+            "shuffle-optimizer",
+            &format!("Reordered {}-way join: order={:?}, rows={:?}", inputs.len(), order, rows),
+        );
+
+        // Restore the original column order for the operators above.
+        let columns = plan.schema().columns().into_iter().map(Expr::Column);
+        let reordered = LogicalPlanBuilder::from(current).project(columns)?.build()?;
+        Ok(Transformed::yes(reordered))
+    }
+}
+
+impl OptimizerRule for JoinReorderRule {
+    fn name(&self) -> &str {
+        "join_reorder_rule"
+    }
+
+    fn supports_rewrite(&self) -> bool {
+        true
+    }
+
+    fn rewrite(
+        &self,
+        plan: LogicalPlan,
+        _config: &dyn OptimizerConfig,
+    ) -> DFResult<Transformed<LogicalPlan>> {
+        // Top-down, so each chain is reordered as a whole. Sub-chains of a
+        // reordered tree come out of `choose_join_order` unchanged.
+        plan.transform_down(|node| {
+            if is_reorderable_join(&node) {
+                self.reorder(node)
+            } else {
+                Ok(Transformed::no(node))
+            }
+        })
+    }
+}
+
+fn is_reorderable_join(plan: &LogicalPlan) -> bool {
+    matches!(
+        plan,
+        LogicalPlan::Join(join)
+            if join.join_type == JoinType::Inner
+                && join.join_constraint == JoinConstraint::On
+                && join.null_equality == NullEquality::NullEqualsNothing
+    )
+}
+
+/// Collect the inputs and predicates of a chain of inner joins, returning the
+/// written shape.
+fn flatten_join_chain(
+    plan: LogicalPlan,
+    inputs: &mut Vec<LogicalPlan>,
+    predicates: &mut Vec<Expr>,
+) -> JoinShape {
+    if !is_reorderable_join(&plan) {
+        inputs.push(plan);
+        return JoinShape::Input(inputs.len() - 1);
+    }
+    let LogicalPlan::Join(join) = plan else {
+        unreachable!("checked by is_reorderable_join")
+    };
+    let left = flatten_join_chain(Arc::unwrap_or_clone(join.left), inputs, predicates);
+    let right = flatten_join_chain(Arc::unwrap_or_clone(join.right), inputs, predicates);
+    predicates.extend(join.on.into_iter().map(|(l, r)| l.eq(r)));
+    if let Some(filter) = join.filter {
+        predicates.extend(split_conjunction_owned(filter));
+    }
+    JoinShape::Join(Box::new(left), Box::new(right))
+}
+
+/// Greedy join order: start from the smallest input, then repeatedly take the
+/// smallest input connected to the joined set by a predicate (any remaining
+/// input if none is). Ties break on `keys`, then on position.
+fn choose_join_order(rows: &[u64], keys: &[String], predicate_inputs: &[BTreeSet<usize>]) -> Vec<usize> {
+    let rank = |i: &usize| (rows[*i], &keys[*i], *i);
+    let mut remaining: Vec<usize> = (0..rows.len()).collect();
+    remaining.sort_by(|a, b| rank(a).cmp(&rank(b)));
+
+    let mut order = vec![remaining.remove(0)];
+    while !remaining.is_empty() {
+        let connected = remaining.iter().position(|candidate| {
+            predicate_inputs.iter().any(|refs| {
+                refs.contains(candidate)
+                    && refs.iter().any(|r| order.contains(r))
+                    && refs.iter().all(|r| r == candidate || order.contains(r))
+            })
+        });
+        order.push(remaining.remove(connected.unwrap_or(0)));
+    }
+    order
+}
+
 /// Extract table names from an execution plan by looking for known plan types
 /// (federation scan nodes, distributed exec, etc.).
 fn extract_table_names_from_plan(plan: &Arc<dyn ExecutionPlan>) -> Vec<String> {
@@ -407,7 +1023,15 @@ mod tests {
         CatalogStats {
             table_stats: HashMap::new(),
             broadcast_threshold: DEFAULT_BROADCAST_THRESHOLD,
+            sort_merge_threshold: DEFAULT_SORT_MERGE_THRESHOLD,
+            hot_keys: HashMap::new(),
+            salt_factor: DEFAULT_SALT_FACTOR,
+            skew_threshold: DEFAULT_SKEW_THRESHOLD,
+            shuffle_buffer_bytes: shuffle_registry::DEFAULT_SHUFFLE_BUFFER_MB * 1024 * 1024,
             local_endpoint: None,
+            endpoint_nodes: HashMap::new(),
+            load_policy: LoadPolicy::default(),
+            node_scores: HashMap::new(),
             runtime_handle: tokio::runtime::Runtime::new().unwrap().handle().clone(),
         }
     }
@@ -425,7 +1049,15 @@ mod tests {
         CatalogStats {
             table_stats,
             broadcast_threshold: DEFAULT_BROADCAST_THRESHOLD,
+            sort_merge_threshold: DEFAULT_SORT_MERGE_THRESHOLD,
+            hot_keys: HashMap::new(),
+            salt_factor: DEFAULT_SALT_FACTOR,
+            skew_threshold: DEFAULT_SKEW_THRESHOLD,
+            shuffle_buffer_bytes: shuffle_registry::DEFAULT_SHUFFLE_BUFFER_MB * 1024 * 1024,
             local_endpoint: Some("http://10.0.0.1:8815".to_string()),
+            endpoint_nodes: HashMap::new(),
+            load_policy: LoadPolicy::default(),
+            node_scores: HashMap::new(),
             runtime_handle: tokio::runtime::Runtime::new().unwrap().handle().clone(),
         }
     }
@@ -459,6 +1091,7 @@ mod tests {
             strategy,
             JoinStrategy::Broadcast {
                 small_side: BroadcastSide::Left,
+                target: Some("http://10.0.0.2:8815".to_string()),
             }
         );
     }
@@ -478,10 +1111,44 @@ mod tests {
             strategy,
             JoinStrategy::Broadcast {
                 small_side: BroadcastSide::Right,
+                target: Some("http://10.0.0.1:8815".to_string()),
             }
         );
     }
 
+    #[test]
+    fn broadcast_target_follows_load_policy() {
+        let target = |policy: LoadPolicy| {
+            let mut stats = stats_with_tables(vec![
+                ("dim_table", 50_000, vec!["http://10.0.0.3:8815"]),
+                (
+                    "fact_table",
+                    10_000_000,
+                    vec!["http://10.0.0.1:8815", "http://10.0.0.2:8815"],
+                ),
+            ]);
+            stats.endpoint_nodes = HashMap::from([
+                ("http://10.0.0.1:8815".to_string(), "node-a".to_string()),
+                ("http://10.0.0.2:8815".to_string(), "node-b".to_string()),
+            ]);
+            stats.node_scores =
+                HashMap::from([("node-a".to_string(), 1.4), ("node-b".to_string(), 0.2)]);
+            stats.load_policy = policy;
+            let rule = ShuffleInsertionRule::new(Arc::new(stats));
+            match rule.choose_strategy(&["dim_table".to_string()], &["fact_table".to_string()]) {
+                JoinStrategy::Broadcast { target, .. } => target,
+                other => panic!("expected a broadcast, got {:?}", other),
+            }
+        };
+
+        // The fact table is on this node (node-a), but node-b is less loaded.
+        assert_eq!(
+            target(LoadPolicy::LeastLoaded).as_deref(),
+            Some("http://10.0.0.2:8815")
+        );
+        assert_eq!(target(LoadPolicy::Locality).as_deref(), Some("http://10.0.0.1:8815"));
+    }
+
     #[test]
     fn hash_shuffle_both_large() {
         let stats = stats_with_tables(vec![
@@ -496,6 +1163,156 @@ mod tests {
         assert_eq!(strategy, JoinStrategy::HashShuffle);
     }
 
+    #[test]
+    fn sort_merge_shuffle_both_very_large() {
+        let stats = stats_with_tables(vec![
+            ("events", 800_000_000, vec!["http://10.0.0.1:8815"]),
+            ("sessions", 50_000_000, vec!["http://10.0.0.2:8815"]),
+        ]);
+        let rule = ShuffleInsertionRule::new(Arc::new(stats));
+        let strategy = rule.choose_strategy(
+            &["events".to_string()],
+            &["sessions".to_string()],
+        );
+        assert_eq!(strategy, JoinStrategy::SortMergeShuffle);
+    }
+
+    #[test]
+    fn skewed_values_from_probe_hot_keys() {
+        let mut stats = stats_with_tables(vec![
+            ("orders", 1_000_000, vec!["http://10.0.0.1:8815"]),
+            ("customers", 2_000_000, vec!["http://10.0.0.2:8815"]),
+        ]);
+        let hot = |value: StatValue, rows| HotKey { value, rows };
+        stats.hot_keys.insert(
+            "orders".to_string(),
+            BTreeMap::from([(
+                "customer_id".to_string(),
+                vec![hot(StatValue::Int(42), 300_000), hot(StatValue::Int(7), 50_000)],
+            )]),
+        );
+        let rule = ShuffleInsertionRule::new(Arc::new(stats));
+        let orders = ["orders".to_string()];
+
+        // Only 42 holds at least 10% of the probe rows.
+        assert_eq!(rule.skewed_values(&JoinType::Inner, "customer_id", &orders), vec!["42"]);
+        assert_eq!(rule.skewed_values(&JoinType::Right, "customer_id", &orders), vec!["42"]);
+        assert!(rule.skewed_values(&JoinType::Inner, "region", &orders).is_empty());
+        // Replicated build rows would show up unmatched more than once.
+        assert!(rule.skewed_values(&JoinType::Left, "customer_id", &orders).is_empty());
+        assert!(rule.skewed_values(&JoinType::Full, "customer_id", &orders).is_empty());
+    }
+
+    #[test]
+    fn salting_disabled_by_factor() {
+        let mut stats = stats_with_tables(vec![("orders", 1_000, vec!["http://10.0.0.1:8815"])]);
+        stats.salt_factor = 1;
+        stats.hot_keys.insert(
+            "orders".to_string(),
+            BTreeMap::from([(
+                "k".to_string(),
+                vec![HotKey { value: StatValue::Text("x".to_string()), rows: 900 }],
+            )]),
+        );
+        let rule = ShuffleInsertionRule::new(Arc::new(stats));
+        assert!(rule
+            .skewed_values(&JoinType::Inner, "k", &["orders".to_string()])
+            .is_empty());
+    }
+
+    fn keyed_input(name: &str, keys: Vec<Option<i64>>) -> Arc<dyn ExecutionPlan> {
+        use arrow::array::Int64Array;
+        use arrow::datatypes::{DataType, Field, Schema};
+        use datafusion::datasource::memory::MemorySourceConfig;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(format!("{name}_key"), DataType::Int64, true),
+            Field::new(format!("{name}_row"), DataType::Int64, false),
+        ]));
+        let rows = Int64Array::from_iter_values(0..keys.len() as i64);
+        let batch = arrow::array::RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int64Array::from(keys)), Arc::new(rows)],
+        )
+        .unwrap();
+        MemorySourceConfig::try_new_exec(&[vec![batch]], schema, None).unwrap()
+    }
+
+    /// Run `join_type` as a hash join and as the sort-merge rewrite, returning
+    /// both results as sorted `(left_row, right_row)` pairs.
+    fn hash_and_sort_merge_rows(
+        join_type: JoinType,
+        projection: Option<Vec<usize>>,
+    ) -> (Vec<String>, Vec<String>) {
+        use arrow::util::display::array_value_to_string;
+        use datafusion::execution::TaskContext;
+
+        let left = keyed_input("l", vec![Some(1), Some(2), Some(2), None]);
+        let right = keyed_input("r", vec![Some(2), None, Some(2), Some(3), Some(2)]);
+        let on = vec![(
+            Arc::new(Column::new("l_key", 0)) as Arc<dyn PhysicalExpr>,
+            Arc::new(Column::new("r_key", 0)) as Arc<dyn PhysicalExpr>,
+        )];
+        let hash_join = HashJoinExec::try_new(
+            Arc::clone(&left),
+            Arc::clone(&right),
+            on,
+            None,
+            &join_type,
+            projection,
+            PartitionMode::Partitioned,
+            NullEquality::NullEqualsNothing,
+        )
+        .unwrap();
+        let sort_merge = sort_merge_join(&hash_join, left, right).unwrap();
+        assert_eq!(sort_merge.schema(), hash_join.schema());
+
+        let rows = |plan: Arc<dyn ExecutionPlan>| {
+            let batches = tokio::runtime::Runtime::new()
+                .unwrap()
+                .block_on(datafusion::physical_plan::collect(
+                    plan,
+                    Arc::new(TaskContext::default()),
+                ))
+                .unwrap();
+            let mut rows = Vec::new();
+            for batch in batches {
+                for i in 0..batch.num_rows() {
+                    let row: Vec<String> = batch
+                        .columns()
+                        .iter()
+                        .map(|c| array_value_to_string(c, i).unwrap())
+                        .collect();
+                    rows.push(row.join(","));
+                }
+            }
+            rows.sort();
+            rows
+        };
+        (rows(Arc::new(hash_join)), rows(sort_merge))
+    }
+
+    #[test]
+    fn sort_merge_join_skips_null_keys_and_crosses_duplicates() {
+        let (hash, sort_merge) = hash_and_sort_merge_rows(JoinType::Inner, None);
+        // Two left rows and three right rows share key 2; the nulls match nothing.
+        assert_eq!(sort_merge.len(), 6, "{sort_merge:?}");
+        assert!(sort_merge.iter().all(|row| row.starts_with("2,")));
+        assert_eq!(sort_merge, hash);
+    }
+
+    #[test]
+    fn sort_merge_join_keeps_outer_rows_and_projection() {
+        let (hash, sort_merge) = hash_and_sort_merge_rows(JoinType::Left, None);
+        assert_eq!(sort_merge, hash);
+        // The null-keyed left row is kept, unmatched.
+        assert!(sort_merge.contains(&",3,,".to_string()), "{sort_merge:?}");
+
+        let (hash, sort_merge) = hash_and_sort_merge_rows(JoinType::Inner, Some(vec![1, 3]));
+        assert_eq!(sort_merge, hash);
+        assert_eq!(sort_merge.len(), 6);
+    }
+
     #[test]
     fn pull_to_coordinator_no_stats() {
         let stats = empty_stats();
@@ -521,6 +1338,81 @@ mod tests {
         assert!(rule.schema_check());
     }
 
+    #[test]
+    fn join_order_starts_small_and_follows_predicates() {
+        let rows = [1_000_000, 10, 5_000];
+        let keys: Vec<String> = ["a", "b", "c"].iter().map(|k| k.to_string()).collect();
+        let preds = vec![BTreeSet::from([0, 1]), BTreeSet::from([0, 2])];
+        // `c` is smaller than `a` but only joins through it.
+        assert_eq!(choose_join_order(&rows, &keys, &preds), vec![1, 0, 2]);
+
+        // Equal row counts break on the key, whatever the input position.
+        let keys: Vec<String> = ["z", "y", "x"].iter().map(|k| k.to_string()).collect();
+        let preds = vec![BTreeSet::from([0, 1]), BTreeSet::from([1, 2])];
+        assert_eq!(choose_join_order(&[5, 5, 5], &keys, &preds), vec![2, 1, 0]);
+    }
+
+    fn optimized_three_way_join(stats: CatalogStats) -> String {
+        use arrow::datatypes::{DataType, Field, Schema};
+        use datafusion::datasource::MemTable;
+        use datafusion::execution::SessionStateBuilder;
+        use datafusion::optimizer::Optimizer;
+        use datafusion::prelude::SessionContext;
+
+        let mut rules = Optimizer::new().rules;
+        rules.insert(0, Arc::new(JoinReorderRule::new(Arc::new(stats))));
+        let state = SessionStateBuilder::new()
+            .with_default_features()
+            .with_optimizer_rules(rules)
+            .build();
+        let ctx = SessionContext::new_with_state(state);
+        for table in ["fact", "dim_a", "dim_b"] {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new(format!("{table}_v"), DataType::Int64, false),
+            ]));
+            ctx.register_table(table, Arc::new(MemTable::try_new(schema, vec![vec![]]).unwrap()))
+                .unwrap();
+        }
+
+        let sql = "SELECT * FROM fact \
+                   JOIN dim_b ON fact.id = dim_b.id \
+                   JOIN dim_a ON fact.id = dim_a.id";
+        let df = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(ctx.sql(sql))
+            .unwrap();
+        df.into_optimized_plan().unwrap().display_indent().to_string()
+    }
+
+    fn scan_order(plan: &str) -> Vec<&str> {
+        plan.lines()
+            .filter_map(|line| line.trim().strip_prefix("TableScan: "))
+            .map(|rest| rest.split_whitespace().next().unwrap_or(rest))
+            .collect()
+    }
+
+    #[test]
+    fn reorders_three_way_join_by_row_counts() {
+        let stats = || {
+            stats_with_tables(vec![
+                ("fact", 1_000_000, vec!["http://10.0.0.1:8815"]),
+                ("dim_a", 100, vec!["http://10.0.0.2:8815"]),
+                ("dim_b", 10_000, vec!["http://10.0.0.3:8815"]),
+            ])
+        };
+        let plan = optimized_three_way_join(stats());
+        // dim_a ⋈ fact first, then dim_b.
+        assert_eq!(scan_order(&plan), vec!["dim_a", "fact", "dim_b"], "{plan}");
+        assert_eq!(plan, optimized_three_way_join(stats()));
+    }
+
+    #[test]
+    fn keeps_written_order_without_stats() {
+        let plan = optimized_three_way_join(empty_stats());
+        assert_eq!(scan_order(&plan), vec!["fact", "dim_b", "dim_a"], "{plan}");
+    }
+
     #[test]
     fn extract_table_names_from_empty_plan() {
         let schema = Arc::new(arrow::datatypes::Schema::new(vec![
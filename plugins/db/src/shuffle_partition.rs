@@ -1,10 +1,19 @@
 //! Hash-partitions Arrow RecordBatches by join key columns.
 
-use arrow::array::RecordBatch;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, RecordBatch, StringArray};
 use arrow::compute::take;
 use datafusion::common::hash_utils::create_hashes;
 use datafusion::error::{DataFusionError, Result as DFResult};
 
+use crate::shuffle_descriptor::SaltSpec;
+
+fn hash_state() -> ahash::RandomState {
+    ahash::RandomState::with_seeds(0, 0, 0, 0)
+}
+
 /// Split a batch into `num_partitions` by `hash(join_key_columns) % num_partitions`.
 ///
 /// Uses DataFusion's `create_hashes` for consistency with DataFusion's own hash
@@ -13,6 +22,20 @@ pub fn partition_batch(
     batch: &RecordBatch,
     join_key_indices: &[usize],
     num_partitions: usize,
+) -> DFResult<Vec<RecordBatch>> {
+    partition_batch_with_salt(batch, join_key_indices, num_partitions, None)
+}
+
+/// [`partition_batch`], with the rows of `salt`'s hot keys spread over
+/// `salt.factor` consecutive partitions starting at the key's own.
+///
+/// A row is hot when its first join key hashes like one of the hot values,
+/// so both sides of a join agree on which keys are salted.
+pub fn partition_batch_with_salt(
+    batch: &RecordBatch,
+    join_key_indices: &[usize],
+    num_partitions: usize,
+    salt: Option<&SaltSpec>,
 ) -> DFResult<Vec<RecordBatch>> {
     if num_partitions == 0 {
         return Err(DataFusionError::Internal(
@@ -32,12 +55,33 @@ pub fn partition_batch(
         .collect();
 
     let mut hashes = vec![0u64; num_rows];
-    create_hashes(&hash_columns, &ahash::RandomState::with_seeds(0, 0, 0, 0), &mut hashes)?;
+    create_hashes(&hash_columns, &hash_state(), &mut hashes)?;
+
+    let hot = match (salt, hash_columns.first()) {
+        (Some(salt), Some(first)) if salt.factor > 1 => {
+            hot_rows(first, &salt.hot_values)?.map(|rows| (salt, rows))
+        }
+        _ => None,
+    };
 
     let mut partition_indices: Vec<Vec<u32>> = vec![Vec::new(); num_partitions];
     for (row_idx, hash) in hashes.iter().enumerate() {
         let partition = (*hash as usize) % num_partitions;
-        partition_indices[partition].push(row_idx as u32);
+        match &hot {
+            Some((salt, rows)) if rows[row_idx] => {
+                let factor = salt.factor.min(num_partitions);
+                if salt.replicate {
+                    for offset in 0..factor {
+                        partition_indices[(partition + offset) % num_partitions]
+                            .push(row_idx as u32);
+                    }
+                } else {
+                    let offset = row_idx % factor;
+                    partition_indices[(partition + offset) % num_partitions].push(row_idx as u32);
+                }
+            }
+            _ => partition_indices[partition].push(row_idx as u32),
+        }
     }
 
     let schema = batch.schema();
@@ -66,6 +110,36 @@ pub fn partition_batch(
     Ok(result)
 }
 
+/// Which rows of `column` hold one of `hot_values`, matched by hash after
+/// casting the values to the column's type. Values that don't cast are
+/// ignored; `None` when none remain.
+fn hot_rows(column: &ArrayRef, hot_values: &[String]) -> DFResult<Option<Vec<bool>>> {
+    let values = StringArray::from_iter_values(hot_values.iter());
+    let values = arrow::compute::cast(&values, column.data_type())
+        .map_err(|e| DataFusionError::ArrowError(Box::new(e), None))?;
+    let mut value_hashes = vec![0u64; values.len()];
+    create_hashes(&[Arc::clone(&values)], &hash_state(), &mut value_hashes)?;
+    let hot: HashSet<u64> = value_hashes
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| values.is_valid(*i))
+        .map(|(_, h)| *h)
+        .collect();
+    if hot.is_empty() {
+        return Ok(None);
+    }
+
+    let mut hashes = vec![0u64; column.len()];
+    create_hashes(&[Arc::clone(column)], &hash_state(), &mut hashes)?;
+    Ok(Some(
+        hashes
+            .iter()
+            .enumerate()
+            .map(|(i, h)| column.is_valid(i) && hot.contains(h))
+            .collect(),
+    ))
+}
+
 /// Resolve column names to their indices in the schema.
 pub fn resolve_key_indices(
     schema: &arrow::datatypes::SchemaRef,
@@ -90,7 +164,6 @@ mod tests {
     use super::*;
     use arrow::array::{Int64Array, StringArray};
     use arrow::datatypes::{DataType, Field, Schema};
-    use std::sync::Arc;
 
     fn test_batch() -> RecordBatch {
         let schema = Arc::new(Schema::new(vec![
@@ -164,6 +237,132 @@ mod tests {
         }
     }
 
+    fn key_batch(keys: Vec<i64>) -> RecordBatch {
+        let schema = Arc::new(Schema::new(vec![Field::new("k", DataType::Int64, true)]));
+        RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(keys))]).unwrap()
+    }
+
+    fn salt(replicate: bool) -> SaltSpec {
+        SaltSpec {
+            hot_values: vec!["7".to_string(), "not a number".to_string()],
+            factor: 3,
+            replicate,
+        }
+    }
+
+    fn partitions_holding(parts: &[RecordBatch], key: i64) -> Vec<usize> {
+        parts
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| {
+                let col = p.column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+                col.iter().any(|v| v == Some(key))
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    #[test]
+    fn salted_probe_side_splits_hot_key() {
+        let batch = key_batch(vec![7; 30].into_iter().chain([1, 2]).collect());
+        let parts = partition_batch_with_salt(&batch, &[0], 4, Some(&salt(false))).unwrap();
+        let total: usize = parts.iter().map(|p| p.num_rows()).sum();
+        assert_eq!(total, 32, "probe rows are moved, not copied");
+        assert_eq!(partitions_holding(&parts, 7).len(), 3);
+
+        // Cold keys stay where plain hashing puts them.
+        let plain = partition_batch(&batch, &[0], 4).unwrap();
+        for key in [1, 2] {
+            assert_eq!(partitions_holding(&parts, key), partitions_holding(&plain, key));
+        }
+    }
+
+    #[test]
+    fn salted_build_side_replicates_to_every_probe_partition() {
+        let probe = key_batch(vec![7; 30]);
+        let build = key_batch(vec![7, 7, 3]);
+        let probe_parts = partition_batch_with_salt(&probe, &[0], 4, Some(&salt(false))).unwrap();
+        let build_parts = partition_batch_with_salt(&build, &[0], 4, Some(&salt(true))).unwrap();
+        assert_eq!(build_parts.iter().map(|p| p.num_rows()).sum::<usize>(), 7);
+        assert_eq!(
+            partitions_holding(&probe_parts, 7),
+            partitions_holding(&build_parts, 7)
+        );
+        // Every replicated partition holds both build copies of the key.
+        for pid in partitions_holding(&build_parts, 7) {
+            let col = build_parts[pid].column(0).as_any().downcast_ref::<Int64Array>().unwrap();
+            assert_eq!(col.iter().filter(|v| *v == Some(7)).count(), 2);
+        }
+    }
+
+    #[test]
+    fn salt_factor_is_capped_and_nulls_are_never_hot() {
+        let batch = key_batch(vec![7; 10]);
+        let parts = partition_batch_with_salt(&batch, &[0], 2, Some(&salt(true))).unwrap();
+        assert_eq!(parts.iter().map(|p| p.num_rows()).sum::<usize>(), 20);
+
+        let schema = Arc::new(Schema::new(vec![Field::new("k", DataType::Int64, true)]));
+        let nulls = RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int64Array::from(vec![None, None, Some(7)]))],
+        )
+        .unwrap();
+        let parts = partition_batch_with_salt(&nulls, &[0], 4, Some(&salt(true))).unwrap();
+        assert_eq!(parts.iter().map(|p| p.num_rows()).sum::<usize>(), 2 + 3);
+    }
+
+    /// `(left_row, right_row)` pairs of an inner equi-join on column 0, run
+    /// independently in each partition.
+    fn partitioned_join(left: &[RecordBatch], right: &[RecordBatch]) -> Vec<(i64, i64)> {
+        let rows = |b: &RecordBatch| {
+            let key = b.column(0).as_any().downcast_ref::<Int64Array>().unwrap().clone();
+            let id = b.column(1).as_any().downcast_ref::<Int64Array>().unwrap().clone();
+            key.iter().zip(id.iter()).collect::<Vec<_>>()
+        };
+        let mut pairs = Vec::new();
+        for (l, r) in left.iter().zip(right) {
+            for (lk, lid) in rows(l) {
+                for (rk, rid) in rows(r) {
+                    if lk.is_some() && lk == rk {
+                        pairs.push((lid.unwrap(), rid.unwrap()));
+                    }
+                }
+            }
+        }
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn salted_join_matches_unsalted_join() {
+        let keyed = |keys: Vec<Option<i64>>| {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("k", DataType::Int64, true),
+                Field::new("id", DataType::Int64, false),
+            ]));
+            let ids = Int64Array::from_iter_values(0..keys.len() as i64);
+            RecordBatch::try_new(schema, vec![Arc::new(Int64Array::from(keys)), Arc::new(ids)])
+                .unwrap()
+        };
+        let build = keyed(vec![Some(7), Some(7), Some(1), None, Some(3)]);
+        let probe = keyed(
+            std::iter::repeat_n(Some(7), 40)
+                .chain([Some(1), Some(2), None, Some(3), Some(3)])
+                .collect(),
+        );
+
+        let plain = partitioned_join(
+            &partition_batch(&build, &[0], 4).unwrap(),
+            &partition_batch(&probe, &[0], 4).unwrap(),
+        );
+        let salted = partitioned_join(
+            &partition_batch_with_salt(&build, &[0], 4, Some(&salt(true))).unwrap(),
+            &partition_batch_with_salt(&probe, &[0], 4, Some(&salt(false))).unwrap(),
+        );
+        assert_eq!(plain.len(), 2 * 40 + 1 + 2);
+        assert_eq!(salted, plain);
+    }
+
     #[test]
     fn resolve_key_indices_found() {
         let schema = Arc::new(Schema::new(vec![
@@ -1,5 +1,5 @@
 //! DataFusion `ExecutionPlan` that receives shuffled partitions from the
-//! shuffle registry, waiting for all expected source nodes to deliver data.
+//! shuffle registry, streaming batches as the source nodes deliver them.
 
 use std::any::Any;
 use std::fmt;
@@ -9,18 +9,19 @@ use arrow::datatypes::SchemaRef;
 use datafusion::error::{DataFusionError, Result as DFResult};
 use datafusion::execution::{SendableRecordBatchStream, TaskContext};
 use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricBuilder, MetricsSet};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
 };
 use futures::StreamExt;
 
-use crate::logging::SwarmLogger;
+use crate::logging::{self, SwarmLogger};
 use crate::shuffle_descriptor::ShuffleDescriptor;
 use crate::shuffle_registry;
 
-/// Reads shuffle data from the in-process shuffle registry. Blocks (async)
-/// until all expected source nodes have submitted their partition data.
+/// Reads shuffle data from the in-process shuffle registry. Yields batches as
+/// they arrive and ends once all expected source nodes have finished.
 #[derive(Debug)]
 pub struct ShuffleReaderExec {
     descriptor: ShuffleDescriptor,
@@ -31,6 +32,8 @@ pub struct ShuffleReaderExec {
     schema: SchemaRef,
     runtime_handle: tokio::runtime::Handle,
     properties: PlanProperties,
+    /// Rows received from the source nodes.
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl ShuffleReaderExec {
@@ -55,6 +58,7 @@ impl ShuffleReaderExec {
             schema,
             runtime_handle,
             properties,
+            metrics: ExecutionPlanMetricsSet::new(),
         }
     }
 }
@@ -95,13 +99,17 @@ impl ExecutionPlan for ShuffleReaderExec {
 
     fn execute(
         &self,
-        _partition: usize,
-        _context: Arc<TaskContext>,
+        partition: usize,
+        context: Arc<TaskContext>,
     ) -> DFResult<SendableRecordBatchStream> {
+        let query_id = logging::query_id_for_task(&context)
+            .or_else(|| self.descriptor.query_id.clone());
+        let _log_guard = query_id.as_deref().map(logging::enter_query);
         let shuffle_id = self.descriptor.shuffle_id.clone();
         let partition_id = self.partition_id;
         let expected_sources = self.expected_sources;
         let schema = self.schema.clone();
+        let output_rows = MetricBuilder::new(&self.metrics).output_rows(partition);
 
         SwarmLogger::debug(
             "shuffle-reader",
@@ -111,36 +119,48 @@ impl ExecutionPlan for ShuffleReaderExec {
             ),
         );
 
-        let join_handle = self.runtime_handle.spawn(async move {
-            let batches =
-                shuffle_registry::wait_for_partition(&shuffle_id, partition_id, expected_sources)
+        // Chunks are handed over as they arrive, so writers waiting on this
+        // partition's buffer budget resume while the consumer works.
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        self.runtime_handle.spawn(logging::with_query_id(query_id, async move {
+            // Dropped with this task, also when the consumer goes away.
+            let _reader = shuffle_registry::PartitionReader::new(&shuffle_id, partition_id);
+            let mut batch_count = 0usize;
+            let mut rows = 0usize;
+            loop {
+                match shuffle_registry::next_batches(&shuffle_id, partition_id, expected_sources)
                     .await
-                    .map_err(|e| DataFusionError::Internal(e))?;
+                {
+                    Ok(Some(batches)) => {
+                        batch_count += batches.len();
+                        let chunk_rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+                        rows += chunk_rows;
+                        output_rows.add(chunk_rows);
+                        if tx.send(Ok(batches)).await.is_err() {
+                            return; // Consumer dropped the stream.
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = tx.send(Err(DataFusionError::Internal(e))).await;
+                        return;
+                    }
+                }
+            }
 
-            let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
             SwarmLogger::debug(
                 "shuffle-reader",
                 &format!(
                     "Shuffle '{}' partition {}: received {} batch(es), {} row(s)",
-                    shuffle_id,
-                    partition_id,
-                    batches.len(),
-                    rows,
+                    shuffle_id, partition_id, batch_count, rows,
                 ),
             );
+        }));
 
-            Ok::<_, DataFusionError>(batches)
-        });
-
-        let result_stream = futures::stream::once(async move {
-            let batches = join_handle
-                .await
-                .map_err(|e| {
-                    DataFusionError::Internal(format!("Shuffle reader task panicked: {e}"))
-                })??;
-            Ok::<Vec<arrow::array::RecordBatch>, DataFusionError>(batches)
+        let result_stream = futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|chunk| (chunk, rx))
         })
-        .flat_map(|result| match result {
+        .flat_map(|result: DFResult<Vec<arrow::array::RecordBatch>>| match result {
             Ok(batches) => futures::stream::iter(batches.into_iter().map(Ok)).boxed(),
             Err(e) => futures::stream::once(async move { Err(e) }).boxed(),
         });
@@ -150,6 +170,10 @@ impl ExecutionPlan for ShuffleReaderExec {
             result_stream,
         )))
     }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
 }
 
 #[cfg(test)]
@@ -163,6 +187,8 @@ mod tests {
             join_keys: vec!["id".to_string()],
             num_partitions: 2,
             target_table: None,
+            query_id: None,
+            salt: None,
             partition_targets: vec![
                 ShuffleTarget {
                     partition_id: 0,
@@ -272,4 +298,42 @@ mod tests {
 
         shuffle_registry::cleanup_shuffle(&desc.shuffle_id);
     }
+
+    #[tokio::test]
+    async fn shuffle_reader_streams_before_all_sources_finish() {
+        let mut desc = sample_descriptor();
+        desc.shuffle_id = "test-reader-streaming".to_string();
+        let schema = Arc::new(arrow::datatypes::Schema::new(vec![
+            arrow::datatypes::Field::new("id", arrow::datatypes::DataType::Int64, false),
+        ]));
+        let batch = |values: Vec<i64>| {
+            arrow::array::RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(arrow::array::Int64Array::from(values))],
+            )
+            .unwrap()
+        };
+        shuffle_registry::register_shuffle(&desc.shuffle_id, 2);
+        shuffle_registry::submit_partition(&desc.shuffle_id, 0, vec![batch(vec![1, 2])]);
+
+        let reader = ShuffleReaderExec::new(
+            desc.clone(),
+            0,
+            2,
+            schema.clone(),
+            tokio::runtime::Handle::current(),
+        );
+        let mut stream = reader.execute(0, Arc::new(TaskContext::default())).unwrap();
+
+        let wait = std::time::Duration::from_secs(1);
+        let first = tokio::time::timeout(wait, stream.next()).await.unwrap().unwrap().unwrap();
+        assert_eq!(first.num_rows(), 2);
+
+        shuffle_registry::submit_partition(&desc.shuffle_id, 0, vec![batch(vec![3])]);
+        let second = tokio::time::timeout(wait, stream.next()).await.unwrap().unwrap().unwrap();
+        assert_eq!(second.num_rows(), 1);
+        assert!(tokio::time::timeout(wait, stream.next()).await.unwrap().is_none());
+
+        shuffle_registry::cleanup_shuffle(&desc.shuffle_id);
+    }
 }
@@ -1,7 +1,19 @@
 //! In-process registry for coordinating shuffle data between the Flight
 //! DoExchange handler (writer) and ShuffleReaderExec (consumer).
-
-use std::collections::HashMap;
+//!
+//! Each partition buffers at most `shuffle_buffer_mb` of undrained batches.
+//! Writers wait in [`push_batches`] until the reader has taken enough of the
+//! buffer via [`next_batches`]; since the DoExchange handler stops reading its
+//! inbound stream while it waits, the sending node is throttled by Flight's
+//! own flow control. Budgets are per partition, so a full partition only
+//! holds up the writers feeding it, and a partition with nothing buffered
+//! always admits the next chunk however large it is.
+//!
+//! A writer waits for as long as the reader is alive, however slowly it
+//! drains. It fails only once the shuffle is cleaned up or the partition's
+//! [`PartitionReader`] is dropped, e.g. because the query was cancelled.
+
+use std::collections::{HashMap, HashSet};
 use std::sync::{Mutex, OnceLock};
 use std::time::Instant;
 
@@ -16,10 +28,27 @@ const STALE_SHUFFLE_SECS: u64 = 300; // 5 minutes
 /// Maximum time `wait_for_partition` will block before returning an error.
 const WAIT_TIMEOUT_SECS: u64 = 120; // 2 minutes
 
+/// Gossip config key capping the undrained bytes buffered per partition.
+pub const SHUFFLE_BUFFER_MB_KEY: &str = "shuffle_buffer_mb";
+
+/// Per-partition buffer budget when `shuffle_buffer_mb` is not set.
+pub const DEFAULT_SHUFFLE_BUFFER_MB: usize = 64;
+
+/// Batches received for one partition that the reader has not taken yet.
+#[derive(Default)]
+struct PartitionBuffer {
+    batches: Vec<RecordBatch>,
+    bytes: usize,
+}
+
 /// State for a single shuffle operation.
 pub struct ShuffleState {
-    /// partition_id → accumulated batches from all source nodes.
-    partitions: HashMap<usize, Vec<RecordBatch>>,
+    /// partition_id → batches from all source nodes not yet taken by the reader.
+    partitions: HashMap<usize, PartitionBuffer>,
+    /// Undrained bytes a partition may hold before writers have to wait.
+    buffer_limit_bytes: usize,
+    /// Partitions whose reader has gone away; pushes to them fail.
+    closed: HashSet<usize>,
     /// How many source nodes are expected to send data.
     expected_sources: usize,
     /// How many source nodes have completed sending.
@@ -36,11 +65,26 @@ fn registry() -> &'static Mutex<HashMap<String, ShuffleState>> {
     SHUFFLE_REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-/// Register a new shuffle operation. Must be called before any data arrives.
+/// Register a new shuffle operation with the default buffer budget. Must be
+/// called before any data arrives.
+pub fn register_shuffle(shuffle_id: &str, expected_sources: usize) {
+    register_shuffle_with_buffer(
+        shuffle_id,
+        expected_sources,
+        DEFAULT_SHUFFLE_BUFFER_MB * 1024 * 1024,
+    );
+}
+
+/// Register a new shuffle operation whose partitions each buffer at most
+/// `buffer_limit_bytes` of undrained data.
 ///
 /// Also opportunistically cleans up stale entries from previous failed queries
 /// that were never explicitly cleaned up.
-pub fn register_shuffle(shuffle_id: &str, expected_sources: usize) {
+pub fn register_shuffle_with_buffer(
+    shuffle_id: &str,
+    expected_sources: usize,
+    buffer_limit_bytes: usize,
+) {
     let mut map = registry().lock().expect("shuffle registry lock poisoned");
 
     cleanup_stale_entries(&mut map);
@@ -56,6 +100,8 @@ pub fn register_shuffle(shuffle_id: &str, expected_sources: usize) {
         shuffle_id.to_string(),
         ShuffleState {
             partitions: HashMap::new(),
+            buffer_limit_bytes,
+            closed: HashSet::new(),
             expected_sources,
             received_sources: 0,
             notify: Notify::new(),
@@ -65,8 +111,8 @@ pub fn register_shuffle(shuffle_id: &str, expected_sources: usize) {
     SwarmLogger::debug(
         "shuffle-registry",
         &format!(
-            "Registered shuffle '{}' expecting {} source(s)",
-            shuffle_id, expected_sources,
+            "Registered shuffle '{}' expecting {} source(s), {} byte(s) buffered per partition",
+            shuffle_id, expected_sources, buffer_limit_bytes,
         ),
     );
 }
@@ -94,16 +140,24 @@ fn cleanup_stale_entries(map: &mut HashMap<String, ShuffleState>) {
     }
 }
 
-/// Submit partition data from one source node. Called by the DoExchange handler.
+/// Append batches to a partition's buffer. Called under lock.
+fn append_batches(state: &mut ShuffleState, partition_id: usize, batches: Vec<RecordBatch>) {
+    let buffer = state.partitions.entry(partition_id).or_default();
+    buffer.bytes += batches
+        .iter()
+        .map(|b| b.get_array_memory_size())
+        .sum::<usize>();
+    buffer.batches.extend(batches);
+}
+
+/// Submit all of one source node's data for a partition at once, without
+/// waiting for buffer budget. Used for the local partition, whose batches are
+/// already in memory.
 pub fn submit_partition(shuffle_id: &str, partition_id: usize, batches: Vec<RecordBatch>) {
     let mut map = registry().lock().expect("shuffle registry lock poisoned");
     if let Some(state) = map.get_mut(shuffle_id) {
         let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
-        state
-            .partitions
-            .entry(partition_id)
-            .or_default()
-            .extend(batches);
+        append_batches(state, partition_id, batches);
         state.received_sources += 1;
         SwarmLogger::debug(
             "shuffle-registry",
@@ -124,50 +178,120 @@ pub fn submit_partition(shuffle_id: &str, partition_id: usize, batches: Vec<Reco
     }
 }
 
-/// Wait until the specified partition has data from all expected sources,
-/// then take and return all accumulated batches.
+/// Buffer a chunk of one source's data for a partition. Waits while the
+/// chunk would take the partition past its budget; a partition with nothing
+/// buffered admits any chunk, so batches larger than the budget still get
+/// through. Called by the DoExchange handler for every chunk it decodes.
 ///
-/// Returns an error if the wait exceeds `WAIT_TIMEOUT_SECS` (prevents
+/// Returns an error if the shuffle is cleaned up while the chunk waits, or
+/// once the partition's reader has gone away.
+pub async fn push_batches(
+    shuffle_id: &str,
+    partition_id: usize,
+    batches: Vec<RecordBatch>,
+) -> Result<(), String> {
+    let incoming: usize = batches.iter().map(|b| b.get_array_memory_size()).sum();
+    let mut waited = false;
+
+    loop {
+        {
+            let mut map = registry().lock().expect("shuffle registry lock poisoned");
+            let Some(state) = map.get_mut(shuffle_id) else {
+                if waited {
+                    return Err(format!(
+                        "Shuffle '{}' was cancelled while partition {} waited for its reader",
+                        shuffle_id, partition_id,
+                    ));
+                }
+                SwarmLogger::warn(
+                    "shuffle-registry",
+                    &format!(
+                        "Shuffle '{}' not registered, dropping partition {} data",
+                        shuffle_id, partition_id,
+                    ),
+                );
+                return Ok(());
+            };
+            if state.closed.contains(&partition_id) {
+                return Err(format!(
+                    "Shuffle '{}' partition {}: reader has gone away",
+                    shuffle_id, partition_id,
+                ));
+            }
+            let buffered = state
+                .partitions
+                .get(&partition_id)
+                .map(|b| b.bytes)
+                .unwrap_or(0);
+            if buffered == 0 || buffered + incoming <= state.buffer_limit_bytes {
+                append_batches(state, partition_id, batches);
+                state.notify.notify_waiters();
+                return Ok(());
+            }
+        }
+
+        waited = true;
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+}
+
+/// Record that one source node has sent all its data for a partition.
+/// Called by the DoExchange handler once its inbound stream ends.
+pub fn finish_source(shuffle_id: &str, partition_id: usize) {
+    let mut map = registry().lock().expect("shuffle registry lock poisoned");
+    if let Some(state) = map.get_mut(shuffle_id) {
+        state.received_sources += 1;
+        SwarmLogger::debug(
+            "shuffle-registry",
+            &format!(
+                "Shuffle '{}' partition {}: source finished ({}/{} sources)",
+                shuffle_id, partition_id, state.received_sources, state.expected_sources,
+            ),
+        );
+        state.notify.notify_waiters();
+    }
+}
+
+/// Take the batches buffered for a partition, waiting until there are some
+/// or all expected sources have finished. Taking them frees their budget for
+/// waiting writers. Returns `None` once every source has finished and the
+/// buffer is empty.
+///
+/// Returns an error if nothing arrives within `WAIT_TIMEOUT_SECS` (prevents
 /// infinite hangs on failed queries where sources never send data).
-pub async fn wait_for_partition(
+pub async fn next_batches(
     shuffle_id: &str,
     partition_id: usize,
     expected_sources: usize,
-) -> Result<Vec<RecordBatch>, String> {
+) -> Result<Option<Vec<RecordBatch>>, String> {
     let deadline = Instant::now() + std::time::Duration::from_secs(WAIT_TIMEOUT_SECS);
 
     loop {
-        let ready: bool = {
-            let map = registry().lock().expect("shuffle registry lock poisoned");
-            if let Some(state) = map.get(shuffle_id) {
-                let count = state
-                    .partitions
-                    .get(&partition_id)
-                    .map(|v| v.len())
-                    .unwrap_or(0);
-                state.received_sources >= expected_sources || count >= expected_sources
-            } else {
-                false
+        let received_sources = {
+            let mut map = registry().lock().expect("shuffle registry lock poisoned");
+            match map.get_mut(shuffle_id) {
+                Some(state) => {
+                    if let Some(buffer) = state.partitions.get_mut(&partition_id) {
+                        if !buffer.batches.is_empty() {
+                            buffer.bytes = 0;
+                            return Ok(Some(std::mem::take(&mut buffer.batches)));
+                        }
+                    }
+                    if state.received_sources >= expected_sources {
+                        return Ok(None);
+                    }
+                    state.received_sources
+                }
+                None => 0,
             }
         };
 
-        if ready {
-            break;
-        }
-
         if Instant::now() >= deadline {
             SwarmLogger::warn(
                 "shuffle-registry",
                 &format!(
                     "Shuffle '{}' partition {} timed out after {}s waiting for {}/{} sources",
-                    shuffle_id, partition_id, WAIT_TIMEOUT_SECS,
-                    {
-                        let map = registry().lock().expect("lock");
-                        map.get(shuffle_id)
-                            .map(|s| s.received_sources)
-                            .unwrap_or(0)
-                    },
-                    expected_sources,
+                    shuffle_id, partition_id, WAIT_TIMEOUT_SECS, received_sources, expected_sources,
                 ),
             );
             return Err(format!(
@@ -178,13 +302,51 @@ pub async fn wait_for_partition(
 
         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
     }
+}
 
-    let mut map = registry().lock().expect("shuffle registry lock poisoned");
-    if let Some(state) = map.get_mut(shuffle_id) {
-        Ok(state.partitions.remove(&partition_id).unwrap_or_default())
-    } else {
-        Ok(Vec::new())
+/// Held by the reader of a partition while it drains it through
+/// [`next_batches`]. Dropping it closes the partition: what is still buffered
+/// is freed and writers waiting on its budget fail rather than wait for a
+/// reader that is not coming back.
+pub struct PartitionReader {
+    shuffle_id: String,
+    partition_id: usize,
+}
+
+impl PartitionReader {
+    pub fn new(shuffle_id: &str, partition_id: usize) -> Self {
+        Self {
+            shuffle_id: shuffle_id.to_string(),
+            partition_id,
+        }
+    }
+}
+
+impl Drop for PartitionReader {
+    fn drop(&mut self) {
+        let Ok(mut map) = registry().lock() else {
+            return;
+        };
+        if let Some(state) = map.get_mut(&self.shuffle_id) {
+            state.closed.insert(self.partition_id);
+            state.partitions.remove(&self.partition_id);
+        }
+    }
+}
+
+/// Wait until the specified partition has data from all expected sources,
+/// then return all of its batches. The buffer is drained while waiting, so
+/// writers are not held up by the partition's budget.
+pub async fn wait_for_partition(
+    shuffle_id: &str,
+    partition_id: usize,
+    expected_sources: usize,
+) -> Result<Vec<RecordBatch>, String> {
+    let mut batches = Vec::new();
+    while let Some(chunk) = next_batches(shuffle_id, partition_id, expected_sources).await? {
+        batches.extend(chunk);
     }
+    Ok(batches)
 }
 
 /// Remove a completed shuffle from the registry.
@@ -273,4 +435,113 @@ mod tests {
         assert_eq!(total, 4);
         cleanup_shuffle(id);
     }
+
+    #[tokio::test]
+    async fn push_waits_until_reader_drains() {
+        let id = "test-push-backpressure";
+        let first = make_batch(vec![1, 2, 3]);
+        register_shuffle_with_buffer(id, 1, first.get_array_memory_size());
+        push_batches(id, 0, vec![first]).await.unwrap();
+
+        let blocked = tokio::spawn(push_batches(id, 0, vec![make_batch(vec![4, 5, 6])]));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!blocked.is_finished());
+
+        let drained = next_batches(id, 0, 1).await.unwrap().unwrap();
+        assert_eq!(drained[0].num_rows(), 3);
+        blocked.await.unwrap().unwrap();
+        finish_source(id, 0);
+
+        let rest = next_batches(id, 0, 1).await.unwrap().unwrap();
+        assert_eq!(rest[0].num_rows(), 3);
+        assert!(next_batches(id, 0, 1).await.unwrap().is_none());
+        cleanup_shuffle(id);
+    }
+
+    #[tokio::test]
+    async fn slow_reader_holds_writers_without_failing_them() {
+        let id = "test-push-slow-reader";
+        register_shuffle_with_buffer(id, 1, 1);
+        let writer = tokio::spawn(async move {
+            for i in 0..4 {
+                push_batches(id, 0, vec![make_batch(vec![i])]).await?;
+            }
+            finish_source(id, 0);
+            Ok::<_, String>(())
+        });
+
+        let mut rows = 0;
+        while let Some(chunk) = next_batches(id, 0, 1).await.unwrap() {
+            rows += chunk.iter().map(|b| b.num_rows()).sum::<usize>();
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(rows, 4);
+        writer.await.unwrap().unwrap();
+        cleanup_shuffle(id);
+    }
+
+    #[tokio::test]
+    async fn writers_fail_once_the_reader_is_gone() {
+        let id = "test-push-reader-gone";
+        register_shuffle_with_buffer(id, 1, 1);
+        let reader = PartitionReader::new(id, 0);
+        push_batches(id, 0, vec![make_batch(vec![1])]).await.unwrap();
+        let blocked = tokio::spawn(push_batches(id, 0, vec![make_batch(vec![2])]));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert!(!blocked.is_finished());
+
+        drop(reader);
+        assert!(blocked.await.unwrap().unwrap_err().contains("gone away"));
+        let after = push_batches(id, 0, vec![make_batch(vec![3])]).await;
+        assert!(after.is_err());
+        // Other partitions keep their readers.
+        push_batches(id, 1, vec![make_batch(vec![4])]).await.unwrap();
+        cleanup_shuffle(id);
+    }
+
+    #[tokio::test]
+    async fn waiting_writers_fail_when_the_shuffle_is_cancelled() {
+        let id = "test-push-cancelled";
+        register_shuffle_with_buffer(id, 1, 1);
+        push_batches(id, 0, vec![make_batch(vec![1])]).await.unwrap();
+        let blocked = tokio::spawn(push_batches(id, 0, vec![make_batch(vec![2])]));
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+
+        cleanup_shuffle(id);
+        assert!(blocked.await.unwrap().unwrap_err().contains("cancelled"));
+    }
+
+    #[tokio::test]
+    async fn empty_partition_admits_oversized_chunk() {
+        let id = "test-push-oversized";
+        register_shuffle_with_buffer(id, 1, 1);
+        let pushed = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            push_batches(id, 0, vec![make_batch(vec![1, 2, 3, 4])]),
+        )
+        .await;
+        assert!(matches!(pushed, Ok(Ok(()))));
+        cleanup_shuffle(id);
+    }
+
+    #[tokio::test]
+    async fn full_partition_does_not_block_others() {
+        let id = "test-push-independent";
+        register_shuffle_with_buffer(id, 1, 1);
+        push_batches(id, 0, vec![make_batch(vec![1])]).await.unwrap();
+        let blocked = tokio::spawn(push_batches(id, 0, vec![make_batch(vec![2])]));
+
+        let other = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            push_batches(id, 1, vec![make_batch(vec![3])]),
+        )
+        .await;
+        assert!(matches!(other, Ok(Ok(()))));
+        assert!(!blocked.is_finished());
+
+        let drained = next_batches(id, 0, 1).await.unwrap().unwrap();
+        assert_eq!(drained.len(), 1);
+        blocked.await.unwrap().unwrap();
+        cleanup_shuffle(id);
+    }
 }
@@ -5,24 +5,25 @@
 //! do_exchange() method in flight_server.rs — since flight and swarm are
 //! now in the same cdylib, no separate shuffle service is needed.
 
+use std::sync::{Arc, Mutex};
+
 use arrow::array::RecordBatch;
 use arrow::datatypes::SchemaRef;
 use arrow_flight::encode::FlightDataEncoderBuilder;
-use arrow_flight::flight_service_client::FlightServiceClient;
-use arrow_flight::FlightData;
 use arrow_flight::FlightDescriptor;
-use futures::TryStreamExt;
-use tonic::transport::Endpoint;
+use futures::{Stream, StreamExt};
 use tonic::Request;
 
-use crate::logging::SwarmLogger;
+use crate::error::SwarmError;
+use crate::flight_client::FlightClient;
+use crate::logging::{self, SwarmLogger};
 use crate::shuffle_descriptor::ShuffleDescriptor;
 
 /// Send partitioned batches to a remote node via Flight DoExchange.
 ///
 /// The `FlightDescriptor` carries the `ShuffleDescriptor` JSON in `cmd` and the
-/// `partition_id` as the first path element. Connects directly to the flight
-/// endpoint (DoExchange is handled by the merged flight server).
+/// `partition_id` as the first path element. Uses a pooled connection to the
+/// flight endpoint (DoExchange is handled by the merged flight server).
 pub async fn send_partition(
     endpoint: &str,
     descriptor: &ShuffleDescriptor,
@@ -52,14 +53,37 @@ pub async fn send_partition(
         ),
     );
 
-    let channel = Endpoint::from_shared(endpoint.to_string())
-        .map_err(|e| format!("Invalid flight endpoint {endpoint}: {e}"))?
-        .connect()
+    send_partition_stream(
+        endpoint,
+        descriptor,
+        partition_id,
+        schema,
+        futures::stream::iter(batches),
+    )
+    .await
+}
+
+/// Stream batches to a remote node via Flight DoExchange as they are
+/// produced, for the same receiver as [`send_partition`].
+///
+/// Batches are pulled from `batches` and encoded only as the request stream
+/// is polled, so a receiver that stops reading (its partition buffer is full)
+/// stops this sender from pulling more. An encoding error ends the stream
+/// early and is returned once the exchange completes.
+pub async fn send_partition_stream<S>(
+    endpoint: &str,
+    descriptor: &ShuffleDescriptor,
+    partition_id: usize,
+    schema: SchemaRef,
+    batches: S,
+) -> Result<(), String>
+where
+    S: Stream<Item = RecordBatch> + Send + 'static,
+{
+    let mut client = FlightClient::connect(endpoint)
         .await
         .map_err(|e| format!("Failed to connect to flight server {endpoint}: {e}"))?;
 
-    let mut client = FlightServiceClient::new(channel);
-
     let desc_bytes = descriptor.to_json_bytes()?;
     let flight_descriptor = FlightDescriptor {
         r#type: arrow_flight::flight_descriptor::DescriptorType::Cmd as i32,
@@ -67,24 +91,40 @@ pub async fn send_partition(
         path: vec![partition_id.to_string()],
     };
 
-    let batch_stream = futures::stream::iter(batches.into_iter().map(Ok));
+    let encode_error: Arc<Mutex<Option<String>>> = Arc::new(Mutex::new(None));
+    let error_slot = encode_error.clone();
     let flight_data_stream = FlightDataEncoderBuilder::new()
         .with_schema(schema)
         .with_flight_descriptor(Some(flight_descriptor))
-        .build(batch_stream)
-        .map_err(|e| format!("Flight encoding error: {e}"));
+        .build(batches.map(Ok))
+        .scan((), move |_, result| {
+            let item = match result {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    if let Ok(mut slot) = error_slot.lock() {
+                        *slot = Some(format!("Failed to encode shuffle data: {e}"));
+                    }
+                    None
+                }
+            };
+            futures::future::ready(item)
+        });
 
-    let flight_data: Vec<FlightData> = flight_data_stream
-        .try_collect()
-        .await
-        .map_err(|e| format!("Failed to encode shuffle data: {e}"))?;
+    let mut request = Request::new(flight_data_stream);
+    logging::tag_request(&mut request);
 
-    let request = Request::new(futures::stream::iter(flight_data.into_iter()));
+    let exchange = client.service_client().do_exchange(request).await.map_err(|e| {
+        let message = format!(
+            "DoExchange failed for shuffle '{}' partition {}: {e}",
+            descriptor.shuffle_id, partition_id
+        );
+        SwarmError::from_status(endpoint, &e, message)
+    });
+    let _response = client.evict_if_broken(exchange)?;
 
-    let _response = client
-        .do_exchange(request)
-        .await
-        .map_err(|e| format!("DoExchange failed for shuffle '{}' partition {}: {e}", descriptor.shuffle_id, partition_id))?;
+    if let Some(e) = encode_error.lock().ok().and_then(|mut slot| slot.take()) {
+        return Err(e);
+    }
 
     SwarmLogger::debug(
         "shuffle-transport",
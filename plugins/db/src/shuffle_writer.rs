@@ -4,24 +4,96 @@
 use std::any::Any;
 use std::fmt;
 use std::sync::Arc;
+use std::time::Instant;
 
+use arrow::datatypes::SchemaRef;
 use datafusion::error::{DataFusionError, Result as DFResult};
 use datafusion::execution::{SendableRecordBatchStream, TaskContext};
 use datafusion::physical_expr::EquivalenceProperties;
+use datafusion::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricBuilder, MetricsSet};
 use datafusion::physical_plan::stream::RecordBatchStreamAdapter;
 use datafusion::physical_plan::{
     DisplayAs, DisplayFormatType, ExecutionPlan, Partitioning, PlanProperties,
 };
 use futures::StreamExt;
 
-use crate::logging::SwarmLogger;
+use crate::error::{SwarmError, SwarmResult};
+use crate::logging::{self, SwarmLogger};
+use crate::query_profile::{self, FragmentProfile};
 use crate::shuffle_descriptor::ShuffleDescriptor;
 use crate::shuffle_partition;
 use crate::shuffle_registry;
 use crate::shuffle_transport;
 
+/// Name of the metric counting the bytes a writer sent to other nodes.
+pub const BYTES_SHUFFLED_METRIC: &str = "bytes_shuffled";
+
+/// Batches queued per remote partition before the writer waits for the
+/// transfer to catch up.
+const SEND_QUEUE_BATCHES: usize = 4;
+
+/// A DoExchange transfer of one partition, fed batch by batch.
+struct PartitionSender {
+    tx: tokio::sync::mpsc::Sender<arrow::array::RecordBatch>,
+    task: tokio::task::JoinHandle<Result<(), String>>,
+    partition_id: usize,
+    endpoint: String,
+}
+
+impl PartitionSender {
+    fn spawn(
+        endpoint: &str,
+        descriptor: &ShuffleDescriptor,
+        partition_id: usize,
+        schema: SchemaRef,
+    ) -> Self {
+        let (tx, rx) = tokio::sync::mpsc::channel(SEND_QUEUE_BATCHES);
+        let batches = futures::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|batch| (batch, rx))
+        });
+        let task_endpoint = endpoint.to_string();
+        let task_descriptor = descriptor.clone();
+        let task = tokio::spawn(logging::with_query_id(
+            logging::current_query_id(),
+            async move {
+                shuffle_transport::send_partition_stream(
+                    &task_endpoint,
+                    &task_descriptor,
+                    partition_id,
+                    schema,
+                    batches,
+                )
+                .await
+            },
+        ));
+        Self {
+            tx,
+            task,
+            partition_id,
+            endpoint: endpoint.to_string(),
+        }
+    }
+
+    /// Close the queue and wait for the receiver to take the rest.
+    async fn finish(self) -> SwarmResult<()> {
+        drop(self.tx);
+        self.task
+            .await
+            .map_err(|e| SwarmError::Internal(format!("Shuffle send task panicked: {e}")))?
+            .map_err(|e| {
+                SwarmError::ShuffleFailed(format!(
+                    "Failed to send partition {} to {}: {e}",
+                    self.partition_id, self.endpoint,
+                ))
+            })
+    }
+}
+
 /// Reads from a child plan, hash-partitions by join keys, stores local partition
-/// in the shuffle registry, and sends remote partitions via Flight DoExchange.
+/// in the shuffle registry, and streams remote partitions via Flight DoExchange
+/// while the input is still being read. Each remote partition has a bounded
+/// queue, so a receiver that is over its buffer budget slows the writer down
+/// instead of letting partitions pile up in memory.
 #[derive(Debug)]
 pub struct ShuffleWriterExec {
     input: Arc<dyn ExecutionPlan>,
@@ -32,6 +104,8 @@ pub struct ShuffleWriterExec {
     local_partition_id: usize,
     runtime_handle: tokio::runtime::Handle,
     properties: PlanProperties,
+    /// Rows read, time taken and bytes sent to other nodes.
+    metrics: ExecutionPlanMetricsSet,
 }
 
 impl ShuffleWriterExec {
@@ -58,6 +132,7 @@ impl ShuffleWriterExec {
             local_partition_id,
             runtime_handle,
             properties,
+            metrics: ExecutionPlanMetricsSet::new(),
         }
     }
 }
@@ -115,6 +190,8 @@ impl ExecutionPlan for ShuffleWriterExec {
         partition: usize,
         context: Arc<TaskContext>,
     ) -> DFResult<SendableRecordBatchStream> {
+        let query_id = logging::query_id_for_task(&context)
+            .or_else(|| self.descriptor.query_id.clone());
         let input_stream = self.input.execute(partition, context)?;
         let schema = self.input.schema();
         let descriptor = self.descriptor.clone();
@@ -124,54 +201,70 @@ impl ExecutionPlan for ShuffleWriterExec {
         let runtime_handle = self.runtime_handle.clone();
         let out_schema = schema.clone();
 
-        let join_handle = runtime_handle.spawn(async move {
+        let profile_id = query_id.clone();
+        let output_rows = MetricBuilder::new(&self.metrics).output_rows(partition);
+        let elapsed_compute = MetricBuilder::new(&self.metrics).elapsed_compute(partition);
+        let bytes_shuffled_metric =
+            MetricBuilder::new(&self.metrics).counter(BYTES_SHUFFLED_METRIC, partition);
+
+        let join_handle = runtime_handle.spawn(logging::with_query_id(query_id, async move {
+            let started = Instant::now();
+            let mut rows: u64 = 0;
+            let mut bytes_shuffled: u64 = 0;
             let mut input_stream = input_stream;
-            let mut partition_buffers: Vec<Vec<arrow::array::RecordBatch>> =
-                vec![Vec::new(); num_partitions];
+            let mut local_batches: Vec<arrow::array::RecordBatch> = Vec::new();
+            let mut senders: Vec<Option<PartitionSender>> =
+                (0..num_partitions).map(|_| None).collect();
 
             while let Some(result) = input_stream.next().await {
-                let batch = result.map_err(|e| format!("Input stream error: {e}"))?;
+                let batch =
+                    result.map_err(|e| SwarmError::from_datafusion(&e, "Input stream error"))?;
                 if batch.num_rows() == 0 {
                     continue;
                 }
+                rows += batch.num_rows() as u64;
 
-                let partitioned =
-                    shuffle_partition::partition_batch(&batch, &join_key_indices, num_partitions)
-                        .map_err(|e| format!("Partition error: {e}"))?;
+                let partitioned = shuffle_partition::partition_batch_with_salt(
+                    &batch,
+                    &join_key_indices,
+                    num_partitions,
+                    descriptor.salt.as_ref(),
+                )
+                .map_err(|e| SwarmError::Internal(format!("Partition error: {e}")))?;
 
                 for (pid, pbatch) in partitioned.into_iter().enumerate() {
-                    if pbatch.num_rows() > 0 {
-                        partition_buffers[pid].push(pbatch);
+                    if pbatch.num_rows() == 0 {
+                        continue;
+                    }
+                    if pid == local_partition_id {
+                        local_batches.push(pbatch);
+                        continue;
+                    }
+                    let Some(target) = descriptor.target_for_partition(pid) else {
+                        continue;
+                    };
+                    bytes_shuffled += pbatch.get_array_memory_size() as u64;
+                    let sender = senders[pid].get_or_insert_with(|| {
+                        PartitionSender::spawn(&target.flight_endpoint, &descriptor, pid, schema.clone())
+                    });
+                    // Waits while the partition's queue is full, which holds
+                    // up the input until the receiver catches up.
+                    if sender.tx.send(pbatch).await.is_err() {
+                        if let Some(sender) = senders[pid].take() {
+                            sender.finish().await?;
+                        }
+                        return Err(SwarmError::ShuffleFailed(format!(
+                            "Transfer of partition {} ended early",
+                            pid
+                        )));
                     }
                 }
             }
 
-            for (pid, batches) in partition_buffers.iter().enumerate() {
-                if pid == local_partition_id {
-                    continue; // Local partition handled below.
-                }
-                if batches.is_empty() {
-                    continue;
-                }
-                if let Some(target) = descriptor.target_for_partition(pid) {
-                    shuffle_transport::send_partition(
-                        &target.flight_endpoint,
-                        &descriptor,
-                        pid,
-                        schema.clone(),
-                        batches.clone(),
-                    )
-                    .await
-                    .map_err(|e| {
-                        format!(
-                            "Failed to send partition {} to {}: {e}",
-                            pid, target.flight_endpoint,
-                        )
-                    })?;
-                }
+            for sender in senders.into_iter().flatten() {
+                sender.finish().await?;
             }
 
-            let local_batches = std::mem::take(&mut partition_buffers[local_partition_id]);
             let local_rows: usize = local_batches.iter().map(|b| b.num_rows()).sum();
             shuffle_registry::submit_partition(
                 &descriptor.shuffle_id,
@@ -187,8 +280,25 @@ impl ExecutionPlan for ShuffleWriterExec {
                 ),
             );
 
-            Ok::<Vec<arrow::array::RecordBatch>, String>(local_batches)
-        });
+            output_rows.add(local_rows);
+            elapsed_compute.add_duration(started.elapsed());
+            bytes_shuffled_metric.add(bytes_shuffled as usize);
+
+            if query_profile::is_profiling(profile_id.as_deref()) {
+                query_profile::record_fragment(
+                    profile_id.as_deref(),
+                    FragmentProfile {
+                        fragment: format!("shuffle {}[{}]", descriptor.shuffle_id, partition),
+                        node_name: query_profile::local_node_name(),
+                        rows,
+                        bytes_shuffled,
+                        wall_ms: started.elapsed().as_secs_f64() * 1000.0,
+                    },
+                );
+            }
+
+            Ok::<Vec<arrow::array::RecordBatch>, SwarmError>(local_batches)
+        }));
 
         let result_stream = futures::stream::once(async move {
             let batches = join_handle
@@ -209,6 +319,10 @@ impl ExecutionPlan for ShuffleWriterExec {
             result_stream,
         )))
     }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
 }
 
 #[cfg(test)]
@@ -222,6 +336,8 @@ mod tests {
             join_keys: vec!["id".to_string()],
             num_partitions: 2,
             target_table: None,
+            query_id: None,
+            salt: None,
             partition_targets: vec![
                 ShuffleTarget {
                     partition_id: 0,
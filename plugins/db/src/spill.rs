@@ -0,0 +1,230 @@
+//! Scratch space for DataFusion operators that spill to disk.
+//!
+//! Sorts, aggregations and joins running under `SWARM_MEMORY_LIMIT` write
+//! what does not fit in memory to files under
+//! `<spill_dir>/trex-spill-<node>/<query_id>/`. `spill_dir` is the config
+//! key set through `trex_db_set`, else `SWARM_SPILL_DIR`, else the system
+//! temp dir. A query's directory is removed when the query finishes, and a
+//! starting node removes whatever an earlier run of it left behind, so the
+//! files of a crashed node do not pile up.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use datafusion::execution::runtime_env::{RuntimeEnv, RuntimeEnvBuilder};
+
+/// Gossip config key naming the directory spill files go under.
+pub const SPILL_DIR_KEY: &str = "spill_dir";
+
+/// Environment variable naming the spill directory at node start.
+pub const SPILL_DIR_ENV: &str = "SWARM_SPILL_DIR";
+
+/// `SWARM_SPILL_DIR`, when set and non-empty.
+pub fn spill_dir_from_env() -> Option<PathBuf> {
+    std::env::var(SPILL_DIR_ENV)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+}
+
+/// This node's gossip config, empty while gossip is not running.
+fn self_config() -> Vec<(String, String)> {
+    crate::gossip::GossipRegistry::instance()
+        .get_self_config()
+        .unwrap_or_default()
+}
+
+fn config_value(config: &[(String, String)], key: &str) -> Option<String> {
+    config
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// The directory spill files go under on this node: `spill_dir` as set
+/// through `trex_db_set`, else `SWARM_SPILL_DIR`, else the system temp dir.
+pub fn spill_dir() -> PathBuf {
+    spill_dir_in(&self_config())
+}
+
+fn spill_dir_in(config: &[(String, String)]) -> PathBuf {
+    config_value(config, SPILL_DIR_KEY)
+        .map(PathBuf::from)
+        .or_else(spill_dir_from_env)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Check that `dir` is an existing directory this process can create
+/// files in.
+pub fn validate_spill_dir(dir: &Path) -> Result<(), String> {
+    let metadata = std::fs::metadata(dir)
+        .map_err(|e| format!("spill_dir '{}' is not accessible: {e}", dir.display()))?;
+    if !metadata.is_dir() {
+        return Err(format!("spill_dir '{}' is not a directory", dir.display()));
+    }
+    let probe = dir.join(format!(".trex-spill-probe-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&probe, b"")
+        .map_err(|e| format!("spill_dir '{}' is not writable: {e}", dir.display()))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
+
+/// `name` with everything but ASCII letters, digits, `-` and `_` replaced,
+/// so it can be used as one path component.
+fn path_component(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Where `node_name` keeps its spill files under `dir`. Nodes sharing a
+/// host and a `spill_dir` get a directory each, so a node clearing its own
+/// at start leaves the others' running queries alone.
+pub fn node_spill_root(dir: &Path, node_name: &str) -> PathBuf {
+    dir.join(format!("trex-spill-{}", path_component(node_name)))
+}
+
+/// Check `dir` and remove the query directories an earlier run of
+/// `node_name` left in it. Returns how many were removed.
+pub fn prepare_node_spill_dir(dir: &Path, node_name: &str) -> Result<usize, String> {
+    validate_spill_dir(dir)?;
+    let root = node_spill_root(dir, node_name);
+    let entries = match std::fs::read_dir(&root) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => {
+            return Err(format!(
+                "Failed to read spill directory '{}': {e}",
+                root.display()
+            ))
+        }
+    };
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let result = if path.is_dir() {
+            std::fs::remove_dir_all(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => crate::logging::SwarmLogger::warn(
+                "spill",
+                &format!("Failed to remove leftover spill files '{}': {e}", path.display()),
+            ),
+        }
+    }
+    Ok(removed)
+}
+
+/// A query's spill directory. Dropping it removes the directory and
+/// everything in it.
+#[derive(Debug)]
+pub struct QuerySpillDir {
+    path: PathBuf,
+}
+
+impl QuerySpillDir {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for QuerySpillDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
+
+/// `runtime` with spill files for `query_id` going to a directory of the
+/// query's own. The memory pool and caches are shared with `runtime`.
+pub fn query_runtime(
+    runtime: &RuntimeEnv,
+    query_id: &str,
+) -> Result<(Arc<RuntimeEnv>, QuerySpillDir), String> {
+    let config = self_config();
+    let node_name = config_value(&config, "node_name").unwrap_or_else(|| "local".to_string());
+    let path = node_spill_root(&spill_dir_in(&config), &node_name).join(path_component(query_id));
+    std::fs::create_dir_all(&path).map_err(|e| {
+        format!("Failed to create spill directory '{}': {e}", path.display())
+    })?;
+    let spill = QuerySpillDir { path };
+    let runtime = RuntimeEnvBuilder::from_runtime_env(runtime)
+        .with_temp_file_path(spill.path())
+        .build_arc()
+        .map_err(|e| format!("Runtime environment failed: {e}"))?;
+    Ok((runtime, spill))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("trex-spill-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn validate_spill_dir_requires_existing_directory() {
+        let dir = scratch_dir();
+        assert!(validate_spill_dir(&dir).is_ok());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0, "probe file left behind");
+
+        let missing = dir.join("missing");
+        let err = validate_spill_dir(&missing).unwrap_err();
+        assert!(err.contains("not accessible"), "error was: {err}");
+
+        let file = dir.join("file");
+        std::fs::write(&file, b"").unwrap();
+        let err = validate_spill_dir(&file).unwrap_err();
+        assert!(err.contains("not a directory"), "error was: {err}");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn prepare_node_spill_dir_removes_only_this_nodes_leftovers() {
+        let dir = scratch_dir();
+        let ours = node_spill_root(&dir, "node-127.0.0.1:7100");
+        let theirs = node_spill_root(&dir, "node-127.0.0.1:7101");
+        assert_ne!(ours, theirs);
+        std::fs::create_dir_all(ours.join("q-1")).unwrap();
+        std::fs::write(ours.join("q-1").join("spill.arrow"), b"x").unwrap();
+        std::fs::create_dir_all(ours.join("q-2")).unwrap();
+        std::fs::create_dir_all(theirs.join("q-3")).unwrap();
+
+        assert_eq!(prepare_node_spill_dir(&dir, "node-127.0.0.1:7100").unwrap(), 2);
+        assert_eq!(std::fs::read_dir(&ours).unwrap().count(), 0);
+        assert!(theirs.join("q-3").exists());
+        assert_eq!(prepare_node_spill_dir(&dir, "node-new").unwrap(), 0);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn query_runtime_spills_into_query_directory_until_dropped() {
+        let (runtime, spill) =
+            query_runtime(&RuntimeEnv::default(), "q/../1").expect("query runtime");
+        let path = spill.path().to_path_buf();
+        assert_eq!(path.file_name().unwrap(), "q____1");
+
+        let file = runtime.disk_manager.create_tmp_file("test").expect("spill file");
+        assert!(file.path().starts_with(&path));
+
+        drop(file);
+        drop(runtime);
+        drop(spill);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,243 @@
+//! SWIM-style suspicion on top of chitchat's failure detector.
+//!
+//! Chitchat's phi-accrual detector is the direct probe: a node whose
+//! heartbeats stop arriving drops out of `live_nodes()`. Instead of trusting
+//! that verdict, the node becomes `suspect` and up to `indirect_probes`
+//! random peers are asked, through a `probe_req:<suspect>` gossip key, to
+//! check it from their side. A peer that still sees the suspect alive
+//! answers with `probe_ack:<suspect>`, which clears the suspicion. Only a
+//! suspect that stays unconfirmed for `suspicion_timeout` is declared dead.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+pub const PROBE_REQUEST_PREFIX: &str = "probe_req:";
+pub const PROBE_ACK_PREFIX: &str = "probe_ack:";
+
+#[derive(Debug, Clone, Copy)]
+pub struct SwimConfig {
+    /// Peers asked to check a suspect (SWIM's `k`).
+    pub indirect_probes: usize,
+    /// How long a suspect may go unconfirmed before it is declared dead.
+    pub suspicion_timeout: Duration,
+}
+
+impl Default for SwimConfig {
+    fn default() -> Self {
+        Self {
+            indirect_probes: 3,
+            suspicion_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liveness {
+    Alive,
+    Suspect,
+    Dead,
+}
+
+impl Liveness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Liveness::Alive => "alive",
+            Liveness::Suspect => "suspect",
+            Liveness::Dead => "dead",
+        }
+    }
+}
+
+/// What the caller should do after [`SwimDetector::observe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeAction {
+    None,
+    /// The node just became suspect: publish a probe request to these peers.
+    Request(Vec<String>),
+    /// The probe is over (refuted, node back, or declared dead): withdraw
+    /// the request.
+    Withdraw,
+}
+
+struct Suspicion {
+    since: Instant,
+    helpers: Vec<String>,
+    dead: bool,
+}
+
+pub struct SwimDetector {
+    config: SwimConfig,
+    suspects: HashMap<String, Suspicion>,
+}
+
+impl SwimDetector {
+    pub fn new(config: SwimConfig) -> Self {
+        Self {
+            config,
+            suspects: HashMap::new(),
+        }
+    }
+
+    pub fn liveness(&self, node_id: &str) -> Liveness {
+        match self.suspects.get(node_id) {
+            None => Liveness::Alive,
+            Some(s) if s.dead => Liveness::Dead,
+            Some(_) => Liveness::Suspect,
+        }
+    }
+
+    /// Nodes currently suspect or dead, which must not be handed new work.
+    pub fn unreachable(&self) -> HashSet<String> {
+        self.suspects.keys().cloned().collect()
+    }
+
+    /// Peers asked to probe `node_id`, if it is currently suspected.
+    pub fn helpers(&self, node_id: &str) -> &[String] {
+        self.suspects
+            .get(node_id)
+            .map(|s| s.helpers.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Feed one round of evidence for `node_id`. `directly_alive` is the
+    /// local failure detector's verdict and `acked` whether a helper
+    /// confirmed the node. `candidates` are peers that may be asked to probe;
+    /// `shuffle_seed` picks among them.
+    pub fn observe(
+        &mut self,
+        now: Instant,
+        node_id: &str,
+        directly_alive: bool,
+        acked: bool,
+        candidates: &[String],
+        shuffle_seed: u64,
+    ) -> ProbeAction {
+        if directly_alive || acked {
+            return match self.suspects.remove(node_id) {
+                Some(_) => ProbeAction::Withdraw,
+                None => ProbeAction::None,
+            };
+        }
+
+        match self.suspects.get_mut(node_id) {
+            Some(s) => {
+                if !s.dead && now.duration_since(s.since) >= self.config.suspicion_timeout {
+                    s.dead = true;
+                    return ProbeAction::Withdraw;
+                }
+                ProbeAction::None
+            }
+            None => {
+                let helpers = pick_helpers(candidates, node_id, self.config.indirect_probes, shuffle_seed);
+                self.suspects.insert(
+                    node_id.to_string(),
+                    Suspicion {
+                        since: now,
+                        helpers: helpers.clone(),
+                        dead: false,
+                    },
+                );
+                ProbeAction::Request(helpers)
+            }
+        }
+    }
+
+    /// Drop state for nodes chitchat no longer tracks at all.
+    pub fn retain(&mut self, known: impl Fn(&str) -> bool) {
+        self.suspects.retain(|id, _| known(id));
+    }
+}
+
+/// Pick up to `k` peers other than `suspect`, using a small LCG so the
+/// choice varies between suspicions without pulling in an RNG crate.
+fn pick_helpers(candidates: &[String], suspect: &str, k: usize, seed: u64) -> Vec<String> {
+    let mut pool: Vec<&String> = candidates.iter().filter(|c| *c != suspect).collect();
+    let mut state = seed;
+    for i in (1..pool.len()).rev() {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let j = (state >> 33) as usize % (i + 1);
+        pool.swap(i, j);
+    }
+    pool.into_iter().take(k).cloned().collect()
+}
+
+/// Encode helper ids as the value of a `probe_req:` key.
+pub fn encode_helpers(helpers: &[String]) -> String {
+    helpers.join(",")
+}
+
+/// Whether a `probe_req:` value names `node_id` as a helper.
+pub fn is_helper(value: &str, node_id: &str) -> bool {
+    value.split(',').any(|h| h == node_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peers() -> Vec<String> {
+        ["a", "b", "c", "d", "e"].iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn missed_heartbeat_makes_node_suspect_and_requests_probes() {
+        let mut d = SwimDetector::new(SwimConfig::default());
+        let now = Instant::now();
+        match d.observe(now, "c", false, false, &peers(), 7) {
+            ProbeAction::Request(helpers) => {
+                assert_eq!(helpers.len(), 3);
+                assert!(!helpers.contains(&"c".to_string()));
+                assert_eq!(helpers, d.helpers("c"));
+            }
+            other => panic!("expected probe request, got {other:?}"),
+        }
+        assert_eq!(d.liveness("c"), Liveness::Suspect);
+        assert_eq!(d.unreachable(), HashSet::from(["c".to_string()]));
+    }
+
+    #[test]
+    fn indirect_ack_refutes_suspicion() {
+        let mut d = SwimDetector::new(SwimConfig::default());
+        let now = Instant::now();
+        d.observe(now, "c", false, false, &peers(), 1);
+        assert_eq!(
+            d.observe(now, "c", false, true, &peers(), 1),
+            ProbeAction::Withdraw
+        );
+        assert_eq!(d.liveness("c"), Liveness::Alive);
+        assert!(d.unreachable().is_empty());
+    }
+
+    #[test]
+    fn unconfirmed_suspect_becomes_dead_after_timeout() {
+        let config = SwimConfig {
+            indirect_probes: 2,
+            suspicion_timeout: Duration::from_secs(5),
+        };
+        let mut d = SwimDetector::new(config);
+        let start = Instant::now();
+        d.observe(start, "c", false, false, &peers(), 1);
+        d.observe(start + Duration::from_secs(4), "c", false, false, &peers(), 1);
+        assert_eq!(d.liveness("c"), Liveness::Suspect);
+        assert_eq!(
+            d.observe(start + Duration::from_secs(5), "c", false, false, &peers(), 1),
+            ProbeAction::Withdraw
+        );
+        assert_eq!(d.liveness("c"), Liveness::Dead);
+        assert!(d.unreachable().contains("c"));
+
+        // A heartbeat brings a dead node straight back.
+        d.observe(start + Duration::from_secs(6), "c", true, false, &peers(), 1);
+        assert_eq!(d.liveness("c"), Liveness::Alive);
+    }
+
+    #[test]
+    fn helper_values_round_trip() {
+        let value = encode_helpers(&["a".to_string(), "bb".to_string()]);
+        assert!(is_helper(&value, "bb"));
+        assert!(!is_helper(&value, "b"));
+        assert!(pick_helpers(&["x".to_string()], "x", 3, 0).is_empty());
+    }
+}
@@ -5,18 +5,22 @@ extern crate libduckdb_sys;
 use chrono::Utc;
 use duckdb::{
     core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
-    vtab::{BindInfo, InitInfo, TableFunctionInfo, VTab},
+    vscalar::{ScalarFunctionSignature, VScalar},
+    vtab::{arrow::WritableVector, BindInfo, InitInfo, TableFunctionInfo, VTab},
     Connection,
 };
 use libduckdb_sys as ffi;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use siphasher::sip::SipHasher13;
 use std::{
     collections::HashMap,
     error::Error,
-    fs,
+    fmt, fs,
     hash::{Hash, Hasher},
     path::Path,
     sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
 };
 
 /// Execute SQL using the shared trex_pool via a one-off session.
@@ -30,24 +34,69 @@ fn execute_sql(sql: &str) -> Result<(), Box<dyn Error>> {
 
 /// Run a list of statements inside a single BEGIN/COMMIT on one session.
 /// Rolls back and propagates the first failure.
-fn execute_statements_in_transaction(statements: &[&str]) -> Result<(), String> {
+fn execute_statements_in_transaction(statements: &[&str]) -> Result<Vec<StatementStats>, String> {
     let sid = trex_pool_client::create_session()?;
     if let Err(e) = trex_pool_client::session_execute(sid, "BEGIN") {
         let _ = trex_pool_client::destroy_session(sid);
         return Err(e);
     }
+    let mut stats = Vec::with_capacity(statements.len());
     for stmt in statements {
-        if let Err(e) = trex_pool_client::session_execute(sid, stmt) {
-            let _ = trex_pool_client::session_execute(sid, "ROLLBACK");
-            let _ = trex_pool_client::destroy_session(sid);
-            return Err(e);
+        let started = Instant::now();
+        match trex_pool_client::session_execute(sid, stmt) {
+            Ok((schema, batches)) => stats.push(StatementStats::of(started, &schema, &batches)),
+            Err(e) => {
+                let _ = trex_pool_client::session_execute(sid, "ROLLBACK");
+                let _ = trex_pool_client::destroy_session(sid);
+                return Err(e);
+            }
         }
     }
-    let commit = trex_pool_client::session_execute(sid, "COMMIT").map(|_| ());
+    let commit = trex_pool_client::session_execute(sid, "COMMIT").map(|_| stats);
     let _ = trex_pool_client::destroy_session(sid);
     commit
 }
 
+/// Wall time of one statement and the rows it changed, where DuckDB
+/// reports them.
+#[derive(Debug, Clone, Copy)]
+struct StatementStats {
+    duration_ms: f64,
+    rows_affected: Option<i64>,
+}
+
+impl StatementStats {
+    /// Stats of a statement started at `started` that returned `batches`.
+    /// DuckDB answers INSERT, UPDATE, DELETE and CREATE TABLE AS with a
+    /// single `Count` column; other statements have no row count. With
+    /// several statements in one string, only the last one's result comes
+    /// back.
+    fn of(
+        started: Instant,
+        schema: &trex_pool_client::arrow_schema::Schema,
+        batches: &[trex_pool_client::arrow_array::RecordBatch],
+    ) -> Self {
+        let rows_affected = match schema.fields().as_ref() {
+            [field] if field.name() == "Count" => batches.first().and_then(|batch| {
+                let counts = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<trex_pool_client::arrow_array::Int64Array>()?;
+                counts.iter().next().flatten()
+            }),
+            _ => None,
+        };
+        StatementStats {
+            duration_ms: elapsed_ms(started),
+            rows_affected,
+        }
+    }
+}
+
+fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
+
 struct QueryRow {
     columns: Vec<String>,
 }
@@ -168,15 +217,153 @@ fn format_hex(bytes: &[u8]) -> String {
     s
 }
 
+/// How a migration's checksum is computed. The algorithm a migration was
+/// applied with is recorded in the history table and used to verify it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChecksumAlgorithm {
+    /// Refinery's SipHash-1-3 of name, version and SQL.
+    SipHash,
+    /// Flyway's CRC32 of the SQL lines, as a signed 32-bit integer.
+    Crc32,
+    /// Lowercase hex SHA-256 of the SQL text.
+    Sha256,
+}
+
+impl ChecksumAlgorithm {
+    fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "siphash" => Ok(ChecksumAlgorithm::SipHash),
+            "crc32" => Ok(ChecksumAlgorithm::Crc32),
+            "sha256" | "sha-256" => Ok(ChecksumAlgorithm::Sha256),
+            other => Err(format!(
+                "Unknown checksum algorithm '{}': expected siphash, crc32 or sha256",
+                other
+            )),
+        }
+    }
+
+    /// Algorithm recorded for a history row. Rows written before the marker
+    /// existed, or by refinery itself, have none and use SipHash.
+    fn from_history(marker: &str) -> Result<Self, String> {
+        if marker.trim().is_empty() {
+            Ok(ChecksumAlgorithm::SipHash)
+        } else {
+            Self::parse(marker)
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::SipHash => "siphash",
+            ChecksumAlgorithm::Crc32 => "crc32",
+            ChecksumAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+/// Checksum algorithm from the optional `checksum` named parameter.
+fn checksum_parameter(bind: &BindInfo) -> Result<ChecksumAlgorithm, Box<dyn Error>> {
+    match bind.get_named_parameter("checksum") {
+        Some(value) => Ok(ChecksumAlgorithm::parse(&value.to_string())?),
+        None => Ok(ChecksumAlgorithm::SipHash),
+    }
+}
+
+fn checksum_named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+    Some(vec![(
+        "checksum".to_string(),
+        LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    )])
+}
+
+/// Whether the optional `allow_out_of_order` named parameter is set.
+fn out_of_order_parameter(bind: &BindInfo) -> bool {
+    bind.get_named_parameter("allow_out_of_order")
+        .map(|value| value.to_string().eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Values for `${name}` tokens from the optional `placeholders` named
+/// parameter, a JSON object. Non-string values are substituted as their JSON
+/// text.
+fn placeholders_parameter(bind: &BindInfo) -> Result<HashMap<String, String>, Box<dyn Error>> {
+    let Some(value) = bind.get_named_parameter("placeholders") else {
+        return Ok(HashMap::new());
+    };
+    let json: serde_json::Value = serde_json::from_str(&value.to_string())
+        .map_err(|e| format!("Invalid placeholders JSON: {}", e))?;
+    let object = json
+        .as_object()
+        .ok_or("Invalid placeholders JSON: expected an object")?;
+    Ok(object
+        .iter()
+        .map(|(key, value)| {
+            let text = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            (key.clone(), text)
+        })
+        .collect())
+}
+
+fn migrate_named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+    let mut params = checksum_named_parameters().unwrap_or_default();
+    params.push((
+        "allow_out_of_order".to_string(),
+        LogicalTypeHandle::from(LogicalTypeId::Boolean),
+    ));
+    params.push((
+        "placeholders".to_string(),
+        LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    ));
+    Some(params)
+}
+
+/// Replace `${name}` tokens in `sql`, or list the names that have no value.
+/// A `${` without a closing brace is left alone.
+fn substitute_placeholders(
+    sql: &str,
+    placeholders: &HashMap<String, String>,
+) -> Result<String, Vec<String>> {
+    let mut out = String::with_capacity(sql.len());
+    let mut missing: Vec<String> = Vec::new();
+    let mut rest = sql;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        let key = &rest[start + 2..start + 2 + len];
+        out.push_str(&rest[..start]);
+        match placeholders.get(key) {
+            Some(value) => out.push_str(value),
+            None => {
+                if !missing.iter().any(|m| m == key) {
+                    missing.push(key.to_string());
+                }
+            }
+        }
+        rest = &rest[start + 3 + len..];
+    }
+    out.push_str(rest);
+    if missing.is_empty() {
+        Ok(out)
+    } else {
+        Err(missing)
+    }
+}
+
 struct MigrationFile {
     version: i32,
     name: String,
     sql: String,
-    checksum: u64,
+    /// Checksum under the algorithm selected for this run.
+    checksum: String,
+    algorithm: ChecksumAlgorithm,
 }
 
 impl MigrationFile {
-    fn from_path(path: &Path) -> Option<Self> {
+    fn from_path(path: &Path, algorithm: ChecksumAlgorithm) -> Option<Self> {
         let filename = path.file_name()?.to_str()?;
 
         if !filename.starts_with('V') || !filename.ends_with(".sql") {
@@ -200,27 +387,76 @@ impl MigrationFile {
         }
 
         let sql = fs::read_to_string(path).ok()?;
-        let checksum = compute_checksum(name, version, &sql);
+        let checksum = compute_checksum(algorithm, name, version, &sql);
 
         Some(MigrationFile {
             version,
             name: name.to_string(),
             sql,
             checksum,
+            algorithm,
+        })
+    }
+
+    /// SQL to execute, with placeholders substituted. The checksum stays that
+    /// of the file as written, so it is the same in every environment.
+    fn resolved_sql(&self, placeholders: &HashMap<String, String>) -> Result<String, Box<dyn Error>> {
+        substitute_placeholders(&self.sql, placeholders).map_err(|missing| {
+            format!(
+                "Migration V{}__{} references undefined placeholders: {}",
+                self.version,
+                self.name,
+                missing.join(", ")
+            )
+            .into()
         })
     }
+
+    /// Whether this file still matches what was recorded when `applied`
+    /// ran, recomputing with the algorithm recorded for it.
+    fn matches(&self, applied: &AppliedMigration) -> bool {
+        let expected = if applied.algorithm == self.algorithm {
+            self.checksum.clone()
+        } else {
+            compute_checksum(applied.algorithm, &self.name, self.version, &self.sql)
+        };
+        applied.checksum.trim() == expected
+    }
+}
+
+fn compute_checksum(algorithm: ChecksumAlgorithm, name: &str, version: i32, sql: &str) -> String {
+    match algorithm {
+        ChecksumAlgorithm::SipHash => {
+            let mut hasher = SipHasher13::new();
+            name.hash(&mut hasher);
+            version.hash(&mut hasher);
+            sql.hash(&mut hasher);
+            hasher.finish().to_string()
+        }
+        ChecksumAlgorithm::Crc32 => flyway_crc32(sql).to_string(),
+        ChecksumAlgorithm::Sha256 => Sha256::digest(sql.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect(),
+    }
 }
 
-fn compute_checksum(name: &str, version: i32, sql: &str) -> u64 {
-    let mut hasher = SipHasher13::new();
-    name.hash(&mut hasher);
-    version.hash(&mut hasher);
-    sql.hash(&mut hasher);
-    hasher.finish()
+/// Flyway feeds each line to CRC32 without its terminator and drops a
+/// leading byte-order mark, so only the bytes between line breaks count.
+fn flyway_crc32(sql: &str) -> i32 {
+    let sql = sql.strip_prefix('\u{feff}').unwrap_or(sql);
+    let mut hasher = crc32fast::Hasher::new();
+    for line in sql.split(['\r', '\n']) {
+        hasher.update(line.as_bytes());
+    }
+    hasher.finalize() as i32
 }
 
 
-fn discover_migrations(dir_path: &str) -> Result<Vec<MigrationFile>, Box<dyn Error>> {
+fn discover_migrations(
+    dir_path: &str,
+    algorithm: ChecksumAlgorithm,
+) -> Result<Vec<MigrationFile>, Box<dyn Error>> {
     let path = Path::new(dir_path);
     if !path.exists() {
         return Err(format!("Directory not found: {}", dir_path).into());
@@ -235,7 +471,7 @@ fn discover_migrations(dir_path: &str) -> Result<Vec<MigrationFile>, Box<dyn Err
         let entry = entry?;
         let file_path = entry.path();
         if file_path.is_file() {
-            if let Some(migration) = MigrationFile::from_path(&file_path) {
+            if let Some(migration) = MigrationFile::from_path(&file_path, algorithm) {
                 migrations.push(migration);
             }
         }
@@ -267,47 +503,78 @@ struct AppliedMigration {
     version: i32,
     name: String,
     applied_on: String,
-    checksum: u64,
+    checksum: String,
+    algorithm: ChecksumAlgorithm,
+    /// Recorded by `trex_migration_baseline` rather than run.
+    baseline: bool,
 }
 
+/// `migration_type` of history rows written by `trex_migration_baseline`.
+const BASELINE_TYPE: &str = "baseline";
+
+/// Columns added to the history table after its first release, created on
+/// tables that predate them.
+const ADDED_HISTORY_COLUMNS: &[(&str, &str)] = &[
+    ("checksum_algorithm", "VARCHAR(32)"),
+    ("migration_type", "VARCHAR(16)"),
+];
+
 fn ensure_history_table() -> Result<(), Box<dyn Error>> {
     execute_sql(
         "CREATE TABLE IF NOT EXISTS refinery_schema_history(\
             version INT4 PRIMARY KEY,\
             name VARCHAR(255),\
             applied_on VARCHAR(255),\
-            checksum VARCHAR(255)\
+            checksum VARCHAR(255),\
+            checksum_algorithm VARCHAR(32),\
+            migration_type VARCHAR(16)\
         );",
-    )
-}
-
-fn query_applied_migrations() -> Result<Vec<AppliedMigration>, Box<dyn Error>> {
-    let rows = query_sql(
-        "SELECT version, name, applied_on, checksum \
-         FROM refinery_schema_history ORDER BY version",
     )?;
+    for (column, column_type) in ADDED_HISTORY_COLUMNS {
+        execute_sql(&format!(
+            "ALTER TABLE refinery_schema_history ADD COLUMN IF NOT EXISTS {} {}",
+            column, column_type
+        ))?;
+    }
+    Ok(())
+}
 
+/// Parse `version, name, applied_on, checksum, checksum_algorithm,
+/// migration_type` rows.
+fn parse_applied_rows(rows: Vec<QueryRow>) -> Result<Vec<AppliedMigration>, Box<dyn Error>> {
     let mut result = Vec::new();
     for row in rows {
-        if row.columns.len() < 4 {
+        if row.columns.len() < 6 {
             continue;
         }
         let version: i32 = row.columns[0]
             .parse()
             .map_err(|_| format!("Invalid version in schema history: {}", row.columns[0]))?;
-        let checksum: u64 = row.columns[3]
-            .parse()
-            .map_err(|_| format!("Invalid checksum in schema history: {}", row.columns[3]))?;
+        let algorithm = ChecksumAlgorithm::from_history(&row.columns[4])
+            .map_err(|e| format!("Invalid checksum algorithm for version {}: {}", version, e))?;
+        if algorithm == ChecksumAlgorithm::SipHash && row.columns[3].trim().parse::<u64>().is_err() {
+            return Err(format!("Invalid checksum in schema history: {}", row.columns[3]).into());
+        }
         result.push(AppliedMigration {
             version,
             name: row.columns[1].clone(),
             applied_on: row.columns[2].clone(),
-            checksum,
+            checksum: row.columns[3].clone(),
+            algorithm,
+            baseline: row.columns[5] == BASELINE_TYPE,
         });
     }
     Ok(result)
 }
 
+fn query_applied_migrations() -> Result<Vec<AppliedMigration>, Box<dyn Error>> {
+    let rows = query_sql(
+        "SELECT version, name, applied_on, checksum, checksum_algorithm, migration_type \
+         FROM refinery_schema_history ORDER BY version",
+    )?;
+    parse_applied_rows(rows)
+}
+
 fn insert_migration_record(migration: &MigrationFile) -> Result<(), Box<dyn Error>> {
     let sql = build_insert_migration_sql(migration);
     execute_sql(&sql)
@@ -315,30 +582,47 @@ fn insert_migration_record(migration: &MigrationFile) -> Result<(), Box<dyn Erro
 
 /// Build the INSERT SQL for a migration history record.
 fn build_insert_migration_sql(migration: &MigrationFile) -> String {
+    build_history_insert_sql(migration, "NULL")
+}
+
+/// Build the INSERT SQL recording `migration` as baselined.
+fn build_baseline_record_sql(migration: &MigrationFile) -> String {
+    build_history_insert_sql(migration, &format!("'{}'", BASELINE_TYPE))
+}
+
+fn build_history_insert_sql(migration: &MigrationFile, migration_type: &str) -> String {
     let applied_on = Utc::now().to_rfc3339();
     format!(
-        "INSERT INTO refinery_schema_history (version, name, applied_on, checksum) \
-         VALUES ({}, '{}', '{}', '{}')",
+        "INSERT INTO refinery_schema_history \
+         (version, name, applied_on, checksum, checksum_algorithm, migration_type) \
+         VALUES ({}, '{}', '{}', '{}', '{}', {})",
         migration.version,
         migration.name.replace('\'', "''"),
         applied_on.replace('\'', "''"),
         migration.checksum,
+        migration.algorithm.as_str(),
+        migration_type,
     )
 }
 
 
+/// Indices of the discovered migrations that still need to run. A pending
+/// migration older than the latest applied one is an error unless
+/// `allow_out_of_order` is set, in which case it is applied like any other.
 fn verify_migrations(
     discovered: &[MigrationFile],
     applied: &[AppliedMigration],
+    allow_out_of_order: bool,
 ) -> Result<Vec<usize>, Box<dyn Error>> {
     let applied_map: HashMap<i32, &AppliedMigration> =
         applied.iter().map(|a| (a.version, a)).collect();
+    let latest_applied = applied.iter().map(|a| a.version).max();
 
     let mut pending_indices = Vec::new();
 
     for (idx, migration) in discovered.iter().enumerate() {
         if let Some(applied_migration) = applied_map.get(&migration.version) {
-            if applied_migration.checksum != migration.checksum {
+            if !migration.matches(applied_migration) {
                 return Err(format!(
                     "Checksum mismatch for migration V{}__{}: \
                      file has been modified since it was applied",
@@ -347,6 +631,16 @@ fn verify_migrations(
                 .into());
             }
         } else {
+            if let Some(latest) = latest_applied.filter(|&v| v > migration.version) {
+                if !allow_out_of_order {
+                    return Err(format!(
+                        "Migration V{}__{} is older than the latest applied version {}: \
+                         pass allow_out_of_order := true to apply it",
+                        migration.version, migration.name, latest
+                    )
+                    .into());
+                }
+            }
             pending_indices.push(idx);
         }
     }
@@ -355,46 +649,136 @@ fn verify_migrations(
 }
 
 
+#[derive(Debug, Serialize)]
 struct MigrationResult {
     version: i32,
     name: String,
     status: String,
+    /// Wall time of the migration's SQL, without recording it in the
+    /// history; none for migrations that were not run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rows_affected: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl MigrationResult {
+    fn new(migration: &MigrationFile, status: &str) -> Self {
+        MigrationResult {
+            version: migration.version,
+            name: migration.name.clone(),
+            status: status.to_string(),
+            duration_ms: None,
+            rows_affected: None,
+            error: None,
+        }
+    }
+
+    fn with_stats(mut self, stats: StatementStats) -> Self {
+        self.duration_ms = Some(stats.duration_ms);
+        self.rows_affected = stats.rows_affected;
+        self
+    }
+}
+
+/// A run of [`execute_migrations`] that stopped at a migration that failed,
+/// with the results up to and including it.
+#[derive(Debug)]
+struct MigrationFailure {
+    results: Vec<MigrationResult>,
+    message: String,
+}
+
+impl MigrationFailure {
+    /// `duration_ms` is how long the migration's SQL ran before failing,
+    /// none if it failed before the SQL ran.
+    fn at(
+        mut results: Vec<MigrationResult>,
+        migration: &MigrationFile,
+        duration_ms: Option<f64>,
+        error: String,
+    ) -> Self {
+        let message = format!(
+            "Migration V{}__{} failed: {}", migration.version, migration.name, error
+        );
+        let mut failed = MigrationResult::new(migration, "failed");
+        failed.duration_ms = duration_ms;
+        failed.error = Some(error);
+        results.push(failed);
+        results.sort_by_key(|r| r.version);
+        MigrationFailure { results, message }
+    }
+}
+
+impl fmt::Display for MigrationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for MigrationFailure {}
+
+/// Substituted SQL of each pending migration, resolved up front so an
+/// undefined placeholder fails the run before anything is applied.
+fn resolve_pending_sql(
+    discovered: &[MigrationFile],
+    pending_indices: &[usize],
+    placeholders: &HashMap<String, String>,
+) -> Result<Vec<String>, Box<dyn Error>> {
+    pending_indices
+        .iter()
+        .map(|&idx| discovered[idx].resolved_sql(placeholders))
+        .collect()
 }
 
 fn execute_migrations(
     discovered: &[MigrationFile],
     pending_indices: &[usize],
-) -> Result<Vec<MigrationResult>, Box<dyn Error>> {
+    placeholders: &HashMap<String, String>,
+) -> Result<Vec<MigrationResult>, MigrationFailure> {
+    let pending_sql = resolve_pending_sql(discovered, pending_indices, placeholders).map_err(|e| {
+        MigrationFailure {
+            results: Vec::new(),
+            message: e.to_string(),
+        }
+    })?;
     let mut results = Vec::new();
     let pending_set: std::collections::HashSet<usize> =
         pending_indices.iter().copied().collect();
 
     for (idx, migration) in discovered.iter().enumerate() {
         if !pending_set.contains(&idx) {
-            results.push(MigrationResult {
-                version: migration.version,
-                name: migration.name.clone(),
-                status: "skipped".to_string(),
-            });
+            results.push(MigrationResult::new(migration, "skipped"));
         }
     }
 
-    for &idx in pending_indices {
+    for (&idx, sql) in pending_indices.iter().zip(&pending_sql) {
         let migration = &discovered[idx];
 
         // Run migration + insert record in a single transaction via session
         let insert_sql = build_insert_migration_sql(migration);
-        let sid = trex_pool_client::create_session()
-            .map_err(|e| -> Box<dyn Error> { e.into() })?;
+        let sid = match trex_pool_client::create_session() {
+            Ok(sid) => sid,
+            Err(e) => return Err(MigrationFailure::at(results, migration, None, e.to_string())),
+        };
 
-        let txn_result: Result<(), Box<dyn Error>> = (|| {
+        let mut sql_ms = None;
+        let txn_result: Result<StatementStats, Box<dyn Error>> = (|| {
             trex_pool_client::session_execute(sid, "BEGIN")
                 .map_err(|e| -> Box<dyn Error> { e.into() })?;
 
-            if let Err(e) = trex_pool_client::session_execute(sid, &migration.sql) {
-                let _ = trex_pool_client::session_execute(sid, "ROLLBACK");
-                return Err(e.into());
-            }
+            let started = Instant::now();
+            let stats = match trex_pool_client::session_execute(sid, sql) {
+                Ok((schema, batches)) => StatementStats::of(started, &schema, &batches),
+                Err(e) => {
+                    sql_ms = Some(elapsed_ms(started));
+                    let _ = trex_pool_client::session_execute(sid, "ROLLBACK");
+                    return Err(e.into());
+                }
+            };
+            sql_ms = Some(stats.duration_ms);
 
             if let Err(e) = trex_pool_client::session_execute(sid, &insert_sql) {
                 let _ = trex_pool_client::session_execute(sid, "ROLLBACK");
@@ -402,23 +786,16 @@ fn execute_migrations(
             }
 
             trex_pool_client::session_execute(sid, "COMMIT")
-                .map(|_| ())
+                .map(|_| stats)
                 .map_err(|e| -> Box<dyn Error> { e.into() })
         })();
 
         let _ = trex_pool_client::destroy_session(sid);
 
-        if let Err(e) = txn_result {
-            return Err(format!(
-                "Migration V{}__{} failed: {}", migration.version, migration.name, e
-            ).into());
+        match txn_result {
+            Ok(stats) => results.push(MigrationResult::new(migration, "applied").with_stats(stats)),
+            Err(e) => return Err(MigrationFailure::at(results, migration, sql_ms, e.to_string())),
         }
-
-        results.push(MigrationResult {
-            version: migration.version,
-            name: migration.name.clone(),
-            status: "applied".to_string(),
-        });
     }
 
     results.sort_by_key(|r| r.version);
@@ -426,9 +803,47 @@ fn execute_migrations(
 }
 
 
+/// Columns of `trex_migration_run` and `trex_migration_run_schema`.
+fn add_run_result_columns(bind: &BindInfo) {
+    bind.add_result_column("version", LogicalTypeHandle::from(LogicalTypeId::Integer));
+    bind.add_result_column("name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+    bind.add_result_column("status", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+    bind.add_result_column("duration_ms", LogicalTypeHandle::from(LogicalTypeId::Double));
+    bind.add_result_column("rows_affected", LogicalTypeHandle::from(LogicalTypeId::Bigint));
+}
+
+/// Write `result` as the single row of `output`.
+fn write_run_result(output: &mut DataChunkHandle, result: &MigrationResult) {
+    let mut version_vector = output.flat_vector(0);
+    version_vector.as_mut_slice::<i32>()[0] = result.version;
+
+    let name_vector = output.flat_vector(1);
+    name_vector.insert(0, result.name.as_str());
+
+    let status_vector = output.flat_vector(2);
+    status_vector.insert(0, result.status.as_str());
+
+    let mut duration_vector = output.flat_vector(3);
+    match result.duration_ms {
+        Some(ms) => duration_vector.as_mut_slice::<f64>()[0] = ms,
+        None => duration_vector.set_null(0),
+    }
+
+    let mut rows_vector = output.flat_vector(4);
+    match result.rows_affected {
+        Some(rows) => rows_vector.as_mut_slice::<i64>()[0] = rows,
+        None => rows_vector.set_null(0),
+    }
+
+    output.set_len(1);
+}
+
 #[repr(C)]
 struct MigrateBindData {
     path: String,
+    algorithm: ChecksumAlgorithm,
+    allow_out_of_order: bool,
+    placeholders: HashMap<String, String>,
 }
 
 #[repr(C)]
@@ -444,12 +859,18 @@ impl VTab for MigrateVTab {
     type BindData = MigrateBindData;
 
     fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn Error>> {
-        bind.add_result_column("version", LogicalTypeHandle::from(LogicalTypeId::Integer));
-        bind.add_result_column("name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("status", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        add_run_result_columns(bind);
 
         let path = bind.get_parameter(0).to_string();
-        Ok(MigrateBindData { path })
+        let algorithm = checksum_parameter(bind)?;
+        let allow_out_of_order = out_of_order_parameter(bind);
+        let placeholders = placeholders_parameter(bind)?;
+        Ok(MigrateBindData {
+            path,
+            algorithm,
+            allow_out_of_order,
+            placeholders,
+        })
     }
 
     fn init(init: &InitInfo) -> Result<Self::InitData, Box<dyn Error>> {
@@ -457,13 +878,20 @@ impl VTab for MigrateVTab {
         if bind_data.is_null() {
             return Err("Bind data is null".into());
         }
-        let path = unsafe { (*bind_data).path.clone() };
+        let (path, algorithm, allow_out_of_order, placeholders) = unsafe {
+            (
+                (*bind_data).path.clone(),
+                (*bind_data).algorithm,
+                (*bind_data).allow_out_of_order,
+                (*bind_data).placeholders.clone(),
+            )
+        };
 
-        let discovered = discover_migrations(&path)?;
+        let discovered = discover_migrations(&path, algorithm)?;
         ensure_history_table()?;
         let applied = query_applied_migrations()?;
-        let pending_indices = verify_migrations(&discovered, &applied)?;
-        let results = execute_migrations(&discovered, &pending_indices)?;
+        let pending_indices = verify_migrations(&discovered, &applied, allow_out_of_order)?;
+        let results = execute_migrations(&discovered, &pending_indices, &placeholders)?;
 
         Ok(MigrateInitData {
             results,
@@ -483,23 +911,111 @@ impl VTab for MigrateVTab {
             return Ok(());
         }
 
-        let result = &init_data.results[current_index];
+        write_run_result(output, &init_data.results[current_index]);
+        Ok(())
+    }
 
-        let mut version_vector = output.flat_vector(0);
-        version_vector.as_mut_slice::<i32>()[0] = result.version;
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)])
+    }
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        migrate_named_parameters()
+    }
+}
 
-        let name_vector = output.flat_vector(1);
-        name_vector.insert(0, result.name.as_str());
 
-        let status_vector = output.flat_vector(2);
-        status_vector.insert(0, result.status.as_str());
+/// What `trex_migration_run_json` returns for a run.
+#[derive(Debug, Serialize)]
+struct MigrationRunReport {
+    success: bool,
+    applied: usize,
+    skipped: usize,
+    failed_version: Option<i32>,
+    error: Option<String>,
+    duration_ms: f64,
+    results: Vec<MigrationResult>,
+}
 
-        output.set_len(1);
+impl MigrationRunReport {
+    fn new(results: Vec<MigrationResult>, error: Option<String>, started: Instant) -> Self {
+        let count = |status: &str| results.iter().filter(|r| r.status == status).count();
+        MigrationRunReport {
+            success: error.is_none(),
+            applied: count("applied"),
+            skipped: count("skipped"),
+            failed_version: results.iter().find(|r| r.status == "failed").map(|r| r.version),
+            error,
+            duration_ms: started.elapsed().as_secs_f64() * 1000.0,
+            results,
+        }
+    }
+
+    /// Results of the run so far, and the error that stopped it.
+    fn from_run(run: Result<Vec<MigrationResult>, Box<dyn Error>>, started: Instant) -> Self {
+        match run {
+            Ok(results) => Self::new(results, None, started),
+            Err(e) => match e.downcast::<MigrationFailure>() {
+                Ok(failure) => Self::new(failure.results, Some(failure.message), started),
+                Err(e) => Self::new(Vec::new(), Some(e.to_string()), started),
+            },
+        }
+    }
+
+    fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_else(|e| {
+            serde_json::json!({ "success": false, "error": e.to_string() }).to_string()
+        })
+    }
+}
+
+/// Apply the pending migrations in `path` as `trex_migration_run` does with
+/// its default options, and report the outcome as JSON even when it fails.
+fn migrate_to_json(path: &str) -> String {
+    let started = Instant::now();
+    let run = (|| -> Result<Vec<MigrationResult>, Box<dyn Error>> {
+        let discovered = discover_migrations(path, ChecksumAlgorithm::SipHash)?;
+        ensure_history_table()?;
+        let applied = query_applied_migrations()?;
+        let pending_indices = verify_migrations(&discovered, &applied, false)?;
+        Ok(execute_migrations(&discovered, &pending_indices, &HashMap::new())?)
+    })();
+    MigrationRunReport::from_run(run, started).to_json()
+}
+
+struct MigrateJsonScalar;
+
+impl VScalar for MigrateJsonScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn Error>> {
+        let len = input.len();
+        let path_vector = input.flat_vector(0);
+        let paths = path_vector.as_slice_with_len::<ffi::duckdb_string_t>(len);
+        let output_vector = output.flat_vector();
+        for (i, path) in paths.iter().enumerate() {
+            let path = duckdb::types::DuckString::new(&mut { *path })
+                .as_str()
+                .to_string();
+            output_vector.insert(i, migrate_to_json(&path).as_str());
+        }
         Ok(())
     }
 
-    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
-        Some(vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)])
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![ScalarFunctionSignature::exact(
+            vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)],
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        )]
+    }
+
+    /// Running migrations is a side effect, so the call must not be folded
+    /// into a constant at planning time.
+    fn volatile() -> bool {
+        true
     }
 }
 
@@ -510,11 +1026,51 @@ struct MigrationStatusResult {
     status: String,
     applied_on: String,
     checksum: String,
+    checksum_algorithm: String,
+}
+
+/// Status of each discovered migration against the history. Applied
+/// migrations are checked, and their checksum shown, with the algorithm
+/// recorded for them; pending ones use `algorithm`.
+fn migration_statuses(
+    discovered: &[MigrationFile],
+    applied: &[AppliedMigration],
+) -> Vec<MigrationStatusResult> {
+    let applied_map: HashMap<i32, &AppliedMigration> =
+        applied.iter().map(|a| (a.version, a)).collect();
+
+    discovered
+        .iter()
+        .map(|migration| {
+            let (status, applied_on, algorithm) = match applied_map.get(&migration.version) {
+                Some(am) => {
+                    let status = if !migration.matches(am) {
+                        "checksum_mismatch"
+                    } else if am.baseline {
+                        "baseline"
+                    } else {
+                        "applied"
+                    };
+                    (status, am.applied_on.clone(), am.algorithm)
+                }
+                None => ("pending", String::new(), migration.algorithm),
+            };
+            MigrationStatusResult {
+                version: migration.version,
+                name: migration.name.clone(),
+                status: status.to_string(),
+                applied_on,
+                checksum: compute_checksum(algorithm, &migration.name, migration.version, &migration.sql),
+                checksum_algorithm: algorithm.as_str().to_string(),
+            }
+        })
+        .collect()
 }
 
 #[repr(C)]
 struct MigrationStatusBindData {
     path: String,
+    algorithm: ChecksumAlgorithm,
 }
 
 #[repr(C)]
@@ -538,9 +1094,14 @@ impl VTab for MigrationStatusVTab {
             LogicalTypeHandle::from(LogicalTypeId::Varchar),
         );
         bind.add_result_column("checksum", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "checksum_algorithm",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
 
         let path = bind.get_parameter(0).to_string();
-        Ok(MigrationStatusBindData { path })
+        let algorithm = checksum_parameter(bind)?;
+        Ok(MigrationStatusBindData { path, algorithm })
     }
 
     fn init(init: &InitInfo) -> Result<Self::InitData, Box<dyn Error>> {
@@ -548,36 +1109,12 @@ impl VTab for MigrationStatusVTab {
         if bind_data.is_null() {
             return Err("Bind data is null".into());
         }
-        let path = unsafe { (*bind_data).path.clone() };
+        let (path, algorithm) = unsafe { ((*bind_data).path.clone(), (*bind_data).algorithm) };
 
-        let discovered = discover_migrations(&path)?;
+        let discovered = discover_migrations(&path, algorithm)?;
         ensure_history_table()?;
         let applied = query_applied_migrations()?;
-
-        let applied_map: HashMap<i32, &AppliedMigration> =
-            applied.iter().map(|a| (a.version, a)).collect();
-
-        let mut results = Vec::new();
-        for migration in &discovered {
-            let (status, applied_on) = match applied_map.get(&migration.version) {
-                Some(am) => {
-                    if am.checksum == migration.checksum {
-                        ("applied".to_string(), am.applied_on.clone())
-                    } else {
-                        ("checksum_mismatch".to_string(), am.applied_on.clone())
-                    }
-                }
-                None => ("pending".to_string(), String::new()),
-            };
-
-            results.push(MigrationStatusResult {
-                version: migration.version,
-                name: migration.name.clone(),
-                status,
-                applied_on,
-                checksum: migration.checksum.to_string(),
-            });
-        }
+        let results = migration_statuses(&discovered, &applied);
 
         Ok(MigrationStatusInitData {
             results,
@@ -614,6 +1151,9 @@ impl VTab for MigrationStatusVTab {
         let checksum_vector = output.flat_vector(4);
         checksum_vector.insert(0, result.checksum.as_str());
 
+        let algorithm_vector = output.flat_vector(5);
+        algorithm_vector.insert(0, result.checksum_algorithm.as_str());
+
         output.set_len(1);
         Ok(())
     }
@@ -621,9 +1161,147 @@ impl VTab for MigrationStatusVTab {
     fn parameters() -> Option<Vec<LogicalTypeHandle>> {
         Some(vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)])
     }
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        checksum_named_parameters()
+    }
 }
 
 
+/// Record every discovered migration at or below `baseline_version` as
+/// applied without running it. Only allowed on an empty history, so an
+/// existing record is never overwritten.
+fn baseline_migrations(
+    discovered: &[MigrationFile],
+    applied: &[AppliedMigration],
+    baseline_version: i32,
+) -> Result<Vec<MigrationResult>, Box<dyn Error>> {
+    if !applied.is_empty() {
+        return Err(format!(
+            "Cannot baseline: refinery_schema_history already has {} entries",
+            applied.len()
+        )
+        .into());
+    }
+
+    let baselined: Vec<&MigrationFile> = discovered
+        .iter()
+        .filter(|m| m.version <= baseline_version)
+        .collect();
+    if baselined.is_empty() {
+        return Err(format!(
+            "No migrations at or below version {} to baseline",
+            baseline_version
+        )
+        .into());
+    }
+    let inserts: Vec<String> = baselined.iter().map(|m| build_baseline_record_sql(m)).collect();
+    let statements: Vec<&str> = inserts.iter().map(String::as_str).collect();
+    execute_statements_in_transaction(&statements)
+        .map_err(|e| -> Box<dyn Error> { format!("Baseline failed: {}", e).into() })?;
+
+    Ok(baselined
+        .into_iter()
+        .map(|m| MigrationResult::new(m, BASELINE_TYPE))
+        .collect())
+}
+
+#[repr(C)]
+struct BaselineBindData {
+    path: String,
+    baseline_version: i32,
+    algorithm: ChecksumAlgorithm,
+}
+
+#[repr(C)]
+struct BaselineInitData {
+    results: Vec<MigrationResult>,
+    index: AtomicUsize,
+}
+
+struct BaselineVTab;
+
+impl VTab for BaselineVTab {
+    type InitData = BaselineInitData;
+    type BindData = BaselineBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn Error>> {
+        bind.add_result_column("version", LogicalTypeHandle::from(LogicalTypeId::Integer));
+        bind.add_result_column("name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("status", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+
+        let path = bind.get_parameter(0).to_string();
+        let baseline_version = i32::try_from(bind.get_parameter(1).to_int64())
+            .map_err(|_| "baseline_version is out of range")?;
+        let algorithm = checksum_parameter(bind)?;
+        Ok(BaselineBindData {
+            path,
+            baseline_version,
+            algorithm,
+        })
+    }
+
+    fn init(init: &InitInfo) -> Result<Self::InitData, Box<dyn Error>> {
+        let bind_data = init.get_bind_data::<Self::BindData>();
+        if bind_data.is_null() {
+            return Err("Bind data is null".into());
+        }
+        let (path, baseline_version, algorithm) = unsafe {
+            (
+                (*bind_data).path.clone(),
+                (*bind_data).baseline_version,
+                (*bind_data).algorithm,
+            )
+        };
+
+        let discovered = discover_migrations(&path, algorithm)?;
+        ensure_history_table()?;
+        let applied = query_applied_migrations()?;
+        let results = baseline_migrations(&discovered, &applied, baseline_version)?;
+
+        Ok(BaselineInitData {
+            results,
+            index: AtomicUsize::new(0),
+        })
+    }
+
+    fn func(
+        func: &TableFunctionInfo<Self>,
+        output: &mut DataChunkHandle,
+    ) -> Result<(), Box<dyn Error>> {
+        let init_data = func.get_init_data();
+        let current_index = init_data.index.fetch_add(1, Ordering::Relaxed);
+
+        if current_index >= init_data.results.len() {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let result = &init_data.results[current_index];
+
+        let mut version_vector = output.flat_vector(0);
+        version_vector.as_mut_slice::<i32>()[0] = result.version;
+
+        let name_vector = output.flat_vector(1);
+        name_vector.insert(0, result.name.as_str());
+
+        let status_vector = output.flat_vector(2);
+        status_vector.insert(0, result.status.as_str());
+
+        output.set_len(1);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        Some(vec![
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+            LogicalTypeHandle::from(LogicalTypeId::Integer),
+        ])
+    }
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        checksum_named_parameters()
+    }
+}
+
 fn escape_sql_ident(s: &str) -> String {
     s.replace('"', "\"\"")
 }
@@ -694,22 +1372,30 @@ fn ensure_history_table_in(
             version INT4 PRIMARY KEY,\
             name VARCHAR(255),\
             applied_on VARCHAR(255),\
-            checksum VARCHAR(255)\
+            checksum VARCHAR(255),\
+            checksum_algorithm VARCHAR(32),\
+            migration_type VARCHAR(16)\
         );",
         escape_sql_ident(schema)
     );
     if is_postgres {
-        postgres_execute_sql(database, &ddl)
+        postgres_execute_sql(database, &ddl)?;
+        for (column, column_type) in ADDED_HISTORY_COLUMNS {
+            postgres_execute_sql(
+                database,
+                &format!(
+                    "ALTER TABLE \"{}\".refinery_schema_history \
+                     ADD COLUMN IF NOT EXISTS {} {}",
+                    escape_sql_ident(schema),
+                    column,
+                    column_type
+                ),
+            )?;
+        }
+        Ok(())
     } else {
         // Table resolves via active USE context
-        execute_sql(
-            "CREATE TABLE IF NOT EXISTS refinery_schema_history(\
-                version INT4 PRIMARY KEY,\
-                name VARCHAR(255),\
-                applied_on VARCHAR(255),\
-                checksum VARCHAR(255)\
-            );",
-        )
+        ensure_history_table()
     }
 }
 
@@ -723,30 +1409,12 @@ fn query_applied_migrations_from(
         escape_sql_ident(schema)
     );
     let rows = query_sql(&format!(
-        "SELECT version, name, applied_on, checksum FROM {} ORDER BY version",
+        "SELECT version, name, applied_on, checksum, checksum_algorithm, migration_type \
+         FROM {} ORDER BY version",
         fq_table
     ))
     .unwrap_or_default();
-
-    let mut result = Vec::new();
-    for row in rows {
-        if row.columns.len() < 4 {
-            continue;
-        }
-        let version: i32 = row.columns[0]
-            .parse()
-            .map_err(|_| format!("Invalid version in schema history: {}", row.columns[0]))?;
-        let checksum: u64 = row.columns[3]
-            .parse()
-            .map_err(|_| format!("Invalid checksum in schema history: {}", row.columns[3]))?;
-        result.push(AppliedMigration {
-            version,
-            name: row.columns[1].clone(),
-            applied_on: row.columns[2].clone(),
-            checksum,
-        });
-    }
-    Ok(result)
+    parse_applied_rows(rows)
 }
 
 fn insert_migration_record_in(
@@ -756,16 +1424,16 @@ fn insert_migration_record_in(
     is_postgres: bool,
 ) -> Result<(), Box<dyn Error>> {
     let applied_on = Utc::now().to_rfc3339();
-    let checksum_str = migration.checksum.to_string();
-
     if is_postgres {
         let sql = format!(
-            "INSERT INTO \"{schema}\".refinery_schema_history (version, name, applied_on, checksum) \
-             VALUES ({}, '{}', '{}', '{}')",
+            "INSERT INTO \"{schema}\".refinery_schema_history \
+             (version, name, applied_on, checksum, checksum_algorithm) \
+             VALUES ({}, '{}', '{}', '{}', '{}')",
             migration.version,
             escape_sql_str(&migration.name),
             escape_sql_str(&applied_on),
-            escape_sql_str(&checksum_str),
+            escape_sql_str(&migration.checksum),
+            migration.algorithm.as_str(),
             schema = escape_sql_ident(schema),
         );
         postgres_execute_sql(database, &sql)
@@ -793,27 +1461,25 @@ fn execute_migrations_in_schema(
     schema: &str,
     database: &str,
     is_postgres: bool,
+    placeholders: &HashMap<String, String>,
 ) -> Result<Vec<MigrationResult>, Box<dyn Error>> {
+    let pending_sql = resolve_pending_sql(discovered, pending_indices, placeholders)?;
     let mut results = Vec::new();
     let pending_set: std::collections::HashSet<usize> =
         pending_indices.iter().copied().collect();
 
     for (idx, migration) in discovered.iter().enumerate() {
         if !pending_set.contains(&idx) {
-            results.push(MigrationResult {
-                version: migration.version,
-                name: migration.name.clone(),
-                status: "skipped".to_string(),
-            });
+            results.push(MigrationResult::new(migration, "skipped"));
         }
     }
 
     if !is_postgres {
-        for &idx in pending_indices {
+        for (&idx, sql) in pending_indices.iter().zip(&pending_sql) {
             let migration = &discovered[idx];
 
             let insert_sql = build_insert_migration_sql(migration);
-            execute_statements_in_transaction(&[&migration.sql, &insert_sql])
+            let stats = execute_statements_in_transaction(&[sql, &insert_sql])
                 .map_err(|e| -> Box<dyn Error> {
                     format!(
                         "Migration V{}__{} failed: {}",
@@ -822,25 +1488,25 @@ fn execute_migrations_in_schema(
                     .into()
                 })?;
 
-            results.push(MigrationResult {
-                version: migration.version,
-                name: migration.name.clone(),
-                status: "applied".to_string(),
-            });
+            results.push(MigrationResult::new(migration, "applied").with_stats(stats[0]));
         }
     } else {
         // Postgres handles transactions internally via postgres_execute
-        for &idx in pending_indices {
+        for (&idx, sql) in pending_indices.iter().zip(&pending_sql) {
             let migration = &discovered[idx];
-            match execute_migration_sql(&migration.sql, database, is_postgres) {
+            let started = Instant::now();
+            let executed = execute_migration_sql(sql, database, is_postgres);
+            let duration_ms = elapsed_ms(started);
+            match executed {
                 Ok(_) => match insert_migration_record_in(migration, schema, database, is_postgres)
                 {
                     Ok(_) => {
-                        results.push(MigrationResult {
-                            version: migration.version,
-                            name: migration.name.clone(),
-                            status: "applied".to_string(),
-                        });
+                        // postgres_execute reports no row count.
+                        let stats = StatementStats {
+                            duration_ms,
+                            rows_affected: None,
+                        };
+                        results.push(MigrationResult::new(migration, "applied").with_stats(stats));
                     }
                     Err(e) => {
                         return Err(format!(
@@ -871,6 +1537,9 @@ struct MigrateSchemaBindData {
     path: String,
     schema: String,
     database: String,
+    algorithm: ChecksumAlgorithm,
+    allow_out_of_order: bool,
+    placeholders: HashMap<String, String>,
 }
 
 #[repr(C)]
@@ -886,17 +1555,21 @@ impl VTab for MigrateSchemaVTab {
     type BindData = MigrateSchemaBindData;
 
     fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn Error>> {
-        bind.add_result_column("version", LogicalTypeHandle::from(LogicalTypeId::Integer));
-        bind.add_result_column("name", LogicalTypeHandle::from(LogicalTypeId::Varchar));
-        bind.add_result_column("status", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        add_run_result_columns(bind);
 
         let path = bind.get_parameter(0).to_string();
         let schema = bind.get_parameter(1).to_string();
         let database = bind.get_parameter(2).to_string();
+        let algorithm = checksum_parameter(bind)?;
+        let allow_out_of_order = out_of_order_parameter(bind);
+        let placeholders = placeholders_parameter(bind)?;
         Ok(MigrateSchemaBindData {
             path,
             schema,
             database,
+            algorithm,
+            allow_out_of_order,
+            placeholders,
         })
     }
 
@@ -905,11 +1578,14 @@ impl VTab for MigrateSchemaVTab {
         if bind_data.is_null() {
             return Err("Bind data is null".into());
         }
-        let (path, schema, database) = unsafe {
+        let (path, schema, database, algorithm, allow_out_of_order, placeholders) = unsafe {
             (
                 (*bind_data).path.clone(),
                 (*bind_data).schema.clone(),
                 (*bind_data).database.clone(),
+                (*bind_data).algorithm,
+                (*bind_data).allow_out_of_order,
+                (*bind_data).placeholders.clone(),
             )
         };
 
@@ -917,11 +1593,18 @@ impl VTab for MigrateSchemaVTab {
         setup_schema_context(&schema, &database, is_pg)?;
 
         let run = (|| -> Result<Vec<MigrationResult>, Box<dyn Error>> {
-            let discovered = discover_migrations(&path)?;
+            let discovered = discover_migrations(&path, algorithm)?;
             ensure_history_table_in(&schema, &database, is_pg)?;
             let applied = query_applied_migrations_from(&schema, &database)?;
-            let pending_indices = verify_migrations(&discovered, &applied)?;
-            execute_migrations_in_schema(&discovered, &pending_indices, &schema, &database, is_pg)
+            let pending_indices = verify_migrations(&discovered, &applied, allow_out_of_order)?;
+            execute_migrations_in_schema(
+                &discovered,
+                &pending_indices,
+                &schema,
+                &database,
+                is_pg,
+                &placeholders,
+            )
         })();
 
         teardown_schema_context(is_pg)?;
@@ -946,18 +1629,7 @@ impl VTab for MigrateSchemaVTab {
             return Ok(());
         }
 
-        let result = &init_data.results[current_index];
-
-        let mut version_vector = output.flat_vector(0);
-        version_vector.as_mut_slice::<i32>()[0] = result.version;
-
-        let name_vector = output.flat_vector(1);
-        name_vector.insert(0, result.name.as_str());
-
-        let status_vector = output.flat_vector(2);
-        status_vector.insert(0, result.status.as_str());
-
-        output.set_len(1);
+        write_run_result(output, &init_data.results[current_index]);
         Ok(())
     }
 
@@ -968,6 +1640,9 @@ impl VTab for MigrateSchemaVTab {
             LogicalTypeHandle::from(LogicalTypeId::Varchar),
         ])
     }
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        migrate_named_parameters()
+    }
 }
 
 
@@ -976,6 +1651,7 @@ struct MigrationStatusSchemaBindData {
     path: String,
     schema: String,
     database: String,
+    algorithm: ChecksumAlgorithm,
 }
 
 #[repr(C)]
@@ -999,14 +1675,20 @@ impl VTab for MigrationStatusSchemaVTab {
             LogicalTypeHandle::from(LogicalTypeId::Varchar),
         );
         bind.add_result_column("checksum", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column(
+            "checksum_algorithm",
+            LogicalTypeHandle::from(LogicalTypeId::Varchar),
+        );
 
         let path = bind.get_parameter(0).to_string();
         let schema = bind.get_parameter(1).to_string();
         let database = bind.get_parameter(2).to_string();
+        let algorithm = checksum_parameter(bind)?;
         Ok(MigrationStatusSchemaBindData {
             path,
             schema,
             database,
+            algorithm,
         })
     }
 
@@ -1015,11 +1697,12 @@ impl VTab for MigrationStatusSchemaVTab {
         if bind_data.is_null() {
             return Err("Bind data is null".into());
         }
-        let (path, schema, database) = unsafe {
+        let (path, schema, database, algorithm) = unsafe {
             (
                 (*bind_data).path.clone(),
                 (*bind_data).schema.clone(),
                 (*bind_data).database.clone(),
+                (*bind_data).algorithm,
             )
         };
 
@@ -1027,35 +1710,10 @@ impl VTab for MigrationStatusSchemaVTab {
         setup_schema_context(&schema, &database, is_pg)?;
 
         let run = (|| -> Result<Vec<MigrationStatusResult>, Box<dyn Error>> {
-            let discovered = discover_migrations(&path)?;
+            let discovered = discover_migrations(&path, algorithm)?;
             ensure_history_table_in(&schema, &database, is_pg)?;
             let applied = query_applied_migrations_from(&schema, &database)?;
-
-            let applied_map: HashMap<i32, &AppliedMigration> =
-                applied.iter().map(|a| (a.version, a)).collect();
-
-            let mut results = Vec::new();
-            for migration in &discovered {
-                let (status, applied_on) = match applied_map.get(&migration.version) {
-                    Some(am) => {
-                        if am.checksum == migration.checksum {
-                            ("applied".to_string(), am.applied_on.clone())
-                        } else {
-                            ("checksum_mismatch".to_string(), am.applied_on.clone())
-                        }
-                    }
-                    None => ("pending".to_string(), String::new()),
-                };
-
-                results.push(MigrationStatusResult {
-                    version: migration.version,
-                    name: migration.name.clone(),
-                    status,
-                    applied_on,
-                    checksum: migration.checksum.to_string(),
-                });
-            }
-            Ok(results)
+            Ok(migration_statuses(&discovered, &applied))
         })();
 
         teardown_schema_context(is_pg)?;
@@ -1097,6 +1755,9 @@ impl VTab for MigrationStatusSchemaVTab {
         let checksum_vector = output.flat_vector(4);
         checksum_vector.insert(0, result.checksum.as_str());
 
+        let algorithm_vector = output.flat_vector(5);
+        algorithm_vector.insert(0, result.checksum_algorithm.as_str());
+
         output.set_len(1);
         Ok(())
     }
@@ -1108,12 +1769,17 @@ impl VTab for MigrationStatusSchemaVTab {
             LogicalTypeHandle::from(LogicalTypeId::Varchar),
         ])
     }
+    fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+        checksum_named_parameters()
+    }
 }
 
 
 unsafe fn extension_entrypoint(connection: Connection) -> Result<(), Box<dyn Error>> {
     connection.register_table_function::<MigrateVTab>("trex_migration_run")?;
+    connection.register_scalar_function::<MigrateJsonScalar>("trex_migration_run_json")?;
     connection.register_table_function::<MigrationStatusVTab>("trex_migration_status")?;
+    connection.register_table_function::<BaselineVTab>("trex_migration_baseline")?;
     connection.register_table_function::<MigrateSchemaVTab>("trex_migration_run_schema")?;
     connection
         .register_table_function::<MigrationStatusSchemaVTab>("trex_migration_status_schema")?;
@@ -1215,3 +1881,298 @@ mod arrow_value_tests {
         assert_eq!(arrow_value_to_string(&f, 0), "1.5");
     }
 }
+
+#[cfg(test)]
+mod checksum_tests {
+    use super::*;
+
+    fn applied(checksum: &str, algorithm: ChecksumAlgorithm) -> AppliedMigration {
+        AppliedMigration {
+            version: 1,
+            name: "create_users".to_string(),
+            applied_on: String::new(),
+            checksum: checksum.to_string(),
+            algorithm,
+            baseline: false,
+        }
+    }
+
+    fn migration(sql: &str, algorithm: ChecksumAlgorithm) -> MigrationFile {
+        MigrationFile {
+            version: 1,
+            name: "create_users".to_string(),
+            sql: sql.to_string(),
+            checksum: compute_checksum(algorithm, "create_users", 1, sql),
+            algorithm,
+        }
+    }
+
+    #[test]
+    fn parse_algorithm_names() {
+        assert_eq!(ChecksumAlgorithm::parse("CRC32").unwrap(), ChecksumAlgorithm::Crc32);
+        assert_eq!(ChecksumAlgorithm::parse("sha-256").unwrap(), ChecksumAlgorithm::Sha256);
+        assert_eq!(ChecksumAlgorithm::from_history("").unwrap(), ChecksumAlgorithm::SipHash);
+        assert!(ChecksumAlgorithm::parse("md5").is_err());
+    }
+
+    #[test]
+    fn crc32_ignores_line_endings_and_bom() {
+        // CRC32("abc") = 0x352441C2, stored by Flyway as a signed integer.
+        assert_eq!(flyway_crc32("abc"), 891568578);
+        assert_eq!(flyway_crc32("\u{feff}a\r\nb\nc\n"), 891568578);
+        assert_eq!(flyway_crc32(""), 0);
+    }
+
+    #[test]
+    fn sha256_is_hex_digest_of_sql() {
+        assert_eq!(
+            compute_checksum(ChecksumAlgorithm::Sha256, "ignored", 7, "abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn verification_uses_recorded_algorithm() {
+        let sql = "CREATE TABLE users (id INT);\n";
+        let crc = compute_checksum(ChecksumAlgorithm::Crc32, "create_users", 1, sql);
+        let sip = compute_checksum(ChecksumAlgorithm::SipHash, "create_users", 1, sql);
+
+        // Mixed history: each row is checked with its own algorithm,
+        // whatever this run selects.
+        let file = migration(sql, ChecksumAlgorithm::SipHash);
+        assert!(file.matches(&applied(&crc, ChecksumAlgorithm::Crc32)));
+        assert!(file.matches(&applied(&sip, ChecksumAlgorithm::SipHash)));
+        assert!(!file.matches(&applied(&sip, ChecksumAlgorithm::Crc32)));
+
+        let tampered = migration("DROP TABLE users;\n", ChecksumAlgorithm::Crc32);
+        assert!(!tampered.matches(&applied(&crc, ChecksumAlgorithm::Crc32)));
+    }
+
+    #[test]
+    fn statuses_report_recorded_algorithm() {
+        let sql = "SELECT 1;";
+        let crc = compute_checksum(ChecksumAlgorithm::Crc32, "create_users", 1, sql);
+        let mut pending = migration(sql, ChecksumAlgorithm::Sha256);
+        pending.version = 2;
+        let discovered = vec![migration(sql, ChecksumAlgorithm::Sha256), pending];
+        let statuses = migration_statuses(&discovered, &[applied(&crc, ChecksumAlgorithm::Crc32)]);
+        assert_eq!(statuses[0].status, "applied");
+        assert_eq!(statuses[0].checksum, crc);
+        assert_eq!(statuses[0].checksum_algorithm, "crc32");
+        assert_eq!(statuses[1].status, "pending");
+        assert_eq!(statuses[1].checksum_algorithm, "sha256");
+    }
+}
+
+#[cfg(test)]
+mod out_of_order_tests {
+    use super::*;
+
+    fn file(version: i32) -> MigrationFile {
+        let name = format!("step_{}", version);
+        let sql = format!("SELECT {};", version);
+        MigrationFile {
+            checksum: compute_checksum(ChecksumAlgorithm::SipHash, &name, version, &sql),
+            version,
+            name,
+            sql,
+            algorithm: ChecksumAlgorithm::SipHash,
+        }
+    }
+
+    fn applied(file: &MigrationFile) -> AppliedMigration {
+        AppliedMigration {
+            version: file.version,
+            name: file.name.clone(),
+            applied_on: String::new(),
+            checksum: file.checksum.clone(),
+            algorithm: file.algorithm,
+            baseline: false,
+        }
+    }
+
+    #[test]
+    fn newer_pending_migrations_are_in_order() {
+        let discovered = vec![file(1), file(2), file(3)];
+        let history = vec![applied(&discovered[0])];
+        assert_eq!(verify_migrations(&discovered, &history, false).unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn older_pending_migration_needs_flag() {
+        let discovered = vec![file(1), file(2), file(3)];
+        let history = vec![applied(&discovered[0]), applied(&discovered[2])];
+
+        let err = verify_migrations(&discovered, &history, false).unwrap_err();
+        assert!(err.to_string().contains("V2__step_2 is older than the latest applied version 3"));
+
+        assert_eq!(verify_migrations(&discovered, &history, true).unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn baseline_refuses_existing_history() {
+        let discovered = vec![file(1), file(2)];
+        let history = vec![applied(&discovered[0])];
+        let err = baseline_migrations(&discovered, &history, 2).err().unwrap();
+        assert!(err.to_string().contains("already has 1 entries"));
+
+        let err = baseline_migrations(&[file(5)], &[], 2).err().unwrap();
+        assert!(err.to_string().contains("No migrations at or below version 2"));
+    }
+
+    #[test]
+    fn baselined_versions_have_their_own_status() {
+        let discovered = vec![file(1), file(2)];
+        let mut baselined = applied(&discovered[0]);
+        baselined.baseline = true;
+        let statuses = migration_statuses(&discovered, &[baselined]);
+        assert_eq!(statuses[0].status, "baseline");
+        assert_eq!(statuses[1].status, "pending");
+        // Versions above the baseline run in order afterwards.
+        let history = [applied(&discovered[0])];
+        assert_eq!(verify_migrations(&discovered, &history, false).unwrap(), vec![1]);
+    }
+}
+
+#[cfg(test)]
+mod placeholder_tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn substitutes_defined_placeholders() {
+        let map = values(&[("schema", "tenant_a"), ("ts", "fast")]);
+        assert_eq!(
+            substitute_placeholders("CREATE TABLE ${schema}.t() TABLESPACE ${ts};", &map).unwrap(),
+            "CREATE TABLE tenant_a.t() TABLESPACE fast;"
+        );
+        assert_eq!(substitute_placeholders("SELECT '${open';", &map).unwrap(), "SELECT '${open';");
+    }
+
+    #[test]
+    fn lists_each_missing_placeholder_once() {
+        let map = values(&[("schema", "tenant_a")]);
+        let missing = substitute_placeholders("${a} ${schema} ${b} ${a}", &map).unwrap_err();
+        assert_eq!(missing, vec!["a".to_string(), "b".to_string()]);
+    }
+
+    #[test]
+    fn checksum_ignores_substitution() {
+        let sql = "CREATE SCHEMA ${schema};";
+        let file = MigrationFile {
+            version: 1,
+            name: "init".to_string(),
+            sql: sql.to_string(),
+            checksum: compute_checksum(ChecksumAlgorithm::SipHash, "init", 1, sql),
+            algorithm: ChecksumAlgorithm::SipHash,
+        };
+        let resolved = file.resolved_sql(&values(&[("schema", "prod")])).unwrap();
+        assert_eq!(resolved, "CREATE SCHEMA prod;");
+        assert_eq!(file.checksum, compute_checksum(ChecksumAlgorithm::SipHash, "init", 1, sql));
+
+        let err = resolve_pending_sql(&[file], &[0], &HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("V1__init references undefined placeholders: schema"));
+    }
+}
+
+#[cfg(test)]
+mod json_report_tests {
+    use super::*;
+
+    fn file(version: i32, name: &str) -> MigrationFile {
+        let sql = format!("SELECT {};", version);
+        MigrationFile {
+            checksum: compute_checksum(ChecksumAlgorithm::SipHash, name, version, &sql),
+            version,
+            name: name.to_string(),
+            sql,
+            algorithm: ChecksumAlgorithm::SipHash,
+        }
+    }
+
+    fn report_json(run: Result<Vec<MigrationResult>, Box<dyn Error>>) -> serde_json::Value {
+        let report = MigrationRunReport::from_run(run, Instant::now());
+        serde_json::from_str(&report.to_json()).unwrap()
+    }
+
+    #[test]
+    fn successful_run_counts_each_status() {
+        let stats = StatementStats {
+            duration_ms: 4.5,
+            rows_affected: Some(3),
+        };
+        let results = vec![
+            MigrationResult::new(&file(1, "init"), "skipped"),
+            MigrationResult::new(&file(2, "add_email"), "applied").with_stats(stats),
+        ];
+        let json = report_json(Ok(results));
+        assert_eq!(json["success"], true);
+        assert_eq!(json["applied"], 1);
+        assert_eq!(json["skipped"], 1);
+        assert!(json["failed_version"].is_null());
+        assert!(json["error"].is_null());
+        assert_eq!(json["results"][0]["status"], "skipped");
+        assert!(json["results"][0].get("duration_ms").is_none());
+        assert_eq!(json["results"][1]["duration_ms"], 4.5);
+        assert_eq!(json["results"][1]["rows_affected"], 3);
+    }
+
+    #[test]
+    fn failed_migration_is_reported_with_earlier_results() {
+        let earlier = vec![MigrationResult::new(&file(1, "init"), "applied")];
+        let failure =
+            MigrationFailure::at(earlier, &file(2, "bad"), Some(1.5), "syntax error".to_string());
+        assert_eq!(failure.to_string(), "Migration V2__bad failed: syntax error");
+
+        let json = report_json(Err(failure.into()));
+        assert_eq!(json["success"], false);
+        assert_eq!(json["applied"], 1);
+        assert_eq!(json["failed_version"], 2);
+        assert_eq!(json["error"], "Migration V2__bad failed: syntax error");
+        assert_eq!(json["results"][1]["status"], "failed");
+        assert_eq!(json["results"][1]["error"], "syntax error");
+        assert_eq!(json["results"][1]["duration_ms"], 1.5);
+    }
+
+    #[test]
+    fn errors_before_any_migration_still_produce_a_report() {
+        let json = report_json(Err("Directory not found: /nope".into()));
+        assert_eq!(json["success"], false);
+        assert_eq!(json["error"], "Directory not found: /nope");
+        assert!(json["failed_version"].is_null());
+        assert_eq!(json["results"], serde_json::json!([]));
+    }
+}
+
+#[cfg(test)]
+mod statement_stats_tests {
+    use super::*;
+    use std::sync::Arc;
+    use trex_pool_client::arrow_array::{Int64Array, RecordBatch, StringArray};
+    use trex_pool_client::arrow_schema::{Field, Schema};
+
+    fn stats_of(name: &str, column: Arc<dyn trex_pool_client::arrow_array::Array>) -> StatementStats {
+        let schema = Arc::new(Schema::new(vec![Field::new(name, column.data_type().clone(), true)]));
+        let batch = RecordBatch::try_new(schema.clone(), vec![column]).unwrap();
+        StatementStats::of(Instant::now(), &schema, &[batch])
+    }
+
+    #[test]
+    fn rows_affected_comes_from_the_count_column() {
+        let stats = stats_of("Count", Arc::new(Int64Array::from(vec![42])));
+        assert_eq!(stats.rows_affected, Some(42));
+        assert!(stats.duration_ms >= 0.0);
+    }
+
+    #[test]
+    fn other_results_have_no_row_count() {
+        assert_eq!(stats_of("name", Arc::new(StringArray::from(vec!["a"]))).rows_affected, None);
+        assert_eq!(stats_of("id", Arc::new(Int64Array::from(vec![7]))).rows_affected, None);
+
+        let empty = Schema::new(Vec::<Field>::new());
+        assert_eq!(StatementStats::of(Instant::now(), &empty, &[]).rows_affected, None);
+    }
+}
@@ -0,0 +1 @@
+CREATE TABLE payments(id INTEGER);
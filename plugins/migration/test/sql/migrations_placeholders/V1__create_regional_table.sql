@@ -0,0 +1 @@
+CREATE TABLE ${table_name}(id INTEGER, region VARCHAR DEFAULT '${region}');
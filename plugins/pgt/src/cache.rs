@@ -0,0 +1,124 @@
+//! Bounded parse cache that several [`crate::SqlTransformer`]s can share.
+//!
+//! Every transformer parses its input as PostgreSQL, whatever its target
+//! dialect, so parsed statements depend only on the SQL text and one cache
+//! can serve transformers of any dialect and configuration. Wrap it in an
+//! [`Arc`] and pass it to [`crate::SqlTransformer::with_shared_cache`] or
+//! [`crate::SqlTransformerBuilder::with_shared_cache`]; a server that builds a
+//! transformer per request then parses each distinct query once.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use sqlparser::ast::Statement;
+
+/// Parsed statements keyed by SQL text, holding at most `capacity` entries
+/// and evicting the least recently used one to make room.
+pub struct ParseCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    /// SQL text to its statements and the tick it was last used at.
+    entries: HashMap<String, (Vec<Statement>, u64)>,
+    /// Tick of last use to SQL text; the first entry is the next evicted.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl LruState {
+    fn touch(&mut self, sql: &str) -> Option<&Vec<Statement>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (statements, last_used) = self.entries.get_mut(sql)?;
+        let key = self.recency.remove(last_used)?;
+        *last_used = tick;
+        self.recency.insert(tick, key);
+        Some(statements)
+    }
+}
+
+impl ParseCache {
+    /// Capacity of [`ParseCache::global`] and of the cache each transformer
+    /// gets by default.
+    pub const DEFAULT_CAPACITY: usize = 1000;
+
+    /// An empty cache; a `capacity` of 0 caches nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    /// The process-wide cache, created with [`ParseCache::DEFAULT_CAPACITY`]
+    /// on first use.
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<ParseCache>> = OnceLock::new();
+        Arc::clone(GLOBAL.get_or_init(|| Arc::new(Self::new(Self::DEFAULT_CAPACITY))))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().map(|s| s.entries.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            *state = LruState::default();
+        }
+    }
+
+    /// The statements cached for `sql`, marking them most recently used.
+    pub(crate) fn get(&self, sql: &str) -> Option<Vec<Statement>> {
+        let mut state = self.state.lock().ok()?;
+        state.touch(sql).cloned()
+    }
+
+    pub(crate) fn insert(&self, sql: &str, statements: Vec<Statement>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        if state.touch(sql).is_some() {
+            // Another transformer parsed it first; keep its copy.
+            return;
+        }
+        while state.entries.len() >= self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+        let tick = state.tick;
+        state.entries.insert(sql.to_string(), (statements, tick));
+        state.recency.insert(tick, sql.to_string());
+    }
+}
+
+impl Default for ParseCache {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl fmt::Debug for ParseCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParseCache")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
@@ -32,6 +32,14 @@ pub struct DataTypeConfig {
     pub preserve_precision: bool,
     pub custom_mappings: HashMap<String, String>,
     pub handle_arrays: ArrayHandlingStrategy,
+    /// Write HANA booleans as TINYINT 1/0, for schemas that model flags as
+    /// integers. `IS TRUE`/`IS FALSE` become `= 1`/`= 0`.
+    #[serde(default)]
+    pub boolean_as_integer: bool,
+    /// Give HANA SERIAL columns a separate sequence instead of an IDENTITY
+    /// clause. HANA defaults cannot call a sequence, so inserts must.
+    #[serde(default)]
+    pub serial_as_sequence: bool,
 }
 
 impl Default for DataTypeConfig {
@@ -40,6 +48,8 @@ impl Default for DataTypeConfig {
             preserve_precision: true,
             custom_mappings: HashMap::new(),
             handle_arrays: ArrayHandlingStrategy::AsJson,
+            boolean_as_integer: false,
+            serial_as_sequence: false,
         }
     }
 }
@@ -109,6 +119,14 @@ pub struct FormattingConfig {
     pub max_line_length: usize,
     pub capitalize_keywords: bool,
     pub preserve_comments: bool,
+    /// Ends every generated statement, e.g. `"\n/"` for scripts whose
+    /// client splits on a line of its own.
+    #[serde(default = "default_statement_terminator")]
+    pub statement_terminator: String,
+}
+
+fn default_statement_terminator() -> String {
+    ";".to_string()
 }
 
 impl Default for FormattingConfig {
@@ -117,7 +135,8 @@ impl Default for FormattingConfig {
             indent_size: 2,
             max_line_length: 120,
             capitalize_keywords: true,
-            preserve_comments: true,
+            preserve_comments: false,
+            statement_terminator: default_statement_terminator(),
         }
     }
 }
@@ -189,6 +208,10 @@ impl TransformationConfig {
             config.data_types.preserve_precision = val.parse().unwrap_or(true);
         }
 
+        if let Ok(val) = std::env::var("PGT_BOOLEAN_AS_INTEGER") {
+            config.data_types.boolean_as_integer = val.parse().unwrap_or(false);
+        }
+
         if let Ok(val) = std::env::var("PGT_PRESERVE_CASE") {
             config.functions.preserve_case = val.parse().unwrap_or(false);
         }
@@ -201,9 +224,32 @@ impl TransformationConfig {
             config.rules.validate_hana_compatibility = val.parse().unwrap_or(true);
         }
 
+        // PGT_FUNCTION_MAPPINGS="my_pg_fn=my_hana_fn,other_fn=OTHER"
+        if let Ok(val) = std::env::var("PGT_FUNCTION_MAPPINGS") {
+            config
+                .functions
+                .custom_mappings
+                .extend(Self::parse_mapping_list(&val));
+        }
+
         config
     }
 
+    fn parse_mapping_list(list: &str) -> HashMap<String, String> {
+        list.split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .filter_map(|entry| match entry.split_once('=') {
+                Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => {
+                    Some((from.trim().to_string(), to.trim().to_string()))
+                }
+                _ => {
+                    log::warn!("Ignoring malformed function mapping '{}'", entry);
+                    None
+                }
+            })
+            .collect()
+    }
+
     pub fn merge(configs: Vec<Self>) -> TransformationResult<Self> {
         let mut result = Self::default();
 
@@ -1,9 +1,21 @@
-use crate::dialects::{Dialect, DialectTransformationEngine};
+use crate::dialects::{Dialect, DialectCapabilities, DialectTransformationEngine};
 use crate::config::TransformationConfig;
 use crate::error::TransformationResult;
 use regex::Regex;
 use sqlparser::ast::Statement;
 
+pub const CAPABILITIES: DialectCapabilities = DialectCapabilities {
+    supports_returning: true,
+    supports_arrays: true,
+    supports_recursive_cte: true,
+    supports_json: true,
+    supports_boolean_type: true,
+    supports_interval_type: true,
+    supports_ilike: true,
+    supports_limit_offset: true,
+    identifier_quote_char: '"',
+};
+
 pub struct DuckDbTransformationEngine {
     _config: TransformationConfig,
     transformation_rules: Vec<(Regex, String)>,
@@ -97,4 +109,8 @@ impl DialectTransformationEngine for DuckDbTransformationEngine {
     fn name(&self) -> &'static str {
         "trexsql"
     }
+
+    fn capabilities(&self) -> DialectCapabilities {
+        CAPABILITIES
+    }
 }
@@ -1,12 +1,21 @@
 use super::Transformer;
 use crate::config::TransformationConfig;
-use crate::error::TransformationResult;
-use sqlparser::ast::{ColumnDef, DataType, Statement};
+use crate::error::{TransformationResult, TransformationWarning};
+use sqlparser::ast::{
+    ColumnDef, ColumnOption, DataType, Expr, Ident, ObjectName, ObjectNamePart, Statement, Value,
+};
 use std::collections::HashMap;
+use std::sync::Mutex;
 
 pub struct DataTypeTransformer {
     mappings: HashMap<String, String>,
     preserve_precision: bool,
+    boolean_as_integer: bool,
+    serial_as_sequence: bool,
+    warnings: Mutex<Vec<TransformationWarning>>,
+    /// CREATE SEQUENCE statements for SERIAL columns, to run before the
+    /// table that uses them.
+    sequences: Mutex<Vec<Statement>>,
 }
 
 impl DataTypeTransformer {
@@ -20,6 +29,17 @@ impl DataTypeTransformer {
         Self {
             mappings,
             preserve_precision: config.data_types.preserve_precision,
+            boolean_as_integer: config.data_types.boolean_as_integer,
+            serial_as_sequence: config.data_types.serial_as_sequence,
+            warnings: Mutex::new(Vec::new()),
+            sequences: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn warn(&self, warning: TransformationWarning) {
+        log::warn!("{}", warning.message);
+        if let Ok(mut warnings) = self.warnings.lock() {
+            warnings.push(warning);
         }
     }
 
@@ -53,11 +73,19 @@ impl DataTypeTransformer {
                 *data_type = DataType::Clob(None);
                 changed = true;
             }
-            DataType::Boolean => {}
+            DataType::Boolean if self.boolean_as_integer => {
+                *data_type = DataType::TinyInt(None);
+                changed = true;
+            }
             DataType::Integer(display) => {}
             DataType::BigInt(display) => {}
             DataType::Timestamp(precision, timezone) => {
-                if *timezone == sqlparser::ast::TimezoneInfo::WithTimeZone {
+                // `timestamptz` parses as `Tz`, `timestamp with time zone`
+                // as `WithTimeZone`; HANA has neither.
+                if matches!(
+                    timezone,
+                    sqlparser::ast::TimezoneInfo::WithTimeZone | sqlparser::ast::TimezoneInfo::Tz
+                ) {
                     *data_type =
                         DataType::Timestamp(precision.clone(), sqlparser::ast::TimezoneInfo::None);
                     changed = true;
@@ -83,6 +111,20 @@ impl Transformer for DataTypeTransformer {
         10
     }
 
+    fn collect_warnings(&self) -> Vec<TransformationWarning> {
+        self.warnings
+            .lock()
+            .map(|mut warnings| std::mem::take(&mut *warnings))
+            .unwrap_or_default()
+    }
+
+    fn take_preceding_statements(&self) -> Vec<Statement> {
+        self.sequences
+            .lock()
+            .map(|mut sequences| std::mem::take(&mut *sequences))
+            .unwrap_or_default()
+    }
+
     fn supports_statement_type(&self, stmt: &Statement) -> bool {
         matches!(
             stmt,
@@ -98,16 +140,18 @@ impl Transformer for DataTypeTransformer {
         match stmt {
             Statement::CreateTable(create_table) => {
                 for column in &mut create_table.columns {
-                    if self.transform_column_data_type(column)? {
+                    if self.transform_column_data_type(&create_table.name, column)? {
                         changed = true;
                     }
                 }
             }
-            Statement::AlterTable { operations, .. } => {
+            Statement::AlterTable {
+                name, operations, ..
+            } => {
                 for operation in operations {
                     match operation {
                         sqlparser::ast::AlterTableOperation::AddColumn { column_def, .. } => {
-                            if self.transform_column_data_type(column_def)? {
+                            if self.transform_column_data_type(name, column_def)? {
                                 changed = true;
                             }
                         }
@@ -123,71 +167,111 @@ impl Transformer for DataTypeTransformer {
 }
 
 impl DataTypeTransformer {
-    fn transform_column_data_type(&self, column: &mut ColumnDef) -> TransformationResult<bool> {
+    fn transform_column_data_type(
+        &self,
+        table: &ObjectName,
+        column: &mut ColumnDef,
+    ) -> TransformationResult<bool> {
         let mut changed = false;
 
-        if self.transform_data_type(&mut column.data_type)? {
+        let was_boolean = matches!(column.data_type, DataType::Boolean);
+        let serial_type = serial_column_type(&column.data_type);
+        if let Some(integer_type) = &serial_type {
+            column.data_type = integer_type.clone();
+            changed = true;
+        } else if self.transform_data_type(&mut column.data_type)? {
             changed = true;
         }
 
-        let mut is_serial_column = false;
-        let mut is_bigserial_column = false;
-
-        for option in &column.options {
-            if let sqlparser::ast::ColumnOption::Default(expr) = &option.option {
-                if let sqlparser::ast::Expr::Function(func) = expr {
-                    if func.name.to_string().to_lowercase() == "nextval" {
-                        if matches!(column.data_type, DataType::Integer(_)) {
-                            is_serial_column = true;
-                        } else if matches!(column.data_type, DataType::BigInt(_)) {
-                            is_bigserial_column = true;
-                        }
-                        break;
+        // A TINYINT column cannot default to TRUE.
+        if was_boolean && self.boolean_as_integer {
+            for option in &mut column.options {
+                if let ColumnOption::Default(Expr::Value(value)) = &mut option.option {
+                    if let Value::Boolean(b) = value.value {
+                        value.value = Value::Number(if b { "1" } else { "0" }.to_string(), false);
                     }
                 }
             }
         }
 
-        if is_serial_column {
-            column.options.retain(|opt| {
-                !matches!(opt.option, sqlparser::ast::ColumnOption::Default(ref expr)
-                    if matches!(expr, sqlparser::ast::Expr::Function(ref func)
-                        if func.name.to_string().to_lowercase() == "nextval"))
-            });
+        let defaults_to_nextval = column.options.iter().any(|opt| {
+            matches!(&opt.option, ColumnOption::Default(Expr::Function(func))
+                if func.name.to_string().to_lowercase() == "nextval")
+        });
+        let integer_column = matches!(column.data_type, DataType::Integer(_) | DataType::BigInt(_));
 
-            column.options.push(sqlparser::ast::ColumnOptionDef {
-                name: None,
-                option: sqlparser::ast::ColumnOption::Generated {
-                    generated_as: sqlparser::ast::GeneratedAs::ByDefault,
-                    sequence_options: None,
-                    generation_expr: None,
-                    generation_expr_mode: None,
-                    generated_keyword: true,
-                },
-            });
-            changed = true;
-        } else if is_bigserial_column {
+        if serial_type.is_some() && self.serial_as_sequence {
+            self.add_serial_sequence(table, column);
+        } else if serial_type.is_some() || (defaults_to_nextval && integer_column) {
             column.options.retain(|opt| {
-                !matches!(opt.option, sqlparser::ast::ColumnOption::Default(ref expr)
-                    if matches!(expr, sqlparser::ast::Expr::Function(ref func)
-                        if func.name.to_string().to_lowercase() == "nextval"))
+                !matches!(&opt.option, ColumnOption::Default(Expr::Function(func))
+                    if func.name.to_string().to_lowercase() == "nextval")
             });
-
-            column.options.push(sqlparser::ast::ColumnOptionDef {
-                name: None,
-                option: sqlparser::ast::ColumnOption::Generated {
-                    generated_as: sqlparser::ast::GeneratedAs::ByDefault,
-                    sequence_options: None,
-                    generation_expr: None,
-                    generation_expr_mode: None,
-                    generated_keyword: true,
+            // HANA wants the identity clause ahead of constraints.
+            column.options.insert(
+                0,
+                sqlparser::ast::ColumnOptionDef {
+                    name: None,
+                    option: ColumnOption::Generated {
+                        generated_as: sqlparser::ast::GeneratedAs::ByDefault,
+                        sequence_options: None,
+                        generation_expr: None,
+                        generation_expr_mode: None,
+                        generated_keyword: true,
+                    },
                 },
-            });
+            );
             changed = true;
         }
 
         Ok(changed)
     }
+
+    /// Queue `CREATE SEQUENCE <table>_<column>_seq`, named as PostgreSQL
+    /// names the sequence behind a SERIAL. HANA column defaults cannot call a
+    /// sequence, so inserts have to supply `NEXTVAL` themselves.
+    fn add_serial_sequence(&self, table: &ObjectName, column: &ColumnDef) {
+        let mut parts = table.0.clone();
+        let table_name = match parts.pop() {
+            Some(ObjectNamePart::Identifier(ident)) => ident.value,
+            Some(part) => part.to_string(),
+            None => return,
+        };
+        parts.push(ObjectNamePart::Identifier(Ident::new(format!(
+            "{}_{}_seq",
+            table_name, column.name.value
+        ))));
+        let sequence = ObjectName(parts);
+
+        self.warn(TransformationWarning::medium(&format!(
+            "Column '{}' draws from sequence {}; HANA column defaults cannot use a sequence, \
+             so inserts must set it to {}.NEXTVAL",
+            column.name.value, sequence, sequence
+        )));
+        if let Ok(mut sequences) = self.sequences.lock() {
+            sequences.push(Statement::CreateSequence {
+                temporary: false,
+                if_not_exists: false,
+                name: sequence,
+                data_type: None,
+                sequence_options: Vec::new(),
+                owned_by: None,
+            });
+        }
+    }
+}
+
+/// The integer type behind a SERIAL pseudo-type, if `data_type` is one.
+fn serial_column_type(data_type: &DataType) -> Option<DataType> {
+    let DataType::Custom(name, _) = data_type else {
+        return None;
+    };
+    match name.to_string().to_uppercase().as_str() {
+        "SERIAL" | "SERIAL4" => Some(DataType::Integer(None)),
+        "BIGSERIAL" | "SERIAL8" => Some(DataType::BigInt(None)),
+        "SMALLSERIAL" | "SERIAL2" => Some(DataType::SmallInt(None)),
+        _ => None,
+    }
 }
 
 fn get_default_mappings() -> HashMap<String, String> {
@@ -1,8 +1,10 @@
 use super::Transformer;
 use crate::config::TransformationConfig;
-use crate::error::TransformationResult;
+use crate::error::{TransformationError, TransformationResult};
 use sqlparser::ast::{
-    BinaryOperator, CastKind, Delete, Expr, Function, Ident, Statement, UnaryOperator,
+    BinaryOperator, CastKind, Delete, Expr, Function, FunctionArg,
+    FunctionArgExpr, FunctionArgumentList, FunctionArguments, Ident, Interval, ObjectName,
+    ObjectNamePart, Statement, UnaryOperator, Value,
 };
 
 pub struct ExpressionTransformer {
@@ -19,7 +21,23 @@ impl ExpressionTransformer {
     fn transform_expression(&self, expr: &mut Expr) -> TransformationResult<bool> {
         let mut changed = false;
 
+        if let Some(new_expr) = self.build_hana_interval_arithmetic(expr)? {
+            *expr = new_expr;
+            return Ok(true);
+        }
+
+        if let Some(new_expr) = self.build_integer_boolean_test(expr)? {
+            *expr = new_expr;
+            return Ok(true);
+        }
+
         match expr {
+            Expr::Value(value) if self.config.data_types.boolean_as_integer => {
+                if let Value::Boolean(b) = value.value {
+                    value.value = Value::Number(if b { "1" } else { "0" }.to_string(), false);
+                    changed = true;
+                }
+            }
             Expr::BinaryOp { left, op, right } => {
                 if self.transform_expression(left)? {
                     changed = true;
@@ -31,6 +49,11 @@ impl ExpressionTransformer {
                 if self.transform_binary_operator(op)? {
                     changed = true;
                 }
+
+                if let Some(new_expr) = self.build_hana_regex_match(left, op, right) {
+                    *expr = new_expr;
+                    changed = true;
+                }
             }
             Expr::UnaryOp {
                 op,
@@ -77,6 +100,7 @@ impl ExpressionTransformer {
             }
             Expr::Case {
                 operand,
+                conditions,
                 else_result,
                 ..
             } => {
@@ -86,6 +110,15 @@ impl ExpressionTransformer {
                     }
                 }
 
+                for when in conditions {
+                    if self.transform_expression(&mut when.condition)? {
+                        changed = true;
+                    }
+                    if self.transform_expression(&mut when.result)? {
+                        changed = true;
+                    }
+                }
+
                 if let Some(else_result) = else_result {
                     if self.transform_expression(else_result)? {
                         changed = true;
@@ -161,6 +194,19 @@ impl ExpressionTransformer {
                     changed = true;
                 }
             }
+            Expr::AnyOp { .. } | Expr::AllOp { .. } => {
+                if let Some(new_expr) = self.build_hana_array_comparison(expr)? {
+                    *expr = new_expr;
+                    changed = true;
+                }
+            }
+            Expr::Interval(interval) => {
+                return Err(TransformationError::unsupported_with_context(
+                    "INTERVAL",
+                    &format!("standalone interval {}", interval),
+                    Some("HANA has no interval type; add the interval to a date or timestamp, e.g. ts + INTERVAL '1 day' becomes ADD_DAYS(ts, 1)"),
+                ));
+            }
             Expr::Function(function) => {
                 let function_name = function.name.to_string().to_uppercase();
 
@@ -177,6 +223,13 @@ impl ExpressionTransformer {
                             changed = true;
                         }
                     }
+                    "SETVAL" => {
+                        return Err(TransformationError::unsupported_with_context(
+                            "setval()",
+                            &format!("{}", function),
+                            Some("HANA resets a sequence with a statement: ALTER SEQUENCE <seq> RESTART WITH <next value>"),
+                        ));
+                    }
                     _ => {
                         if let sqlparser::ast::FunctionArguments::List(ref mut arg_list) =
                             function.args
@@ -204,22 +257,6 @@ impl ExpressionTransformer {
     fn transform_binary_operator(&self, op: &mut BinaryOperator) -> TransformationResult<bool> {
         match op {
             BinaryOperator::StringConcat => Ok(false),
-            BinaryOperator::PGRegexMatch => {
-                log::warn!("Regex operator ~ requires manual conversion");
-                Ok(false)
-            }
-            BinaryOperator::PGRegexIMatch => {
-                log::warn!("Regex operator ~* requires manual conversion");
-                Ok(false)
-            }
-            BinaryOperator::PGRegexNotMatch => {
-                log::warn!("Regex operator !~ requires manual conversion");
-                Ok(false)
-            }
-            BinaryOperator::PGRegexNotIMatch => {
-                log::warn!("Regex operator !~* requires manual conversion");
-                Ok(false)
-            }
             _ => Ok(false),
         }
     }
@@ -315,6 +352,13 @@ impl ExpressionTransformer {
 
         match stmt {
             Statement::Query(query) => {
+                if let Some(with) = &mut query.with {
+                    for cte in &mut with.cte_tables {
+                        if self.transform_query_expressions(&mut cte.query.body)? {
+                            changed = true;
+                        }
+                    }
+                }
                 if self.transform_query_expressions(&mut query.body)? {
                     changed = true;
                 }
@@ -361,49 +405,318 @@ impl ExpressionTransformer {
         Ok(changed)
     }
 
-    fn build_hana_nextval_expr(&self, function: &Function) -> TransformationResult<Option<Expr>> {
-        if let sqlparser::ast::FunctionArguments::List(ref arg_list) = function.args {
-            if arg_list.args.len() == 1 {
-                if let sqlparser::ast::FunctionArg::Unnamed(
-                    sqlparser::ast::FunctionArgExpr::Expr(Expr::Value(
-                        sqlparser::ast::ValueWithSpan {
-                            value: sqlparser::ast::Value::SingleQuotedString(ref seq_name),
-                            span: _,
-                        },
-                    )),
-                ) = &arg_list.args[0]
-                {
-                    let new_expr =
-                        Expr::CompoundIdentifier(vec![Ident::new(seq_name), Ident::new("NEXTVAL")]);
-                    return Ok(Some(new_expr));
-                }
+    /// Rewrite `x = ANY(ARRAY[...])` and `x <> ALL(ARRAY[...])` into `IN` /
+    /// `NOT IN` lists, and other comparisons against a literal array into an
+    /// `OR` (ANY) or `AND` (ALL) of plain comparisons.
+    fn build_hana_array_comparison(&self, expr: &Expr) -> TransformationResult<Option<Expr>> {
+        let (left, compare_op, right, any) = match expr {
+            Expr::AnyOp {
+                left,
+                compare_op,
+                right,
+                ..
+            } => (left, compare_op, right, true),
+            Expr::AllOp {
+                left,
+                compare_op,
+                right,
+            } => (left, compare_op, right, false),
+            _ => return Ok(None),
+        };
+
+        let elements = match right.as_ref() {
+            Expr::Array(array) if !array.elem.is_empty() => &array.elem,
+            // `x = ANY (SELECT ...)` is valid HANA as it stands.
+            Expr::Subquery(_) => return Ok(None),
+            _ => {
+                return Err(TransformationError::unsupported_with_context(
+                    "ANY/ALL over a non-literal array",
+                    &format!("{}", expr),
+                    Some("HANA has no array type; list the values with IN (...) or compare against a subquery"),
+                ));
             }
+        };
+
+        let mut left = left.as_ref().clone();
+        self.transform_expression(&mut left)?;
+        let mut list = elements.clone();
+        for item in &mut list {
+            self.transform_expression(item)?;
         }
-        Ok(None)
+
+        let new_expr = match (any, compare_op) {
+            (true, BinaryOperator::Eq) | (false, BinaryOperator::NotEq) => Expr::InList {
+                expr: Box::new(left),
+                list,
+                negated: !any,
+            },
+            _ => {
+                let join_op = if any {
+                    BinaryOperator::Or
+                } else {
+                    BinaryOperator::And
+                };
+                let combined = list
+                    .into_iter()
+                    .map(|item| Expr::BinaryOp {
+                        left: Box::new(left.clone()),
+                        op: compare_op.clone(),
+                        right: Box::new(item),
+                    })
+                    .reduce(|acc, comparison| Expr::BinaryOp {
+                        left: Box::new(acc),
+                        op: join_op.clone(),
+                        right: Box::new(comparison),
+                    })
+                    .expect("array has at least one element");
+                Expr::Nested(Box::new(combined))
+            }
+        };
+
+        Ok(Some(new_expr))
+    }
+
+    /// Rewrite `a ~ p`, `a ~* p`, `a !~ p` and `a !~* p` into
+    /// `LIKE_REGEXPR(a, p[, 'i']) > 0`, negated for the `!` forms. The pattern
+    /// is passed through verbatim.
+    fn build_hana_regex_match(
+        &self,
+        left: &Expr,
+        op: &BinaryOperator,
+        right: &Expr,
+    ) -> Option<Expr> {
+        let (case_insensitive, negated) = match op {
+            BinaryOperator::PGRegexMatch => (false, false),
+            BinaryOperator::PGRegexIMatch => (true, false),
+            BinaryOperator::PGRegexNotMatch => (false, true),
+            BinaryOperator::PGRegexNotIMatch => (true, true),
+            _ => return None,
+        };
+
+        let mut args = vec![left.clone(), right.clone()];
+        if case_insensitive {
+            args.push(Expr::value(Value::SingleQuotedString("i".to_string())));
+        }
+        let matches = Expr::BinaryOp {
+            left: Box::new(build_function_call("LIKE_REGEXPR", args)),
+            op: BinaryOperator::Gt,
+            right: Box::new(number_literal(0)),
+        };
+
+        Some(if negated {
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr: Box::new(Expr::Nested(Box::new(matches))),
+            }
+        } else {
+            matches
+        })
+    }
+
+    /// Rewrite `x + INTERVAL '...'`, `INTERVAL '...' + x` and `x - INTERVAL
+    /// '...'` into nested `ADD_YEARS`/`ADD_MONTHS`/`ADD_DAYS`/`ADD_SECONDS`
+    /// calls, one per non-zero component.
+    fn build_hana_interval_arithmetic(&self, expr: &Expr) -> TransformationResult<Option<Expr>> {
+        let (base, interval, negate) = match expr {
+            Expr::BinaryOp { left, op, right } => match (left.as_ref(), op, right.as_ref()) {
+                (base, BinaryOperator::Plus, Expr::Interval(interval)) => (base, interval, false),
+                (base, BinaryOperator::Minus, Expr::Interval(interval)) => (base, interval, true),
+                (Expr::Interval(interval), BinaryOperator::Plus, base) => (base, interval, false),
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+
+        let mut result = base.clone();
+        self.transform_expression(&mut result)?;
+
+        let components = IntervalComponents::parse(interval)?;
+        let sign = if negate { -1 } else { 1 };
+        for (function, amount) in [
+            ("ADD_YEARS", components.years),
+            ("ADD_MONTHS", components.months),
+            ("ADD_DAYS", components.days),
+            ("ADD_SECONDS", components.seconds),
+        ] {
+            if amount != 0 {
+                let amount = amount
+                    .checked_mul(sign)
+                    .ok_or_else(|| unsupported_interval(interval))?;
+                result = build_function_call(function, vec![result, number_literal(amount)]);
+            }
+        }
+
+        Ok(Some(result))
+    }
+
+    /// With `boolean_as_integer`, rewrite `x IS TRUE` and `x IS FALSE` into
+    /// `x = 1` and `x = 0`, since HANA only allows IS TRUE on BOOLEAN.
+    fn build_integer_boolean_test(&self, expr: &Expr) -> TransformationResult<Option<Expr>> {
+        if !self.config.data_types.boolean_as_integer {
+            return Ok(None);
+        }
+        let (inner, value) = match expr {
+            Expr::IsTrue(inner) => (inner, 1),
+            Expr::IsFalse(inner) => (inner, 0),
+            _ => return Ok(None),
+        };
+
+        let mut left = inner.as_ref().clone();
+        self.transform_expression(&mut left)?;
+        Ok(Some(Expr::BinaryOp {
+            left: Box::new(left),
+            op: BinaryOperator::Eq,
+            right: Box::new(number_literal(value)),
+        }))
+    }
+
+    fn build_hana_nextval_expr(&self, function: &Function) -> TransformationResult<Option<Expr>> {
+        Ok(sequence_name_arg(function)
+            .map(|seq_name| Expr::CompoundIdentifier(vec![Ident::new(seq_name), Ident::new("NEXTVAL")])))
     }
 
     fn build_hana_currval_expr(&self, function: &Function) -> TransformationResult<Option<Expr>> {
-        if let sqlparser::ast::FunctionArguments::List(ref arg_list) = function.args {
-            if arg_list.args.len() == 1 {
-                if let sqlparser::ast::FunctionArg::Unnamed(
-                    sqlparser::ast::FunctionArgExpr::Expr(Expr::Value(
-                        sqlparser::ast::ValueWithSpan {
-                            value: sqlparser::ast::Value::SingleQuotedString(ref seq_name),
-                            span: _,
-                        },
-                    )),
-                ) = &arg_list.args[0]
-                {
-                    let new_expr =
-                        Expr::CompoundIdentifier(vec![Ident::new(seq_name), Ident::new("CURRVAL")]);
-                    return Ok(Some(new_expr));
-                }
+        Ok(sequence_name_arg(function)
+            .map(|seq_name| Expr::CompoundIdentifier(vec![Ident::new(seq_name), Ident::new("CURRVAL")])))
+    }
+}
+
+/// The sequence named by the only argument of `nextval('seq')` or
+/// `currval('seq'::regclass)`.
+fn sequence_name_arg(function: &Function) -> Option<&str> {
+    let FunctionArguments::List(arg_list) = &function.args else {
+        return None;
+    };
+    let [FunctionArg::Unnamed(FunctionArgExpr::Expr(arg))] = arg_list.args.as_slice() else {
+        return None;
+    };
+    let arg = match arg {
+        Expr::Cast { expr, .. } => expr.as_ref(),
+        arg => arg,
+    };
+    match arg {
+        Expr::Value(value) => match &value.value {
+            Value::SingleQuotedString(seq_name) => Some(seq_name),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// A PostgreSQL interval reduced to the units HANA can add.
+#[derive(Debug, Default, PartialEq)]
+struct IntervalComponents {
+    years: i64,
+    months: i64,
+    days: i64,
+    seconds: i64,
+}
+
+impl IntervalComponents {
+    fn parse(interval: &Interval) -> TransformationResult<Self> {
+        let text = match interval.value.as_ref() {
+            Expr::Value(value) => match &value.value {
+                Value::SingleQuotedString(s) => s.clone(),
+                Value::Number(n, _) => n.clone(),
+                _ => return Err(unsupported_interval(interval)),
+            },
+            _ => return Err(unsupported_interval(interval)),
+        };
+
+        // `INTERVAL '3' DAY` carries the unit outside the literal.
+        let text = match &interval.leading_field {
+            Some(field) if interval.last_field.is_none() => format!("{} {}", text, field),
+            Some(_) => return Err(unsupported_interval(interval)),
+            None => text,
+        };
+
+        let mut components = Self::default();
+        let mut tokens = text.split_whitespace().peekable();
+        let mut empty = true;
+        while let Some(token) = tokens.next() {
+            empty = false;
+            if token.contains(':') {
+                components.seconds = parse_clock(token)
+                    .and_then(|seconds| components.seconds.checked_add(seconds))
+                    .ok_or_else(|| unsupported_interval(interval))?;
+                continue;
             }
+            let amount: i64 = token.parse().map_err(|_| unsupported_interval(interval))?;
+            let unit = tokens.next().ok_or_else(|| unsupported_interval(interval))?;
+            let (total, factor) = match unit.to_lowercase().trim_end_matches('s') {
+                "year" | "yr" => (&mut components.years, 1),
+                "month" | "mon" => (&mut components.months, 1),
+                "week" => (&mut components.days, 7),
+                "day" => (&mut components.days, 1),
+                "hour" | "hr" => (&mut components.seconds, 3600),
+                "minute" | "min" => (&mut components.seconds, 60),
+                "second" | "sec" => (&mut components.seconds, 1),
+                _ => return Err(unsupported_interval(interval)),
+            };
+            *total = amount
+                .checked_mul(factor)
+                .and_then(|amount| total.checked_add(amount))
+                .ok_or_else(|| unsupported_interval(interval))?;
+        }
+        if empty {
+            return Err(unsupported_interval(interval));
         }
-        Ok(None)
+
+        Ok(components)
     }
 }
 
+/// Seconds in an `HH:MM[:SS]` clock value.
+fn parse_clock(token: &str) -> Option<i64> {
+    let (negative, token) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let parts: Vec<i64> = token
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<_>>()?;
+    let (h, m, s) = match parts.as_slice() {
+        [h, m] => (*h, *m, 0),
+        [h, m, s] => (*h, *m, *s),
+        _ => return None,
+    };
+    let seconds = h.checked_mul(3600)?.checked_add(m.checked_mul(60)?)?.checked_add(s)?;
+    Some(if negative { -seconds } else { seconds })
+}
+
+fn unsupported_interval(interval: &Interval) -> TransformationError {
+    TransformationError::unsupported_with_context(
+        "INTERVAL",
+        &format!("cannot translate {} to HANA", interval),
+        Some("use whole years, months, weeks, days, hours, minutes or seconds, e.g. INTERVAL '1 day 2 hours'"),
+    )
+}
+
+pub(super) fn build_function_call(name: &str, args: Vec<Expr>) -> Expr {
+    Expr::Function(Function {
+        name: ObjectName(vec![ObjectNamePart::Identifier(Ident::new(name))]),
+        uses_odbc_syntax: false,
+        parameters: FunctionArguments::None,
+        args: FunctionArguments::List(FunctionArgumentList {
+            duplicate_treatment: None,
+            args: args
+                .into_iter()
+                .map(|arg| FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)))
+                .collect(),
+            clauses: vec![],
+        }),
+        filter: None,
+        null_treatment: None,
+        over: None,
+        within_group: vec![],
+    })
+}
+
+fn number_literal(value: i64) -> Expr {
+    Expr::value(Value::Number(value.to_string(), false))
+}
+
 impl Transformer for ExpressionTransformer {
     fn name(&self) -> &'static str {
         "ExpressionTransformer"
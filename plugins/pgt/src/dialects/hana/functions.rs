@@ -1,7 +1,8 @@
+use super::expressions::build_function_call;
 use super::Transformer;
 use crate::config::TransformationConfig;
 use crate::error::TransformationResult;
-use sqlparser::ast::{Expr, Function, Ident, ObjectName, Statement};
+use sqlparser::ast::{ColumnOption, Expr, Function, Ident, ObjectName, Statement};
 use std::collections::HashMap;
 
 pub struct FunctionTransformer {
@@ -11,10 +12,15 @@ pub struct FunctionTransformer {
 
 impl FunctionTransformer {
     pub fn new(config: &TransformationConfig) -> Self {
-        let mut simple_mappings = config.functions.custom_mappings.clone();
-
-        for (pg_func, hana_func) in get_default_function_mappings() {
-            simple_mappings.entry(pg_func).or_insert(hana_func);
+        let mut simple_mappings = get_default_function_mappings();
+
+        // User mappings replace built-in ones, including the special-cased
+        // functions, since they are looked up first. Names are matched in
+        // upper case like the built-ins.
+        if config.functions.enable_custom_functions {
+            for (pg_func, hana_func) in &config.functions.custom_mappings {
+                simple_mappings.insert(pg_func.to_uppercase(), hana_func.clone());
+            }
         }
 
         Self {
@@ -106,6 +112,7 @@ impl FunctionTransformer {
                 Ok(true)
             }
             "NEXTVAL" => self.transform_nextval_function(func),
+            "ARRAY_AGG" => self.transform_array_agg_function(func),
             _ => Ok(false),
         }
     }
@@ -137,6 +144,36 @@ impl FunctionTransformer {
         Ok(false)
     }
 
+    /// HANA has no array type to aggregate into, so `array_agg(x)` becomes a
+    /// comma-separated `STRING_AGG(TO_NVARCHAR(x), ',')`. `ORDER BY` inside the
+    /// call is kept.
+    fn transform_array_agg_function(&self, func: &mut Function) -> TransformationResult<bool> {
+        if let sqlparser::ast::FunctionArguments::List(arg_list) = &mut func.args {
+            if arg_list.args.len() != 1 {
+                return Ok(false);
+            }
+            if let sqlparser::ast::FunctionArg::Unnamed(sqlparser::ast::FunctionArgExpr::Expr(
+                expr,
+            )) = &mut arg_list.args[0]
+            {
+                *expr = build_function_call("TO_NVARCHAR", vec![expr.clone()]);
+            } else {
+                return Ok(false);
+            }
+            arg_list.args.push(sqlparser::ast::FunctionArg::Unnamed(
+                sqlparser::ast::FunctionArgExpr::Expr(Expr::value(
+                    sqlparser::ast::Value::SingleQuotedString(",".to_string()),
+                )),
+            ));
+            func.name = ObjectName(vec![sqlparser::ast::ObjectNamePart::Identifier(
+                Ident::new("STRING_AGG"),
+            )]);
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+
     fn transform_nextval_function(&self, func: &mut Function) -> TransformationResult<bool> {
         if let sqlparser::ast::FunctionArguments::List(arg_list) = &func.args {
             if arg_list.args.len() == 1 {
@@ -239,6 +276,13 @@ impl Transformer for FunctionTransformer {
 
         match stmt {
             Statement::Query(query) => {
+                if let Some(with) = &mut query.with {
+                    for cte in &mut with.cte_tables {
+                        if self.transform_query_functions(&mut cte.query.body)? {
+                            changed = true;
+                        }
+                    }
+                }
                 if self.transform_query_functions(&mut query.body)? {
                     changed = true;
                 }
@@ -274,6 +318,24 @@ impl Transformer for FunctionTransformer {
                     }
                 }
             }
+            Statement::CreateTable(create_table) => {
+                for column in &mut create_table.columns {
+                    for option in &mut column.options {
+                        match &mut option.option {
+                            ColumnOption::Default(expr)
+                            | ColumnOption::Generated {
+                                generation_expr: Some(expr),
+                                ..
+                            } => {
+                                if self.transform_expression(expr)? {
+                                    changed = true;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+            }
             _ => {}
         }
 
@@ -7,9 +7,25 @@ pub mod statements;
 use crate::config::TransformationConfig;
 use crate::error::{TransformationError, TransformationResult, TransformationWarning};
 use crate::rules::TransformationRules;
+use super::DialectCapabilities;
 use sqlparser::ast::Statement;
+use std::sync::Mutex;
 use std::time::Duration;
 
+/// HANA has no RETURNING, array columns, JSON column type (JSON lives in
+/// NCLOB), interval values or ILIKE.
+pub const CAPABILITIES: DialectCapabilities = DialectCapabilities {
+    supports_returning: false,
+    supports_arrays: false,
+    supports_recursive_cte: true,
+    supports_json: false,
+    supports_boolean_type: true,
+    supports_interval_type: false,
+    supports_ilike: false,
+    supports_limit_offset: true,
+    identifier_quote_char: '"',
+};
+
 pub struct DetailedTransformationResult {
     pub sql: Option<String>,
     pub errors: Vec<TransformationError>,
@@ -25,7 +41,7 @@ pub struct TransformationMetadata {
     pub transformation_time: Duration,
 }
 
-pub trait Transformer {
+pub trait Transformer: Send + Sync {
     fn name(&self) -> &'static str;
     fn transform(&self, stmt: &mut Statement) -> TransformationResult<bool>;
     fn supports_statement_type(&self, stmt: &Statement) -> bool;
@@ -38,12 +54,19 @@ pub trait Transformer {
     fn collect_warnings(&self) -> Vec<TransformationWarning> {
         Vec::new()
     }
+
+    /// Statements the last transformed statement now depends on, e.g. the
+    /// sequence behind a SERIAL column. Drains them.
+    fn take_preceding_statements(&self) -> Vec<Statement> {
+        Vec::new()
+    }
 }
 
 pub struct TransformationEngine {
     transformers: Vec<Box<dyn Transformer>>,
     config: TransformationConfig,
     rules: TransformationRules,
+    warnings: Mutex<Vec<TransformationWarning>>,
 }
 
 impl TransformationEngine {
@@ -61,11 +84,24 @@ impl TransformationEngine {
             transformers,
             config: config.clone(),
             rules: TransformationRules::new(config.rules.clone()),
+            warnings: Mutex::new(Vec::new()),
         }
     }
 
-    pub fn transform_statement(&self, mut stmt: Statement) -> TransformationResult<Statement> {
+    /// The transformed statement alone; statements it needs to run first
+    /// are only returned by [`Self::transform_statement_group`].
+    pub fn transform_statement(&self, stmt: Statement) -> TransformationResult<Statement> {
+        let mut group = self.transform_statement_group(stmt)?;
+        Ok(group.pop().expect("group ends with the transformed statement"))
+    }
+
+    /// The transformed statement, preceded by any statements it needs.
+    pub fn transform_statement_group(
+        &self,
+        mut stmt: Statement,
+    ) -> TransformationResult<Vec<Statement>> {
         let mut warnings = Vec::new();
+        let mut group = Vec::new();
         let mut any_changes = false;
 
         for transformer in &self.transformers {
@@ -76,6 +112,9 @@ impl TransformationEngine {
                             any_changes = true;
                         }
                     }
+                    // Leaving the construct in place would only produce SQL
+                    // HANA rejects, so unsupported features are not downgraded.
+                    Err(e @ TransformationError::UnsupportedFeature { .. }) => return Err(e),
                     Err(e) => {
                         log::warn!("Transformer '{}' failed: {}", transformer.name(), e);
                         warnings.push(TransformationWarning::high(&format!(
@@ -85,39 +124,61 @@ impl TransformationEngine {
                         )));
                     }
                 }
+                warnings.extend(transformer.collect_warnings());
+                group.extend(transformer.take_preceding_statements());
             }
         }
 
         if !warnings.is_empty() {
             log::info!("Transformation completed with {} warnings", warnings.len());
+            if let Ok(mut collected) = self.warnings.lock() {
+                collected.extend(warnings);
+            }
         }
 
-        Ok(stmt)
+        group.push(stmt);
+        Ok(group)
     }
 
     pub fn transform_statements(
         &self,
         statements: &[Statement],
     ) -> TransformationResult<Vec<Statement>> {
+        Ok(self
+            .transform_statement_groups(statements)?
+            .into_iter()
+            .flatten()
+            .collect())
+    }
+
+    pub fn transform_statement_groups(
+        &self,
+        statements: &[Statement],
+    ) -> TransformationResult<Vec<Vec<Statement>>> {
         let start_time = std::time::Instant::now();
 
+        if let Ok(mut collected) = self.warnings.lock() {
+            collected.clear();
+        }
+
         self.rules.validate_hana_compatibility(statements)?;
 
         let mut transformed_statements = Vec::new();
         let mut errors = Vec::new();
 
         for (index, stmt) in statements.iter().enumerate() {
-            match self.transform_statement(stmt.clone()) {
-                Ok(transformed_stmt) => {
-                    transformed_statements.push(transformed_stmt);
+            match self.transform_statement_group(stmt.clone()) {
+                Ok(group) => {
+                    transformed_statements.push(group);
                 }
+                Err(e @ TransformationError::UnsupportedFeature { .. }) => return Err(e),
                 Err(e) => {
                     if self.config.rules.enable_strict_mode {
                         return Err(e);
                     } else {
                         errors.push(e);
                         log::warn!("Statement {} failed: using original", index);
-                        transformed_statements.push(stmt.clone());
+                        transformed_statements.push(vec![stmt.clone()]);
                     }
                 }
             }
@@ -139,6 +200,15 @@ impl TransformationEngine {
         self.rules.apply_transformation_rules(sql)
     }
 
+    /// Warnings raised by the transformers during the last
+    /// `transform_statements` call.
+    pub fn take_warnings(&self) -> Vec<TransformationWarning> {
+        self.warnings
+            .lock()
+            .map(|mut warnings| std::mem::take(&mut *warnings))
+            .unwrap_or_default()
+    }
+
     pub fn validate_statement_for_hana(
         &self,
         stmt: &Statement,
@@ -174,6 +244,13 @@ impl super::DialectTransformationEngine for HanaTransformationEngine {
         self.engine.transform_statements(statements)
     }
 
+    fn transform_statement_groups(
+        &self,
+        statements: &[Statement],
+    ) -> TransformationResult<Vec<Vec<Statement>>> {
+        self.engine.transform_statement_groups(statements)
+    }
+
     fn apply_post_processing_rules(&self, sql: &str) -> TransformationResult<String> {
         self.engine.apply_post_processing_rules(sql)
     }
@@ -182,6 +259,14 @@ impl super::DialectTransformationEngine for HanaTransformationEngine {
         self.engine.validate_statement_for_hana(stmt)
     }
 
+    fn take_warnings(&self) -> Vec<TransformationWarning> {
+        self.engine.take_warnings()
+    }
+
+    fn capabilities(&self) -> super::DialectCapabilities {
+        CAPABILITIES
+    }
+
     fn name(&self) -> &'static str {
         "HANA Transformation Engine"
     }
@@ -34,7 +34,7 @@ impl PostProcessor {
 
     fn fix_index_using_clause(&self, sql: &str) -> TransformationResult<String> {
         let regex = Regex::new(
-            r"\bUSING\s+(?:btree|gin|hash|gist|spgist|brin|BTREE|GIN|HASH|GIST|SPGIST|BRIN)\b",
+            r"\s+USING\s+(?:btree|gin|hash|gist|spgist|brin|BTREE|GIN|HASH|GIST|SPGIST|BRIN)\b",
         )
         .map_err(|e| crate::error::TransformationError::ParseError {
             message: format!("Regex error: {}", e),
@@ -42,16 +42,9 @@ impl PostProcessor {
             column: 0,
         })?;
 
-        let result = regex.replace_all(sql, "").to_string();
-
-        let cleanup_regex =
-            Regex::new(r"\s+").map_err(|e| crate::error::TransformationError::ParseError {
-                message: format!("Regex error: {}", e),
-                line: 0,
-                column: 0,
-            })?;
-
-        Ok(cleanup_regex.replace_all(&result, " ").trim().to_string())
+        // Only the clause goes: whitespace elsewhere may sit inside string
+        // literals or dollar-quoted bodies.
+        Ok(regex.replace_all(sql, "").to_string())
     }
 }
 
@@ -93,4 +86,13 @@ mod tests {
         assert!(result.contains("FULL OUTER JOIN"));
         assert!(!result.contains("USING BTREE"));
     }
+
+    #[test]
+    fn test_process_keeps_whitespace_in_bodies() {
+        let processor = PostProcessor::new();
+
+        let sql =
+            "CREATE FUNCTION f() RETURNS INT AS $$\n  -- one\n  SELECT 1;\n$$;\n\nSELECT 'a\n  b';";
+        assert_eq!(processor.process(sql).unwrap(), sql);
+    }
 }
@@ -1,16 +1,200 @@
 use super::Transformer;
 use crate::config::TransformationConfig;
-use crate::error::TransformationResult;
-use sqlparser::ast::{DataType, Expr, Query, SelectItem, SetExpr, Statement};
+use crate::error::{TransformationError, TransformationResult, TransformationWarning};
+use sqlparser::ast::{
+    BinaryOperator, ColumnOption, DataType, Expr, FunctionArg, FunctionArgExpr,
+    FunctionArguments, GeneratedAs, GeneratedExpressionMode, Ident, Join, JoinConstraint,
+    JoinOperator, Query, Select, SelectItem, SequenceOptions, SetExpr, SetOperator,
+    SetQuantifier, Statement, TableAliasColumnDef, TableFactor, Value,
+};
+use std::sync::Mutex;
 
 pub struct StatementTransformer {
     config: TransformationConfig,
+    warnings: Mutex<Vec<TransformationWarning>>,
 }
 
 impl StatementTransformer {
     pub fn new(config: &TransformationConfig) -> Self {
         Self {
             config: config.clone(),
+            warnings: Mutex::new(Vec::new()),
+        }
+    }
+
+    fn warn(&self, warning: TransformationWarning) {
+        log::warn!("{}", warning.message);
+        if let Ok(mut warnings) = self.warnings.lock() {
+            warnings.push(warning);
+        }
+    }
+
+    /// HANA takes PostgreSQL's recursive CTE shape (anchor, `UNION ALL`,
+    /// recursive member) but resolves the recursive member against the CTE's
+    /// column list, so the list is filled in from the anchor when missing.
+    /// `MATERIALIZED` hints are dropped. Constructs that would change the
+    /// result on HANA are left in place with a warning.
+    fn transform_ctes(&self, query: &mut Query) -> TransformationResult<bool> {
+        let mut changed = false;
+
+        let with = match query.with.as_mut() {
+            Some(with) => with,
+            None => return Ok(false),
+        };
+
+        for cte in &mut with.cte_tables {
+            if cte.materialized.take().is_some() {
+                changed = true;
+            }
+            if !with.recursive {
+                continue;
+            }
+
+            let name = cte.alias.name.value.clone();
+            let (set_quantifier, anchor, recursive) = match cte.query.body.as_ref() {
+                SetExpr::SetOperation {
+                    op: SetOperator::Union,
+                    set_quantifier,
+                    left,
+                    right,
+                } => (set_quantifier, left, right),
+                // WITH RECURSIVE may also hold plain CTEs.
+                _ => continue,
+            };
+
+            if !matches!(set_quantifier, SetQuantifier::All) {
+                self.warn(TransformationWarning::high(&format!(
+                    "Recursive CTE '{}' uses UNION; HANA only accepts UNION ALL here, \
+                     so duplicate rows would no longer be removed",
+                    name
+                )));
+            }
+            if let SetExpr::Query(member) = recursive.as_ref() {
+                if member.order_by.is_some() || member.limit_clause.is_some() {
+                    self.warn(TransformationWarning::high(&format!(
+                        "Recursive CTE '{}' has ORDER BY or LIMIT in its recursive member, \
+                         which HANA does not support",
+                        name
+                    )));
+                }
+            }
+
+            if cte.alias.columns.is_empty() {
+                match anchor_columns(anchor) {
+                    Some(columns) => {
+                        cte.alias.columns = columns;
+                        changed = true;
+                    }
+                    None => self.warn(TransformationWarning::medium(&format!(
+                        "Recursive CTE '{}' needs an explicit column list for HANA",
+                        name
+                    ))),
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// HANA joins a `LATERAL (subquery)` only with `INNER JOIN` or `LEFT
+    /// OUTER JOIN` and a real predicate, so `CROSS JOIN LATERAL` and `,
+    /// LATERAL` become `INNER JOIN LATERAL ... ON 1 = 1`, and `ON true`
+    /// becomes `ON 1 = 1`. Lateral function calls and other join types with
+    /// a lateral are rejected.
+    fn transform_laterals(&self, query: &mut Query) -> TransformationResult<bool> {
+        let mut changed = false;
+
+        if let Some(with) = &mut query.with {
+            for cte in &mut with.cte_tables {
+                changed |= self.transform_laterals(&mut cte.query)?;
+            }
+        }
+        changed |= self.transform_set_expr_laterals(&mut query.body)?;
+
+        Ok(changed)
+    }
+
+    fn transform_set_expr_laterals(&self, body: &mut SetExpr) -> TransformationResult<bool> {
+        match body {
+            SetExpr::Select(select) => self.transform_select_laterals(select),
+            SetExpr::Query(query) => self.transform_laterals(query),
+            SetExpr::SetOperation { left, right, .. } => {
+                let left_changed = self.transform_set_expr_laterals(left)?;
+                Ok(self.transform_set_expr_laterals(right)? || left_changed)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn transform_select_laterals(&self, select: &mut Select) -> TransformationResult<bool> {
+        let mut changed = false;
+
+        // `FROM a, b, LATERAL (...) c` may refer to a and b from c, so
+        // everything before it is cross joined into one join tree.
+        let mut from: Vec<sqlparser::ast::TableWithJoins> = Vec::with_capacity(select.from.len());
+        for table in std::mem::take(&mut select.from) {
+            if from.is_empty() || !is_lateral_subquery(&table.relation) {
+                from.push(table);
+                continue;
+            }
+            let mut merged = from.remove(0);
+            for previous in from.drain(..).chain(std::iter::once(table)) {
+                merged.joins.push(Join {
+                    relation: previous.relation,
+                    global: false,
+                    join_operator: JoinOperator::CrossJoin,
+                });
+                merged.joins.extend(previous.joins);
+            }
+            from.push(merged);
+            changed = true;
+        }
+        select.from = from;
+
+        for table in &mut select.from {
+            if self.transform_table_factor_laterals(&mut table.relation)? {
+                changed = true;
+            }
+            for join in &mut table.joins {
+                if self.transform_lateral_join(join)? {
+                    changed = true;
+                }
+            }
+        }
+
+        Ok(changed)
+    }
+
+    fn transform_lateral_join(&self, join: &mut Join) -> TransformationResult<bool> {
+        let mut changed = self.transform_table_factor_laterals(&mut join.relation)?;
+        if is_lateral_subquery(&join.relation) && lateral_join_for_hana(join)? {
+            changed = true;
+        }
+        Ok(changed)
+    }
+
+    fn transform_table_factor_laterals(&self, relation: &mut TableFactor) -> TransformationResult<bool> {
+        match relation {
+            TableFactor::Derived { subquery, .. } => self.transform_laterals(subquery),
+            TableFactor::NestedJoin {
+                table_with_joins, ..
+            } => {
+                let mut changed = self.transform_table_factor_laterals(&mut table_with_joins.relation)?;
+                for join in &mut table_with_joins.joins {
+                    if self.transform_lateral_join(join)? {
+                        changed = true;
+                    }
+                }
+                Ok(changed)
+            }
+            TableFactor::Function { lateral: true, .. } => {
+                Err(TransformationError::unsupported_with_context(
+                    "LATERAL function call",
+                    &format!("{}", relation),
+                    Some("HANA only joins LATERAL subqueries; select from the function in a subquery or rewrite the join manually"),
+                ))
+            }
+            _ => Ok(false),
         }
     }
 
@@ -81,10 +265,30 @@ impl StatementTransformer {
 
             for column in &mut create_table.columns {
                 for option in &mut column.options {
-                    if let sqlparser::ast::ColumnOption::Default(expr) = &mut option.option {
-                        if self.transform_default_expression(expr)? {
-                            changed = true;
+                    match &mut option.option {
+                        ColumnOption::Default(expr) => {
+                            changed |= self.transform_default_expression(expr)?;
+                        }
+                        ColumnOption::Generated {
+                            generation_expr: None,
+                            sequence_options: Some(options),
+                            ..
+                        } => {
+                            changed |= hana_sequence_options(options);
+                        }
+                        ColumnOption::Generated {
+                            generated_as,
+                            generation_expr: Some(_),
+                            generation_expr_mode,
+                            ..
+                        } => {
+                            changed |= self.transform_computed_column(
+                                &column.name,
+                                generated_as,
+                                generation_expr_mode,
+                            );
                         }
+                        _ => {}
                     }
                 }
             }
@@ -99,11 +303,102 @@ impl StatementTransformer {
         Ok(changed)
     }
 
+    /// HANA spells sequence options as PostgreSQL does, but only with `START
+    /// WITH` and `INCREMENT BY`, and has no temporary sequences, `IF NOT
+    /// EXISTS`, `AS <type>` or `OWNED BY`. Those are dropped with a warning.
+    fn transform_create_sequence(&self, stmt: &mut Statement) -> TransformationResult<bool> {
+        let mut changed = false;
+
+        if let Statement::CreateSequence {
+            temporary,
+            if_not_exists,
+            name,
+            data_type,
+            sequence_options,
+            owned_by,
+        } = stmt
+        {
+            if std::mem::take(temporary) {
+                self.warn(TransformationWarning::high(&format!(
+                    "Sequence {} is created as a regular sequence; HANA has no temporary sequences",
+                    name
+                )));
+                changed = true;
+            }
+            if std::mem::take(if_not_exists) {
+                self.warn(TransformationWarning::medium(&format!(
+                    "IF NOT EXISTS dropped from CREATE SEQUENCE {}; HANA fails if it exists",
+                    name
+                )));
+                changed = true;
+            }
+            if let Some(data_type) = data_type.take() {
+                self.warn(TransformationWarning::low(&format!(
+                    "AS {} dropped from CREATE SEQUENCE {}; HANA sequences are BIGINT",
+                    data_type, name
+                )));
+                changed = true;
+            }
+            if let Some(owner) = owned_by.take() {
+                self.warn(TransformationWarning::low(&format!(
+                    "OWNED BY {} dropped from CREATE SEQUENCE {}; drop the sequence with its table",
+                    owner, name
+                )));
+                changed = true;
+            }
+
+            if hana_sequence_options(sequence_options) {
+                changed = true;
+            }
+        }
+
+        Ok(changed)
+    }
+
+    /// `GENERATED ALWAYS AS (expr) STORED` becomes HANA's `GENERATED ALWAYS
+    /// AS (expr)`, which HANA always stores. Virtual columns, which
+    /// PostgreSQL cannot declare, are left as they are with a warning.
+    fn transform_computed_column(
+        &self,
+        column: &Ident,
+        generated_as: &mut GeneratedAs,
+        mode: &mut Option<GeneratedExpressionMode>,
+    ) -> bool {
+        match mode {
+            Some(GeneratedExpressionMode::Stored) => {
+                *generated_as = GeneratedAs::Always;
+                *mode = None;
+                true
+            }
+            Some(GeneratedExpressionMode::Virtual) => {
+                self.warn(TransformationWarning::medium(&format!(
+                    "Virtual generated column {} kept as is; check that the HANA version supports it",
+                    column
+                )));
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// HANA defaults take `CURRENT_TIMESTAMP` where PostgreSQL calls
+    /// `now()`, anywhere in the expression. Other functions have already
+    /// been through the function rules.
     fn transform_default_expression(&self, expr: &mut Expr) -> TransformationResult<bool> {
         let mut changed = false;
 
         match expr {
             Expr::Function(func) => {
+                if let FunctionArguments::List(arg_list) = &mut func.args {
+                    for arg in &mut arg_list.args {
+                        if let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)) = arg {
+                            if self.transform_default_expression(arg)? {
+                                changed = true;
+                            }
+                        }
+                    }
+                }
+
                 let func_name = func.name.to_string().to_lowercase();
                 if func_name == "nextval" {
                     log::warn!("nextval() in DEFAULT - convert to IDENTITY");
@@ -131,11 +426,25 @@ impl StatementTransformer {
                     }
                 }
             }
-            Expr::Nested(inner_expr) => {
+            Expr::Nested(inner_expr)
+            | Expr::Cast {
+                expr: inner_expr, ..
+            }
+            | Expr::UnaryOp {
+                expr: inner_expr, ..
+            } => {
                 if self.transform_default_expression(inner_expr)? {
                     changed = true;
                 }
             }
+            Expr::BinaryOp { left, right, .. } => {
+                if self.transform_default_expression(left)? {
+                    changed = true;
+                }
+                if self.transform_default_expression(right)? {
+                    changed = true;
+                }
+            }
             _ => {}
         }
 
@@ -225,6 +534,99 @@ impl StatementTransformer {
     }
 }
 
+/// HANA requires the `WITH` of `START WITH` and the `BY` of `INCREMENT
+/// BY`, which PostgreSQL lets sequence and identity options omit.
+fn hana_sequence_options(options: &mut [SequenceOptions]) -> bool {
+    let mut changed = false;
+    for option in options {
+        match option {
+            SequenceOptions::StartWith(_, with) | SequenceOptions::IncrementBy(_, with)
+                if !*with =>
+            {
+                *with = true;
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    changed
+}
+
+fn is_lateral_subquery(relation: &TableFactor) -> bool {
+    matches!(relation, TableFactor::Derived { lateral: true, .. })
+}
+
+/// Rewrite the operator of a join to a lateral subquery into one HANA
+/// accepts, or fail if there is none.
+fn lateral_join_for_hana(join: &mut Join) -> TransformationResult<bool> {
+    let always = || {
+        JoinConstraint::On(Expr::BinaryOp {
+            left: Box::new(Expr::value(Value::Number("1".to_string(), false))),
+            op: BinaryOperator::Eq,
+            right: Box::new(Expr::value(Value::Number("1".to_string(), false))),
+        })
+    };
+    let is_true =
+        |on: &Expr| matches!(on, Expr::Value(value) if value.value == Value::Boolean(true));
+
+    let new_operator = match &join.join_operator {
+        JoinOperator::CrossJoin => JoinOperator::Inner(always()),
+        JoinOperator::Join(JoinConstraint::On(on)) | JoinOperator::Inner(JoinConstraint::On(on)) => {
+            if !is_true(on) {
+                return Ok(false);
+            }
+            JoinOperator::Inner(always())
+        }
+        JoinOperator::Left(JoinConstraint::On(on))
+        | JoinOperator::LeftOuter(JoinConstraint::On(on)) => {
+            if is_true(on) {
+                JoinOperator::LeftOuter(always())
+            } else {
+                JoinOperator::LeftOuter(JoinConstraint::On(on.clone()))
+            }
+        }
+        _ => {
+            return Err(TransformationError::unsupported_with_context(
+                "LATERAL join",
+                &format!("{}", join),
+                Some("HANA only supports LATERAL subqueries with INNER JOIN or LEFT OUTER JOIN ... ON <predicate>; rewrite the join manually"),
+            ));
+        }
+    };
+
+    let changed = new_operator != join.join_operator;
+    join.join_operator = new_operator;
+    Ok(changed)
+}
+
+/// Output column names of a recursive CTE's anchor member, if every
+/// projection item has one.
+fn anchor_columns(anchor: &SetExpr) -> Option<Vec<TableAliasColumnDef>> {
+    match anchor {
+        SetExpr::Select(select) => select
+            .projection
+            .iter()
+            .map(|item| {
+                let name = match item {
+                    SelectItem::ExprWithAlias { alias, .. } => alias.clone(),
+                    SelectItem::UnnamedExpr(Expr::Identifier(ident)) => ident.clone(),
+                    SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
+                        idents.last()?.clone()
+                    }
+                    _ => return None,
+                };
+                Some(TableAliasColumnDef {
+                    name,
+                    data_type: None,
+                })
+            })
+            .collect(),
+        SetExpr::SetOperation { left, .. } => anchor_columns(left),
+        SetExpr::Query(query) => anchor_columns(&query.body),
+        _ => None,
+    }
+}
+
 impl Transformer for StatementTransformer {
     fn name(&self) -> &'static str {
         "StatementTransformer"
@@ -234,6 +636,13 @@ impl Transformer for StatementTransformer {
         50
     }
 
+    fn collect_warnings(&self) -> Vec<TransformationWarning> {
+        self.warnings
+            .lock()
+            .map(|mut warnings| std::mem::take(&mut *warnings))
+            .unwrap_or_default()
+    }
+
     fn supports_statement_type(&self, stmt: &Statement) -> bool {
         matches!(
             stmt,
@@ -243,6 +652,7 @@ impl Transformer for StatementTransformer {
                 | Statement::Delete(_)
                 | Statement::CreateTable(_)
                 | Statement::CreateView { .. }
+                | Statement::CreateSequence { .. }
         )
     }
 
@@ -251,6 +661,12 @@ impl Transformer for StatementTransformer {
 
         match stmt {
             Statement::Query(query) => {
+                if self.transform_ctes(query)? {
+                    changed = true;
+                }
+                if self.transform_laterals(query)? {
+                    changed = true;
+                }
                 if self.transform_limit_offset(query)? {
                     changed = true;
                 }
@@ -263,7 +679,23 @@ impl Transformer for StatementTransformer {
                     changed = true;
                 }
             }
-            Statement::Insert(_) => {
+            Statement::CreateView { query, .. } => {
+                if self.transform_ctes(query)? {
+                    changed = true;
+                }
+                if self.transform_laterals(query)? {
+                    changed = true;
+                }
+            }
+            Statement::Insert(insert) => {
+                if let Some(source) = &mut insert.source {
+                    if self.transform_ctes(source)? {
+                        changed = true;
+                    }
+                    if self.transform_laterals(source)? {
+                        changed = true;
+                    }
+                }
                 if self.transform_insert(stmt)? {
                     changed = true;
                 }
@@ -278,6 +710,11 @@ impl Transformer for StatementTransformer {
                     changed = true;
                 }
             }
+            Statement::CreateSequence { .. } => {
+                if self.transform_create_sequence(stmt)? {
+                    changed = true;
+                }
+            }
             _ => {}
         }
 
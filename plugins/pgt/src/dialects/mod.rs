@@ -1,8 +1,10 @@
 pub mod hana;
 pub mod duckdb;
+pub mod mysql;
+pub mod snowflake;
 
 use crate::config::TransformationConfig;
-use crate::error::{TransformationError, TransformationResult};
+use crate::error::{TransformationError, TransformationResult, TransformationWarning};
 use sqlparser::ast::Statement;
 use std::fmt;
 
@@ -10,17 +12,21 @@ use std::fmt;
 pub enum Dialect {
     Hana,
     DuckDb,
+    MySql,
+    Snowflake,
 }
 
 impl Dialect {
     pub fn all() -> &'static [Dialect] {
-        &[Dialect::Hana, Dialect::DuckDb]
+        &[Dialect::Hana, Dialect::DuckDb, Dialect::MySql, Dialect::Snowflake]
     }
 
     pub fn name(&self) -> &'static str {
         match self {
             Dialect::Hana => "hana",
             Dialect::DuckDb => "duckdb",
+            Dialect::MySql => "mysql",
+            Dialect::Snowflake => "snowflake",
         }
     }
 
@@ -28,6 +34,8 @@ impl Dialect {
         match s.to_lowercase().as_str() {
             "hana" | "sap-hana" | "sap_hana" => Ok(Dialect::Hana),
             "duckdb" | "duck-db" | "duck_db" => Ok(Dialect::DuckDb),
+            "mysql" | "my-sql" | "my_sql" => Ok(Dialect::MySql),
+            "snowflake" => Ok(Dialect::Snowflake),
             _ => Err(format!("Unsupported dialect: {}. Supported dialects: {}", 
                 s, 
                 Dialect::all().iter()
@@ -39,6 +47,37 @@ impl Dialect {
     }
 }
 
+/// What a target dialect can express, so callers can check SQL against a
+/// dialect before transforming it, or pick a dialect programmatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DialectCapabilities {
+    /// `INSERT/UPDATE/DELETE ... RETURNING`
+    pub supports_returning: bool,
+    /// Array column types and `ARRAY[...]` values
+    pub supports_arrays: bool,
+    pub supports_recursive_cte: bool,
+    /// A native JSON (or semi-structured) column type
+    pub supports_json: bool,
+    pub supports_boolean_type: bool,
+    /// `INTERVAL` values outside of date arithmetic
+    pub supports_interval_type: bool,
+    pub supports_ilike: bool,
+    pub supports_limit_offset: bool,
+    /// Character used to quote identifiers in generated SQL
+    pub identifier_quote_char: char,
+}
+
+impl Dialect {
+    pub fn capabilities(&self) -> DialectCapabilities {
+        match self {
+            Dialect::Hana => hana::CAPABILITIES,
+            Dialect::DuckDb => duckdb::CAPABILITIES,
+            Dialect::MySql => mysql::CAPABILITIES,
+            Dialect::Snowflake => snowflake::CAPABILITIES,
+        }
+    }
+}
+
 impl Default for Dialect {
     fn default() -> Self {
         Dialect::Hana
@@ -51,13 +90,34 @@ impl fmt::Display for Dialect {
     }
 }
 
-pub trait DialectTransformationEngine {
+/// `Send + Sync` so a [`crate::SqlTransformer`] can be shared between threads.
+pub trait DialectTransformationEngine: Send + Sync {
     fn dialect(&self) -> Dialect;
     fn transform_statement(&self, stmt: Statement) -> TransformationResult<Statement>;
     fn transform_statements(&self, statements: &[Statement]) -> TransformationResult<Vec<Statement>>;
+
+    /// Like `transform_statements`, with one entry per input statement that
+    /// also holds the statements it has to be preceded by, e.g. the sequence
+    /// a SERIAL column draws from on HANA.
+    fn transform_statement_groups(
+        &self,
+        statements: &[Statement],
+    ) -> TransformationResult<Vec<Vec<Statement>>> {
+        Ok(self
+            .transform_statements(statements)?
+            .into_iter()
+            .map(|stmt| vec![stmt])
+            .collect())
+    }
     fn apply_post_processing_rules(&self, sql: &str) -> TransformationResult<String>;
     fn validate_statement_for_hana(&self, stmt: &Statement) -> TransformationResult<Vec<String>>;
     fn name(&self) -> &'static str;
+    fn capabilities(&self) -> DialectCapabilities;
+
+    /// Warnings from the last `transform_statements` call, drained.
+    fn take_warnings(&self) -> Vec<TransformationWarning> {
+        Vec::new()
+    }
 }
 
 pub struct DialectEngineFactory;
@@ -67,6 +127,8 @@ impl DialectEngineFactory {
         match dialect {
             Dialect::Hana => Ok(Box::new(hana::HanaTransformationEngine::new(config))),
             Dialect::DuckDb => Ok(Box::new(duckdb::DuckDbTransformationEngine::new(config))),
+            Dialect::MySql => Ok(Box::new(mysql::MySqlTransformationEngine::new(config))),
+            Dialect::Snowflake => Ok(Box::new(snowflake::SnowflakeTransformationEngine::new(config))),
         }
     }
 
@@ -0,0 +1,331 @@
+use crate::config::TransformationConfig;
+use crate::dialects::{Dialect, DialectCapabilities, DialectTransformationEngine};
+use crate::error::TransformationResult;
+use sqlparser::ast::{
+    CastKind, ColumnOption, DataType, Delete, ExactNumberInfo, Expr, Function,
+    FunctionArg, FunctionArgExpr, FunctionArguments, Ident, JoinConstraint, JoinOperator,
+    ObjectName, ObjectNamePart, Query, SelectItem, SetExpr, Statement, TableWithJoins, Value,
+};
+
+/// MySQL 8: recursive CTEs and JSON, but BOOLEAN is only TINYINT(1).
+pub const CAPABILITIES: DialectCapabilities = DialectCapabilities {
+    supports_returning: false,
+    supports_arrays: false,
+    supports_recursive_cte: true,
+    supports_json: true,
+    supports_boolean_type: false,
+    supports_interval_type: false,
+    supports_ilike: false,
+    supports_limit_offset: true,
+    identifier_quote_char: '`',
+};
+
+pub struct MySqlTransformationEngine {
+    _config: TransformationConfig,
+}
+
+impl MySqlTransformationEngine {
+    pub fn new(config: &TransformationConfig) -> Self {
+        Self {
+            _config: config.clone(),
+        }
+    }
+
+    fn transform_expression(&self, expr: &mut Expr) -> bool {
+        let mut changed = false;
+
+        match expr {
+            // MySQL has TRUE/FALSE only as aliases of 1/0, and BOOLEAN columns
+            // are TINYINT(1), so write the integers it stores.
+            Expr::Value(value) => {
+                if let Value::Boolean(b) = value.value {
+                    value.value = Value::Number(if b { "1" } else { "0" }.to_string(), false);
+                    changed = true;
+                }
+            }
+            Expr::BinaryOp { left, right, .. } => {
+                changed |= self.transform_expression(left);
+                changed |= self.transform_expression(right);
+            }
+            Expr::UnaryOp { expr: inner, .. }
+            | Expr::Nested(inner)
+            | Expr::IsNull(inner)
+            | Expr::IsNotNull(inner) => {
+                changed |= self.transform_expression(inner);
+            }
+            Expr::Cast {
+                expr: inner,
+                data_type,
+                kind,
+                ..
+            } => {
+                if matches!(kind, CastKind::DoubleColon) {
+                    *kind = CastKind::Cast;
+                    changed = true;
+                }
+                changed |= self.transform_expression(inner);
+                changed |= transform_cast_type(data_type);
+            }
+            Expr::Case {
+                operand,
+                conditions,
+                else_result,
+                ..
+            } => {
+                if let Some(operand) = operand {
+                    changed |= self.transform_expression(operand);
+                }
+                for when in conditions {
+                    changed |= self.transform_expression(&mut when.condition);
+                    changed |= self.transform_expression(&mut when.result);
+                }
+                if let Some(else_result) = else_result {
+                    changed |= self.transform_expression(else_result);
+                }
+            }
+            Expr::InList {
+                expr: inner, list, ..
+            } => {
+                changed |= self.transform_expression(inner);
+                for item in list {
+                    changed |= self.transform_expression(item);
+                }
+            }
+            Expr::Between {
+                expr: inner,
+                low,
+                high,
+                ..
+            } => {
+                changed |= self.transform_expression(inner);
+                changed |= self.transform_expression(low);
+                changed |= self.transform_expression(high);
+            }
+            Expr::Like {
+                expr: inner,
+                pattern,
+                ..
+            }
+            | Expr::ILike {
+                expr: inner,
+                pattern,
+                ..
+            } => {
+                changed |= self.transform_expression(inner);
+                changed |= self.transform_expression(pattern);
+            }
+            Expr::InSubquery {
+                expr: inner,
+                subquery,
+                ..
+            } => {
+                changed |= self.transform_expression(inner);
+                changed |= self.transform_query(subquery);
+            }
+            Expr::Subquery(query) | Expr::Exists { subquery: query, .. } => {
+                changed |= self.transform_query(query);
+            }
+            Expr::Function(function) => {
+                changed |= self.transform_function(function);
+            }
+            _ => {}
+        }
+
+        changed
+    }
+
+    fn transform_function(&self, function: &mut Function) -> bool {
+        let mut changed = false;
+
+        if function.name.to_string().eq_ignore_ascii_case("gen_random_uuid") {
+            function.name = ObjectName(vec![ObjectNamePart::Identifier(Ident::new("UUID"))]);
+            changed = true;
+        }
+
+        if let FunctionArguments::List(arg_list) = &mut function.args {
+            for arg in &mut arg_list.args {
+                if let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) = arg {
+                    changed |= self.transform_expression(arg_expr);
+                }
+            }
+        }
+
+        changed
+    }
+
+    fn transform_query(&self, query: &mut Query) -> bool {
+        self.transform_set_expr(&mut query.body)
+    }
+
+    fn transform_set_expr(&self, set_expr: &mut SetExpr) -> bool {
+        let mut changed = false;
+
+        match set_expr {
+            SetExpr::Select(select) => {
+                for item in &mut select.projection {
+                    if let SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } =
+                        item
+                    {
+                        changed |= self.transform_expression(expr);
+                    }
+                }
+                for table in &mut select.from {
+                    changed |= self.transform_table_with_joins(table);
+                }
+                if let Some(selection) = &mut select.selection {
+                    changed |= self.transform_expression(selection);
+                }
+                if let Some(having) = &mut select.having {
+                    changed |= self.transform_expression(having);
+                }
+            }
+            SetExpr::Query(query) => {
+                changed |= self.transform_query(query);
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                changed |= self.transform_set_expr(left);
+                changed |= self.transform_set_expr(right);
+            }
+            SetExpr::Values(values) => {
+                for row in &mut values.rows {
+                    for expr in row {
+                        changed |= self.transform_expression(expr);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        changed
+    }
+
+    fn transform_table_with_joins(&self, table: &mut TableWithJoins) -> bool {
+        let mut changed = false;
+
+        for join in &mut table.joins {
+            let constraint = match &mut join.join_operator {
+                JoinOperator::Join(c)
+                | JoinOperator::Inner(c)
+                | JoinOperator::Left(c)
+                | JoinOperator::LeftOuter(c)
+                | JoinOperator::Right(c)
+                | JoinOperator::RightOuter(c)
+                | JoinOperator::FullOuter(c) => Some(c),
+                _ => None,
+            };
+            if let Some(JoinConstraint::On(expr)) = constraint {
+                changed |= self.transform_expression(expr);
+            }
+        }
+
+        changed
+    }
+
+    fn transform_mysql_statement(&self, stmt: &mut Statement) -> bool {
+        let mut changed = false;
+
+        match stmt {
+            Statement::Query(query) => {
+                changed |= self.transform_query(query);
+            }
+            Statement::Insert(insert) => {
+                if let Some(source) = &mut insert.source {
+                    changed |= self.transform_query(source);
+                }
+            }
+            Statement::Update {
+                assignments,
+                selection,
+                ..
+            } => {
+                for assignment in assignments {
+                    changed |= self.transform_expression(&mut assignment.value);
+                }
+                if let Some(selection) = selection {
+                    changed |= self.transform_expression(selection);
+                }
+            }
+            Statement::Delete(Delete {
+                selection: Some(selection),
+                ..
+            }) => {
+                changed |= self.transform_expression(selection);
+            }
+            Statement::CreateView { query, .. } => {
+                changed |= self.transform_query(query);
+            }
+            Statement::CreateTable(create_table) => {
+                for column in &mut create_table.columns {
+                    for option in &mut column.options {
+                        if let ColumnOption::Default(expr) = &mut option.option {
+                            changed |= self.transform_expression(expr);
+                        }
+                    }
+                }
+                if let Some(query) = &mut create_table.query {
+                    changed |= self.transform_query(query);
+                }
+            }
+            _ => {}
+        }
+
+        changed
+    }
+}
+
+/// `CAST(x AS t)` in MySQL only accepts a handful of target types; map the
+/// PostgreSQL ones onto them.
+fn transform_cast_type(data_type: &mut DataType) -> bool {
+    let mapped = match data_type {
+        DataType::SmallInt(_)
+        | DataType::Int(_)
+        | DataType::Integer(_)
+        | DataType::BigInt(_)
+        | DataType::Int2(_)
+        | DataType::Int4(_)
+        | DataType::Int8(_) => DataType::Signed,
+        DataType::Text | DataType::String(_) => DataType::Char(None),
+        DataType::Varchar(length) | DataType::CharacterVarying(length) => DataType::Char(*length),
+        DataType::Timestamp(_, _) => DataType::Datetime(None),
+        DataType::Numeric(info) => DataType::Decimal(*info),
+        DataType::Real | DataType::Float4 => DataType::Float(None),
+        DataType::DoublePrecision | DataType::Float8 => DataType::Double(ExactNumberInfo::None),
+        _ => return false,
+    };
+    *data_type = mapped;
+    true
+}
+
+impl DialectTransformationEngine for MySqlTransformationEngine {
+    fn dialect(&self) -> Dialect {
+        Dialect::MySql
+    }
+
+    fn transform_statement(&self, mut stmt: Statement) -> TransformationResult<Statement> {
+        self.transform_mysql_statement(&mut stmt);
+        Ok(stmt)
+    }
+
+    fn transform_statements(&self, statements: &[Statement]) -> TransformationResult<Vec<Statement>> {
+        statements
+            .iter()
+            .map(|stmt| self.transform_statement(stmt.clone()))
+            .collect()
+    }
+
+    fn apply_post_processing_rules(&self, sql: &str) -> TransformationResult<String> {
+        Ok(sql.to_string())
+    }
+
+    fn validate_statement_for_hana(&self, _stmt: &Statement) -> TransformationResult<Vec<String>> {
+        Ok(vec![])
+    }
+
+    fn name(&self) -> &'static str {
+        "MySQL Transformation Engine"
+    }
+
+    fn capabilities(&self) -> DialectCapabilities {
+        CAPABILITIES
+    }
+}
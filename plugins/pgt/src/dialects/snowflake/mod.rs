@@ -0,0 +1,355 @@
+use crate::config::TransformationConfig;
+use crate::dialects::{Dialect, DialectCapabilities, DialectTransformationEngine};
+use crate::error::TransformationResult;
+use sqlparser::ast::{
+    ArrayElemTypeDef, ColumnDef, ColumnOption, ColumnOptionDef, DataType, Delete, Expr, Function,
+    FunctionArg, FunctionArgExpr, FunctionArguments, Ident, IdentityProperty,
+    IdentityPropertyKind, JoinConstraint, JoinOperator, ObjectName, ObjectNamePart, Query,
+    SelectItem, SetExpr, Statement, TableWithJoins,
+};
+
+/// Semi-structured data lives in VARIANT/ARRAY; there is no RETURNING.
+pub const CAPABILITIES: DialectCapabilities = DialectCapabilities {
+    supports_returning: false,
+    supports_arrays: true,
+    supports_recursive_cte: true,
+    supports_json: true,
+    supports_boolean_type: true,
+    supports_interval_type: false,
+    supports_ilike: true,
+    supports_limit_offset: true,
+    identifier_quote_char: '"',
+};
+
+pub struct SnowflakeTransformationEngine {
+    _config: TransformationConfig,
+}
+
+impl SnowflakeTransformationEngine {
+    pub fn new(config: &TransformationConfig) -> Self {
+        Self {
+            _config: config.clone(),
+        }
+    }
+
+    fn transform_expression(&self, expr: &mut Expr) -> bool {
+        let mut changed = false;
+
+        match expr {
+            Expr::BinaryOp { left, right, .. } => {
+                changed |= self.transform_expression(left);
+                changed |= self.transform_expression(right);
+            }
+            Expr::UnaryOp { expr: inner, .. }
+            | Expr::Nested(inner)
+            | Expr::IsNull(inner)
+            | Expr::IsNotNull(inner) => {
+                changed |= self.transform_expression(inner);
+            }
+            // Snowflake accepts `::` casts as-is, only the target type needs mapping.
+            Expr::Cast {
+                expr: inner,
+                data_type,
+                ..
+            } => {
+                changed |= self.transform_expression(inner);
+                changed |= transform_data_type(data_type);
+            }
+            Expr::Case {
+                operand,
+                conditions,
+                else_result,
+                ..
+            } => {
+                if let Some(operand) = operand {
+                    changed |= self.transform_expression(operand);
+                }
+                for when in conditions {
+                    changed |= self.transform_expression(&mut when.condition);
+                    changed |= self.transform_expression(&mut when.result);
+                }
+                if let Some(else_result) = else_result {
+                    changed |= self.transform_expression(else_result);
+                }
+            }
+            Expr::InList {
+                expr: inner, list, ..
+            } => {
+                changed |= self.transform_expression(inner);
+                for item in list {
+                    changed |= self.transform_expression(item);
+                }
+            }
+            Expr::Between {
+                expr: inner,
+                low,
+                high,
+                ..
+            } => {
+                changed |= self.transform_expression(inner);
+                changed |= self.transform_expression(low);
+                changed |= self.transform_expression(high);
+            }
+            Expr::Like {
+                expr: inner,
+                pattern,
+                ..
+            }
+            | Expr::ILike {
+                expr: inner,
+                pattern,
+                ..
+            } => {
+                changed |= self.transform_expression(inner);
+                changed |= self.transform_expression(pattern);
+            }
+            Expr::InSubquery {
+                expr: inner,
+                subquery,
+                ..
+            } => {
+                changed |= self.transform_expression(inner);
+                changed |= self.transform_query(subquery);
+            }
+            Expr::Subquery(query) | Expr::Exists { subquery: query, .. } => {
+                changed |= self.transform_query(query);
+            }
+            Expr::Function(function) => {
+                changed |= self.transform_function(function);
+            }
+            _ => {}
+        }
+
+        changed
+    }
+
+    fn transform_function(&self, function: &mut Function) -> bool {
+        let mut changed = false;
+
+        let name = function.name.to_string();
+        let renamed = if name.eq_ignore_ascii_case("now") {
+            Some("CURRENT_TIMESTAMP")
+        } else if name.eq_ignore_ascii_case("random") {
+            Some("RANDOM")
+        } else {
+            None
+        };
+        if let Some(renamed) = renamed {
+            function.name = ObjectName(vec![ObjectNamePart::Identifier(Ident::new(renamed))]);
+            changed = true;
+        }
+
+        if let FunctionArguments::List(arg_list) = &mut function.args {
+            for arg in &mut arg_list.args {
+                if let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) = arg {
+                    changed |= self.transform_expression(arg_expr);
+                }
+            }
+        }
+
+        changed
+    }
+
+    fn transform_query(&self, query: &mut Query) -> bool {
+        self.transform_set_expr(&mut query.body)
+    }
+
+    fn transform_set_expr(&self, set_expr: &mut SetExpr) -> bool {
+        let mut changed = false;
+
+        match set_expr {
+            SetExpr::Select(select) => {
+                for item in &mut select.projection {
+                    if let SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } =
+                        item
+                    {
+                        changed |= self.transform_expression(expr);
+                    }
+                }
+                for table in &mut select.from {
+                    changed |= self.transform_table_with_joins(table);
+                }
+                if let Some(selection) = &mut select.selection {
+                    changed |= self.transform_expression(selection);
+                }
+                if let Some(having) = &mut select.having {
+                    changed |= self.transform_expression(having);
+                }
+            }
+            SetExpr::Query(query) => {
+                changed |= self.transform_query(query);
+            }
+            SetExpr::SetOperation { left, right, .. } => {
+                changed |= self.transform_set_expr(left);
+                changed |= self.transform_set_expr(right);
+            }
+            SetExpr::Values(values) => {
+                for row in &mut values.rows {
+                    for expr in row {
+                        changed |= self.transform_expression(expr);
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        changed
+    }
+
+    fn transform_table_with_joins(&self, table: &mut TableWithJoins) -> bool {
+        let mut changed = false;
+
+        for join in &mut table.joins {
+            let constraint = match &mut join.join_operator {
+                JoinOperator::Join(c)
+                | JoinOperator::Inner(c)
+                | JoinOperator::Left(c)
+                | JoinOperator::LeftOuter(c)
+                | JoinOperator::Right(c)
+                | JoinOperator::RightOuter(c)
+                | JoinOperator::FullOuter(c) => Some(c),
+                _ => None,
+            };
+            if let Some(JoinConstraint::On(expr)) = constraint {
+                changed |= self.transform_expression(expr);
+            }
+        }
+
+        changed
+    }
+
+    fn transform_column(&self, column: &mut ColumnDef) -> bool {
+        let mut changed = false;
+
+        // Snowflake has no SERIAL pseudo-types; the column keeps its integer
+        // type and gets an AUTOINCREMENT identity instead.
+        if let DataType::Custom(name, _) = &column.data_type {
+            let integer = match name.to_string().to_uppercase().as_str() {
+                "SERIAL" | "SERIAL4" => Some(DataType::Integer(None)),
+                "BIGSERIAL" | "SERIAL8" => Some(DataType::BigInt(None)),
+                "SMALLSERIAL" | "SERIAL2" => Some(DataType::SmallInt(None)),
+                _ => None,
+            };
+            if let Some(integer) = integer {
+                column.data_type = integer;
+                column.options.push(ColumnOptionDef {
+                    name: None,
+                    option: ColumnOption::Identity(IdentityPropertyKind::Autoincrement(
+                        IdentityProperty {
+                            parameters: None,
+                            order: None,
+                        },
+                    )),
+                });
+                changed = true;
+            }
+        }
+        changed |= transform_data_type(&mut column.data_type);
+
+        for option in &mut column.options {
+            if let ColumnOption::Default(expr) = &mut option.option {
+                changed |= self.transform_expression(expr);
+            }
+        }
+
+        changed
+    }
+
+    fn transform_snowflake_statement(&self, stmt: &mut Statement) -> bool {
+        let mut changed = false;
+
+        match stmt {
+            Statement::Query(query) => {
+                changed |= self.transform_query(query);
+            }
+            Statement::Insert(insert) => {
+                if let Some(source) = &mut insert.source {
+                    changed |= self.transform_query(source);
+                }
+            }
+            Statement::Update {
+                assignments,
+                selection,
+                ..
+            } => {
+                for assignment in assignments {
+                    changed |= self.transform_expression(&mut assignment.value);
+                }
+                if let Some(selection) = selection {
+                    changed |= self.transform_expression(selection);
+                }
+            }
+            Statement::Delete(Delete {
+                selection: Some(selection),
+                ..
+            }) => {
+                changed |= self.transform_expression(selection);
+            }
+            Statement::CreateView { query, .. } => {
+                changed |= self.transform_query(query);
+            }
+            Statement::CreateTable(create_table) => {
+                for column in &mut create_table.columns {
+                    changed |= self.transform_column(column);
+                }
+                if let Some(query) = &mut create_table.query {
+                    changed |= self.transform_query(query);
+                }
+            }
+            _ => {}
+        }
+
+        changed
+    }
+}
+
+/// Map PostgreSQL types onto Snowflake's: strings are all VARCHAR, binary is
+/// BINARY, and semi-structured data lives in VARIANT or an untyped ARRAY.
+fn transform_data_type(data_type: &mut DataType) -> bool {
+    let mapped = match data_type {
+        DataType::Text => DataType::Varchar(None),
+        DataType::Bytea => DataType::Binary(None),
+        DataType::JSON | DataType::JSONB => {
+            DataType::Custom(ObjectName(vec![ObjectNamePart::Identifier(Ident::new("VARIANT"))]), vec![])
+        }
+        DataType::Array(ArrayElemTypeDef::None) => return false,
+        DataType::Array(_) => DataType::Array(ArrayElemTypeDef::None),
+        _ => return false,
+    };
+    *data_type = mapped;
+    true
+}
+
+impl DialectTransformationEngine for SnowflakeTransformationEngine {
+    fn dialect(&self) -> Dialect {
+        Dialect::Snowflake
+    }
+
+    fn transform_statement(&self, mut stmt: Statement) -> TransformationResult<Statement> {
+        self.transform_snowflake_statement(&mut stmt);
+        Ok(stmt)
+    }
+
+    fn transform_statements(&self, statements: &[Statement]) -> TransformationResult<Vec<Statement>> {
+        statements
+            .iter()
+            .map(|stmt| self.transform_statement(stmt.clone()))
+            .collect()
+    }
+
+    fn apply_post_processing_rules(&self, sql: &str) -> TransformationResult<String> {
+        Ok(sql.to_string())
+    }
+
+    fn validate_statement_for_hana(&self, _stmt: &Statement) -> TransformationResult<Vec<String>> {
+        Ok(vec![])
+    }
+
+    fn name(&self) -> &'static str {
+        "Snowflake Transformation Engine"
+    }
+
+    fn capabilities(&self) -> DialectCapabilities {
+        CAPABILITIES
+    }
+}
@@ -49,6 +49,9 @@ pub enum TransformationError {
     #[error("Expression transformation error: {message}")]
     ExpressionError { message: String },
 
+    #[error("Generated {dialect} SQL failed validation: {message}")]
+    OutputValidationError { dialect: String, message: String },
+
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
@@ -141,6 +144,10 @@ impl Clone for TransformationError {
             Self::ExpressionError { message } => Self::ExpressionError {
                 message: message.clone(),
             },
+            Self::OutputValidationError { dialect, message } => Self::OutputValidationError {
+                dialect: dialect.clone(),
+                message: message.clone(),
+            },
             Self::IoError(e) => Self::IoError(std::io::Error::new(e.kind(), e.to_string())),
             #[cfg(feature = "json_output")]
             Self::SerializationError(e) => {
@@ -212,6 +219,13 @@ impl TransformationError {
             message: message.to_string(),
         }
     }
+
+    pub fn output_validation(dialect: &str, message: &str) -> Self {
+        Self::OutputValidationError {
+            dialect: dialect.to_string(),
+            message: message.to_string(),
+        }
+    }
 }
 
 impl TransformationWarning {
@@ -299,3 +313,20 @@ pub struct EnhancedTransformationMetadata {
     pub warnings: Vec<String>,
     pub performance_metrics: PerformanceMetrics,
 }
+
+/// A statement from a stream that could not be transformed.
+#[derive(Debug, Clone)]
+pub struct StatementError {
+    /// Zero-based position of the statement in the input.
+    pub index: usize,
+    pub sql: String,
+    pub error: TransformationError,
+}
+
+/// Outcome of [`crate::SqlTransformer::transform_reader`].
+#[derive(Debug, Clone, Default)]
+pub struct StreamSummary {
+    pub statements: usize,
+    pub transformed: usize,
+    pub errors: Vec<StatementError>,
+}
@@ -0,0 +1,94 @@
+//! Carry `--` and `/* */` comments across a transformation. sqlparser drops
+//! comments while parsing, so they are collected from the token stream up
+//! front and written back in front of the statement they preceded.
+
+use crate::error::{TransformationError, TransformationResult};
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::tokenizer::{Token, Tokenizer, Whitespace};
+
+#[derive(Debug, Default, PartialEq)]
+pub struct StatementComments {
+    /// Comments before each statement, indexed like the parsed statements.
+    pub leading: Vec<Vec<String>>,
+    /// Comments after the last statement.
+    pub trailing: Vec<String>,
+}
+
+impl StatementComments {
+    pub fn is_empty(&self) -> bool {
+        self.trailing.is_empty() && self.leading.iter().all(|c| c.is_empty())
+    }
+
+    /// Join already generated statements (each terminated), putting every
+    /// statement's leading comments on their own lines above it.
+    pub fn attach(&self, statements: &[String]) -> String {
+        let mut result = String::new();
+
+        for (i, stmt) in statements.iter().enumerate() {
+            if i > 0 {
+                result.push_str("\n\n");
+            }
+            for comment in self.leading.get(i).into_iter().flatten() {
+                result.push_str(comment);
+                result.push('\n');
+            }
+            result.push_str(stmt);
+        }
+
+        // Comment groups without a statement to sit on (there should be none)
+        // are kept at the end rather than dropped.
+        let orphaned = self.leading.iter().skip(statements.len()).flatten();
+        for comment in orphaned.chain(&self.trailing) {
+            result.push('\n');
+            result.push_str(comment);
+        }
+
+        result
+    }
+}
+
+/// Collect the comments of `sql`, grouped by the statement they precede.
+/// Comments inside a statement are not kept.
+pub fn extract_comments(sql: &str) -> TransformationResult<StatementComments> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+        .tokenize_with_location()
+        .map_err(|e| TransformationError::ParseError {
+            message: e.to_string(),
+            line: e.location.line as usize,
+            column: e.location.column as usize,
+        })?;
+
+    let mut comments = StatementComments::default();
+    let mut pending = Vec::new();
+    let mut in_statement = false;
+
+    for token in &tokens {
+        match &token.token {
+            Token::Whitespace(Whitespace::SingleLineComment { comment, prefix }) => {
+                if !in_statement {
+                    pending.push(format!("{}{}", prefix, comment.trim_end()));
+                }
+            }
+            Token::Whitespace(Whitespace::MultiLineComment(comment)) => {
+                if !in_statement {
+                    pending.push(format!("/*{}*/", comment));
+                }
+            }
+            Token::Whitespace(_) | Token::EOF => {}
+            // The parser skips empty statements, so only a semicolon that
+            // closes a real statement starts the next group.
+            Token::SemiColon => {
+                in_statement = false;
+            }
+            _ => {
+                if !in_statement {
+                    comments.leading.push(std::mem::take(&mut pending));
+                    in_statement = true;
+                }
+            }
+        }
+    }
+    comments.trailing = pending;
+
+    Ok(comments)
+}
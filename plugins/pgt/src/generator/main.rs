@@ -1,20 +1,28 @@
-use crate::error::TransformationResult;
+use crate::error::{TransformationError, TransformationResult};
 use crate::dialects::hana::post_processor::PostProcessor;
 use sqlparser::ast::Statement;
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
 
 pub fn generate_hana_sql(statements: &[Statement]) -> TransformationResult<String> {
+    generate_statements(statements, ";")
+}
+
+/// Generate `statements` in order, each ending in `terminator` and separated
+/// by a blank line.
+pub fn generate_statements(
+    statements: &[Statement],
+    terminator: &str,
+) -> TransformationResult<String> {
     let mut result = String::new();
 
     for (i, stmt) in statements.iter().enumerate() {
         if i > 0 {
-            result.push_str(";\n\n");
+            result.push_str("\n\n");
         }
 
         result.push_str(&format!("{}", stmt));
-    }
-
-    if !statements.is_empty() {
-        result.push(';');
+        result.push_str(terminator);
     }
 
     let post_processor = PostProcessor::new();
@@ -22,3 +30,48 @@ pub fn generate_hana_sql(statements: &[Statement]) -> TransformationResult<Strin
 
     Ok(processed_sql)
 }
+
+/// Rewrite every double-quoted identifier in `sql` to use `quote` instead,
+/// e.g. backticks for MySQL. Tokenizing keeps string literals and comments
+/// that merely contain `"` untouched.
+pub fn requote_identifiers(sql: &str, quote: char) -> TransformationResult<String> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql)
+        .tokenize_with_location()
+        .map_err(|e| TransformationError::ParseError {
+            message: e.to_string(),
+            line: e.location.line as usize,
+            column: e.location.column as usize,
+        })?;
+
+    // Token spans are 1-based (line, column) in characters.
+    let mut line_starts = vec![0];
+    line_starts.extend(sql.char_indices().filter(|(_, c)| *c == '\n').map(|(i, _)| i + 1));
+    let offset = |line: u64, column: u64| -> usize {
+        let start = line_starts[(line as usize).saturating_sub(1)];
+        sql[start..]
+            .char_indices()
+            .nth((column as usize).saturating_sub(1))
+            .map(|(i, _)| start + i)
+            .unwrap_or(sql.len())
+    };
+
+    let mut result = String::with_capacity(sql.len());
+    let mut copied = 0;
+    for token in &tokens {
+        if let Token::Word(word) = &token.token {
+            if word.quote_style == Some('"') {
+                let start = offset(token.span.start.line, token.span.start.column);
+                let end = offset(token.span.end.line, token.span.end.column);
+                result.push_str(&sql[copied..start]);
+                let escaped = word.value.replace(quote, &format!("{quote}{quote}"));
+                result.push(quote);
+                result.push_str(&escaped);
+                result.push(quote);
+                copied = end;
+            }
+        }
+    }
+    result.push_str(&sql[copied..]);
+
+    Ok(result)
+}
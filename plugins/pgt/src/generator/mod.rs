@@ -1,9 +1,24 @@
+pub mod comments;
 pub mod dialect;
 pub mod main;
+pub mod validate;
 
+use crate::dialects::Dialect;
 use crate::error::TransformationResult;
 use sqlparser::ast::Statement;
 
 pub fn generate_sql(statements: &[Statement]) -> TransformationResult<String> {
     main::generate_hana_sql(statements)
 }
+
+pub fn generate_sql_for_dialect(
+    dialect: Dialect,
+    statements: &[Statement],
+    terminator: &str,
+) -> TransformationResult<String> {
+    let sql = main::generate_statements(statements, terminator)?;
+    match dialect {
+        Dialect::MySql => main::requote_identifiers(&sql, '`'),
+        Dialect::Hana | Dialect::DuckDb | Dialect::Snowflake => Ok(sql),
+    }
+}
@@ -0,0 +1,81 @@
+//! Checks that generated SQL is accepted by the target dialect, so a
+//! transformation that produces broken output fails instead of handing the
+//! broken SQL on.
+//!
+//! DuckDB, MySQL and Snowflake output is re-parsed with the sqlparser
+//! dialect of the target. sqlparser has no HANA dialect, so HANA output only
+//! gets a structural check: it must tokenize, which catches unterminated
+//! strings and quoted identifiers, its brackets must balance, and it must
+//! not contain empty statements.
+
+use crate::dialects::Dialect;
+use crate::error::{TransformationError, TransformationResult};
+use crate::generator::dialect::HanaDialect;
+use sqlparser::dialect::{DuckDbDialect, MySqlDialect, SnowflakeDialect};
+use sqlparser::parser::Parser;
+use sqlparser::tokenizer::{Token, Tokenizer};
+
+pub fn validate_output(dialect: Dialect, sql: &str) -> TransformationResult<()> {
+    let result = match dialect {
+        Dialect::Hana => check_structure(sql),
+        Dialect::DuckDb => reparse(&DuckDbDialect {}, sql),
+        Dialect::MySql => reparse(&MySqlDialect {}, sql),
+        Dialect::Snowflake => reparse(&SnowflakeDialect {}, sql),
+    };
+    result.map_err(|message| TransformationError::output_validation(dialect.name(), &message))
+}
+
+fn reparse(dialect: &dyn sqlparser::dialect::Dialect, sql: &str) -> Result<(), String> {
+    let statements = Parser::parse_sql(dialect, sql).map_err(|e| e.to_string())?;
+    if statements.is_empty() {
+        return Err("no statements".to_string());
+    }
+    Ok(())
+}
+
+fn check_structure(sql: &str) -> Result<(), String> {
+    let tokens = Tokenizer::new(&HanaDialect::new(), sql)
+        .tokenize()
+        .map_err(|e| e.to_string())?;
+
+    let mut open = Vec::new();
+    let mut statements = 0;
+    let mut statement_has_tokens = false;
+    for token in &tokens {
+        match token {
+            Token::Whitespace(_) => continue,
+            Token::LParen => open.push(')'),
+            Token::LBracket => open.push(']'),
+            Token::RParen | Token::RBracket => {
+                let close = if *token == Token::RParen { ')' } else { ']' };
+                if open.pop() != Some(close) {
+                    return Err(format!("unbalanced '{}'", close));
+                }
+            }
+            Token::SemiColon => {
+                if !statement_has_tokens {
+                    return Err("empty statement".to_string());
+                }
+                if let Some(close) = open.last() {
+                    return Err(format!("statement ends before a '{}'", close));
+                }
+                statements += 1;
+                statement_has_tokens = false;
+                continue;
+            }
+            _ => {}
+        }
+        statement_has_tokens = true;
+    }
+
+    if let Some(close) = open.last() {
+        return Err(format!("missing '{}'", close));
+    }
+    if statement_has_tokens {
+        statements += 1;
+    }
+    if statements == 0 {
+        return Err("no statements".to_string());
+    }
+    Ok(())
+}
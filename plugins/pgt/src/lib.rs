@@ -7,11 +7,12 @@
 //!
 //! Currently supported target dialects:
 //! - **SAP HANA** - Full PostgreSQL to HANA transformation support
+//! - **trexsql** (DuckDB) - PostgreSQL syntax kept, with DuckDB rewrites
+//! - **MySQL** - casts, booleans, UUIDs and backtick identifier quoting
+//! - **Snowflake** - SERIAL identities, VARCHAR/BINARY/VARIANT types, timestamps
 //!
 //! Future planned dialects:
-//! - trexsql
 //! - ClickHouse
-//! - Snowflake
 //!
 //! ## Quick Start
 //!
@@ -39,38 +40,48 @@
 //! let transformer = SqlTransformer::with_config(pgt::TransformationConfig::default()).unwrap();
 //! ```
 
+pub mod cache;
 pub mod config;
 pub mod dialects;
 pub mod error;
 pub mod generator;
+pub mod metrics;
 pub mod parser;
 pub mod rules;
 pub mod utils;
 
+pub use cache::ParseCache;
 pub use config::{DataTypeConfig, FunctionConfig, RulesConfig, TransformationConfig};
-pub use dialects::Dialect;
+pub use dialects::{Dialect, DialectCapabilities};
 pub use error::{
-    DetailedResult, EnhancedTransformationMetadata, PerformanceMetrics, TransformationError,
-    TransformationResult, TransformationWarning,
+    DetailedResult, EnhancedTransformationMetadata, PerformanceMetrics, StatementError,
+    StreamSummary, TransformationError, TransformationResult, TransformationWarning,
 };
 pub use dialects::hana::TransformationMetadata;
+pub use metrics::TransformMetrics;
 
 use log::{debug, info};
 use sqlparser::dialect::PostgreSqlDialect;
 use sqlparser::parser::Parser;
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use parser::splitter::StatementSplitter;
+use std::io::{BufReader, Read, Write};
+use std::sync::Arc;
+use std::time::Instant;
+
+use metrics::MetricsRecorder;
 
 struct CachedParser {
     dialect: PostgreSqlDialect,
-    parse_cache: Arc<Mutex<HashMap<String, Vec<sqlparser::ast::Statement>>>>,
+    parse_cache: Arc<ParseCache>,
+    metrics: Option<Arc<MetricsRecorder>>,
 }
 
 impl CachedParser {
-    fn new() -> Self {
+    fn new(parse_cache: Arc<ParseCache>, metrics: Option<Arc<MetricsRecorder>>) -> Self {
         Self {
             dialect: PostgreSqlDialect {},
-            parse_cache: Arc::new(Mutex::new(HashMap::with_capacity(100))),
+            parse_cache,
+            metrics,
         }
     }
 
@@ -78,27 +89,22 @@ impl CachedParser {
         &self,
         sql: &str,
     ) -> Result<Vec<sqlparser::ast::Statement>, sqlparser::parser::ParserError> {
-        if let Ok(cache) = self.parse_cache.lock() {
-            if let Some(cached_statements) = cache.get(sql) {
-                return Ok(cached_statements.clone());
-            }
+        let cached = self.parse_cache.get(sql);
+        if let Some(metrics) = &self.metrics {
+            metrics.record_cache_lookup(cached.is_some());
+        }
+        if let Some(cached_statements) = cached {
+            return Ok(cached_statements);
         }
 
         let statements = Parser::parse_sql(&self.dialect, sql)?;
-
-        if let Ok(mut cache) = self.parse_cache.lock() {
-            if cache.len() < 1000 {
-                cache.insert(sql.to_string(), statements.clone());
-            }
-        }
+        self.parse_cache.insert(sql, statements.clone());
 
         Ok(statements)
     }
 
     fn clear_cache(&self) {
-        if let Ok(mut cache) = self.parse_cache.lock() {
-            cache.clear();
-        }
+        self.parse_cache.clear();
     }
 }
 
@@ -107,16 +113,39 @@ pub struct SqlTransformer {
     dialect: Dialect,
     transformer: Box<dyn dialects::DialectTransformationEngine>,
     parser: CachedParser,
+    metrics: Option<Arc<MetricsRecorder>>,
 }
 
 impl SqlTransformer {
     pub fn new(config: TransformationConfig, dialect: Dialect) -> Result<Self, TransformationError> {
+        Self::with_parts(config, dialect, None, None)
+    }
+
+    /// Transformer that parses through `cache`, shared with every other
+    /// transformer holding it, instead of a cache of its own. See
+    /// [`ParseCache`].
+    pub fn with_shared_cache(
+        cache: Arc<ParseCache>,
+        config: TransformationConfig,
+        dialect: Dialect,
+    ) -> Result<Self, TransformationError> {
+        Self::with_parts(config, dialect, Some(cache), None)
+    }
+
+    fn with_parts(
+        config: TransformationConfig,
+        dialect: Dialect,
+        parse_cache: Option<Arc<ParseCache>>,
+        metrics: Option<Arc<MetricsRecorder>>,
+    ) -> Result<Self, TransformationError> {
         let transformer = dialects::DialectEngineFactory::create_engine(dialect, &config)?;
+        let parse_cache = parse_cache.unwrap_or_default();
         Ok(Self {
             config,
             dialect,
             transformer,
-            parser: CachedParser::new(),
+            parser: CachedParser::new(parse_cache, metrics.clone()),
+            metrics,
         })
     }
 
@@ -132,7 +161,34 @@ impl SqlTransformer {
         self.dialect
     }
 
+    pub fn capabilities(&self) -> DialectCapabilities {
+        self.transformer.capabilities()
+    }
+
     pub fn transform(&self, sql: &str) -> TransformationResult<String> {
+        let start = Instant::now();
+        let result = self.transform_terminated(sql, &self.config.formatting.statement_terminator);
+        self.record_transform(start, result.as_ref().err());
+        result
+    }
+
+    /// Counters aggregated over every transform since the transformer was
+    /// built. All zero unless it was built with
+    /// [`SqlTransformerBuilder::with_metrics`].
+    pub fn metrics(&self) -> TransformMetrics {
+        self.metrics
+            .as_ref()
+            .map(|metrics| metrics.snapshot())
+            .unwrap_or_default()
+    }
+
+    fn record_transform(&self, start: Instant, error: Option<&TransformationError>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_transform(start.elapsed(), error);
+        }
+    }
+
+    fn transform_terminated(&self, sql: &str, terminator: &str) -> TransformationResult<String> {
         let statements = self
             .parser
             .parse(sql)
@@ -142,14 +198,72 @@ impl SqlTransformer {
                 column: 0,
             })?;
 
-        let transformed_statements = self.transformer.transform_statements(&statements)?;
-        let mut generated_sql = self.generate_sql(&transformed_statements)?;
+        let groups = self.transformer.transform_statement_groups(&statements)?;
+
+        if self.config.formatting.preserve_comments {
+            let comments = generator::comments::extract_comments(sql)?;
+            if !comments.is_empty() {
+                // One entry per input statement, so each keeps its comments.
+                let generated = groups
+                    .iter()
+                    .map(|group| {
+                        let sql = self.generate_sql(group, terminator)?;
+                        self.transformer.apply_post_processing_rules(&sql)
+                    })
+                    .collect::<TransformationResult<Vec<_>>>()?;
+                return Ok(comments.attach(&generated));
+            }
+        }
+
+        let transformed_statements: Vec<_> = groups.into_iter().flatten().collect();
+        let mut generated_sql = self.generate_sql(&transformed_statements, terminator)?;
 
         generated_sql = self.transformer.apply_post_processing_rules(&generated_sql)?;
 
         Ok(generated_sql)
     }
 
+    /// Like [`Self::transform`], but fails with
+    /// [`TransformationError::OutputValidationError`] when the output does
+    /// not parse in the target dialect. HANA output is checked structurally,
+    /// as there is no HANA parser to run it through.
+    pub fn transform_and_validate(&self, sql: &str) -> TransformationResult<String> {
+        let start = Instant::now();
+        let result = self.transform_and_validate_unrecorded(sql);
+        self.record_transform(start, result.as_ref().err());
+        result
+    }
+
+    fn transform_and_validate_unrecorded(&self, sql: &str) -> TransformationResult<String> {
+        // The target parsers only know `;`, so other terminators are checked
+        // on a `;` rendering of the same statements.
+        let output = self.transform_terminated(sql, ";")?;
+        generator::validate::validate_output(self.dialect, &output)?;
+        if self.config.formatting.statement_terminator == ";" {
+            Ok(output)
+        } else {
+            self.transform_terminated(sql, &self.config.formatting.statement_terminator)
+        }
+    }
+
+    /// [`Self::transform`] on tokio's blocking pool, so async servers can
+    /// call it without stalling their executor. Clones of the `Arc` share
+    /// the parse cache.
+    #[cfg(feature = "async")]
+    pub async fn transform_async(self: &Arc<Self>, sql: &str) -> TransformationResult<String> {
+        let transformer = Arc::clone(self);
+        let sql = sql.to_string();
+        tokio::task::spawn_blocking(move || transformer.transform(&sql))
+            .await
+            .map_err(|e| TransformationError::IoError(std::io::Error::other(e)))?
+    }
+
+    /// Warnings raised while transforming the last input, e.g. constructs
+    /// that were kept but may not behave the same on the target. Drains them.
+    pub fn take_warnings(&self) -> Vec<TransformationWarning> {
+        self.transformer.take_warnings()
+    }
+
     pub fn can_transform(&self, sql: &str) -> bool {
         self.parser.parse(sql).is_ok()
     }
@@ -158,7 +272,68 @@ impl SqlTransformer {
         sqls.into_iter().map(|sql| self.transform(sql)).collect()
     }
 
+    /// Transform a stream of statements, e.g. a large `.sql` dump, writing
+    /// each result to `writer` as soon as it is ready. Statements that fail
+    /// are left out of the output and reported in the summary; only I/O
+    /// errors stop the stream.
+    pub fn transform_reader<R: Read, W: Write>(
+        &self,
+        reader: R,
+        mut writer: W,
+    ) -> TransformationResult<StreamSummary> {
+        let mut summary = StreamSummary::default();
+        let mut wrote_any = false;
+
+        for statement in StatementSplitter::new(BufReader::new(reader)) {
+            let statement = statement?;
+            let index = summary.statements;
+            summary.statements += 1;
+
+            match self.transform(&statement) {
+                Ok(sql) => {
+                    let sql = sql.trim();
+                    if !sql.is_empty() {
+                        if wrote_any {
+                            writer.write_all(b"\n")?;
+                        }
+                        writer.write_all(sql.as_bytes())?;
+                        writer.write_all(b"\n")?;
+                        wrote_any = true;
+                    }
+                    summary.transformed += 1;
+                }
+                Err(error) => summary.errors.push(StatementError {
+                    index,
+                    sql: statement,
+                    error,
+                }),
+            }
+        }
+
+        writer.flush()?;
+        Ok(summary)
+    }
+
+    /// [`Self::transform_reader`] from one file into another.
+    pub fn transform_file<P: AsRef<std::path::Path>, Q: AsRef<std::path::Path>>(
+        &self,
+        input: P,
+        output: Q,
+    ) -> TransformationResult<StreamSummary> {
+        let reader = std::fs::File::open(input)?;
+        utils::ensure_parent_dir(&output)?;
+        let writer = std::io::BufWriter::new(std::fs::File::create(output)?);
+        self.transform_reader(reader, writer)
+    }
+
     pub fn transform_detailed(&self, sql: &str) -> DetailedResult<String> {
+        let start = Instant::now();
+        let detailed = self.transform_detailed_unrecorded(sql);
+        self.record_transform(start, detailed.result.as_ref().err());
+        detailed
+    }
+
+    fn transform_detailed_unrecorded(&self, sql: &str) -> DetailedResult<String> {
         debug!("Detailed transformation");
 
         if let Err(e) = self.config.validate() {
@@ -209,6 +384,7 @@ impl SqlTransformer {
         let transformed_statements = match self.transformer.transform_statements(&statements) {
             Ok(stmts) => {
                 transformations_applied.push("Transformation complete".to_string());
+                warnings.extend(self.transformer.take_warnings().into_iter().map(|w| w.message));
                 stmts
             }
             Err(e) => {
@@ -240,7 +416,8 @@ impl SqlTransformer {
             }
         };
 
-        let mut hana_sql = match self.generate_sql(&transformed_statements) {
+        let terminator = &self.config.formatting.statement_terminator;
+        let mut hana_sql = match self.generate_sql(&transformed_statements, terminator) {
             Ok(sql) => {
                 transformations_applied.push("SQL generated".to_string());
                 sql
@@ -328,8 +505,9 @@ impl SqlTransformer {
     fn generate_sql(
         &self,
         statements: &[sqlparser::ast::Statement],
+        terminator: &str,
     ) -> TransformationResult<String> {
-        let sql = generator::generate_sql(&statements)?;
+        let sql = generator::generate_sql_for_dialect(self.dialect, statements, terminator)?;
         Ok(sql)
     }
 
@@ -347,16 +525,15 @@ impl SqlTransformer {
         SqlTransformerBuilder::new()
     }
 
+    /// Empty the parse cache, including for the other transformers sharing
+    /// it.
     pub fn clear_caches(&self) {
         self.parser.clear_cache();
     }
 
+    /// Entries in the parse cache and its capacity.
     pub fn cache_stats(&self) -> (usize, usize) {
-        if let Ok(cache) = self.parser.parse_cache.lock() {
-            (cache.len(), cache.capacity())
-        } else {
-            (0, 0)
-        }
+        (self.parser.parse_cache.len(), self.parser.parse_cache.capacity())
     }
 
     pub fn validate_hana_compatibility(&self, sql: &str) -> TransformationResult<Vec<String>> {
@@ -399,6 +576,8 @@ impl Default for SqlTransformer {
 pub struct SqlTransformerBuilder {
     config: TransformationConfig,
     dialect: Dialect,
+    parse_cache: Option<Arc<ParseCache>>,
+    metrics: bool,
 }
 
 impl SqlTransformerBuilder {
@@ -406,6 +585,8 @@ impl SqlTransformerBuilder {
         Self {
             config: TransformationConfig::default(),
             dialect: Dialect::default(),
+            parse_cache: None,
+            metrics: false,
         }
     }
 
@@ -432,8 +613,21 @@ impl SqlTransformerBuilder {
         self
     }
 
+    /// Record the counters returned by [`SqlTransformer::metrics`].
+    pub fn with_metrics(mut self, enabled: bool) -> Self {
+        self.metrics = enabled;
+        self
+    }
+
+    /// Parse through `cache`, as [`SqlTransformer::with_shared_cache`] does.
+    pub fn with_shared_cache(mut self, cache: Arc<ParseCache>) -> Self {
+        self.parse_cache = Some(cache);
+        self
+    }
+
     pub fn build(self) -> Result<SqlTransformer, TransformationError> {
-        SqlTransformer::new(self.config, self.dialect)
+        let metrics = self.metrics.then(|| Arc::new(MetricsRecorder::default()));
+        SqlTransformer::with_parts(self.config, self.dialect, self.parse_cache, metrics)
     }
 }
 
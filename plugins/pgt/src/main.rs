@@ -16,7 +16,7 @@ struct Cli {
     #[arg(long)]
     quiet: bool,
 
-    /// Target SQL dialect (hana)
+    /// Target SQL dialect (hana, duckdb, mysql, snowflake)
     #[arg(short, long, default_value = "hana")]
     dialect: String,
 }
@@ -0,0 +1,80 @@
+//! Counters aggregated across every call to a [`crate::SqlTransformer`].
+//!
+//! Recording is off unless the transformer is built with
+//! [`crate::SqlTransformerBuilder::with_metrics`]. The counters are atomics,
+//! so a transformer shared between threads records without locking.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::error::TransformationError;
+
+/// Snapshot of a transformer's counters, from
+/// [`crate::SqlTransformer::metrics`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransformMetrics {
+    /// Calls to `transform`, `transform_and_validate` and
+    /// `transform_detailed`, including the ones that failed. Batch and
+    /// stream methods count each statement they transform.
+    pub transforms: u64,
+    pub parse_errors: u64,
+    pub unsupported_features: u64,
+    /// Parse cache lookups that found the statements already parsed.
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// Time spent in the counted calls.
+    pub total_transform_time: Duration,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    transforms: AtomicU64,
+    parse_errors: AtomicU64,
+    unsupported_features: AtomicU64,
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    transform_time_ns: AtomicU64,
+}
+
+impl MetricsRecorder {
+    pub(crate) fn record_transform(
+        &self,
+        elapsed: Duration,
+        error: Option<&TransformationError>,
+    ) {
+        self.transforms.fetch_add(1, Ordering::Relaxed);
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.transform_time_ns.fetch_add(nanos, Ordering::Relaxed);
+        match error {
+            Some(TransformationError::ParseError { .. }) => {
+                self.parse_errors.fetch_add(1, Ordering::Relaxed);
+            }
+            Some(TransformationError::UnsupportedFeature { .. }) => {
+                self.unsupported_features.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn record_cache_lookup(&self, hit: bool) {
+        let counter = if hit {
+            &self.cache_hits
+        } else {
+            &self.cache_misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> TransformMetrics {
+        TransformMetrics {
+            transforms: self.transforms.load(Ordering::Relaxed),
+            parse_errors: self.parse_errors.load(Ordering::Relaxed),
+            unsupported_features: self.unsupported_features.load(Ordering::Relaxed),
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            total_transform_time: Duration::from_nanos(
+                self.transform_time_ns.load(Ordering::Relaxed),
+            ),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+pub mod splitter;
+
 use crate::error::{TransformationError, TransformationResult};
 use sqlparser::dialect::PostgreSqlDialect;
 use sqlparser::parser::Parser;
@@ -0,0 +1,237 @@
+//! Split a stream of PostgreSQL into statements without reading it all into
+//! memory. Semicolons inside string literals, quoted identifiers, comments
+//! and dollar-quoted bodies do not end a statement.
+
+use std::io::{self, BufRead};
+
+#[derive(Debug, PartialEq)]
+enum State {
+    Normal,
+    SingleQuote { backslash_escapes: bool },
+    DoubleQuote,
+    LineComment,
+    BlockComment(usize),
+    DollarQuote(String),
+}
+
+pub struct StatementSplitter<R: BufRead> {
+    reader: R,
+    line: Vec<char>,
+    pos: usize,
+    state: State,
+    current: String,
+    has_content: bool,
+    done: bool,
+}
+
+impl<R: BufRead> StatementSplitter<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+            pos: 0,
+            state: State::Normal,
+            current: String::new(),
+            has_content: false,
+            done: false,
+        }
+    }
+
+    fn next_line(&mut self) -> io::Result<bool> {
+        let mut buf = String::new();
+        if self.reader.read_line(&mut buf)? == 0 {
+            return Ok(false);
+        }
+        self.line = buf.chars().collect();
+        self.pos = 0;
+        Ok(true)
+    }
+
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.line.get(self.pos + offset).copied()
+    }
+
+    fn take(&mut self, count: usize) {
+        for _ in 0..count {
+            if let Some(c) = self.peek(0) {
+                self.current.push(c);
+                self.pos += 1;
+            }
+        }
+    }
+
+    /// `$tag$` starting at the current position, if there is one.
+    fn dollar_tag(&self) -> Option<String> {
+        if let Some(prev) = self.pos.checked_sub(1).and_then(|i| self.line.get(i)) {
+            if prev.is_alphanumeric() || *prev == '_' || *prev == '$' {
+                return None;
+            }
+        }
+        let mut tag = String::from("$");
+        for (i, c) in self.line[self.pos + 1..].iter().enumerate() {
+            match c {
+                '$' => {
+                    tag.push('$');
+                    return Some(tag);
+                }
+                c if c.is_alphabetic() || *c == '_' || (i > 0 && c.is_ascii_digit()) => {
+                    tag.push(*c)
+                }
+                _ => return None,
+            }
+        }
+        None
+    }
+
+    fn finish_statement(&mut self) -> Option<String> {
+        let statement = std::mem::take(&mut self.current).trim().to_string();
+        self.has_content = false;
+        if statement.is_empty() {
+            None
+        } else {
+            Some(statement)
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for StatementSplitter<R> {
+    /// One statement without its terminating semicolon, with any comments
+    /// that preceded it.
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let c = match self.peek(0) {
+                Some(c) => c,
+                None => match self.next_line() {
+                    Ok(true) => continue,
+                    Ok(false) => {
+                        self.done = true;
+                        // Trailing comments with no statement after them
+                        // are dropped.
+                        if !self.has_content {
+                            return None;
+                        }
+                        return self.finish_statement().map(Ok);
+                    }
+                    Err(e) => {
+                        self.done = true;
+                        return Some(Err(e));
+                    }
+                },
+            };
+
+            match self.state {
+                State::Normal => match c {
+                    ';' => {
+                        self.pos += 1;
+                        // Comments and blank space before a statement stay
+                        // with the statement that follows them.
+                        if self.has_content {
+                            if let Some(statement) = self.finish_statement() {
+                                return Some(Ok(statement));
+                            }
+                        }
+                    }
+                    '\'' => {
+                        let backslash_escapes = self.current.ends_with(['e', 'E'])
+                            && !self
+                                .current
+                                .chars()
+                                .rev()
+                                .nth(1)
+                                .is_some_and(|p| p.is_alphanumeric() || p == '_');
+                        self.state = State::SingleQuote { backslash_escapes };
+                        self.has_content = true;
+                        self.take(1);
+                    }
+                    '"' => {
+                        self.state = State::DoubleQuote;
+                        self.has_content = true;
+                        self.take(1);
+                    }
+                    '-' if self.peek(1) == Some('-') => {
+                        self.state = State::LineComment;
+                        self.take(2);
+                    }
+                    '/' if self.peek(1) == Some('*') => {
+                        self.state = State::BlockComment(1);
+                        self.take(2);
+                    }
+                    '$' => {
+                        self.has_content = true;
+                        match self.dollar_tag() {
+                            Some(tag) => {
+                                let len = tag.chars().count();
+                                self.state = State::DollarQuote(tag);
+                                self.take(len);
+                            }
+                            None => self.take(1),
+                        }
+                    }
+                    c => {
+                        if !c.is_whitespace() {
+                            self.has_content = true;
+                        }
+                        self.take(1);
+                    }
+                },
+                State::SingleQuote { backslash_escapes } => match c {
+                    '\\' if backslash_escapes => self.take(2),
+                    '\'' if self.peek(1) == Some('\'') => self.take(2),
+                    '\'' => {
+                        self.state = State::Normal;
+                        self.take(1);
+                    }
+                    _ => self.take(1),
+                },
+                State::DoubleQuote => match c {
+                    '"' if self.peek(1) == Some('"') => self.take(2),
+                    '"' => {
+                        self.state = State::Normal;
+                        self.take(1);
+                    }
+                    _ => self.take(1),
+                },
+                State::LineComment => {
+                    if c == '\n' {
+                        self.state = State::Normal;
+                    }
+                    self.take(1);
+                }
+                State::BlockComment(depth) => {
+                    if c == '/' && self.peek(1) == Some('*') {
+                        self.state = State::BlockComment(depth + 1);
+                        self.take(2);
+                    } else if c == '*' && self.peek(1) == Some('/') {
+                        self.state = if depth == 1 {
+                            State::Normal
+                        } else {
+                            State::BlockComment(depth - 1)
+                        };
+                        self.take(2);
+                    } else {
+                        self.take(1);
+                    }
+                }
+                State::DollarQuote(ref tag) => {
+                    let len = tag.chars().count();
+                    let closes = tag
+                        .chars()
+                        .enumerate()
+                        .all(|(i, t)| self.peek(i) == Some(t));
+                    if closes {
+                        self.state = State::Normal;
+                        self.take(len);
+                    } else {
+                        self.take(1);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
 pub mod ast_helpers;
 pub mod file_ops;
+pub mod statements;
 pub mod validation;
 
 pub use file_ops::*;
+pub use statements::split_statements;
 pub use validation::{SqlValidator, ValidationResult};
@@ -0,0 +1,18 @@
+//! Split a SQL script held in memory into statements.
+
+use crate::parser::splitter::StatementSplitter;
+
+/// The statements of `sql`, trimmed and without their terminating
+/// semicolons, in order. Semicolons inside single-quoted strings (with `''`
+/// and `E'\''` escapes), quoted identifiers, line and nested block comments,
+/// and dollar-quoted bodies such as `$$ ... $$` or `$fn$ ... $fn$` do not end
+/// a statement. Comments before a statement stay with it; empty statements
+/// and trailing comments are dropped.
+///
+/// Use [`StatementSplitter`] to split a stream without reading it whole.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    // Reading from a `&[u8]` cannot fail.
+    StatementSplitter::new(sql.as_bytes())
+        .map_while(Result::ok)
+        .collect()
+}
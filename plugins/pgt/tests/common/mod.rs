@@ -1,5 +1,8 @@
+// Each test binary compiles this module and uses only some of it.
+#![allow(dead_code)]
+
 use hdbconnect::{Connection, HdbResult};
-use pgt::{SqlTransformer, TransformationConfig};
+use pgt::{Dialect, SqlTransformer, TransformationConfig};
 use std::env;
 use std::sync::Once;
 
@@ -13,6 +16,18 @@ pub fn init_test_env() {
     });
 }
 
+/// HANA transformer with the default configuration
+pub fn hana_transformer() -> SqlTransformer {
+    hana_transformer_with(|_| {})
+}
+
+/// HANA transformer with `configure` applied to the default configuration
+pub fn hana_transformer_with(configure: impl FnOnce(&mut TransformationConfig)) -> SqlTransformer {
+    let mut config = TransformationConfig::default();
+    configure(&mut config);
+    SqlTransformer::new(config, Dialect::Hana).unwrap()
+}
+
 /// Test configuration for integration tests
 pub struct TestConfig {
     pub hana_url: String,
@@ -27,7 +42,7 @@ impl TestConfig {
             env::var("HANA_URL").expect("HANA_URL environment variable must be set in .env file");
 
         let config = TransformationConfig::default();
-        let transformer = SqlTransformer::new(config, pgt::Dialect::Hana)
+        let transformer = SqlTransformer::new(config, Dialect::Hana)
             .expect("Failed to create transformer");
 
         Self {
@@ -0,0 +1,49 @@
+use pgt::config::TransformationConfig;
+use pgt::{Dialect, SqlTransformer};
+
+fn assert_send_sync<T: Send + Sync>() {}
+
+#[test]
+fn test_transformer_is_send_and_sync() {
+    assert_send_sync::<SqlTransformer>();
+}
+
+#[cfg(feature = "async")]
+mod transform_async {
+    use super::*;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_transform_async_matches_transform() {
+        let transformer =
+            Arc::new(SqlTransformer::new(TransformationConfig::default(), Dialect::Hana).unwrap());
+
+        let sql = "SELECT NOW(), id FROM users WHERE name ILIKE 'a%'";
+        let result = transformer.transform_async(sql).await;
+        assert_eq!(result.unwrap(), transformer.transform(sql).unwrap());
+        assert!(transformer.transform_async("SELEC FROM").await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_transforms_share_the_cache() {
+        let transformer =
+            Arc::new(SqlTransformer::new(TransformationConfig::default(), Dialect::DuckDb).unwrap());
+
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let transformer = Arc::clone(&transformer);
+                tokio::spawn(async move {
+                    transformer
+                        .transform_async(&format!("SELECT {} FROM users", i % 2))
+                        .await
+                })
+            })
+            .collect();
+        for task in tasks {
+            assert!(task.await.unwrap().is_ok());
+        }
+
+        let (cached, _) = transformer.cache_stats();
+        assert_eq!(cached, 2);
+    }
+}
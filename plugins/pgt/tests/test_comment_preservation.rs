@@ -0,0 +1,61 @@
+use pgt::config::TransformationConfig;
+use pgt::generator::comments::extract_comments;
+use pgt::{Dialect, SqlTransformer};
+
+fn transformer(preserve_comments: bool) -> SqlTransformer {
+    let mut config = TransformationConfig::default();
+    config.formatting.preserve_comments = preserve_comments;
+    SqlTransformer::new(config, Dialect::Hana).unwrap()
+}
+
+const MIGRATION: &str = "
+-- Users table
+-- keeps one row per account
+CREATE TABLE users (id INTEGER, name TEXT);
+
+/* Backfill */
+INSERT INTO users (id, name) VALUES (1, 'a');
+
+SELECT id FROM users LIMIT 1;
+-- end of migration
+";
+
+#[test]
+fn test_leading_comments_survive_in_order() {
+    let result = transformer(true).transform(MIGRATION).unwrap();
+    assert_eq!(
+        result,
+        "-- Users table\n\
+         -- keeps one row per account\n\
+         CREATE TABLE users (id INTEGER, name CLOB);\n\n\
+         /* Backfill */\n\
+         INSERT INTO users (id, name) VALUES (1, 'a');\n\n\
+         SELECT id FROM users LIMIT 1;\n\
+         -- end of migration"
+    );
+}
+
+#[test]
+fn test_comments_dropped_when_disabled() {
+    let result = transformer(false).transform(MIGRATION).unwrap();
+    assert!(!result.contains("--"), "got: {}", result);
+    assert!(!result.contains("/*"), "got: {}", result);
+
+    // Input without comments is generated the same way either way.
+    let sql = "SELECT 1; SELECT 2";
+    assert_eq!(
+        transformer(true).transform(sql).unwrap(),
+        transformer(false).transform(sql).unwrap()
+    );
+}
+
+#[test]
+fn test_extract_comments_groups_by_statement() {
+    let comments = extract_comments("-- a\nSELECT 1 -- inside\n;;\n/* b */ SELECT 2;").unwrap();
+    assert_eq!(
+        comments.leading,
+        vec![vec!["-- a".to_string()], vec!["/* b */".to_string()]]
+    );
+    assert!(comments.trailing.is_empty());
+    assert!(extract_comments("SELECT 1").unwrap().is_empty());
+}
@@ -0,0 +1,33 @@
+use pgt::config::TransformationConfig;
+use pgt::{Dialect, SqlTransformer};
+
+#[test]
+fn test_hana_capabilities() {
+    let caps = Dialect::Hana.capabilities();
+    assert!(!caps.supports_returning);
+    assert!(!caps.supports_arrays);
+    assert!(!caps.supports_interval_type);
+    assert!(caps.supports_recursive_cte);
+    assert_eq!(caps.identifier_quote_char, '"');
+}
+
+#[test]
+fn test_transformer_reports_engine_capabilities() {
+    for dialect in Dialect::all() {
+        let transformer = SqlTransformer::new(TransformationConfig::default(), *dialect).unwrap();
+        assert_eq!(transformer.capabilities(), dialect.capabilities(), "{}", dialect);
+    }
+
+    assert_eq!(Dialect::MySql.capabilities().identifier_quote_char, '`');
+    assert!(Dialect::DuckDb.capabilities().supports_arrays);
+}
+
+#[test]
+fn test_pick_dialect_by_capability() {
+    let with_returning: Vec<Dialect> = Dialect::all()
+        .iter()
+        .copied()
+        .filter(|d| d.capabilities().supports_returning)
+        .collect();
+    assert_eq!(with_returning, vec![Dialect::DuckDb]);
+}
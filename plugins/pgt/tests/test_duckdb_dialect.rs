@@ -76,5 +76,7 @@ fn test_dialect_metadata() {
     let all_dialects = Dialect::all();
     assert!(all_dialects.contains(&Dialect::Hana));
     assert!(all_dialects.contains(&Dialect::DuckDb));
-    assert_eq!(all_dialects.len(), 2);
+    assert!(all_dialects.contains(&Dialect::MySql));
+    assert!(all_dialects.contains(&Dialect::Snowflake));
+    assert_eq!(all_dialects.len(), 4);
 }
@@ -233,6 +233,56 @@ validate_hana_compatibility = true
         std::env::remove_var("PGT_VALIDATE_COMPATIBILITY");
     }
 
+    #[test]
+    fn test_function_mappings_from_file_override_builtins() {
+        let temp_dir = tempdir().expect("Failed to create temp directory");
+        let config_file = temp_dir.path().join("functions.toml");
+
+        let mut config = TransformationConfig::default();
+        config
+            .functions
+            .custom_mappings
+            .insert("my_pg_fn".to_string(), "my_hana_fn".to_string());
+        config
+            .functions
+            .custom_mappings
+            .insert("random".to_string(), "RAND_SECURE".to_string());
+        config.to_file(&config_file).expect("Failed to write config file");
+
+        let config = TransformationConfig::from_file(&config_file).expect("Failed to load config");
+        let transformer =
+            SqlTransformer::new(config, pgt::Dialect::Hana).expect("Failed to create transformer");
+
+        let result = transformer
+            .transform("SELECT my_pg_fn(id), MY_PG_FN(name), random() FROM users")
+            .expect("Transformation failed");
+        assert_eq!(
+            result,
+            "SELECT my_hana_fn(id), my_hana_fn(name), RAND_SECURE() FROM users;"
+        );
+    }
+
+    #[test]
+    fn test_function_mappings_from_environment() {
+        std::env::set_var("PGT_FUNCTION_MAPPINGS", "my_pg_fn=my_hana_fn, broken, other_fn=OTHER");
+
+        let config = TransformationConfig::from_env();
+        std::env::remove_var("PGT_FUNCTION_MAPPINGS");
+
+        assert_eq!(config.functions.custom_mappings.len(), 2);
+        assert_eq!(
+            config.functions.custom_mappings.get("other_fn"),
+            Some(&"OTHER".to_string())
+        );
+
+        let transformer =
+            SqlTransformer::new(config, pgt::Dialect::Hana).expect("Failed to create transformer");
+        let result = transformer
+            .transform("SELECT my_pg_fn(1)")
+            .expect("Transformation failed");
+        assert_eq!(result, "SELECT my_hana_fn(1);");
+    }
+
     #[test]
     fn test_configuration_merging() {
         // Test merging multiple configuration sources
@@ -190,6 +190,8 @@ mod phase3c_error_handling_tests {
             data_types: pgt::config::DataTypeConfig {
                 preserve_precision: true,
                 handle_arrays: pgt::config::ArrayHandlingStrategy::AsJson,
+                boolean_as_integer: false,
+                serial_as_sequence: false,
                 custom_mappings: {
                     let mut map = std::collections::HashMap::new();
                     map.insert("INVALID_TYPE".to_string(), "".to_string()); // Empty mapping
@@ -0,0 +1,61 @@
+mod common;
+
+use common::hana_transformer;
+use pgt::TransformationError;
+
+#[test]
+fn test_any_literal_array_becomes_in_list() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE id = ANY(ARRAY[1, 2, 3])")
+        .unwrap();
+    assert_eq!(result, "SELECT id FROM users WHERE id IN (1, 2, 3);");
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE status <> ALL(ARRAY['deleted', 'banned'])")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT id FROM users WHERE status NOT IN ('deleted', 'banned');"
+    );
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE score > ANY(ARRAY[10, 20])")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT id FROM users WHERE (score > 10 OR score > 20);"
+    );
+}
+
+#[test]
+fn test_any_column_array_is_unsupported() {
+    let transformer = hana_transformer();
+
+    assert!(matches!(
+        transformer.transform("SELECT id FROM users WHERE 'admin' = ANY(roles)"),
+        Err(TransformationError::UnsupportedFeature { .. })
+    ));
+}
+
+#[test]
+fn test_array_agg_maps_to_string_agg() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT dept, array_agg(name) FROM employees GROUP BY dept")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT dept, STRING_AGG(TO_NVARCHAR(name), ',') FROM employees GROUP BY dept;"
+    );
+
+    let result = transformer
+        .transform("SELECT array_agg(id ORDER BY id) FROM employees")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT STRING_AGG(TO_NVARCHAR(id), ',' ORDER BY id) FROM employees;"
+    );
+}
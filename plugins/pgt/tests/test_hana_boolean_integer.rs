@@ -0,0 +1,58 @@
+mod common;
+
+use common::hana_transformer_with;
+
+#[test]
+fn test_boolean_literals_become_integers() {
+    let transformer = hana_transformer_with(|config| config.data_types.boolean_as_integer = true);
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE active = true AND deleted = false")
+        .unwrap();
+    assert_eq!(result, "SELECT id FROM users WHERE active = 1 AND deleted = 0;");
+
+    let result = transformer
+        .transform("UPDATE users SET active = false WHERE id = 1")
+        .unwrap();
+    assert_eq!(result, "UPDATE users SET active = 0 WHERE id = 1;");
+
+    let result = transformer
+        .transform("SELECT CASE WHEN active = true THEN 'yes' END FROM users")
+        .unwrap();
+    assert_eq!(result, "SELECT CASE WHEN active = 1 THEN 'yes' END FROM users;");
+}
+
+#[test]
+fn test_is_true_and_is_false_become_comparisons() {
+    let transformer = hana_transformer_with(|config| config.data_types.boolean_as_integer = true);
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE active IS TRUE OR archived IS FALSE")
+        .unwrap();
+    assert_eq!(result, "SELECT id FROM users WHERE active = 1 OR archived = 0;");
+}
+
+#[test]
+fn test_boolean_columns_become_tinyint() {
+    let transformer = hana_transformer_with(|config| config.data_types.boolean_as_integer = true);
+
+    let result = transformer
+        .transform("CREATE TABLE flags (id INTEGER, active BOOLEAN DEFAULT true)")
+        .unwrap();
+    assert!(result.contains("active TINYINT DEFAULT 1"), "{}", result);
+}
+
+#[test]
+fn test_booleans_pass_through_by_default() {
+    let transformer = hana_transformer_with(|config| config.data_types.boolean_as_integer = false);
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE active = true AND archived IS FALSE")
+        .unwrap();
+    assert_eq!(result, "SELECT id FROM users WHERE active = true AND archived IS FALSE;");
+
+    let result = transformer
+        .transform("CREATE TABLE flags (id INTEGER, active BOOLEAN DEFAULT true)")
+        .unwrap();
+    assert!(result.contains("active BOOLEAN DEFAULT true"), "{}", result);
+}
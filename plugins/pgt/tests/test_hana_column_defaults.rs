@@ -0,0 +1,77 @@
+mod common;
+
+use common::{hana_transformer, hana_transformer_with};
+
+#[test]
+fn test_identity_columns_keep_hana_identity_syntax() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform(
+            "CREATE TABLE orders (id int GENERATED ALWAYS AS IDENTITY, \
+             ref bigint GENERATED BY DEFAULT AS IDENTITY (INCREMENT 5 START 100))",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "CREATE TABLE orders (id INT GENERATED ALWAYS AS IDENTITY, \
+         ref BIGINT GENERATED BY DEFAULT AS IDENTITY ( INCREMENT BY 5 START WITH 100 ));"
+    );
+    assert!(transformer.take_warnings().is_empty());
+}
+
+#[test]
+fn test_now_default_becomes_current_timestamp() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("CREATE TABLE events (created_at timestamptz DEFAULT now())")
+        .unwrap();
+    assert_eq!(
+        result,
+        "CREATE TABLE events (created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP);"
+    );
+
+    let result = transformer
+        .transform("CREATE TABLE events (expires_at timestamp DEFAULT (now() + interval '1 day'))")
+        .unwrap();
+    assert!(result.contains("DEFAULT (CURRENT_TIMESTAMP + "));
+}
+
+#[test]
+fn test_functions_in_defaults_follow_the_function_rules() {
+    let transformer = hana_transformer_with(|config| {
+        config.functions.enable_custom_functions = true;
+        config
+            .functions
+            .custom_mappings
+            .insert("to_char".to_string(), "TO_VARCHAR".to_string());
+    });
+
+    let result = transformer
+        .transform(
+            "CREATE TABLE t (r double precision DEFAULT abs(random()), \
+             y varchar(4) DEFAULT to_char(now(), 'YYYY'))",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "CREATE TABLE t (r DOUBLE PRECISION DEFAULT ABS(RAND()), \
+         y NVARCHAR(4) DEFAULT TO_VARCHAR(CURRENT_TIMESTAMP, 'YYYY'));"
+    );
+}
+
+#[test]
+fn test_stored_generated_columns_become_hana_generated_columns() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("CREATE TABLE t (price numeric, qty int, total numeric GENERATED ALWAYS AS (price * qty) STORED)")
+        .unwrap();
+    assert!(
+        result.ends_with("total NUMERIC GENERATED ALWAYS AS (price * qty));"),
+        "{}",
+        result
+    );
+    assert!(!result.contains("STORED"));
+}
@@ -0,0 +1,96 @@
+mod common;
+
+use common::hana_transformer;
+use pgt::TransformationError;
+
+#[test]
+fn test_interval_addition_and_subtraction() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT created_at + INTERVAL '1 day' FROM events")
+        .unwrap();
+    assert_eq!(result, "SELECT ADD_DAYS(created_at, 1) FROM events;");
+
+    let result = transformer
+        .transform("SELECT id FROM events WHERE created_at > updated_at - INTERVAL '3 months'")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT id FROM events WHERE created_at > ADD_MONTHS(updated_at, -3);"
+    );
+
+    let result = transformer
+        .transform("SELECT INTERVAL '2 weeks' + created_at FROM events")
+        .unwrap();
+    assert_eq!(result, "SELECT ADD_DAYS(created_at, 14) FROM events;");
+
+    let result = transformer
+        .transform("SELECT created_at + INTERVAL '90' MINUTE FROM events")
+        .unwrap();
+    assert_eq!(result, "SELECT ADD_SECONDS(created_at, 5400) FROM events;");
+}
+
+#[test]
+fn test_compound_interval_nests_calls() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT created_at + INTERVAL '2 hours 30 minutes' FROM events")
+        .unwrap();
+    assert_eq!(result, "SELECT ADD_SECONDS(created_at, 9000) FROM events;");
+
+    let result = transformer
+        .transform("SELECT created_at + INTERVAL '1 year 2 days 01:00:30' FROM events")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT ADD_SECONDS(ADD_DAYS(ADD_YEARS(created_at, 1), 2), 3630) FROM events;"
+    );
+
+    let result = transformer
+        .transform("SELECT created_at + INTERVAL '1 day' - INTERVAL '1 hour' FROM events")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT ADD_SECONDS(ADD_DAYS(created_at, 1), -3600) FROM events;"
+    );
+}
+
+#[test]
+fn test_bare_interval_is_unsupported() {
+    let transformer = hana_transformer();
+
+    match transformer.transform("SELECT INTERVAL '1 day' FROM events") {
+        Err(TransformationError::UnsupportedFeature { suggestion, .. }) => {
+            assert!(suggestion.unwrap().contains("ADD_DAYS"));
+        }
+        other => panic!("expected UnsupportedFeature, got {:?}", other),
+    }
+
+    assert!(matches!(
+        transformer.transform("SELECT created_at + INTERVAL '1 fortnight' FROM events"),
+        Err(TransformationError::UnsupportedFeature { .. })
+    ));
+}
+
+#[test]
+fn test_overflowing_interval_is_unsupported() {
+    let transformer = hana_transformer();
+
+    for sql in [
+        "SELECT created_at + INTERVAL '9223372036854775807' WEEK FROM events",
+        "SELECT created_at + INTERVAL '9223372036854775807 hours' FROM events",
+        "SELECT created_at + INTERVAL '9223372036854775807 days 7 days' FROM events",
+        "SELECT created_at + INTERVAL '9223372036854775807:00' FROM events",
+        "SELECT created_at - INTERVAL '-9223372036854775808 seconds' FROM events",
+    ] {
+        assert!(
+            matches!(
+                transformer.transform(sql),
+                Err(TransformationError::UnsupportedFeature { .. })
+            ),
+            "{sql}"
+        );
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::hana_transformer;
+use pgt::TransformationError;
+
+#[test]
+fn test_cross_join_lateral_becomes_inner_join() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform(
+            "SELECT u.id, o.total FROM users u CROSS JOIN LATERAL \
+             (SELECT total FROM orders WHERE orders.user_id = u.id ORDER BY total DESC LIMIT 1) o",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT u.id, o.total FROM users AS u INNER JOIN LATERAL \
+         (SELECT total FROM orders WHERE orders.user_id = u.id ORDER BY total DESC LIMIT 1) AS o ON 1 = 1;"
+    );
+}
+
+#[test]
+fn test_left_join_lateral_on_true() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform(
+            "SELECT u.id, o.total FROM users u LEFT JOIN LATERAL \
+             (SELECT total FROM orders WHERE orders.user_id = u.id) o ON true",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT u.id, o.total FROM users AS u LEFT OUTER JOIN LATERAL \
+         (SELECT total FROM orders WHERE orders.user_id = u.id) AS o ON 1 = 1;"
+    );
+
+    // A real predicate is kept.
+    let result = transformer
+        .transform(
+            "SELECT u.id FROM users u JOIN LATERAL \
+             (SELECT total FROM orders WHERE orders.user_id = u.id) o ON o.total > 10",
+        )
+        .unwrap();
+    assert!(result.contains("ON o.total > 10"), "{}", result);
+}
+
+#[test]
+fn test_comma_lateral_joins_everything_before_it() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform(
+            "SELECT o.total FROM users u, teams t, LATERAL \
+             (SELECT total FROM orders WHERE orders.user_id = u.id AND orders.team_id = t.id) o",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT o.total FROM users AS u CROSS JOIN teams AS t INNER JOIN LATERAL \
+         (SELECT total FROM orders WHERE orders.user_id = u.id AND orders.team_id = t.id) AS o ON 1 = 1;"
+    );
+}
+
+#[test]
+fn test_nested_lateral_is_rewritten() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT * FROM (SELECT u.id FROM users u CROSS JOIN LATERAL (SELECT 1 AS one) l) s")
+        .unwrap();
+    assert!(result.contains("INNER JOIN LATERAL (SELECT 1 AS one) AS l ON 1 = 1"), "{}", result);
+}
+
+#[test]
+fn test_inexpressible_laterals_are_unsupported() {
+    let transformer = hana_transformer();
+
+    for sql in [
+        "SELECT u.id, t FROM users u CROSS JOIN LATERAL unnest(u.tags) t",
+        "SELECT u.id FROM users u RIGHT JOIN LATERAL (SELECT 1 AS one) l ON true",
+    ] {
+        match transformer.transform(sql) {
+            Err(TransformationError::UnsupportedFeature { suggestion, .. }) => {
+                assert!(suggestion.unwrap().contains("manually"));
+            }
+            other => panic!("expected UnsupportedFeature for {}, got {:?}", sql, other),
+        }
+    }
+}
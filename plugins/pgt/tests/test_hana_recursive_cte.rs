@@ -0,0 +1,62 @@
+mod common;
+
+use common::hana_transformer;
+
+#[test]
+fn test_recursive_hierarchy_walk() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform(
+            "WITH RECURSIVE tree AS (\
+                 SELECT id, parent_id, name, 1 AS depth FROM categories WHERE parent_id IS NULL \
+                 UNION ALL \
+                 SELECT c.id, c.parent_id, c.name, t.depth + 1 FROM categories c \
+                 JOIN tree t ON c.parent_id = t.id WHERE c.name ~ '^[A-Z]'\
+             ) SELECT id, name, depth FROM tree ORDER BY depth",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "WITH RECURSIVE tree (id, parent_id, name, depth) AS (\
+         SELECT id, parent_id, name, 1 AS depth FROM categories WHERE parent_id IS NULL \
+         UNION ALL \
+         SELECT c.id, c.parent_id, c.name, t.depth + 1 FROM categories AS c \
+         JOIN tree AS t ON c.parent_id = t.id WHERE LIKE_REGEXPR(c.name, '^[A-Z]') > 0\
+         ) SELECT id, name, depth FROM tree ORDER BY depth;"
+    );
+    assert!(transformer.take_warnings().is_empty());
+}
+
+#[test]
+fn test_recursive_cte_keeps_explicit_columns_and_drops_materialized() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform(
+            "WITH RECURSIVE n (v) AS MATERIALIZED (SELECT 1 UNION ALL SELECT v + 1 FROM n WHERE v < 10) \
+             SELECT v FROM n",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "WITH RECURSIVE n (v) AS (SELECT 1 UNION ALL SELECT v + 1 FROM n WHERE v < 10) SELECT v FROM n;"
+    );
+}
+
+#[test]
+fn test_recursive_cte_union_distinct_warns() {
+    let transformer = hana_transformer();
+
+    let sql = "WITH RECURSIVE reach AS (SELECT 1 AS node UNION SELECT e.dst FROM edges e \
+               JOIN reach r ON e.src = r.node) SELECT node FROM reach";
+    let result = transformer.transform(sql).unwrap();
+    assert!(result.contains(" UNION SELECT "), "got: {}", result);
+
+    let warnings = transformer.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].message.contains("UNION ALL"));
+
+    let detailed = transformer.transform_detailed(sql);
+    assert!(detailed.warnings.iter().any(|w| w.contains("reach")));
+}
@@ -0,0 +1,43 @@
+mod common;
+
+use common::hana_transformer;
+
+#[test]
+fn test_anchored_regex_match() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE name ~ '^foo.*[0-9]$'")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT id FROM users WHERE LIKE_REGEXPR(name, '^foo.*[0-9]$') > 0;"
+    );
+}
+
+#[test]
+fn test_case_insensitive_regex_match() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE email ~* 'bar\\.com'")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT id FROM users WHERE LIKE_REGEXPR(email, 'bar\\.com', 'i') > 0;"
+    );
+}
+
+#[test]
+fn test_negated_regex_match_in_where() {
+    let transformer = hana_transformer();
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE name !~ '^tmp_' AND email !~* 'TEST' OR code ~ '^A'")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT id FROM users WHERE NOT (LIKE_REGEXPR(name, '^tmp_') > 0) \
+         AND NOT (LIKE_REGEXPR(email, 'TEST', 'i') > 0) OR LIKE_REGEXPR(code, '^A') > 0;"
+    );
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::hana_transformer_with;
+use pgt::TransformationError;
+
+#[test]
+fn test_nextval_and_currval_become_sequence_references() {
+    let transformer = hana_transformer_with(|config| config.data_types.serial_as_sequence = false);
+
+    let result = transformer
+        .transform("INSERT INTO orders (id) VALUES (nextval('orders_seq'))")
+        .unwrap();
+    assert_eq!(result, "INSERT INTO orders (id) VALUES (orders_seq.NEXTVAL);");
+
+    let result = transformer
+        .transform("SELECT nextval('orders_seq'::regclass), currval('orders_seq')")
+        .unwrap();
+    assert_eq!(result, "SELECT orders_seq.NEXTVAL, orders_seq.CURRVAL;");
+}
+
+#[test]
+fn test_setval_is_unsupported() {
+    let transformer = hana_transformer_with(|config| config.data_types.serial_as_sequence = false);
+
+    match transformer.transform("SELECT setval('orders_seq', 100)") {
+        Err(TransformationError::UnsupportedFeature { suggestion, .. }) => {
+            assert!(suggestion.unwrap().contains("ALTER SEQUENCE"));
+        }
+        other => panic!("expected UnsupportedFeature, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_create_sequence_uses_hana_option_syntax() {
+    let transformer = hana_transformer_with(|config| config.data_types.serial_as_sequence = false);
+
+    let result = transformer
+        .transform("CREATE SEQUENCE orders_seq INCREMENT 5 MINVALUE 1 START 100 CACHE 20 NO CYCLE")
+        .unwrap();
+    assert_eq!(
+        result,
+        "CREATE SEQUENCE orders_seq INCREMENT BY 5 MINVALUE 1 START WITH 100 CACHE 20 NO CYCLE;"
+    );
+    assert!(transformer.take_warnings().is_empty());
+}
+
+#[test]
+fn test_unsupported_sequence_options_are_dropped_with_warnings() {
+    let transformer = hana_transformer_with(|config| config.data_types.serial_as_sequence = false);
+
+    let result = transformer
+        .transform("CREATE SEQUENCE IF NOT EXISTS orders_seq AS INTEGER START WITH 1 OWNED BY orders.id")
+        .unwrap();
+    assert_eq!(result, "CREATE SEQUENCE orders_seq START WITH 1;");
+
+    let warnings = transformer.take_warnings();
+    assert_eq!(warnings.len(), 3, "{:?}", warnings);
+    assert!(warnings.iter().any(|w| w.message.contains("IF NOT EXISTS")));
+    assert!(warnings.iter().any(|w| w.message.contains("OWNED BY orders.id")));
+}
+
+#[test]
+fn test_serial_becomes_identity_by_default() {
+    let transformer = hana_transformer_with(|config| config.data_types.serial_as_sequence = false);
+
+    let result = transformer
+        .transform("CREATE TABLE orders (id BIGSERIAL PRIMARY KEY, note TEXT)")
+        .unwrap();
+    assert_eq!(
+        result,
+        "CREATE TABLE orders (id BIGINT GENERATED BY DEFAULT AS IDENTITY PRIMARY KEY, note CLOB);"
+    );
+}
+
+#[test]
+fn test_serial_as_sequence_creates_the_sequence_first() {
+    let transformer = hana_transformer_with(|config| config.data_types.serial_as_sequence = true);
+
+    let result = transformer
+        .transform("CREATE TABLE sales.orders (id SERIAL PRIMARY KEY, note TEXT)")
+        .unwrap();
+    assert_eq!(
+        result,
+        "CREATE SEQUENCE sales.orders_id_seq;\n\nCREATE TABLE sales.orders (id INTEGER PRIMARY KEY, note CLOB);"
+    );
+
+    let warnings = transformer.take_warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].message.contains("sales.orders_id_seq.NEXTVAL"));
+}
+
+#[test]
+fn test_serial_sequence_keeps_comments_with_their_statement() {
+    let transformer = hana_transformer_with(|config| {
+        config.data_types.serial_as_sequence = true;
+        config.formatting.preserve_comments = true;
+    });
+
+    let result = transformer
+        .transform("-- orders\nCREATE TABLE orders (id SERIAL);\n-- count\nSELECT COUNT(*) FROM orders")
+        .unwrap();
+    assert_eq!(
+        result,
+        "-- orders\nCREATE SEQUENCE orders_id_seq;\n\nCREATE TABLE orders (id INTEGER);\n\n-- count\nSELECT COUNT(*) FROM orders;"
+    );
+}
@@ -0,0 +1,57 @@
+use pgt::config::TransformationConfig;
+use pgt::{Dialect, SqlTransformer};
+
+const SCRIPT: &str = "CREATE TABLE audit (id INTEGER, note TEXT);
+CREATE FUNCTION log_it() RETURNS trigger AS $$
+BEGIN
+  -- keep; the semicolons
+  INSERT INTO audit VALUES (1, 'a;b');
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+SELECT $tag$x; y$tag$ AS body, 'one;
+two' AS note;
+DELETE FROM audit";
+
+fn transformer(config: TransformationConfig) -> SqlTransformer {
+    SqlTransformer::new(config, Dialect::Hana).unwrap()
+}
+
+#[test]
+fn test_statements_keep_their_order_and_bodies() {
+    let mut config = TransformationConfig::default();
+    config.formatting.preserve_comments = false;
+    let result = transformer(config).transform(SCRIPT).unwrap();
+
+    let statements: Vec<&str> = result.split(";\n\n").collect();
+    assert_eq!(statements.len(), 4, "{}", result);
+    assert!(statements[0].starts_with("CREATE TABLE audit"));
+    assert!(statements[1]
+        .contains("\n  -- keep; the semicolons\n  INSERT INTO audit VALUES (1, 'a;b');\n"));
+    assert!(statements[2].contains("$tag$x; y$tag$"));
+    assert!(statements[2].contains("'one;\ntwo'"));
+    assert_eq!(statements[3], "DELETE FROM audit;");
+}
+
+#[test]
+fn test_output_parses_back_into_the_same_statements() {
+    let transformer = transformer(TransformationConfig::default());
+    let once = transformer.transform(SCRIPT).unwrap();
+    assert_eq!(transformer.transform(&once).unwrap(), once);
+}
+
+#[test]
+fn test_custom_statement_terminator() {
+    let mut config = TransformationConfig::default();
+    config.formatting.statement_terminator = "\n/".to_string();
+    let transformer = transformer(config);
+
+    let result = transformer.transform("SELECT 1; SELECT 'a;b'").unwrap();
+    assert_eq!(result, "SELECT 1\n/\n\nSELECT 'a;b'\n/");
+    assert_eq!(
+        transformer
+            .transform_and_validate("SELECT 1; SELECT 2")
+            .unwrap(),
+        "SELECT 1\n/\n\nSELECT 2\n/"
+    );
+}
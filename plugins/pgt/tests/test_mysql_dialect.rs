@@ -0,0 +1,100 @@
+use pgt::config::TransformationConfig;
+use pgt::{Dialect, SqlTransformer};
+
+fn mysql_transformer() -> SqlTransformer {
+    SqlTransformer::new(TransformationConfig::default(), Dialect::MySql).unwrap()
+}
+
+#[test]
+fn test_mysql_dialect_metadata() {
+    assert_eq!(Dialect::MySql.name(), "mysql");
+    assert_eq!(Dialect::from_str("mysql").unwrap(), Dialect::MySql);
+    assert_eq!(Dialect::from_str("MySQL").unwrap(), Dialect::MySql);
+    assert_eq!(Dialect::from_str("my_sql").unwrap(), Dialect::MySql);
+    assert!(Dialect::all().contains(&Dialect::MySql));
+    assert_eq!(mysql_transformer().dialect(), Dialect::MySql);
+}
+
+#[test]
+fn test_mysql_can_transform() {
+    let transformer = mysql_transformer();
+
+    let valid_queries = [
+        "SELECT * FROM users",
+        "SELECT id, name FROM users WHERE active = true",
+        "SELECT NOW(), gen_random_uuid()",
+        "SELECT id::text FROM users LIMIT 10 OFFSET 5",
+        "INSERT INTO users (id, active) VALUES (1, false)",
+        "UPDATE users SET active = true WHERE id = 1",
+        "DELETE FROM users WHERE id = 1",
+        "CREATE TABLE test (id INTEGER, active BOOLEAN DEFAULT true)",
+    ];
+    for sql in valid_queries {
+        assert!(transformer.can_transform(sql), "should parse: {}", sql);
+        assert!(transformer.transform(sql).is_ok(), "should transform: {}", sql);
+    }
+
+    assert!(!transformer.can_transform("SELECT * FROM users WHERE"));
+    assert!(!transformer.can_transform("INVALID SQL SYNTAX"));
+}
+
+#[test]
+fn test_mysql_function_and_literal_rewrites() {
+    let transformer = mysql_transformer();
+
+    let result = transformer.transform("SELECT NOW(), gen_random_uuid()").unwrap();
+    assert_eq!(result, "SELECT NOW(), UUID();");
+
+    let result = transformer
+        .transform("SELECT id FROM users WHERE active = true AND deleted = false")
+        .unwrap();
+    assert_eq!(result, "SELECT id FROM users WHERE active = 1 AND deleted = 0;");
+
+    let result = transformer
+        .transform("INSERT INTO users (id, active) VALUES (1, true)")
+        .unwrap();
+    assert!(result.contains("VALUES (1, 1)"), "got: {}", result);
+
+    let result = transformer
+        .transform("SELECT id FROM users LIMIT 10 OFFSET 5")
+        .unwrap();
+    assert!(result.contains("LIMIT 10 OFFSET 5"), "got: {}", result);
+}
+
+#[test]
+fn test_mysql_casts() {
+    let transformer = mysql_transformer();
+
+    let result = transformer
+        .transform("SELECT id::text, amount::numeric(10,2), created::timestamp, n::integer FROM t")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT CAST(id AS CHAR), CAST(amount AS DECIMAL(10,2)), CAST(created AS DATETIME), CAST(n AS SIGNED) FROM t;"
+    );
+
+    let result = transformer.transform("SELECT CAST(id AS VARCHAR(20)) FROM t").unwrap();
+    assert_eq!(result, "SELECT CAST(id AS CHAR(20)) FROM t;");
+}
+
+#[test]
+fn test_mysql_identifier_quoting() {
+    let transformer = mysql_transformer();
+
+    let result = transformer
+        .transform(r#"SELECT "User Id", 'say "hi"' FROM "my schema"."users""#)
+        .unwrap();
+    assert_eq!(
+        result,
+        r#"SELECT `User Id`, 'say "hi"' FROM `my schema`.`users`;"#
+    );
+
+    // Unquoted identifiers are left alone, other dialects keep double quotes.
+    let result = transformer.transform("SELECT id FROM users").unwrap();
+    assert_eq!(result, "SELECT id FROM users;");
+    let duckdb = SqlTransformer::new(TransformationConfig::default(), Dialect::DuckDb).unwrap();
+    assert_eq!(
+        duckdb.transform(r#"SELECT "User Id" FROM users"#).unwrap(),
+        r#"SELECT "User Id" FROM users;"#
+    );
+}
@@ -0,0 +1,67 @@
+use pgt::config::TransformationConfig;
+use pgt::error::TransformationError;
+use pgt::generator::validate::validate_output;
+use pgt::{Dialect, SqlTransformer};
+
+const CORPUS: &[&str] = &[
+    "SELECT * FROM users",
+    "SELECT id, name FROM users WHERE active = true ORDER BY name LIMIT 10 OFFSET 5",
+    "SELECT NOW(), COUNT(*) FROM orders GROUP BY customer_id HAVING COUNT(*) > 1",
+    "SELECT u.id, o.total FROM users u JOIN orders o ON o.user_id = u.id",
+    "SELECT CASE WHEN total > 100 THEN 'big' ELSE 'small' END FROM orders",
+    "SELECT id FROM users WHERE id IN (SELECT user_id FROM orders)",
+    "WITH recent AS (SELECT * FROM orders) SELECT COUNT(*) FROM recent",
+    "INSERT INTO users (id, name) VALUES (1, 'it''s me')",
+    "UPDATE users SET active = false WHERE id = 1",
+    "DELETE FROM users WHERE id = 1",
+    "CREATE TABLE items (id INTEGER PRIMARY KEY, name VARCHAR(100), price DECIMAL(10, 2))",
+    "SELECT 1; SELECT 2",
+];
+
+#[test]
+fn test_corpus_round_trips_in_every_dialect() {
+    for dialect in Dialect::all() {
+        let transformer = SqlTransformer::new(TransformationConfig::default(), *dialect).unwrap();
+        for sql in CORPUS {
+            let result = transformer.transform_and_validate(sql);
+            assert!(result.is_ok(), "{} rejected output for {:?}: {:?}", dialect, sql, result);
+            assert_eq!(result.unwrap(), transformer.transform(sql).unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_unparseable_output_is_rejected() {
+    for dialect in [Dialect::DuckDb, Dialect::MySql, Dialect::Snowflake] {
+        let err = validate_output(dialect, "SELECT FROM WHERE;").unwrap_err();
+        match err {
+            TransformationError::OutputValidationError { dialect: name, .. } => {
+                assert_eq!(name, dialect.name());
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_hana_structural_check() {
+    assert!(validate_output(Dialect::Hana, "SELECT (1 + 2) FROM DUMMY;").is_ok());
+    assert!(validate_output(Dialect::Hana, "SELECT 1 FROM DUMMY; SELECT 2 FROM DUMMY;").is_ok());
+
+    for broken in [
+        "SELECT (1 + 2 FROM DUMMY;",
+        "SELECT 1 + 2) FROM DUMMY;",
+        "SELECT 'unterminated FROM DUMMY;",
+        "SELECT \"unterminated FROM DUMMY;",
+        "SELECT 1 FROM DUMMY;;",
+        "",
+    ] {
+        let err = validate_output(Dialect::Hana, broken).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Generated hana SQL failed validation"),
+            "{:?}: {}",
+            broken,
+            err
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use pgt::{Dialect, ParseCache, SqlTransformer, TransformationConfig};
+use std::sync::Arc;
+
+fn sharing(cache: &Arc<ParseCache>, dialect: Dialect) -> SqlTransformer {
+    SqlTransformer::builder()
+        .with_dialect(dialect)
+        .with_shared_cache(Arc::clone(cache))
+        .with_metrics(true)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_transformers_reuse_each_others_parses() {
+    let cache = Arc::new(ParseCache::new(10));
+    let first = sharing(&cache, Dialect::Hana);
+    let second = sharing(&cache, Dialect::DuckDb);
+
+    first.transform("SELECT id FROM users LIMIT 5").unwrap();
+    let result = second.transform("SELECT id FROM users LIMIT 5").unwrap();
+    assert!(result.contains("LIMIT 5"));
+
+    assert_eq!(first.metrics().cache_misses, 1);
+    assert_eq!(second.metrics().cache_hits, 1);
+    assert_eq!(second.metrics().cache_misses, 0);
+    assert_eq!(cache.len(), 1);
+    assert_eq!(second.cache_stats(), (1, 10));
+}
+
+#[test]
+fn test_with_shared_cache_constructor() {
+    let cache = Arc::new(ParseCache::new(10));
+    let transformer = SqlTransformer::with_shared_cache(
+        Arc::clone(&cache),
+        TransformationConfig::default(),
+        Dialect::Hana,
+    )
+    .unwrap();
+    transformer.transform("SELECT NOW()").unwrap();
+    assert_eq!(cache.len(), 1);
+
+    transformer.clear_caches();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn test_least_recently_used_entry_is_evicted() {
+    let cache = Arc::new(ParseCache::new(2));
+    let transformer = sharing(&cache, Dialect::Hana);
+
+    transformer.transform("SELECT 1").unwrap();
+    transformer.transform("SELECT 2").unwrap();
+    transformer.transform("SELECT 1").unwrap();
+    transformer.transform("SELECT 3").unwrap();
+    assert_eq!(cache.len(), 2);
+
+    // SELECT 2 was evicted; SELECT 1 was used more recently and stayed.
+    transformer.transform("SELECT 1").unwrap();
+    transformer.transform("SELECT 2").unwrap();
+    let metrics = transformer.metrics();
+    assert_eq!(metrics.cache_hits, 2);
+    assert_eq!(metrics.cache_misses, 4);
+}
+
+#[test]
+fn test_default_cache_is_per_instance() {
+    let first = SqlTransformer::default();
+    let second = SqlTransformer::default();
+    first.transform("SELECT 1").unwrap();
+    assert_eq!(first.cache_stats(), (1, ParseCache::DEFAULT_CAPACITY));
+    assert_eq!(second.cache_stats().0, 0);
+}
+
+#[test]
+fn test_zero_capacity_caches_nothing() {
+    let cache = Arc::new(ParseCache::new(0));
+    let transformer = sharing(&cache, Dialect::Hana);
+    transformer.transform("SELECT 1").unwrap();
+    transformer.transform("SELECT 1").unwrap();
+    assert!(cache.is_empty());
+    assert_eq!(transformer.metrics().cache_hits, 0);
+}
+
+#[test]
+fn test_shared_cache_across_threads() {
+    let cache = ParseCache::global();
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let cache = Arc::clone(&cache);
+            std::thread::spawn(move || {
+                let transformer = sharing(&cache, Dialect::Hana);
+                for j in 0..20 {
+                    transformer
+                        .transform(&format!("SELECT {} FROM shared_t", j % 5))
+                        .unwrap();
+                }
+                transformer.metrics().cache_hits
+            })
+        })
+        .collect();
+    let hits: u64 = handles.into_iter().map(|h| h.join().unwrap()).sum();
+    // At worst every thread misses each of the five queries once.
+    assert!(hits >= 80 - 4 * 5);
+    assert!(Arc::ptr_eq(&cache, &ParseCache::global()));
+}
@@ -0,0 +1,70 @@
+use pgt::{Dialect, SqlTransformer};
+
+fn snowflake_transformer() -> SqlTransformer {
+    SqlTransformer::builder()
+        .with_dialect(Dialect::Snowflake)
+        .with_data_types(true)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_snowflake_dialect_metadata() {
+    assert_eq!(Dialect::Snowflake.name(), "snowflake");
+    assert_eq!(Dialect::from_str("Snowflake").unwrap(), Dialect::Snowflake);
+    assert!(Dialect::all().contains(&Dialect::Snowflake));
+    assert_eq!(snowflake_transformer().dialect(), Dialect::Snowflake);
+}
+
+#[test]
+fn test_snowflake_function_rewrites() {
+    let transformer = snowflake_transformer();
+
+    let result = transformer.transform("SELECT NOW(), random()").unwrap();
+    assert_eq!(result, "SELECT CURRENT_TIMESTAMP(), RANDOM();");
+
+    let result = transformer
+        .transform("SELECT id FROM events WHERE created < now() LIMIT 10")
+        .unwrap();
+    assert_eq!(
+        result,
+        "SELECT id FROM events WHERE created < CURRENT_TIMESTAMP() LIMIT 10;"
+    );
+}
+
+#[test]
+fn test_snowflake_create_table_types() {
+    let transformer = snowflake_transformer();
+
+    let result = transformer
+        .transform(
+            "CREATE TABLE docs (id SERIAL PRIMARY KEY, big BIGSERIAL, body TEXT, raw BYTEA, \
+             meta JSONB, tags TEXT[], created TIMESTAMP DEFAULT NOW())",
+        )
+        .unwrap();
+    assert_eq!(
+        result,
+        "CREATE TABLE docs (id INTEGER PRIMARY KEY AUTOINCREMENT, big BIGINT AUTOINCREMENT, \
+         body VARCHAR, raw BINARY, meta VARIANT, tags ARRAY, created TIMESTAMP DEFAULT CURRENT_TIMESTAMP());"
+    );
+}
+
+#[test]
+fn test_snowflake_casts() {
+    let transformer = snowflake_transformer();
+
+    let result = transformer
+        .transform("SELECT payload::json, name::text, id::integer FROM t")
+        .unwrap();
+    assert_eq!(result, "SELECT payload::VARIANT, name::VARCHAR, id::INTEGER FROM t;");
+}
+
+#[test]
+fn test_snowflake_preserves_quoted_identifiers() {
+    let transformer = snowflake_transformer();
+
+    let result = transformer
+        .transform(r#"SELECT "UserId", "order" FROM "MySchema"."Users""#)
+        .unwrap();
+    assert_eq!(result, r#"SELECT "UserId", "order" FROM "MySchema"."Users";"#);
+}
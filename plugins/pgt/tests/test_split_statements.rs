@@ -0,0 +1,72 @@
+use pgt::utils::split_statements;
+
+#[test]
+fn test_split_simple_script() {
+    assert_eq!(
+        split_statements("CREATE TABLE t (id int);\nINSERT INTO t VALUES (1);\n\n;SELECT * FROM t"),
+        vec![
+            "CREATE TABLE t (id int)",
+            "INSERT INTO t VALUES (1)",
+            "SELECT * FROM t",
+        ]
+    );
+    assert!(split_statements("").is_empty());
+    assert!(split_statements(" ;\n; ").is_empty());
+}
+
+#[test]
+fn test_split_respects_quotes_and_escapes() {
+    assert_eq!(
+        split_statements(
+            "SELECT 'a;b', 'it''s;', E'back\\'slash;', \"semi;\"\"col\" FROM t; SELECT 2;"
+        ),
+        vec![
+            "SELECT 'a;b', 'it''s;', E'back\\'slash;', \"semi;\"\"col\" FROM t",
+            "SELECT 2",
+        ]
+    );
+    // Outside E'' strings a backslash is an ordinary character.
+    assert_eq!(
+        split_statements("SELECT 'C:\\'; SELECT 3"),
+        vec!["SELECT 'C:\\'", "SELECT 3"]
+    );
+}
+
+#[test]
+fn test_split_respects_comments() {
+    assert_eq!(
+        split_statements(
+            "-- setup; not a statement\nSELECT 1 /* a; /* nested; */ b */;\n\
+             SELECT 2; -- trailing; comment\n/* closing ; remark */"
+        ),
+        vec![
+            "-- setup; not a statement\nSELECT 1 /* a; /* nested; */ b */",
+            "SELECT 2",
+        ]
+    );
+}
+
+#[test]
+fn test_split_nested_dollar_quoted_bodies() {
+    let script = "CREATE FUNCTION outer_fn() RETURNS void AS $outer$\n\
+                  BEGIN\n\
+                  \x20 EXECUTE $inner$ CREATE FUNCTION inner_fn() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql; $inner$;\n\
+                  \x20 RAISE NOTICE 'done; really';\n\
+                  END;\n\
+                  $outer$ LANGUAGE plpgsql;\n\
+                  SELECT outer_fn();";
+    let statements = split_statements(script);
+    assert_eq!(statements.len(), 2);
+    assert!(statements[0].starts_with("CREATE FUNCTION outer_fn()"));
+    assert!(statements[0].contains("$$ SELECT 1; $$ LANGUAGE sql; $inner$;"));
+    assert!(statements[0].ends_with("$outer$ LANGUAGE plpgsql"));
+    assert_eq!(statements[1], "SELECT outer_fn()");
+}
+
+#[test]
+fn test_split_does_not_treat_parameters_as_dollar_quotes() {
+    assert_eq!(
+        split_statements("SELECT $1, a$b$c FROM t WHERE x = $2; SELECT $tag$;$tag$"),
+        vec!["SELECT $1, a$b$c FROM t WHERE x = $2", "SELECT $tag$;$tag$"]
+    );
+}
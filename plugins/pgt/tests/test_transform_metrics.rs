@@ -0,0 +1,72 @@
+use pgt::{Dialect, SqlTransformer, TransformMetrics};
+use std::sync::Arc;
+
+fn transformer_with_metrics() -> SqlTransformer {
+    SqlTransformer::builder()
+        .with_dialect(Dialect::Hana)
+        .with_metrics(true)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_metrics_are_off_by_default() {
+    let transformer = SqlTransformer::builder().build().unwrap();
+    transformer.transform("SELECT 1").unwrap();
+    assert_eq!(transformer.metrics(), TransformMetrics::default());
+}
+
+#[test]
+fn test_metrics_count_transforms_and_cache_lookups() {
+    let transformer = transformer_with_metrics();
+    transformer.transform("SELECT id FROM users").unwrap();
+    transformer.transform("SELECT id FROM users").unwrap();
+    assert!(transformer.transform("SELEC FROM").is_err());
+
+    let metrics = transformer.metrics();
+    assert_eq!(metrics.transforms, 3);
+    assert_eq!(metrics.parse_errors, 1);
+    assert_eq!(metrics.unsupported_features, 0);
+    assert_eq!(metrics.cache_hits, 1);
+    assert_eq!(metrics.cache_misses, 2);
+    assert!(metrics.total_transform_time > std::time::Duration::ZERO);
+}
+
+#[test]
+fn test_metrics_count_unsupported_features() {
+    let transformer = transformer_with_metrics();
+    let err = transformer.transform("SELECT setval('seq', 10)").unwrap_err();
+    assert!(matches!(err, pgt::TransformationError::UnsupportedFeature { .. }));
+
+    let detailed = transformer.transform_detailed("SELECT 1");
+    assert!(detailed.result.is_ok());
+
+    let metrics = transformer.metrics();
+    assert_eq!(metrics.transforms, 2);
+    assert_eq!(metrics.unsupported_features, 1);
+    assert_eq!(metrics.parse_errors, 0);
+}
+
+#[test]
+fn test_metrics_aggregate_across_threads() {
+    let transformer = Arc::new(transformer_with_metrics());
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            let transformer = Arc::clone(&transformer);
+            std::thread::spawn(move || {
+                for j in 0..10 {
+                    transformer
+                        .transform(&format!("SELECT {} FROM t", i * 10 + j))
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let metrics = transformer.metrics();
+    assert_eq!(metrics.transforms, 40);
+    assert_eq!(metrics.cache_hits + metrics.cache_misses, 40);
+}
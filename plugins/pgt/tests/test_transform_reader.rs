@@ -0,0 +1,92 @@
+use pgt::config::TransformationConfig;
+use pgt::parser::splitter::StatementSplitter;
+use pgt::{Dialect, SqlTransformer, TransformationError};
+use std::io::{BufReader, Cursor};
+
+fn split(sql: &str) -> Vec<String> {
+    StatementSplitter::new(BufReader::new(sql.as_bytes()))
+        .collect::<std::io::Result<_>>()
+        .unwrap()
+}
+
+#[test]
+fn test_splitter_respects_quotes_and_comments() {
+    let statements = split(
+        "SELECT 'a;b', \"odd;name\" FROM t;\n\
+         SELECT 'it''s; fine';\n\
+         -- not; a statement\n\
+         SELECT 1 /* nested /* ; */ still ; comment */;\n\
+         SELECT E'back\\'slash;';;\n\
+         SELECT 2",
+    );
+    assert_eq!(
+        statements,
+        vec![
+            "SELECT 'a;b', \"odd;name\" FROM t",
+            "SELECT 'it''s; fine'",
+            "-- not; a statement\nSELECT 1 /* nested /* ; */ still ; comment */",
+            "SELECT E'back\\'slash;'",
+            "SELECT 2",
+        ]
+    );
+}
+
+#[test]
+fn test_splitter_respects_dollar_quoting() {
+    let statements = split(
+        "CREATE FUNCTION f() RETURNS int AS $body$\n\
+         BEGIN\n  RETURN 1; -- $$ inside\nEND;\n$body$ LANGUAGE plpgsql;\n\
+         SELECT $$a;b$$, $1;",
+    );
+    assert_eq!(statements.len(), 2);
+    assert!(statements[0].ends_with("$body$ LANGUAGE plpgsql"));
+    assert_eq!(statements[1], "SELECT $$a;b$$, $1");
+}
+
+#[test]
+fn test_transform_reader_streams_and_collects_errors() {
+    let mut config = TransformationConfig::default();
+    config.formatting.preserve_comments = false;
+    let transformer = SqlTransformer::new(config, Dialect::Hana).unwrap();
+
+    let input = "SELECT id FROM users LIMIT 10;\n\
+                 SELECT FROM WHERE garbage (;\n\
+                 SELECT 'semi;colon' FROM users\n";
+    let mut output = Vec::new();
+    let summary = transformer
+        .transform_reader(Cursor::new(input), &mut output)
+        .unwrap();
+
+    assert_eq!(summary.statements, 3);
+    assert_eq!(summary.transformed, 2);
+    assert_eq!(summary.errors.len(), 1);
+    assert_eq!(summary.errors[0].index, 1);
+    assert!(matches!(
+        summary.errors[0].error,
+        TransformationError::ParseError { .. }
+    ));
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "SELECT id FROM users LIMIT 10;\n\nSELECT 'semi;colon' FROM users;\n"
+    );
+}
+
+#[test]
+fn test_transform_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("dump.sql");
+    let output = dir.path().join("out/dump.hana.sql");
+    std::fs::write(&input, "SELECT 1;\nSELECT 2;\n").unwrap();
+
+    let summary = SqlTransformer::new(TransformationConfig::default(), Dialect::Hana)
+        .unwrap()
+        .transform_file(&input, &output)
+        .unwrap();
+
+    assert_eq!(summary.transformed, 2);
+    assert!(summary.errors.is_empty());
+    assert_eq!(
+        std::fs::read_to_string(&output).unwrap(),
+        "SELECT 1;\n\nSELECT 2;\n"
+    );
+}
@@ -0,0 +1,697 @@
+//! Parsing and row encoding for `COPY ... FROM STDIN` / `COPY ... TO STDOUT`.
+//!
+//! DuckDB understands `COPY` against files but not the Postgres
+//! sub-protocol where rows travel over the wire as CopyData messages, so the
+//! server intercepts the STDIN/STDOUT forms. Everything else (`COPY t TO
+//! 'file.parquet'`, ...) is left for DuckDB to execute as-is.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyDirection {
+    /// `COPY ... FROM STDIN`
+    In,
+    /// `COPY ... TO STDOUT`
+    Out,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CopyFormat {
+    Text,
+    Csv,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CopySource {
+    /// A table reference plus its optional column list, kept as SQL text.
+    Table { name: String, columns: Option<String> },
+    /// The `(SELECT ...)` of `COPY (query) TO STDOUT`, without parentheses.
+    Query(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CopyStatement {
+    pub direction: CopyDirection,
+    pub source: CopySource,
+    pub format: CopyFormat,
+    pub delimiter: char,
+    pub null: String,
+    pub header: bool,
+}
+
+impl CopyStatement {
+    /// Table plus column list as it should appear in generated SQL.
+    pub fn target_sql(&self) -> String {
+        match &self.source {
+            CopySource::Table { name, columns: Some(cols) } => format!("{} ({})", name, cols),
+            CopySource::Table { name, columns: None } => name.clone(),
+            CopySource::Query(q) => format!("({})", q),
+        }
+    }
+
+    /// Unquoted name parts and column names of a COPY FROM target, as the
+    /// Appender takes them. `None` for a query source.
+    pub fn target_parts(&self) -> Option<(Vec<String>, Vec<String>)> {
+        match &self.source {
+            CopySource::Table { name, columns } => Some((
+                split_identifiers(name, '.'),
+                columns.as_deref().map(|c| split_identifiers(c, ',')).unwrap_or_default(),
+            )),
+            CopySource::Query(_) => None,
+        }
+    }
+
+    /// Query producing the rows for COPY TO, or the target's shape for COPY FROM.
+    pub fn select_sql(&self) -> String {
+        match &self.source {
+            CopySource::Table { name, columns: Some(cols) } => format!("SELECT {} FROM {}", cols, name),
+            CopySource::Table { name, columns: None } => format!("SELECT * FROM {}", name),
+            CopySource::Query(q) => q.clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Group(String),
+    Comma,
+}
+
+/// Split COPY SQL into words, single-quoted strings and parenthesised
+/// groups. Double-quoted identifiers stay inside their word.
+fn tokenize(sql: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() || c == ';' {
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else if c == '\'' {
+            let mut s = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return None,
+                    Some('\'') if chars.get(i + 1) == Some(&'\'') => {
+                        s.push('\'');
+                        i += 2;
+                    }
+                    Some('\'') => {
+                        i += 1;
+                        break;
+                    }
+                    Some(&ch) => {
+                        s.push(ch);
+                        i += 1;
+                    }
+                }
+            }
+            tokens.push(Token::Quoted(s));
+        } else if c == '(' {
+            let start = i + 1;
+            let mut depth = 0;
+            let mut in_single = false;
+            let mut in_double = false;
+            while i < chars.len() {
+                match chars[i] {
+                    '\'' if !in_double => in_single = !in_single,
+                    '"' if !in_single => in_double = !in_double,
+                    '(' if !in_single && !in_double => depth += 1,
+                    ')' if !in_single && !in_double => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ => {}
+                }
+                i += 1;
+            }
+            if i >= chars.len() {
+                return None;
+            }
+            tokens.push(Token::Group(chars[start..i].iter().collect::<String>().trim().to_string()));
+            i += 1;
+        } else {
+            let mut s = String::new();
+            let mut in_double = false;
+            while i < chars.len() {
+                let ch = chars[i];
+                if ch == '"' {
+                    in_double = !in_double;
+                } else if !in_double && (ch.is_whitespace() || matches!(ch, '(' | ')' | ',' | ';' | '\'')) {
+                    break;
+                }
+                s.push(ch);
+                i += 1;
+            }
+            tokens.push(Token::Word(s));
+        }
+    }
+    Some(tokens)
+}
+
+fn is_word(token: Option<&Token>, keyword: &str) -> bool {
+    matches!(token, Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword))
+}
+
+/// Recognise `COPY ... FROM STDIN` and `COPY ... TO STDOUT`. Returns `None`
+/// for any other statement, including COPY against files, so the caller can
+/// fall through to normal execution. Malformed STDIN/STDOUT options are an
+/// error rather than `None` so they aren't silently sent to DuckDB.
+pub fn parse_copy_statement(sql: &str) -> Option<Result<CopyStatement, String>> {
+    let trimmed = sql.trim_start();
+    if !trimmed.get(..5).is_some_and(|p| p.eq_ignore_ascii_case("COPY ")) {
+        return None;
+    }
+    let tokens = tokenize(&trimmed[5..])?;
+    let mut it = tokens.iter().peekable();
+
+    let source = match it.next()? {
+        Token::Group(q) => CopySource::Query(q.clone()),
+        Token::Word(name) => {
+            let columns = match it.peek() {
+                Some(Token::Group(cols)) => {
+                    let cols = cols.clone();
+                    it.next();
+                    Some(cols)
+                }
+                _ => None,
+            };
+            CopySource::Table { name: name.clone(), columns }
+        }
+        _ => return None,
+    };
+
+    let direction = match (it.next(), it.next()) {
+        (Some(Token::Word(d)), Some(Token::Word(t)))
+            if d.eq_ignore_ascii_case("FROM") && t.eq_ignore_ascii_case("STDIN") =>
+        {
+            CopyDirection::In
+        }
+        (Some(Token::Word(d)), Some(Token::Word(t)))
+            if d.eq_ignore_ascii_case("TO") && t.eq_ignore_ascii_case("STDOUT") =>
+        {
+            CopyDirection::Out
+        }
+        _ => return None,
+    };
+
+    if direction == CopyDirection::In && matches!(source, CopySource::Query(_)) {
+        return Some(Err("COPY FROM STDIN requires a table, not a query".to_string()));
+    }
+
+    let stmt = CopyStatement {
+        direction,
+        source,
+        format: CopyFormat::Text,
+        delimiter: '\t',
+        null: "\\N".to_string(),
+        header: false,
+    };
+
+    let rest: Vec<&Token> = it.collect();
+    let mut idx = 0;
+    if is_word(rest.get(idx).copied(), "WITH") {
+        idx += 1;
+    }
+
+    // Modern syntax: WITH (FORMAT csv, HEADER, DELIMITER ',')
+    if let Some(Token::Group(group)) = rest.get(idx) {
+        let inner = match tokenize(group) {
+            Some(t) => t,
+            None => return Some(Err("unterminated COPY option list".to_string())),
+        };
+        let options = inner
+            .split(|t| *t == Token::Comma)
+            .map(|opt| opt.iter().collect())
+            .collect();
+        return Some(apply_options(stmt, options));
+    }
+
+    // Legacy syntax: CSV HEADER DELIMITER [AS] ',' NULL [AS] ''
+    let mut options = Vec::new();
+    while idx < rest.len() {
+        let mut opt = vec![rest[idx]];
+        idx += 1;
+        if is_word(rest.get(idx).copied(), "AS") {
+            idx += 1;
+        }
+        if let Some(Token::Quoted(_)) = rest.get(idx).copied() {
+            opt.push(rest[idx]);
+            idx += 1;
+        }
+        options.push(opt);
+    }
+    Some(apply_options(stmt, options))
+}
+
+fn apply_options(mut stmt: CopyStatement, options: Vec<Vec<&Token>>) -> Result<CopyStatement, String> {
+    let mut delimiter: Option<char> = None;
+    let mut null: Option<String> = None;
+
+    for opt in options {
+        let Some(Token::Word(name)) = opt.first() else {
+            if opt.is_empty() {
+                continue;
+            }
+            return Err("invalid COPY option".to_string());
+        };
+        let value = match opt.get(1) {
+            Some(Token::Word(v)) | Some(Token::Quoted(v)) => Some(v.as_str()),
+            _ => None,
+        };
+        match name.to_ascii_uppercase().as_str() {
+            "CSV" => stmt.format = CopyFormat::Csv,
+            "BINARY" => return Err("COPY BINARY format is not supported".to_string()),
+            "FORMAT" => match value.map(|v| v.to_ascii_lowercase()).as_deref() {
+                Some("csv") => stmt.format = CopyFormat::Csv,
+                Some("text") => stmt.format = CopyFormat::Text,
+                Some("binary") => return Err("COPY BINARY format is not supported".to_string()),
+                other => return Err(format!("unsupported COPY format: {:?}", other.unwrap_or(""))),
+            },
+            "HEADER" => {
+                stmt.header = !matches!(
+                    value.map(|v| v.to_ascii_lowercase()).as_deref(),
+                    Some("false" | "off" | "0")
+                )
+            }
+            "DELIMITER" => {
+                let v = value.ok_or("DELIMITER requires a value")?;
+                let mut chars = v.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => delimiter = Some(c),
+                    _ => return Err("COPY delimiter must be a single character".to_string()),
+                }
+            }
+            "NULL" => null = Some(value.ok_or("NULL requires a value")?.to_string()),
+            // Accepted for compatibility; DuckDB already reads UTF-8.
+            "ENCODING" | "FREEZE" => {}
+            other => return Err(format!("unsupported COPY option: {}", other)),
+        }
+    }
+
+    if stmt.format == CopyFormat::Csv {
+        stmt.delimiter = ',';
+        stmt.null = String::new();
+    }
+    if let Some(d) = delimiter {
+        stmt.delimiter = d;
+    }
+    if let Some(n) = null {
+        stmt.null = n;
+    }
+    if stmt.header && stmt.format == CopyFormat::Text {
+        return Err("COPY HEADER is only available in CSV mode".to_string());
+    }
+    Ok(stmt)
+}
+
+/// Encode one row for COPY TO STDOUT, including the trailing newline.
+pub fn encode_row(stmt: &CopyStatement, values: &[Option<String>]) -> String {
+    let mut line = String::new();
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            line.push(stmt.delimiter);
+        }
+        match (stmt.format, value) {
+            (_, None) => line.push_str(&stmt.null),
+            (CopyFormat::Text, Some(v)) => {
+                for ch in v.chars() {
+                    match ch {
+                        '\\' => line.push_str("\\\\"),
+                        '\n' => line.push_str("\\n"),
+                        '\r' => line.push_str("\\r"),
+                        '\t' if stmt.delimiter == '\t' => line.push_str("\\t"),
+                        c if c == stmt.delimiter => {
+                            line.push('\\');
+                            line.push(c);
+                        }
+                        c => line.push(c),
+                    }
+                }
+            }
+            (CopyFormat::Csv, Some(v)) => line.push_str(&csv_field(v, stmt.delimiter, &stmt.null)),
+        }
+    }
+    line.push('\n');
+    line
+}
+
+/// Quote a CSV field when it would otherwise be ambiguous: it contains the
+/// delimiter, a quote or a line break, or it equals the NULL marker (so an
+/// empty string survives as `""` rather than reading back as NULL).
+fn csv_field(v: &str, delimiter: char, null: &str) -> String {
+    let needs_quotes = v == null
+        || v.contains(delimiter)
+        || v.contains('"')
+        || v.contains('\n')
+        || v.contains('\r');
+    if needs_quotes {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v.to_string()
+    }
+}
+
+/// Decode one line of COPY text format (without its newline) into values.
+pub fn decode_text_line(line: &str, delimiter: char, null: &str) -> Vec<Option<String>> {
+    let mut fields = Vec::new();
+    let mut raw = String::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            // Keep escapes intact until the whole field is known so the NULL
+            // marker (which is itself `\N`) can be compared before unescaping.
+            raw.push(c);
+            if let Some(next) = chars.next() {
+                raw.push(next);
+            }
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut raw));
+        } else {
+            raw.push(c);
+        }
+    }
+    fields.push(raw);
+
+    fields
+        .into_iter()
+        .map(|f| if f == null { None } else { Some(unescape_text(&f)) })
+        .collect()
+}
+
+fn unescape_text(field: &str) -> String {
+    let mut out = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some('b') => out.push('\u{8}'),
+            Some('f') => out.push('\u{c}'),
+            Some('v') => out.push('\u{b}'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Split a comma- or dot-separated identifier list, unquoting
+/// double-quoted parts (`"My ""Table"""` becomes `My "Table"`).
+fn split_identifiers(sql: &str, separator: char) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+    let mut in_double = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_double && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_double = !in_double,
+            c if c == separator && !in_double => parts.push(std::mem::take(&mut current)),
+            c if c.is_whitespace() && !in_double => {}
+            c => current.push(c),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+/// Decode one CSV record (without its newline) into values. A quoted field
+/// is never NULL, so `""` stays an empty string while an unquoted field
+/// equal to the NULL marker reads as NULL.
+pub fn decode_csv_line(line: &str, delimiter: char, null: &str) -> Result<Vec<Option<String>>, String> {
+    let mut fields = Vec::new();
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            if c == '"' && chars.peek() == Some(&'"') {
+                value.push('"');
+                chars.next();
+            } else if c == '"' {
+                in_quotes = false;
+            } else {
+                value.push(c);
+            }
+        } else if c == '"' {
+            in_quotes = true;
+            quoted = true;
+        } else if c == delimiter {
+            let field = std::mem::take(&mut value);
+            fields.push(if !quoted && field == null { None } else { Some(field) });
+            quoted = false;
+        } else {
+            value.push(c);
+        }
+    }
+    if in_quotes {
+        return Err("unterminated CSV quoted field".to_string());
+    }
+    fields.push(if !quoted && value == null { None } else { Some(value) });
+    Ok(fields)
+}
+
+/// Incoming COPY FROM STDIN data, decoded into rows as CopyData chunks
+/// arrive so they can be appended straight away. Bytes after the last
+/// complete record wait for the next chunk.
+pub struct CopyInDecoder {
+    stmt: CopyStatement,
+    pending: Vec<u8>,
+    /// How far `pending` has been scanned for a record end, and whether
+    /// that point is inside a CSV quoted field.
+    scanned: usize,
+    in_quotes: bool,
+    /// The CSV header record still has to be skipped.
+    skip_header: bool,
+    /// The `\.` end-of-data marker was seen; anything after it is ignored.
+    ended: bool,
+}
+
+impl CopyInDecoder {
+    pub fn new(stmt: CopyStatement) -> Self {
+        let skip_header = stmt.header;
+        Self {
+            stmt,
+            pending: Vec::new(),
+            scanned: 0,
+            in_quotes: false,
+            skip_header,
+            ended: false,
+        }
+    }
+
+    pub fn statement(&self) -> &CopyStatement {
+        &self.stmt
+    }
+
+    /// Decode every record completed by `data`.
+    pub fn decode(&mut self, data: &[u8]) -> Result<Vec<Vec<Option<String>>>, String> {
+        if self.ended {
+            return Ok(Vec::new());
+        }
+        self.pending.extend_from_slice(data);
+        let mut rows = Vec::new();
+        while let Some(end) = self.next_record_end() {
+            let record: Vec<u8> = self.pending.drain(..=end).collect();
+            self.scanned = 0;
+            if let Some(row) = self.decode_record(&record[..record.len() - 1])? {
+                rows.push(row);
+            }
+            if self.ended {
+                self.pending.clear();
+                break;
+            }
+        }
+        Ok(rows)
+    }
+
+    /// Decode the last record when the data didn't end with a newline.
+    pub fn finish(&mut self) -> Result<Option<Vec<Option<String>>>, String> {
+        if self.ended || self.pending.is_empty() {
+            return Ok(None);
+        }
+        let record = std::mem::take(&mut self.pending);
+        self.decode_record(&record)
+    }
+
+    /// Position of the newline ending the next record. In CSV a newline
+    /// inside a quoted field belongs to the value.
+    fn next_record_end(&mut self) -> Option<usize> {
+        let csv = self.stmt.format == CopyFormat::Csv;
+        while self.scanned < self.pending.len() {
+            let b = self.pending[self.scanned];
+            if csv && b == b'"' {
+                self.in_quotes = !self.in_quotes;
+            } else if b == b'\n' && !self.in_quotes {
+                return Some(self.scanned);
+            }
+            self.scanned += 1;
+        }
+        None
+    }
+
+    fn decode_record(&mut self, record: &[u8]) -> Result<Option<Vec<Option<String>>>, String> {
+        let line = std::str::from_utf8(record)
+            .map_err(|_| "invalid UTF-8 in COPY data".to_string())?
+            .trim_end_matches('\r');
+        // End-of-data marker sent by older clients.
+        if line == "\\." {
+            self.ended = true;
+            return Ok(None);
+        }
+        if self.skip_header {
+            self.skip_header = false;
+            return Ok(None);
+        }
+        match self.stmt.format {
+            CopyFormat::Text => Ok(Some(decode_text_line(line, self.stmt.delimiter, &self.stmt.null))),
+            CopyFormat::Csv => decode_csv_line(line, self.stmt.delimiter, &self.stmt.null).map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(sql: &str) -> CopyStatement {
+        parse_copy_statement(sql).expect("is COPY").expect("parses")
+    }
+
+    #[test]
+    fn ignores_file_copies_and_other_statements() {
+        assert!(parse_copy_statement("SELECT 1").is_none());
+        assert!(parse_copy_statement("COPY t TO 'out.parquet' (FORMAT parquet)").is_none());
+        assert!(parse_copy_statement("COPY t FROM '/tmp/in.csv'").is_none());
+        // Byte 5 falls inside a multi-byte character.
+        assert!(parse_copy_statement("ÄÖÜ 1").is_none());
+        assert!(parse_copy_statement("  日本語のテーブル").is_none());
+    }
+
+    #[test]
+    fn parses_copy_from_stdin_with_columns_and_options() {
+        let stmt = parse("COPY public.events (id, name) FROM STDIN WITH (FORMAT csv, HEADER true, DELIMITER ';')");
+        assert_eq!(stmt.direction, CopyDirection::In);
+        assert_eq!(
+            stmt.source,
+            CopySource::Table { name: "public.events".into(), columns: Some("id, name".into()) }
+        );
+        assert_eq!(stmt.format, CopyFormat::Csv);
+        assert!(stmt.header);
+        assert_eq!(stmt.delimiter, ';');
+        assert_eq!(stmt.null, "");
+        assert_eq!(stmt.target_sql(), "public.events (id, name)");
+    }
+
+    #[test]
+    fn parses_psql_legacy_options() {
+        let stmt = parse("COPY \"My Table\" FROM STDIN CSV HEADER DELIMITER AS '|' NULL 'NA';");
+        assert_eq!(stmt.format, CopyFormat::Csv);
+        assert!(stmt.header);
+        assert_eq!(stmt.delimiter, '|');
+        assert_eq!(stmt.null, "NA");
+        assert_eq!(stmt.target_sql(), "\"My Table\"");
+    }
+
+    #[test]
+    fn parses_copy_query_to_stdout() {
+        let stmt = parse("copy (SELECT a, 'x,y' FROM t WHERE f(a) > 1) to stdout");
+        assert_eq!(stmt.direction, CopyDirection::Out);
+        assert_eq!(stmt.source, CopySource::Query("SELECT a, 'x,y' FROM t WHERE f(a) > 1".into()));
+        assert_eq!(stmt.format, CopyFormat::Text);
+        assert_eq!(stmt.delimiter, '\t');
+        assert_eq!(stmt.null, "\\N");
+    }
+
+    #[test]
+    fn rejects_unsupported_options() {
+        assert!(parse_copy_statement("COPY t TO STDOUT (FORMAT binary)").unwrap().is_err());
+        assert!(parse_copy_statement("COPY t FROM STDIN (DELIMITER '::')").unwrap().is_err());
+        assert!(parse_copy_statement("COPY t TO STDOUT (HEADER)").unwrap().is_err());
+        assert!(parse_copy_statement("COPY (SELECT 1) FROM STDIN").unwrap().is_err());
+    }
+
+    #[test]
+    fn text_rows_round_trip() {
+        let stmt = parse("COPY t TO STDOUT");
+        let values = vec![Some("a\tb".to_string()), None, Some("back\\slash\nline".to_string())];
+        let line = encode_row(&stmt, &values);
+        assert_eq!(line, "a\\tb\t\\N\tback\\\\slash\\nline\n");
+        assert_eq!(decode_text_line(line.trim_end_matches('\n'), '\t', "\\N"), values);
+    }
+
+    #[test]
+    fn csv_rows_quote_ambiguous_fields() {
+        let stmt = parse("COPY t TO STDOUT (FORMAT csv)");
+        let values = vec![Some("plain".to_string()), Some("".to_string()), None, Some("say \"hi\", ok".to_string())];
+        assert_eq!(encode_row(&stmt, &values), "plain,\"\",,\"say \"\"hi\"\", ok\"\n");
+    }
+
+    #[test]
+    fn csv_lines_distinguish_null_from_empty() {
+        assert_eq!(
+            decode_csv_line(",\"\",\"a\"\"b\",c", ',', "").unwrap(),
+            vec![None, Some(String::new()), Some("a\"b".into()), Some("c".into())]
+        );
+        assert!(decode_csv_line("\"open", ',', "").is_err());
+    }
+
+    #[test]
+    fn target_parts_unquote_names_and_columns() {
+        let stmt = parse("COPY public.\"My \"\"Table\"\"\" (id, \"Full Name\") FROM STDIN");
+        assert_eq!(
+            stmt.target_parts(),
+            Some((
+                vec!["public".to_string(), "My \"Table\"".to_string()],
+                vec!["id".to_string(), "Full Name".to_string()],
+            ))
+        );
+        assert_eq!(parse("COPY t FROM STDIN").target_parts(), Some((vec!["t".to_string()], vec![])));
+    }
+
+    #[test]
+    fn decoder_yields_text_rows_split_across_chunks() {
+        let mut decoder = CopyInDecoder::new(parse("COPY t (a, b) FROM STDIN"));
+        assert!(decoder.decode(b"1\tfo").unwrap().is_empty());
+        let rows = decoder.decode(b"o\\tbar\r\n2\t\\N\n\\.\nignored\n").unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![Some("1".to_string()), Some("foo\tbar".to_string())],
+                vec![Some("2".to_string()), None],
+            ]
+        );
+        assert_eq!(decoder.finish().unwrap(), None);
+    }
+
+    #[test]
+    fn decoder_keeps_quoted_csv_newlines_and_skips_the_header() {
+        let mut decoder = CopyInDecoder::new(parse("COPY t FROM STDIN (FORMAT csv, HEADER)"));
+        assert!(decoder.decode(b"id,note\n1,\"two").unwrap().is_empty());
+        let rows = decoder.decode(b"\nlines\"\n2,").unwrap();
+        assert_eq!(rows, vec![vec![Some("1".to_string()), Some("two\nlines".to_string())]]);
+        assert_eq!(decoder.finish().unwrap(), Some(vec![Some("2".to_string()), None]));
+    }
+
+    #[test]
+    fn decoder_rejects_invalid_utf8() {
+        let mut decoder = CopyInDecoder::new(parse("COPY t FROM STDIN"));
+        assert!(decoder.decode(b"\xff\xfe\n").is_err());
+    }
+}
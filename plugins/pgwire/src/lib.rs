@@ -2,8 +2,14 @@ extern crate duckdb;
 extern crate duckdb_loadable_macros;
 extern crate libduckdb_sys;
 
+mod copy;
+mod pg_types;
 mod pgwire_server;
+mod query_rewrite;
+mod read_only;
+mod server_options;
 mod server_registry;
+mod session_params;
 
 use duckdb::{
     core::{DataChunkHandle, Inserter, LogicalTypeHandle, LogicalTypeId},
@@ -14,40 +20,101 @@ use duckdb::{
 use duckdb_loadable_macros::duckdb_entrypoint_c_api;
 use libduckdb_sys as ffi;
 use std::{
+    collections::HashMap,
     error::Error,
     ffi::CString,
-    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, OnceLock as OnceCell},
+    sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex, OnceLock as OnceCell, RwLock},
 };
 
-static DESCRIBE_CONNECTIONS: OnceCell<Vec<Arc<Mutex<Connection>>>> = OnceCell::new();
+static DESCRIBE_BASE: OnceCell<Mutex<Connection>> = OnceCell::new();
+static DESCRIBE_CONNECTIONS: RwLock<Vec<Arc<Mutex<Connection>>>> = RwLock::new(Vec::new());
+/// Describe connections pointed at a database, keyed by worker slot and
+/// database name. Kept apart from `DESCRIBE_CONNECTIONS` so those never run
+/// `USE` and always describe against the default catalog.
+static DATABASE_DESCRIBE_CONNECTIONS: OnceCell<Mutex<DatabaseDescribeConnections>> = OnceCell::new();
 
-const DESCRIBE_POOL_SIZE: usize = 4;
+type DatabaseDescribeConnections = HashMap<(usize, String), Arc<Mutex<Connection>>>;
 
 fn store_shared_connection(connection: &Connection) -> Result<(), Box<dyn Error>> {
-    // Create one describe connection per worker so each pgwire session gets its
-    // own connection for DESCRIBE operations, preventing USE DATABASE state from
-    // leaking between sessions that share the same worker_id.
-    let mut describe_conns = Vec::with_capacity(DESCRIBE_POOL_SIZE);
-    for i in 0..DESCRIBE_POOL_SIZE {
-        let cloned = connection
-            .try_clone()
-            .map_err(|e| format!("describe connection clone {i}: {e}"))?;
-        describe_conns.push(Arc::new(Mutex::new(cloned)));
-    }
-
-    DESCRIBE_CONNECTIONS
-        .set(describe_conns)
+    let base = connection
+        .try_clone()
+        .map_err(|e| format!("describe base connection clone: {e}"))?;
+    DESCRIBE_BASE
+        .set(Mutex::new(base))
         .map_err(|_| "describe connections already stored")?;
+    ensure_describe_pool(server_options::DEFAULT_DESCRIBE_POOL_SIZE)?;
+    Ok(())
+}
 
+/// Grow the describe pool to at least `size` connections. Create one
+/// describe connection per worker so each pgwire session gets its own
+/// connection for DESCRIBE operations, preventing USE DATABASE state from
+/// leaking between sessions that share the same worker_id. The pool never
+/// shrinks since other running servers may be using it.
+pub fn ensure_describe_pool(size: usize) -> Result<(), String> {
+    let base = DESCRIBE_BASE
+        .get()
+        .ok_or("describe connections not initialised")?
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut conns = DESCRIBE_CONNECTIONS.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    while conns.len() < size {
+        let cloned = base
+            .try_clone()
+            .map_err(|e| format!("describe connection clone {}: {e}", conns.len()))?;
+        conns.push(Arc::new(Mutex::new(cloned)));
+    }
     Ok(())
 }
 
 /// Returns the describe connection for the given worker_id, ensuring USE DATABASE
 /// state is isolated per worker (and thus per pgwire session).
 pub fn get_describe_connection(worker_id: usize) -> Option<Arc<Mutex<Connection>>> {
-    DESCRIBE_CONNECTIONS.get().and_then(|conns| {
-        conns.get(worker_id % conns.len()).cloned()
-    })
+    let conns = DESCRIBE_CONNECTIONS.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if conns.is_empty() {
+        return None;
+    }
+    conns.get(worker_id % conns.len()).cloned()
+}
+
+/// Returns the describe connection for `worker_id` pointed at `database`.
+/// Each (worker slot, database) pair gets its own connection, which runs
+/// `context_sql` once when it is created, so the `USE` issued for one
+/// database can't leak into a describe for another database or for a
+/// session without one. With no `context_sql` this is the plain per-worker
+/// connection.
+pub fn get_database_describe_connection(
+    worker_id: usize,
+    database: Option<&str>,
+    context_sql: &[String],
+) -> Option<Arc<Mutex<Connection>>> {
+    let (Some(database), false) = (database, context_sql.is_empty()) else {
+        return get_describe_connection(worker_id);
+    };
+    let slots = DESCRIBE_CONNECTIONS.read().unwrap_or_else(|poisoned| poisoned.into_inner()).len();
+    if slots == 0 {
+        return None;
+    }
+    let key = (worker_id % slots, database.to_string());
+    let mut conns = DATABASE_DESCRIBE_CONNECTIONS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(conn) = conns.get(&key) {
+        return Some(Arc::clone(conn));
+    }
+    let conn = DESCRIBE_BASE
+        .get()?
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .try_clone()
+        .ok()?;
+    for sql in context_sql {
+        let _ = conn.execute(sql, []);
+    }
+    let conn = Arc::new(Mutex::new(conn));
+    conns.insert(key, Arc::clone(&conn));
+    Some(conn)
 }
 
 struct PgwireVersionScalar;
@@ -104,7 +171,17 @@ impl VScalar for StartPgWireServerScalar {
         let password = duckdb::types::DuckString::new(&mut { password_slice[0] }).as_str().to_string();
         let db_credentials = duckdb::types::DuckString::new(&mut { db_credentials_slice[0] }).as_str().to_string();
 
-        let response = match pgwire_server::start_pgwire_server_capi(host, port, Some(&password), db_credentials) {
+        let options_json = if input.num_columns() >= 5 {
+            let options_vector = input.flat_vector(4);
+            let options_slice = options_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+            duckdb::types::DuckString::new(&mut { options_slice[0] }).as_str().to_string()
+        } else {
+            String::new()
+        };
+
+        let response = match server_options::PgWireServerOptions::from_json(&options_json)
+            .and_then(|options| pgwire_server::start_pgwire_server_capi(host, port, Some(&password), db_credentials, options))
+        {
             Ok(msg) => msg,
             Err(err) => format!("Error: {}", err),
         };
@@ -115,15 +192,29 @@ impl VScalar for StartPgWireServerScalar {
     }
 
     fn signatures() -> Vec<ScalarFunctionSignature> {
-        vec![ScalarFunctionSignature::exact(
-            vec![
-                LogicalTypeId::Varchar.into(),
-                LogicalTypeId::Integer.into(),
+        vec![
+            // trex_pgwire_start(host, port, password, db_credentials)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Integer.into(),
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
                 LogicalTypeId::Varchar.into(),
+            ),
+            // trex_pgwire_start(host, port, password, db_credentials, options_json)
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Integer.into(),
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
                 LogicalTypeId::Varchar.into(),
-            ],
-            LogicalTypeId::Varchar.into(),
-        )]
+            ),
+        ]
     }
 }
 
@@ -211,6 +302,69 @@ impl VScalar for UpdateDbCredentialsScalar {
     }
 }
 
+struct SetRewriteDialectScalar;
+
+impl VScalar for SetRewriteDialectScalar {
+    type State = ();
+
+    unsafe fn invoke(
+        _state: &Self::State,
+        input: &mut DataChunkHandle,
+        output: &mut dyn WritableVector,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if input.len() == 0 {
+            return Err("No input provided".into());
+        }
+
+        // trex_pgwire_set_rewrite_dialect(dialect) applies to every running
+        // server; the (host, port, dialect) form to one server.
+        let dialect_column = input.num_columns() - 1;
+        let dialect_vector = input.flat_vector(dialect_column);
+        let dialect_slice = dialect_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+        let dialect_name = duckdb::types::DuckString::new(&mut { dialect_slice[0] }).as_str().to_string();
+
+        let server = if input.num_columns() == 3 {
+            let host_vector = input.flat_vector(0);
+            let port_vector = input.flat_vector(1);
+            let host_slice = host_vector.as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+            let port_slice = port_vector.as_slice_with_len::<i32>(input.len());
+            let host = duckdb::types::DuckString::new(&mut { host_slice[0] }).as_str().to_string();
+            Some((host, port_slice[0] as u16))
+        } else {
+            None
+        };
+
+        let response = match query_rewrite::parse_rewrite_dialect(&dialect_name).and_then(|dialect| {
+            server_registry::ServerRegistry::instance()
+                .set_rewrite_dialect(server.as_ref().map(|(host, port)| (host.as_str(), *port)), dialect)
+        }) {
+            Ok(msg) => msg,
+            Err(err) => format!("Error: {}", err),
+        };
+
+        let flat_vector = output.flat_vector();
+        flat_vector.insert(0, &response);
+        Ok(())
+    }
+
+    fn signatures() -> Vec<ScalarFunctionSignature> {
+        vec![
+            ScalarFunctionSignature::exact(
+                vec![LogicalTypeId::Varchar.into()],
+                LogicalTypeId::Varchar.into(),
+            ),
+            ScalarFunctionSignature::exact(
+                vec![
+                    LogicalTypeId::Varchar.into(),
+                    LogicalTypeId::Integer.into(),
+                    LogicalTypeId::Varchar.into(),
+                ],
+                LogicalTypeId::Varchar.into(),
+            ),
+        ]
+    }
+}
+
 struct PgWireServerStatusTable;
 
 #[repr(C)]
@@ -283,6 +437,88 @@ impl VTab for PgWireServerStatusTable {
     }
 }
 
+/// Rows emitted per `trex_pgwire_sessions` chunk.
+const BATCH_SIZE: usize = 2048;
+
+struct PgWireSessionsTable;
+
+#[repr(C)]
+struct PgWireSessionsBindData {}
+
+#[repr(C)]
+struct PgWireSessionsInitData {
+    sessions: Vec<server_registry::BackendSessionInfo>,
+    cursor: Mutex<usize>,
+}
+
+impl VTab for PgWireSessionsTable {
+    type InitData = PgWireSessionsInitData;
+    type BindData = PgWireSessionsBindData;
+
+    fn bind(bind: &BindInfo) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+        bind.add_result_column("backend_pid", LogicalTypeHandle::from(LogicalTypeId::Integer));
+        bind.add_result_column("client_addr", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("database", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("current_query", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("state", LogicalTypeHandle::from(LogicalTypeId::Varchar));
+        bind.add_result_column("connected_at", LogicalTypeHandle::from(LogicalTypeId::Timestamp));
+        Ok(PgWireSessionsBindData {})
+    }
+
+    fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+        Ok(PgWireSessionsInitData {
+            sessions: server_registry::ServerRegistry::instance().backend_sessions(),
+            cursor: Mutex::new(0),
+        })
+    }
+
+    fn func(func: &TableFunctionInfo<Self>, output: &mut DataChunkHandle) -> Result<(), Box<dyn std::error::Error>> {
+        let init_data = func.get_init_data();
+        let mut cursor = init_data
+            .cursor
+            .lock()
+            .map_err(|_| "sessions cursor lock poisoned")?;
+
+        let start = *cursor;
+        let end = (start + BATCH_SIZE).min(init_data.sessions.len());
+        if start >= end {
+            output.set_len(0);
+            return Ok(());
+        }
+
+        let mut pid_vector = output.flat_vector(0);
+        let addr_vector = output.flat_vector(1);
+        let mut database_vector = output.flat_vector(2);
+        let query_vector = output.flat_vector(3);
+        let state_vector = output.flat_vector(4);
+        let mut connected_vector = output.flat_vector(5);
+
+        for (i, session) in init_data.sessions[start..end].iter().enumerate() {
+            pid_vector.as_mut_slice::<i32>()[i] = session.pid;
+            addr_vector.insert(i, CString::new(session.client_addr.clone())?);
+            match &session.database {
+                Some(db) => database_vector.insert(i, CString::new(db.clone())?),
+                None => database_vector.set_null(i),
+            }
+            query_vector.insert(i, CString::new(session.current_query.replace('\0', ""))?);
+            state_vector.insert(i, if session.active { "active" } else { "idle" });
+            connected_vector.as_mut_slice::<i64>()[i] = session
+                .connected_at
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_micros() as i64)
+                .unwrap_or(0);
+        }
+
+        *cursor = end;
+        output.set_len(end - start);
+        Ok(())
+    }
+
+    fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+        None
+    }
+}
+
 #[duckdb_entrypoint_c_api()]
 pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>> {
     store_shared_connection(&con)?;
@@ -305,8 +541,14 @@ pub unsafe fn extension_entrypoint(con: Connection) -> Result<(), Box<dyn Error>
     con.register_scalar_function::<UpdateDbCredentialsScalar>("update_db_credentials")
         .expect("Failed to register update_db_credentials alias");
 
+    con.register_scalar_function::<SetRewriteDialectScalar>("trex_pgwire_set_rewrite_dialect")
+        .expect("Failed to register trex_pgwire_set_rewrite_dialect function");
+
     con.register_table_function::<PgWireServerStatusTable>("trex_pgwire_status")
         .expect("Failed to register trex_pgwire_status function");
+
+    con.register_table_function::<PgWireSessionsTable>("trex_pgwire_sessions")
+        .expect("Failed to register trex_pgwire_sessions function");
     
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,316 @@
+//! Postgres types advertised in RowDescription for trexsql result columns.
+//!
+//! Results reach the server as Arrow, so the mapping works on the Arrow
+//! field DuckDB exports for each logical type, plus the
+//! `ARROW:extension:name` metadata it sets for UUID and JSON:
+//!
+//! | DuckDB | Arrow | Postgres |
+//! |--------|-------|----------|
+//! | BOOLEAN | Boolean | bool |
+//! | TINYINT, SMALLINT, UTINYINT | Int8, Int16, UInt8 | int2 |
+//! | INTEGER, USMALLINT | Int32, UInt16 | int4 |
+//! | BIGINT, UINTEGER | Int64, UInt32 | int8 |
+//! | UBIGINT, HUGEINT, DECIMAL(p, s) | UInt64, Decimal128 | numeric |
+//! | FLOAT, DOUBLE | Float32, Float64 | float4, float8 |
+//! | VARCHAR, ENUM | Utf8, Dictionary | text |
+//! | UUID, JSON | Utf8 or FixedSizeBinary(16) with `arrow.uuid` / `arrow.json` | uuid, json |
+//! | BLOB, BIT | Binary | bytea |
+//! | DATE, TIME | Date32, Time64 | date, time |
+//! | TIMESTAMP, TIMESTAMP_TZ | Timestamp without / with tz | timestamp, timestamptz |
+//! | INTERVAL | Interval | interval |
+//! | LIST, ARRAY | List, FixedSizeList | array of the element type |
+//! | STRUCT | Struct | record |
+//!
+//! pgwire's own RowDescription sends 0 as every type modifier, so the
+//! handlers build it with [`row_description`] instead, which sends
+//! `numeric(p, s)` as Postgres does and -1 for everything else.
+//! DuckDB exports UUID as `arrow.uuid` only with `arrow_lossless_conversion`
+//! set; otherwise it arrives as plain VARCHAR and is advertised as `text`.
+//! Types with no Postgres counterpart (MAP, UNION, opaque extension types)
+//! are sent as `text`, and each is logged at debug level the first time it
+//! is seen.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use duckdb::arrow::datatypes::{DataType, Field};
+use pgwire::api::results::FieldInfo;
+use pgwire::api::Type;
+use pgwire::messages::data::{FieldDescription, RowDescription};
+
+use crate::pgwire_server::log_debug;
+
+const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+
+/// Arrow types already logged as having no Postgres counterpart.
+static UNMAPPED_LOGGED: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+
+/// The Postgres type advertised for a result column.
+pub fn pg_type(field: &Field) -> Type {
+    let extension = field.metadata().get(EXTENSION_NAME_KEY).map(String::as_str);
+    pg_type_of(field.data_type(), extension).unwrap_or_else(|| {
+        log_unmapped(field.data_type());
+        Type::TEXT
+    })
+}
+
+/// The type modifier advertised for a column of `data_type`: for
+/// `numeric(p, s)` Postgres sends `((p << 16) | s) + 4`, and -1 means none.
+/// An array carries its element's modifier.
+pub fn type_modifier(data_type: &DataType) -> i32 {
+    match data_type {
+        DataType::Decimal128(precision, scale) | DataType::Decimal256(precision, scale)
+            if *scale >= 0 =>
+        {
+            ((i32::from(*precision) << 16) | i32::from(*scale)) + 4
+        }
+        DataType::List(element)
+        | DataType::LargeList(element)
+        | DataType::FixedSizeList(element, _)
+        | DataType::ListView(element)
+        | DataType::LargeListView(element) => type_modifier(element.data_type()),
+        _ => -1,
+    }
+}
+
+/// RowDescription for `fields`, with `modifiers[idx]` as column `idx`'s type
+/// modifier; columns past the end of `modifiers` get -1.
+pub fn row_description(fields: &[FieldInfo], modifiers: &[i32]) -> RowDescription {
+    RowDescription::new(
+        fields
+            .iter()
+            .enumerate()
+            .map(|(idx, field)| {
+                FieldDescription::new(
+                    field.name().to_owned(),
+                    field.table_id().unwrap_or(0),
+                    field.column_id().unwrap_or(0),
+                    field.datatype().oid(),
+                    0,
+                    modifiers.get(idx).copied().unwrap_or(-1),
+                    field.format().value(),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// True for a UUID exported as 16 bytes, which is formatted as text
+/// before encoding.
+pub fn is_binary_uuid(field: &Field) -> bool {
+    field.data_type() == &DataType::FixedSizeBinary(16)
+        && field.metadata().get(EXTENSION_NAME_KEY).map(String::as_str) == Some("arrow.uuid")
+}
+
+fn pg_type_of(data_type: &DataType, extension: Option<&str>) -> Option<Type> {
+    let pg = match (data_type, extension) {
+        (DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View, Some("arrow.json")) => {
+            Type::JSON
+        }
+        (DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View, Some("arrow.uuid"))
+        | (DataType::FixedSizeBinary(16), Some("arrow.uuid")) => Type::UUID,
+        // Postgres resolves an untyped NULL to text as well.
+        (DataType::Null, _) => Type::TEXT,
+        (DataType::Boolean, _) => Type::BOOL,
+        (DataType::Int8 | DataType::Int16 | DataType::UInt8, _) => Type::INT2,
+        (DataType::Int32 | DataType::UInt16, _) => Type::INT4,
+        (DataType::Int64 | DataType::UInt32, _) => Type::INT8,
+        // UBIGINT exceeds int8.
+        (DataType::UInt64, _) => Type::NUMERIC,
+        (DataType::Float16 | DataType::Float32, _) => Type::FLOAT4,
+        (DataType::Float64, _) => Type::FLOAT8,
+        (DataType::Decimal128(_, _) | DataType::Decimal256(_, _), _) => Type::NUMERIC,
+        (DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View, _) => Type::TEXT,
+        (DataType::Binary | DataType::LargeBinary | DataType::BinaryView, _) => Type::BYTEA,
+        (DataType::Date32 | DataType::Date64, _) => Type::DATE,
+        (DataType::Time32(_) | DataType::Time64(_), _) => Type::TIME,
+        // Timestamp WITH timezone -> TIMESTAMPTZ (OID 1184).
+        // Timestamp WITHOUT timezone -> TIMESTAMP (OID 1114).
+        // arrow-pg's encoder relies on this distinction to format the value
+        // (it formats DateTime<FixedOffset> for TIMESTAMPTZ, NaiveDateTime
+        // for TIMESTAMP). Returning TIMESTAMP for a tz-aware column makes
+        // text-mode encoding produce a value with no offset, but more
+        // importantly the column is also pre-cast to Utf8 (see
+        // `needs_string_cast`) to avoid arrow-pg's Tz::from_str path, which
+        // panics on DuckDB's UTC offset tz strings (e.g. "+00:00") that
+        // chrono-tz cannot parse as IANA.
+        (DataType::Timestamp(_, Some(_)), _) => Type::TIMESTAMPTZ,
+        (DataType::Timestamp(_, None), _) => Type::TIMESTAMP,
+        (DataType::Interval(_) | DataType::Duration(_), _) => Type::INTERVAL,
+        // ENUM values are sent as their labels.
+        (DataType::Dictionary(_, value_type), _) => return pg_type_of(value_type, None),
+        (
+            DataType::List(element)
+            | DataType::LargeList(element)
+            | DataType::FixedSizeList(element, _)
+            | DataType::ListView(element)
+            | DataType::LargeListView(element),
+            _,
+        ) => {
+            let extension = element.metadata().get(EXTENSION_NAME_KEY).map(String::as_str);
+            return pg_type_of(element.data_type(), extension).and_then(|t| array_type(&t));
+        }
+        (DataType::Struct(_), _) => Type::RECORD,
+        _ => return None,
+    };
+    Some(pg)
+}
+
+/// The Postgres array type whose elements are `element`. `None` for an
+/// element that is itself an array: Postgres has no arrays of arrays.
+fn array_type(element: &Type) -> Option<Type> {
+    let array = match *element {
+        Type::BOOL => Type::BOOL_ARRAY,
+        Type::INT2 => Type::INT2_ARRAY,
+        Type::INT4 => Type::INT4_ARRAY,
+        Type::INT8 => Type::INT8_ARRAY,
+        Type::NUMERIC => Type::NUMERIC_ARRAY,
+        Type::FLOAT4 => Type::FLOAT4_ARRAY,
+        Type::FLOAT8 => Type::FLOAT8_ARRAY,
+        Type::TEXT => Type::TEXT_ARRAY,
+        Type::JSON => Type::JSON_ARRAY,
+        Type::UUID => Type::UUID_ARRAY,
+        Type::BYTEA => Type::BYTEA_ARRAY,
+        Type::DATE => Type::DATE_ARRAY,
+        Type::TIME => Type::TIME_ARRAY,
+        Type::TIMESTAMP => Type::TIMESTAMP_ARRAY,
+        Type::TIMESTAMPTZ => Type::TIMESTAMPTZ_ARRAY,
+        Type::INTERVAL => Type::INTERVAL_ARRAY,
+        Type::RECORD => Type::RECORD_ARRAY,
+        _ => return None,
+    };
+    Some(array)
+}
+
+fn log_unmapped(data_type: &DataType) {
+    let Ok(mut logged) = UNMAPPED_LOGGED.lock() else {
+        return;
+    };
+    if logged.get_or_insert_with(HashSet::new).insert(data_type.to_string()) {
+        log_debug(&format!("no Postgres type for Arrow type {data_type}; sending it as text"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn with_extension(field: Field, name: &str) -> Field {
+        field.with_metadata(HashMap::from([(EXTENSION_NAME_KEY.to_string(), name.to_string())]))
+    }
+
+    /// The schema DuckDB returns for
+    /// `SELECT 1::TINYINT, 1::UBIGINT, 1::HUGEINT, 1.5::DECIMAL(10,2),
+    /// 'a'::ENUM('a','b'), now()::TIMESTAMPTZ, INTERVAL 1 DAY, [1, 2],
+    /// ['a'], [1, 2]::INT[2], {'a': 1}, MAP {'k': 1}, ...`.
+    #[test]
+    fn representative_query_columns_get_postgres_oids() {
+        use duckdb::arrow::datatypes::{IntervalUnit, TimeUnit};
+        let int_list = |name: &str| Arc::new(Field::new(name, DataType::Int32, true));
+        let columns: Vec<(Field, u32)> = vec![
+            (Field::new("b", DataType::Boolean, true), 16),
+            (Field::new("t", DataType::Int8, true), 21),
+            (Field::new("ut", DataType::UInt8, true), 21),
+            (Field::new("us", DataType::UInt16, true), 23),
+            (Field::new("i", DataType::Int32, true), 23),
+            (Field::new("ui", DataType::UInt32, true), 20),
+            (Field::new("bi", DataType::Int64, true), 20),
+            (Field::new("ub", DataType::UInt64, true), 1700),
+            (Field::new("h", DataType::Decimal128(38, 0), true), 1700),
+            (Field::new("d", DataType::Decimal128(10, 2), true), 1700),
+            (Field::new("f", DataType::Float32, true), 700),
+            (Field::new("db", DataType::Float64, true), 701),
+            (Field::new("v", DataType::Utf8, true), 25),
+            (
+                Field::new(
+                    "e",
+                    DataType::Dictionary(Box::new(DataType::UInt8), Box::new(DataType::Utf8)),
+                    true,
+                ),
+                25,
+            ),
+            (Field::new("blob", DataType::Binary, true), 17),
+            (Field::new("dt", DataType::Date32, true), 1082),
+            (Field::new("tm", DataType::Time64(TimeUnit::Microsecond), true), 1083),
+            (Field::new("ts", DataType::Timestamp(TimeUnit::Microsecond, None), true), 1114),
+            (Field::new("tns", DataType::Timestamp(TimeUnit::Nanosecond, None), true), 1114),
+            (
+                Field::new("tz", DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), true),
+                1184,
+            ),
+            (Field::new("iv", DataType::Interval(IntervalUnit::MonthDayNano), true), 1186),
+            (Field::new("l", DataType::List(int_list("l")), true), 1007),
+            (
+                Field::new(
+                    "ls",
+                    DataType::List(Arc::new(Field::new("l", DataType::Utf8, true))),
+                    true,
+                ),
+                1009,
+            ),
+            (Field::new("arr", DataType::FixedSizeList(int_list(""), 2), true), 1007),
+            (
+                Field::new(
+                    "s",
+                    DataType::Struct(vec![Field::new("a", DataType::Int32, true)].into()),
+                    true,
+                ),
+                2249,
+            ),
+            (with_extension(Field::new("u", DataType::Utf8, true), "arrow.uuid"), 2950),
+            (with_extension(Field::new("ub16", DataType::FixedSizeBinary(16), true), "arrow.uuid"), 2950),
+            (with_extension(Field::new("j", DataType::Utf8, true), "arrow.json"), 114),
+        ];
+        for (field, oid) in &columns {
+            assert_eq!(pg_type(field).oid(), *oid, "column {}", field.name());
+        }
+    }
+
+    #[test]
+    fn numeric_columns_carry_precision_and_scale() {
+        use pgwire::api::results::FieldFormat;
+        assert_eq!(type_modifier(&DataType::Decimal128(10, 2)), (10 << 16 | 2) + 4);
+        assert_eq!(type_modifier(&DataType::Decimal128(38, 0)), (38 << 16) + 4);
+        assert_eq!(type_modifier(&DataType::Decimal128(5, -2)), -1);
+        assert_eq!(type_modifier(&DataType::Int32), -1);
+        let list = DataType::List(Arc::new(Field::new("item", DataType::Decimal128(12, 4), true)));
+        assert_eq!(type_modifier(&list), (12 << 16 | 4) + 4);
+
+        let fields = vec![
+            FieldInfo::new("n".into(), None, None, Type::NUMERIC, FieldFormat::Text),
+            FieldInfo::new("i".into(), None, None, Type::INT4, FieldFormat::Binary),
+        ];
+        let description = row_description(&fields, &[type_modifier(&DataType::Decimal128(10, 2))]);
+        assert_eq!(description.fields[0].type_id, 1700);
+        assert_eq!(description.fields[0].type_modifier, 655_366);
+        assert_eq!(description.fields[0].format_code, 0);
+        assert_eq!(description.fields[1].type_modifier, -1);
+        assert_eq!(description.fields[1].format_code, 1);
+    }
+
+    #[test]
+    fn unmappable_types_fall_back_to_text() {
+        let map = Field::new_map(
+            "m",
+            "entries",
+            Field::new("key", DataType::Utf8, false),
+            Field::new("value", DataType::Int32, true),
+            false,
+            true,
+        );
+        assert_eq!(pg_type(&map), Type::TEXT);
+
+        let opaque = with_extension(Field::new("uh", DataType::FixedSizeBinary(16), true), "arrow.opaque");
+        assert_eq!(pg_type(&opaque), Type::TEXT);
+        assert!(!is_binary_uuid(&opaque));
+
+        let nested = DataType::List(Arc::new(Field::new(
+            "l",
+            DataType::List(Arc::new(Field::new("l", DataType::Int32, true))),
+            true,
+        )));
+        assert_eq!(pg_type(&Field::new("nested", nested, true)), Type::TEXT);
+    }
+}
@@ -1,7 +1,10 @@
+use std::collections::HashMap;
 use std::panic::{self, AssertUnwindSafe};
-use std::sync::{Arc, Once};
+use std::fmt::Debug;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, Once};
 use std::thread;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 /// rustls 0.23+ requires a CryptoProvider be installed before any TLS handshake.
 /// The pgwire crate's `server-api-aws-lc-rs` feature pulls in `aws-lc-rs`, while
@@ -24,37 +27,60 @@ pub(crate) fn ensure_crypto_provider() {
 
 use duckdb::arrow::datatypes::Schema;
 use duckdb::arrow::record_batch::RecordBatch;
+use duckdb::arrow::util::display::array_value_to_string;
 use duckdb::params;
 use async_trait::async_trait;
-use futures::stream;
+use futures::{stream, Sink, SinkExt};
 use serde_json;
 use base64::{Engine as _, engine::general_purpose};
 
 use pgwire::api::auth::StartupHandler;
 use pgwire::api::auth::sasl::SASLAuthStartupHandler;
 use pgwire::api::auth::sasl::scram::{gen_salted_password, ScramAuth};
+use pgwire::api::auth::cleartext::CleartextPasswordAuthStartupHandler;
+use pgwire::api::auth::md5pass::{hash_md5_password, Md5PasswordAuthStartupHandler};
 use pgwire::api::auth::{AuthSource, DefaultServerParameterProvider, LoginInfo, Password};
-use pgwire::api::query::{ExtendedQueryHandler, SimpleQueryHandler};
+use pgwire::api::cancel::CancelHandler;
+use pgwire::api::copy::{send_copy_both_response, send_copy_in_response, send_copy_out_response, CopyHandler};
+use pgwire::api::query::{
+    send_execution_response, send_query_response, send_ready_for_query, ExtendedQueryHandler, SimpleQueryHandler,
+};
 use pgwire::api::stmt::NoopQueryParser;
-use pgwire::api::results::{Response, Tag, QueryResponse, DescribeStatementResponse, DescribePortalResponse, FieldInfo};
-use pgwire::api::{PgWireServerHandlers, ClientInfo, NoopHandler, Type};
+use pgwire::api::results::{CopyResponse, Response, Tag, QueryResponse, DescribeStatementResponse, DescribePortalResponse, FieldFormat, FieldInfo};
+use pgwire::api::{PgWireServerHandlers, ClientInfo, ClientPortalStore, NoopHandler, PgWireConnectionState, Type, DEFAULT_NAME};
 use pgwire::api::portal::{Portal, Format};
 use pgwire::api::stmt::StoredStatement;
+use pgwire::api::store::PortalStore;
 use pgwire::error::{ErrorInfo, PgWireError, PgWireResult};
+use pgwire::messages::cancel::CancelRequest;
+use pgwire::messages::copy::{CopyData, CopyDone, CopyFail};
+use pgwire::messages::data::{NoData, ParameterDescription};
+use pgwire::messages::extendedquery::{Describe, Sync as PgSync, TARGET_TYPE_BYTE_PORTAL, TARGET_TYPE_BYTE_STATEMENT};
+use pgwire::messages::response::{EmptyQueryResponse, NotificationResponse, ReadyForQuery, TransactionStatus};
+use pgwire::messages::simplequery::Query;
+use pgwire::messages::startup::{ParameterStatus, SecretKey};
+use pgwire::messages::{PgWireBackendMessage, PgWireFrontendMessage};
 use pgwire::tokio::process_socket;
 
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use tokio::sync::oneshot;
 
-use arrow_pg::datatypes::{encode_recordbatch, into_pg_type};
+use arrow_pg::datatypes::encode_recordbatch;
 
-use crate::get_describe_connection;
-use crate::server_registry::{ServerHandle, ServerRegistry};
+use crate::copy::{encode_row, parse_copy_statement, CopyDirection, CopyInDecoder, CopyStatement};
+use crate::{get_database_describe_connection, get_describe_connection};
+use crate::pg_types;
+use crate::query_rewrite;
+use crate::read_only;
+use crate::server_options::{AuthMethod, PgWireServerOptions};
+use crate::server_registry::{BackendKey, ServerHandle, ServerRegistry};
+use crate::session_params::{is_postgres_parameter, parse_session_command, SessionCommand, SessionParameters};
 
 const DEBUG_LOGGING: bool = false;
 
 #[inline]
-fn log_debug(_msg: &str) {
+pub(crate) fn log_debug(_msg: &str) {
     #[cfg(debug_assertions)]
     if DEBUG_LOGGING {
         eprintln!("[pgwire] {}", sanitize_log_message(_msg));
@@ -103,7 +129,9 @@ impl std::fmt::Debug for HanaCredentials {
 
 #[derive(Debug)]
 pub enum DatabaseAction {
-    SetDatabase,
+    /// Switch to a DuckDB catalog of the same name, attaching `attach_path`
+    /// under that name first when the entry points at a database file.
+    SetDatabase { attach_path: Option<String> },
     UseHana(HanaCredentials),
     Skip,
 }
@@ -116,7 +144,13 @@ pub fn check_database_action(database_name: &str, db_credentials: &str) -> Datab
                     for db in databases {
                         if let Some(db_id) = db.get("id").and_then(|v| v.as_str()) {
                             if db_id == database_name {
-                                if let Some(dialect) = db.get("dialect").and_then(|v| v.as_str()) {
+                                let attach_path = db.get("path").and_then(|v| v.as_str()).map(|p| p.to_string());
+                                // Entries with only a `path` are DuckDB files.
+                                let dialect = db
+                                    .get("dialect")
+                                    .and_then(|v| v.as_str())
+                                    .or(attach_path.as_ref().map(|_| "duckdb"));
+                                if let Some(dialect) = dialect {
                                     if dialect == "hana" {
                                         if let (Some(host), Some(port), Some(name)) = (
                                             db.get("host").and_then(|v| v.as_str()),
@@ -146,7 +180,7 @@ pub fn check_database_action(database_name: &str, db_credentials: &str) -> Datab
                                         }
                                         return DatabaseAction::Skip;
                                     } else {
-                                        return DatabaseAction::SetDatabase;
+                                        return DatabaseAction::SetDatabase { attach_path };
                                     }
                                 }
                             }
@@ -159,6 +193,82 @@ pub fn check_database_action(database_name: &str, db_credentials: &str) -> Datab
     DatabaseAction::Skip
 }
 
+/// Statements that point a connection at `database`: an `ATTACH` for
+/// file-backed entries followed by `USE`.
+fn database_context_sql(database: &str, attach_path: Option<&str>) -> Vec<String> {
+    let ident = format!("\"{}\"", database.replace('"', "\"\""));
+    let mut sql = Vec::new();
+    if let Some(path) = attach_path {
+        sql.push(format!("ATTACH IF NOT EXISTS '{}' AS {}", path.replace('\'', "''"), ident));
+    }
+    sql.push(format!("USE {}", ident));
+    sql
+}
+
+/// Whether `db_credentials` has an entry with the given id.
+fn database_listed(database_name: &str, db_credentials: &str) -> bool {
+    general_purpose::STANDARD
+        .decode(db_credentials)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        .and_then(|value| {
+            value.as_array().map(|dbs| {
+                dbs.iter()
+                    .any(|db| db.get("id").and_then(|v| v.as_str()) == Some(database_name))
+            })
+        })
+        .unwrap_or(false)
+}
+
+fn database_attached(database_name: &str) -> bool {
+    let Some(connection) = get_describe_connection(0) else {
+        return false;
+    };
+    let guard = connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    guard
+        .query_row(
+            "SELECT count(*) FROM duckdb_databases() WHERE database_name = ?",
+            params![database_name],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|n| n > 0)
+        .unwrap_or(false)
+}
+
+/// Reject a startup packet naming a database this server can't route to.
+/// Known names are `db_credentials` entries and catalogs already attached in
+/// DuckDB. libpq fills in the user name when no database is given, so that
+/// case keeps landing in the default catalog.
+async fn check_startup_database(
+    server_host: &str,
+    server_port: u16,
+    parameters: &std::collections::BTreeMap<String, String>,
+) -> PgWireResult<()> {
+    let Some(database) = parameters.get("database").filter(|d| !d.is_empty()) else {
+        return Ok(());
+    };
+    if parameters.get("user") == Some(database) {
+        return Ok(());
+    }
+    if let Some(db_credentials) = ServerRegistry::instance().get_db_credentials(server_host, server_port) {
+        if database_listed(database, &db_credentials) {
+            return Ok(());
+        }
+    }
+    let name = database.clone();
+    let attached = tokio::task::spawn_blocking(move || database_attached(&name))
+        .await
+        .unwrap_or(false);
+    if attached {
+        return Ok(());
+    }
+    Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+        "FATAL".to_owned(),
+        "3D000".to_owned(),
+        format!("database \"{}\" does not exist", database),
+    ))))
+}
+
 fn get_hana_credentials_if_available(
     database: &Option<String>,
     server_host: &str,
@@ -232,115 +342,832 @@ where
 /// so without this intercept the very first statement of a JDBC handshake
 /// fails with "Catalog Error: unrecognized configuration parameter".
 fn is_postgres_only_set(sql: &str) -> bool {
-    let s = sql.trim_start();
-    if s.len() < 4 || !s[..4].eq_ignore_ascii_case("SET ") {
-        return false;
+    matches!(
+        parse_session_command(sql),
+        Some(SessionCommand::Set { name, .. }) if is_postgres_parameter(&name)
+    )
+}
+
+/// LISTEN / UNLISTEN / NOTIFY, which DuckDB has no equivalent for. They are
+/// served by the process-local channel registry in `ServerRegistry`, so
+/// notifications reach listeners on any pgwire server in this process.
+/// Unlike Postgres, a NOTIFY is queued immediately rather than at commit.
+#[derive(Debug, PartialEq, Eq)]
+enum NotifyCommand {
+    Listen(String),
+    /// `None` is `UNLISTEN *`.
+    Unlisten(Option<String>),
+    Notify { channel: String, payload: String },
+}
+
+/// Parse a channel name the way Postgres parses an identifier: unquoted
+/// names fold to lower case. Returns the name and the remaining input.
+fn parse_channel(s: &str) -> Option<(String, &str)> {
+    let s = s.trim_start();
+    if let Some(rest) = s.strip_prefix('"') {
+        let mut name = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((i, c)) = chars.next() {
+            if c == '"' {
+                if rest[i + 1..].starts_with('"') {
+                    name.push('"');
+                    chars.next();
+                } else {
+                    return Some((name, &rest[i + 1..]));
+                }
+            } else {
+                name.push(c);
+            }
+        }
+        return None;
+    }
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+        .unwrap_or(s.len());
+    if end == 0 {
+        return None;
     }
-    let mut rest = s[4..].trim_start();
-    // SET LOCAL <name> ... and SET SESSION <name> ... are also valid.
-    for prefix in ["LOCAL ", "SESSION "] {
-        if rest.len() >= prefix.len() && rest[..prefix.len()].eq_ignore_ascii_case(prefix) {
-            rest = rest[prefix.len()..].trim_start();
-            break;
+    Some((s[..end].to_lowercase(), &s[end..]))
+}
+
+fn parse_notify_command(sql: &str) -> Option<NotifyCommand> {
+    let s = sql.trim().trim_end_matches(';').trim_end();
+    let keyword_end = s.find(char::is_whitespace).unwrap_or(s.len());
+    let rest = &s[keyword_end..];
+    match s[..keyword_end].to_ascii_uppercase().as_str() {
+        "LISTEN" => {
+            let (channel, tail) = parse_channel(rest)?;
+            tail.trim().is_empty().then_some(NotifyCommand::Listen(channel))
+        }
+        "UNLISTEN" => {
+            if rest.trim() == "*" {
+                return Some(NotifyCommand::Unlisten(None));
+            }
+            let (channel, tail) = parse_channel(rest)?;
+            tail.trim().is_empty().then_some(NotifyCommand::Unlisten(Some(channel)))
+        }
+        "NOTIFY" => {
+            let (channel, tail) = parse_channel(rest)?;
+            let tail = tail.trim();
+            if tail.is_empty() {
+                return Some(NotifyCommand::Notify { channel, payload: String::new() });
+            }
+            let literal = tail.strip_prefix(',')?.trim();
+            let inner = literal.strip_prefix('\'')?.strip_suffix('\'')?;
+            if inner.replace("''", "").contains('\'') {
+                return None;
+            }
+            Some(NotifyCommand::Notify { channel, payload: inner.replace("''", "'") })
         }
+        _ => None,
     }
-    let rest = rest.trim_start_matches('"');
-    let name: String = rest.chars()
-        .take_while(|c| c.is_alphanumeric() || *c == '_')
-        .collect();
-    matches!(
-        name.to_ascii_uppercase().as_str(),
-        "EXTRA_FLOAT_DIGITS"
-            | "APPLICATION_NAME"
-            | "CLIENT_ENCODING"
-            | "DATESTYLE"
-            | "INTERVALSTYLE"
-            | "TIMEZONE"
-            | "STATEMENT_TIMEOUT"
-            | "STANDARD_CONFORMING_STRINGS"
-            | "SEARCH_PATH"
-            | "BYTEA_OUTPUT"
-            | "ROW_SECURITY"
-            | "SESSION_AUTHORIZATION"
-    )
 }
 
 pub fn random_salt() -> Vec<u8> {
     Vec::from(rand::random::<[u8; 10]>())
 }
 
+/// What the server keeps to check a client's password.
+///
+/// For SCRAM-SHA-256 only the salt and the PBKDF2-derived salted password
+/// are retained, computed once at server start: the plaintext is dropped and
+/// the 4096 PBKDF2 iterations are no longer paid on every login. MD5 and
+/// cleartext auth need the plaintext (MD5 mixes in the login username), so
+/// those legacy modes keep it.
+pub enum PasswordVerifier {
+    Scram { salt: Vec<u8>, salted_password: Vec<u8> },
+    Plain(String),
+}
+
+impl PasswordVerifier {
+    pub fn for_method(method: AuthMethod, password: &str) -> Self {
+        match method {
+            AuthMethod::ScramSha256 => {
+                let salt = random_salt();
+                let salted_password = gen_salted_password(password, &salt, SCRAM_ITERATIONS);
+                PasswordVerifier::Scram { salt, salted_password }
+            }
+            AuthMethod::Md5 | AuthMethod::Password | AuthMethod::Trust => {
+                PasswordVerifier::Plain(password.to_string())
+            }
+        }
+    }
+}
+
 pub struct SimpleAuthSource {
-    required_password: String,
+    method: AuthMethod,
+    verifier: Arc<PasswordVerifier>,
 }
 
 impl std::fmt::Debug for SimpleAuthSource {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SimpleAuthSource")
-            .field("required_password", &"[REDACTED]")
+            .field("method", &self.method)
+            .field("verifier", &"[REDACTED]")
             .finish()
     }
 }
 
 impl SimpleAuthSource {
-    pub fn new(password: String) -> Self {
+    pub fn new(method: AuthMethod, verifier: Arc<PasswordVerifier>) -> Self {
+        Self { method, verifier }
+    }
+}
+
+#[async_trait]
+impl AuthSource for SimpleAuthSource {
+    async fn get_password(&self, login_info: &LoginInfo) -> PgWireResult<Password> {
+        match (self.method, self.verifier.as_ref()) {
+            (_, PasswordVerifier::Scram { salt, salted_password }) => {
+                Ok(Password::new(Some(salt.clone()), salted_password.clone()))
+            }
+            (AuthMethod::Md5, PasswordVerifier::Plain(password)) => {
+                let salt = Vec::from(rand::random::<[u8; 4]>());
+                let user = login_info.user().unwrap_or_default();
+                let hashed = hash_md5_password(user, password, &salt);
+                Ok(Password::new(Some(salt), hashed.into_bytes()))
+            }
+            (_, PasswordVerifier::Plain(password)) => {
+                Ok(Password::new(None, password.as_bytes().to_vec()))
+            }
+        }
+    }
+}
+
+/// Authentication step of startup. `PgWireServerHandlers` must return a
+/// single concrete type, so the configured method is selected here at
+/// runtime and the message is forwarded to pgwire's handler for it.
+enum AuthStartupHandler {
+    Trust(NoopHandler),
+    Scram(SASLAuthStartupHandler<DefaultServerParameterProvider>),
+    Md5(Md5PasswordAuthStartupHandler<SimpleAuthSource, DefaultServerParameterProvider>),
+    Password(CleartextPasswordAuthStartupHandler<SimpleAuthSource, DefaultServerParameterProvider>),
+}
+
+/// Startup handler shared by both server flavours: checks the requested
+/// database before handing the message to the authentication handler.
+pub struct TrexStartupHandler {
+    auth: AuthStartupHandler,
+    server_host: String,
+    server_port: u16,
+    backend_key: BackendKey,
+}
+
+impl TrexStartupHandler {
+    pub fn new(
+        method: AuthMethod,
+        verifier: Option<Arc<PasswordVerifier>>,
+        host: String,
+        port: u16,
+        backend_key: BackendKey,
+    ) -> Self {
+        let auth = match verifier {
+            Some(verifier) if method != AuthMethod::Trust => {
+                let auth_source = SimpleAuthSource::new(method, verifier);
+                let parameter_provider = DefaultServerParameterProvider::default();
+                match method {
+                    AuthMethod::Md5 => AuthStartupHandler::Md5(Md5PasswordAuthStartupHandler::new(
+                        Arc::new(auth_source),
+                        Arc::new(parameter_provider),
+                    )),
+                    AuthMethod::Password => AuthStartupHandler::Password(
+                        CleartextPasswordAuthStartupHandler::new(auth_source, parameter_provider),
+                    ),
+                    _ => {
+                        let mut scram_auth = ScramAuth::new(Arc::new(auth_source));
+                        scram_auth.set_iterations(SCRAM_ITERATIONS);
+                        AuthStartupHandler::Scram(
+                            SASLAuthStartupHandler::new(Arc::new(parameter_provider)).with_scram(scram_auth),
+                        )
+                    }
+                }
+            }
+            _ => AuthStartupHandler::Trust(NoopHandler),
+        };
         Self {
-            required_password: password,
+            auth,
+            server_host: host,
+            server_port: port,
+            backend_key,
         }
     }
 }
 
 #[async_trait]
-impl AuthSource for SimpleAuthSource {
-    async fn get_password(&self, _login_info: &LoginInfo) -> PgWireResult<Password> {
-        let salt = random_salt();
-        let hash_password = gen_salted_password(&self.required_password, salt.as_ref(), SCRAM_ITERATIONS);
-        Ok(Password::new(Some(salt), hash_password))
+impl StartupHandler for TrexStartupHandler {
+    async fn on_startup<C>(
+        &self,
+        client: &mut C,
+        message: PgWireFrontendMessage,
+    ) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: std::fmt::Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        if let PgWireFrontendMessage::Startup(startup) = &message {
+            check_startup_database(&self.server_host, self.server_port, &startup.parameters).await?;
+            // Sent back in BackendKeyData once authentication completes.
+            client.set_pid_and_secret_key(self.backend_key.pid, SecretKey::I32(self.backend_key.secret));
+            ServerRegistry::instance()
+                .set_backend_database(self.backend_key.pid, startup.parameters.get("database").cloned());
+        }
+        match &self.auth {
+            AuthStartupHandler::Trust(h) => h.on_startup(client, message).await,
+            AuthStartupHandler::Scram(h) => h.on_startup(client, message).await,
+            AuthStartupHandler::Md5(h) => h.on_startup(client, message).await,
+            AuthStartupHandler::Password(h) => h.on_startup(client, message).await,
+        }
     }
 }
 
+/// Batches COPY TO STDOUT may queue between the pool session producing
+/// them and the client socket draining them.
+const COPY_OUT_QUEUED_BATCHES: usize = 4;
+
+/// A COPY FROM STDIN between CopyInResponse and CopyDone or CopyFail.
+struct CopyInProgress {
+    decoder: CopyInDecoder,
+    /// Unquoted target name parts and column list for the Appender.
+    table: Vec<String>,
+    columns: Vec<String>,
+    width: usize,
+    rows: usize,
+    /// First failure; later CopyData is discarded and the error is
+    /// reported at CopyDone, as Postgres does.
+    error: Option<String>,
+    /// The COPY opened its own transaction because the client wasn't in
+    /// one, so CopyDone commits it and a failure rolls it back.
+    owns_transaction: bool,
+}
+
 #[derive(Clone)]
 pub struct TrexQueryHandler {
     server_host: String,
     server_port: u16,
     worker_id: usize,
     session_id: u64,
+    backend_key: BackendKey,
+    /// Refuse writes and DDL; see [`read_only::refused_verb`].
+    read_only: bool,
+    /// COPY FROM STDIN in progress on this connection, if any.
+    copy_in: Arc<Mutex<Option<CopyInProgress>>>,
+    /// Postgres session parameters set with SET on this connection.
+    session_params: Arc<Mutex<SessionParameters>>,
 }
 
 impl TrexQueryHandler {
-    pub fn new(host: String, port: u16, worker_id: usize, session_id: u64) -> Self {
+    pub fn new(
+        host: String,
+        port: u16,
+        worker_id: usize,
+        session_id: u64,
+        backend_key: BackendKey,
+        read_only: bool,
+    ) -> Self {
         Self {
             server_host: host,
             server_port: port,
             worker_id,
             session_id,
+            backend_key,
+            read_only,
+            copy_in: Arc::new(Mutex::new(None)),
+            session_params: Arc::new(Mutex::new(SessionParameters::new())),
+        }
+    }
+
+    /// Switch the session to the database named in the startup packet when
+    /// the server's credentials map it to a local database.
+    async fn set_database_context<C>(&self, client: &C) -> PgWireResult<()>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        let login_info = LoginInfo::from_client_info(client);
+        let context_sql = self.database_context_sql(login_info.database());
+        if context_sql.is_empty() {
+            return Ok(());
+        }
+        let session_id = self.session_id;
+        let result = tokio::task::spawn_blocking(move || {
+            context_sql
+                .iter()
+                .try_for_each(|sql| trex_pool_client::session_execute(session_id, sql).map(|_| ()))
+        })
+        .await
+        .unwrap_or_else(|e| Err(format!("spawn error: {e}")));
+        if let Err(err) = result {
+            return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_owned(),
+                "XX000".to_owned(),
+                format!("Failed to set database context: {}", err),
+            ))));
+        }
+        Ok(())
+    }
+
+    /// Statements pointing a connection at `database`, or none when it isn't
+    /// a DuckDB entry of `db_credentials`.
+    fn database_context_sql(&self, database: Option<&str>) -> Vec<String> {
+        let Some(db) = database else {
+            return Vec::new();
+        };
+        let Some(db_credentials) = ServerRegistry::instance().get_db_credentials(&self.server_host, self.server_port) else {
+            return Vec::new();
+        };
+        match check_database_action(db, &db_credentials) {
+            DatabaseAction::SetDatabase { attach_path } => database_context_sql(db, attach_path.as_deref()),
+            _ => Vec::new(),
+        }
+    }
+
+    async fn session_query(&self, sql: String) -> PgWireResult<(Arc<Schema>, Vec<RecordBatch>)> {
+        self.session_query_params(sql, Vec::new()).await
+    }
+
+    /// Run SQL on this connection's pool session.
+    async fn session_query_params(
+        &self,
+        sql: String,
+        params: Vec<Option<String>>,
+    ) -> PgWireResult<(Arc<Schema>, Vec<RecordBatch>)> {
+        self.run_on_session(sql, move |session_id, sql| {
+            if params.is_empty() {
+                trex_pool_client::session_execute(session_id, sql)
+            } else {
+                trex_pool_client::session_execute_nullable_params(session_id, sql, &params)
+            }
+        })
+        .await
+    }
+
+    /// Run `task` for `sql` against this connection's pool session on the
+    /// blocking pool. The query is registered as running so a CancelRequest
+    /// carrying our backend key can interrupt it, in which case the error is
+    /// reported as `query_canceled`. A query running past the session's
+    /// `statement_timeout` is interrupted too.
+    async fn run_on_session<T, F>(&self, sql: String, task: F) -> PgWireResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(u64, &str) -> Result<T, String> + Send + 'static,
+    {
+        let session_id = self.session_id;
+        let timeout = self
+            .session_params
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .statement_timeout();
+        let running = ServerRegistry::instance().begin_query(self.backend_key.pid, &sql);
+        let mut task = tokio::task::spawn_blocking(move || task(session_id, &sql));
+        let mut timed_out = false;
+        let joined = match timeout {
+            Some(limit) => match tokio::time::timeout(limit, &mut task).await {
+                Ok(joined) => joined,
+                Err(_) => {
+                    timed_out = true;
+                    log_debug(&format!("Statement timeout after {:?} on backend {}", limit, self.backend_key.pid));
+                    if let Err(e) = trex_pool_client::session_interrupt(session_id) {
+                        log_debug(&format!("session_interrupt: {e}"));
+                    }
+                    task.await
+                }
+            },
+            None => task.await,
+        };
+        let result = joined.map_err(|e| {
+            PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_owned(),
+                "XX000".to_owned(),
+                format!("Query execution failed: {}", e),
+            )))
+        })?;
+        result.map_err(|e| {
+            if timed_out {
+                PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "57014".to_owned(),
+                    "canceling statement due to statement timeout".to_owned(),
+                )))
+            } else if running.as_ref().is_some_and(|r| r.was_cancelled()) {
+                PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "57014".to_owned(),
+                    "canceling statement due to user request".to_owned(),
+                )))
+            } else {
+                PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "XX000".to_owned(),
+                    e,
+                )))
+            }
+        })
+    }
+
+    /// Restart this connection's idle timer.
+    fn touch(&self) {
+        ServerRegistry::instance().touch_backend(self.backend_key.pid);
+    }
+
+    /// Fail with `read_only_sql_transaction` when this is a read-only server
+    /// and any of `statements` would write.
+    fn check_read_only(&self, statements: &[&str]) -> PgWireResult<()> {
+        if !self.read_only {
+            return Ok(());
+        }
+        match statements.iter().find_map(|sql| read_only::refused_verb(sql)) {
+            Some(verb) => Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_owned(),
+                "25006".to_owned(),
+                format!("cannot execute {} on a read-only server", verb),
+            )))),
+            None => Ok(()),
+        }
+    }
+
+    /// Rewrite `sql` with pgt when the server has a rewrite dialect set, and
+    /// return it unchanged otherwise. A query pgt can't transform fails with
+    /// the transformer's error instead of running as written.
+    async fn rewrite_query(&self, sql: &str) -> PgWireResult<String> {
+        let Some(dialect) = ServerRegistry::instance().get_rewrite_dialect(&self.server_host, self.server_port) else {
+            return Ok(sql.to_string());
+        };
+        let rewrite_error = |code: &str, e: String| {
+            PgWireError::UserError(Box::new(ErrorInfo::new(
+                "ERROR".to_owned(),
+                code.to_owned(),
+                format!("Failed to rewrite query to {}: {}", dialect.name(), e),
+            )))
+        };
+        let transformer = query_rewrite::transformer_for(dialect).map_err(|e| rewrite_error("XX000", e))?;
+        let rewritten = transformer
+            .transform_async(sql)
+            .await
+            .map_err(|e| rewrite_error(query_rewrite::error_code(&e), e.to_string()))?;
+        log_debug(&format!("Rewrote query to {}: {}", dialect.name(), rewritten));
+        Ok(rewritten.trim().to_string())
+    }
+
+    /// Check the COPY target exists, open a transaction for the incoming
+    /// rows unless the client is already in one, and answer with
+    /// CopyInResponse. `transaction_status` is the connection's status
+    /// after the statements before the COPY.
+    async fn begin_copy_in(
+        &self,
+        stmt: CopyStatement,
+        transaction_status: TransactionStatus,
+    ) -> PgWireResult<Response> {
+        let (schema, _) = self.session_query(format!("{} LIMIT 0", stmt.select_sql())).await?;
+        let width = schema.fields().len();
+        let Some((table, columns)) = stmt.target_parts() else {
+            return Err(copy_error("COPY FROM STDIN requires a table, not a query".to_string()));
+        };
+        let owns_transaction = matches!(transaction_status, TransactionStatus::Idle);
+        if owns_transaction {
+            self.session_query("BEGIN".to_string()).await?;
+        }
+        *self.copy_in.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(CopyInProgress {
+            decoder: CopyInDecoder::new(stmt),
+            table,
+            columns,
+            width,
+            rows: 0,
+            error: None,
+            owns_transaction,
+        });
+        Ok(Response::CopyIn(CopyResponse::new(0, width, vec![0; width])))
+    }
+
+    /// Append decoded COPY rows through the session's Appender.
+    async fn append_copy_rows(
+        &self,
+        copy: &mut CopyInProgress,
+        rows: Vec<Vec<Option<String>>>,
+    ) -> Result<(), String> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != copy.width) {
+            return Err(format!(
+                "COPY row has {} columns but the target has {}",
+                row.len(),
+                copy.width
+            ));
+        }
+        let session_id = self.session_id;
+        let table = copy.table.clone();
+        let columns = copy.columns.clone();
+        let width = copy.width;
+        let cells: Vec<Option<String>> = rows.into_iter().flatten().collect();
+        let appended = tokio::task::spawn_blocking(move || {
+            trex_pool_client::session_append(session_id, &table, &columns, &cells, width)
+        })
+        .await
+        .map_err(|e| e.to_string())??;
+        copy.rows += appended;
+        Ok(())
+    }
+
+    /// Undo a COPY FROM STDIN that failed, when it opened the transaction.
+    async fn abort_copy_in(&self, copy: &CopyInProgress) {
+        if copy.owns_transaction {
+            if let Err(e) = self.session_query("ROLLBACK".to_string()).await {
+                log_debug(&format!("COPY rollback failed: {}", e));
+            }
+        }
+    }
+
+    /// Run the COPY TO STDOUT query and stream its rows as CopyData, one
+    /// batch at a time as the pool session hands them over. The default
+    /// query flow can only announce CopyOut, not send the data, so this
+    /// drives the whole exchange including ReadyForQuery.
+    async fn copy_out<C>(&self, client: &mut C, stmt: CopyStatement) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        if !matches!(client.state(), PgWireConnectionState::ReadyForQuery) {
+            return Err(PgWireError::NotReadyForQuery);
+        }
+        client.set_state(PgWireConnectionState::QueryInProgress);
+
+        self.set_database_context(client).await?;
+        let (tx, rx) = tokio::sync::mpsc::channel::<RecordBatch>(COPY_OUT_QUEUED_BATCHES);
+        // Dropping `rx`, e.g. because the client went away mid-copy, makes
+        // the send fail and stops the query.
+        let query = self.run_on_session(stmt.select_sql(), move |session_id, sql| {
+            trex_pool_client::session_execute_stream(session_id, sql, |batch| tx.blocking_send(batch).is_ok())
+        });
+        let (queried, sent) = futures::join!(query, send_copy_out_rows(&mut *client, &stmt, rx));
+        let rows = match (queried, sent?) {
+            (Ok(()), Some(rows)) => rows,
+            (Err(e), _) => return Err(e),
+            (Ok(()), None) => return Err(copy_error("COPY query returned no result".to_string())),
+        };
+        client.feed(PgWireBackendMessage::CopyDone(CopyDone::new())).await?;
+        send_execution_response(client, Tag::new("COPY").with_rows(rows)).await?;
+
+        self.deliver_notifications(client).await?;
+        let transaction_status = client.transaction_status();
+        client.set_state(PgWireConnectionState::ReadyForQuery);
+        send_ready_for_query(client, transaction_status).await
+    }
+
+    fn run_notify_command(&self, command: NotifyCommand) -> Response {
+        let registry = ServerRegistry::instance();
+        let pid = self.backend_key.pid;
+        let tag = match command {
+            NotifyCommand::Listen(channel) => {
+                registry.listen(pid, &channel);
+                "LISTEN"
+            }
+            NotifyCommand::Unlisten(Some(channel)) => {
+                registry.unlisten(pid, &channel);
+                "UNLISTEN"
+            }
+            NotifyCommand::Unlisten(None) => {
+                registry.unlisten_all(pid);
+                "UNLISTEN"
+            }
+            NotifyCommand::Notify { channel, payload } => {
+                registry.notify(pid, &channel, &payload);
+                "NOTIFY"
+            }
+        };
+        Response::Execution(Tag::new(tag))
+    }
+
+    /// Apply SET / RESET / SHOW of a session parameter. Returns `None` when
+    /// the statement belongs to DuckDB (`SET threads = 4`, `SHOW tables`)
+    /// and should run on the session like any other query.
+    async fn run_session_command<C>(
+        &self,
+        client: &C,
+        command: SessionCommand,
+        sql: &str,
+    ) -> PgWireResult<Option<Response>>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        let startup = client.metadata();
+        match command {
+            SessionCommand::Set { name, value } => {
+                if !is_postgres_parameter(&name) {
+                    // DuckDB settings keep working; anything DuckDB does not
+                    // know is stored as a custom parameter instead of failing.
+                    match self.session_query(sql.to_string()).await {
+                        Ok(_) => return Ok(Some(Response::Execution(Tag::new("SET")))),
+                        Err(e) if is_unrecognized_parameter(&e) => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                log_debug(&format!("Storing session parameter: {}", name));
+                self.session_params
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .set(&name, value, startup)
+                    .map_err(|e| {
+                        PgWireError::UserError(Box::new(ErrorInfo::new("ERROR".to_owned(), e.code.to_owned(), e.message)))
+                    })?;
+                Ok(Some(Response::Execution(Tag::new("SET"))))
+            }
+            SessionCommand::Reset(name) => {
+                let mut params = self.session_params.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                match name {
+                    None => params.reset_all(startup),
+                    Some(name) if is_postgres_parameter(&name) || params.contains(&name) => params.reset(&name, startup),
+                    Some(_) => return Ok(None),
+                }
+                Ok(Some(Response::Execution(Tag::new("RESET"))))
+            }
+            SessionCommand::Show(name) => {
+                let params = self.session_params.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+                let (columns, rows) = match name {
+                    None => (vec!["name".to_string(), "setting".to_string()], params.all(startup)),
+                    Some(name) => match params.get(&name, startup) {
+                        Some((display, value)) => (vec![display.clone()], vec![(display, value)]),
+                        None => return Ok(None),
+                    },
+                };
+                drop(params);
+                let rows = rows
+                    .into_iter()
+                    .map(|(name, value)| if columns.len() == 1 { vec![value] } else { vec![name, value] })
+                    .collect();
+                let mut response = text_query_response(&columns, rows)?;
+                response.set_command_tag("SHOW");
+                Ok(Some(Response::Query(response)))
+            }
+        }
+    }
+
+    /// Describe a statement `run_session_command` answers without DuckDB.
+    fn describe_session_command<C>(&self, client: &C, sql: &str) -> Option<Vec<FieldInfo>>
+    where
+        C: ClientInfo,
+    {
+        if is_postgres_only_set(sql) {
+            return Some(Vec::new());
+        }
+        let params = self.session_params.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let text_field = |name: &str| FieldInfo::new(name.to_string(), None, None, Type::TEXT, FieldFormat::Text);
+        match parse_session_command(sql)? {
+            SessionCommand::Show(None) => Some(vec![text_field("name"), text_field("setting")]),
+            SessionCommand::Show(Some(name)) => params.get(&name, client.metadata()).map(|(display, _)| vec![text_field(&display)]),
+            SessionCommand::Reset(None) => Some(Vec::new()),
+            SessionCommand::Reset(Some(name)) if is_postgres_parameter(&name) => Some(Vec::new()),
+            _ => None,
+        }
+    }
+
+    /// Send ParameterStatus updates and notifications queued for this
+    /// connection. Called just before ReadyForQuery, which is where Postgres
+    /// delivers notifications too.
+    async fn deliver_notifications<C>(&self, client: &mut C) -> PgWireResult<()>
+    where
+        C: Sink<PgWireBackendMessage> + Unpin + Send,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        let updates = self
+            .session_params
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take_pending();
+        for (name, value) in updates {
+            client
+                .feed(PgWireBackendMessage::ParameterStatus(ParameterStatus::new(name, value)))
+                .await?;
+        }
+        for n in ServerRegistry::instance().take_notifications(self.backend_key.pid) {
+            client
+                .feed(PgWireBackendMessage::NotificationResponse(NotificationResponse::new(
+                    n.pid, n.channel, n.payload,
+                )))
+                .await?;
         }
+        Ok(())
     }
 }
 
-/// Convert trexsql statement columns to pgwire field info (for describe operations)
-fn row_desc_from_stmt(stmt: &duckdb::Statement, format: &Format) -> PgWireResult<Vec<FieldInfo>> {
-    let columns = stmt.column_count();
-    if columns == 1 {
-        let name = stmt.column_name(0).cloned().unwrap_or_default();
-        let datatype = stmt.column_type(0);
-        let pg = into_pg_type(&datatype).unwrap_or(Type::TEXT);
-        if (name == "Success" && pg == Type::BOOL) || (name == "Count" && pg == Type::INT8) {
-            return Ok(Vec::new());
+/// DuckDB's error for a `SET` of a setting it does not have.
+fn is_unrecognized_parameter(err: &PgWireError) -> bool {
+    matches!(err, PgWireError::UserError(info) if info.message.contains("unrecognized configuration parameter"))
+}
+
+/// A result set of text columns, used for answers produced without DuckDB.
+fn text_query_response(columns: &[String], rows: Vec<Vec<String>>) -> PgWireResult<QueryResponse> {
+    use duckdb::arrow::array::{ArrayRef, StringArray};
+    use duckdb::arrow::datatypes::{DataType, Field};
+    let schema = Arc::new(Schema::new(
+        columns.iter().map(|c| Field::new(c, DataType::Utf8, false)).collect::<Vec<_>>(),
+    ));
+    let arrays: Vec<ArrayRef> = (0..columns.len())
+        .map(|idx| Arc::new(StringArray::from_iter_values(rows.iter().map(|r| r[idx].as_str()))) as ArrayRef)
+        .collect();
+    let batch = RecordBatch::try_new(schema.clone(), arrays)
+        .map_err(|e| PgWireError::ApiError(Box::new(e)))?;
+    let header = Arc::new(schema_to_field_info(&schema, &Format::UnifiedText)?);
+    let data = encode_batches_safely(header.clone(), vec![batch]);
+    Ok(QueryResponse::new(header, stream::iter(data)))
+}
+
+/// Encode the batches of a COPY TO STDOUT as CopyData, flushing after each
+/// batch. The first batch only announces the columns. Returns the rows
+/// sent, or `None` when the query ended before producing its schema.
+async fn send_copy_out_rows<C>(
+    client: &mut C,
+    stmt: &CopyStatement,
+    mut batches: tokio::sync::mpsc::Receiver<RecordBatch>,
+) -> PgWireResult<Option<usize>>
+where
+    C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+    C::Error: Debug,
+    PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+{
+    let Some(first) = batches.recv().await else {
+        return Ok(None);
+    };
+    let columns = first.num_columns();
+    send_copy_out_response(client, CopyResponse::new(0, columns, vec![0; columns])).await?;
+
+    if stmt.header {
+        let names: Vec<Option<String>> = first.schema().fields().iter().map(|f| Some(f.name().clone())).collect();
+        client
+            .feed(PgWireBackendMessage::CopyData(CopyData::new(encode_row(stmt, &names).into_bytes().into())))
+            .await?;
+    }
+
+    let mut rows = 0;
+    while let Some(batch) = batches.recv().await {
+        let rb = rebuild_record_batch_for_pg(batch);
+        for row in 0..rb.num_rows() {
+            let values = rb
+                .columns()
+                .iter()
+                .map(|col| {
+                    if col.is_null(row) {
+                        Ok(None)
+                    } else {
+                        array_value_to_string(col, row).map(Some)
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| copy_error(e.to_string()))?;
+            client
+                .feed(PgWireBackendMessage::CopyData(CopyData::new(encode_row(stmt, &values).into_bytes().into())))
+                .await?;
+            rows += 1;
         }
+        client.flush().await?;
     }
-    (0..columns)
+    Ok(Some(rows))
+}
+
+fn copy_error(msg: String) -> PgWireError {
+    PgWireError::UserError(Box::new(ErrorInfo::new(
+        "ERROR".to_owned(),
+        "22P04".to_owned(),
+        msg,
+    )))
+}
+
+/// Convert trexsql statement columns to pgwire field info (for describe
+/// operations), along with each column's type modifier.
+///
+/// Only the Arrow type of each column is known here, not its extension
+/// metadata, so a UUID or JSON column described before execution is `text`.
+fn row_desc_from_stmt(stmt: &duckdb::Statement, format: &Format) -> PgWireResult<(Vec<FieldInfo>, Vec<i32>)> {
+    use duckdb::arrow::datatypes::Field;
+    let columns = stmt.column_count();
+    let fields: Vec<Field> = (0..columns)
         .map(|idx| {
-            let datatype = stmt.column_type(idx);
             let name = stmt.column_name(idx).map_or("unknown".to_string(), |v| v.clone());
-            Ok(FieldInfo::new(
-                name.to_string(),
+            Field::new(name, stmt.column_type(idx), true)
+        })
+        .collect();
+    if let [field] = fields.as_slice() {
+        let pg = pg_types::pg_type(field);
+        if (field.name() == "Success" && pg == Type::BOOL) || (field.name() == "Count" && pg == Type::INT8) {
+            return Ok((Vec::new(), Vec::new()));
+        }
+    }
+    let modifiers = fields.iter().map(|f| pg_types::type_modifier(f.data_type())).collect();
+    let infos = fields
+        .iter()
+        .enumerate()
+        .map(|(idx, field)| {
+            FieldInfo::new(
+                field.name().clone(),
                 None,
                 None,
-                into_pg_type(&datatype).unwrap_or(Type::TEXT),
-                format.format_for(idx),
-            ))
+                pg_types::pg_type(field),
+                result_field_format(format, idx, field.data_type()),
+            )
         })
-        .collect()
+        .collect();
+    Ok((infos, modifiers))
 }
 
 /// Detects DuckDB's synthetic result schemas for statements that have no
@@ -362,57 +1189,315 @@ fn is_duckdb_non_query_schema(schema: &duckdb::arrow::datatypes::Schema) -> bool
     )
 }
 
-/// Convert Arrow schema to pgwire field info.
-///
-/// The pg type is derived from the *original* Arrow data type (so TIMESTAMPTZ
-/// columns advertise OID 1184 to the client) even when the column is later
-/// cast to Utf8 by `rebuild_record_batch_for_pg` for safe text encoding.
+/// CommandComplete tag for a statement answered with DuckDB's synthetic
+/// schema, in the form PostgreSQL uses. DML and COPY report the affected
+/// rows DuckDB returns in `Count` (`INSERT 0 5`, `UPDATE 3`), as does
+/// `CREATE TABLE ... AS` (`SELECT 5`); DDL names the object kind
+/// (`CREATE TABLE`, `DROP VIEW`) and other commands their verb (`BEGIN`,
+/// `SET`). Only a statement without any keyword is `OK 0`.
+fn execution_tag(sql: &str, schema: &Schema, batches: &[RecordBatch]) -> Tag {
+    let rows = affected_rows(schema, batches);
+    let words = command_words(sql);
+    let Some(verb) = words.first().map(String::as_str) else {
+        return Tag::new("OK").with_rows(0);
+    };
+    match verb {
+        "INSERT" => Tag::new("INSERT").with_oid(0).with_rows(rows),
+        "UPDATE" | "DELETE" | "MERGE" | "COPY" => Tag::new(verb).with_rows(rows),
+        "CREATE" | "DROP" | "ALTER" => {
+            let kind = object_kind(&words[1..]);
+            if verb == "CREATE" && kind == Some("TABLE") && words.iter().any(|w| w == "AS") {
+                return Tag::new("SELECT").with_rows(rows);
+            }
+            match kind {
+                Some(kind) => Tag::new(&format!("{} {}", verb, kind)),
+                None => Tag::new(verb),
+            }
+        }
+        "START" => Tag::new("START TRANSACTION"),
+        "END" => Tag::new("COMMIT"),
+        "ABORT" => Tag::new("ROLLBACK"),
+        "TRUNCATE" => Tag::new("TRUNCATE TABLE"),
+        _ => Tag::new(verb),
+    }
+}
+
+/// The response for a statement that returned no rows: `execution_tag`,
+/// as a transaction start or end for transaction control so pgwire tracks
+/// the status ReadyForQuery reports.
+fn execution_response(sql: &str, schema: &Schema, batches: &[RecordBatch]) -> Response {
+    let tag = execution_tag(sql, schema, batches);
+    match command_words(sql).first().map(String::as_str) {
+        Some("BEGIN" | "START") => Response::TransactionStart(tag),
+        Some("COMMIT" | "END" | "ROLLBACK" | "ABORT") => Response::TransactionEnd(tag),
+        _ => Response::Execution(tag),
+    }
+}
+
+/// Object kind of a CREATE, DROP or ALTER from the words after the verb,
+/// past modifiers such as `OR REPLACE`, `TEMP` and `UNIQUE`.
+fn object_kind(words: &[String]) -> Option<&'static str> {
+    const MODIFIERS: &[&str] = &["OR", "REPLACE", "TEMP", "TEMPORARY", "UNIQUE", "UNLOGGED", "PERSISTENT"];
+    const KINDS: &[&str] = &[
+        "TABLE", "VIEW", "INDEX", "SCHEMA", "SEQUENCE", "TYPE", "MACRO", "FUNCTION", "SECRET", "DATABASE",
+        "EXTENSION", "ROLE", "USER",
+    ];
+    let mut words = words.iter().map(String::as_str).skip_while(|w| MODIFIERS.contains(w));
+    match words.next()? {
+        "MATERIALIZED" if words.next() == Some("VIEW") => Some("MATERIALIZED VIEW"),
+        "FOREIGN" if words.next() == Some("TABLE") => Some("FOREIGN TABLE"),
+        kind => KINDS.iter().copied().find(|k| *k == kind),
+    }
+}
+
+/// Command of the tag sent after a row-returning statement; pgwire appends
+/// the number of rows sent. Only DML with RETURNING differs from `SELECT`,
+/// also when a `WITH` clause comes first.
+fn query_command_tag(sql: &str) -> &'static str {
+    match statement_verb(sql).as_deref() {
+        Some("INSERT") => "INSERT 0",
+        Some("UPDATE") => "UPDATE",
+        Some("DELETE") => "DELETE",
+        Some("MERGE") => "MERGE",
+        _ => "SELECT",
+    }
+}
+
+/// Sum of DuckDB's `Count` column, the rows changed by a DML statement.
+fn affected_rows(schema: &Schema, batches: &[RecordBatch]) -> usize {
+    use duckdb::arrow::array::{Array, Int64Array};
+    use duckdb::arrow::datatypes::DataType;
+    match schema.fields().first() {
+        Some(f) if f.name() == "Count" && f.data_type() == &DataType::Int64 => {}
+        _ => return 0,
+    }
+    batches
+        .iter()
+        .filter_map(|b| b.column(0).as_any().downcast_ref::<Int64Array>())
+        .flat_map(|a| a.iter().flatten())
+        .map(|n| n.max(0) as usize)
+        .sum()
+}
+
+/// First keyword of the command in `sql`, upper-cased; see
+/// [`command_words`].
+fn statement_verb(sql: &str) -> Option<String> {
+    command_words(sql).into_iter().next()
+}
+
+/// Keywords that start the statement a `WITH` clause belongs to.
+const WITH_STATEMENT_VERBS: &[&str] = &["SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "VALUES", "TABLE", "FROM"];
+
+/// Top-level words of `sql`, upper-cased: outside parentheses, string
+/// literals and comments, past opening parentheses and any `WITH` clause.
+/// Quoted identifiers are kept as written.
+fn command_words(sql: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut depth = 0usize;
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        let skip = if rest.starts_with("--") {
+            rest.find('\n').unwrap_or(rest.len())
+        } else if rest.starts_with("/*") {
+            rest.find("*/").map_or(rest.len(), |end| end + 2)
+        } else if c == '\'' || c == '"' {
+            let len = rest[1..].find(c).map_or(rest.len(), |end| end + 2);
+            if c == '"' && depth == 0 {
+                words.push(rest[..len].to_string());
+            }
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            if depth == 0 {
+                words.push(rest[..len].to_ascii_uppercase());
+            }
+            len
+        } else {
+            // Leading parentheses wrap the statement itself.
+            if c == '(' && !words.is_empty() {
+                depth += 1;
+            } else if c == ')' {
+                depth = depth.saturating_sub(1);
+            }
+            c.len_utf8()
+        };
+        rest = &rest[skip..];
+    }
+    if words.first().is_some_and(|w| w == "WITH") {
+        match words.iter().position(|w| WITH_STATEMENT_VERBS.contains(&w.as_str())) {
+            Some(verb) => {
+                words.drain(..verb);
+            }
+            None => words.truncate(1),
+        }
+    }
+    words
+}
+
+/// Convert Arrow schema to pgwire field info.
+///
+/// The pg type is derived from the *original* Arrow data type (so TIMESTAMPTZ
+/// columns advertise OID 1184 to the client) even when the column is later
+/// cast to Utf8 by `rebuild_record_batch_for_pg` for safe text encoding.
 fn schema_to_field_info(schema: &duckdb::arrow::datatypes::Schema, format: &Format) -> PgWireResult<Vec<FieldInfo>> {
     schema.fields().iter().enumerate().map(|(idx, field)| {
-        let pg_type = arrow_type_to_pg_type(field.data_type());
+        let pg_type = pg_types::pg_type(field);
         Ok(FieldInfo::new(
             field.name().clone(),
             None,
             None,
             pg_type,
-            format.format_for(idx),
+            result_field_format(format, idx, field.data_type()),
         ))
     }).collect()
 }
 
-/// Convert Arrow data type to PostgreSQL type
-fn arrow_type_to_pg_type(arrow_type: &duckdb::arrow::datatypes::DataType) -> Type {
+/// Resolve the wire format for result column `idx`.
+///
+/// Binary output is only produced for the types whose Arrow value maps
+/// directly onto the Postgres binary layout of the advertised OID (int2/4/8,
+/// float4/8, bool, date, timestamp, timestamptz, text and bytea); see
+/// [`binary_timestamptz_as_utc`] for timestamptz. Everything else is
+/// either pre-cast to Utf8 by `rebuild_record_batch_for_pg` or encoded
+/// through a widened Rust type, so its binary bytes would not match the OID
+/// in the RowDescription; those columns stay in text format, which the
+/// per-column format code in RowDescription tells the client.
+fn result_field_format(format: &Format, idx: usize, dt: &duckdb::arrow::datatypes::DataType) -> FieldFormat {
+    match format.format_for(idx) {
+        FieldFormat::Binary if supports_binary_format(dt) => FieldFormat::Binary,
+        _ => FieldFormat::Text,
+    }
+}
+
+fn supports_binary_format(dt: &duckdb::arrow::datatypes::DataType) -> bool {
     use duckdb::arrow::datatypes::DataType;
-    match arrow_type {
-        DataType::Boolean => Type::BOOL,
-        DataType::Int8 | DataType::Int16 => Type::INT2,
-        DataType::Int32 => Type::INT4,
-        DataType::Int64 => Type::INT8,
-        DataType::UInt8 | DataType::UInt16 => Type::INT2,
-        DataType::UInt32 => Type::INT4,
-        DataType::UInt64 => Type::INT8,
-        DataType::Float16 | DataType::Float32 => Type::FLOAT4,
-        DataType::Float64 => Type::FLOAT8,
-        DataType::Decimal128(_, _) | DataType::Decimal256(_, _) => Type::NUMERIC,
-        DataType::Utf8 | DataType::LargeUtf8 => Type::TEXT,
-        DataType::Date32 | DataType::Date64 => Type::DATE,
-        // Timestamp WITH timezone -> TIMESTAMPTZ (OID 1184).
-        // Timestamp WITHOUT timezone -> TIMESTAMP (OID 1114).
-        // arrow-pg's encoder relies on this distinction to format the value
-        // (it formats DateTime<FixedOffset> for TIMESTAMPTZ, NaiveDateTime
-        // for TIMESTAMP). Returning TIMESTAMP for a tz-aware column makes
-        // text-mode encoding produce a value with no offset, but more
-        // importantly the column is also pre-cast to Utf8 below to avoid
-        // arrow-pg's Tz::from_str path, which panics on DuckDB's UTC offset
-        // tz strings (e.g. "+00:00") that chrono-tz cannot parse as IANA.
-        DataType::Timestamp(_, Some(_)) => Type::TIMESTAMPTZ,
-        DataType::Timestamp(_, None) => Type::TIMESTAMP,
-        DataType::Time32(_) | DataType::Time64(_) => Type::TIME,
-        DataType::Binary | DataType::LargeBinary => Type::BYTEA,
-        _ => Type::TEXT,
+    matches!(
+        dt,
+        DataType::Boolean
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Date32
+            | DataType::Timestamp(_, _)
+            | DataType::Utf8
+            | DataType::LargeUtf8
+            | DataType::Binary
+            | DataType::LargeBinary
+    )
+}
+
+/// Decode the values bound to a portal into the text form DuckDB binds
+/// against `$n` placeholders. SQL NULLs stay `None`.
+///
+/// Text-format parameters are already in Postgres text syntax and pass
+/// through as-is. Binary-format parameters are decoded from their big-endian
+/// wire layout using the type OID the client declared in Parse; a binary
+/// parameter with no declared (or an unsupported) type is rejected because
+/// its bytes cannot be interpreted.
+fn decode_portal_parameters(portal: &Portal<String>) -> PgWireResult<Vec<Option<String>>> {
+    portal
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(idx, param)| {
+            let Some(bytes) = param else {
+                return Ok(None);
+            };
+            if portal.parameter_format.is_text(idx) {
+                return String::from_utf8(bytes.to_vec()).map(Some).map_err(|e| {
+                    parameter_error(idx, &format!("invalid UTF-8 in text parameter: {e}"))
+                });
+            }
+            let pg_type = portal
+                .statement
+                .parameter_types
+                .get(idx)
+                .cloned()
+                .flatten()
+                .unwrap_or(Type::UNKNOWN);
+            decode_binary_parameter(bytes, &pg_type)
+                .map(Some)
+                .map_err(|e| parameter_error(idx, &e))
+        })
+        .collect()
+}
+
+fn parameter_error(idx: usize, msg: &str) -> PgWireError {
+    PgWireError::UserError(Box::new(ErrorInfo::new(
+        "ERROR".to_owned(),
+        "22P03".to_owned(),
+        format!("parameter ${}: {}", idx + 1, msg),
+    )))
+}
+
+/// Microseconds between the UNIX epoch and the Postgres epoch (2000-01-01).
+const PG_EPOCH_OFFSET_MICROS: i64 = 946_684_800_000_000;
+/// Days between the UNIX epoch and the Postgres epoch (2000-01-01).
+const PG_EPOCH_OFFSET_DAYS: i64 = 10_957;
+
+fn decode_binary_parameter(bytes: &[u8], pg_type: &Type) -> Result<String, String> {
+    fn fixed<const N: usize>(bytes: &[u8], name: &str) -> Result<[u8; N], String> {
+        bytes
+            .try_into()
+            .map_err(|_| format!("expected {} bytes for {}, got {}", N, name, bytes.len()))
+    }
+
+    match *pg_type {
+        Type::BOOL => Ok((fixed::<1>(bytes, "bool")?[0] != 0).to_string()),
+        Type::INT2 => Ok(i16::from_be_bytes(fixed(bytes, "int2")?).to_string()),
+        Type::INT4 => Ok(i32::from_be_bytes(fixed(bytes, "int4")?).to_string()),
+        Type::INT8 => Ok(i64::from_be_bytes(fixed(bytes, "int8")?).to_string()),
+        Type::OID => Ok(u32::from_be_bytes(fixed(bytes, "oid")?).to_string()),
+        Type::FLOAT4 => Ok(f32::from_be_bytes(fixed(bytes, "float4")?).to_string()),
+        Type::FLOAT8 => Ok(f64::from_be_bytes(fixed(bytes, "float8")?).to_string()),
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::JSON | Type::UNKNOWN => {
+            String::from_utf8(bytes.to_vec()).map_err(|e| format!("invalid UTF-8: {e}"))
+        }
+        Type::UUID => {
+            let b: [u8; 16] = fixed(bytes, "uuid")?;
+            let h = hex_lower(&b);
+            Ok(format!("{}-{}-{}-{}-{}", &h[0..8], &h[8..12], &h[12..16], &h[16..20], &h[20..32]))
+        }
+        // DuckDB's BLOB text syntax escapes every byte as `\xHH`.
+        Type::BYTEA => Ok(bytes.iter().map(|b| format!("\\x{:02X}", b)).collect()),
+        Type::DATE => {
+            let days = i32::from_be_bytes(fixed(bytes, "date")?) as i64 + PG_EPOCH_OFFSET_DAYS;
+            chrono::DateTime::from_timestamp(days * 86_400, 0)
+                .map(|dt| dt.format("%Y-%m-%d").to_string())
+                .ok_or_else(|| "date out of range".to_string())
+        }
+        Type::TIMESTAMP | Type::TIMESTAMPTZ => {
+            let micros = i64::from_be_bytes(fixed(bytes, "timestamp")?)
+                .saturating_add(PG_EPOCH_OFFSET_MICROS);
+            let secs = micros.div_euclid(1_000_000);
+            let nsecs = (micros.rem_euclid(1_000_000) as u32) * 1_000;
+            let dt = chrono::DateTime::from_timestamp(secs, nsecs)
+                .ok_or_else(|| "timestamp out of range".to_string())?;
+            if *pg_type == Type::TIMESTAMPTZ {
+                Ok(dt.format("%Y-%m-%d %H:%M:%S%.6f+00").to_string())
+            } else {
+                Ok(dt.format("%Y-%m-%d %H:%M:%S%.6f").to_string())
+            }
+        }
+        ref other => Err(format!("binary format not supported for type {}", other.name())),
     }
 }
 
+fn hex_lower(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parameter types to advertise in ParameterDescription. The client's
+/// declared OIDs win; placeholders it left unspecified are reported as
+/// `unknown` so the description always has one entry per `$n` in the SQL.
+fn describe_parameter_types(declared: &[Option<Type>], count: usize) -> Vec<Type> {
+    (0..count.max(declared.len()))
+        .map(|i| declared.get(i).cloned().flatten().unwrap_or(Type::UNKNOWN))
+        .collect()
+}
+
 fn extract_panic_message(err: Box<dyn std::any::Any + Send>) -> String {
     if let Some(s) = err.downcast_ref::<&str>() {
         s.to_string()
@@ -433,7 +1518,7 @@ fn extract_panic_message(err: Box<dyn std::any::Any + Send>) -> String {
 /// on DuckDB's UTC-offset strings ("+00:00", "UTC+08:00") in some chrono-tz
 /// builds. The simplest, panic-free fix is to pre-cast the column to Utf8
 /// using DuckDB's own ISO-8601 formatter; the wire field still advertises
-/// OID 1184 (TIMESTAMPTZ) via `arrow_type_to_pg_type`, so clients see the
+/// OID 1184 (TIMESTAMPTZ) via `pg_types::pg_type`, so clients see the
 /// correct column type while we ship the bytes as text.
 fn needs_string_cast(dt: &duckdb::arrow::datatypes::DataType) -> bool {
     use duckdb::arrow::datatypes::DataType;
@@ -451,7 +1536,7 @@ fn needs_string_cast(dt: &duckdb::arrow::datatypes::DataType) -> bool {
         // (SIGTERM) when the underlying i128 exceeds rust_decimal's 96-bit
         // mantissa — i.e. on full-width DECIMAL(38, *) values. We pre-format
         // every Decimal128 so the column reaches arrow-pg as Utf8; the wire
-        // type is still NUMERIC because `arrow_type_to_pg_type` keys off the
+        // type is still NUMERIC because `pg_types::pg_type` keys off the
         // *original* schema in `schema_to_field_info`.
         DataType::Decimal128(_, _) => true,
         // Interval columns are routed through our own formatter because
@@ -541,7 +1626,7 @@ fn format_timestamptz_as_utf8(
 /// significand). We bypass that path entirely by formatting the underlying
 /// i128 ourselves, honouring the column scale, sign, and validity bitmap.
 ///
-/// The wire field type stays NUMERIC because `arrow_type_to_pg_type` is
+/// The wire field type stays NUMERIC because `pg_types::pg_type` is
 /// driven by the *original* schema in `schema_to_field_info`; psycopg2
 /// parses NUMERIC text as `decimal.Decimal`, which round-trips losslessly.
 fn format_decimal128_as_utf8(
@@ -604,6 +1689,37 @@ fn format_i128_with_scale(v: i128, scale: i8) -> String {
     }
 }
 
+/// Format a UUID exported as 16 bytes (`arrow.uuid`, with DuckDB's
+/// `arrow_lossless_conversion`) as a Utf8 array of the usual
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx` text, honouring the validity
+/// bitmap. Arrow's generic cast has no FixedSizeBinary to Utf8 path.
+fn format_uuid_as_utf8(arr: &dyn duckdb::arrow::array::Array) -> duckdb::arrow::array::ArrayRef {
+    use duckdb::arrow::array::{Array, ArrayRef, FixedSizeBinaryArray, StringArray};
+
+    let bytes = arr
+        .as_any()
+        .downcast_ref::<FixedSizeBinaryArray>()
+        .expect("array must be FixedSizeBinaryArray");
+    let out: Vec<Option<String>> = (0..bytes.len())
+        .map(|i| {
+            if bytes.is_null(i) {
+                return None;
+            }
+            let hex: String = bytes.value(i).iter().map(|b| format!("{:02x}", b)).collect();
+            Some(format!(
+                "{}-{}-{}-{}-{}",
+                &hex[0..8],
+                &hex[8..12],
+                &hex[12..16],
+                &hex[16..20],
+                &hex[20..32]
+            ))
+        })
+        .collect();
+    let arr: ArrayRef = std::sync::Arc::new(StringArray::from(out));
+    arr
+}
+
 /// Format an Arrow `Interval*Array` as a Utf8 array of Postgres-style
 /// interval strings, honouring the validity bitmap.
 ///
@@ -742,6 +1858,15 @@ fn rebuild_record_batch_for_pg(rb: RecordBatch) -> RecordBatch {
             ));
             continue;
         }
+        if pg_types::is_binary_uuid(field) {
+            new_columns.push(format_uuid_as_utf8(rb.column(i).as_ref()));
+            new_fields.push(Field::new(
+                field.name(),
+                DataType::Utf8,
+                field.is_nullable(),
+            ));
+            continue;
+        }
         if needs_string_cast(field.data_type()) {
             match cast(rb.column(i), &DataType::Utf8) {
                 Ok(casted) => {
@@ -765,6 +1890,49 @@ fn rebuild_record_batch_for_pg(rb: RecordBatch) -> RecordBatch {
     RecordBatch::try_new(new_schema, new_columns).unwrap_or(rb)
 }
 
+/// Drop the time zone of the TIMESTAMPTZ columns sent in binary format.
+///
+/// DuckDB stores TIMESTAMPTZ as a UTC instant, so the values already are
+/// what Postgres sends for `timestamptz` in binary: microseconds since
+/// 2000-01-01 UTC. Without the zone arrow-pg encodes them exactly that way,
+/// and `rebuild_record_batch_for_pg` leaves them alone; text-format columns
+/// keep the zone and go through `format_timestamptz_as_utf8`.
+fn binary_timestamptz_as_utc(rb: RecordBatch, header: &[FieldInfo]) -> RecordBatch {
+    use duckdb::arrow::array::{make_array, ArrayRef};
+    use duckdb::arrow::datatypes::{DataType, Field};
+
+    let schema = rb.schema();
+    let is_binary_tz = |i: usize, field: &Field| {
+        matches!(field.data_type(), DataType::Timestamp(_, Some(_)))
+            && header.get(i).is_some_and(|info| info.format() == FieldFormat::Binary)
+    };
+    if !schema.fields().iter().enumerate().any(|(i, f)| is_binary_tz(i, f)) {
+        return rb;
+    }
+
+    let mut new_fields = Vec::with_capacity(schema.fields().len());
+    let mut new_columns: Vec<ArrayRef> = Vec::with_capacity(rb.num_columns());
+    for (i, field) in schema.fields().iter().enumerate() {
+        match field.data_type() {
+            DataType::Timestamp(unit, Some(_)) if is_binary_tz(i, field) => {
+                let naive = DataType::Timestamp(*unit, None);
+                match rb.column(i).to_data().into_builder().data_type(naive.clone()).build() {
+                    Ok(data) => {
+                        new_columns.push(make_array(data));
+                        new_fields.push(Field::new(field.name(), naive, field.is_nullable()));
+                    }
+                    Err(_) => return rb,
+                }
+            }
+            _ => {
+                new_columns.push(rb.column(i).clone());
+                new_fields.push(field.as_ref().clone());
+            }
+        }
+    }
+    RecordBatch::try_new(Arc::new(Schema::new(new_fields)), new_columns).unwrap_or(rb)
+}
+
 fn rebuild_schema_for_pg(schema: &Schema) -> Schema {
     use duckdb::arrow::datatypes::{DataType, Field};
     if !schema.fields().iter().any(|f| needs_string_cast(f.data_type())) {
@@ -791,6 +1959,7 @@ fn encode_batches_safely(
     match panic::catch_unwind(AssertUnwindSafe(|| {
         batches
             .into_iter()
+            .map(|rb| binary_timestamptz_as_utc(rb, &header))
             .map(rebuild_record_batch_for_pg)
             .flat_map(|rb| encode_recordbatch(header.clone(), rb))
             .collect::<Vec<_>>()
@@ -807,43 +1976,31 @@ fn encode_batches_safely(
     }
 }
 
-#[async_trait]
-impl SimpleQueryHandler for TrexQueryHandler {
-    async fn do_query<C>(&self, _client: &mut C, query: &str) -> PgWireResult<Vec<Response>>
+impl TrexQueryHandler {
+    /// The responses to a simple query, and the type modifiers of each
+    /// result set's columns keyed by the index of its response.
+    async fn simple_query_responses<C>(
+        &self,
+        _client: &mut C,
+        query: &str,
+    ) -> PgWireResult<(Vec<Response>, HashMap<usize, Vec<i32>>)>
     where
         C: ClientInfo + Unpin + Send + Sync,
     {
         log_debug(&format!("SimpleQuery: {}", query));
 
-        let login_info = LoginInfo::from_client_info(_client);
-        if let Some(db) = login_info.database() {
-            if let Some(db_credentials) = ServerRegistry::instance().get_db_credentials(&self.server_host, self.server_port) {
-                if matches!(check_database_action(db, &db_credentials), DatabaseAction::SetDatabase) {
-                    let use_sql = format!("USE \"{}\"", db.replace('"', "\"\""));
-                    let session_id = self.session_id;
-                    let result = tokio::task::spawn_blocking(move || {
-                        trex_pool_client::session_execute(session_id, &use_sql).map(|_| ())
-                    })
-                    .await
-                    .unwrap_or_else(|e| Err(format!("spawn error: {e}")));
-                    if let Err(err) = result {
-                        return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
-                            "ERROR".to_owned(),
-                            "XX000".to_owned(),
-                            format!("Failed to set database context: {}", err),
-                        ))));
-                    }
-                }
-            }
-        }
+        self.set_database_context(_client).await?;
 
-        let queries: Vec<&str> = query
-            .split(';')
-            .map(|s| s.trim())
-            .filter(|s| !s.is_empty())
-            .collect();
+        // Quote-aware, so `NOTIFY ch, 'a;b'` and dollar-quoted bodies stay
+        // one statement.
+        let statements = pgt::utils::split_statements(query);
+        let queries: Vec<&str> = statements.iter().map(String::as_str).collect();
+
+        // Refuse the whole query before any of its statements runs.
+        self.check_read_only(&queries)?;
 
         let mut responses = Vec::new();
+        let mut modifiers = HashMap::new();
 
         for sql in queries {
             // Apply PostgreSQL compatibility transformations
@@ -857,48 +2014,59 @@ impl SimpleQueryHandler for TrexQueryHandler {
                 .replace("SELECT c.oid,c.*,t.relname as tabrelname,rt.relnamespace as refnamespace,d.description, null as consrc_copy",
                 "SELECT c.oid,t.relname  as tabrelname,rt.relnamespace as refnamespace,d.description, null as consrc_copy");
 
-            // Intercept Postgres-only session parameters that libpq/JDBC drivers
+            // Intercept Postgres session parameters that libpq/JDBC drivers
             // SET on connect. DuckDB rejects them; without this, every JDBC client
             // fails on the first SET statement before user SQL even runs.
-            if is_postgres_only_set(&sql) {
-                log_debug(&format!("Intercepting pg-compat SET: {}", sql));
-                responses.push(Response::Execution(Tag::new("SET").with_rows(0)));
+            if let Some(command) = parse_session_command(&sql) {
+                if let Some(response) = self.run_session_command(_client, command, &sql).await? {
+                    log_debug(&format!("Handled session parameter command: {}", sql));
+                    responses.push(response);
+                    continue;
+                }
+            }
+
+            if let Some(command) = parse_notify_command(&sql) {
+                responses.push(self.run_notify_command(command));
                 continue;
             }
 
+            // COPY FROM STDIN hands the connection over to CopyData messages,
+            // so nothing after it in the same query string can run.
+            if let Some(copy) = parse_copy_statement(&sql) {
+                let copy = copy.map_err(copy_error)?;
+                if copy.direction == CopyDirection::Out {
+                    return Err(copy_error("COPY TO STDOUT must be sent as its own query".to_string()));
+                }
+                let transaction_status =
+                    responses.iter().fold(_client.transaction_status(), |status, response| match response {
+                        Response::TransactionStart(_) => status.to_in_transaction_state(),
+                        Response::TransactionEnd(_) => status.to_idle_state(),
+                        _ => status,
+                    });
+                responses.push(self.begin_copy_in(copy, transaction_status).await?);
+                break;
+            }
+
             log_debug(&format!("Submitting query: {}", sql));
-            let sql_owned = sql.clone();
-            let session_id = self.session_id;
-            let (schema, batches): (Arc<Schema>, Vec<RecordBatch>) = tokio::task::spawn_blocking(move || {
-                trex_pool_client::session_execute(session_id, &sql_owned)
-            }).await.map_err(|e| {
-                PgWireError::UserError(Box::new(ErrorInfo::new(
-                    "ERROR".to_owned(),
-                    "XX000".to_owned(),
-                    format!("Query execution failed: {}", e),
-                )))
-            })?.map_err(|e| {
-                PgWireError::UserError(Box::new(ErrorInfo::new(
-                    "ERROR".to_owned(),
-                    "XX000".to_owned(),
-                    e,
-                )))
-            })?;
+            let (schema, batches) = self.session_query(self.rewrite_query(&sql).await?).await?;
 
             if (schema.fields().is_empty() && batches.is_empty())
                 || is_duckdb_non_query_schema(&schema)
             {
                 log_debug("Got EXECUTE result");
-                responses.push(Response::Execution(Tag::new("OK").with_rows(0)));
+                responses.push(execution_response(&sql, &schema, &batches));
             } else {
                 log_debug(&format!("Got SELECT result: {} batches", batches.len()));
                 let header = Arc::new(schema_to_field_info(&schema, &Format::UnifiedText)?);
                 let data = encode_batches_safely(header.clone(), batches);
 
-                responses.push(Response::Query(QueryResponse::new(
-                    header,
-                    stream::iter(data.into_iter()),
-                )));
+                let mut response = QueryResponse::new(header, stream::iter(data.into_iter()));
+                response.set_command_tag(query_command_tag(&sql));
+                modifiers.insert(
+                    responses.len(),
+                    schema.fields().iter().map(|f| pg_types::type_modifier(f.data_type())).collect(),
+                );
+                responses.push(Response::Query(response));
             }
         }
 
@@ -906,131 +2074,190 @@ impl SimpleQueryHandler for TrexQueryHandler {
             responses.push(Response::Execution(Tag::new("OK").with_rows(0)));
         }
 
-        Ok(responses)
+        Ok((responses, modifiers))
     }
 }
 
 #[async_trait]
-impl ExtendedQueryHandler for TrexQueryHandler {
-    type Statement = String;
-    type QueryParser = NoopQueryParser;
-
-    fn query_parser(&self) -> Arc<Self::QueryParser> {
-        Arc::new(NoopQueryParser::new())
-    }
-
-    async fn do_query<C>(
-        &self,
-        _client: &mut C,
-        portal: &Portal<Self::Statement>,
-        _max_rows: usize,
-    ) -> PgWireResult<Response>
+impl SimpleQueryHandler for TrexQueryHandler {
+    async fn on_query<C>(&self, client: &mut C, query: Query) -> PgWireResult<()>
     where
-        C: ClientInfo + Unpin + Send + Sync,
+        C: ClientInfo + ClientPortalStore + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
     {
-        let query = portal.statement.statement.clone();
-        log_debug(&format!("ExtendedQuery: {}", query));
+        self.touch();
+        let single = query.query.trim().trim_end_matches(';');
+        if !single.contains(';') {
+            if let Some(Ok(stmt)) = parse_copy_statement(single) {
+                if stmt.direction == CopyDirection::Out {
+                    log_debug(&format!("CopyOut: {}", single));
+                    return self.copy_out(client, stmt).await;
+                }
+            }
+        }
 
-        // See SimpleQueryHandler::do_query for context.
-        if is_postgres_only_set(&query) {
-            log_debug(&format!("Intercepting pg-compat SET: {}", query));
-            return Ok(Response::Execution(Tag::new("SET").with_rows(0)));
-        }
-
-        let login_info = LoginInfo::from_client_info(_client);
-        if let Some(db) = login_info.database() {
-            if let Some(db_credentials) = ServerRegistry::instance().get_db_credentials(&self.server_host, self.server_port) {
-                if matches!(check_database_action(db, &db_credentials), DatabaseAction::SetDatabase) {
-                    let use_sql = format!("USE \"{}\"", db.replace('"', "\"\""));
-                    let session_id = self.session_id;
-                    let result = tokio::task::spawn_blocking(move || {
-                        trex_pool_client::session_execute(session_id, &use_sql).map(|_| ())
-                    })
-                    .await
-                    .unwrap_or_else(|e| Err(format!("spawn error: {e}")));
-                    if let Err(err) = result {
-                        return Err(PgWireError::UserError(Box::new(ErrorInfo::new(
-                            "ERROR".to_owned(),
-                            "XX000".to_owned(),
-                            format!("Failed to set database context: {}", err),
-                        ))));
+        // Mirrors pgwire's default `_on_query`, which sends ReadyForQuery
+        // itself and so leaves no point to deliver notifications before it.
+        if !matches!(client.state(), PgWireConnectionState::ReadyForQuery) {
+            return Err(PgWireError::NotReadyForQuery);
+        }
+        let mut transaction_status = client.transaction_status();
+        client.set_state(PgWireConnectionState::QueryInProgress);
+
+        if single.is_empty() {
+            client
+                .feed(PgWireBackendMessage::EmptyQueryResponse(EmptyQueryResponse::new()))
+                .await?;
+        } else {
+            let (responses, mut modifiers) = self.simple_query_responses(client, &query.query).await?;
+            for (idx, response) in responses.into_iter().enumerate() {
+                match response {
+                    Response::EmptyQuery => {
+                        client
+                            .feed(PgWireBackendMessage::EmptyQueryResponse(EmptyQueryResponse::new()))
+                            .await?;
+                    }
+                    Response::Query(mut results) => {
+                        // pgwire's RowDescription would send 0 as every type
+                        // modifier.
+                        let modifiers = modifiers.remove(&idx).unwrap_or_default();
+                        let row_desc = pg_types::row_description(&results.row_schema(), &modifiers);
+                        client.feed(PgWireBackendMessage::RowDescription(row_desc)).await?;
+                        send_query_response(client, &mut results, false).await?;
+                    }
+                    Response::Execution(tag) => {
+                        send_execution_response(client, tag).await?;
+                    }
+                    Response::TransactionStart(tag) => {
+                        send_execution_response(client, tag).await?;
+                        transaction_status = transaction_status.to_in_transaction_state();
+                    }
+                    Response::TransactionEnd(tag) => {
+                        send_execution_response(client, tag).await?;
+                        transaction_status = transaction_status.to_idle_state();
+                    }
+                    Response::Error(e) => {
+                        client
+                            .feed(PgWireBackendMessage::ErrorResponse((*e).into()))
+                            .await?;
+                        transaction_status = transaction_status.to_error_state();
+                    }
+                    Response::CopyIn(result) => {
+                        send_copy_in_response(client, result).await?;
+                        client.set_state(PgWireConnectionState::CopyInProgress(false));
+                    }
+                    Response::CopyOut(result) => {
+                        send_copy_out_response(client, result).await?;
+                        client.set_state(PgWireConnectionState::CopyInProgress(false));
+                    }
+                    Response::CopyBoth(result) => {
+                        send_copy_both_response(client, result).await?;
+                        client.set_state(PgWireConnectionState::CopyInProgress(false));
                     }
                 }
             }
         }
 
-        let session_id = self.session_id;
-        let (schema, batches): (Arc<Schema>, Vec<RecordBatch>) = tokio::task::spawn_blocking(move || {
-            trex_pool_client::session_execute(session_id, &query)
-        }).await.map_err(|e| {
-            PgWireError::UserError(Box::new(ErrorInfo::new(
-                "ERROR".to_owned(),
-                "XX000".to_owned(),
-                format!("Query execution failed: {}", e),
-            )))
-        })?.map_err(|e| {
-            PgWireError::UserError(Box::new(ErrorInfo::new(
-                "ERROR".to_owned(),
-                "XX000".to_owned(),
-                e,
-            )))
-        })?;
+        // Sending a large result to a slow client doesn't count as idle.
+        self.touch();
 
-        if (schema.fields().is_empty() && batches.is_empty())
-            || is_duckdb_non_query_schema(&schema)
-        {
-            Ok(Response::Execution(Tag::new("OK").with_rows(0)))
-        } else {
-            let header = Arc::new(schema_to_field_info(&schema, &Format::UnifiedText)?);
-            let data = encode_batches_safely(header.clone(), batches);
+        // ReadyForQuery for COPY FROM STDIN is sent after CopyDone/CopyFail,
+        // with the status a BEGIN before the COPY left.
+        client.set_transaction_status(transaction_status);
+        if !matches!(client.state(), PgWireConnectionState::CopyInProgress(_)) {
+            self.deliver_notifications(client).await?;
+            client.set_state(PgWireConnectionState::ReadyForQuery);
+            send_ready_for_query(client, transaction_status).await?;
+        }
 
-            Ok(Response::Query(QueryResponse::new(
-                header,
-                stream::iter(data.into_iter()),
-            )))
+        Ok(())
+    }
+
+    async fn do_query<C>(&self, client: &mut C, query: &str) -> PgWireResult<Vec<Response>>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        Ok(self.simple_query_responses(client, query).await?.0)
+    }
+}
+
+impl TrexQueryHandler {
+    /// `do_describe_statement`, with the type modifiers of the columns.
+    async fn describe_statement<C>(
+        &self,
+        _client: &mut C,
+        stmt: &StoredStatement<String>,
+    ) -> PgWireResult<(DescribeStatementResponse, Vec<i32>)>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        self.touch();
+        if let Some(fields) = self.describe_session_command(_client, &stmt.statement) {
+            return Ok((DescribeStatementResponse::new(Vec::new(), fields), Vec::new()));
         }
+
+        let param_types = stmt.parameter_types.clone();
+        self.describe_sql(_client, &stmt.statement, move |stmt| {
+            let (fields, modifiers) = row_desc_from_stmt(stmt, &Format::UnifiedBinary)?;
+            let param_types = describe_parameter_types(&param_types, stmt.parameter_count());
+            Ok((DescribeStatementResponse::new(param_types, fields), modifiers))
+        })
+        .await
     }
 
-    async fn do_describe_statement<C>(
+    /// `do_describe_portal`, with the type modifiers of the columns.
+    async fn describe_portal<C>(
         &self,
         _client: &mut C,
-        stmt: &StoredStatement<Self::Statement>,
-    ) -> PgWireResult<DescribeStatementResponse>
+        portal: &Portal<String>,
+    ) -> PgWireResult<(DescribePortalResponse, Vec<i32>)>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        self.touch();
+        if let Some(fields) = self.describe_session_command(_client, &portal.statement.statement) {
+            return Ok((DescribePortalResponse::new(fields), Vec::new()));
+        }
+
+        let format = portal.result_column_format.clone();
+        self.describe_sql(_client, &portal.statement.statement, move |stmt| {
+            let (fields, modifiers) = row_desc_from_stmt(stmt, &format)?;
+            Ok((DescribePortalResponse::new(fields), modifiers))
+        })
+        .await
+    }
+
+    /// Prepare `sql`, rewritten as it would run, on the client's describe
+    /// connection and pass the prepared statement to `describe`.
+    async fn describe_sql<C, T, F>(&self, client: &C, sql: &str, describe: F) -> PgWireResult<T>
     where
         C: ClientInfo + Unpin + Send + Sync,
+        T: Send + 'static,
+        F: FnOnce(&duckdb::Statement) -> PgWireResult<T> + Send + 'static,
     {
-        let login_info = LoginInfo::from_client_info(_client);
+        let login_info = LoginInfo::from_client_info(client);
         let database = login_info.database().map(|s| s.to_string());
 
-        // Use the per-worker describe connection so USE DATABASE state is isolated
-        // per session and doesn't leak between concurrent clients.
-        let connection = get_describe_connection(self.worker_id).ok_or_else(|| {
-            PgWireError::UserError(Box::new(ErrorInfo::new(
-                "ERROR".to_owned(),
-                "XX000".to_owned(),
-                "No describe connection available".to_owned(),
-            )))
-        })?;
-        let statement = stmt.statement.clone();
-        let param_types = stmt.parameter_types.clone();
+        // Describe connections are keyed by worker and database, so the
+        // catalog chosen with USE never leaks between clients.
+        let context_sql = self.database_context_sql(database.as_deref());
+        let connection = get_database_describe_connection(self.worker_id, database.as_deref(), &context_sql)
+            .ok_or_else(|| {
+                PgWireError::UserError(Box::new(ErrorInfo::new(
+                    "ERROR".to_owned(),
+                    "XX000".to_owned(),
+                    "No describe connection available".to_owned(),
+                )))
+            })?;
+        let statement = self.rewrite_query(sql).await?;
         let server_host = self.server_host.clone();
         let server_port = self.server_port;
 
-        tokio::task::spawn_blocking(move || -> PgWireResult<DescribeStatementResponse> {
+        tokio::task::spawn_blocking(move || -> PgWireResult<T> {
             let guard = connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
             let conn = &*guard;
 
-            if let Some(db) = &database {
-                if let Some(db_credentials) = ServerRegistry::instance().get_db_credentials(&server_host, server_port) {
-                    match check_database_action(db, &db_credentials) {
-                        DatabaseAction::SetDatabase => {
-                            let _ = conn.execute(&format!("USE \"{}\"", db.replace('"', "\"\"")), params![]);
-                        }
-                        _ => {}
-                    }
-                }
-            }
-
             let hana_credentials = get_hana_credentials_if_available(&database, &server_host, server_port);
 
             let (actual_statement, fallback_statement) = if let Some(hana_creds) = &hana_credentials {
@@ -1044,9 +2271,7 @@ impl ExtendedQueryHandler for TrexQueryHandler {
                 conn.prepare(query_str)
             }).map_err(|e| PgWireError::ApiError(Box::new(e)))?;
 
-            let fields = row_desc_from_stmt(&stmt, &Format::UnifiedBinary)?;
-            let param_types_unwrapped: Vec<Type> = param_types.into_iter().filter_map(|t| t).collect();
-            Ok(DescribeStatementResponse::new(param_types_unwrapped, fields))
+            describe(&stmt)
         })
         .await
         .map_err(|e| {
@@ -1057,156 +2282,564 @@ impl ExtendedQueryHandler for TrexQueryHandler {
             )))
         })?
     }
+}
 
-    async fn do_describe_portal<C>(
+#[async_trait]
+impl ExtendedQueryHandler for TrexQueryHandler {
+    type Statement = String;
+    type QueryParser = NoopQueryParser;
+
+    fn query_parser(&self) -> Arc<Self::QueryParser> {
+        Arc::new(NoopQueryParser::new())
+    }
+
+    async fn on_sync<C>(&self, client: &mut C, _message: PgSync) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        self.touch();
+        self.deliver_notifications(client).await?;
+        client
+            .send(PgWireBackendMessage::ReadyForQuery(ReadyForQuery::new(
+                client.transaction_status(),
+            )))
+            .await?;
+        client.flush().await?;
+        Ok(())
+    }
+
+    async fn do_query<C>(
         &self,
         _client: &mut C,
         portal: &Portal<Self::Statement>,
-    ) -> PgWireResult<DescribePortalResponse>
+        _max_rows: usize,
+    ) -> PgWireResult<Response>
     where
         C: ClientInfo + Unpin + Send + Sync,
     {
-        let login_info = LoginInfo::from_client_info(_client);
-        let database = login_info.database().map(|s| s.to_string());
+        self.touch();
+        let query = portal.statement.statement.clone();
+        log_debug(&format!("ExtendedQuery: {}", query));
+        self.check_read_only(&[&query])?;
 
-        // Use the per-worker describe connection so USE DATABASE state is isolated
-        // per session and doesn't leak between concurrent clients.
-        let connection = get_describe_connection(self.worker_id).ok_or_else(|| {
-            PgWireError::UserError(Box::new(ErrorInfo::new(
-                "ERROR".to_owned(),
-                "XX000".to_owned(),
-                "No describe connection available".to_owned(),
-            )))
-        })?;
-        let statement = portal.statement.statement.clone();
-        let format = portal.result_column_format.clone();
-        let server_host = self.server_host.clone();
-        let server_port = self.server_port;
+        // See SimpleQueryHandler::do_query for context.
+        if let Some(command) = parse_session_command(&query) {
+            if let Some(response) = self.run_session_command(_client, command, &query).await? {
+                return Ok(response);
+            }
+        }
+        if let Some(command) = parse_notify_command(&query) {
+            return Ok(self.run_notify_command(command));
+        }
 
-        tokio::task::spawn_blocking(move || -> PgWireResult<DescribePortalResponse> {
-            let guard = connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
-            let conn = &*guard;
+        self.set_database_context(_client).await?;
 
-            if let Some(db) = &database {
-                if let Some(db_credentials) = ServerRegistry::instance().get_db_credentials(&server_host, server_port) {
-                    match check_database_action(db, &db_credentials) {
-                        DatabaseAction::SetDatabase => {
-                            let _ = conn.execute(&format!("USE \"{}\"", db.replace('"', "\"\"")), params![]);
-                        }
-                        _ => {}
-                    }
-                }
+        let params = decode_portal_parameters(portal)?;
+        let rewritten = self.rewrite_query(&query).await?;
+        let (schema, batches) = self.session_query_params(rewritten, params).await?;
+
+        if (schema.fields().is_empty() && batches.is_empty())
+            || is_duckdb_non_query_schema(&schema)
+        {
+            Ok(execution_response(&query, &schema, &batches))
+        } else {
+            let header = Arc::new(schema_to_field_info(&schema, &portal.result_column_format)?);
+            let data = encode_batches_safely(header.clone(), batches);
+
+            let mut response = QueryResponse::new(header, stream::iter(data.into_iter()));
+            response.set_command_tag(query_command_tag(&query));
+            Ok(Response::Query(response))
+        }
+    }
+
+    async fn do_describe_statement<C>(
+        &self,
+        client: &mut C,
+        stmt: &StoredStatement<Self::Statement>,
+    ) -> PgWireResult<DescribeStatementResponse>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        Ok(self.describe_statement(client, stmt).await?.0)
+    }
+
+    async fn do_describe_portal<C>(
+        &self,
+        client: &mut C,
+        portal: &Portal<Self::Statement>,
+    ) -> PgWireResult<DescribePortalResponse>
+    where
+        C: ClientInfo + Unpin + Send + Sync,
+    {
+        Ok(self.describe_portal(client, portal).await?.0)
+    }
+
+    /// pgwire's `_on_describe`, except that the RowDescription carries the
+    /// columns' type modifiers instead of 0.
+    async fn on_describe<C>(&self, client: &mut C, message: Describe) -> PgWireResult<()>
+    where
+        C: ClientInfo + ClientPortalStore + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::PortalStore: PortalStore<Statement = Self::Statement>,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        let name = message.name.as_deref().unwrap_or(DEFAULT_NAME);
+        let (parameters, fields, modifiers) = match message.target_type {
+            TARGET_TYPE_BYTE_STATEMENT => {
+                let stmt = client
+                    .portal_store()
+                    .get_statement(name)
+                    .ok_or_else(|| PgWireError::StatementNotFound(name.to_owned()))?;
+                let (response, modifiers) = self.describe_statement(client, &stmt).await?;
+                (Some(response.parameters), response.fields, modifiers)
             }
+            TARGET_TYPE_BYTE_PORTAL => {
+                let portal = client
+                    .portal_store()
+                    .get_portal(name)
+                    .ok_or_else(|| PgWireError::PortalNotFound(name.to_owned()))?;
+                let (response, modifiers) = self.describe_portal(client, &portal).await?;
+                (None, response.fields, modifiers)
+            }
+            other => return Err(PgWireError::InvalidTargetType(other)),
+        };
 
-            let hana_credentials = get_hana_credentials_if_available(&database, &server_host, server_port);
+        if let Some(parameters) = &parameters {
+            let oids = parameters.iter().map(|t| t.oid()).collect();
+            client
+                .send(PgWireBackendMessage::ParameterDescription(ParameterDescription::new(oids)))
+                .await?;
+        }
+        if fields.is_empty() && parameters.as_ref().is_none_or(Vec::is_empty) {
+            client.send(PgWireBackendMessage::NoData(NoData::new())).await?;
+        } else {
+            let row_desc = pg_types::row_description(&fields, &modifiers);
+            client.send(PgWireBackendMessage::RowDescription(row_desc)).await?;
+        }
+        Ok(())
+    }
+}
 
-            let (actual_statement, fallback_statement) = if let Some(hana_creds) = &hana_credentials {
-                (wrap_query_for_hana(&statement, hana_creds), Some(statement.clone()))
-            } else {
-                (statement.clone(), None)
+#[async_trait]
+impl CopyHandler for TrexQueryHandler {
+    async fn on_copy_data<C>(&self, _client: &mut C, copy_data: CopyData) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        self.touch();
+        // Taken out for the append so the lock isn't held across it.
+        let Some(mut copy) = self.copy_in.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() else {
+            return Ok(());
+        };
+        if copy.error.is_none() {
+            let appended = match copy.decoder.decode(&copy_data.data) {
+                Ok(rows) => self.append_copy_rows(&mut copy, rows).await,
+                Err(e) => Err(e),
             };
+            copy.error = appended.err();
+        }
+        *self.copy_in.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(copy);
+        Ok(())
+    }
 
-            let fallback_ref = fallback_statement.as_deref();
-            let stmt = execute_with_fallback(&actual_statement, fallback_ref, |query_str| {
-                conn.prepare(query_str)
-            }).map_err(|e| PgWireError::ApiError(Box::new(e)))?;
+    async fn on_copy_done<C>(&self, client: &mut C, _done: CopyDone) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        let Some(mut copy) = self.copy_in.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() else {
+            return Err(copy_error("no COPY FROM STDIN in progress".to_string()));
+        };
+        if copy.error.is_none() {
+            let appended = match copy.decoder.finish() {
+                Ok(row) => self.append_copy_rows(&mut copy, row.into_iter().collect()).await,
+                Err(e) => Err(e),
+            };
+            copy.error = appended.err();
+        }
+        if let Some(e) = copy.error.take() {
+            self.abort_copy_in(&copy).await;
+            return Err(copy_error(e));
+        }
+        if copy.owns_transaction {
+            if let Err(e) = self.session_query("COMMIT".to_string()).await {
+                self.abort_copy_in(&copy).await;
+                return Err(e);
+            }
+        }
+        log_debug(&format!(
+            "CopyIn appended {} rows to {}",
+            copy.rows,
+            copy.decoder.statement().target_sql()
+        ));
+        send_execution_response(client, Tag::new("COPY").with_rows(copy.rows)).await
+    }
 
-            let fields = row_desc_from_stmt(&stmt, &format)?;
-            Ok(DescribePortalResponse::new(fields))
-        })
-        .await
-        .map_err(|e| {
-            PgWireError::UserError(Box::new(ErrorInfo::new(
-                "ERROR".to_owned(),
-                "XX000".to_owned(),
-                format!("Task execution failed: {}", e),
-            )))
-        })?
+    async fn on_copy_fail<C>(&self, _client: &mut C, fail: CopyFail) -> PgWireError
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        let copy = self.copy_in.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+        if let Some(copy) = copy {
+            self.abort_copy_in(&copy).await;
+        }
+        PgWireError::UserError(Box::new(ErrorInfo::new(
+            "ERROR".to_owned(),
+            "57014".to_owned(),
+            format!("COPY from stdin failed: {}", fail.message),
+        )))
+    }
+}
+
+/// Handles CancelRequest, which arrives on its own short-lived connection,
+/// by interrupting the pool session of the connection that owns the key.
+pub struct TrexCancelHandler;
+
+#[async_trait]
+impl CancelHandler for TrexCancelHandler {
+    async fn on_cancel_request(&self, cancel_request: CancelRequest) {
+        let SecretKey::I32(secret) = cancel_request.secret_key else {
+            return;
+        };
+        let key = BackendKey { pid: cancel_request.pid, secret };
+        if let Some(session_id) = ServerRegistry::instance().cancel_backend(key) {
+            log_debug(&format!("Cancelling query on backend {}", key.pid));
+            if let Err(e) = trex_pool_client::session_interrupt(session_id) {
+                log_debug(&format!("session_interrupt: {e}"));
+            }
+        }
+    }
+}
+
+pub struct TrexPgWireServerFactory {
+    query_handler: Arc<TrexQueryHandler>,
+}
+
+impl TrexPgWireServerFactory {
+    pub fn new(query_handler: TrexQueryHandler) -> Self {
+        Self {
+            query_handler: Arc::new(query_handler),
+        }
+    }
+}
+
+impl PgWireServerHandlers for TrexPgWireServerFactory {
+    fn simple_query_handler(&self) -> Arc<impl SimpleQueryHandler> {
+        self.query_handler.clone()
+    }
+
+    fn extended_query_handler(&self) -> Arc<impl ExtendedQueryHandler> {
+        self.query_handler.clone()
+    }
+
+    fn copy_handler(&self) -> Arc<impl CopyHandler> {
+        self.query_handler.clone()
+    }
+
+    fn cancel_handler(&self) -> Arc<impl CancelHandler> {
+        Arc::new(TrexCancelHandler)
+    }
+
+    fn startup_handler(&self) -> Arc<impl StartupHandler> {
+        Arc::new(TrexStartupHandler::new(
+            AuthMethod::Trust,
+            None,
+            self.query_handler.server_host.clone(),
+            self.query_handler.server_port,
+            self.query_handler.backend_key,
+        ))
+    }
+}
+
+pub struct TrexPgWireServerWithAuth {
+    query_handler: Arc<TrexQueryHandler>,
+    auth_method: AuthMethod,
+    verifier: Arc<PasswordVerifier>,
+}
+
+impl TrexPgWireServerWithAuth {
+    pub fn new(auth_method: AuthMethod, verifier: Arc<PasswordVerifier>, query_handler: TrexQueryHandler) -> Self {
+        Self {
+            query_handler: Arc::new(query_handler),
+            auth_method,
+            verifier,
+        }
+    }
+}
+
+impl PgWireServerHandlers for TrexPgWireServerWithAuth {
+    fn simple_query_handler(&self) -> Arc<impl SimpleQueryHandler> {
+        self.query_handler.clone()
+    }
+
+    fn extended_query_handler(&self) -> Arc<impl ExtendedQueryHandler> {
+        self.query_handler.clone()
+    }
+
+    fn copy_handler(&self) -> Arc<impl CopyHandler> {
+        self.query_handler.clone()
+    }
+
+    fn cancel_handler(&self) -> Arc<impl CancelHandler> {
+        Arc::new(TrexCancelHandler)
+    }
+
+    fn startup_handler(&self) -> Arc<impl StartupHandler> {
+        Arc::new(TrexStartupHandler::new(
+            self.auth_method,
+            Some(self.verifier.clone()),
+            self.query_handler.server_host.clone(),
+            self.query_handler.server_port,
+            self.query_handler.backend_key,
+        ))
     }
 }
 
-pub struct TrexPgWireServerFactory {
-    query_handler: Arc<TrexQueryHandler>,
+/// Caps concurrent connections per server. A slot is a semaphore permit
+/// held by the connection task, so it is released however the connection
+/// ends, and acquiring it is atomic across concurrent accepts.
+#[derive(Clone)]
+struct ConnectionLimiter {
+    slots: Option<Arc<Semaphore>>,
+    queue_timeout: Duration,
 }
 
-impl TrexPgWireServerFactory {
-    pub fn new(host: String, port: u16, worker_id: usize, session_id: u64) -> Self {
+impl ConnectionLimiter {
+    fn new(max_connections: usize, queue_timeout_ms: u64) -> Self {
         Self {
-            query_handler: Arc::new(TrexQueryHandler::new(host, port, worker_id, session_id)),
+            slots: (max_connections > 0).then(|| Arc::new(Semaphore::new(max_connections))),
+            queue_timeout: Duration::from_millis(queue_timeout_ms),
         }
     }
-}
 
-impl PgWireServerHandlers for TrexPgWireServerFactory {
-    fn simple_query_handler(&self) -> Arc<impl SimpleQueryHandler> {
-        self.query_handler.clone()
+    /// Take a slot, waiting up to the queue timeout. `Err` means the server
+    /// is full; `Ok(None)` means connections are unlimited.
+    async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>, ()> {
+        let Some(slots) = &self.slots else {
+            return Ok(None);
+        };
+        if let Ok(permit) = slots.clone().try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+        if self.queue_timeout.is_zero() {
+            return Err(());
+        }
+        match tokio::time::timeout(self.queue_timeout, slots.clone().acquire_owned()).await {
+            Ok(Ok(permit)) => Ok(Some(permit)),
+            _ => Err(()),
+        }
     }
+}
 
-    fn extended_query_handler(&self) -> Arc<impl ExtendedQueryHandler> {
-        self.query_handler.clone()
+/// Answers the startup packet of a connection over `max_connections` with
+/// the same FATAL error Postgres uses. CancelRequests are still honoured so
+/// a client can cancel a query even when the server is full.
+struct TooManyConnectionsHandler;
+
+#[async_trait]
+impl StartupHandler for TooManyConnectionsHandler {
+    async fn on_startup<C>(
+        &self,
+        _client: &mut C,
+        message: PgWireFrontendMessage,
+    ) -> PgWireResult<()>
+    where
+        C: ClientInfo + Sink<PgWireBackendMessage> + Unpin + Send + Sync,
+        C::Error: std::fmt::Debug,
+        PgWireError: From<<C as Sink<PgWireBackendMessage>>::Error>,
+    {
+        match message {
+            PgWireFrontendMessage::Startup(_) => Err(PgWireError::UserError(Box::new(ErrorInfo::new(
+                "FATAL".to_owned(),
+                "53300".to_owned(),
+                "sorry, too many clients already".to_owned(),
+            )))),
+            _ => Ok(()),
+        }
     }
+}
 
+struct RejectingServer;
+
+impl PgWireServerHandlers for RejectingServer {
     fn startup_handler(&self) -> Arc<impl StartupHandler> {
-        Arc::new(NoopHandler)
+        Arc::new(TooManyConnectionsHandler)
+    }
+
+    fn cancel_handler(&self) -> Arc<impl CancelHandler> {
+        Arc::new(TrexCancelHandler)
     }
 }
 
-pub struct TrexPgWireServerWithAuth {
-    query_handler: Arc<TrexQueryHandler>,
-    password: String,
+/// Per-server settings shared by every connection task.
+struct ServerContext {
+    host: String,
+    port: u16,
+    auth_method: AuthMethod,
+    verifier: Option<Arc<PasswordVerifier>>,
+    read_only: bool,
+    /// Close connections idle for this long; `None` keeps them open.
+    idle_timeout: Option<Duration>,
 }
 
-impl TrexPgWireServerWithAuth {
-    pub fn new(
-        password: String,
-        host: String,
-        port: u16,
-        worker_id: usize,
-        session_id: u64,
-    ) -> Self {
-        Self {
-            query_handler: Arc::new(TrexQueryHandler::new(host, port, worker_id, session_id)),
-            password,
+/// Sleep until connection `pid` has been idle for `timeout`. Returns at
+/// once for a connection already idle that long.
+async fn wait_until_idle(pid: i32, timeout: Duration) {
+    loop {
+        match ServerRegistry::instance().backend_idle_for(pid) {
+            Some(idle) if idle >= timeout => return,
+            Some(idle) => tokio::time::sleep(timeout - idle).await,
+            None => tokio::time::sleep(timeout).await,
         }
     }
 }
 
-impl PgWireServerHandlers for TrexPgWireServerWithAuth {
-    fn simple_query_handler(&self) -> Arc<impl SimpleQueryHandler> {
-        self.query_handler.clone()
-    }
+/// The FATAL error Postgres sends before closing an idle session.
+fn idle_termination_message() -> Vec<u8> {
+    use pgwire::messages::Message;
+    use pgwire::messages::response::ErrorResponse;
 
-    fn extended_query_handler(&self) -> Arc<impl ExtendedQueryHandler> {
-        self.query_handler.clone()
-    }
+    let error: ErrorResponse = ErrorInfo::new(
+        "FATAL".to_owned(),
+        "57P05".to_owned(),
+        "terminating connection due to idle-session timeout".to_owned(),
+    )
+    .into();
+    let mut buf = bytes::BytesMut::new();
+    // An ErrorResponse with fixed fields is far below the size limit.
+    let _ = error.encode(&mut buf);
+    buf.to_vec()
+}
 
-    fn startup_handler(&self) -> Arc<impl StartupHandler> {
-        let auth_source = SimpleAuthSource::new(self.password.clone());
-        let parameter_provider = DefaultServerParameterProvider::default();
-        let mut scram_auth = ScramAuth::new(Arc::new(auth_source));
-        scram_auth.set_iterations(SCRAM_ITERATIONS);
-        let sasl_handler = SASLAuthStartupHandler::new(Arc::new(parameter_provider))
-            .with_scram(scram_auth);
-        Arc::new(sasl_handler)
+/// Serve a connection, closing it with [`idle_termination_message`] once
+/// it has been idle for `idle_timeout`.
+async fn process_with_idle_timeout<H>(
+    socket: TcpStream,
+    handlers: Arc<H>,
+    pid: i32,
+    idle_timeout: Option<Duration>,
+) -> Result<(), std::io::Error>
+where
+    H: PgWireServerHandlers + Send + Sync + 'static,
+{
+    let Some(timeout) = idle_timeout else {
+        return process_socket(socket, None, handlers).await;
+    };
+
+    // A second handle on the socket, so the termination can be written
+    // after the session future, which owns the first, is dropped.
+    let socket = socket.into_std()?;
+    let mut notifier = TcpStream::from_std(socket.try_clone()?)?;
+    let socket = TcpStream::from_std(socket)?;
+
+    tokio::select! {
+        result = process_socket(socket, None, handlers) => result,
+        _ = wait_until_idle(pid, timeout) => {
+            log_debug(&format!("Closing backend {} after {:?} idle", pid, timeout));
+            use tokio::io::AsyncWriteExt;
+            let _ = notifier.write_all(&idle_termination_message()).await;
+            let _ = notifier.shutdown().await;
+            Ok(())
+        }
     }
 }
 
+async fn serve_connection(
+    socket: TcpStream,
+    addr: SocketAddr,
+    worker_id: usize,
+    limiter: ConnectionLimiter,
+    server: Arc<ServerContext>,
+) {
+    let _slot = match limiter.acquire().await {
+        Ok(slot) => slot,
+        Err(()) => {
+            log_debug(&format!("Rejecting {:?}: too many connections", addr));
+            let _ = process_socket(socket, None, RejectingServer).await;
+            return;
+        }
+    };
+
+    // Leasing a session blocks until a pool Connection is free.
+    let session_id = match tokio::task::spawn_blocking(trex_pool_client::create_session)
+        .await
+        .unwrap_or_else(|e| Err(format!("spawn error: {e}")))
+    {
+        Ok(id) => id,
+        Err(e) => {
+            log_debug(&format!("create_session: {e}"));
+            return;
+        }
+    };
+    let backend_key = ServerRegistry::instance().register_backend(session_id, addr.to_string());
+
+    log_debug("Processing socket...");
+    let query_handler = TrexQueryHandler::new(
+        server.host.clone(),
+        server.port,
+        worker_id,
+        session_id,
+        backend_key,
+        server.read_only,
+    );
+    let pid = backend_key.pid;
+    let result = match &server.verifier {
+        Some(verifier) => {
+            let handlers = Arc::new(TrexPgWireServerWithAuth::new(server.auth_method, verifier.clone(), query_handler));
+            process_with_idle_timeout(socket, handlers, pid, server.idle_timeout).await
+        }
+        None => {
+            let handlers = Arc::new(TrexPgWireServerFactory::new(query_handler));
+            process_with_idle_timeout(socket, handlers, pid, server.idle_timeout).await
+        }
+    };
+    log_debug(&format!("Socket result: {:?}", result));
+
+    ServerRegistry::instance().unregister_backend(backend_key.pid);
+    let _ = tokio::task::spawn_blocking(move || trex_pool_client::destroy_session(session_id)).await;
+}
+
 pub fn start_pgwire_server_capi(
     host: String,
     port: u16,
     password: Option<&str>,
     db_credentials: String,
+    options: PgWireServerOptions,
 ) -> Result<String, String> {
     if ServerRegistry::instance().is_server_running(&host, port) {
         return Err(format!("Server already running on {}:{}", host, port));
     }
 
+    // Treat an empty password or `trust` as no authentication. The verifier
+    // is derived once here so the plaintext never reaches the accept loop.
+    let auth_method = options.auth_method;
+    let verifier = password
+        .filter(|p| !p.is_empty() && auth_method != AuthMethod::Trust)
+        .map(|p| Arc::new(PasswordVerifier::for_method(auth_method, p)));
+
+    crate::ensure_describe_pool(options.describe_pool_size)?;
+    let limiter = ConnectionLimiter::new(options.max_connections, options.connection_queue_timeout_ms);
+
     let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
 
     let server_host = host.clone();
     let server_port = port;
+    let context = Arc::new(ServerContext {
+        host: host.clone(),
+        port,
+        auth_method,
+        verifier,
+        read_only: options.read_only,
+        idle_timeout: (options.idle_timeout_seconds > 0).then(|| Duration::from_secs(options.idle_timeout_seconds)),
+    });
     let success_host = host.clone();
-    let password_opt = password.map(|s| s.to_string());
     
     let thread_handle = thread::Builder::new()
         .name(format!("pgwire-server-{}:{}", host, port))
@@ -1219,69 +2852,29 @@ pub fn start_pgwire_server_capi(
                 let listener = TcpListener::bind(format!("{}:{}", server_host, server_port)).await?;
                 log_debug(&format!("Bound to {}:{}", server_host, server_port));
 
-                let worker_counter = std::sync::atomic::AtomicUsize::new(0);
-
-                // Treat empty password as no authentication
-                if let Some(required_password) = password_opt.filter(|p| !p.is_empty()) {
-                    loop {
-                        tokio::select! {
-                            _ = &mut shutdown_rx => break,
-                            result = listener.accept() => {
-                                match result {
-                                    Ok((socket, _addr)) => {
-                                        let worker_id = worker_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                                        let session_id = match trex_pool_client::create_session() {
-                                            Ok(id) => id,
-                                            Err(e) => {
-                                                log_debug(&format!("create_session: {e}"));
-                                                continue;
-                                            }
-                                        };
-                                        let handlers = Arc::new(TrexPgWireServerWithAuth::new(required_password.to_string(), server_host.clone(), server_port, worker_id, session_id));
-                                        tokio::spawn(async move {
-                                            let _ = process_socket(socket, None, handlers).await;
-                                            let _ = trex_pool_client::destroy_session(session_id);
-                                        });
-                                    }
-                                    Err(_) => break,
-                                }
-                            }
-                        }
-                    }
-                } else {
+                if context.verifier.is_none() {
                     eprintln!("WARNING: pgwire starting without authentication — all connections will have full access");
                     log_debug("Using no-auth mode");
+                }
 
-                    loop {
-                        tokio::select! {
-                            _ = &mut shutdown_rx => {
-                                log_debug("Received shutdown signal");
-                                break;
-                            }
-                            result = listener.accept() => {
-                                match result {
-                                    Ok((socket, addr)) => {
-                                        log_debug(&format!("New connection from {:?}", addr));
-                                        let worker_id = worker_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-                                        let session_id = match trex_pool_client::create_session() {
-                                            Ok(id) => id,
-                                            Err(e) => {
-                                                log_debug(&format!("create_session: {e}"));
-                                                continue;
-                                            }
-                                        };
-                                        let handlers = Arc::new(TrexPgWireServerFactory::new(server_host.clone(), server_port, worker_id, session_id));
-                                        tokio::spawn(async move {
-                                            log_debug("Processing socket...");
-                                            let result = process_socket(socket, None, handlers).await;
-                                            log_debug(&format!("Socket result: {:?}", result));
-                                            let _ = trex_pool_client::destroy_session(session_id);
-                                        });
-                                    }
-                                    Err(e) => {
-                                        log_debug(&format!("Accept error: {}", e));
-                                        break;
-                                    }
+                let worker_counter = std::sync::atomic::AtomicUsize::new(0);
+
+                loop {
+                    tokio::select! {
+                        _ = &mut shutdown_rx => {
+                            log_debug("Received shutdown signal");
+                            break;
+                        }
+                        result = listener.accept() => {
+                            match result {
+                                Ok((socket, addr)) => {
+                                    log_debug(&format!("New connection from {:?}", addr));
+                                    let worker_id = worker_counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                                    tokio::spawn(serve_connection(socket, addr, worker_id, limiter.clone(), context.clone()));
+                                }
+                                Err(e) => {
+                                    log_debug(&format!("Accept error: {}", e));
+                                    break;
                                 }
                             }
                         }
@@ -1301,6 +2894,7 @@ pub fn start_pgwire_server_capi(
         shutdown_tx,
         start_time,
         db_credentials,
+        rewrite_dialect: None,
     };
     
     ServerRegistry::instance().register_server(host.clone(), port, server_handle)?;
@@ -1366,6 +2960,360 @@ mod tests {
         assert!(!is_postgres_only_set("SETOF integer"));
     }
 
+    // -------- LISTEN / NOTIFY --------
+
+    #[test]
+    fn parses_listen_and_unlisten() {
+        assert_eq!(parse_notify_command("LISTEN Cache_Events;"), Some(NotifyCommand::Listen("cache_events".into())));
+        assert_eq!(parse_notify_command("listen \"MixedCase\""), Some(NotifyCommand::Listen("MixedCase".into())));
+        assert_eq!(parse_notify_command("UNLISTEN *"), Some(NotifyCommand::Unlisten(None)));
+        assert_eq!(parse_notify_command("unlisten ch"), Some(NotifyCommand::Unlisten(Some("ch".into()))));
+        assert_eq!(parse_notify_command("LISTEN"), None);
+        assert_eq!(parse_notify_command("LISTEN a b"), None);
+        assert_eq!(parse_notify_command("SELECT 'LISTEN x'"), None);
+    }
+
+    #[test]
+    fn parses_notify_payloads() {
+        assert_eq!(
+            parse_notify_command("NOTIFY cache"),
+            Some(NotifyCommand::Notify { channel: "cache".into(), payload: String::new() })
+        );
+        assert_eq!(
+            parse_notify_command("NOTIFY cache, 'user''s table'"),
+            Some(NotifyCommand::Notify { channel: "cache".into(), payload: "user's table".into() })
+        );
+        assert_eq!(parse_notify_command("NOTIFY cache, unquoted"), None);
+        assert_eq!(parse_notify_command("NOTIFY cache, 'a' 'b'"), None);
+    }
+
+    #[test]
+    fn notify_payloads_with_semicolons_stay_one_statement() {
+        let statements = pgt::utils::split_statements("NOTIFY ch, 'a;b'; SELECT $$x;y$$; LISTEN ch");
+        assert_eq!(statements.len(), 3);
+        assert_eq!(
+            parse_notify_command(&statements[0]),
+            Some(NotifyCommand::Notify { channel: "ch".into(), payload: "a;b".into() })
+        );
+        assert_eq!(statements[1], "SELECT $$x;y$$");
+        assert_eq!(parse_notify_command(&statements[2]), Some(NotifyCommand::Listen("ch".into())));
+    }
+
+    // -------- database routing --------
+
+    fn encode_credentials(json: &str) -> String {
+        general_purpose::STANDARD.encode(json)
+    }
+
+    #[test]
+    fn file_backed_entries_attach_before_use() {
+        let creds = encode_credentials(
+            r#"[{"id": "analytics", "path": "/data/analytics.duckdb"}, {"id": "staging", "dialect": "duckdb"}]"#,
+        );
+        match check_database_action("analytics", &creds) {
+            DatabaseAction::SetDatabase { attach_path } => {
+                assert_eq!(attach_path.as_deref(), Some("/data/analytics.duckdb"));
+                assert_eq!(
+                    database_context_sql("analytics", attach_path.as_deref()),
+                    vec![
+                        "ATTACH IF NOT EXISTS '/data/analytics.duckdb' AS \"analytics\"".to_string(),
+                        "USE \"analytics\"".to_string(),
+                    ]
+                );
+            }
+            other => panic!("unexpected action: {:?}", other),
+        }
+        assert!(matches!(
+            check_database_action("staging", &creds),
+            DatabaseAction::SetDatabase { attach_path: None }
+        ));
+        assert!(matches!(check_database_action("other", &creds), DatabaseAction::Skip));
+    }
+
+    #[test]
+    fn lists_databases_from_credentials() {
+        let creds = encode_credentials(r#"[{"id": "analytics", "path": "a.duckdb"}, {"id": "hana1", "dialect": "hana"}]"#);
+        assert!(database_listed("analytics", &creds));
+        assert!(database_listed("hana1", &creds));
+        assert!(!database_listed("staging", &creds));
+        assert!(!database_listed("analytics", "not base64"));
+    }
+
+    #[tokio::test]
+    async fn startup_rejects_unknown_database() {
+        let params = |db: &str| {
+            [("user".to_string(), "trex".to_string()), ("database".to_string(), db.to_string())]
+                .into_iter()
+                .collect::<std::collections::BTreeMap<_, _>>()
+        };
+        // No server registered on this port, so only the libpq default applies.
+        assert!(check_startup_database("127.0.0.1", 1, &params("trex")).await.is_ok());
+        assert!(check_startup_database("127.0.0.1", 1, &params("")).await.is_ok());
+        let err: ErrorInfo = check_startup_database("127.0.0.1", 1, &params("nope")).await.unwrap_err().into();
+        assert!(err.is_fatal());
+        assert_eq!(err.code, "3D000");
+    }
+
+    // -------- idle timeout --------
+
+    #[tokio::test]
+    async fn idle_connection_is_closed_with_fatal_error() {
+        use tokio::io::AsyncReadExt;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).await.unwrap();
+        let (socket, _) = listener.accept().await.unwrap();
+        let key = ServerRegistry::instance().register_backend(0, "idle-test".to_string());
+        let server = tokio::spawn(process_with_idle_timeout(
+            socket,
+            Arc::new(RejectingServer),
+            key.pid,
+            Some(Duration::from_millis(50)),
+        ));
+
+        let mut received = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), client.read_to_end(&mut received))
+            .await
+            .expect("connection was not closed")
+            .unwrap();
+        assert!(server.await.unwrap().is_ok());
+        assert_eq!(received, idle_termination_message());
+        assert_eq!(received[0], b'E');
+        assert!(String::from_utf8_lossy(&received).contains("57P05"));
+        ServerRegistry::instance().unregister_backend(key.pid);
+    }
+
+    // -------- connection limits --------
+
+    #[tokio::test]
+    async fn limiter_frees_slot_when_connection_ends() {
+        let limiter = ConnectionLimiter::new(1, 0);
+        let first = limiter.acquire().await.unwrap();
+        assert!(first.is_some());
+        assert!(limiter.acquire().await.is_err());
+        drop(first);
+        assert!(limiter.acquire().await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn limiter_queues_until_timeout() {
+        let limiter = ConnectionLimiter::new(1, 200);
+        let first = limiter.acquire().await.unwrap();
+        let waiter = {
+            let limiter = limiter.clone();
+            tokio::spawn(async move { limiter.acquire().await.map(|p| p.is_some()) })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(first);
+        assert_eq!(waiter.await.unwrap(), Ok(true));
+
+        let _held = limiter.acquire().await.unwrap();
+        assert!(limiter.acquire().await.is_err());
+        assert!(ConnectionLimiter::new(0, 0).acquire().await.unwrap().is_none());
+    }
+
+    // -------- authentication --------
+
+    #[test]
+    fn scram_verifier_keeps_only_salted_password() {
+        match PasswordVerifier::for_method(AuthMethod::ScramSha256, "secret") {
+            PasswordVerifier::Scram { salt, salted_password } => {
+                assert_eq!(salted_password, gen_salted_password("secret", &salt, SCRAM_ITERATIONS));
+                assert_ne!(salted_password, b"secret".to_vec());
+            }
+            PasswordVerifier::Plain(_) => panic!("SCRAM must not retain the plaintext password"),
+        }
+    }
+
+    #[test]
+    fn legacy_methods_keep_plaintext() {
+        assert!(matches!(
+            PasswordVerifier::for_method(AuthMethod::Md5, "secret"),
+            PasswordVerifier::Plain(ref p) if p == "secret"
+        ));
+        assert!(matches!(
+            PasswordVerifier::for_method(AuthMethod::Password, "secret"),
+            PasswordVerifier::Plain(_)
+        ));
+    }
+
+    // -------- extended query parameters --------
+
+    #[test]
+    fn decodes_binary_integer_and_float_parameters() {
+        assert_eq!(decode_binary_parameter(&42i16.to_be_bytes(), &Type::INT2).unwrap(), "42");
+        assert_eq!(decode_binary_parameter(&(-7i32).to_be_bytes(), &Type::INT4).unwrap(), "-7");
+        assert_eq!(
+            decode_binary_parameter(&9_000_000_000i64.to_be_bytes(), &Type::INT8).unwrap(),
+            "9000000000"
+        );
+        assert_eq!(decode_binary_parameter(&1.5f64.to_be_bytes(), &Type::FLOAT8).unwrap(), "1.5");
+        assert_eq!(decode_binary_parameter(&[1], &Type::BOOL).unwrap(), "true");
+    }
+
+    #[test]
+    fn decodes_binary_temporal_parameters() {
+        // 2000-01-02 is one day after the Postgres epoch.
+        assert_eq!(decode_binary_parameter(&1i32.to_be_bytes(), &Type::DATE).unwrap(), "2000-01-02");
+        assert_eq!(
+            decode_binary_parameter(&1_500_000i64.to_be_bytes(), &Type::TIMESTAMP).unwrap(),
+            "2000-01-01 00:00:01.500000"
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_binary_parameters() {
+        assert!(decode_binary_parameter(&[0, 1], &Type::INT4).is_err());
+        assert!(decode_binary_parameter(&[0; 4], &Type::POINT).is_err());
+    }
+
+    #[test]
+    fn describe_fills_unspecified_parameter_types() {
+        let declared = vec![Some(Type::INT4), None];
+        assert_eq!(
+            describe_parameter_types(&declared, 3),
+            vec![Type::INT4, Type::UNKNOWN, Type::UNKNOWN]
+        );
+        assert!(describe_parameter_types(&[], 0).is_empty());
+    }
+
+    // -------- command tags --------
+
+    fn count_result(counts: &[i64]) -> (Arc<Schema>, Vec<RecordBatch>) {
+        use duckdb::arrow::array::Int64Array;
+        use duckdb::arrow::datatypes::{DataType, Field};
+        let schema = Arc::new(Schema::new(vec![Field::new("Count", DataType::Int64, false)]));
+        let rb = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int64Array::from(counts.to_vec()))])
+            .unwrap();
+        (schema, vec![rb])
+    }
+
+    #[test]
+    fn dml_tags_report_affected_rows() {
+        let (schema, batches) = count_result(&[5]);
+        assert_eq!(
+            execution_tag("INSERT INTO t VALUES (1)", &schema, &batches),
+            Tag::new("INSERT").with_oid(0).with_rows(5)
+        );
+        assert_eq!(
+            execution_tag("  update t SET a = 1", &schema, &batches),
+            Tag::new("UPDATE").with_rows(5)
+        );
+        assert_eq!(
+            execution_tag("-- purge\n/* old rows */ DELETE FROM t", &schema, &batches),
+            Tag::new("DELETE").with_rows(5)
+        );
+        let tag: pgwire::messages::response::CommandComplete =
+            execution_tag("insert into t select 1", &schema, &batches).into();
+        assert_eq!(tag.tag, "INSERT 0 5");
+    }
+
+    #[test]
+    fn dml_after_a_with_clause_reports_affected_rows() {
+        let (schema, batches) = count_result(&[2]);
+        assert_eq!(
+            execution_tag(
+                "WITH src (a) AS (SELECT 1 UNION ALL SELECT 2) INSERT INTO t SELECT a FROM src",
+                &schema,
+                &batches
+            ),
+            Tag::new("INSERT").with_oid(0).with_rows(2)
+        );
+        assert_eq!(
+            execution_tag(
+                "WITH RECURSIVE old AS MATERIALIZED (SELECT id FROM t) DELETE FROM t USING old",
+                &schema,
+                &batches
+            ),
+            Tag::new("DELETE").with_rows(2)
+        );
+        assert_eq!(
+            execution_tag("CREATE TABLE t2 AS SELECT * FROM t", &schema, &batches),
+            Tag::new("SELECT").with_rows(2)
+        );
+    }
+
+    #[test]
+    fn other_statements_are_tagged_by_command() {
+        let (schema, batches) = count_result(&[0]);
+        let empty = Schema::empty();
+        for (sql, tag) in [
+            ("CREATE TABLE t (a INT, b VARCHAR AS ('x'))", "CREATE TABLE"),
+            ("create or replace temp view v as select 1", "CREATE VIEW"),
+            ("CREATE UNIQUE INDEX i ON t (a)", "CREATE INDEX"),
+            ("CREATE MATERIALIZED VIEW m AS SELECT 1", "CREATE MATERIALIZED VIEW"),
+            ("DROP TABLE IF EXISTS t", "DROP TABLE"),
+            ("ALTER TABLE t ADD COLUMN c INT", "ALTER TABLE"),
+            ("CREATE \"weird\" thing", "CREATE"),
+            ("BEGIN", "BEGIN"),
+            ("START TRANSACTION", "START TRANSACTION"),
+            ("COMMIT", "COMMIT"),
+            ("end", "COMMIT"),
+            ("ROLLBACK", "ROLLBACK"),
+            ("SET threads = 4", "SET"),
+            ("TRUNCATE t", "TRUNCATE TABLE"),
+            ("CHECKPOINT", "CHECKPOINT"),
+            ("/* ( */ ATTACH 'x.db' AS x", "ATTACH"),
+        ] {
+            assert_eq!(execution_tag(sql, &schema, &batches), Tag::new(tag), "{sql}");
+            assert_eq!(execution_tag(sql, &empty, &[]), Tag::new(tag), "{sql}");
+        }
+        assert_eq!(
+            execution_tag("DELETE FROM t", &empty, &[]),
+            Tag::new("DELETE").with_rows(0)
+        );
+        assert_eq!(execution_tag("-- nothing", &empty, &[]), Tag::new("OK").with_rows(0));
+    }
+
+    #[test]
+    fn transaction_control_moves_the_transaction_status() {
+        let empty = Schema::empty();
+        for sql in ["BEGIN", "start transaction"] {
+            assert!(matches!(execution_response(sql, &empty, &[]), Response::TransactionStart(_)), "{sql}");
+        }
+        for sql in ["COMMIT", "END", "rollback", "ABORT"] {
+            assert!(matches!(execution_response(sql, &empty, &[]), Response::TransactionEnd(_)), "{sql}");
+        }
+        assert!(matches!(
+            execution_response("CHECKPOINT", &empty, &[]),
+            Response::Execution(_)
+        ));
+    }
+
+    #[test]
+    fn row_returning_statements_tag_by_verb() {
+        assert_eq!(query_command_tag("SELECT 1"), "SELECT");
+        assert_eq!(query_command_tag("(SELECT 1) UNION (SELECT 2)"), "SELECT");
+        assert_eq!(query_command_tag("WITH x AS (SELECT 1) SELECT * FROM x"), "SELECT");
+        assert_eq!(query_command_tag("INSERT INTO t VALUES (1) RETURNING id"), "INSERT 0");
+        assert_eq!(query_command_tag("delete from t returning *"), "DELETE");
+        assert_eq!(
+            query_command_tag("WITH x (a) AS (SELECT 1) INSERT INTO t SELECT a FROM x RETURNING *"),
+            "INSERT 0"
+        );
+        assert_eq!(query_command_tag("WITH \"insert\" AS (SELECT 1) SELECT * FROM \"insert\""), "SELECT");
+        assert_eq!(statement_verb(" -- only a comment"), None);
+    }
+
+    #[test]
+    fn read_only_server_refuses_writes_before_running_any() {
+        let key = BackendKey { pid: 1, secret: 2 };
+        let handler = TrexQueryHandler::new("127.0.0.1".to_string(), 5432, 0, 0, key, true);
+        assert!(handler.check_read_only(&["SELECT 1", "SET search_path = main"]).is_ok());
+
+        let err = handler.check_read_only(&["SELECT 1", "INSERT INTO t VALUES (1)"]).unwrap_err();
+        match err {
+            PgWireError::UserError(info) => {
+                assert_eq!(info.code, "25006");
+                assert_eq!(info.message, "cannot execute INSERT on a read-only server");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        let writable = TrexQueryHandler::new("127.0.0.1".to_string(), 5432, 0, 0, key, false);
+        assert!(writable.check_read_only(&["DROP TABLE t"]).is_ok());
+    }
+
     // -------- needs_string_cast / rebuild_*_for_pg --------
 
     #[test]
@@ -1492,4 +3440,216 @@ mod tests {
         assert_eq!(rebuilt.schema().field(0).data_type(), &DataType::Int32);
         assert_eq!(rebuilt.num_rows(), 1);
     }
+
+    /// Split a DataRow payload into its length-prefixed column values.
+    fn data_row_values(row: &pgwire::messages::data::DataRow) -> Vec<Option<Vec<u8>>> {
+        let mut data = &row.data[..];
+        let mut values = Vec::new();
+        for _ in 0..row.field_count {
+            let len = i32::from_be_bytes(data[..4].try_into().unwrap());
+            data = &data[4..];
+            if len < 0 {
+                values.push(None);
+            } else {
+                values.push(Some(data[..len as usize].to_vec()));
+                data = &data[len as usize..];
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn binary_result_format_encodes_big_endian_values() {
+        use duckdb::arrow::array::{
+            BooleanArray, Date32Array, Float64Array, Int16Array, Int32Array, Int64Array,
+            TimestampMicrosecondArray,
+        };
+        use duckdb::arrow::datatypes::{DataType, Field, TimeUnit};
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("s", DataType::Int16, true),
+            Field::new("i", DataType::Int32, true),
+            Field::new("l", DataType::Int64, true),
+            Field::new("f", DataType::Float64, true),
+            Field::new("b", DataType::Boolean, true),
+            Field::new("d", DataType::Date32, true),
+            Field::new("t", DataType::Timestamp(TimeUnit::Microsecond, None), true),
+        ]));
+        // 2000-01-02 is day 10958 after the UNIX epoch and day 1 of the
+        // Postgres epoch; the timestamp is one second past the Postgres epoch.
+        let rb = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int16Array::from(vec![Some(-2), None])),
+                Arc::new(Int32Array::from(vec![Some(1), None])),
+                Arc::new(Int64Array::from(vec![Some(1i64 << 40), None])),
+                Arc::new(Float64Array::from(vec![Some(1.5), None])),
+                Arc::new(BooleanArray::from(vec![Some(true), None])),
+                Arc::new(Date32Array::from(vec![Some(10958), None])),
+                Arc::new(TimestampMicrosecondArray::from(vec![
+                    Some(946_684_801_000_000),
+                    None,
+                ])),
+            ],
+        )
+        .unwrap();
+
+        let header = Arc::new(schema_to_field_info(&schema, &Format::UnifiedBinary).unwrap());
+        assert!(header.iter().all(|f| f.format() == FieldFormat::Binary));
+        let rows: Vec<_> = encode_batches_safely(header, vec![rb])
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(rows.len(), 2);
+
+        let values = data_row_values(&rows[0]);
+        assert_eq!(values[0].as_deref(), Some(&(-2i16).to_be_bytes()[..]));
+        assert_eq!(values[1].as_deref(), Some(&1i32.to_be_bytes()[..]));
+        assert_eq!(values[2].as_deref(), Some(&(1i64 << 40).to_be_bytes()[..]));
+        assert_eq!(values[3].as_deref(), Some(&1.5f64.to_be_bytes()[..]));
+        assert_eq!(values[4].as_deref(), Some(&[1u8][..]));
+        assert_eq!(values[5].as_deref(), Some(&1i32.to_be_bytes()[..]));
+        assert_eq!(values[6].as_deref(), Some(&1_000_000i64.to_be_bytes()[..]));
+
+        assert!(data_row_values(&rows[1]).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn binary_timestamptz_is_microseconds_since_the_postgres_epoch() {
+        use duckdb::arrow::array::{TimestampMicrosecondArray, TimestampMillisecondArray};
+        use duckdb::arrow::datatypes::{DataType, Field, TimeUnit};
+        let micros = DataType::Timestamp(TimeUnit::Microsecond, Some("Etc/UTC".into()));
+        let millis = DataType::Timestamp(TimeUnit::Millisecond, Some("+00:00".into()));
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("us", micros.clone(), true),
+            Field::new("ms", millis, true),
+            Field::new("text", micros, true),
+        ]));
+        // One second and half a second past 2000-01-01 00:00:00 UTC.
+        let utc_micros =
+            TimestampMicrosecondArray::from(vec![Some(946_684_801_000_000), None]).with_timezone("Etc/UTC");
+        let rb = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(utc_micros.clone()),
+                Arc::new(TimestampMillisecondArray::from(vec![Some(946_684_800_500), None]).with_timezone("+00:00")),
+                Arc::new(utc_micros),
+            ],
+        )
+        .unwrap();
+
+        let format = Format::Individual(vec![1, 1, 0]);
+        let header = Arc::new(schema_to_field_info(&schema, &format).unwrap());
+        assert_eq!(header[0].format(), FieldFormat::Binary);
+        assert_eq!(header[0].datatype(), &Type::TIMESTAMPTZ);
+        assert_eq!(header[2].format(), FieldFormat::Text);
+
+        let rows: Vec<_> = encode_batches_safely(header, vec![rb])
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let values = data_row_values(&rows[0]);
+        assert_eq!(values[0].as_deref(), Some(&1_000_000i64.to_be_bytes()[..]));
+        assert_eq!(values[1].as_deref(), Some(&500_000i64.to_be_bytes()[..]));
+        assert_eq!(values[2].as_deref(), Some(&b"2000-01-01 00:00:01.000000+00"[..]));
+        assert!(data_row_values(&rows[1]).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn binary_result_format_falls_back_to_text_for_string_cast_columns() {
+        use duckdb::arrow::array::{Decimal128Array, Int32Array};
+        use duckdb::arrow::datatypes::{DataType, Field};
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new("n", DataType::Decimal128(10, 2), false),
+        ]));
+        let rb = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![7])),
+                Arc::new(
+                    Decimal128Array::from(vec![12345])
+                        .with_precision_and_scale(10, 2)
+                        .unwrap(),
+                ),
+            ],
+        )
+        .unwrap();
+
+        let header = Arc::new(schema_to_field_info(&schema, &Format::UnifiedBinary).unwrap());
+        assert_eq!(header[0].format(), FieldFormat::Binary);
+        assert_eq!(header[1].format(), FieldFormat::Text);
+        assert_eq!(header[1].datatype(), &Type::NUMERIC);
+        // UInt8 is advertised as int2, which its one-byte binary value is not.
+        assert!(!supports_binary_format(&DataType::UInt8));
+
+        let rows: Vec<_> = encode_batches_safely(header, vec![rb])
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let values = data_row_values(&rows[0]);
+        assert_eq!(values[0].as_deref(), Some(&7i32.to_be_bytes()[..]));
+        assert_eq!(values[1].as_deref(), Some(&b"123.45"[..]));
+    }
+
+    #[test]
+    fn text_result_format_remains_default() {
+        use duckdb::arrow::array::Int32Array;
+        use duckdb::arrow::datatypes::{DataType, Field};
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)]));
+        let rb = RecordBatch::try_new(schema.clone(), vec![Arc::new(Int32Array::from(vec![42]))])
+            .unwrap();
+        let header = Arc::new(schema_to_field_info(&schema, &Format::UnifiedText).unwrap());
+        assert_eq!(header[0].format(), FieldFormat::Text);
+        let rows: Vec<_> = encode_batches_safely(header, vec![rb])
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(data_row_values(&rows[0])[0].as_deref(), Some(&b"42"[..]));
+    }
+
+    #[test]
+    fn typed_columns_advertise_postgres_oids() {
+        use duckdb::arrow::array::{FixedSizeBinaryArray, ListArray, UInt64Array};
+        use duckdb::arrow::datatypes::{DataType, Field, Int32Type};
+        let uuid = Field::new("u", DataType::FixedSizeBinary(16), true).with_metadata(
+            std::collections::HashMap::from([(
+                "ARROW:extension:name".to_string(),
+                "arrow.uuid".to_string(),
+            )]),
+        );
+        let schema = Arc::new(Schema::new(vec![
+            uuid,
+            Field::new("ub", DataType::UInt64, true),
+            Field::new(
+                "l",
+                DataType::List(Arc::new(Field::new("item", DataType::Int32, true))),
+                true,
+            ),
+        ]));
+        let uuid_bytes: Vec<u8> = (0u8..16).map(|b| b * 17).collect();
+        let rb = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(FixedSizeBinaryArray::try_from_iter(vec![uuid_bytes].into_iter()).unwrap()),
+                Arc::new(UInt64Array::from(vec![u64::MAX])),
+                Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![Some(vec![
+                    Some(1),
+                    Some(2),
+                ])])),
+            ],
+        )
+        .unwrap();
+        let header = Arc::new(schema_to_field_info(&schema, &Format::UnifiedText).unwrap());
+        let oids: Vec<u32> = header.iter().map(|f| f.datatype().oid()).collect();
+        assert_eq!(oids, vec![2950, 1700, 1007]);
+
+        let rows: Vec<_> = encode_batches_safely(header, vec![rb])
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        let values = data_row_values(&rows[0]);
+        assert_eq!(values[0].as_deref(), Some(&b"00112233-4455-6677-8899-aabbccddeeff"[..]));
+        assert_eq!(values[1].as_deref(), Some(&b"18446744073709551615"[..]));
+        assert_eq!(values[2].as_deref(), Some(&b"{1,2}"[..]));
+    }
 }
@@ -0,0 +1,80 @@
+//! Optional rewriting of client SQL with the pgt transformer before it runs.
+//!
+//! pgt reads PostgreSQL and writes the chosen dialect, so enabling a rewrite
+//! dialect on a server lets clients send PostgreSQL that DuckDB would reject
+//! as written. Servers start with no rewrite dialect and pass queries through
+//! unchanged.
+
+use pgt::{Dialect, SqlTransformer, TransformationConfig, TransformationError};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Parse the dialect argument of `trex_pgwire_set_rewrite_dialect`. An empty
+/// string, `none` or `off` turns rewriting off.
+pub fn parse_rewrite_dialect(name: &str) -> Result<Option<Dialect>, String> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "" | "none" | "off" => Ok(None),
+        other => Dialect::from_str(other).map(Some),
+    }
+}
+
+/// The shared transformer for `dialect`. Transformers are built once per
+/// dialect and reused by every connection, so their parse caches are shared.
+pub fn transformer_for(dialect: Dialect) -> Result<Arc<SqlTransformer>, String> {
+    static TRANSFORMERS: OnceLock<Mutex<HashMap<Dialect, Arc<SqlTransformer>>>> = OnceLock::new();
+    let mut transformers = TRANSFORMERS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(transformer) = transformers.get(&dialect) {
+        return Ok(Arc::clone(transformer));
+    }
+
+    // Each query is sent to DuckDB on its own, so leave off the statement
+    // terminator and the comments pgt would otherwise write back.
+    let mut config = TransformationConfig::default();
+    config.formatting.statement_terminator = String::new();
+    config.formatting.preserve_comments = false;
+    let transformer = Arc::new(SqlTransformer::new(config, dialect).map_err(|e| e.to_string())?);
+    transformers.insert(dialect, Arc::clone(&transformer));
+    Ok(transformer)
+}
+
+/// SQLSTATE reported to the client when a query can't be rewritten.
+pub fn error_code(err: &TransformationError) -> &'static str {
+    match err {
+        TransformationError::ParseError { .. } => "42601",
+        TransformationError::UnsupportedFeature { .. } => "0A000",
+        _ => "XX000",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rewrite_dialects() {
+        assert_eq!(parse_rewrite_dialect("").unwrap(), None);
+        assert_eq!(parse_rewrite_dialect(" None ").unwrap(), None);
+        assert_eq!(parse_rewrite_dialect("off").unwrap(), None);
+        assert_eq!(parse_rewrite_dialect("DuckDB").unwrap(), Some(Dialect::DuckDb));
+        assert_eq!(parse_rewrite_dialect("hana").unwrap(), Some(Dialect::Hana));
+        assert!(parse_rewrite_dialect("oracle").is_err());
+    }
+
+    #[test]
+    fn rewrites_without_terminator() {
+        let transformer = transformer_for(Dialect::DuckDb).unwrap();
+        let sql = transformer.transform("-- note\nSELECT 1 AS one").unwrap();
+        assert_eq!(sql.trim(), "SELECT 1 AS one");
+        assert!(Arc::ptr_eq(&transformer, &transformer_for(Dialect::DuckDb).unwrap()));
+    }
+
+    #[test]
+    fn rewrite_errors_map_to_sqlstate() {
+        let transformer = transformer_for(Dialect::DuckDb).unwrap();
+        let err = transformer.transform("SELEC 1").unwrap_err();
+        assert_eq!(error_code(&err), "42601");
+    }
+}
@@ -0,0 +1,379 @@
+//! Statement classification for servers started with `read_only`.
+//!
+//! The statement's leading verb is inspected, past comments and
+//! parentheses. The body of every CTE in a `WITH` clause is checked as a
+//! statement of its own, followed by the statement the clause belongs to,
+//! so a CTE wrapping a SELECT is a read while one feeding an INSERT is not.
+//! Anything that can't be classified is refused.
+//!
+//! A read can still have side effects through the functions it calls, so
+//! every call in the statement is checked as well: sequence updates and the
+//! `trex_` extension functions, which manage nodes, servers and tables
+//! outside the query, are refused wherever they appear.
+//!
+//! DuckDB settings are global unless they say otherwise, so `SET` and
+//! `RESET` are only run for the Postgres parameters the session answers
+//! itself, custom `name.key` parameters, session variables and the DuckDB
+//! settings in [`LOCAL_SETTINGS`]. Every other setting is refused.
+
+use crate::session_params::{is_postgres_parameter, parse_session_command, SessionCommand};
+
+/// Verbs a read-only server runs: queries, session settings and
+/// transaction control.
+const ALLOWED_VERBS: &[&str] = &[
+    "SELECT", "VALUES", "TABLE", "FROM", "SHOW", "DESCRIBE", "SUMMARIZE", "SET", "RESET", "BEGIN",
+    "START", "COMMIT", "END", "ROLLBACK", "ABORT",
+];
+
+/// Functions that write even when called from a query.
+const WRITING_FUNCTIONS: &[&str] = &["nextval", "setval"];
+
+/// Prefix of the extension's own functions.
+const EXTENSION_FUNCTION_PREFIX: &str = "trex_";
+
+/// Scopes of `SET` and `RESET` that change more than this session.
+const SHARED_SCOPES: &[&str] = &["GLOBAL", "PERSIST", "PERSIST_ONLY"];
+
+/// DuckDB settings whose default scope is the connection.
+const LOCAL_SETTINGS: &[&str] = &[
+    "search_path", "schema", "calendar", "explain_output", "enable_progress_bar", "enable_progress_bar_print",
+    "errors_as_json", "max_expression_depth",
+];
+
+/// The verb to report when a read-only server must refuse `sql`, or `None`
+/// when it may run.
+pub fn refused_verb(sql: &str) -> Option<String> {
+    Scanner { sql, pos: 0 }
+        .check_statement()
+        .and_then(|()| check_calls(sql))
+        .err()
+}
+
+/// Refuse the first call in `sql` to a function that writes. Function names
+/// may be quoted or schema-qualified; what is inside string literals and
+/// comments is ignored.
+fn check_calls(sql: &str) -> Result<(), String> {
+    let mut scanner = Scanner { sql, pos: 0 };
+    let mut name: Option<String> = None;
+    loop {
+        scanner.skip_trivia();
+        let rest = scanner.rest();
+        let Some(c) = rest.chars().next() else {
+            return Ok(());
+        };
+        if c == '(' {
+            if let Some(function) = name.take() {
+                let function = function.trim_matches('"').to_ascii_lowercase();
+                if WRITING_FUNCTIONS.contains(&function.as_str()) || function.starts_with(EXTENSION_FUNCTION_PREFIX) {
+                    return Err(format!("{}()", function));
+                }
+            }
+            scanner.pos += 1;
+        } else if c == '.' {
+            // Keep only the last part of `schema.function`.
+            scanner.pos += 1;
+        } else if c == '\'' || c == '$' {
+            if !scanner.skip_string() {
+                return Err(unclassified());
+            }
+            name = None;
+        } else if let Some(word) = scanner.word() {
+            name = Some(word);
+        } else {
+            scanner.pos += c.len_utf8();
+            name = None;
+        }
+    }
+}
+
+/// True for a setting that only lasts as long as this session.
+fn is_session_setting(name: &str) -> bool {
+    is_postgres_parameter(name) || name.contains('.') || LOCAL_SETTINGS.contains(&name)
+}
+
+fn unclassified() -> String {
+    "this statement".to_string()
+}
+
+struct Scanner<'a> {
+    sql: &'a str,
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn rest(&self) -> &'a str {
+        &self.sql[self.pos..]
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if let Some(after) = trimmed.strip_prefix("--") {
+                self.pos = self.sql.len() - after.split_once('\n').map_or("", |(_, r)| r).len();
+            } else if let Some(after) = trimmed.strip_prefix("/*") {
+                self.pos = self.sql.len() - after.split_once("*/").map_or("", |(_, r)| r).len();
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_trivia();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Next keyword or identifier, upper-cased; quoted identifiers keep
+    /// their quotes.
+    fn word(&mut self) -> Option<String> {
+        self.skip_trivia();
+        let rest = self.rest();
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let end = quoted.find('"')?;
+            self.pos += end + 2;
+            return Some(rest[..end + 2].to_string());
+        }
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        self.pos += len;
+        Some(rest[..len].to_ascii_uppercase())
+    }
+
+    /// Skip the string literal at the current position: `'...'`, `E'...'`
+    /// with backslash escapes, or dollar-quoted `$tag$...$tag$`. A `$` that
+    /// doesn't open a dollar quote, such as a `$1` placeholder, is skipped
+    /// on its own. `false` when the literal isn't closed.
+    fn skip_string(&mut self) -> bool {
+        let rest = self.rest();
+        if let Some(body) = rest.strip_prefix('$') {
+            let tag_len = body.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(body.len());
+            if !body[tag_len..].starts_with('$') || body.starts_with(|c: char| c.is_ascii_digit()) {
+                self.pos += 1;
+                return true;
+            }
+            let delimiter = &rest[..tag_len + 2];
+            return match rest[delimiter.len()..].find(delimiter) {
+                Some(end) => {
+                    self.pos += delimiter.len() * 2 + end;
+                    true
+                }
+                None => false,
+            };
+        }
+        let escapes = self.sql[..self.pos].ends_with(['E', 'e'])
+            && !self.sql[..self.pos - 1].ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+        let mut chars = rest.char_indices().skip(1);
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' if escapes => {
+                    chars.next();
+                }
+                '\'' => {
+                    self.pos += i + 1;
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        false
+    }
+
+    fn peek_word(&mut self) -> Option<String> {
+        let pos = self.pos;
+        let word = self.word();
+        self.pos = pos;
+        word
+    }
+
+    /// Skip a parenthesised group at the current position, including
+    /// nested groups, string literals, quoted identifiers and comments.
+    fn skip_group(&mut self) -> Option<()> {
+        if !self.eat('(') {
+            return None;
+        }
+        let mut depth = 1;
+        while depth > 0 {
+            self.skip_trivia();
+            let c = self.rest().chars().next()?;
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                '\'' | '"' => {
+                    let end = self.rest()[1..].find(c)?;
+                    self.pos += end + 1;
+                }
+                _ => {}
+            }
+            self.pos += c.len_utf8();
+        }
+        Some(())
+    }
+
+    /// Check the statement at the current position, past leading
+    /// parentheses. `Err` holds the verb that makes it a write.
+    fn check_statement(&mut self) -> Result<(), String> {
+        while self.eat('(') {}
+        self.skip_trivia();
+        let start = self.pos;
+        let verb = self.word().ok_or_else(unclassified)?;
+        match verb.as_str() {
+            "WITH" => {
+                self.check_with()?;
+                self.check_statement()
+            }
+            // EXPLAIN ANALYZE runs the statement, so check what it explains.
+            "EXPLAIN" => {
+                self.skip_explain_options();
+                self.check_statement()
+            }
+            "SET" | "RESET" => match self.peek_word() {
+                Some(scope) if SHARED_SCOPES.contains(&scope.as_str()) => Err(format!("{} {}", verb, scope)),
+                Some(scope) if scope == "VARIABLE" => Ok(()),
+                scope => match parse_session_command(&self.sql[start..]) {
+                    Some(SessionCommand::Set { name, .. } | SessionCommand::Reset(Some(name))) => {
+                        if is_session_setting(&name) {
+                            Ok(())
+                        } else {
+                            Err(format!("{} {}", verb, name))
+                        }
+                    }
+                    Some(SessionCommand::Reset(None)) => Ok(()),
+                    _ => Err(scope.map_or(verb.clone(), |scope| format!("{} {}", verb, scope))),
+                },
+            },
+            v if ALLOWED_VERBS.contains(&v) => Ok(()),
+            _ => Err(verb),
+        }
+    }
+
+    /// Check every CTE of a `WITH` clause, leaving the position at the
+    /// statement that follows it.
+    fn check_with(&mut self) -> Result<(), String> {
+        if self.peek_word().as_deref() == Some("RECURSIVE") {
+            self.word();
+        }
+        loop {
+            self.word().ok_or_else(unclassified)?;
+            if self.rest().trim_start().starts_with('(') {
+                self.skip_group().ok_or_else(unclassified)?;
+            }
+            if self.word().as_deref() != Some("AS") {
+                return Err(unclassified());
+            }
+            while matches!(self.peek_word().as_deref(), Some("NOT" | "MATERIALIZED")) {
+                self.word();
+            }
+            let body = self.pos;
+            self.skip_group().ok_or_else(unclassified)?;
+            let end = self.pos;
+            self.pos = body;
+            self.check_statement()?;
+            self.pos = end;
+            if !self.eat(',') {
+                return Ok(());
+            }
+        }
+    }
+
+    fn skip_explain_options(&mut self) {
+        if self.rest().trim_start().starts_with('(') {
+            let _ = self.skip_group();
+        }
+        while matches!(self.peek_word().as_deref(), Some("ANALYZE" | "ANALYSE" | "VERBOSE")) {
+            self.word();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_and_session_statements_are_allowed() {
+        for sql in [
+            "SELECT 1",
+            "  -- comment\n/* block */ (SELECT * FROM t)",
+            "select * from t where note = 'INSERT'",
+            "VALUES (1), (2)",
+            "FROM t",
+            "SHOW TABLES",
+            "SET search_path = public",
+            "BEGIN",
+            "WITH a AS (SELECT 1), b(x) AS MATERIALIZED (SELECT ')' FROM a) SELECT * FROM b",
+            "WITH RECURSIVE r AS (SELECT 1 UNION ALL SELECT n + 1 FROM r) (SELECT * FROM r)",
+            "EXPLAIN SELECT 1",
+            "EXPLAIN ANALYZE SELECT 1",
+            "SELECT count(*), lower(name) FROM t WHERE id IN (1, 2)",
+            "SELECT 'nextval(1)', $$trex_db_kill_node()$$ /* setval(2) */",
+            "SELECT E'it\\'s nextval(' || currval('s')",
+            "SELECT $1, $tag$ ' $tag$ FROM t",
+            "SET SESSION search_path = main",
+            "RESET search_path",
+            "SET TimeZone = 'UTC'",
+            "SET LOCAL statement_timeout = 0",
+            "set application_name to 'psql'",
+            "SET schema = 'main'",
+            "SET myapp.user_id = 42",
+            "SET VARIABLE x = 1",
+            "RESET ALL",
+        ] {
+            assert_eq!(refused_verb(sql), None, "{sql}");
+        }
+    }
+
+    #[test]
+    fn writes_and_ddl_are_refused() {
+        for (sql, verb) in [
+            ("INSERT INTO t VALUES (1)", "INSERT"),
+            ("update t set a = 1", "UPDATE"),
+            ("/* x */ DELETE FROM t", "DELETE"),
+            ("CREATE TABLE t (a INT)", "CREATE"),
+            ("DROP TABLE t", "DROP"),
+            ("COPY t FROM '/tmp/t.csv'", "COPY"),
+            ("WITH a AS (SELECT 1) INSERT INTO t SELECT * FROM a", "INSERT"),
+            ("WITH a AS (DELETE FROM t RETURNING *) SELECT * FROM a", "DELETE"),
+            ("EXPLAIN ANALYZE UPDATE t SET a = 1", "UPDATE"),
+            ("EXPLAIN (ANALYZE) DELETE FROM t", "DELETE"),
+            ("ATTACH 'x.db'", "ATTACH"),
+            ("SELECT nextval('s')", "nextval()"),
+            ("select 1 where Main.SetVal('s', 1) > 0", "setval()"),
+            ("SELECT \"NEXTVAL\"('s')", "nextval()"),
+            ("SELECT trex_db_kill_node('n1')", "trex_db_kill_node()"),
+            ("FROM trex_db_vacuum_table('t')", "trex_db_vacuum_table()"),
+            ("WITH a AS (SELECT nextval('s')) SELECT * FROM a", "nextval()"),
+            ("SELECT E'\\'' || nextval('s') || ''", "nextval()"),
+            ("SET GLOBAL threads = 1", "SET GLOBAL"),
+            ("set persist memory_limit = '1GB'", "SET PERSIST"),
+            ("RESET GLOBAL threads", "RESET GLOBAL"),
+            ("SET threads = 1", "SET threads"),
+            ("SET memory_limit='1GB'", "SET memory_limit"),
+            ("SET SESSION threads TO 4", "SET threads"),
+            ("RESET threads", "RESET threads"),
+            ("SET profile_output = '/tmp/p.json'", "SET profile_output"),
+        ] {
+            assert_eq!(refused_verb(sql).as_deref(), Some(verb), "{sql}");
+        }
+    }
+
+    #[test]
+    fn unclassifiable_statements_are_refused() {
+        assert!(refused_verb("WITH a AS SELECT 1").is_some());
+        assert!(refused_verb("WITH a AS (SELECT 1").is_some());
+        assert!(refused_verb("(((").is_some());
+        assert!(refused_verb("-- only a comment").is_some());
+        assert!(refused_verb("SELECT 'unterminated").is_some());
+        assert!(refused_verb("SELECT $x$ unterminated").is_some());
+    }
+}
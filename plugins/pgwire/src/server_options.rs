@@ -0,0 +1,171 @@
+/// Authentication method offered to clients during startup.
+///
+/// `ScramSha256` is the default because modern libpq/JDBC builds refuse to
+/// fall back to weaker methods. `Md5` and `Password` (cleartext) remain for
+/// older clients, and `Trust` accepts any connection without a password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuthMethod {
+    ScramSha256,
+    Md5,
+    Password,
+    Trust,
+}
+
+impl AuthMethod {
+    /// Parse a method name using the spellings from `pg_hba.conf`.
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "scram-sha-256" | "scram" => Ok(AuthMethod::ScramSha256),
+            "md5" => Ok(AuthMethod::Md5),
+            "password" | "cleartext" => Ok(AuthMethod::Password),
+            "trust" => Ok(AuthMethod::Trust),
+            other => Err(format!(
+                "unknown auth_method '{}': expected scram-sha-256, md5, password or trust",
+                other
+            )),
+        }
+    }
+}
+
+/// Optional server settings passed as the JSON `options` argument of
+/// `trex_pgwire_start`. Every key is optional; an empty string or `{}`
+/// yields the defaults.
+#[derive(Clone, Debug)]
+pub struct PgWireServerOptions {
+    pub auth_method: AuthMethod,
+    /// Concurrent client connections; `0` disables the limit.
+    pub max_connections: usize,
+    /// How long a connection beyond `max_connections` waits for a free slot
+    /// before it is rejected. `0` rejects immediately.
+    pub connection_queue_timeout_ms: u64,
+    /// Connections used to describe statements for the extended protocol.
+    pub describe_pool_size: usize,
+    /// Refuse every statement except queries, `SET` and transaction control.
+    pub read_only: bool,
+    /// Close a connection that has sent nothing for this long while no
+    /// query runs. `0` disables the timeout.
+    pub idle_timeout_seconds: u64,
+}
+
+pub const DEFAULT_MAX_CONNECTIONS: usize = 100;
+pub const DEFAULT_DESCRIBE_POOL_SIZE: usize = 4;
+
+impl Default for PgWireServerOptions {
+    fn default() -> Self {
+        Self {
+            auth_method: AuthMethod::ScramSha256,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            connection_queue_timeout_ms: 0,
+            describe_pool_size: DEFAULT_DESCRIBE_POOL_SIZE,
+            read_only: false,
+            idle_timeout_seconds: 0,
+        }
+    }
+}
+
+fn get_u64(obj: &serde_json::Map<String, serde_json::Value>, key: &str) -> Result<Option<u64>, String> {
+    match obj.get(key) {
+        None => Ok(None),
+        Some(v) => v
+            .as_u64()
+            .map(Some)
+            .ok_or_else(|| format!("{} must be a non-negative integer", key)),
+    }
+}
+
+impl PgWireServerOptions {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let mut options = Self::default();
+        if json.trim().is_empty() {
+            return Ok(options);
+        }
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| format!("invalid options JSON: {}", e))?;
+        let obj = value
+            .as_object()
+            .ok_or_else(|| "options must be a JSON object".to_string())?;
+
+        if let Some(v) = obj.get("auth_method") {
+            let s = v
+                .as_str()
+                .ok_or_else(|| "auth_method must be a string".to_string())?;
+            options.auth_method = AuthMethod::parse(s)?;
+        }
+        if let Some(n) = get_u64(obj, "max_connections")? {
+            options.max_connections = n as usize;
+        }
+        if let Some(n) = get_u64(obj, "connection_queue_timeout_ms")? {
+            options.connection_queue_timeout_ms = n;
+        }
+        if let Some(n) = get_u64(obj, "describe_pool_size")? {
+            if n == 0 {
+                return Err("describe_pool_size must be at least 1".to_string());
+            }
+            options.describe_pool_size = n as usize;
+        }
+        if let Some(n) = get_u64(obj, "idle_timeout_seconds")? {
+            options.idle_timeout_seconds = n;
+        }
+        if let Some(v) = obj.get("read_only") {
+            options.read_only = v
+                .as_bool()
+                .ok_or_else(|| "read_only must be a boolean".to_string())?;
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_options_use_defaults() {
+        assert_eq!(PgWireServerOptions::from_json("").unwrap().auth_method, AuthMethod::ScramSha256);
+        assert_eq!(PgWireServerOptions::from_json("{}").unwrap().auth_method, AuthMethod::ScramSha256);
+    }
+
+    #[test]
+    fn parses_auth_method() {
+        let opts = PgWireServerOptions::from_json(r#"{"auth_method": "MD5"}"#).unwrap();
+        assert_eq!(opts.auth_method, AuthMethod::Md5);
+        let opts = PgWireServerOptions::from_json(r#"{"auth_method": "trust"}"#).unwrap();
+        assert_eq!(opts.auth_method, AuthMethod::Trust);
+    }
+
+    #[test]
+    fn parses_connection_limits() {
+        let opts = PgWireServerOptions::from_json(
+            r#"{"max_connections": 8, "connection_queue_timeout_ms": 250, "describe_pool_size": 2}"#,
+        )
+        .unwrap();
+        assert_eq!(opts.max_connections, 8);
+        assert_eq!(opts.connection_queue_timeout_ms, 250);
+        assert_eq!(opts.describe_pool_size, 2);
+        assert_eq!(PgWireServerOptions::from_json(r#"{"idle_timeout_seconds": 300}"#).unwrap().idle_timeout_seconds, 300);
+
+        let defaults = PgWireServerOptions::default();
+        assert_eq!(defaults.max_connections, DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(defaults.connection_queue_timeout_ms, 0);
+        assert_eq!(defaults.idle_timeout_seconds, 0);
+    }
+
+    #[test]
+    fn parses_read_only() {
+        assert!(!PgWireServerOptions::default().read_only);
+        assert!(PgWireServerOptions::from_json(r#"{"read_only": true}"#).unwrap().read_only);
+        assert!(PgWireServerOptions::from_json(r#"{"read_only": "yes"}"#).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_options() {
+        assert!(PgWireServerOptions::from_json(r#"{"max_connections": -1}"#).is_err());
+        assert!(PgWireServerOptions::from_json(r#"{"describe_pool_size": 0}"#).is_err());
+        assert!(PgWireServerOptions::from_json(r#"{"idle_timeout_seconds": -5}"#).is_err());
+        assert!(PgWireServerOptions::from_json(r#"{"auth_method": "kerberos"}"#).is_err());
+        assert!(PgWireServerOptions::from_json(r#"{"auth_method": 1}"#).is_err());
+        assert!(PgWireServerOptions::from_json("[]").is_err());
+        assert!(PgWireServerOptions::from_json("not json").is_err());
+    }
+}
@@ -1,6 +1,8 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tokio::sync::oneshot;
 
 pub struct ServerHandle {
@@ -12,6 +14,9 @@ pub struct ServerHandle {
     pub shutdown_tx: oneshot::Sender<()>,
     pub start_time: std::time::SystemTime,
     pub db_credentials: String,
+    /// Dialect client SQL is rewritten to with pgt before it runs; `None`
+    /// passes queries through unchanged.
+    pub rewrite_dialect: Option<pgt::Dialect>,
 }
 
 impl std::fmt::Debug for ServerHandle {
@@ -19,18 +24,103 @@ impl std::fmt::Debug for ServerHandle {
         f.debug_struct("ServerHandle")
             .field("start_time", &self.start_time)
             .field("db_credentials", &"[REDACTED]")
+            .field("rewrite_dialect", &self.rewrite_dialect)
             .finish()
     }
 }
 
+/// Process id and secret sent to a client in BackendKeyData. A
+/// CancelRequest must echo both to interrupt that connection's query.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BackendKey {
+    pub pid: i32,
+    pub secret: i32,
+}
+
+/// A NOTIFY waiting to be delivered to a listening connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    /// Backend pid of the notifying connection.
+    pub pid: i32,
+    pub channel: String,
+    pub payload: String,
+}
+
+struct BackendEntry {
+    notifications: VecDeque<Notification>,
+    secret: i32,
+    session_id: u64,
+    client_addr: String,
+    database: Option<String>,
+    connected_at: std::time::SystemTime,
+    query: Arc<QueryState>,
+}
+
+pub struct QueryState {
+    running: AtomicBool,
+    cancelled: AtomicBool,
+    /// Running query, or the last one once the connection is idle.
+    current_query: Mutex<String>,
+    /// When the client last sent a message or a query finished.
+    last_activity: Mutex<Instant>,
+}
+
+impl Default for QueryState {
+    fn default() -> Self {
+        Self {
+            running: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            current_query: Mutex::new(String::new()),
+            last_activity: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+/// Snapshot of one client connection for `trex_pgwire_sessions()`.
+#[derive(Clone, Debug)]
+pub struct BackendSessionInfo {
+    pub pid: i32,
+    pub client_addr: String,
+    pub database: Option<String>,
+    pub current_query: String,
+    pub active: bool,
+    pub connected_at: std::time::SystemTime,
+}
+
+/// Marks a query as in flight for cancellation until dropped.
+pub struct RunningQuery {
+    state: Arc<QueryState>,
+}
+
+impl RunningQuery {
+    /// Whether a CancelRequest interrupted this query.
+    pub fn was_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for RunningQuery {
+    fn drop(&mut self) {
+        *self.state.last_activity.lock().unwrap() = Instant::now();
+        self.state.running.store(false, Ordering::SeqCst);
+    }
+}
+
 pub struct ServerRegistry {
     servers: Arc<Mutex<HashMap<String, ServerHandle>>>,
+    backends: Mutex<HashMap<i32, BackendEntry>>,
+    /// LISTEN registrations: channel name to listening backend pids.
+    channels: Mutex<HashMap<String, HashSet<i32>>>,
+    next_pid: AtomicI32,
 }
 
 impl ServerRegistry {
     pub fn new() -> Self {
         Self {
             servers: Arc::new(Mutex::new(HashMap::new())),
+            backends: Mutex::new(HashMap::new()),
+            channels: Mutex::new(HashMap::new()),
+            next_pid: AtomicI32::new(1),
         }
     }
 
@@ -87,6 +177,46 @@ impl ServerRegistry {
         servers.get(&key).map(|handle| handle.db_credentials.clone())
     }
 
+    /// Set the rewrite dialect of the server on `server`, or of every
+    /// running server when `server` is `None`.
+    pub fn set_rewrite_dialect(
+        &self,
+        server: Option<(&str, u16)>,
+        dialect: Option<pgt::Dialect>,
+    ) -> Result<String, String> {
+        let mut servers = self.servers.lock().unwrap();
+        let count = match server {
+            Some((host, port)) => {
+                let handle = servers
+                    .get_mut(&Self::server_key(host, port))
+                    .ok_or_else(|| format!("No server running on {}:{}", host, port))?;
+                handle.rewrite_dialect = dialect;
+                1
+            }
+            None => {
+                if servers.is_empty() {
+                    return Err("No servers are currently running".to_string());
+                }
+                for handle in servers.values_mut() {
+                    handle.rewrite_dialect = dialect;
+                }
+                servers.len()
+            }
+        };
+
+        Ok(match dialect {
+            Some(d) => format!("Rewriting queries to {} on {} server(s)", d.name(), count),
+            None => format!("Query rewriting disabled on {} server(s)", count),
+        })
+    }
+
+    pub fn get_rewrite_dialect(&self, host: &str, port: u16) -> Option<pgt::Dialect> {
+        let servers = self.servers.lock().unwrap();
+        servers
+            .get(&Self::server_key(host, port))
+            .and_then(|handle| handle.rewrite_dialect)
+    }
+
     pub fn stop_server(&self, host: &str, port: u16) -> Result<String, String> {
         // Remove the handle while holding the lock briefly so that a
         // concurrent caller can't observe a half-stopped server, then drop
@@ -134,4 +264,252 @@ impl ServerRegistry {
         
         server_info
     }
+
+    /// Assign a backend key to a new connection bound to `session_id`.
+    pub fn register_backend(&self, session_id: u64, client_addr: String) -> BackendKey {
+        let pid = self.next_pid.fetch_add(1, Ordering::Relaxed);
+        let secret = rand::random::<i32>();
+        self.backends.lock().unwrap().insert(
+            pid,
+            BackendEntry {
+                notifications: VecDeque::new(),
+                secret,
+                session_id,
+                client_addr,
+                database: None,
+                connected_at: std::time::SystemTime::now(),
+                query: Arc::new(QueryState::default()),
+            },
+        );
+        BackendKey { pid, secret }
+    }
+
+    pub fn unregister_backend(&self, pid: i32) {
+        self.unlisten_all(pid);
+        self.backends.lock().unwrap().remove(&pid);
+    }
+
+    pub fn listen(&self, pid: i32, channel: &str) {
+        self.channels
+            .lock()
+            .unwrap()
+            .entry(channel.to_string())
+            .or_default()
+            .insert(pid);
+    }
+
+    pub fn unlisten(&self, pid: i32, channel: &str) {
+        let mut channels = self.channels.lock().unwrap();
+        if let Some(listeners) = channels.get_mut(channel) {
+            listeners.remove(&pid);
+            if listeners.is_empty() {
+                channels.remove(channel);
+            }
+        }
+    }
+
+    pub fn unlisten_all(&self, pid: i32) {
+        let mut channels = self.channels.lock().unwrap();
+        channels.retain(|_, listeners| {
+            listeners.remove(&pid);
+            !listeners.is_empty()
+        });
+    }
+
+    /// Queue a notification for every connection listening on `channel`,
+    /// including the sender if it listens. Returns the number of listeners.
+    pub fn notify(&self, pid: i32, channel: &str, payload: &str) -> usize {
+        let listeners: Vec<i32> = match self.channels.lock().unwrap().get(channel) {
+            Some(listeners) => listeners.iter().copied().collect(),
+            None => return 0,
+        };
+        let mut backends = self.backends.lock().unwrap();
+        let mut delivered = 0;
+        for listener in listeners {
+            if let Some(entry) = backends.get_mut(&listener) {
+                entry.notifications.push_back(Notification {
+                    pid,
+                    channel: channel.to_string(),
+                    payload: payload.to_string(),
+                });
+                delivered += 1;
+            }
+        }
+        delivered
+    }
+
+    /// Drain the notifications queued for a connection.
+    pub fn take_notifications(&self, pid: i32) -> Vec<Notification> {
+        self.backends
+            .lock()
+            .unwrap()
+            .get_mut(&pid)
+            .map(|entry| entry.notifications.drain(..).collect())
+            .unwrap_or_default()
+    }
+
+    /// Record the database a connection asked for in its startup packet.
+    pub fn set_backend_database(&self, pid: i32, database: Option<String>) {
+        if let Some(entry) = self.backends.lock().unwrap().get_mut(&pid) {
+            entry.database = database;
+        }
+    }
+
+    pub fn backend_sessions(&self) -> Vec<BackendSessionInfo> {
+        let backends = self.backends.lock().unwrap();
+        let mut sessions: Vec<BackendSessionInfo> = backends
+            .iter()
+            .map(|(pid, entry)| BackendSessionInfo {
+                pid: *pid,
+                client_addr: entry.client_addr.clone(),
+                database: entry.database.clone(),
+                current_query: entry.query.current_query.lock().unwrap().clone(),
+                active: entry.query.running.load(Ordering::SeqCst),
+                connected_at: entry.connected_at,
+            })
+            .collect();
+        sessions.sort_by_key(|s| s.pid);
+        sessions
+    }
+
+    /// Mark `sql` as the connection's running query; cancellation only
+    /// interrupts while the returned guard is alive.
+    pub fn begin_query(&self, pid: i32, sql: &str) -> Option<RunningQuery> {
+        let backends = self.backends.lock().unwrap();
+        let state = Arc::clone(&backends.get(&pid)?.query);
+        *state.current_query.lock().unwrap() = sql.to_string();
+        state.cancelled.store(false, Ordering::SeqCst);
+        state.running.store(true, Ordering::SeqCst);
+        Some(RunningQuery { state })
+    }
+
+    /// Restart the idle timer of a connection after a client message.
+    pub fn touch_backend(&self, pid: i32) {
+        if let Some(entry) = self.backends.lock().unwrap().get(&pid) {
+            *entry.query.last_activity.lock().unwrap() = Instant::now();
+        }
+    }
+
+    /// How long a connection has been idle. `None` while it runs a query,
+    /// since a long query is not idleness, and once it has disconnected.
+    pub fn backend_idle_for(&self, pid: i32) -> Option<Duration> {
+        let backends = self.backends.lock().unwrap();
+        let state = &backends.get(&pid)?.query;
+        if state.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        let idle = state.last_activity.lock().unwrap().elapsed();
+        Some(idle)
+    }
+
+    /// Resolve a CancelRequest to the pool session to interrupt. Returns
+    /// `None` when the key doesn't match or nothing is running, so a stray
+    /// cancel can't affect the connection's next query.
+    pub fn cancel_backend(&self, key: BackendKey) -> Option<u64> {
+        let backends = self.backends.lock().unwrap();
+        let entry = backends.get(&key.pid).filter(|e| e.secret == key.secret)?;
+        if !entry.query.running.load(Ordering::SeqCst) {
+            return None;
+        }
+        entry.query.cancelled.store(true, Ordering::SeqCst);
+        Some(entry.session_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_requires_matching_key_and_running_query() {
+        let registry = ServerRegistry::new();
+        let key = registry.register_backend(42, "127.0.0.1:5000".to_string());
+        let wrong = BackendKey { pid: key.pid, secret: key.secret.wrapping_add(1) };
+
+        assert_eq!(registry.cancel_backend(key), None);
+        let running = registry.begin_query(key.pid, "SELECT 1").unwrap();
+        assert_eq!(registry.cancel_backend(wrong), None);
+        assert!(!running.was_cancelled());
+        assert_eq!(registry.cancel_backend(key), Some(42));
+        assert!(running.was_cancelled());
+        drop(running);
+        assert_eq!(registry.cancel_backend(key), None);
+
+        let next = registry.begin_query(key.pid, "SELECT 2").unwrap();
+        assert!(!next.was_cancelled());
+        drop(next);
+        registry.unregister_backend(key.pid);
+        assert!(registry.begin_query(key.pid, "SELECT 3").is_none());
+    }
+
+    #[test]
+    fn notify_reaches_listeners_until_they_leave() {
+        let registry = ServerRegistry::new();
+        let sender = registry.register_backend(1, "a".to_string());
+        let listener = registry.register_backend(2, "b".to_string());
+        let other = registry.register_backend(3, "c".to_string());
+
+        registry.listen(listener.pid, "cache");
+        registry.listen(other.pid, "other");
+        assert_eq!(registry.notify(sender.pid, "cache", "users"), 1);
+        assert_eq!(
+            registry.take_notifications(listener.pid),
+            vec![Notification { pid: sender.pid, channel: "cache".to_string(), payload: "users".to_string() }]
+        );
+        assert!(registry.take_notifications(listener.pid).is_empty());
+        assert!(registry.take_notifications(other.pid).is_empty());
+
+        registry.unlisten(listener.pid, "cache");
+        assert_eq!(registry.notify(sender.pid, "cache", "again"), 0);
+
+        registry.listen(listener.pid, "cache");
+        registry.unregister_backend(listener.pid);
+        assert_eq!(registry.notify(sender.pid, "cache", "gone"), 0);
+        assert!(registry.channels.lock().unwrap().get("cache").is_none());
+    }
+
+    #[test]
+    fn sessions_report_state_and_last_query() {
+        let registry = ServerRegistry::new();
+        let key = registry.register_backend(7, "10.0.0.1:4242".to_string());
+        registry.set_backend_database(key.pid, Some("analytics".to_string()));
+
+        let running = registry.begin_query(key.pid, "SELECT 42").unwrap();
+        let sessions = registry.backend_sessions();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].pid, key.pid);
+        assert_eq!(sessions[0].client_addr, "10.0.0.1:4242");
+        assert_eq!(sessions[0].database.as_deref(), Some("analytics"));
+        assert_eq!(sessions[0].current_query, "SELECT 42");
+        assert!(sessions[0].active);
+
+        drop(running);
+        let sessions = registry.backend_sessions();
+        assert!(!sessions[0].active);
+        assert_eq!(sessions[0].current_query, "SELECT 42");
+
+        registry.unregister_backend(key.pid);
+        assert!(registry.backend_sessions().is_empty());
+    }
+
+    #[test]
+    fn running_queries_are_not_idle() {
+        let registry = ServerRegistry::new();
+        let key = registry.register_backend(9, "a".to_string());
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(registry.backend_idle_for(key.pid).unwrap() >= Duration::from_millis(20));
+
+        let running = registry.begin_query(key.pid, "SELECT pg_sleep(60)").unwrap();
+        assert_eq!(registry.backend_idle_for(key.pid), None);
+        std::thread::sleep(Duration::from_millis(20));
+        drop(running);
+        assert!(registry.backend_idle_for(key.pid).unwrap() < Duration::from_millis(20));
+
+        std::thread::sleep(Duration::from_millis(20));
+        registry.touch_backend(key.pid);
+        assert!(registry.backend_idle_for(key.pid).unwrap() < Duration::from_millis(20));
+
+        registry.unregister_backend(key.pid);
+        assert_eq!(registry.backend_idle_for(key.pid), None);
+    }
 }
@@ -0,0 +1,547 @@
+//! Postgres session parameters (`SET` / `RESET` / `SHOW`) for a pgwire
+//! connection.
+//!
+//! Drivers set a handful of Postgres GUCs on connect (`client_encoding`,
+//! `DateStyle`, `TimeZone`, `application_name`, ...) and read some back with
+//! `SHOW`. DuckDB knows none of them, so each connection keeps its own map:
+//! known Postgres parameters are answered here, DuckDB settings (`SET
+//! threads = 4`) still run on the session, and a `SET` DuckDB rejects as an
+//! unrecognized parameter is stored so custom GUCs round-trip too.
+//!
+//! Values are stored and echoed only, except `statement_timeout`, which the
+//! server enforces. `TimeZone` in particular does not change how
+//! TIMESTAMPTZ values are rendered; they are always sent in UTC.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use pgwire::api::auth::DefaultServerParameterProvider;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionCommand {
+    /// `SET name = value`; `None` is `SET name TO DEFAULT`.
+    Set { name: String, value: Option<String> },
+    /// `RESET name`; `None` is `RESET ALL`.
+    Reset(Option<String>),
+    /// `SHOW name`; `None` is `SHOW ALL`.
+    Show(Option<String>),
+}
+
+struct KnownParameter {
+    name: &'static str,
+    /// Changes are reported to the client with a ParameterStatus message.
+    reported: bool,
+    read_only: bool,
+}
+
+const fn known(name: &'static str, reported: bool, read_only: bool) -> KnownParameter {
+    KnownParameter { name, reported, read_only }
+}
+
+/// Postgres parameters answered without DuckDB, with the spelling `SHOW`
+/// and ParameterStatus use. The reported set matches Postgres' GUC_REPORT
+/// parameters.
+const KNOWN_PARAMETERS: &[KnownParameter] = &[
+    known("application_name", true, false),
+    known("bytea_output", false, false),
+    known("client_encoding", true, false),
+    known("DateStyle", true, false),
+    known("default_transaction_read_only", true, false),
+    known("extra_float_digits", false, false),
+    known("idle_in_transaction_session_timeout", false, false),
+    known("in_hot_standby", true, true),
+    known("integer_datetimes", true, true),
+    known("IntervalStyle", true, false),
+    known("is_superuser", true, true),
+    known("lock_timeout", false, false),
+    known("max_identifier_length", false, true),
+    known("row_security", false, false),
+    known("search_path", true, false),
+    known("server_encoding", true, true),
+    known("server_version", true, true),
+    known("session_authorization", true, false),
+    known("standard_conforming_strings", true, false),
+    known("statement_timeout", false, false),
+    known("TimeZone", true, false),
+    known("transaction_isolation", false, false),
+];
+
+fn known_parameter(name: &str) -> Option<&'static KnownParameter> {
+    KNOWN_PARAMETERS.iter().find(|p| p.name.eq_ignore_ascii_case(name))
+}
+
+/// True for the Postgres parameters this module answers itself.
+pub fn is_postgres_parameter(name: &str) -> bool {
+    known_parameter(name).is_some()
+}
+
+/// Startup packet keys that are connection settings rather than GUCs.
+const STARTUP_ONLY_KEYS: &[&str] = &["user", "database", "options", "replication"];
+
+/// A `SET` that cannot be applied, with the SQLSTATE Postgres reports.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParameterError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub struct SessionParameters {
+    defaults: HashMap<&'static str, String>,
+    /// Values set on this connection, keyed by lower-cased name and holding
+    /// the display name alongside the value.
+    values: HashMap<String, (String, String)>,
+    /// ParameterStatus messages owed to the client.
+    pending: Vec<(String, String)>,
+}
+
+impl Default for SessionParameters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionParameters {
+    pub fn new() -> Self {
+        // Start from the values the startup handlers report so SHOW agrees
+        // with the ParameterStatus messages sent at login.
+        let provider = DefaultServerParameterProvider::default();
+        let on_off = |v: bool| if v { "on" } else { "off" }.to_string();
+        let defaults = HashMap::from([
+            ("application_name", String::new()),
+            ("bytea_output", "hex".to_string()),
+            ("client_encoding", "UTF8".to_string()),
+            ("DateStyle", provider.date_style.clone()),
+            ("default_transaction_read_only", on_off(provider.default_transaction_read_only)),
+            ("extra_float_digits", "1".to_string()),
+            ("idle_in_transaction_session_timeout", "0".to_string()),
+            ("in_hot_standby", on_off(provider.in_hot_standby)),
+            ("integer_datetimes", on_off(provider.integer_datetimes)),
+            ("IntervalStyle", provider.interval_style.clone()),
+            ("is_superuser", on_off(provider.is_superuser)),
+            ("lock_timeout", "0".to_string()),
+            ("max_identifier_length", "63".to_string()),
+            ("row_security", "on".to_string()),
+            ("search_path", provider.search_path.clone()),
+            ("server_encoding", provider.server_encoding.clone()),
+            ("server_version", provider.server_version.clone()),
+            ("session_authorization", String::new()),
+            ("standard_conforming_strings", on_off(provider.standard_conforming_strings)),
+            ("statement_timeout", "0".to_string()),
+            ("TimeZone", provider.time_zone.clone()),
+            ("transaction_isolation", "read committed".to_string()),
+        ]);
+        Self { defaults, values: HashMap::new(), pending: Vec::new() }
+    }
+
+    /// Current value of `name`: set on this connection, else sent in the
+    /// startup packet, else the built-in default. Returns the display name
+    /// with the value, or `None` for a parameter nobody has set.
+    pub fn get(&self, name: &str, startup: &HashMap<String, String>) -> Option<(String, String)> {
+        let lower = name.to_ascii_lowercase();
+        if let Some(entry) = self.values.get(&lower) {
+            return Some(entry.clone());
+        }
+        let known = known_parameter(&lower);
+        let display = known.map_or_else(|| lower.clone(), |p| p.name.to_string());
+        let from_startup = if lower == "session_authorization" {
+            startup.get("user")
+        } else if STARTUP_ONLY_KEYS.contains(&lower.as_str()) {
+            None
+        } else {
+            startup
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(&lower))
+                .map(|(_, v)| v)
+        };
+        if let Some(value) = from_startup {
+            return Some((display, value.clone()));
+        }
+        known.map(|p| (display, self.defaults.get(p.name).cloned().unwrap_or_default()))
+    }
+
+    /// True if `name` was set on this connection.
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(&name.to_ascii_lowercase())
+    }
+
+    /// Apply `SET name = value`; `None` resets the parameter.
+    pub fn set(
+        &mut self,
+        name: &str,
+        value: Option<String>,
+        startup: &HashMap<String, String>,
+    ) -> Result<(), ParameterError> {
+        let known = known_parameter(name);
+        if known.is_some_and(|p| p.read_only) {
+            return Err(ParameterError {
+                code: "55P02",
+                message: format!("parameter \"{}\" cannot be changed", name),
+            });
+        }
+        let Some(mut value) = value else {
+            self.reset(name, startup);
+            return Ok(());
+        };
+        if name.eq_ignore_ascii_case("client_encoding") {
+            // Results are always produced as UTF-8; there is no conversion.
+            match value.to_ascii_uppercase().replace('-', "").as_str() {
+                "UTF8" | "UNICODE" => value = "UTF8".to_string(),
+                _ => {
+                    return Err(ParameterError {
+                        code: "22023",
+                        message: format!("invalid value for parameter \"client_encoding\": \"{}\"", value),
+                    })
+                }
+            }
+        }
+        if name.eq_ignore_ascii_case("statement_timeout") && parse_timeout_ms(&value).is_none() {
+            return Err(ParameterError {
+                code: "22023",
+                message: format!("invalid value for parameter \"statement_timeout\": \"{}\"", value),
+            });
+        }
+        let display = known.map_or_else(|| name.to_ascii_lowercase(), |p| p.name.to_string());
+        if known.is_some_and(|p| p.reported) {
+            self.pending.push((display.clone(), value.clone()));
+        }
+        self.values.insert(name.to_ascii_lowercase(), (display, value));
+        Ok(())
+    }
+
+    /// Drop the value set on this connection, reporting the restored value
+    /// if the parameter is one clients track.
+    pub fn reset(&mut self, name: &str, startup: &HashMap<String, String>) {
+        let lower = name.to_ascii_lowercase();
+        if self.values.remove(&lower).is_none() {
+            return;
+        }
+        if known_parameter(&lower).is_some_and(|p| p.reported) {
+            if let Some(restored) = self.get(&lower, startup) {
+                self.pending.push(restored);
+            }
+        }
+    }
+
+    pub fn reset_all(&mut self, startup: &HashMap<String, String>) {
+        let names: Vec<String> = self.values.keys().cloned().collect();
+        for name in names {
+            self.reset(&name, startup);
+        }
+    }
+
+    /// Every parameter with a value, sorted by name, for `SHOW ALL`.
+    pub fn all(&self, startup: &HashMap<String, String>) -> Vec<(String, String)> {
+        let mut names: Vec<String> = KNOWN_PARAMETERS
+            .iter()
+            .map(|p| p.name.to_ascii_lowercase())
+            .chain(
+                startup
+                    .keys()
+                    .map(|k| k.to_ascii_lowercase())
+                    .filter(|k| !STARTUP_ONLY_KEYS.contains(&k.as_str())),
+            )
+            .chain(self.values.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names.iter().filter_map(|n| self.get(n, startup)).collect()
+    }
+
+    /// The `statement_timeout` set on this connection; `None` when it is `0`
+    /// or was never set.
+    pub fn statement_timeout(&self) -> Option<Duration> {
+        let (_, value) = self.values.get("statement_timeout")?;
+        let ms = parse_timeout_ms(value)?;
+        (ms > 0).then(|| Duration::from_millis(ms))
+    }
+
+    /// ParameterStatus updates queued by `set` / `reset` since the last call.
+    pub fn take_pending(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.pending)
+    }
+}
+
+/// Parse a Postgres time setting in milliseconds: a number with an optional
+/// unit (`us`, `ms`, `s`, `min`, `h`, `d`); a bare number is milliseconds.
+pub fn parse_timeout_ms(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(value.len());
+    let number: f64 = value[..split].parse().ok()?;
+    let ms_per_unit = match value[split..].trim() {
+        "" | "ms" => 1.0,
+        "us" => 0.001,
+        "s" => 1_000.0,
+        "min" => 60_000.0,
+        "h" => 3_600_000.0,
+        "d" => 86_400_000.0,
+        _ => return None,
+    };
+    Some((number * ms_per_unit).round() as u64)
+}
+
+/// Strip a leading keyword (case-insensitive) that must be followed by
+/// whitespace or the end of input.
+fn strip_keyword<'a>(s: &'a str, keyword: &str) -> Option<&'a str> {
+    let s = s.trim_start();
+    let head = s.get(..keyword.len())?;
+    if !head.eq_ignore_ascii_case(keyword) {
+        return None;
+    }
+    let rest = &s[keyword.len()..];
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        Some(rest.trim_start())
+    } else {
+        None
+    }
+}
+
+/// Parse a parameter name: a plain or dotted identifier, or a quoted one.
+/// Names are case-insensitive and returned lower-cased.
+fn parse_name(s: &str) -> Option<(String, &str)> {
+    let s = s.trim_start();
+    if let Some(rest) = s.strip_prefix('"') {
+        let end = rest.find('"')?;
+        if end == 0 {
+            return None;
+        }
+        return Some((rest[..end].to_ascii_lowercase(), &rest[end + 1..]));
+    }
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .unwrap_or(s.len());
+    if end == 0 {
+        return None;
+    }
+    Some((s[..end].to_ascii_lowercase(), &s[end..]))
+}
+
+/// Parse the value list of a SET. `Some(None)` is `DEFAULT`. Quoted items
+/// are unquoted and list items are joined with ", " as Postgres displays
+/// them.
+fn parse_value(s: &str) -> Option<Option<String>> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    if s.eq_ignore_ascii_case("DEFAULT") || s.eq_ignore_ascii_case("LOCAL") {
+        return Some(None);
+    }
+    let mut items = Vec::new();
+    let mut item = String::new();
+    let mut chars = s.chars().peekable();
+    let mut in_quote = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' if in_quote && chars.peek() == Some(&'\'') => {
+                item.push('\'');
+                chars.next();
+            }
+            '\'' => in_quote = !in_quote,
+            ',' if !in_quote => items.push(std::mem::take(&mut item).trim().to_string()),
+            c if !in_quote && c.is_whitespace() && item.is_empty() => {}
+            c => item.push(c),
+        }
+    }
+    if in_quote {
+        return None;
+    }
+    items.push(item.trim().to_string());
+    Some(Some(items.join(", ")))
+}
+
+/// Recognize `SET` / `RESET` / `SHOW` of a session parameter. Forms that
+/// DuckDB owns (`SET GLOBAL ...`, `SET VARIABLE ...`, `SHOW ALL TABLES`)
+/// return `None`.
+pub fn parse_session_command(sql: &str) -> Option<SessionCommand> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+
+    if let Some(rest) = strip_keyword(sql, "SET") {
+        let mut rest = rest;
+        let mut session = false;
+        if let Some(r) = strip_keyword(rest, "LOCAL") {
+            rest = r;
+        } else if let Some(r) = strip_keyword(rest, "SESSION") {
+            rest = r;
+            session = true;
+        }
+        if let Some(value) = strip_keyword(rest, "TIME").and_then(|r| strip_keyword(r, "ZONE")) {
+            return Some(SessionCommand::Set {
+                name: "timezone".to_string(),
+                value: parse_value(value)?,
+            });
+        }
+        if session {
+            if let Some(value) = strip_keyword(rest, "AUTHORIZATION") {
+                return Some(SessionCommand::Set {
+                    name: "session_authorization".to_string(),
+                    value: parse_value(value)?,
+                });
+            }
+        }
+        let (name, rest) = parse_name(rest)?;
+        let rest = rest.trim_start();
+        let value = match rest.strip_prefix('=') {
+            Some(v) => v,
+            None => strip_keyword(rest, "TO")?,
+        };
+        return Some(SessionCommand::Set { name, value: parse_value(value)? });
+    }
+
+    let (is_show, rest) = match strip_keyword(sql, "SHOW") {
+        Some(rest) => (true, rest),
+        None => (false, strip_keyword(sql, "RESET")?),
+    };
+    let name = if strip_keyword(rest, "ALL").is_some_and(str::is_empty) {
+        None
+    } else if strip_keyword(rest, "TIME").and_then(|r| strip_keyword(r, "ZONE")).is_some_and(str::is_empty) {
+        Some("timezone".to_string())
+    } else if is_show
+        && strip_keyword(rest, "TRANSACTION")
+            .and_then(|r| strip_keyword(r, "ISOLATION"))
+            .and_then(|r| strip_keyword(r, "LEVEL"))
+            .is_some_and(str::is_empty)
+    {
+        Some("transaction_isolation".to_string())
+    } else {
+        let (name, rest) = parse_name(rest)?;
+        if !rest.trim().is_empty() {
+            return None;
+        }
+        Some(name)
+    };
+    Some(if is_show { SessionCommand::Show(name) } else { SessionCommand::Reset(name) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(name: &str, value: &str) -> Option<SessionCommand> {
+        Some(SessionCommand::Set { name: name.to_string(), value: Some(value.to_string()) })
+    }
+
+    #[test]
+    fn parses_set_forms() {
+        assert_eq!(parse_session_command("SET client_encoding = 'UTF8'"), set("client_encoding", "UTF8"));
+        assert_eq!(parse_session_command("set DateStyle to ISO, MDY;"), set("datestyle", "ISO, MDY"));
+        assert_eq!(parse_session_command("SET LOCAL statement_timeout=0"), set("statement_timeout", "0"));
+        assert_eq!(parse_session_command("SET SESSION \"TimeZone\" = 'UTC'"), set("timezone", "UTC"));
+        assert_eq!(parse_session_command("SET TIME ZONE 'Europe/Berlin'"), set("timezone", "Europe/Berlin"));
+        assert_eq!(parse_session_command("SET myapp.user_id = 'it''s'"), set("myapp.user_id", "it's"));
+        assert_eq!(parse_session_command("SET search_path TO a, 'b c'"), set("search_path", "a, b c"));
+        assert_eq!(
+            parse_session_command("SET SESSION AUTHORIZATION alice"),
+            set("session_authorization", "alice")
+        );
+        assert_eq!(
+            parse_session_command("SET TimeZone TO DEFAULT"),
+            Some(SessionCommand::Set { name: "timezone".to_string(), value: None })
+        );
+    }
+
+    #[test]
+    fn parses_reset_and_show() {
+        assert_eq!(parse_session_command("RESET ALL"), Some(SessionCommand::Reset(None)));
+        assert_eq!(
+            parse_session_command("reset TimeZone"),
+            Some(SessionCommand::Reset(Some("timezone".to_string())))
+        );
+        assert_eq!(parse_session_command("SHOW ALL;"), Some(SessionCommand::Show(None)));
+        assert_eq!(
+            parse_session_command("SHOW TIME ZONE"),
+            Some(SessionCommand::Show(Some("timezone".to_string())))
+        );
+        assert_eq!(
+            parse_session_command("SHOW TRANSACTION ISOLATION LEVEL"),
+            Some(SessionCommand::Show(Some("transaction_isolation".to_string())))
+        );
+        assert_eq!(
+            parse_session_command("show server_version"),
+            Some(SessionCommand::Show(Some("server_version".to_string())))
+        );
+    }
+
+    #[test]
+    fn leaves_other_statements_alone() {
+        assert_eq!(parse_session_command("SET"), None);
+        assert_eq!(parse_session_command("SET x"), None);
+        assert_eq!(parse_session_command("SET GLOBAL threads = 4"), None);
+        assert_eq!(parse_session_command("SET VARIABLE x = 1"), None);
+        assert_eq!(parse_session_command("SHOW ALL TABLES"), None);
+        assert_eq!(parse_session_command("SETOF integer"), None);
+        assert_eq!(parse_session_command("SELECT 1"), None);
+        assert_eq!(parse_session_command("SET x = 'unterminated"), None);
+    }
+
+    #[test]
+    fn defaults_come_from_startup_then_builtins() {
+        let params = SessionParameters::new();
+        let startup = HashMap::from([
+            ("user".to_string(), "alice".to_string()),
+            ("application_name".to_string(), "psql".to_string()),
+        ]);
+        assert_eq!(
+            params.get("server_version", &startup).unwrap().1,
+            DefaultServerParameterProvider::default().server_version
+        );
+        assert_eq!(params.get("APPLICATION_NAME", &startup).unwrap().1, "psql");
+        assert_eq!(params.get("session_authorization", &startup).unwrap().1, "alice");
+        assert_eq!(params.get("timezone", &startup).unwrap().0, "TimeZone");
+        assert_eq!(params.get("user", &startup), None);
+        assert_eq!(params.get("tables", &startup), None);
+    }
+
+    #[test]
+    fn set_reports_tracked_parameters() {
+        let mut params = SessionParameters::new();
+        let startup = HashMap::new();
+        params.set("timezone", Some("Europe/Berlin".to_string()), &startup).unwrap();
+        params.set("extra_float_digits", Some("3".to_string()), &startup).unwrap();
+        params.set("myapp.flag", Some("on".to_string()), &startup).unwrap();
+        assert_eq!(params.take_pending(), vec![("TimeZone".to_string(), "Europe/Berlin".to_string())]);
+        assert_eq!(params.get("TimeZone", &startup).unwrap().1, "Europe/Berlin");
+        assert_eq!(params.get("myapp.flag", &startup).unwrap().1, "on");
+        assert!(params.all(&startup).contains(&("myapp.flag".to_string(), "on".to_string())));
+
+        params.reset_all(&startup);
+        assert_eq!(params.take_pending(), vec![("TimeZone".to_string(), "Etc/UTC".to_string())]);
+        assert_eq!(params.get("myapp.flag", &startup), None);
+        assert!(params.take_pending().is_empty());
+    }
+
+    #[test]
+    fn rejects_read_only_and_foreign_encodings() {
+        let mut params = SessionParameters::new();
+        let startup = HashMap::new();
+        assert_eq!(params.set("server_version", Some("9.6".to_string()), &startup).unwrap_err().code, "55P02");
+        assert_eq!(params.set("client_encoding", Some("LATIN1".to_string()), &startup).unwrap_err().code, "22023");
+        params.set("client_encoding", Some("utf-8".to_string()), &startup).unwrap();
+        assert_eq!(params.get("client_encoding", &startup).unwrap().1, "UTF8");
+        assert_eq!(params.set("statement_timeout", Some("soon".to_string()), &startup).unwrap_err().code, "22023");
+    }
+
+    #[test]
+    fn statement_timeout_is_parsed_with_units() {
+        assert_eq!(parse_timeout_ms("1500"), Some(1500));
+        assert_eq!(parse_timeout_ms("30s"), Some(30_000));
+        assert_eq!(parse_timeout_ms(" 2 min "), Some(120_000));
+        assert_eq!(parse_timeout_ms("1.5h"), Some(5_400_000));
+        assert_eq!(parse_timeout_ms("0"), Some(0));
+        assert_eq!(parse_timeout_ms("30 seconds"), None);
+        assert_eq!(parse_timeout_ms("-1"), None);
+
+        let mut params = SessionParameters::new();
+        let startup = HashMap::new();
+        assert_eq!(params.statement_timeout(), None);
+        params.set("statement_timeout", Some("30s".to_string()), &startup).unwrap();
+        assert_eq!(params.statement_timeout(), Some(Duration::from_secs(30)));
+        params.set("statement_timeout", Some("0".to_string()), &startup).unwrap();
+        assert_eq!(params.statement_timeout(), None);
+        params.set("statement_timeout", Some("250".to_string()), &startup).unwrap();
+        params.reset("statement_timeout", &startup);
+        assert_eq!(params.statement_timeout(), None);
+    }
+}
@@ -11,11 +11,21 @@ pub use arrow_schema;
 
 use arrow_array::RecordBatch;
 use arrow_schema::Schema;
+use std::os::raw::c_void;
 use std::sync::{Arc, OnceLock};
 
 type FnSessionCreate = unsafe extern "C" fn() -> u64;
 type FnSessionExecuteArrow = unsafe extern "C" fn(u64, *const u8, usize) -> *mut Opaque;
 type FnSessionExecuteParamsArrow = unsafe extern "C" fn(u64, *const u8, usize, *const *const u8, *const usize, usize) -> *mut Opaque;
+type FnSessionExecuteStreamArrow = unsafe extern "C" fn(u64, *const u8, usize, BatchCallback, *mut c_void) -> *mut Opaque;
+type FnSessionAppend = unsafe extern "C" fn(
+    u64,
+    *const *const u8, *const usize, usize,
+    *const *const u8, *const usize, usize,
+    *const *const u8, *const usize, usize,
+    usize,
+) -> *mut Opaque;
+type FnSessionInterrupt = unsafe extern "C" fn(u64) -> i32;
 type FnSessionDestroy = unsafe extern "C" fn(u64);
 
 type FnArrowIsError = unsafe extern "C" fn(*const Opaque) -> i32;
@@ -23,6 +33,8 @@ type FnArrowData = unsafe extern "C" fn(*const Opaque, *mut *const u8, *mut usiz
 type FnArrowError = unsafe extern "C" fn(*const Opaque, *mut *const u8, *mut usize);
 type FnArrowFree = unsafe extern "C" fn(*mut Opaque);
 
+type BatchCallback = extern "C" fn(*mut c_void, *const u8, usize) -> i32;
+
 /// Opaque pointer for C ABI result handles.
 #[repr(C)]
 pub struct Opaque {
@@ -33,6 +45,9 @@ struct PoolFns {
     session_create: FnSessionCreate,
     session_execute_arrow: FnSessionExecuteArrow,
     session_execute_params_arrow: FnSessionExecuteParamsArrow,
+    session_execute_stream_arrow: FnSessionExecuteStreamArrow,
+    session_append: FnSessionAppend,
+    session_interrupt: FnSessionInterrupt,
     session_destroy: FnSessionDestroy,
     arrow_is_error: FnArrowIsError,
     arrow_data: FnArrowData,
@@ -111,6 +126,9 @@ unsafe fn discover_pool_fns() -> Option<PoolFns> {
         session_create: sym!("trex_pool_session_create"),
         session_execute_arrow: sym!("trex_pool_session_execute_arrow"),
         session_execute_params_arrow: sym!("trex_pool_session_execute_params_arrow"),
+        session_execute_stream_arrow: sym!("trex_pool_session_execute_stream_arrow"),
+        session_append: sym!("trex_pool_session_append"),
+        session_interrupt: sym!("trex_pool_session_interrupt"),
         session_destroy: sym!("trex_pool_session_destroy"),
         arrow_is_error: sym!("trex_pool_arrow_result_is_error"),
         arrow_data: sym!("trex_pool_arrow_result_data"),
@@ -221,6 +239,141 @@ pub fn session_execute_params(
     arrow_result_to_batches(fns, result)
 }
 
+/// Execute parameterized SQL within a session where `None` parameters bind
+/// as SQL NULL (passed to the pool as a null pointer).
+pub fn session_execute_nullable_params(
+    session_id: u64,
+    sql: &str,
+    params: &[Option<String>],
+) -> Result<(Arc<Schema>, Vec<RecordBatch>), String> {
+    let fns = get_fns()?;
+    let ptrs: Vec<*const u8> = params
+        .iter()
+        .map(|p| p.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()))
+        .collect();
+    let lens: Vec<usize> = params
+        .iter()
+        .map(|p| p.as_ref().map_or(0, |s| s.len()))
+        .collect();
+    let result = unsafe {
+        (fns.session_execute_params_arrow)(
+            session_id,
+            sql.as_ptr(), sql.len(),
+            ptrs.as_ptr(), lens.as_ptr(), params.len(),
+        )
+    };
+    arrow_result_to_batches(fns, result)
+}
+
+/// Execute SQL within a session, handing each result batch to `on_batch`
+/// as the pool produces it rather than collecting the whole result. The
+/// first batch is empty and only carries the schema. Returning false from
+/// `on_batch` stops the query, which then returns an error.
+pub fn session_execute_stream(
+    session_id: u64,
+    sql: &str,
+    mut on_batch: impl FnMut(RecordBatch) -> bool,
+) -> Result<(), String> {
+    struct StreamCtx<'a> {
+        on_batch: &'a mut dyn FnMut(RecordBatch) -> bool,
+        error: Option<String>,
+    }
+
+    extern "C" fn deliver(ctx: *mut c_void, data: *const u8, len: usize) -> i32 {
+        let ctx = unsafe { &mut *(ctx as *mut StreamCtx<'_>) };
+        let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+        let (schema, batches) = match deserialize_arrow_ipc(bytes) {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                ctx.error = Some(e);
+                return 1;
+            }
+        };
+        let batches = if batches.is_empty() {
+            vec![RecordBatch::new_empty(schema)]
+        } else {
+            batches
+        };
+        // A panic must not unwind into the pool's C frames.
+        let keep_going = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            batches.into_iter().all(|batch| (ctx.on_batch)(batch))
+        }));
+        match keep_going {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(_) => {
+                ctx.error = Some("stream consumer panicked".to_string());
+                1
+            }
+        }
+    }
+
+    let fns = get_fns()?;
+    let mut ctx = StreamCtx { on_batch: &mut on_batch, error: None };
+    let result = unsafe {
+        (fns.session_execute_stream_arrow)(
+            session_id,
+            sql.as_ptr(), sql.len(),
+            deliver,
+            &mut ctx as *mut StreamCtx<'_> as *mut c_void,
+        )
+    };
+    let outcome = arrow_result_to_batches(fns, result).map(|_| ());
+    match ctx.error {
+        Some(e) => Err(e),
+        None => outcome,
+    }
+}
+
+/// Append rows to a table within a session through a DuckDB Appender.
+/// `table` holds the unquoted name parts (`[catalog, schema,] table`) and
+/// `columns` the unquoted target columns, empty for all of them. `cells`
+/// holds `row_width` values per row; the Appender casts each value to its
+/// column type and `None` appends NULL. Returns the number of rows appended.
+pub fn session_append(
+    session_id: u64,
+    table: &[String],
+    columns: &[String],
+    cells: &[Option<String>],
+    row_width: usize,
+) -> Result<usize, String> {
+    let fns = get_fns()?;
+    let table_ptrs: Vec<*const u8> = table.iter().map(|s| s.as_ptr()).collect();
+    let table_lens: Vec<usize> = table.iter().map(|s| s.len()).collect();
+    let column_ptrs: Vec<*const u8> = columns.iter().map(|s| s.as_ptr()).collect();
+    let column_lens: Vec<usize> = columns.iter().map(|s| s.len()).collect();
+    let cell_ptrs: Vec<*const u8> = cells
+        .iter()
+        .map(|c| c.as_ref().map_or(std::ptr::null(), |s| s.as_ptr()))
+        .collect();
+    let cell_lens: Vec<usize> = cells
+        .iter()
+        .map(|c| c.as_ref().map_or(0, |s| s.len()))
+        .collect();
+    let result = unsafe {
+        (fns.session_append)(
+            session_id,
+            table_ptrs.as_ptr(), table_lens.as_ptr(), table.len(),
+            column_ptrs.as_ptr(), column_lens.as_ptr(), columns.len(),
+            cell_ptrs.as_ptr(), cell_lens.as_ptr(), cells.len(),
+            row_width,
+        )
+    };
+    arrow_result_to_batches(fns, result)?;
+    Ok(cells.len().checked_div(row_width).unwrap_or(0))
+}
+
+/// Interrupt the query currently running in a session. The interrupted
+/// `session_execute*` call returns an error.
+pub fn session_interrupt(session_id: u64) -> Result<(), String> {
+    let fns = get_fns()?;
+    if unsafe { (fns.session_interrupt)(session_id) } == 0 {
+        Err(format!("session {session_id} not found"))
+    } else {
+        Ok(())
+    }
+}
+
 /// Destroy a session: cleanup its Connection and return it to the pool.
 pub fn destroy_session(session_id: u64) -> Result<(), String> {
     let fns = get_fns()?;
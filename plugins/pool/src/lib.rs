@@ -42,6 +42,9 @@ struct SessionEntry {
     /// (temp tables, prepared statements, SET, attached extensions, …).
     /// Gates the expensive cleanup branch in `destroy_session`.
     dirty: Arc<AtomicBool>,
+    /// Obtained at lease time so a query can be interrupted while its
+    /// Connection is taken out of the entry.
+    interrupt: Arc<duckdb::InterruptHandle>,
 }
 
 static SESSIONS: OnceLock<Mutex<HashMap<u64, SessionEntry>>> = OnceLock::new();
@@ -98,6 +101,7 @@ pub fn create_session() -> Result<u64, String> {
         .recv()
         .map_err(|e| format!("pool receiver closed: {e}"))?;
     let id = NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed);
+    let interrupt = conn.interrupt_handle();
     sessions()
         .lock()
         .expect("sessions lock poisoned")
@@ -106,6 +110,7 @@ pub fn create_session() -> Result<u64, String> {
             SessionEntry {
                 conn: Some(conn),
                 dirty: Arc::new(AtomicBool::new(false)),
+                interrupt,
             },
         );
     Ok(id)
@@ -126,6 +131,17 @@ pub fn session_execute_params(
     session_id: u64,
     sql: &str,
     params: &[String],
+) -> Result<(Arc<Schema>, Vec<RecordBatch>), String> {
+    let params: Vec<Option<String>> = params.iter().cloned().map(Some).collect();
+    session_execute_nullable_params(session_id, sql, &params)
+}
+
+/// Like `session_execute_params`, but `None` entries bind as SQL NULL. Used
+/// by the pgwire extended query protocol, where Bind can carry NULL values.
+pub fn session_execute_nullable_params(
+    session_id: u64,
+    sql: &str,
+    params: &[Option<String>],
 ) -> Result<(Arc<Schema>, Vec<RecordBatch>), String> {
     let (conn, dirty) = take_conn(session_id)?;
     if sql_may_dirty_session(sql) {
@@ -185,22 +201,160 @@ fn return_conn(session_id: u64, conn: Connection) {
     }
 }
 
+/// Parameterised statements go through the Connection's prepared-statement
+/// cache: clients using the extended query protocol re-execute the same SQL
+/// text with different bindings, and re-planning it each time dominates
+/// their latency. Unparameterised SQL is prepared fresh so ad-hoc queries
+/// don't evict the hot entries.
 fn run_query(
     conn: &Connection,
     sql: &str,
-    params: &[String],
+    params: &[Option<String>],
 ) -> Result<(Arc<Schema>, Vec<RecordBatch>), String> {
-    let mut stmt = conn.prepare(sql).map_err(|e| format!("prepare: {e}"))?;
     let param_refs: Vec<&dyn duckdb::types::ToSql> =
         params.iter().map(|s| s as &dyn duckdb::types::ToSql).collect();
+    if params.is_empty() {
+        let mut stmt = conn.prepare(sql).map_err(|e| format!("prepare: {e}"))?;
+        collect_arrow(&mut stmt, &param_refs)
+    } else {
+        let mut stmt = conn.prepare_cached(sql).map_err(|e| format!("prepare: {e}"))?;
+        collect_arrow(&mut stmt, &param_refs)
+    }
+}
+
+fn collect_arrow(
+    stmt: &mut duckdb::Statement<'_>,
+    param_refs: &[&dyn duckdb::types::ToSql],
+) -> Result<(Arc<Schema>, Vec<RecordBatch>), String> {
     let arrow_result = stmt
-        .query_arrow(param_refs.as_slice())
+        .query_arrow(param_refs)
         .map_err(|e| format!("query exec: {e}"))?;
     let schema = arrow_result.get_schema();
     let batches: Vec<RecordBatch> = arrow_result.collect();
     Ok((schema, batches))
 }
 
+/// Run a query on the session's Connection and hand its result to
+/// `on_batch` one batch at a time instead of collecting it. DuckDB still
+/// materialises the result (its streaming fetch cannot report errors that
+/// happen mid-query), but each batch is converted and released before the
+/// next. The first call carries only the schema (`None`), so consumers
+/// learn the shape of an empty result too. Returning false from `on_batch`
+/// stops the query with an error.
+pub fn session_execute_stream(
+    session_id: u64,
+    sql: &str,
+    on_batch: &mut dyn FnMut(&Arc<Schema>, Option<&RecordBatch>) -> bool,
+) -> Result<(), String> {
+    let (conn, dirty) = take_conn(session_id)?;
+    if sql_may_dirty_session(sql) {
+        dirty.store(true, Ordering::Relaxed);
+    }
+    let result = panic::catch_unwind(AssertUnwindSafe(|| stream_query(&conn, sql, on_batch)));
+    return_conn(session_id, conn);
+    match result {
+        Ok(r) => r,
+        Err(panic_err) => {
+            let msg = extract_panic_message(panic_err);
+            warn!(error = %msg, "session stream panicked");
+            Err(format!("query panicked: {msg}"))
+        }
+    }
+}
+
+fn stream_query(
+    conn: &Connection,
+    sql: &str,
+    on_batch: &mut dyn FnMut(&Arc<Schema>, Option<&RecordBatch>) -> bool,
+) -> Result<(), String> {
+    let mut stmt = conn.prepare(sql).map_err(|e| format!("prepare: {e}"))?;
+    let arrow_result = stmt.query_arrow([]).map_err(|e| format!("query exec: {e}"))?;
+    let schema = arrow_result.get_schema();
+    if !on_batch(&schema, None) {
+        return Err("stream cancelled by consumer".to_string());
+    }
+    for batch in arrow_result {
+        if !on_batch(&schema, Some(&batch)) {
+            return Err("stream cancelled by consumer".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Append rows to a table through a DuckDB Appender on the session's
+/// Connection. `table` is the unquoted name split into 1–3 parts
+/// (`[catalog.][schema.]table`); an empty `columns` list fills every column.
+/// `cells` holds `row_width` values per row, bound as VARCHAR for the
+/// Appender to cast, with `None` appending NULL. Returns the rows appended.
+pub fn session_append(
+    session_id: u64,
+    table: &[String],
+    columns: &[String],
+    cells: &[Option<String>],
+    row_width: usize,
+) -> Result<usize, String> {
+    if row_width == 0 || !cells.len().is_multiple_of(row_width) {
+        return Err(format!(
+            "append: {} values do not fill rows of {} columns",
+            cells.len(),
+            row_width
+        ));
+    }
+    let (conn, _) = take_conn(session_id)?;
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        append_rows(&conn, table, columns, cells, row_width)
+    }));
+    return_conn(session_id, conn);
+    match result {
+        Ok(r) => r,
+        Err(panic_err) => {
+            let msg = extract_panic_message(panic_err);
+            warn!(error = %msg, "session append panicked");
+            Err(format!("append panicked: {msg}"))
+        }
+    }
+}
+
+fn append_rows(
+    conn: &Connection,
+    table: &[String],
+    columns: &[String],
+    cells: &[Option<String>],
+    row_width: usize,
+) -> Result<usize, String> {
+    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let appender = match table {
+        [name] => conn.appender_with_columns(name, &columns),
+        [schema, name] => conn.appender_with_columns_to_db(name, schema, &columns),
+        [catalog, schema, name] => {
+            conn.appender_with_columns_to_catalog_and_db(name, catalog, schema, &columns)
+        }
+        _ => return Err(format!("invalid append target {:?}", table.join("."))),
+    };
+    let mut appender = appender.map_err(|e| format!("appender: {e}"))?;
+    for row in cells.chunks(row_width) {
+        appender
+            .append_row(duckdb::appender_params_from_iter(row))
+            .map_err(|e| format!("append: {e}"))?;
+    }
+    appender.flush().map_err(|e| format!("append flush: {e}"))?;
+    Ok(cells.len() / row_width)
+}
+
+/// Interrupt the query currently running on the session's Connection. The
+/// query fails with an interrupt error; returns false for unknown sessions.
+pub fn interrupt_session(session_id: u64) -> bool {
+    let handle = {
+        let map = sessions().lock().expect("sessions lock poisoned");
+        match map.get(&session_id) {
+            Some(entry) => Arc::clone(&entry.interrupt),
+            None => return false,
+        }
+    };
+    handle.interrupt();
+    true
+}
+
 /// Destroy a session: remove from the map, run the cleanup sequence on the
 /// leased Connection, then return it to the pool channel.
 pub fn destroy_session(session_id: u64) {
@@ -213,6 +367,10 @@ pub fn destroy_session(session_id: u64) {
     };
     let Some(conn) = conn else { return };
 
+    // Cached prepared statements were bound under this session's USE/SET
+    // state; the next lessee must not inherit them.
+    conn.flush_prepared_statement_cache();
+
     if dirty.load(Ordering::Relaxed) {
         cleanup_connection(&conn);
     } else if let Err(e) = conn.execute_batch("ROLLBACK") {
@@ -397,6 +555,7 @@ pub extern "C" fn trex_pool_session_execute_arrow(
 }
 
 /// Execute parameterized SQL within a session, returning Arrow IPC bytes.
+/// A null entry in `params_ptrs` binds SQL NULL for that parameter.
 #[no_mangle]
 pub extern "C" fn trex_pool_session_execute_params_arrow(
     session_id: u64,
@@ -407,18 +566,8 @@ pub extern "C" fn trex_pool_session_execute_params_arrow(
     params_count: usize,
 ) -> *mut CArrowResult {
     let sql = unsafe { std::str::from_utf8_unchecked(std::slice::from_raw_parts(sql_ptr, sql_len)) };
-    let params: Vec<String> = if params_count > 0 && !params_ptrs.is_null() && !params_lens.is_null() {
-        unsafe {
-            let ptrs = std::slice::from_raw_parts(params_ptrs, params_count);
-            let lens = std::slice::from_raw_parts(params_lens, params_count);
-            ptrs.iter().zip(lens.iter()).map(|(&p, &l)| {
-                std::str::from_utf8_unchecked(std::slice::from_raw_parts(p, l)).to_string()
-            }).collect()
-        }
-    } else {
-        Vec::new()
-    };
-    let cresult = match session_execute_params(session_id, sql, &params) {
+    let params = unsafe { c_nullable_strings(params_ptrs, params_lens, params_count) };
+    let cresult = match session_execute_nullable_params(session_id, sql, &params) {
         Ok((schema, batches)) => match serialize_arrow_ipc(&schema, &batches) {
             Ok(data) => CArrowResult { data, error: None },
             Err(e) => CArrowResult { data: Vec::new(), error: Some(e) },
@@ -428,6 +577,97 @@ pub extern "C" fn trex_pool_session_execute_params_arrow(
     Box::into_raw(Box::new(cresult))
 }
 
+/// Receives one Arrow IPC stream per result batch from
+/// `trex_pool_session_execute_stream_arrow`. Returning non-zero stops the
+/// query.
+pub type CBatchCallback = extern "C" fn(ctx: *mut c_void, data: *const u8, len: usize) -> i32;
+
+/// Execute SQL within a session, passing each result batch to `callback`
+/// as it is produced. The first call carries the schema and no batches.
+/// The returned handle holds no data, only the error if the query failed.
+#[no_mangle]
+pub extern "C" fn trex_pool_session_execute_stream_arrow(
+    session_id: u64,
+    sql_ptr: *const u8,
+    sql_len: usize,
+    callback: CBatchCallback,
+    ctx: *mut c_void,
+) -> *mut CArrowResult {
+    let sql = unsafe { std::str::from_utf8_unchecked(std::slice::from_raw_parts(sql_ptr, sql_len)) };
+    let mut ipc_error = None;
+    let mut on_batch = |schema: &Arc<Schema>, batch: Option<&RecordBatch>| {
+        let batches: Vec<RecordBatch> = batch.into_iter().cloned().collect();
+        match serialize_arrow_ipc(schema, &batches) {
+            Ok(data) => callback(ctx, data.as_ptr(), data.len()) == 0,
+            Err(e) => {
+                ipc_error = Some(e);
+                false
+            }
+        }
+    };
+    let result = session_execute_stream(session_id, sql, &mut on_batch);
+    let error = match (result, ipc_error) {
+        (_, Some(e)) | (Err(e), None) => Some(e),
+        (Ok(()), None) => None,
+    };
+    Box::into_raw(Box::new(CArrowResult { data: Vec::new(), error }))
+}
+
+/// Append rows to a table within a session. `table_ptrs`/`table_lens` hold
+/// the 1–3 name parts, `column_ptrs`/`column_lens` the optional column list
+/// and `cell_ptrs`/`cell_lens` the values row by row, `row_width` per row; a
+/// null cell pointer appends NULL. The returned handle holds no data, only
+/// the error if the append failed.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub extern "C" fn trex_pool_session_append(
+    session_id: u64,
+    table_ptrs: *const *const u8,
+    table_lens: *const usize,
+    table_count: usize,
+    column_ptrs: *const *const u8,
+    column_lens: *const usize,
+    column_count: usize,
+    cell_ptrs: *const *const u8,
+    cell_lens: *const usize,
+    cell_count: usize,
+    row_width: usize,
+) -> *mut CArrowResult {
+    let table = unsafe { c_nullable_strings(table_ptrs, table_lens, table_count) };
+    let columns = unsafe { c_nullable_strings(column_ptrs, column_lens, column_count) };
+    let cells = unsafe { c_nullable_strings(cell_ptrs, cell_lens, cell_count) };
+    let table: Vec<String> = table.into_iter().flatten().collect();
+    let columns: Vec<String> = columns.into_iter().flatten().collect();
+    let error = session_append(session_id, &table, &columns, &cells, row_width).err();
+    Box::into_raw(Box::new(CArrowResult { data: Vec::new(), error }))
+}
+
+/// Read a C array of (pointer, length) strings; null pointers become `None`.
+unsafe fn c_nullable_strings(ptrs: *const *const u8, lens: *const usize, count: usize) -> Vec<Option<String>> {
+    if count == 0 || ptrs.is_null() || lens.is_null() {
+        return Vec::new();
+    }
+    let ptrs = std::slice::from_raw_parts(ptrs, count);
+    let lens = std::slice::from_raw_parts(lens, count);
+    ptrs.iter()
+        .zip(lens.iter())
+        .map(|(&p, &l)| {
+            if p.is_null() {
+                None
+            } else {
+                Some(std::str::from_utf8_unchecked(std::slice::from_raw_parts(p, l)).to_string())
+            }
+        })
+        .collect()
+}
+
+/// Interrupt the query running in a session. Returns 1 if the session
+/// exists, 0 otherwise.
+#[no_mangle]
+pub extern "C" fn trex_pool_session_interrupt(session_id: u64) -> i32 {
+    interrupt_session(session_id) as i32
+}
+
 /// Destroy a session: clean up its Connection and return it to the pool.
 #[no_mangle]
 pub extern "C" fn trex_pool_session_destroy(session_id: u64) {
@@ -1,6 +1,6 @@
 //! DuckDB connection management — local executor + pool writes.
 
-use crate::query_executor::QueryExecutor;
+use crate::query_executor::{PoolConfig, QueryExecutor};
 use duckdb::Connection;
 use std::sync::{Arc, Mutex, OnceLock};
 use tracing::warn;
@@ -53,7 +53,7 @@ pub fn init_query_executor(
   connection: &Connection,
   pool_size: usize,
 ) -> Result<(), String> {
-  let executor = QueryExecutor::new(connection, pool_size)?;
+  let executor = QueryExecutor::new(connection, PoolConfig::from_env(pool_size))?;
   QUERY_EXECUTOR
     .set(Arc::new(executor))
     .map_err(|_| "executor already initialized".into())
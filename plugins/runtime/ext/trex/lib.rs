@@ -1,4 +1,5 @@
 pub mod connection;
+pub mod metrics;
 pub mod query_executor;
 
 use base64::{engine::general_purpose, Engine as _};
@@ -0,0 +1,219 @@
+//! Aggregate HTTP request metrics for trex runtime servers.
+//!
+//! Each server owns a [`RequestMetrics`] and registers it under the address
+//! it listens on. Every finished request is reported through
+//! [`record_request`] under the address it was accepted on: by the server's
+//! request handler, the same way it asks
+//! [`try_serve_static`](crate::try_serve_static) for static files, or, for
+//! a watched server, by the runtime's front that owns that address across
+//! reloads. The instances behind a front listen on loopback ports nobody
+//! registers, so their requests are not counted twice.
+//! Latency percentiles come from a fixed-size uniform reservoir, so memory
+//! stays bounded however long the server runs.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::Duration;
+
+/// Latency samples kept per server.
+pub const RESERVOIR_SIZE: usize = 1024;
+
+static SERVER_METRICS: LazyLock<
+  RwLock<HashMap<SocketAddr, Arc<RequestMetrics>>>,
+> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Point-in-time copy of a server's counters. Percentiles are in
+/// milliseconds and `None` until a request has been recorded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+  pub total_requests: u64,
+  pub status_2xx: u64,
+  pub status_4xx: u64,
+  pub status_5xx: u64,
+  pub p50_ms: Option<f64>,
+  pub p95_ms: Option<f64>,
+  pub p99_ms: Option<f64>,
+}
+
+#[derive(Default)]
+struct Counters {
+  total: u64,
+  status_2xx: u64,
+  status_4xx: u64,
+  status_5xx: u64,
+  /// Latencies in microseconds, at most `RESERVOIR_SIZE` of them.
+  samples: Vec<u64>,
+  rng: u64,
+}
+
+impl Counters {
+  /// xorshift64; only used to pick reservoir slots.
+  fn next_random(&mut self) -> u64 {
+    if self.rng == 0 {
+      self.rng = 0x9E37_79B9_7F4A_7C15;
+    }
+    self.rng ^= self.rng << 13;
+    self.rng ^= self.rng >> 7;
+    self.rng ^= self.rng << 17;
+    self.rng
+  }
+}
+
+#[derive(Default)]
+pub struct RequestMetrics {
+  counters: Mutex<Counters>,
+}
+
+impl RequestMetrics {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn record(&self, status: u16, elapsed: Duration) {
+    let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+    let mut c = self.counters.lock().unwrap_or_else(|p| p.into_inner());
+    c.total += 1;
+    match status {
+      200..=299 => c.status_2xx += 1,
+      400..=499 => c.status_4xx += 1,
+      500..=599 => c.status_5xx += 1,
+      _ => {}
+    }
+    // Algorithm R: the n-th sample replaces a random slot with probability
+    // RESERVOIR_SIZE / n.
+    if c.samples.len() < RESERVOIR_SIZE {
+      c.samples.push(micros);
+    } else {
+      let slot = c.next_random() % c.total;
+      if let Some(sample) = c.samples.get_mut(slot as usize) {
+        *sample = micros;
+      }
+    }
+  }
+
+  pub fn snapshot(&self) -> MetricsSnapshot {
+    let c = self.counters.lock().unwrap_or_else(|p| p.into_inner());
+    let mut samples = c.samples.clone();
+    samples.sort_unstable();
+    MetricsSnapshot {
+      total_requests: c.total,
+      status_2xx: c.status_2xx,
+      status_4xx: c.status_4xx,
+      status_5xx: c.status_5xx,
+      p50_ms: percentile(&samples, 50.0),
+      p95_ms: percentile(&samples, 95.0),
+      p99_ms: percentile(&samples, 99.0),
+    }
+  }
+
+  pub fn reset(&self) {
+    let mut c = self.counters.lock().unwrap_or_else(|p| p.into_inner());
+    *c = Counters::default();
+  }
+}
+
+/// Nearest-rank percentile of sorted microsecond samples, in milliseconds.
+fn percentile(sorted: &[u64], pct: f64) -> Option<f64> {
+  if sorted.is_empty() {
+    return None;
+  }
+  let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+  let idx = rank.clamp(1, sorted.len()) - 1;
+  Some(sorted[idx] as f64 / 1000.0)
+}
+
+/// Route requests handled on `addr` to `metrics` until unregistered.
+pub fn register_server_metrics(addr: SocketAddr, metrics: Arc<RequestMetrics>) {
+  let mut servers = SERVER_METRICS.write().unwrap_or_else(|p| p.into_inner());
+  servers.insert(addr, metrics);
+}
+
+/// Stop routing `addr` to `metrics`. A registration made since by another
+/// server on the same address is left in place.
+pub fn unregister_server_metrics(
+  addr: SocketAddr,
+  metrics: &Arc<RequestMetrics>,
+) {
+  let mut servers = SERVER_METRICS.write().unwrap_or_else(|p| p.into_inner());
+  if servers.get(&addr).is_some_and(|m| Arc::ptr_eq(m, metrics)) {
+    servers.remove(&addr);
+  }
+}
+
+/// Called once a response for a request accepted on `addr` has been
+/// produced. Requests on addresses nobody registered are ignored.
+pub fn record_request(addr: SocketAddr, status: u16, elapsed: Duration) {
+  let servers = SERVER_METRICS.read().unwrap_or_else(|p| p.into_inner());
+  if let Some(metrics) = servers.get(&addr) {
+    metrics.record(status, elapsed);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn counts_status_classes() {
+    let metrics = RequestMetrics::new();
+    for status in [200, 204, 301, 404, 500, 503] {
+      metrics.record(status, Duration::from_millis(1));
+    }
+    let snap = metrics.snapshot();
+    assert_eq!(snap.total_requests, 6);
+    assert_eq!(snap.status_2xx, 2);
+    assert_eq!(snap.status_4xx, 1);
+    assert_eq!(snap.status_5xx, 2);
+  }
+
+  #[test]
+  fn percentiles_use_nearest_rank() {
+    let metrics = RequestMetrics::new();
+    assert_eq!(metrics.snapshot().p50_ms, None);
+    for ms in 1..=100 {
+      metrics.record(200, Duration::from_millis(ms));
+    }
+    let snap = metrics.snapshot();
+    assert_eq!(snap.p50_ms, Some(50.0));
+    assert_eq!(snap.p95_ms, Some(95.0));
+    assert_eq!(snap.p99_ms, Some(99.0));
+  }
+
+  #[test]
+  fn reservoir_stays_bounded() {
+    let metrics = RequestMetrics::new();
+    for i in 0..(RESERVOIR_SIZE as u64 * 10) {
+      metrics.record(200, Duration::from_micros(i));
+    }
+    let c = metrics.counters.lock().unwrap();
+    assert_eq!(c.samples.len(), RESERVOIR_SIZE);
+    assert_eq!(c.total, RESERVOIR_SIZE as u64 * 10);
+    // Later samples must have displaced some of the first batch.
+    assert!(c.samples.iter().any(|&s| s >= RESERVOIR_SIZE as u64));
+  }
+
+  #[test]
+  fn reset_clears_everything() {
+    let metrics = RequestMetrics::new();
+    metrics.record(500, Duration::from_millis(3));
+    metrics.reset();
+    assert_eq!(metrics.snapshot(), MetricsSnapshot::default());
+  }
+
+  #[test]
+  fn records_only_registered_addresses() {
+    let addr: SocketAddr = "127.0.0.1:18088".parse().unwrap();
+    let other: SocketAddr = "127.0.0.1:18089".parse().unwrap();
+    let metrics = Arc::new(RequestMetrics::new());
+    register_server_metrics(addr, metrics.clone());
+    record_request(addr, 200, Duration::from_millis(2));
+    record_request(other, 200, Duration::from_millis(2));
+    unregister_server_metrics(addr, &Arc::new(RequestMetrics::new()));
+    record_request(addr, 200, Duration::from_millis(2));
+    assert_eq!(metrics.snapshot().total_requests, 2);
+    unregister_server_metrics(addr, &metrics);
+    record_request(addr, 200, Duration::from_millis(2));
+    assert_eq!(metrics.snapshot().total_requests, 2);
+  }
+}
@@ -1,11 +1,49 @@
 //! Thread pool executor for parallel DuckDB query execution.
+//!
+//! `min_size` core workers are started up front and live as long as the
+//! executor; each has its own channel, so a session can pin to it. Unpinned
+//! queries go through a shared queue that every worker reads. When that
+//! queue backs up, elastic workers are added, up to `max_size`. Their
+//! connections are cloned by a spawner thread that owns the base
+//! connection, so no connection is cloned from a thread submitting queries.
+//! An elastic worker closes its connection after `idle_timeout` without
+//! work. It only checks for that between queries, so shrinking never drops
+//! a connection mid-query.
+//!
+//! Each worker keeps an LRU of prepared statements for its own connection,
+//! keyed by database and the SQL's tokens, so queries that differ only in
+//! spacing, comments or keyword case share an entry.
+//! DuckDB statements are bound to the connection that prepared them, so
+//! the caches are never shared.
 
-use crossbeam_channel::{unbounded, Receiver, Sender};
-use duckdb::Connection;
+use crossbeam_channel::{
+  select, unbounded, Receiver, RecvTimeoutError, Sender,
+};
+use duckdb::{Connection, Statement};
+use sqlparser::dialect::DuckDbDialect;
+use sqlparser::keywords::Keyword;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::collections::HashMap;
+use std::env;
 use std::panic::{self, AssertUnwindSafe};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
-use tracing::warn;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Upper bound on pooled connections, whatever the configuration says.
+/// DuckDB has no connection limit of its own, but every connection keeps a
+/// client context and competes for the database's worker threads.
+pub const MAX_POOL_CEILING: usize = 64;
+
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+const DEFAULT_STATEMENT_CACHE_SIZE: usize = 64;
+
+/// Elastic pools grow to this many times their core workers unless
+/// `TREX_CONNECTION_POOL_MAX` says otherwise.
+const DEFAULT_GROWTH_FACTOR: usize = 2;
 
 pub struct QueryRequest {
   pub database: String,
@@ -19,13 +57,127 @@ pub enum QueryResult {
   Error(String),
 }
 
+/// Sizing of a [`QueryExecutor`] pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolConfig {
+  /// Core workers, started up front and never closed.
+  pub min_size: usize,
+  /// Most connections the pool grows to while queries queue up.
+  pub max_size: usize,
+  /// Idle time after which an elastic worker closes its connection.
+  pub idle_timeout: Duration,
+  /// Prepared statements kept per worker; 0 disables the cache.
+  pub statement_cache_size: usize,
+}
+
+impl PoolConfig {
+  /// A pool of `size` workers that never grows.
+  pub fn fixed(size: usize) -> Self {
+    Self {
+      min_size: size,
+      max_size: size,
+      idle_timeout: DEFAULT_IDLE_TIMEOUT,
+      statement_cache_size: DEFAULT_STATEMENT_CACHE_SIZE,
+    }
+  }
+
+  /// `min_size` core workers, growing to `TREX_CONNECTION_POOL_MAX`
+  /// (default: twice `min_size`, at most [`MAX_POOL_CEILING`]). Set it to
+  /// `min_size` for a fixed-size pool. Elastic workers close after
+  /// `TREX_CONNECTION_POOL_IDLE_SECS` idle seconds (default 60). Each
+  /// worker caches `TREX_STATEMENT_CACHE_SIZE` prepared statements
+  /// (default 64).
+  pub fn from_env(min_size: usize) -> Self {
+    let mut config = Self {
+      max_size: min_size.saturating_mul(DEFAULT_GROWTH_FACTOR),
+      ..Self::fixed(min_size)
+    };
+    if let Some(max_size) = env_usize("TREX_CONNECTION_POOL_MAX") {
+      config.max_size = max_size;
+    }
+    if let Some(secs) = env_usize("TREX_CONNECTION_POOL_IDLE_SECS") {
+      config.idle_timeout = Duration::from_secs(secs as u64);
+    }
+    if let Some(size) = env_usize("TREX_STATEMENT_CACHE_SIZE") {
+      config.statement_cache_size = size;
+    }
+    config
+  }
+
+  /// Clamp to `1 <= min_size <= max_size <= MAX_POOL_CEILING`.
+  fn normalized(self) -> Self {
+    let min_size = self.min_size.clamp(1, MAX_POOL_CEILING);
+    Self {
+      min_size,
+      max_size: self.max_size.clamp(min_size, MAX_POOL_CEILING),
+      ..self
+    }
+  }
+}
+
+fn env_usize(name: &str) -> Option<usize> {
+  let v = env::var(name).ok()?;
+  match v.parse() {
+    Ok(n) => Some(n),
+    Err(_) => {
+      warn!(value = %v, "invalid {name}, ignoring");
+      None
+    }
+  }
+}
+
+/// Prepared-statement cache counters, summed over all workers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+  pub hits: u64,
+  pub misses: u64,
+  pub evictions: u64,
+  /// Statements currently cached.
+  pub entries: u64,
+}
+
+#[derive(Default)]
+struct CacheCounters {
+  hits: AtomicU64,
+  misses: AtomicU64,
+  evictions: AtomicU64,
+  entries: AtomicU64,
+}
+
+impl CacheCounters {
+  fn snapshot(&self) -> CacheStats {
+    CacheStats {
+      hits: self.hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      evictions: self.evictions.load(Ordering::Relaxed),
+      entries: self.entries.load(Ordering::Relaxed),
+    }
+  }
+}
+
 struct Worker {
   _handle: JoinHandle<()>,
 }
 
+/// State shared by the executor and its workers.
+struct PoolState {
+  config: PoolConfig,
+  queue: Receiver<QueryRequest>,
+  /// Workers alive, core and elastic.
+  live: AtomicUsize,
+  /// Workers waiting for a request.
+  idle: AtomicUsize,
+  spawned: AtomicUsize,
+  cache: CacheCounters,
+}
+
 /// Distributes queries across a pool of worker threads with pre-cloned connections.
 pub struct QueryExecutor {
   senders: Vec<Sender<QueryRequest>>,
+  queue: Sender<QueryRequest>,
+  /// Asks the spawner thread for one more elastic worker.
+  grow: Sender<()>,
+  pool: Arc<PoolState>,
   #[allow(dead_code)]
   workers: Vec<Worker>,
   next_worker: AtomicUsize,
@@ -35,38 +187,65 @@ impl QueryExecutor {
   /// Creates executor pool. Must be called from the connection's origin thread.
   pub fn new(
     connection: &Connection,
-    pool_size: usize,
+    config: PoolConfig,
   ) -> Result<Self, String> {
-    let mut connections = Vec::with_capacity(pool_size);
-    for i in 0..pool_size {
+    let config = config.normalized();
+    let mut connections = Vec::with_capacity(config.min_size);
+    for i in 0..config.min_size {
       connections.push(
         connection
           .try_clone()
           .map_err(|e| format!("connection clone {i}: {e}"))?,
       );
     }
+    let base = connection
+      .try_clone()
+      .map_err(|e| format!("base connection clone: {e}"))?;
 
-    let mut senders = Vec::with_capacity(pool_size);
-    let mut workers = Vec::with_capacity(pool_size);
+    let (queue_tx, queue_rx) = unbounded();
+    let pool = Arc::new(PoolState {
+      config,
+      queue: queue_rx,
+      live: AtomicUsize::new(config.min_size),
+      idle: AtomicUsize::new(0),
+      spawned: AtomicUsize::new(config.min_size),
+      cache: CacheCounters::default(),
+    });
+
+    let mut senders = Vec::with_capacity(config.min_size);
+    let mut workers = Vec::with_capacity(config.min_size);
     for (i, conn) in connections.into_iter().enumerate() {
       let (sender, receiver): (Sender<QueryRequest>, Receiver<QueryRequest>) =
         unbounded();
       senders.push(sender);
+      let pool = Arc::clone(&pool);
       let handle = thread::Builder::new()
         .name(format!("trex-executor-{i}"))
-        .spawn(move || worker_loop(conn, receiver))
+        .spawn(move || core_worker_loop(conn, receiver, pool))
         .map_err(|e| format!("spawn worker {i}: {e}"))?;
       workers.push(Worker { _handle: handle });
     }
 
+    let (grow, grow_requests) = unbounded();
+    let spawner_pool = Arc::clone(&pool);
+    let handle = thread::Builder::new()
+      .name("trex-executor-spawner".to_string())
+      .spawn(move || spawner_loop(base, grow_requests, spawner_pool))
+      .map_err(|e| format!("spawn executor spawner: {e}"))?;
+    workers.push(Worker { _handle: handle });
+
     Ok(Self {
       senders,
+      queue: queue_tx,
+      grow,
+      pool,
       workers,
       next_worker: AtomicUsize::new(0),
     })
   }
 
-  /// Returns next worker index via round-robin.
+  /// Returns next worker index via round-robin. Only core workers are
+  /// handed out, so a pinned session's connection is never closed.
   pub fn next_worker_id(&self) -> usize {
     self.next_worker.fetch_add(1, Ordering::Relaxed) % self.senders.len()
   }
@@ -79,58 +258,298 @@ impl QueryExecutor {
     sql: String,
     params_json: String,
   ) -> std::sync::mpsc::Receiver<QueryResult> {
-    let (response_tx, response_rx) = std::sync::mpsc::sync_channel(1);
-
     let sender = &self.senders[worker_id % self.senders.len()];
-    if let Err(e) = sender.send(QueryRequest {
-      database,
-      sql,
-      params_json,
-      response_tx,
-    }) {
-      let (tx, rx) = std::sync::mpsc::sync_channel(1);
-      let _ = tx.send(QueryResult::Error(format!("executor closed: {e}")));
-      return rx;
-    }
-
-    response_rx
+    send_request(sender, database, sql, params_json)
   }
 
-  /// Sends request via round-robin (unpinned).
+  /// Sends request to the shared queue (unpinned), growing the pool if it
+  /// is backing up.
   pub fn submit(
     &self,
     database: String,
     sql: String,
     params_json: String,
   ) -> std::sync::mpsc::Receiver<QueryResult> {
-    let worker_id = self.next_worker_id();
-    self.submit_to(worker_id, database, sql, params_json)
+    let rx = send_request(&self.queue, database, sql, params_json);
+    self.maybe_grow();
+    rx
   }
 
+  /// Connections currently open, core and elastic.
   pub fn pool_size(&self) -> usize {
-    self.senders.len()
+    self.pool.live.load(Ordering::Acquire)
+  }
+
+  pub fn config(&self) -> PoolConfig {
+    self.pool.config
+  }
+
+  pub fn cache_stats(&self) -> CacheStats {
+    self.pool.cache.snapshot()
+  }
+
+  /// Start an elastic worker when more requests are queued than workers
+  /// are waiting, unless the pool is already at `max_size`.
+  fn maybe_grow(&self) {
+    let pool = &self.pool;
+    if pool.queue.len() <= pool.idle.load(Ordering::Acquire) {
+      return;
+    }
+    let reserved =
+      pool
+        .live
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |live| {
+          (live < pool.config.max_size).then_some(live + 1)
+        });
+    if reserved.is_err() {
+      return;
+    }
+    if self.grow.send(()).is_err() {
+      pool.live.fetch_sub(1, Ordering::AcqRel);
+      warn!("executor spawner has stopped, not growing the pool");
+    }
+  }
+}
+
+fn send_request(
+  sender: &Sender<QueryRequest>,
+  database: String,
+  sql: String,
+  params_json: String,
+) -> std::sync::mpsc::Receiver<QueryResult> {
+  let (response_tx, response_rx) = std::sync::mpsc::sync_channel(1);
+
+  if let Err(e) = sender.send(QueryRequest {
+    database,
+    sql,
+    params_json,
+    response_tx,
+  }) {
+    let (tx, rx) = std::sync::mpsc::sync_channel(1);
+    let _ = tx.send(QueryResult::Error(format!("executor closed: {e}")));
+    return rx;
+  }
+
+  response_rx
+}
+
+/// Owns the connection elastic workers are cloned from and starts one per
+/// request. Every request was already counted in `live`, which is given
+/// back when the worker cannot be started. Ends with the executor.
+fn spawner_loop(
+  base: Connection,
+  requests: Receiver<()>,
+  pool: Arc<PoolState>,
+) {
+  for () in requests {
+    if let Err(e) = spawn_elastic_worker(&base, Arc::clone(&pool)) {
+      pool.live.fetch_sub(1, Ordering::AcqRel);
+      warn!(error = %e, "failed to grow executor pool");
+    }
   }
 }
 
-fn worker_loop(conn: Connection, receiver: Receiver<QueryRequest>) {
-  while let Ok(req) = receiver.recv() {
-    let result = panic::catch_unwind(AssertUnwindSafe(|| {
-      execute_query(&conn, &req.database, &req.sql, &req.params_json)
-    }));
-    let query_result = match result {
-      Ok(r) => r,
-      Err(panic_err) => {
-        let msg = crate::extract_panic_message(panic_err);
-        warn!(error = %msg, "query panicked");
-        QueryResult::Error(format!("query panicked: {msg}"))
+/// Opens a connection for one more worker.
+fn spawn_elastic_worker(
+  base: &Connection,
+  pool: Arc<PoolState>,
+) -> Result<(), String> {
+  let conn = base
+    .try_clone()
+    .map_err(|e| format!("connection clone: {e}"))?;
+  let i = pool.spawned.fetch_add(1, Ordering::Relaxed);
+  thread::Builder::new()
+    .name(format!("trex-executor-{i}"))
+    .spawn(move || elastic_worker_loop(conn, pool))
+    .map_err(|e| format!("spawn worker {i}: {e}"))?;
+  debug!(worker = i, "executor pool grew");
+  Ok(())
+}
+
+fn core_worker_loop(
+  conn: Connection,
+  pinned: Receiver<QueryRequest>,
+  pool: Arc<PoolState>,
+) {
+  let mut cache = StatementCache::new(&conn, &pool);
+  loop {
+    pool.idle.fetch_add(1, Ordering::AcqRel);
+    let next = select! {
+      recv(pinned) -> req => req,
+      recv(pool.queue) -> req => req,
+    };
+    pool.idle.fetch_sub(1, Ordering::AcqRel);
+    match next {
+      Ok(req) => handle_request(&mut cache, req),
+      Err(_) => break,
+    }
+  }
+}
+
+fn elastic_worker_loop(conn: Connection, pool: Arc<PoolState>) {
+  let mut cache = StatementCache::new(&conn, &pool);
+  loop {
+    pool.idle.fetch_add(1, Ordering::AcqRel);
+    let next = pool.queue.recv_timeout(pool.config.idle_timeout);
+    pool.idle.fetch_sub(1, Ordering::AcqRel);
+    match next {
+      Ok(req) => handle_request(&mut cache, req),
+      Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+        break
       }
+    }
+  }
+  drop(cache);
+  pool.live.fetch_sub(1, Ordering::AcqRel);
+  debug!("executor pool shrank");
+  drop(conn);
+}
+
+/// One worker's prepared statements, least recently used evicted first.
+struct StatementCache<'a> {
+  conn: &'a Connection,
+  counters: &'a CacheCounters,
+  capacity: usize,
+  entries: HashMap<String, CachedStatement<'a>>,
+  tick: u64,
+}
+
+struct CachedStatement<'a> {
+  stmt: Statement<'a>,
+  last_used: u64,
+}
+
+impl<'a> StatementCache<'a> {
+  fn new(conn: &'a Connection, pool: &'a PoolState) -> Self {
+    Self {
+      conn,
+      counters: &pool.cache,
+      capacity: pool.config.statement_cache_size,
+      entries: HashMap::new(),
+      tick: 0,
+    }
+  }
+
+  /// Runs `f` on the statement for `(database, sql)`, preparing it on a
+  /// miss. A statement whose execution fails is dropped from the cache.
+  fn with_statement<T, E>(
+    &mut self,
+    database: &str,
+    sql: &str,
+    f: impl FnOnce(&mut Statement<'a>) -> Result<T, E>,
+  ) -> Result<Result<T, E>, duckdb::Error> {
+    if self.capacity == 0 {
+      let mut stmt = self.conn.prepare(sql)?;
+      return Ok(f(&mut stmt));
+    }
+
+    let key = match normalize_sql(sql) {
+      Some(normalized) => format!("{database}\0{normalized}"),
+      // A second separator keeps raw text apart from any normalized form.
+      None => format!("{database}\0\0{sql}"),
     };
-    let _ = req.response_tx.send(query_result);
+    self.tick += 1;
+    if let Some(entry) = self.entries.get_mut(&key) {
+      self.counters.hits.fetch_add(1, Ordering::Relaxed);
+      entry.last_used = self.tick;
+    } else {
+      self.counters.misses.fetch_add(1, Ordering::Relaxed);
+      let stmt = self.conn.prepare(sql)?;
+      if self.entries.len() >= self.capacity {
+        self.evict_oldest();
+      }
+      self.entries.insert(
+        key.clone(),
+        CachedStatement {
+          stmt,
+          last_used: self.tick,
+        },
+      );
+      self.counters.entries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let entry = self.entries.get_mut(&key).expect("statement just cached");
+    let result = f(&mut entry.stmt);
+    if result.is_err() {
+      self.entries.remove(&key);
+      self.counters.entries.fetch_sub(1, Ordering::Relaxed);
+    }
+    Ok(result)
+  }
+
+  fn evict_oldest(&mut self) {
+    let oldest = self
+      .entries
+      .iter()
+      .min_by_key(|(_, entry)| entry.last_used)
+      .map(|(key, _)| key.clone());
+    if let Some(key) = oldest {
+      self.entries.remove(&key);
+      self.counters.evictions.fetch_add(1, Ordering::Relaxed);
+      self.counters.entries.fetch_sub(1, Ordering::Relaxed);
+    }
   }
 }
 
+/// `sql` rebuilt from sqlparser's tokens: each run of whitespace and
+/// comments becomes one space and unquoted keywords are upper-cased, while
+/// every other token keeps its text. The space is kept rather than dropped
+/// so `E'x'` and `E 'x'` stay apart. `None` when `sql` doesn't tokenize.
+fn normalize_sql(sql: &str) -> Option<String> {
+  let tokens = Tokenizer::new(&DuckDbDialect {}, sql)
+    .with_unescape(false)
+    .tokenize()
+    .ok()?;
+  let mut normalized = String::with_capacity(sql.len());
+  let mut space = false;
+  for token in tokens {
+    match token {
+      Token::Whitespace(_) => {
+        space = true;
+        continue;
+      }
+      _ if space && !normalized.is_empty() => normalized.push(' '),
+      _ => {}
+    }
+    space = false;
+    match token {
+      Token::Word(word)
+        if word.quote_style.is_none() && word.keyword != Keyword::NoKeyword =>
+      {
+        normalized.push_str(&word.value.to_ascii_uppercase());
+      }
+      token => normalized.push_str(&token.to_string()),
+    }
+  }
+  Some(normalized)
+}
+
+impl Drop for StatementCache<'_> {
+  fn drop(&mut self) {
+    self
+      .counters
+      .entries
+      .fetch_sub(self.entries.len() as u64, Ordering::Relaxed);
+  }
+}
+
+fn handle_request(cache: &mut StatementCache<'_>, req: QueryRequest) {
+  let result = panic::catch_unwind(AssertUnwindSafe(|| {
+    execute_query(cache, &req.database, &req.sql, &req.params_json)
+  }));
+  let query_result = match result {
+    Ok(r) => r,
+    Err(panic_err) => {
+      let msg = crate::extract_panic_message(panic_err);
+      warn!(error = %msg, "query panicked");
+      QueryResult::Error(format!("query panicked: {msg}"))
+    }
+  };
+  let _ = req.response_tx.send(query_result);
+}
+
 fn execute_query(
-  conn: &Connection,
+  cache: &mut StatementCache<'_>,
   database: &str,
   sql: &str,
   params_json: &str,
@@ -138,7 +557,7 @@ fn execute_query(
   use duckdb::arrow::record_batch::RecordBatch;
   use duckdb::params_from_iter;
 
-  if let Err(e) = conn.execute(&format!("USE {database}"), []) {
+  if let Err(e) = cache.conn.execute(&format!("USE {database}"), []) {
     warn!(database, error = %e, "failed to switch database");
   }
 
@@ -151,14 +570,16 @@ fn execute_query(
     Err(e) => return QueryResult::Error(format!("param parse: {e}")),
   };
 
-  match conn.prepare(sql) {
-    Ok(mut stmt) => match stmt.query_arrow(params_from_iter(params.iter())) {
-      Ok(iter) => {
-        let batches: Vec<RecordBatch> = iter.collect();
-        QueryResult::Success(crate::record_batches_to_json(&batches))
-      }
-      Err(e) => QueryResult::Error(format!("query exec: {e}")),
-    },
+  let executed = cache.with_statement(database, sql, |stmt| {
+    stmt
+      .query_arrow(params_from_iter(params.iter()))
+      .map(|iter| iter.collect::<Vec<RecordBatch>>())
+  });
+  match executed {
+    Ok(Ok(batches)) => {
+      QueryResult::Success(crate::record_batches_to_json(&batches))
+    }
+    Ok(Err(e)) => QueryResult::Error(format!("query exec: {e}")),
     Err(e) => {
       use std::error::Error as StdError;
       let mut msg = e.to_string();
@@ -171,3 +592,215 @@ fn execute_query(
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn run(executor: &QueryExecutor, sql: &str) -> Result<String, String> {
+    match executor
+      .submit_to(0, "memory".into(), sql.into(), "[]".into())
+      .recv()
+      .unwrap()
+    {
+      QueryResult::Success(s) => Ok(s),
+      QueryResult::Error(e) => Err(e),
+    }
+  }
+
+  fn executor_with_cache(size: usize) -> QueryExecutor {
+    let conn = Connection::open_in_memory().unwrap();
+    let config = PoolConfig {
+      statement_cache_size: size,
+      ..PoolConfig::fixed(1)
+    };
+    QueryExecutor::new(&conn, config).unwrap()
+  }
+
+  #[test]
+  fn normalized_sql_ignores_layout_but_not_literals() {
+    let key = |sql| normalize_sql(sql).unwrap();
+    assert_eq!(
+      key("select 1 as a,\n  2 as b -- note"),
+      key("SELECT /* c */ 1 AS a, 2 AS b")
+    );
+    assert_ne!(key("SELECT '  x  ' AS s"), key("SELECT ' x ' AS s"));
+    assert_ne!(
+      key("SELECT 1 AS a -- , 2 AS b"),
+      key("SELECT 1 AS a, 2 AS b")
+    );
+    assert_ne!(key(r#"SELECT "A" FROM t"#), key(r#"SELECT "a" FROM t"#));
+    assert_ne!(key(r"SELECT E'a\nb'"), key(r"SELECT E AS 'a\nb'"));
+    assert_ne!(key("SELECT E'x'"), key("SELECT E 'x'"));
+    assert_eq!(normalize_sql("SELECT 'unterminated"), None);
+  }
+
+  #[test]
+  fn equivalent_statements_hit_the_cache() {
+    let executor = executor_with_cache(8);
+    let first = run(&executor, "SELECT 1 AS a, 'x  y' AS b").unwrap();
+    let second =
+      run(&executor, "select 1 as a,\n  'x  y' as b -- same\n").unwrap();
+    assert_eq!(first, second);
+    assert_eq!(
+      executor.cache_stats(),
+      CacheStats {
+        hits: 1,
+        misses: 1,
+        evictions: 0,
+        entries: 1
+      }
+    );
+
+    // A different literal or a comment hiding a column is a new statement.
+    run(&executor, "SELECT 1 AS a, 'x y' AS b").unwrap();
+    let hidden = run(&executor, "SELECT 1 AS a -- , 'x  y' AS b").unwrap();
+    assert_ne!(hidden, first);
+    let stats = executor.cache_stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 3, 3));
+  }
+
+  #[test]
+  fn least_recently_used_statement_is_evicted() {
+    let executor = executor_with_cache(2);
+    run(&executor, "SELECT 1").unwrap();
+    run(&executor, "SELECT 2").unwrap();
+    run(&executor, "SELECT 1").unwrap();
+    run(&executor, "SELECT 3").unwrap();
+    assert_eq!(
+      executor.cache_stats(),
+      CacheStats {
+        hits: 1,
+        misses: 3,
+        evictions: 1,
+        entries: 2
+      }
+    );
+
+    // `SELECT 2` was the one dropped; `SELECT 1` and `SELECT 3` stay.
+    run(&executor, "SELECT 1").unwrap();
+    run(&executor, "SELECT 3").unwrap();
+    assert_eq!(executor.cache_stats().hits, 3);
+    run(&executor, "SELECT 2").unwrap();
+    let stats = executor.cache_stats();
+    assert_eq!((stats.misses, stats.evictions), (4, 2));
+  }
+
+  #[test]
+  fn disabled_cache_prepares_every_time() {
+    let executor = executor_with_cache(0);
+    run(&executor, "SELECT 1").unwrap();
+    run(&executor, "SELECT 1").unwrap();
+    assert_eq!(executor.cache_stats(), CacheStats::default());
+  }
+
+  /// Long enough in a debug build that submitted queries queue up.
+  const SLOW_SQL: &str = "SELECT sum(i) AS s FROM range(2000000) t(i)";
+
+  fn elastic(min_size: usize, max_size: usize) -> PoolConfig {
+    PoolConfig {
+      min_size,
+      max_size,
+      idle_timeout: Duration::from_millis(100),
+      ..PoolConfig::fixed(min_size)
+    }
+  }
+
+  fn submit_slow(
+    executor: &QueryExecutor,
+    n: usize,
+  ) -> (Vec<std::sync::mpsc::Receiver<QueryResult>>, usize) {
+    let mut largest = 0;
+    let pending = (0..n)
+      .map(|_| {
+        let rx = executor.submit("memory".into(), SLOW_SQL.into(), "[]".into());
+        largest = largest.max(executor.pool_size());
+        rx
+      })
+      .collect();
+    (pending, largest)
+  }
+
+  fn wait_all(pending: Vec<std::sync::mpsc::Receiver<QueryResult>>) {
+    for rx in pending {
+      assert!(matches!(rx.recv().unwrap(), QueryResult::Success(_)));
+    }
+  }
+
+  #[test]
+  fn normalized_clamps_sizes() {
+    let clamp = |min_size, max_size| {
+      let config = PoolConfig {
+        min_size,
+        max_size,
+        ..PoolConfig::fixed(min_size)
+      }
+      .normalized();
+      (config.min_size, config.max_size)
+    };
+    assert_eq!(clamp(0, 0), (1, 1));
+    assert_eq!(clamp(4, 2), (4, 4));
+    assert_eq!(clamp(2, 1000), (2, MAX_POOL_CEILING));
+    assert_eq!(clamp(1000, 1000), (MAX_POOL_CEILING, MAX_POOL_CEILING));
+  }
+
+  #[test]
+  fn from_env_grows_by_default() {
+    if env::var_os("TREX_CONNECTION_POOL_MAX").is_some() {
+      return;
+    }
+    let config = PoolConfig::from_env(4);
+    assert_eq!((config.min_size, config.max_size), (4, 8));
+    let config = PoolConfig::from_env(40).normalized();
+    assert_eq!(config.max_size, MAX_POOL_CEILING);
+  }
+
+  #[test]
+  fn fixed_pool_never_grows() {
+    let conn = Connection::open_in_memory().unwrap();
+    let executor = QueryExecutor::new(&conn, PoolConfig::fixed(1)).unwrap();
+    let (pending, largest) = submit_slow(&executor, 8);
+    assert_eq!(largest, 1);
+    wait_all(pending);
+    assert_eq!(executor.pool_size(), 1);
+  }
+
+  #[test]
+  fn idle_pool_does_not_grow() {
+    let conn = Connection::open_in_memory().unwrap();
+    let executor = QueryExecutor::new(&conn, elastic(2, 4)).unwrap();
+    while executor.pool.idle.load(Ordering::Acquire) < 2 {
+      thread::sleep(Duration::from_millis(5));
+    }
+    // Queries run one at a time never queue behind the idle core workers.
+    for _ in 0..4 {
+      let rx = executor.submit("memory".into(), "SELECT 1".into(), "[]".into());
+      rx.recv().unwrap();
+    }
+    assert_eq!(executor.pool_size(), 2);
+  }
+
+  #[test]
+  fn queued_queries_grow_the_pool_up_to_max() {
+    let conn = Connection::open_in_memory().unwrap();
+    let executor = QueryExecutor::new(&conn, elastic(1, 3)).unwrap();
+    let (pending, largest) = submit_slow(&executor, 24);
+    assert_eq!(largest, 3);
+    wait_all(pending);
+  }
+
+  #[test]
+  fn idle_elastic_workers_close_down_to_min() {
+    let conn = Connection::open_in_memory().unwrap();
+    let executor = QueryExecutor::new(&conn, elastic(1, 3)).unwrap();
+    let (pending, largest) = submit_slow(&executor, 24);
+    assert!(largest > 1);
+    wait_all(pending);
+
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while executor.pool_size() > 1 && std::time::Instant::now() < deadline {
+      thread::sleep(Duration::from_millis(20));
+    }
+    assert_eq!(executor.pool_size(), 1);
+  }
+}
@@ -5,10 +5,12 @@ use deno_facade::{generate_binary_eszip, EmitterFactory, Metadata};
 use serde::Deserialize;
 use std::fs::File;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread;
 
+use crate::tree_shake;
+
 #[derive(Debug, Clone, Deserialize, Default)]
 pub struct BundleOptions {
   #[serde(default)]
@@ -22,6 +24,17 @@ pub struct BundleOptions {
 
   #[serde(default)]
   pub timeout_sec: Option<u64>,
+
+  /// Project directory. A relative entrypoint is resolved against it, and
+  /// with `tree_shake` every local module must live below it. Defaults to
+  /// the entrypoint's directory.
+  #[serde(default)]
+  pub root: Option<String>,
+
+  /// Leave the modules below `root` that the entrypoint never imports out
+  /// of the bundle, so `static_patterns` can't pull them in.
+  #[serde(default)]
+  pub tree_shake: bool,
 }
 
 impl BundleOptions {
@@ -44,12 +57,106 @@ pub fn create_bundle_sync(
   output: &str,
   options: Option<BundleOptions>,
 ) -> Result<String> {
+  let options = options.unwrap_or_default();
+  let output = output.to_string();
+
+  let (entrypoint, root) =
+    resolve_project(entrypoint, options.root.as_deref())?;
+  let staged = if options.tree_shake {
+    Some(tree_shake::stage(&root, &entrypoint)?)
+  } else {
+    None
+  };
+  let bundle_entrypoint = staged
+    .as_ref()
+    .map_or(&entrypoint, |staged| &staged.entrypoint);
+
+  let bytes = build_eszip(&bundle_entrypoint.display().to_string(), &options)?;
+
+  let mut file = File::create(&output)
+    .with_context(|| format!("Failed to create output file: {}", output))?;
+
+  file
+    .write_all(&bytes)
+    .with_context(|| format!("Failed to write bundle to: {}", output))?;
+
+  Ok(match staged {
+    Some(staged) => format!(
+      "Bundle created successfully: {} ({} bytes, {})",
+      output,
+      bytes.len(),
+      staged.report.summary()
+    ),
+    None => format!(
+      "Bundle created successfully: {} ({} bytes)",
+      output,
+      bytes.len()
+    ),
+  })
+}
+
+/// Canonical entrypoint and project root for a bundle. A relative
+/// entrypoint is taken relative to `root` when one is given.
+fn resolve_project(
+  entrypoint: &str,
+  root: Option<&str>,
+) -> Result<(PathBuf, PathBuf)> {
+  let root = root
+    .map(|root| {
+      Path::new(root)
+        .canonicalize()
+        .with_context(|| format!("Project root does not exist: {}", root))
+    })
+    .transpose()?;
+  if let Some(root) = &root {
+    if !root.is_dir() {
+      bail!("Project root is not a directory: {}", root.display());
+    }
+  }
+
+  let path = match &root {
+    Some(root) if Path::new(entrypoint).is_relative() => root.join(entrypoint),
+    _ => PathBuf::from(entrypoint),
+  };
+  if !path.is_file() {
+    bail!("Entrypoint path is not a file: {}", path.display());
+  }
+  let path = path
+    .canonicalize()
+    .context("Failed to canonicalize entrypoint path")?;
+
+  let root = match root {
+    Some(root) => root,
+    None => path
+      .parent()
+      .map(Path::to_path_buf)
+      .context("Entrypoint has no parent directory")?,
+  };
+  if !path.starts_with(&root) {
+    bail!(
+      "Entrypoint {} is outside the project root {}",
+      path.display(),
+      root.display()
+    );
+  }
+  Ok((path, root))
+}
+
+/// Build the module graph of `entrypoint` without writing a bundle, so a
+/// syntax or resolution error is found before a server is started on it.
+pub fn check_entrypoint(entrypoint: &str) -> Result<()> {
+  let options = BundleOptions {
+    no_module_cache: true,
+    ..Default::default()
+  };
+  build_eszip(entrypoint, &options).map(|_| ())
+}
+
+fn build_eszip(entrypoint: &str, options: &BundleOptions) -> Result<Vec<u8>> {
   // Required before any TLS operation; rustls panics if no provider is installed.
   let _ = rustls::crypto::ring::default_provider().install_default();
 
-  let options = options.unwrap_or_default();
   let entrypoint = entrypoint.to_string();
-  let output = output.to_string();
 
   let entrypoint_path = PathBuf::from(&entrypoint);
   if !entrypoint_path.exists() {
@@ -131,20 +238,7 @@ pub fn create_bundle_sync(
     })
   });
 
-  let bytes = handle
+  handle
     .join()
-    .map_err(|_| anyhow::anyhow!("Bundle thread panicked"))??;
-
-  let mut file = File::create(&output)
-    .with_context(|| format!("Failed to create output file: {}", output))?;
-
-  file
-    .write_all(&bytes)
-    .with_context(|| format!("Failed to write bundle to: {}", output))?;
-
-  Ok(format!(
-    "Bundle created successfully: {} ({} bytes)",
-    output,
-    bytes.len()
-  ))
+    .map_err(|_| anyhow::anyhow!("Bundle thread panicked"))?
 }
@@ -0,0 +1,563 @@
+//! Listening sockets of a watched trex runtime server, kept across reloads.
+//!
+//! Servers started with `watch: true` get a [`Front`], which binds the
+//! configured address once. Each instance of the server listens on loopback
+//! ports of its own and the front forwards what it accepts to the current
+//! instance: HTTP/1.1 requests over kept-alive connections, TLS connections
+//! byte for byte. A reload starts the new instance next to the old one and
+//! only points the front at it once it listens, so no connection is refused
+//! during the swap and an instance that fails to start leaves the old one
+//! serving. Servers without `watch` bind their address directly.
+//!
+//! The instances only see the front as their peer; the client's address is
+//! passed on in `x-forwarded-for`. Every HTTP request of a watched server
+//! passes through here, so this is also where its status and latency are
+//! reported to [`trex_core::metrics`].
+
+use anyhow::{Context, Result};
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{HeaderMap, HeaderName, HeaderValue, CONNECTION, UPGRADE};
+use hyper::{Request, Response, StatusCode, Uri};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use std::net::{
+  Ipv4Addr, SocketAddr, TcpListener as StdTcpListener,
+  TcpStream as StdTcpStream,
+};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use trex_core::metrics;
+
+/// How long closing a front waits for its connections to be torn down.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long a new instance gets to start accepting on its ports.
+const LISTEN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Headers a proxy must not forward, on top of those `Connection` lists.
+const HOP_BY_HOP: [&str; 6] = [
+  "connection",
+  "keep-alive",
+  "proxy-connection",
+  "te",
+  "trailer",
+  "upgrade",
+];
+
+/// Idle connections to an instance are closed after this long.
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+type ProxyBody = BoxBody<Bytes, hyper::Error>;
+
+/// Loopback ports one server instance listens on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backend {
+  pub http: SocketAddr,
+  pub tls: Option<SocketAddr>,
+}
+
+impl Backend {
+  /// Pick free loopback ports for an instance, with a TLS port if `tls`.
+  pub fn pick(tls: bool) -> Result<Self> {
+    Ok(Self {
+      http: free_loopback_addr()?,
+      tls: if tls {
+        Some(free_loopback_addr()?)
+      } else {
+        None
+      },
+    })
+  }
+}
+
+/// A loopback address nothing listens on right now. The port is released
+/// again before the instance binds it, which leaves a short window for
+/// another process to take it; [`wait_until_listening`] catches the
+/// instance that then fails to listen, so it can be retried elsewhere.
+fn free_loopback_addr() -> Result<SocketAddr> {
+  let listener = StdTcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+    .context("Failed to find a free loopback port")?;
+  listener
+    .local_addr()
+    .context("Failed to read the loopback port")
+}
+
+/// Wait until every port of `backend` accepts connections. Returns `false`
+/// if `exited` reports that the instance went away first, or if it is not
+/// listening after [`LISTEN_TIMEOUT`].
+pub fn wait_until_listening(
+  backend: Backend,
+  exited: impl Fn() -> bool,
+) -> bool {
+  let deadline = Instant::now() + LISTEN_TIMEOUT;
+  let ports = std::iter::once(backend.http).chain(backend.tls);
+  for addr in ports {
+    while StdTcpStream::connect_timeout(&addr, Duration::from_millis(100))
+      .is_err()
+    {
+      if exited() || Instant::now() >= deadline {
+        return false;
+      }
+      std::thread::sleep(Duration::from_millis(20));
+    }
+  }
+  true
+}
+
+/// The instance requests go to, with the connections kept open to it.
+/// Replacing it drops the pool, so the old instance is left with only the
+/// requests still in flight.
+#[derive(Clone)]
+struct Upstream {
+  backend: Backend,
+  client: Client<HttpConnector, Incoming>,
+}
+
+impl Upstream {
+  fn new(backend: Backend) -> Self {
+    let mut connector = HttpConnector::new();
+    connector.set_nodelay(true);
+    let client = Client::builder(TokioExecutor::new())
+      .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+      .build(connector);
+    Self { backend, client }
+  }
+}
+
+type SharedUpstream = Arc<RwLock<Option<Upstream>>>;
+
+pub struct Front {
+  addr: SocketAddr,
+  upstream: SharedUpstream,
+  runtime: Mutex<Option<tokio::runtime::Runtime>>,
+  accept_loops: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Front {
+  /// Bind `addr`, and `tls_addr` when given. Connections accepted before
+  /// [`set_backend`](Self::set_backend) is called are answered with 503
+  /// (HTTP) or closed (TLS).
+  pub fn bind(addr: SocketAddr, tls_addr: Option<SocketAddr>) -> Result<Self> {
+    let http_listener = bind_std(addr)?;
+    let tls_listener = tls_addr.map(bind_std).transpose()?;
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+      .worker_threads(2)
+      .thread_name("trex-front")
+      .enable_all()
+      .build()
+      .context("Failed to create the front runtime")?;
+    let upstream: SharedUpstream = Arc::new(RwLock::new(None));
+
+    let mut accept_loops = Vec::new();
+    {
+      let _guard = runtime.enter();
+      let listener = TcpListener::from_std(http_listener)?;
+      accept_loops.push(runtime.spawn(accept_http(
+        listener,
+        addr,
+        upstream.clone(),
+      )));
+      if let Some(listener) = tls_listener {
+        let listener = TcpListener::from_std(listener)?;
+        accept_loops
+          .push(runtime.spawn(accept_tls(listener, upstream.clone())));
+      }
+    }
+
+    Ok(Self {
+      addr,
+      upstream,
+      runtime: Mutex::new(Some(runtime)),
+      accept_loops: Mutex::new(accept_loops),
+    })
+  }
+
+  pub fn addr(&self) -> SocketAddr {
+    self.addr
+  }
+
+  /// Send connections and requests accepted from now on to `backend`.
+  /// Requests already forwarded finish on the instance they went to.
+  pub fn set_backend(&self, backend: Backend) {
+    // The pool's connections are opened on the front's runtime.
+    let upstream = {
+      let runtime = self.runtime.lock().unwrap_or_else(|p| p.into_inner());
+      let Some(runtime) = runtime.as_ref() else {
+        return;
+      };
+      let _guard = runtime.enter();
+      Upstream::new(backend)
+    };
+    *self.upstream.write().unwrap_or_else(|p| p.into_inner()) = Some(upstream);
+  }
+
+  /// Stop accepting connections and release the listening sockets.
+  /// Connections already accepted are kept until [`close`](Self::close).
+  pub fn stop_accepting(&self) {
+    let accept_loops = std::mem::take(
+      &mut *self.accept_loops.lock().unwrap_or_else(|p| p.into_inner()),
+    );
+    for accept_loop in &accept_loops {
+      accept_loop.abort();
+    }
+    // An aborted loop drops its listener once the runtime gets to it; wait
+    // for that so the address can be bound again right away.
+    let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+    while accept_loops.iter().any(|l| !l.is_finished())
+      && Instant::now() < deadline
+    {
+      std::thread::sleep(Duration::from_millis(5));
+    }
+  }
+
+  /// Release the sockets and drop every connection still open.
+  pub fn close(&self) {
+    self.stop_accepting();
+    let runtime = self
+      .runtime
+      .lock()
+      .unwrap_or_else(|p| p.into_inner())
+      .take();
+    if let Some(runtime) = runtime {
+      runtime.shutdown_timeout(SHUTDOWN_TIMEOUT);
+    }
+  }
+}
+
+impl Drop for Front {
+  fn drop(&mut self) {
+    self.close();
+  }
+}
+
+fn bind_std(addr: SocketAddr) -> Result<StdTcpListener> {
+  let listener = StdTcpListener::bind(addr)
+    .with_context(|| format!("Failed to bind {}", addr))?;
+  listener.set_nonblocking(true)?;
+  Ok(listener)
+}
+
+fn current_upstream(upstream: &RwLock<Option<Upstream>>) -> Option<Upstream> {
+  upstream.read().unwrap_or_else(|p| p.into_inner()).clone()
+}
+
+/// Serve HTTP on `listener`, which is bound to `addr`, the address the
+/// server's metrics are registered under.
+async fn accept_http(
+  listener: TcpListener,
+  addr: SocketAddr,
+  upstream: SharedUpstream,
+) {
+  loop {
+    let (stream, peer) = match listener.accept().await {
+      Ok(accepted) => accepted,
+      Err(e) => {
+        eprintln!("[TREX-EXT] Accept failed: {}", e);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        continue;
+      }
+    };
+    let _ = stream.set_nodelay(true);
+    let upstream = upstream.clone();
+    tokio::spawn(async move {
+      let service = hyper::service::service_fn(move |req| {
+        let upstream = current_upstream(&upstream);
+        async move {
+          let started = Instant::now();
+          let resp = forward(upstream, peer, req).await?;
+          metrics::record_request(
+            addr,
+            resp.status().as_u16(),
+            started.elapsed(),
+          );
+          Ok::<_, hyper::Error>(resp)
+        }
+      });
+      let _ = hyper::server::conn::http1::Builder::new()
+        .serve_connection(TokioIo::new(stream), service)
+        .with_upgrades()
+        .await;
+    });
+  }
+}
+
+async fn accept_tls(listener: TcpListener, upstream: SharedUpstream) {
+  loop {
+    let Ok((mut stream, _)) = listener.accept().await else {
+      tokio::time::sleep(Duration::from_millis(50)).await;
+      continue;
+    };
+    let Some(tls) = current_upstream(&upstream).and_then(|u| u.backend.tls)
+    else {
+      continue;
+    };
+    tokio::spawn(async move {
+      if let Ok(mut upstream) = TcpStream::connect(tls).await {
+        let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+      }
+    });
+  }
+}
+
+/// Forward one request to the current instance over one of its pooled
+/// connections. An upgraded connection, e.g. a WebSocket, is spliced
+/// through.
+async fn forward(
+  upstream: Option<Upstream>,
+  peer: SocketAddr,
+  mut req: Request<Incoming>,
+) -> Result<Response<ProxyBody>, hyper::Error> {
+  let Some(upstream) = upstream else {
+    return Ok(error_response(StatusCode::SERVICE_UNAVAILABLE));
+  };
+  append_forwarded_for(&mut req, peer);
+  let path = req
+    .uri()
+    .path_and_query()
+    .map_or("/", |p| p.as_str())
+    .to_string();
+  let Ok(uri) =
+    format!("http://{}{}", upstream.backend.http, path).parse::<Uri>()
+  else {
+    return Ok(error_response(StatusCode::BAD_REQUEST));
+  };
+  *req.uri_mut() = uri;
+  let wants_upgrade = req.headers().contains_key(UPGRADE);
+  if !wants_upgrade {
+    strip_hop_by_hop(req.headers_mut());
+  }
+  let client_upgrade = wants_upgrade.then(|| hyper::upgrade::on(&mut req));
+
+  let mut resp = match upstream.client.request(req).await {
+    Ok(resp) => resp,
+    Err(_) => return Ok(error_response(StatusCode::BAD_GATEWAY)),
+  };
+  if resp.status() == StatusCode::SWITCHING_PROTOCOLS {
+    if let Some(client_upgrade) = client_upgrade {
+      let backend_upgrade = hyper::upgrade::on(&mut resp);
+      tokio::spawn(async move {
+        if let (Ok(client), Ok(upstream)) =
+          tokio::join!(client_upgrade, backend_upgrade)
+        {
+          let _ = tokio::io::copy_bidirectional(
+            &mut TokioIo::new(client),
+            &mut TokioIo::new(upstream),
+          )
+          .await;
+        }
+      });
+    }
+  }
+  if resp.status() != StatusCode::SWITCHING_PROTOCOLS {
+    strip_hop_by_hop(resp.headers_mut());
+  }
+  Ok(resp.map(|body| body.boxed()))
+}
+
+/// Drop the headers that only describe one connection, e.g. a client's
+/// `Connection: close`, which would otherwise close the pooled connection.
+fn strip_hop_by_hop(headers: &mut HeaderMap) {
+  let listed: Vec<HeaderName> = headers
+    .get_all(CONNECTION)
+    .iter()
+    .filter_map(|v| v.to_str().ok())
+    .flat_map(|v| v.split(','))
+    .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+    .collect();
+  for name in listed {
+    headers.remove(name);
+  }
+  for name in HOP_BY_HOP {
+    headers.remove(name);
+  }
+}
+
+/// Tell the instance who the client is; it only sees the front connect.
+fn append_forwarded_for(req: &mut Request<Incoming>, peer: SocketAddr) {
+  let peer_ip = peer.ip().to_string();
+  let value = match req.headers().get("x-forwarded-for") {
+    Some(existing) => match existing.to_str() {
+      Ok(existing) => format!("{}, {}", existing, peer_ip),
+      Err(_) => peer_ip,
+    },
+    None => peer_ip,
+  };
+  if let Ok(value) = HeaderValue::from_str(&value) {
+    req.headers_mut().insert("x-forwarded-for", value);
+  }
+}
+
+fn error_response(status: StatusCode) -> Response<ProxyBody> {
+  let body = Full::new(Bytes::from(
+    status.canonical_reason().unwrap_or_default().to_string(),
+  ))
+  .map_err(|never| match never {})
+  .boxed();
+  let mut resp = Response::new(body);
+  *resp.status_mut() = status;
+  resp
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::{Read, Write};
+  use std::sync::atomic::{AtomicUsize, Ordering};
+
+  /// An instance stand-in answering every request with `body`, and 404 on
+  /// `/missing`. Runs until the returned runtime is dropped.
+  fn backend(body: &'static str) -> (Backend, tokio::runtime::Runtime) {
+    let (backend, runtime, _) = counting_backend(body);
+    (backend, runtime)
+  }
+
+  /// [`backend`], also counting the connections it accepted.
+  fn counting_backend(
+    body: &'static str,
+  ) -> (Backend, tokio::runtime::Runtime, Arc<AtomicUsize>) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let listener = runtime
+      .block_on(TcpListener::bind((Ipv4Addr::LOCALHOST, 0)))
+      .unwrap();
+    let http = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = connections.clone();
+    runtime.spawn(async move {
+      while let Ok((stream, _)) = listener.accept().await {
+        accepted.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(async move {
+          let service = hyper::service::service_fn(|req: Request<Incoming>| {
+            let status = if req.uri().path() == "/missing" {
+              StatusCode::NOT_FOUND
+            } else {
+              StatusCode::OK
+            };
+            async move {
+              let mut resp = Response::new(Full::new(Bytes::from(body)));
+              *resp.status_mut() = status;
+              Ok::<_, hyper::Error>(resp)
+            }
+          });
+          let _ = hyper::server::conn::http1::Builder::new()
+            .serve_connection(TokioIo::new(stream), service)
+            .await;
+        });
+      }
+    });
+    (Backend { http, tls: None }, runtime, connections)
+  }
+
+  /// Status and body of `GET path` on `addr`.
+  fn get(addr: SocketAddr, path: &str) -> (u16, String) {
+    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+    write!(
+      stream,
+      "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+      path
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let body = response
+      .split_once("\r\n\r\n")
+      .map(|(_, body)| body.to_string())
+      .unwrap_or_default();
+    (status, body)
+  }
+
+  fn bind_front() -> Front {
+    let addr = free_loopback_addr().unwrap();
+    Front::bind(addr, None).unwrap()
+  }
+
+  #[test]
+  fn answers_503_until_an_instance_is_set() {
+    let front = bind_front();
+    assert_eq!(get(front.addr(), "/").0, 503);
+  }
+
+  #[test]
+  fn keeps_the_address_across_instances() {
+    let front = bind_front();
+    let (v1, _v1_runtime) = backend("v1");
+    front.set_backend(v1);
+    assert_eq!(get(front.addr(), "/"), (200, "v1".to_string()));
+    assert_eq!(get(front.addr(), "/missing").0, 404);
+
+    let (v2, v2_runtime) = backend("v2");
+    front.set_backend(v2);
+    assert_eq!(get(front.addr(), "/"), (200, "v2".to_string()));
+
+    drop(v2_runtime);
+    assert_eq!(get(front.addr(), "/").0, 502);
+  }
+
+  #[test]
+  fn reuses_connections_to_the_instance() {
+    let front = bind_front();
+    let (v1, _v1_runtime, connections) = counting_backend("v1");
+    front.set_backend(v1);
+    for _ in 0..5 {
+      assert_eq!(get(front.addr(), "/"), (200, "v1".to_string()));
+    }
+    assert_eq!(connections.load(Ordering::Relaxed), 1);
+  }
+
+  #[test]
+  fn waits_for_an_instance_to_listen() {
+    let (listening, _runtime) = backend("v1");
+    assert!(wait_until_listening(listening, || false));
+
+    let closed = Backend {
+      http: free_loopback_addr().unwrap(),
+      tls: None,
+    };
+    let started = Instant::now();
+    assert!(!wait_until_listening(closed, || true));
+    assert!(started.elapsed() < LISTEN_TIMEOUT);
+  }
+
+  #[test]
+  fn records_every_request_in_the_server_metrics() {
+    let front = bind_front();
+    let request_metrics = Arc::new(metrics::RequestMetrics::new());
+    metrics::register_server_metrics(front.addr(), request_metrics.clone());
+
+    // Before an instance is set, and once it is gone, the front answers
+    // itself; those requests count too.
+    assert_eq!(get(front.addr(), "/").0, 503);
+    let (v1, v1_runtime) = backend("v1");
+    front.set_backend(v1);
+    for _ in 0..3 {
+      assert_eq!(get(front.addr(), "/").0, 200);
+    }
+    assert_eq!(get(front.addr(), "/missing").0, 404);
+    drop(v1_runtime);
+    assert_eq!(get(front.addr(), "/").0, 502);
+
+    let snap = request_metrics.snapshot();
+    assert_eq!(snap.total_requests, 6);
+    assert_eq!(snap.status_2xx, 3);
+    assert_eq!(snap.status_4xx, 1);
+    assert_eq!(snap.status_5xx, 2);
+    assert!(snap.p50_ms.is_some());
+    metrics::unregister_server_metrics(front.addr(), &request_metrics);
+  }
+
+  #[test]
+  fn releases_the_address_when_closed() {
+    let front = bind_front();
+    let addr = front.addr();
+    front.stop_accepting();
+    assert!(std::net::TcpStream::connect(addr).is_err());
+    drop(front);
+    Front::bind(addr, None).unwrap();
+  }
+}
@@ -49,10 +49,13 @@ fn store_shared_connection(
 }
 
 mod bundle;
+mod front;
+mod tree_shake;
 mod trex_server;
+mod watch;
 
 use bundle::{create_bundle_sync, BundleOptions};
-use trex_server::{TrexServerConfig, TREX_MANAGER};
+use trex_server::{StopOutcome, TrexServerConfig, TREX_MANAGER};
 
 struct TrexVersionScalar;
 
@@ -133,6 +136,25 @@ impl VScalar for StartTrexServerScalar {
       Some(trex_server::normalize_path(&event_worker_path))
     };
 
+    let import_map_path = if input.num_columns() >= 5 {
+      let import_map_vector = input.flat_vector(4);
+      let import_map_slice = import_map_vector
+        .as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+      let path = duckdb::types::DuckString::new(&mut { import_map_slice[0] })
+        .as_str()
+        .to_string();
+      match trex_server::resolve_import_map_path(Some(path)) {
+        Ok(path) => path,
+        Err(err) => {
+          let flat_vector = output.flat_vector();
+          flat_vector.insert(0, &format!("Error: {:#}", err));
+          return Ok(());
+        }
+      }
+    } else {
+      None
+    };
+
     let config = trex_server::ServerConfig {
       addr,
       main_service_path: main_service_path_normalized,
@@ -156,13 +178,14 @@ impl VScalar for StartTrexServerScalar {
       beforeunload_wall_clock_pct: None,
       beforeunload_cpu_pct: None,
       beforeunload_memory_pct: None,
-      import_map_path: None,
+      import_map_path,
       jsx_specifier: None,
       jsx_module: None,
       worker_pool_max_size: None,
       worker_memory_limit_mb: None,
       decorator: false,
       restrict_host_fs: false,
+      watch: false,
     };
 
     let response = match TREX_MANAGER.start_server_sync(config) {
@@ -176,15 +199,29 @@ impl VScalar for StartTrexServerScalar {
   }
 
   fn signatures() -> Vec<ScalarFunctionSignature> {
-    vec![ScalarFunctionSignature::exact(
-      vec![
-        LogicalTypeId::Varchar.into(),
-        LogicalTypeId::Integer.into(),
+    vec![
+      // trex_start_server(host, port, main_service, event_worker)
+      ScalarFunctionSignature::exact(
+        vec![
+          LogicalTypeId::Varchar.into(),
+          LogicalTypeId::Integer.into(),
+          LogicalTypeId::Varchar.into(),
+          LogicalTypeId::Varchar.into(),
+        ],
         LogicalTypeId::Varchar.into(),
+      ),
+      // trex_start_server(host, port, main_service, event_worker, import_map)
+      ScalarFunctionSignature::exact(
+        vec![
+          LogicalTypeId::Varchar.into(),
+          LogicalTypeId::Integer.into(),
+          LogicalTypeId::Varchar.into(),
+          LogicalTypeId::Varchar.into(),
+          LogicalTypeId::Varchar.into(),
+        ],
         LogicalTypeId::Varchar.into(),
-      ],
-      LogicalTypeId::Varchar.into(),
-    )]
+      ),
+    ]
   }
 }
 
@@ -261,7 +298,9 @@ impl VScalar for StopTrexServerScalar {
       .to_string();
 
     let response = match TREX_MANAGER.stop_server(&server_id) {
-      Ok(_) => format!("Trex server {} stopped successfully", server_id),
+      Ok(outcome) => {
+        format!("Trex server {} stopped ({})", server_id, outcome.as_str())
+      }
       Err(err) => format!("Error stopping server: {}", err),
     };
 
@@ -289,7 +328,17 @@ impl VScalar for StopAllTrexServersScalar {
     output: &mut dyn WritableVector,
   ) -> Result<(), Box<dyn std::error::Error>> {
     let response = match TREX_MANAGER.stop_all_servers() {
-      Ok(count) => format!("Stopped {} Trex server(s)", count),
+      Ok(outcomes) => {
+        let forced = outcomes
+          .iter()
+          .filter(|(_, outcome)| *outcome == StopOutcome::Forced)
+          .count();
+        format!(
+          "Stopped {} Trex server(s), {} forced",
+          outcomes.len(),
+          forced
+        )
+      }
       Err(err) => format!("Error stopping servers: {}", err),
     };
 
@@ -353,6 +402,10 @@ impl VTab for TrexServersTable {
       "status",
       LogicalTypeHandle::from(LogicalTypeId::Varchar),
     );
+    bind.add_result_column(
+      "last_reload",
+      LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    );
     Ok(TrexServersBindData {})
   }
 
@@ -389,6 +442,7 @@ impl VTab for TrexServersTable {
     let started_at_vector = output.flat_vector(5);
     let policy_vector = output.flat_vector(6);
     let status_vector = output.flat_vector(7);
+    let last_reload_vector = output.flat_vector(8);
 
     for (i, (server_id, handle)) in servers.iter().enumerate() {
       let server_id_cstring = CString::new(server_id.as_str())?;
@@ -414,6 +468,12 @@ impl VTab for TrexServersTable {
           .unwrap_or("default"),
       )?;
       let status_cstring = CString::new("running")?;
+      let last_reload_cstring = CString::new(
+        handle
+          .last_reload
+          .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+          .unwrap_or_else(|| "never".to_string()),
+      )?;
 
       server_id_vector.insert(i, server_id_cstring);
       ip_vector.insert(i, ip_cstring);
@@ -423,6 +483,7 @@ impl VTab for TrexServersTable {
       started_at_vector.insert(i, started_at_cstring);
       policy_vector.insert(i, policy_cstring);
       status_vector.insert(i, status_cstring);
+      last_reload_vector.insert(i, last_reload_cstring);
     }
 
     output.set_len(server_count);
@@ -434,6 +495,140 @@ impl VTab for TrexServersTable {
   }
 }
 
+struct TrexServerMetricsTable;
+
+#[repr(C)]
+struct TrexServerMetricsBindData {
+  server_id: String,
+}
+
+#[repr(C)]
+struct TrexServerMetricsInitData {
+  done: AtomicBool,
+}
+
+impl VTab for TrexServerMetricsTable {
+  type InitData = TrexServerMetricsInitData;
+  type BindData = TrexServerMetricsBindData;
+
+  fn bind(
+    bind: &BindInfo,
+  ) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+    let server_id = bind.get_parameter(0).to_string();
+    bind.add_result_column(
+      "server_id",
+      LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    );
+    for column in ["total_requests", "status_2xx", "status_4xx", "status_5xx"] {
+      bind.add_result_column(
+        column,
+        LogicalTypeHandle::from(LogicalTypeId::Bigint),
+      );
+    }
+    for column in ["p50_ms", "p95_ms", "p99_ms"] {
+      bind.add_result_column(
+        column,
+        LogicalTypeHandle::from(LogicalTypeId::Double),
+      );
+    }
+    Ok(TrexServerMetricsBindData { server_id })
+  }
+
+  fn init(_: &InitInfo) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+    Ok(TrexServerMetricsInitData {
+      done: AtomicBool::new(false),
+    })
+  }
+
+  fn func(
+    func: &TableFunctionInfo<Self>,
+    output: &mut DataChunkHandle,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    let init_data = func.get_init_data();
+    let bind_data = func.get_bind_data();
+
+    if init_data.done.swap(true, Ordering::Relaxed) {
+      output.set_len(0);
+      return Ok(());
+    }
+
+    let Some(metrics) = TREX_MANAGER.server_metrics(&bind_data.server_id)
+    else {
+      output.set_len(0);
+      return Ok(());
+    };
+
+    output
+      .flat_vector(0)
+      .insert(0, CString::new(bind_data.server_id.as_str())?);
+    let counters = [
+      metrics.total_requests,
+      metrics.status_2xx,
+      metrics.status_4xx,
+      metrics.status_5xx,
+    ];
+    for (col, value) in counters.into_iter().enumerate() {
+      output.flat_vector(col + 1).as_mut_slice::<i64>()[0] =
+        i64::try_from(value).unwrap_or(i64::MAX);
+    }
+    let percentiles = [metrics.p50_ms, metrics.p95_ms, metrics.p99_ms];
+    for (col, value) in percentiles.into_iter().enumerate() {
+      let mut vector = output.flat_vector(col + 5);
+      match value {
+        Some(ms) => vector.as_mut_slice::<f64>()[0] = ms,
+        None => vector.set_null(0),
+      }
+    }
+
+    output.set_len(1);
+    Ok(())
+  }
+
+  fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+    Some(vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)])
+  }
+}
+
+struct ResetTrexServerMetricsScalar;
+
+impl VScalar for ResetTrexServerMetricsScalar {
+  type State = ();
+
+  unsafe fn invoke(
+    _state: &Self::State,
+    input: &mut DataChunkHandle,
+    output: &mut dyn WritableVector,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    let server_id_vector = input.flat_vector(0);
+    let server_id_slice = server_id_vector
+      .as_slice_with_len::<libduckdb_sys::duckdb_string_t>(input.len());
+
+    if input.is_empty() {
+      return Err("No input provided".into());
+    }
+
+    let server_id = duckdb::types::DuckString::new(&mut { server_id_slice[0] })
+      .as_str()
+      .to_string();
+
+    let response = match TREX_MANAGER.reset_server_metrics(&server_id) {
+      Ok(()) => format!("Metrics for Trex server {} reset", server_id),
+      Err(err) => format!("Error resetting metrics: {}", err),
+    };
+
+    let flat_vector = output.flat_vector();
+    flat_vector.insert(0, &response);
+    Ok(())
+  }
+
+  fn signatures() -> Vec<ScalarFunctionSignature> {
+    vec![ScalarFunctionSignature::exact(
+      vec![LogicalTypeId::Varchar.into()],
+      LogicalTypeId::Varchar.into(),
+    )]
+  }
+}
+
 struct TrexCreateBundleScalar;
 
 impl VScalar for TrexCreateBundleScalar {
@@ -556,6 +751,17 @@ pub unsafe fn extension_entrypoint(
     .register_scalar_function::<TrexCreateBundleScalar>("trex_create_bundle")?;
   con.register_table_function::<TrexServersTable>("trex_runtime_list")?;
   con.register_table_function::<TrexServersTable>("trex_list_servers")?;
+  con.register_table_function::<TrexServerMetricsTable>(
+    "trex_runtime_metrics",
+  )?;
+  con
+    .register_table_function::<TrexServerMetricsTable>("trex_server_metrics")?;
+  con.register_scalar_function::<ResetTrexServerMetricsScalar>(
+    "trex_runtime_metrics_reset",
+  )?;
+  con.register_scalar_function::<ResetTrexServerMetricsScalar>(
+    "trex_reset_server_metrics",
+  )?;
 
   Ok(())
 }
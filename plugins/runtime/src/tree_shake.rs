@@ -0,0 +1,808 @@
+//! Module-graph pruning behind `BundleOptions::tree_shake`.
+//!
+//! Starting from the entrypoint, every local module (a relative, absolute or
+//! `file://` specifier inside the project root) is scanned for its static
+//! imports, re-exports, `require("...")` and `import("...")` calls. The
+//! project is staged in a temporary copy without the modules the
+//! entrypoint never reaches, so `static_patterns` can't pull them into the
+//! bundle, and the copy is bundled as usual. Sources are copied unchanged.
+//!
+//! The scanner is lexical. A reference it misses makes the bundler fail on
+//! the missing module rather than change what runs, and when the graph
+//! can't be known, because of a dynamic import whose specifier is not a
+//! string literal or a bare specifier an import map may point at a local
+//! file, nothing is pruned.
+
+use anyhow::{bail, Context, Result};
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MODULE_EXTENSIONS: &[&str] =
+  &["ts", "tsx", "mts", "js", "jsx", "mjs", "cjs"];
+
+/// Keywords after which a `/` starts a regular expression rather than a
+/// division.
+const REGEX_PREFIX_KEYWORDS: &[&str] = &[
+  "return",
+  "typeof",
+  "instanceof",
+  "in",
+  "of",
+  "new",
+  "delete",
+  "void",
+  "throw",
+  "case",
+  "do",
+  "else",
+  "yield",
+  "await",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+  Ident(String),
+  Str(String),
+  Punct(u8),
+  Num,
+  Template { has_import: bool },
+  Regex,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+  tok: Tok,
+}
+
+impl Token {
+  fn is_ident(&self, name: &str) -> bool {
+    matches!(&self.tok, Tok::Ident(s) if s == name)
+  }
+
+  fn is_punct(&self, c: u8) -> bool {
+    self.tok == Tok::Punct(c)
+  }
+}
+
+fn is_ident_start(b: u8) -> bool {
+  b.is_ascii_alphabetic() || b == b'_' || b == b'$' || b >= 0x80
+}
+
+fn is_ident_continue(b: u8) -> bool {
+  is_ident_start(b) || b.is_ascii_digit()
+}
+
+fn tokenize(src: &str) -> Vec<Token> {
+  let bytes = src.as_bytes();
+  let mut tokens: Vec<Token> = Vec::new();
+  let mut i = 0;
+
+  while i < bytes.len() {
+    let b = bytes[i];
+    if b.is_ascii_whitespace() {
+      i += 1;
+      continue;
+    }
+    if b == b'/' && bytes.get(i + 1) == Some(&b'/') {
+      while i < bytes.len() && bytes[i] != b'\n' {
+        i += 1;
+      }
+      continue;
+    }
+    if b == b'/' && bytes.get(i + 1) == Some(&b'*') {
+      i += 2;
+      while i < bytes.len()
+        && !(bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/'))
+      {
+        i += 1;
+      }
+      i = (i + 2).min(bytes.len());
+      continue;
+    }
+
+    let start = i;
+    let tok = if b == b'"' || b == b'\'' {
+      i += 1;
+      let mut value = String::new();
+      while i < bytes.len() && bytes[i] != b && bytes[i] != b'\n' {
+        if bytes[i] == b'\\' {
+          i += 1;
+        }
+        i += 1;
+      }
+      if let Some(s) = src.get(start + 1..i.min(bytes.len())) {
+        value.push_str(s);
+      }
+      i = (i + 1).min(bytes.len());
+      Tok::Str(value)
+    } else if b == b'`' {
+      i = skip_template(bytes, i + 1);
+      let has_import = src[start..i].contains("import(");
+      Tok::Template { has_import }
+    } else if b == b'/' && regex_allowed(tokens.last()) {
+      i = skip_regex(bytes, i + 1);
+      Tok::Regex
+    } else if is_ident_start(b) {
+      while i < bytes.len() && is_ident_continue(bytes[i]) {
+        i += 1;
+      }
+      Tok::Ident(src[start..i].to_string())
+    } else if b.is_ascii_digit() {
+      while i < bytes.len() && (is_ident_continue(bytes[i]) || bytes[i] == b'.')
+      {
+        i += 1;
+      }
+      Tok::Num
+    } else {
+      i += 1;
+      Tok::Punct(b)
+    };
+
+    tokens.push(Token { tok });
+  }
+  tokens
+}
+
+/// Skip a template literal body starting after the opening backtick,
+/// including nested `${ ... }` expressions. Returns the index after the
+/// closing backtick.
+fn skip_template(bytes: &[u8], mut i: usize) -> usize {
+  while i < bytes.len() {
+    match bytes[i] {
+      b'\\' => i += 2,
+      b'`' => return i + 1,
+      b'$' if bytes.get(i + 1) == Some(&b'{') => {
+        i += 2;
+        let mut depth = 1;
+        while i < bytes.len() && depth > 0 {
+          match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => depth -= 1,
+            b'`' => {
+              i = skip_template(bytes, i + 1);
+              continue;
+            }
+            b'"' | b'\'' => {
+              let quote = bytes[i];
+              i += 1;
+              while i < bytes.len() && bytes[i] != quote {
+                if bytes[i] == b'\\' {
+                  i += 1;
+                }
+                i += 1;
+              }
+            }
+            _ => {}
+          }
+          i += 1;
+        }
+      }
+      _ => i += 1,
+    }
+  }
+  bytes.len()
+}
+
+fn skip_regex(bytes: &[u8], mut i: usize) -> usize {
+  let mut in_class = false;
+  while i < bytes.len() && bytes[i] != b'\n' {
+    match bytes[i] {
+      b'\\' => i += 1,
+      b'[' => in_class = true,
+      b']' => in_class = false,
+      b'/' if !in_class => {
+        i += 1;
+        while i < bytes.len() && is_ident_continue(bytes[i]) {
+          i += 1;
+        }
+        return i;
+      }
+      _ => {}
+    }
+    i += 1;
+  }
+  i
+}
+
+fn regex_allowed(prev: Option<&Token>) -> bool {
+  match prev.map(|t| &t.tok) {
+    None => true,
+    Some(Tok::Punct(c)) => !matches!(c, b')' | b']'),
+    Some(Tok::Ident(word)) => REGEX_PREFIX_KEYWORDS.contains(&word.as_str()),
+    Some(_) => false,
+  }
+}
+
+/// A reference from one module to another.
+#[derive(Debug, PartialEq)]
+struct Edge {
+  specifier: String,
+  /// Loaded through `import("...")`.
+  dynamic: bool,
+}
+
+#[derive(Debug, Default)]
+struct ModuleInfo {
+  edges: Vec<Edge>,
+  /// `import(expr)` with something other than a string literal.
+  opaque_dynamic_import: bool,
+}
+
+fn analyze(src: &str) -> ModuleInfo {
+  let tokens = tokenize(src);
+  let mut info = ModuleInfo {
+    opaque_dynamic_import: tokens
+      .iter()
+      .any(|t| t.tok == Tok::Template { has_import: true }),
+    ..Default::default()
+  };
+
+  // `import` and `export` are reserved words, so apart from member access
+  // and object keys they only start a declaration or an `import()` call.
+  // Nesting is not tracked, which keeps a stray quote in JSX text from
+  // hiding the declarations after it.
+  for (i, t) in tokens.iter().enumerate() {
+    if i > 0 && tokens[i - 1].is_punct(b'.') {
+      continue;
+    }
+
+    let next = tokens.get(i + 1);
+    let is_import = t.is_ident("import");
+    if (is_import || t.is_ident("require"))
+      && next.is_some_and(|n| n.is_punct(b'('))
+    {
+      match call_argument(&tokens, i + 2) {
+        Some(specifier) => info.edges.push(Edge {
+          specifier,
+          dynamic: is_import,
+        }),
+        None if is_import => info.opaque_dynamic_import = true,
+        None => {}
+      }
+    } else if is_import && !next.is_some_and(|n| n.is_punct(b'.')) {
+      if let Some(specifier) = import_source(&tokens, i + 1) {
+        info.edges.push(Edge {
+          specifier,
+          dynamic: false,
+        });
+      }
+    } else if t.is_ident("export") {
+      if let Some(specifier) = reexport_source(&tokens, i + 1) {
+        info.edges.push(Edge {
+          specifier,
+          dynamic: false,
+        });
+      }
+    }
+  }
+  info
+}
+
+fn string_at(tokens: &[Token], i: usize) -> Option<&str> {
+  match &tokens.get(i)?.tok {
+    Tok::Str(s) => Some(s),
+    _ => None,
+  }
+}
+
+/// The string literal at `i` if it is a whole call argument: `f("x")` or
+/// `import("x", { with })`.
+fn call_argument(tokens: &[Token], i: usize) -> Option<String> {
+  let specifier = string_at(tokens, i)?;
+  let close = tokens.get(i + 1)?;
+  (close.is_punct(b')') || close.is_punct(b',')).then(|| specifier.to_string())
+}
+
+/// Specifier of a static `import` starting after the keyword: the string
+/// right after it, or the one after the clause's `from`. `None` for
+/// `import x = require("y")`, whose call is found on its own.
+fn import_source(tokens: &[Token], mut i: usize) -> Option<String> {
+  if let Some(specifier) = string_at(tokens, i) {
+    return Some(specifier.to_string());
+  }
+  loop {
+    let t = tokens.get(i)?;
+    if t.is_punct(b';') || t.is_punct(b'=') {
+      return None;
+    }
+    if t.is_ident("from") {
+      if let Some(specifier) = string_at(tokens, i + 1) {
+        return Some(specifier.to_string());
+      }
+    }
+    i += 1;
+  }
+}
+
+/// Specifier of `export * from`, `export * as name from` or
+/// `export { ... } from`, starting after `export`.
+fn reexport_source(tokens: &[Token], mut i: usize) -> Option<String> {
+  if tokens.get(i)?.is_ident("type") {
+    i += 1;
+  }
+  let t = tokens.get(i)?;
+  let from = if t.is_punct(b'*') {
+    if tokens.get(i + 1)?.is_ident("as") {
+      i + 3
+    } else {
+      i + 1
+    }
+  } else if t.is_punct(b'{') {
+    matching_close(tokens, i)? + 1
+  } else {
+    return None;
+  };
+  if !tokens.get(from)?.is_ident("from") {
+    return None;
+  }
+  string_at(tokens, from + 1).map(str::to_string)
+}
+
+/// Index of the token closing the bracket opened at `open`.
+fn matching_close(tokens: &[Token], open: usize) -> Option<usize> {
+  let mut depth = 0usize;
+  for (j, t) in tokens.iter().enumerate().skip(open) {
+    match t.tok {
+      Tok::Punct(b'{' | b'(' | b'[') => depth += 1,
+      Tok::Punct(b'}' | b')' | b']') => {
+        depth = depth.checked_sub(1)?;
+        if depth == 0 {
+          return Some(j);
+        }
+      }
+      _ => {}
+    }
+  }
+  None
+}
+
+#[derive(Debug, Default)]
+pub struct ShakeReport {
+  /// Local modules reached from the entrypoint.
+  pub modules: usize,
+  /// Project-relative paths of the modules left out of the bundle.
+  pub pruned: Vec<String>,
+  /// Modules loaded through `import("...")`; they stay in the bundle.
+  pub dynamic_imports: usize,
+  /// Why nothing was pruned, when the graph could not be known.
+  pub skipped: Option<&'static str>,
+}
+
+impl ShakeReport {
+  pub fn summary(&self) -> String {
+    let mut summary = format!(
+      "tree-shaken: {} module(s) kept, {} unreachable module(s) pruned, {} dynamic import(s) kept",
+      self.modules,
+      self.pruned.len(),
+      self.dynamic_imports
+    );
+    if let Some(reason) = self.skipped {
+      summary.push_str(", pruning skipped for ");
+      summary.push_str(reason);
+    }
+    summary
+  }
+}
+
+/// Lexically normalize `path`, resolving `.` and `..` components.
+fn normalize(path: &Path) -> PathBuf {
+  let mut out = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::ParentDir => {
+        out.pop();
+      }
+      Component::CurDir => {}
+      other => out.push(other),
+    }
+  }
+  out
+}
+
+/// Resolve a local specifier. Remote, `npm:`, `jsr:`, `node:` and bare
+/// (import-mapped) specifiers return `None` and are left to the bundler;
+/// see [`is_bare`] for the latter.
+fn resolve(from: &Path, specifier: &str) -> Option<PathBuf> {
+  let path = if let Some(path) = specifier.strip_prefix("file://") {
+    PathBuf::from(path)
+  } else if specifier.starts_with("./") || specifier.starts_with("../") {
+    from.parent()?.join(specifier)
+  } else if specifier.starts_with('/') {
+    PathBuf::from(specifier)
+  } else {
+    return None;
+  };
+  Some(normalize(&path))
+}
+
+fn is_module(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|e| e.to_str())
+    .is_some_and(|e| MODULE_EXTENSIONS.contains(&e))
+}
+
+/// A specifier without a scheme that isn't a path either, e.g. `@/lib.ts`
+/// or `lodash`. Only an import map knows whether it is a local file.
+fn is_bare(specifier: &str) -> bool {
+  let has_scheme = specifier
+    .split_once(':')
+    .is_some_and(|(scheme, _)| !scheme.is_empty() && !scheme.contains('/'));
+  !has_scheme
+    && !specifier.starts_with("./")
+    && !specifier.starts_with("../")
+    && !specifier.starts_with('/')
+}
+
+/// Walk the local module graph of `entrypoint` inside `root`. Returns the
+/// modules reached, or `None` in place of them when the graph is open.
+fn module_graph(
+  root: &Path,
+  entrypoint: &Path,
+) -> Result<(Option<HashSet<PathBuf>>, ShakeReport)> {
+  let mut reached: HashSet<PathBuf> = HashSet::new();
+  let mut dynamic_targets: HashSet<PathBuf> = HashSet::new();
+  let mut skipped = None;
+  let mut queue = VecDeque::from([entrypoint.to_path_buf()]);
+
+  while let Some(path) = queue.pop_front() {
+    if !reached.insert(path.clone()) {
+      continue;
+    }
+    let src = fs::read_to_string(&path)
+      .with_context(|| format!("Failed to read module {}", path.display()))?;
+    let info = analyze(&src);
+    if info.opaque_dynamic_import {
+      skipped = Some("a non-literal dynamic import");
+    }
+    for edge in &info.edges {
+      let Some(target) = resolve(&path, &edge.specifier) else {
+        if is_bare(&edge.specifier) && skipped.is_none() {
+          skipped = Some("an import-mapped specifier");
+        }
+        continue;
+      };
+      if !target.starts_with(root) {
+        bail!(
+          "{} imports {} from outside the project root {}",
+          path.display(),
+          edge.specifier,
+          root.display()
+        );
+      }
+      // A missing module is the bundler's error to report.
+      if !target.is_file() {
+        continue;
+      }
+      if edge.dynamic {
+        dynamic_targets.insert(target.clone());
+      }
+      if is_module(&target) {
+        queue.push_back(target);
+      }
+    }
+  }
+
+  let report = ShakeReport {
+    modules: reached.len(),
+    dynamic_imports: dynamic_targets.len(),
+    skipped,
+    ..Default::default()
+  };
+  let keep = report.skipped.is_none().then_some(reached);
+  Ok((keep, report))
+}
+
+/// A tree-shaken copy of a project, removed again when dropped.
+pub struct StagedProject {
+  dir: PathBuf,
+  pub entrypoint: PathBuf,
+  pub report: ShakeReport,
+}
+
+impl Drop for StagedProject {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.dir);
+  }
+}
+
+/// Copy `root` to a temporary directory without the modules the graph
+/// below `entrypoint` never reaches. Other files are copied unchanged.
+/// `node_modules` is linked rather than copied and `.git` is left out;
+/// other dot-files such as `.npmrc` or `.env` are copied along with the
+/// sources.
+pub fn stage(root: &Path, entrypoint: &Path) -> Result<StagedProject> {
+  let (keep, mut report) = module_graph(root, entrypoint)?;
+
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|d| d.as_nanos())
+    .unwrap_or_default();
+  let dir = std::env::temp_dir().join(format!(
+    "trex-bundle-{}-{}",
+    std::process::id(),
+    nanos
+  ));
+  let mut pruned = Vec::new();
+  let copied = copy_tree(root, &dir, keep.as_ref(), root, &mut pruned);
+  pruned.sort();
+  report.pruned = pruned;
+  let staged = StagedProject {
+    entrypoint: dir.join(entrypoint.strip_prefix(root)?),
+    dir,
+    report,
+  };
+  copied?;
+  Ok(staged)
+}
+
+fn copy_tree(
+  from: &Path,
+  to: &Path,
+  keep: Option<&HashSet<PathBuf>>,
+  root: &Path,
+  pruned: &mut Vec<String>,
+) -> Result<()> {
+  fs::create_dir_all(to)
+    .with_context(|| format!("Failed to create {}", to.display()))?;
+  for entry in fs::read_dir(from)
+    .with_context(|| format!("Failed to read {}", from.display()))?
+  {
+    let entry = entry?;
+    let name = entry.file_name();
+    if name == ".git" {
+      continue;
+    }
+    let src = entry.path();
+    let dst = to.join(&name);
+    let relative = src.strip_prefix(root).unwrap_or(&src);
+    let file_type = entry.file_type()?;
+    if file_type.is_dir() && name == "node_modules" {
+      #[cfg(unix)]
+      std::os::unix::fs::symlink(&src, &dst)?;
+      #[cfg(not(unix))]
+      copy_tree(&src, &dst, None, root, pruned)?;
+    } else if file_type.is_dir() {
+      copy_tree(&src, &dst, keep, root, pruned)?;
+    } else if is_module(&src) && keep.is_some_and(|keep| !keep.contains(&src)) {
+      pruned.push(relative.display().to_string());
+    } else {
+      fs::copy(&src, &dst)
+        .with_context(|| format!("Failed to copy {}", relative.display()))?;
+    }
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// A project directory under the system temp dir, removed when dropped.
+  struct Project(PathBuf);
+
+  impl Project {
+    fn new(files: &[(&str, &str)]) -> Self {
+      static NEXT: std::sync::atomic::AtomicUsize =
+        std::sync::atomic::AtomicUsize::new(0);
+      let n = NEXT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+      let dir = std::env::temp_dir().join(format!(
+        "trex-shake-test-{}-{}",
+        std::process::id(),
+        n
+      ));
+      for (path, src) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, src).unwrap();
+      }
+      Project(dir)
+    }
+
+    fn stage(&self) -> StagedProject {
+      stage(&self.0, &self.0.join("main.ts")).unwrap()
+    }
+  }
+
+  impl Drop for Project {
+    fn drop(&mut self) {
+      let _ = fs::remove_dir_all(&self.0);
+    }
+  }
+
+  fn kinds(src: &str) -> Vec<Tok> {
+    tokenize(src).into_iter().map(|t| t.tok).collect()
+  }
+
+  fn specifiers(src: &str) -> Vec<(String, bool)> {
+    analyze(src)
+      .edges
+      .into_iter()
+      .map(|e| (e.specifier, e.dynamic))
+      .collect()
+  }
+
+  #[test]
+  fn regex_literals_are_single_tokens() {
+    let toks = kinds(r#"const re = /"import(x)"[/]\//g; a = b / c / d;"#);
+    assert_eq!(toks[3], Tok::Regex);
+    assert_eq!(toks[4], Tok::Punct(b';'));
+    assert!(!toks.iter().any(|t| matches!(t, Tok::Str(_))));
+    // After an identifier a slash is a division.
+    assert_eq!(toks.iter().filter(|t| **t == Tok::Punct(b'/')).count(), 2);
+
+    let info = analyze("return_ = x; function f() { return /import('y')/ }");
+    assert!(info.edges.is_empty());
+    assert!(!info.opaque_dynamic_import);
+  }
+
+  #[test]
+  fn template_strings_hide_their_contents() {
+    let toks = kinds("const s = `import \"x\" ${ `nested ${a}` } }`; b");
+    assert_eq!(toks[3], Tok::Template { has_import: false });
+    assert_eq!(toks[4..], [Tok::Punct(b';'), Tok::Ident("b".into())]);
+    assert!(analyze("const s = `import \"x\"`;").edges.is_empty());
+
+    let info = analyze("const m = `${await import(name)}`;");
+    assert!(info.opaque_dynamic_import);
+  }
+
+  #[test]
+  fn imports_and_reexports_are_found() {
+    let found = specifiers(
+      r#"
+      import "./polyfill.ts";
+      import def, { a, b as c, type T } from "./a.ts"
+      import * as ns from "./ns.ts";
+      import type { U } from "./types.ts";
+      import legacy = require("./legacy.js");
+      export * from "./all.ts";
+      export { x as y, z } from "./names.ts";
+      export * as space from "./space.ts";
+      export const local = { import: 1, from: "./not-a-module.ts" };
+      const meta = import.meta.url;
+      const lazy = () => import("./lazy.ts", { with: {} });
+      "#,
+    );
+    let expected = [
+      ("./polyfill.ts", false),
+      ("./a.ts", false),
+      ("./ns.ts", false),
+      ("./types.ts", false),
+      ("./legacy.js", false),
+      ("./all.ts", false),
+      ("./names.ts", false),
+      ("./space.ts", false),
+      ("./lazy.ts", true),
+    ];
+    assert_eq!(found, expected.map(|(s, d)| (s.to_string(), d)));
+  }
+
+  #[test]
+  fn jsx_text_does_not_hide_later_imports() {
+    let found = specifiers(
+      "import { h } from \"./h.ts\";\n\
+       export const App = () => <p>Don't {go}</p>;\n\
+       export { Page } from \"./page.tsx\";\n\
+       const Lazy = lazy(() => import(\"./lazy.tsx\"));\n",
+    );
+    let expected = [
+      ("./h.ts", false),
+      ("./page.tsx", false),
+      ("./lazy.tsx", true),
+    ];
+    assert_eq!(found, expected.map(|(s, d)| (s.to_string(), d)));
+  }
+
+  #[test]
+  fn unreachable_modules_are_pruned() {
+    let project = Project::new(&[
+      (
+        "main.ts",
+        "import { used } from \"./lib/used.ts\";\nused();\n",
+      ),
+      (
+        "lib/used.ts",
+        "import \"./setup.ts\";\nexport function used() {}\n",
+      ),
+      ("lib/setup.ts", "globalThis.x = 1;\n"),
+      ("lib/orphan.ts", "export function unused() {}\n"),
+      ("scripts/seed.ts", "import \"../lib/orphan.ts\";\n"),
+      ("data.json", "{}\n"),
+    ]);
+    let staged = project.stage();
+    let dir = staged.entrypoint.parent().unwrap().to_path_buf();
+    assert_eq!(staged.report.modules, 3);
+    assert_eq!(staged.report.pruned, ["lib/orphan.ts", "scripts/seed.ts"]);
+    assert_eq!(staged.report.skipped, None);
+    // Kept modules are copied unchanged.
+    assert_eq!(
+      fs::read_to_string(dir.join("lib/used.ts")).unwrap(),
+      "import \"./setup.ts\";\nexport function used() {}\n"
+    );
+    assert!(dir.join("lib/setup.ts").is_file());
+    assert!(!dir.join("lib/orphan.ts").exists());
+    assert!(dir.join("data.json").is_file());
+  }
+
+  #[test]
+  fn dynamic_imports_keep_their_targets() {
+    let project = Project::new(&[
+      ("main.ts", "const m = await import(\"./lazy.ts\");\n"),
+      ("lazy.ts", "export function f() {}\n"),
+      ("orphan.ts", "export function g() {}\n"),
+    ]);
+    let staged = project.stage();
+    assert_eq!(staged.report.dynamic_imports, 1);
+    assert_eq!(staged.report.pruned, ["orphan.ts"]);
+  }
+
+  #[test]
+  fn open_graphs_are_not_pruned() {
+    let project = Project::new(&[
+      ("main.ts", "await import(`./plugins/${name}.ts`);\n"),
+      ("plugins/a.ts", "export const a = 1;\n"),
+    ]);
+    let staged = project.stage();
+    assert!(staged.report.pruned.is_empty());
+    assert_eq!(staged.report.skipped, Some("a non-literal dynamic import"));
+    assert!(staged.entrypoint.with_file_name("plugins/a.ts").is_file());
+
+    let project = Project::new(&[
+      (
+        "main.ts",
+        "import { a } from \"@/lib.ts\";\nimport x from \"npm:x\";\n",
+      ),
+      ("lib.ts", "export const a = 1;\n"),
+    ]);
+    let staged = project.stage();
+    assert!(staged.report.pruned.is_empty());
+    assert_eq!(staged.report.skipped, Some("an import-mapped specifier"));
+  }
+
+  #[test]
+  fn remote_specifiers_do_not_stop_pruning() {
+    let project = Project::new(&[
+      (
+        "main.ts",
+        "import x from \"npm:x\";\n\
+         import { y } from \"https://example.com/y.ts\";\n\
+         import fs from \"node:fs\";\n\
+         import \"./missing.ts\";\n",
+      ),
+      ("orphan.ts", "export const a = 1;\n"),
+    ]);
+    let staged = project.stage();
+    assert_eq!(staged.report.pruned, ["orphan.ts"]);
+  }
+
+  #[test]
+  fn imports_outside_the_root_are_rejected() {
+    let project =
+      Project::new(&[("main.ts", "import { a } from \"../elsewhere.ts\";\n")]);
+    let err = stage(&project.0, &project.0.join("main.ts")).err().unwrap();
+    assert!(err.to_string().contains("outside the project root"));
+  }
+
+  #[test]
+  fn staging_copies_dot_files_but_not_git() {
+    let project = Project::new(&[
+      ("main.ts", "import { a } from \"./lib.ts\";\na;\n"),
+      ("lib.ts", "export const a = 1;\n"),
+      (".npmrc", "registry=https://registry.example\n"),
+      ("config/.env", "KEY=value\n"),
+      (".git/HEAD", "ref: refs/heads/main\n"),
+    ]);
+    let staged = project.stage();
+    let dir = staged.entrypoint.parent().unwrap().to_path_buf();
+    assert!(dir.join("lib.ts").is_file());
+    assert!(dir.join(".npmrc").is_file());
+    assert!(dir.join("config/.env").is_file());
+    assert!(!dir.join(".git").exists());
+
+    drop(staged);
+    assert!(!dir.exists());
+  }
+}
@@ -7,9 +7,15 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, LazyLock, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+use trex_core::metrics::{self, MetricsSnapshot, RequestMetrics};
+
+use crate::front::{self, Backend, Front};
+use crate::{bundle, watch};
 
 #[derive(Clone)]
 pub struct ServerConfig {
@@ -42,6 +48,8 @@ pub struct ServerConfig {
   pub worker_memory_limit_mb: Option<usize>,
   pub decorator: bool,
   pub restrict_host_fs: bool,
+  /// Reload the server when files next to the main service change.
+  pub watch: bool,
 }
 
 impl std::fmt::Debug for ServerConfig {
@@ -88,6 +96,7 @@ impl std::fmt::Debug for ServerConfig {
       .field("worker_memory_limit_mb", &self.worker_memory_limit_mb)
       .field("decorator", &self.decorator)
       .field("restrict_host_fs", &self.restrict_host_fs)
+      .field("watch", &self.watch)
       .finish()
   }
 }
@@ -124,6 +133,7 @@ impl Default for ServerConfig {
       worker_memory_limit_mb: None,
       decorator: false,
       restrict_host_fs: false,
+      watch: false,
     }
   }
 }
@@ -168,6 +178,13 @@ struct ServerInfo {
   config: ServerConfig,
   status: String,
   _started_at: chrono::DateTime<chrono::Utc>,
+  last_reload: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub struct ServerEntry {
+  pub config: ServerConfig,
+  pub status: String,
+  pub last_reload: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl Default for ServerManager {
@@ -195,22 +212,37 @@ impl ServerManager {
         config,
         status: "running".to_string(),
         _started_at: chrono::Utc::now(),
+        last_reload: None,
       },
     );
     Ok(())
   }
 
+  pub fn record_reload(&self, id: &str) {
+    let mut servers = self.servers.lock().unwrap();
+    if let Some(info) = servers.get_mut(id) {
+      info.last_reload = Some(chrono::Utc::now());
+    }
+  }
+
   pub fn unregister_server(&self, id: &str) -> Result<()> {
     let mut servers = self.servers.lock().unwrap();
     servers.remove(id);
     Ok(())
   }
 
-  pub fn list_servers(&self) -> Result<Vec<(String, ServerConfig, String)>> {
+  pub fn list_servers(&self) -> Result<Vec<(String, ServerEntry)>> {
     let servers = self.servers.lock().unwrap();
     let result = servers
       .iter()
-      .map(|(id, info)| (id.clone(), info.config.clone(), info.status.clone()))
+      .map(|(id, info)| {
+        let entry = ServerEntry {
+          config: info.config.clone(),
+          status: info.status.clone(),
+          last_reload: info.last_reload,
+        };
+        (id.clone(), entry)
+      })
       .collect();
     Ok(result)
   }
@@ -237,15 +269,194 @@ pub fn get_version() -> String {
 static LOG_INIT: AtomicBool = AtomicBool::new(false);
 
 struct ServerThreadEntry {
+  /// Tells the instance a reload replaced apart from its replacement.
+  instance: u64,
+  /// For watched servers, the listening sockets handed from instance to
+  /// instance. Other servers listen on their address directly.
+  front: Option<Arc<Front>>,
   join_handle: thread::JoinHandle<()>,
   termination_token: TerminationToken,
+  /// Makes the server thread drop `listen()`, abandoning whatever requests
+  /// are still in flight.
+  force_tx: tokio::sync::oneshot::Sender<()>,
+  graceful_exit_deadline: Duration,
 }
 
-type ServerThreads = Arc<Mutex<HashMap<String, ServerThreadEntry>>>;
+/// How a stopped server went down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+  /// In-flight requests finished within `graceful_exit_deadline_sec`.
+  Graceful,
+  /// The deadline passed and the server was torn down with requests
+  /// still running.
+  Forced,
+}
+
+impl StopOutcome {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      StopOutcome::Graceful => "graceful",
+      StopOutcome::Forced => "forced",
+    }
+  }
+}
+
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+static NEXT_INSTANCE: AtomicU64 = AtomicU64::new(0);
+
+/// Loopback ports tried for an instance behind a front before giving up.
+const INSTANCE_PORT_ATTEMPTS: usize = 3;
+
+/// Wait until `deadline` for an already-cancelled server thread to drain,
+/// then force it down. Returns once the thread has exited.
+fn finish_stop(
+  id: &str,
+  entry: ServerThreadEntry,
+  deadline: Instant,
+) -> StopOutcome {
+  while !entry.join_handle.is_finished() && Instant::now() < deadline {
+    thread::sleep(STOP_POLL_INTERVAL);
+  }
+
+  let outcome = if entry.join_handle.is_finished() {
+    StopOutcome::Graceful
+  } else {
+    eprintln!(
+      "[TREX-EXT] Server {} still busy after {}s, forcing shutdown",
+      id,
+      entry.graceful_exit_deadline.as_secs()
+    );
+    let _ = entry.force_tx.send(());
+    StopOutcome::Forced
+  };
+
+  // Wait for the worker thread to actually exit so the instance's ports
+  // are released before we return.
+  if let Err(e) = entry.join_handle.join() {
+    eprintln!(
+      "[TREX-EXT] Server thread {} panicked during shutdown: {:?}",
+      id, e
+    );
+  }
+  outcome
+}
+
+type ServerThreadMap = HashMap<String, ServerThreadEntry>;
+type ServerThreads = Arc<Mutex<ServerThreadMap>>;
 
 static SERVER_THREADS: LazyLock<ServerThreads> =
   LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
 
+fn lock_threads() -> std::sync::MutexGuard<'static, ServerThreadMap> {
+  match SERVER_THREADS.lock() {
+    Ok(g) => g,
+    Err(poisoned) => poisoned.into_inner(),
+  }
+}
+
+/// Poll the main service's directory for changes and reload `server_id`
+/// after each one, until the server is stopped.
+fn spawn_watcher(server_id: String, config: ServerConfig) {
+  let Some(entrypoint) = watch::watched_entrypoint(&config.main_service_path)
+  else {
+    eprintln!(
+      "[TREX-EXT] Not watching {}: {} is a bundle",
+      server_id, config.main_service_path
+    );
+    return;
+  };
+  let Some(root) = entrypoint.parent().map(Path::to_path_buf) else {
+    return;
+  };
+
+  let name = format!("trex-watch-{}", server_id);
+  let spawned = thread::Builder::new().name(name).spawn(move || {
+    let mut changes = watch::ChangeDebouncer::new(root);
+    loop {
+      thread::sleep(watch::POLL_INTERVAL);
+      if !lock_threads().contains_key(&server_id) {
+        break;
+      }
+      if changes.poll() {
+        reload_server(&server_id, &config, &entrypoint);
+      }
+    }
+  });
+  if let Err(e) = spawned {
+    eprintln!("[TREX-EXT] Failed to start file watcher: {}", e);
+  }
+}
+
+/// Swap the server's instance for one running the current code, on the
+/// same listening socket. The new code is checked first, and the new
+/// instance starts next to the running one; if either fails, the running
+/// instance keeps serving. Once the new instance listens it gets all new
+/// requests and the old one drains.
+///
+/// The new entry only replaces the old one if the server is still running;
+/// a server stopped during the reload has its new instance stopped too.
+fn reload_server(server_id: &str, config: &ServerConfig, entrypoint: &Path) {
+  if let Err(e) = bundle::check_entrypoint(&entrypoint.display().to_string()) {
+    eprintln!("[TREX-EXT] Not reloading {}: {:#}", server_id, e);
+    return;
+  }
+
+  let Some(front) = lock_threads().get(server_id).and_then(|e| e.front.clone())
+  else {
+    return;
+  };
+  let entry = match TrexServerManagerWrapper::spawn_server(
+    server_id,
+    config.clone(),
+    Some(front),
+  ) {
+    Ok(entry) => entry,
+    Err(e) => {
+      eprintln!(
+        "[TREX-EXT] Reload of {} failed, still serving the previous code: {:#}",
+        server_id, e
+      );
+      return;
+    }
+  };
+  let swapped = {
+    let mut threads = lock_threads();
+    match threads.get_mut(server_id) {
+      Some(current) => Ok(std::mem::replace(current, entry)),
+      None => Err(entry),
+    }
+  };
+  let old = match swapped {
+    Ok(old) => old,
+    Err(orphan) => {
+      orphan.termination_token.cancel();
+      let deadline = Instant::now() + orphan.graceful_exit_deadline;
+      finish_stop(server_id, orphan, deadline);
+      return;
+    }
+  };
+  // The drain runs without any lock held, so stop_server is never kept
+  // waiting for it.
+  old.termination_token.cancel();
+  let deadline = Instant::now() + old.graceful_exit_deadline;
+  finish_stop(server_id, old, deadline);
+
+  get_global_server_manager().record_reload(server_id);
+  eprintln!("[TREX-EXT] Reloaded {}", server_id);
+}
+
+/// Where a server's TLS listener goes, when it has a certificate and key.
+fn tls_addr(config: &ServerConfig) -> Option<SocketAddr> {
+  match (&config.tls_cert_path, &config.tls_key_path) {
+    (Some(_), Some(_)) => Some(SocketAddr::new(
+      config.addr.ip(),
+      config.tls_port.unwrap_or(443),
+    )),
+    _ => None,
+  }
+}
+
 fn init_logging() {
   if LOG_INIT.swap(true, Ordering::Relaxed) {
     return;
@@ -303,6 +514,38 @@ pub(crate) fn normalize_path(path: &str) -> String {
   format!("file://{}", final_path.display())
 }
 
+/// Check that an import map exists and is a JSON object before the server
+/// is started with it, and make its path absolute. An empty path means no
+/// import map.
+pub(crate) fn resolve_import_map_path(
+  path: Option<String>,
+) -> Result<Option<String>> {
+  let path = match path {
+    Some(p) if !p.is_empty() => p,
+    _ => return Ok(None),
+  };
+  let path = path.strip_prefix("file://").unwrap_or(&path);
+  let path_obj = Path::new(path);
+  let abs_path = if path_obj.is_absolute() {
+    path_obj.to_path_buf()
+  } else {
+    std::env::current_dir()
+      .map_err(|e| anyhow::anyhow!("Failed to resolve import map path: {}", e))?
+      .join(path_obj)
+  };
+
+  let contents = std::fs::read_to_string(&abs_path).map_err(|e| {
+    anyhow::anyhow!("Failed to read import map {}: {}", abs_path.display(), e)
+  })?;
+  match serde_json::from_str::<serde_json::Value>(&contents) {
+    Ok(serde_json::Value::Object(_)) => {}
+    Ok(_) => bail!("Import map {} must be a JSON object", abs_path.display()),
+    Err(e) => bail!("Invalid import map {}: {}", abs_path.display(), e),
+  }
+
+  Ok(Some(abs_path.display().to_string()))
+}
+
 fn parse_inspector_option(s: &str) -> Result<InspectorOption> {
   let parts: Vec<&str> = s.split(':').collect();
   if parts.len() < 3 {
@@ -331,14 +574,20 @@ fn parse_inspector_option(s: &str) -> Result<InspectorOption> {
   }
 }
 
+type ServerMetricsMap = HashMap<String, (SocketAddr, Arc<RequestMetrics>)>;
+
 pub struct TrexServerManagerWrapper {
   manager: &'static ServerManager,
+  /// Request metrics per server id, with the address they are registered
+  /// under. Kept across reloads; dropped when the server is stopped.
+  metrics: Mutex<ServerMetricsMap>,
 }
 
 impl TrexServerManagerWrapper {
   pub fn new() -> Self {
     Self {
       manager: get_global_server_manager(),
+      metrics: Mutex::new(HashMap::new()),
     }
   }
 
@@ -352,16 +601,88 @@ impl TrexServerManagerWrapper {
   }
 
   fn start_server_persistent(&self, config: ServerConfig) -> Result<String> {
-    use base::server::Builder;
-    use std::sync::mpsc;
-
     let server_id = format!(
       "trex_{}_{}",
       config.addr.port(),
       chrono::Utc::now().timestamp()
     );
+    let watch = config.watch;
+    let front = if watch {
+      Some(Arc::new(Front::bind(config.addr, tls_addr(&config))?))
+    } else {
+      None
+    };
+    let entry = Self::spawn_server(&server_id, config.clone(), front)?;
+    lock_threads().insert(server_id.clone(), entry);
+    self.track_metrics(&server_id, config.addr);
+    if watch {
+      spawn_watcher(server_id.clone(), config);
+    }
+    Ok(format!("Started Trex server: {}", server_id))
+  }
+
+  /// Start an instance of `server_id`. Without a front it listens on the
+  /// configured address. Behind `front` it gets loopback ports of its own,
+  /// and the front is pointed at it once it accepts on them; an instance
+  /// that doesn't, e.g. because another process took a port first, is
+  /// retried on other ports.
+  fn spawn_server(
+    server_id: &str,
+    config: ServerConfig,
+    front: Option<Arc<Front>>,
+  ) -> Result<ServerThreadEntry> {
+    let Some(front) = front else {
+      let listen = Backend {
+        http: config.addr,
+        tls: tls_addr(&config),
+      };
+      return Self::start_instance(server_id, config, listen, None);
+    };
+
+    for attempt in 1..=INSTANCE_PORT_ATTEMPTS {
+      let backend = Backend::pick(tls_addr(&config).is_some())?;
+      let entry = Self::start_instance(
+        server_id,
+        config.clone(),
+        backend,
+        Some(front.clone()),
+      )?;
+      if front::wait_until_listening(backend, || {
+        entry.join_handle.is_finished()
+      }) {
+        front.set_backend(backend);
+        return Ok(entry);
+      }
+      eprintln!(
+        "[TREX-EXT] Instance of {} is not listening on {} (attempt {}/{})",
+        server_id, backend.http, attempt, INSTANCE_PORT_ATTEMPTS
+      );
+      entry.termination_token.cancel();
+      let _ = entry.force_tx.send(());
+      let _ = entry.join_handle.join();
+    }
+    bail!(
+      "Server {} did not start listening after {} attempts",
+      server_id,
+      INSTANCE_PORT_ATTEMPTS
+    )
+  }
+
+  /// Start the server thread of one instance listening on `listen` and
+  /// wait until it has been built.
+  fn start_instance(
+    server_id: &str,
+    config: ServerConfig,
+    listen: Backend,
+    front: Option<Arc<Front>>,
+  ) -> Result<ServerThreadEntry> {
+    use base::server::Builder;
+    use std::sync::mpsc;
+
+    let instance = NEXT_INSTANCE.fetch_add(1, Ordering::Relaxed);
+    let server_id = server_id.to_string();
     let server_id_clone = server_id.clone();
-    let config_clone = config.clone();
+    let config_clone = config;
 
     let (result_tx, result_rx) = mpsc::channel();
 
@@ -371,6 +692,9 @@ impl TrexServerManagerWrapper {
     // SERVER_THREADS for stop_server to cancel later.
     let termination_token = TerminationToken::new();
     let thread_termination_token = termination_token.clone();
+    let (force_tx, force_rx) = tokio::sync::oneshot::channel::<()>();
+    let graceful_exit_deadline =
+      Duration::from_secs(config.graceful_exit_deadline_sec);
 
     let thread_handle = thread::spawn(move || {
       init_logging();
@@ -391,17 +715,18 @@ impl TrexServerManagerWrapper {
       let local = tokio::task::LocalSet::new();
       let result: Result<()> = local.block_on(&runtime, async {
         let mut builder =
-          Builder::new(config_clone.addr, &config_clone.main_service_path);
+          Builder::new(listen.http, &config_clone.main_service_path);
 
         // Wire the termination token so stop_server can break server.listen().
         builder.termination_token(thread_termination_token);
 
-        if let (Some(cert_path), Some(key_path)) =
-          (&config_clone.tls_cert_path, &config_clone.tls_key_path)
-        {
-          let tls_port = config_clone.tls_port.unwrap_or(443);
+        if let (Some(tls_addr), Some(cert_path), Some(key_path)) = (
+          listen.tls,
+          &config_clone.tls_cert_path,
+          &config_clone.tls_key_path,
+        ) {
           if let Ok(tls) =
-            Self::create_tls_config_static(cert_path, key_path, tls_port)
+            Self::create_tls_config_static(cert_path, key_path, tls_addr.port())
           {
             builder.tls(tls);
           }
@@ -411,6 +736,10 @@ impl TrexServerManagerWrapper {
           builder.event_worker_path(event_worker_path);
         }
 
+        if let Some(import_map_path) = &config_clone.import_map_path {
+          builder.import_map_path(import_map_path);
+        }
+
         if let Some(ref user_worker_policy) = config_clone.user_worker_policy {
           builder.user_worker_policy(user_worker_policy.clone());
         }
@@ -434,21 +763,35 @@ impl TrexServerManagerWrapper {
           *builder.entrypoints_mut() = entrypoints;
         }
 
-        get_global_server_manager()
-          .register_server(server_id_clone.clone(), config_clone.clone())?;
-
         match builder.build().await {
           Ok(mut server) => {
             use std::io::Write;
             let _ = std::io::stdout().flush();
 
+            get_global_server_manager()
+              .register_server(server_id_clone.clone(), config_clone.clone())?;
             let _ = result_tx.send(Ok("Server starting".to_string()));
 
-            eprintln!("[TREX-EXT] Server listening on {}", config_clone.addr);
-
-            let _ = server.listen().await;
+            if listen.http == config_clone.addr {
+              eprintln!("[TREX-EXT] Server listening on {}", listen.http);
+            } else {
+              eprintln!(
+                "[TREX-EXT] Server listening on {} (instance on {})",
+                config_clone.addr, listen.http
+              );
+            }
 
-            eprintln!("[TREX-EXT] Server stopped listening");
+            // Cancelling the termination token stops the accept loop and
+            // lets listen() drain; force_rx only fires once stop_server
+            // has given up waiting for that.
+            tokio::select! {
+              _ = server.listen() => {
+                eprintln!("[TREX-EXT] Server stopped listening");
+              }
+              Ok(()) = force_rx => {
+                eprintln!("[TREX-EXT] Server shutdown forced");
+              }
+            }
           }
           Err(e) => {
             eprintln!("[TREX-EXT] Failed to build server: {}", e);
@@ -461,7 +804,14 @@ impl TrexServerManagerWrapper {
           }
         }
 
-        let _ = get_global_server_manager().unregister_server(&server_id_clone);
+        // After a reload the server lives on in the new instance.
+        let replaced = lock_threads()
+          .get(&server_id_clone)
+          .is_some_and(|entry| entry.instance != instance);
+        if !replaced {
+          let _ =
+            get_global_server_manager().unregister_server(&server_id_clone);
+        }
         Ok(())
       });
 
@@ -472,20 +822,20 @@ impl TrexServerManagerWrapper {
       }
     });
 
-    if let Ok(mut threads) = SERVER_THREADS.lock() {
-      threads.insert(
-        server_id.clone(),
-        ServerThreadEntry {
-          join_handle: thread_handle,
-          termination_token,
-        },
-      );
-    }
-
     match result_rx.recv_timeout(std::time::Duration::from_secs(180)) {
-      Ok(Ok(_)) => Ok(format!("Started Trex server: {}", server_id)),
+      Ok(Ok(_)) => Ok(ServerThreadEntry {
+        instance,
+        front,
+        join_handle: thread_handle,
+        termination_token,
+        force_tx,
+        graceful_exit_deadline,
+      }),
       Ok(Err(e)) => Err(e),
-      Err(_) => Err(anyhow::anyhow!("Server start timeout")),
+      Err(_) => {
+        termination_token.cancel();
+        Err(anyhow::anyhow!("Server start timeout"))
+      }
     }
   }
 
@@ -506,74 +856,90 @@ impl TrexServerManagerWrapper {
 }
 
 impl TrexServerManagerWrapper {
-  pub fn stop_server(&self, server_id: &str) -> Result<String> {
+  /// Stop accepting connections on `server_id`, give in-flight requests
+  /// up to the server's `graceful_exit_deadline_sec` to finish, then tear
+  /// it down regardless.
+  pub fn stop_server(&self, server_id: &str) -> Result<StopOutcome> {
     // Take the entry out of SERVER_THREADS *first* so we own the
     // termination token + join handle. Drop the lock before joining; the
-    // join blocks until the spawned thread exits, which can take several
-    // seconds for graceful shutdown.
-    let entry = {
-      let mut threads = match SERVER_THREADS.lock() {
-        Ok(g) => g,
-        Err(poisoned) => poisoned.into_inner(),
-      };
-      threads.remove(server_id)
-    };
-
-    if let Some(entry) = entry {
-      // Signal the accept loop in server::listen() to break.
-      entry.termination_token.cancel();
-
-      // Wait for the worker thread to actually exit so the listening
-      // socket is released before we return. Without this, an immediate
-      // restart on the same port would race with the previous bind.
-      if let Err(e) = entry.join_handle.join() {
-        eprintln!(
-          "[TREX-EXT] Server thread panicked during shutdown: {:?}",
-          e
-        );
+    // join blocks until the spawned thread exits, which can take up to the
+    // graceful exit deadline. A reload finishing after this sees the entry
+    // gone and stops its new instance itself.
+    let entry = lock_threads().remove(server_id);
+
+    let outcome = match entry {
+      Some(entry) => {
+        let front = entry.front.clone();
+        if let Some(front) = &front {
+          front.stop_accepting();
+        }
+        // Signal the accept loop in server::listen() to break.
+        entry.termination_token.cancel();
+        let deadline = Instant::now() + entry.graceful_exit_deadline;
+        let outcome = finish_stop(server_id, entry, deadline);
+        if let Some(front) = front {
+          front.close();
+        }
+        outcome
       }
-    }
+      None => StopOutcome::Graceful,
+    };
 
     // Cleanup of the ServerManager registry entry happens *after* the
     // thread exits (the spawned thread also calls unregister_server on
     // its way out, so this is idempotent).
     let _ = self.manager.unregister_server(server_id);
+    self.drop_metrics(server_id);
 
-    Ok(format!("Stopped Trex server: {}", server_id))
+    Ok(outcome)
   }
 
-  pub fn stop_all_servers(&self) -> Result<usize> {
+  /// Stop every server as [`stop_server`](Self::stop_server) does. All of
+  /// them are signalled before any is waited on, so their drain deadlines
+  /// run concurrently.
+  pub fn stop_all_servers(&self) -> Result<Vec<(String, StopOutcome)>> {
     // Drain entries (token + handle) under the lock, then signal and
     // join each one outside the lock.
     let entries: Vec<(String, ServerThreadEntry)> =
-      match SERVER_THREADS.lock() {
-        Ok(mut threads) => threads.drain().collect(),
-        Err(poisoned) => poisoned.into_inner().drain().collect(),
-      };
+      lock_threads().drain().collect();
 
-    let count = entries.len();
-    for (id, entry) in entries {
-      entry.termination_token.cancel();
-      if let Err(e) = entry.join_handle.join() {
-        eprintln!(
-          "[TREX-EXT] Server thread {} panicked during shutdown: {:?}",
-          id, e
-        );
+    let started = Instant::now();
+    for (_, entry) in &entries {
+      if let Some(front) = &entry.front {
+        front.stop_accepting();
       }
+      entry.termination_token.cancel();
     }
+    let outcomes = entries
+      .into_iter()
+      .map(|(id, entry)| {
+        let deadline = started + entry.graceful_exit_deadline;
+        let front = entry.front.clone();
+        let outcome = finish_stop(&id, entry, deadline);
+        if let Some(front) = front {
+          front.close();
+        }
+        (id, outcome)
+      })
+      .collect();
 
     let _ = self.manager.stop_all_servers();
-    Ok(count)
+    let tracked: Vec<String> = self.lock_metrics().keys().cloned().collect();
+    for id in tracked {
+      self.drop_metrics(&id);
+    }
+    Ok(outcomes)
   }
 
   pub fn list_servers(&self) -> Vec<(String, ServerHandle)> {
     match self.manager.list_servers() {
       Ok(servers) => servers
         .into_iter()
-        .map(|(id, config, _status)| {
+        .map(|(id, entry)| {
           let handle = ServerHandle {
-            config,
+            config: entry.config,
             started_at: chrono::Utc::now(),
+            last_reload: entry.last_reload,
           };
           (id, handle)
         })
@@ -581,6 +947,44 @@ impl TrexServerManagerWrapper {
       Err(_) => Vec::new(),
     }
   }
+
+  /// Request counters and latency percentiles of `server_id` since it
+  /// started or was last reset.
+  pub fn server_metrics(&self, server_id: &str) -> Option<MetricsSnapshot> {
+    self
+      .lock_metrics()
+      .get(server_id)
+      .map(|(_, metrics)| metrics.snapshot())
+  }
+
+  pub fn reset_server_metrics(&self, server_id: &str) -> Result<()> {
+    match self.lock_metrics().get(server_id) {
+      Some((_, metrics)) => {
+        metrics.reset();
+        Ok(())
+      }
+      None => bail!("Unknown Trex server: {}", server_id),
+    }
+  }
+
+  fn lock_metrics(&self) -> std::sync::MutexGuard<'_, ServerMetricsMap> {
+    self.metrics.lock().unwrap_or_else(|p| p.into_inner())
+  }
+
+  fn track_metrics(&self, server_id: &str, addr: SocketAddr) {
+    let request_metrics = Arc::new(RequestMetrics::new());
+    metrics::register_server_metrics(addr, request_metrics.clone());
+    self
+      .lock_metrics()
+      .insert(server_id.to_string(), (addr, request_metrics));
+  }
+
+  fn drop_metrics(&self, server_id: &str) {
+    if let Some((addr, request_metrics)) = self.lock_metrics().remove(server_id)
+    {
+      metrics::unregister_server_metrics(addr, &request_metrics);
+    }
+  }
 }
 
 pub static TREX_MANAGER: LazyLock<TrexServerManagerWrapper> =
@@ -593,6 +997,7 @@ pub static TREX_MANAGER: LazyLock<TrexServerManagerWrapper> =
 pub struct ServerHandle {
   pub config: ServerConfig,
   pub started_at: chrono::DateTime<chrono::Utc>,
+  pub last_reload: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -659,6 +1064,8 @@ pub struct TrexServerConfig {
   pub decorator: bool,
   #[serde(default)]
   pub restrict_host_fs: bool,
+  #[serde(default)]
+  pub watch: bool,
 }
 
 fn default_host() -> String {
@@ -697,6 +1104,8 @@ impl TrexServerConfig {
         }
       });
 
+    let import_map_path = resolve_import_map_path(self.import_map_path)?;
+
     let inspector_option = if let Some(ref inspector_str) = self.inspector {
       Some(parse_inspector_option(inspector_str)?)
     } else {
@@ -780,13 +1189,14 @@ impl TrexServerConfig {
         .map(|p| p as u8),
       beforeunload_cpu_pct: self.beforeunload_cpu_pct.map(|p| p as u8),
       beforeunload_memory_pct: self.beforeunload_memory_pct.map(|p| p as u8),
-      import_map_path: self.import_map_path,
+      import_map_path,
       jsx_specifier: self.jsx_specifier,
       jsx_module: self.jsx_module,
       worker_pool_max_size: self.max_parallelism,
       worker_memory_limit_mb: self.worker_memory_limit_mb,
       decorator: self.decorator,
       restrict_host_fs: self.restrict_host_fs,
+      watch: self.watch,
     })
   }
 }
@@ -0,0 +1,98 @@
+//! Polling file watcher behind `TrexServerConfig::watch`.
+//!
+//! The watched tree is the directory of the main service. Every
+//! `POLL_INTERVAL` its files' modification times are compared with the
+//! previous scan; a change is only reported once a scan comes back
+//! unchanged, so an editor saving several files (or one file twice) causes
+//! a single reload.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Directory levels below the root that are scanned.
+const MAX_DEPTH: usize = 8;
+
+/// Modification time of every watched file under a root.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Snapshot(BTreeMap<PathBuf, SystemTime>);
+
+impl Snapshot {
+  pub fn scan(root: &Path) -> Self {
+    let mut files = BTreeMap::new();
+    scan_dir(root, 0, &mut files);
+    Snapshot(files)
+  }
+}
+
+fn scan_dir(
+  dir: &Path,
+  depth: usize,
+  files: &mut BTreeMap<PathBuf, SystemTime>,
+) {
+  let Ok(entries) = fs::read_dir(dir) else {
+    return;
+  };
+  for entry in entries.flatten() {
+    let name = entry.file_name();
+    let name = name.to_string_lossy();
+    if name.starts_with('.') || name == "node_modules" {
+      continue;
+    }
+    let Ok(meta) = entry.metadata() else {
+      continue;
+    };
+    if meta.is_dir() {
+      if depth < MAX_DEPTH {
+        scan_dir(&entry.path(), depth + 1, files);
+      }
+    } else if let Ok(modified) = meta.modified() {
+      files.insert(entry.path(), modified);
+    }
+  }
+}
+
+pub struct ChangeDebouncer {
+  root: PathBuf,
+  last: Snapshot,
+  pending: bool,
+}
+
+impl ChangeDebouncer {
+  pub fn new(root: PathBuf) -> Self {
+    let last = Snapshot::scan(&root);
+    Self {
+      root,
+      last,
+      pending: false,
+    }
+  }
+
+  /// Rescan; true once the tree has changed and then stayed the same for
+  /// one poll.
+  pub fn poll(&mut self) -> bool {
+    let now = Snapshot::scan(&self.root);
+    if now != self.last {
+      self.last = now;
+      self.pending = true;
+      false
+    } else {
+      std::mem::take(&mut self.pending)
+    }
+  }
+}
+
+/// Filesystem path of a main service that can be watched: a `file://`
+/// module, not an eszip bundle.
+pub fn watched_entrypoint(main_service_path: &str) -> Option<PathBuf> {
+  if main_service_path.ends_with(".eszip") {
+    return None;
+  }
+  let path = main_service_path
+    .strip_prefix("file://")
+    .unwrap_or(main_service_path);
+  Some(PathBuf::from(path))
+}
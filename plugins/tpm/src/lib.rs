@@ -334,6 +334,7 @@ struct TpmInstallDepsBindData {
   package_spec: String,
   install_dir: String,
   registry_url: Option<String>,
+  install_peers: bool,
 }
 
 #[repr(C)]
@@ -358,10 +359,15 @@ impl VTab for TpmInstallDepsVTab {
     let package_spec = bind.get_parameter(0).to_string();
     let install_dir = bind.get_parameter(1).to_string();
     let registry_url = std::env::var("TPM_REGISTRY_URL").ok();
+    let install_peers = bind
+      .get_named_parameter("install_peers")
+      .map(|value| value.to_string().eq_ignore_ascii_case("true"))
+      .unwrap_or(false);
     Ok(TpmInstallDepsBindData {
       package_spec,
       install_dir,
       registry_url,
+      install_peers,
     })
   }
 
@@ -377,6 +383,7 @@ impl VTab for TpmInstallDepsVTab {
       registry.install_package_with_deps(
         &(*bind_data).package_spec,
         &(*bind_data).install_dir,
+        (*bind_data).install_peers,
       )?
     };
 
@@ -416,6 +423,13 @@ impl VTab for TpmInstallDepsVTab {
       LogicalTypeHandle::from(LogicalTypeId::Varchar),
     ])
   }
+
+  fn named_parameters() -> Option<Vec<(String, LogicalTypeHandle)>> {
+    Some(vec![(
+      "install_peers".to_string(),
+      LogicalTypeHandle::from(LogicalTypeId::Boolean),
+    )])
+  }
 }
 
 #[repr(C)]
@@ -731,6 +745,144 @@ impl VTab for TpmDeleteVTab {
   }
 }
 
+#[repr(C)]
+struct TpmUpdateBindData {
+  package_name: Option<String>,
+  install_dir: String,
+  registry_url: Option<String>,
+}
+
+#[repr(C)]
+struct TpmUpdateInitData {
+  results: Vec<npm::UpdateResponse>,
+  index: std::sync::atomic::AtomicUsize,
+}
+
+fn update_init(
+  bind_data: &TpmUpdateBindData,
+) -> Result<TpmUpdateInitData, Box<dyn std::error::Error>> {
+  let registry =
+    npm::NpmRegistry::with_registry_url(bind_data.registry_url.clone())?;
+  let results = registry.update_packages(
+    &bind_data.install_dir,
+    bind_data.package_name.as_deref(),
+  )?;
+
+  Ok(TpmUpdateInitData {
+    results,
+    index: std::sync::atomic::AtomicUsize::new(0),
+  })
+}
+
+fn update_func(
+  init_data: &TpmUpdateInitData,
+  output: &mut DataChunkHandle,
+) -> Result<(), Box<dyn std::error::Error>> {
+  let current_index = init_data
+    .index
+    .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+  if current_index >= init_data.results.len() {
+    output.set_len(0);
+    return Ok(());
+  }
+
+  let result = &init_data.results[current_index];
+  let json = serde_json::to_string(result)?;
+  let vector = output.flat_vector(0);
+  let cstring = CString::new(json)?;
+  vector.insert(0, cstring);
+  output.set_len(1);
+
+  Ok(())
+}
+
+struct TpmUpdateVTab;
+
+impl VTab for TpmUpdateVTab {
+  type InitData = TpmUpdateInitData;
+  type BindData = TpmUpdateBindData;
+
+  fn bind(
+    bind: &BindInfo,
+  ) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+    bind.add_result_column(
+      "update_results",
+      LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    );
+    let install_dir = bind.get_parameter(0).to_string();
+    let registry_url = std::env::var("TPM_REGISTRY_URL").ok();
+    Ok(TpmUpdateBindData {
+      package_name: None,
+      install_dir,
+      registry_url,
+    })
+  }
+
+  fn init(
+    init: &InitInfo,
+  ) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+    let bind_data = init.get_bind_data::<Self::BindData>();
+    unsafe { update_init(&*bind_data) }
+  }
+
+  fn func(
+    func: &TableFunctionInfo<Self>,
+    output: &mut DataChunkHandle,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    update_func(func.get_init_data(), output)
+  }
+
+  fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+    Some(vec![LogicalTypeHandle::from(LogicalTypeId::Varchar)])
+  }
+}
+
+struct TpmUpdatePackageVTab;
+
+impl VTab for TpmUpdatePackageVTab {
+  type InitData = TpmUpdateInitData;
+  type BindData = TpmUpdateBindData;
+
+  fn bind(
+    bind: &BindInfo,
+  ) -> Result<Self::BindData, Box<dyn std::error::Error>> {
+    bind.add_result_column(
+      "update_results",
+      LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    );
+    let package_name = bind.get_parameter(0).to_string();
+    let install_dir = bind.get_parameter(1).to_string();
+    let registry_url = std::env::var("TPM_REGISTRY_URL").ok();
+    Ok(TpmUpdateBindData {
+      package_name: Some(package_name),
+      install_dir,
+      registry_url,
+    })
+  }
+
+  fn init(
+    init: &InitInfo,
+  ) -> Result<Self::InitData, Box<dyn std::error::Error>> {
+    let bind_data = init.get_bind_data::<Self::BindData>();
+    unsafe { update_init(&*bind_data) }
+  }
+
+  fn func(
+    func: &TableFunctionInfo<Self>,
+    output: &mut DataChunkHandle,
+  ) -> Result<(), Box<dyn std::error::Error>> {
+    update_func(func.get_init_data(), output)
+  }
+
+  fn parameters() -> Option<Vec<LogicalTypeHandle>> {
+    Some(vec![
+      LogicalTypeHandle::from(LogicalTypeId::Varchar),
+      LogicalTypeHandle::from(LogicalTypeId::Varchar),
+    ])
+  }
+}
+
 const EXTENSION_NAME: &str = env!("CARGO_PKG_NAME");
 
 #[duckdb_entrypoint_c_api()]
@@ -776,5 +928,13 @@ pub unsafe fn extension_entrypoint(
     .register_table_function::<TpmDeleteVTab>("trex_plugin_delete")
     .expect("Failed to register trex_plugin_delete table function");
 
+  con
+    .register_table_function::<TpmUpdateVTab>("trex_plugin_update")
+    .expect("Failed to register trex_plugin_update table function");
+
+  con
+    .register_table_function::<TpmUpdatePackageVTab>("trex_plugin_update_package")
+    .expect("Failed to register trex_plugin_update_package table function");
+
   Ok(())
 }
@@ -0,0 +1,163 @@
+use semver::Version;
+use std::path::{Path, PathBuf};
+
+use super::registry::validate_package_name;
+
+// Local copy of registry metadata documents and tarballs, one directory per
+// registry and laid out like an install dir below it:
+//
+//   <cache_dir>/registry.npmjs.org/@scope/name/metadata.json
+//   <cache_dir>/registry.npmjs.org/@scope/name/1.4.2.tgz
+//
+// Two registries can publish different packages under the same name, so
+// one registry's copy is never served for another's. Online lookups fill
+// it; with TPM_OFFLINE set it is the only source.
+pub struct RegistryCache {
+  dir: PathBuf,
+}
+
+impl RegistryCache {
+  pub fn new(dir: PathBuf) -> Self {
+    Self { dir }
+  }
+
+  // The cache of `registry_url` in TPM_CACHE_DIR, else in `tpm` under
+  // XDG_CACHE_HOME or ~/.cache. None when the URL has no host.
+  pub fn from_env(registry_url: &str) -> Option<Self> {
+    let dir = std::env::var_os("TPM_CACHE_DIR")
+      .map(PathBuf::from)
+      .or_else(|| {
+        std::env::var_os("XDG_CACHE_HOME").map(|d| PathBuf::from(d).join("tpm"))
+      })
+      .or_else(|| {
+        std::env::var_os("HOME").map(|d| PathBuf::from(d).join(".cache/tpm"))
+      })
+      .unwrap_or_else(|| std::env::temp_dir().join("tpm-cache"));
+    Some(Self::new(dir.join(registry_dir(registry_url)?)))
+  }
+
+  pub fn dir(&self) -> &Path {
+    &self.dir
+  }
+
+  // None for names that must not be joined into a path.
+  fn package_dir(&self, name: &str) -> Option<PathBuf> {
+    validate_package_name(name).ok()?;
+    Some(name.split('/').fold(self.dir.clone(), |dir, part| dir.join(part)))
+  }
+
+  fn tarball_path(&self, name: &str, version: &str) -> Option<PathBuf> {
+    // Registry-supplied; only a valid semver version is safe as a file name.
+    Version::parse(version).ok()?;
+    Some(self.package_dir(name)?.join(format!("{}.tgz", version)))
+  }
+
+  pub fn read_metadata(&self, name: &str) -> Option<String> {
+    std::fs::read_to_string(self.package_dir(name)?.join("metadata.json")).ok()
+  }
+
+  pub fn write_metadata(&self, name: &str, text: &str) {
+    if let Some(dir) = self.package_dir(name) {
+      write_cache_file(&dir.join("metadata.json"), text.as_bytes());
+    }
+  }
+
+  pub fn read_tarball(&self, name: &str, version: &str) -> Option<Vec<u8>> {
+    std::fs::read(self.tarball_path(name, version)?).ok()
+  }
+
+  pub fn write_tarball(&self, name: &str, version: &str, bytes: &[u8]) {
+    if let Some(path) = self.tarball_path(name, version) {
+      write_cache_file(&path, bytes);
+    }
+  }
+}
+
+// `host[_port]/path` of a registry URL. The URL parser has already
+// resolved `.` and `..` segments, so the result stays inside the cache.
+fn registry_dir(registry_url: &str) -> Option<PathBuf> {
+  let url = reqwest::Url::parse(registry_url).ok()?;
+  let host = url.host_str().filter(|host| !host.is_empty())?;
+  let mut dir = PathBuf::from(match url.port() {
+    Some(port) => format!("{}_{}", host, port),
+    None => host.to_string(),
+  });
+  for segment in url.path_segments().into_iter().flatten() {
+    if !segment.is_empty() {
+      dir.push(segment);
+    }
+  }
+  Some(dir)
+}
+
+// Best effort: a cache that can't be written only costs a later download.
+// Written under a temporary name first so a reader never sees half a file.
+fn write_cache_file(path: &Path, bytes: &[u8]) {
+  let Some(parent) = path.parent() else {
+    return;
+  };
+  if std::fs::create_dir_all(parent).is_err() {
+    return;
+  }
+  let tmp = path.with_extension("partial");
+  if std::fs::write(&tmp, bytes).is_ok() && std::fs::rename(&tmp, path).is_err()
+  {
+    let _ = std::fs::remove_file(&tmp);
+  }
+}
+
+// TPM_OFFLINE=1 (or `true`) resolves and installs from the cache only.
+pub fn offline_from_env() -> bool {
+  std::env::var("TPM_OFFLINE")
+    .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn registries_get_their_own_directory() {
+    assert_eq!(
+      registry_dir("https://registry.npmjs.org"),
+      Some(PathBuf::from("registry.npmjs.org"))
+    );
+    assert_eq!(
+      registry_dir("http://localhost:4873/"),
+      Some(PathBuf::from("localhost_4873"))
+    );
+    assert_eq!(
+      registry_dir("https://repo.example.com/api/npm/../npm-local"),
+      Some(PathBuf::from("repo.example.com/api/npm-local"))
+    );
+    assert_eq!(registry_dir("not a url"), None);
+    assert_eq!(registry_dir("file:///tmp/registry"), None);
+  }
+
+  #[test]
+  fn packages_are_laid_out_like_an_install_dir() {
+    let cache = RegistryCache::new(PathBuf::from("/cache/registry.npmjs.org"));
+    assert_eq!(
+      cache.tarball_path("@trex/my-plugin", "1.4.2"),
+      Some(PathBuf::from(
+        "/cache/registry.npmjs.org/@trex/my-plugin/1.4.2.tgz"
+      ))
+    );
+    assert_eq!(
+      cache.package_dir("left-pad"),
+      Some(PathBuf::from("/cache/registry.npmjs.org/left-pad"))
+    );
+  }
+
+  #[test]
+  fn unsafe_names_and_versions_have_no_path() {
+    let cache = RegistryCache::new(PathBuf::from("/cache"));
+    for name in ["", "../escape", "@scope/../x", "a/b", "a\\b", "@trex/x/y"] {
+      assert_eq!(cache.package_dir(name), None, "{}", name);
+    }
+    for version in ["1.0", "../../1.0.0", "latest", "1.0.0/../x"] {
+      assert_eq!(cache.tarball_path("left-pad", version), None, "{}", version);
+    }
+  }
+}
@@ -0,0 +1,90 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::types::{NpmError, NpmResult};
+
+// Written to the root of an install dir. It is a file, so trex_plugin_list,
+// which only reads package directories, skips it.
+pub const LOCKFILE_NAME: &str = "tpm-lock.json";
+
+const LOCKFILE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Lockfile {
+  #[serde(rename = "lockfileVersion")]
+  pub lockfile_version: u32,
+  #[serde(default)]
+  pub packages: BTreeMap<String, LockedPackage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LockedPackage {
+  pub version: String,
+  // The range the package was requested with; updates stay inside it.
+  pub range: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub shasum: Option<String>,
+}
+
+impl Default for Lockfile {
+  fn default() -> Self {
+    Self {
+      lockfile_version: LOCKFILE_VERSION,
+      packages: BTreeMap::new(),
+    }
+  }
+}
+
+fn lockfile_path(install_dir: &str) -> PathBuf {
+  Path::new(install_dir).join(LOCKFILE_NAME)
+}
+
+impl Lockfile {
+  // An install dir without a lockfile has an empty one.
+  pub fn load(install_dir: &str) -> NpmResult<Self> {
+    let path = lockfile_path(install_dir);
+    if !path.exists() {
+      return Ok(Self::default());
+    }
+    let content = std::fs::read_to_string(&path).map_err(|e| {
+      NpmError::Other(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    Ok(serde_json::from_str(&content)?)
+  }
+
+  pub fn save(&self, install_dir: &str) -> NpmResult<()> {
+    let path = lockfile_path(install_dir);
+    let content = serde_json::to_string_pretty(self)?;
+    std::fs::write(&path, content).map_err(|e| {
+      NpmError::Other(format!("Failed to write {}: {}", path.display(), e))
+    })
+  }
+
+  // Range to update `package` within: the recorded one, or a caret range
+  // on the installed version for packages installed before the lockfile.
+  pub fn range_for(&self, package: &str, installed_version: &str) -> String {
+    self
+      .packages
+      .get(package)
+      .map(|locked| locked.range.clone())
+      .unwrap_or_else(|| format!("^{}", installed_version))
+  }
+}
+
+// The range to record for a package installed from `requested`. Like
+// `npm install`, an unversioned or `latest` install records a caret range
+// on the version it resolved to, so later updates stay within its major.
+pub fn recorded_range(requested: &str, resolved_version: &str) -> String {
+  if requested.is_empty() || requested == "latest" {
+    format!("^{}", resolved_version)
+  } else {
+    requested.to_string()
+  }
+}
+
+// An exact version pins the package; updates leave it alone.
+pub fn is_pinned(range: &str) -> bool {
+  Version::parse(range).is_ok()
+}
@@ -1,3 +1,6 @@
+pub mod cache;
+pub mod lockfile;
+pub mod peers;
 pub mod registry;
 pub mod types;
 
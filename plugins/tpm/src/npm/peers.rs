@@ -0,0 +1,67 @@
+use semver::Version;
+use std::collections::HashMap;
+
+use super::registry::npm_range_matches;
+use super::types::NpmVersionMetadata;
+
+// The peers a version requires, leaving out the ones its
+// `peerDependenciesMeta` marks optional.
+pub fn required_peers(meta: &NpmVersionMetadata) -> HashMap<String, String> {
+  meta
+    .peer_dependencies
+    .iter()
+    .filter(|(name, _)| {
+      !meta
+        .peer_dependencies_meta
+        .get(*name)
+        .is_some_and(|m| m.optional)
+    })
+    .map(|(name, range)| (name.clone(), range.clone()))
+    .collect()
+}
+
+// The peers in `peers` that `available` (package name to version) leaves
+// unmet, in name order, with their range and the version available if any.
+pub fn unmet_peers<'a>(
+  peers: &'a HashMap<String, String>,
+  available: &'a HashMap<String, String>,
+) -> Vec<(&'a str, &'a str, Option<&'a str>)> {
+  let mut names: Vec<&String> = peers.keys().collect();
+  names.sort();
+  names
+    .into_iter()
+    .filter_map(|peer| {
+      let range = peers[peer].as_str();
+      match available.get(peer) {
+        None => Some((peer.as_str(), range, None)),
+        Some(version) => {
+          let satisfied = Version::parse(version)
+            .ok()
+            .and_then(|v| npm_range_matches(range, &v))
+            .unwrap_or(true);
+          (!satisfied).then_some((peer.as_str(), range, Some(version.as_str())))
+        }
+      }
+    })
+    .collect()
+}
+
+pub fn peer_warnings(
+  package: &str,
+  peers: &HashMap<String, String>,
+  available: &HashMap<String, String>,
+) -> Vec<String> {
+  unmet_peers(peers, available)
+    .into_iter()
+    .map(|(peer, range, version)| match version {
+      None => format!(
+        "{} requires peer {}@{}, which is missing",
+        package, peer, range
+      ),
+      Some(version) => format!(
+        "{} requires peer {}@{}, but {}@{} is installed",
+        package, peer, range, peer, version
+      ),
+    })
+    .collect()
+}
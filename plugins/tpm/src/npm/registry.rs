@@ -4,12 +4,26 @@ use sha1::{Digest, Sha1};
 use std::path::{Component, Path};
 use std::time::Duration;
 
+use super::cache::{offline_from_env, RegistryCache};
+use super::lockfile::{is_pinned, recorded_range, LockedPackage, Lockfile};
+use super::peers::{peer_warnings, required_peers, unmet_peers};
 use super::types::{
   DeleteResponse, DependencyTreeResponse, InstallResponse, ListResponse,
   NpmError, NpmPackageMetadata, NpmResult, NpmVersionMetadataExt,
-  PackageInfoResponse, ResolveResponse, SeedResponse,
+  PackageInfoResponse, ResolveResponse, SeedResponse, UpdateResponse,
 };
 
+// Split `name@range` into its name and range; a bare name asks for latest.
+fn split_package_spec(package_spec: &str) -> (&str, &str) {
+  match package_spec.rfind('@') {
+    Some(pos) if pos > 0 => {
+      let (n, v) = package_spec.split_at(pos);
+      (n, &v[1..])
+    }
+    _ => (package_spec, "latest"),
+  }
+}
+
 // Strict npm package-name validation. Reachable from SQL via
 // trex_plugin_install / trex_plugin_delete, so the value must never be
 // interpolated into a filesystem path without being checked here.
@@ -23,7 +37,7 @@ fn is_valid_npm_segment(s: &str) -> bool {
       .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_')
 }
 
-fn validate_package_name(name: &str) -> NpmResult<()> {
+pub(super) fn validate_package_name(name: &str) -> NpmResult<()> {
   if name.is_empty()
     || name.len() > 250
     || name.contains('\0')
@@ -95,9 +109,229 @@ fn assert_path_contained(root: &Path, child: &Path) -> NpmResult<()> {
   }
 }
 
+// Extract a verified tarball into `install_dir`, returning the package's
+// directory. With `replace`, the tarball is extracted beside the installed
+// version and swapped in by rename, so none of the old files outlive the
+// upgrade and a bad archive leaves the installed version untouched.
+fn unpack_package(
+  package: &str,
+  install_dir: &str,
+  tarball_bytes: Vec<u8>,
+  replace: bool,
+) -> NpmResult<std::path::PathBuf> {
+  // Even though `package` came from a registry lookup, validate it before
+  // mixing it into a filesystem path — the registry URL is itself
+  // configurable (TPM_REGISTRY_URL) and could be attacker-controlled.
+  validate_package_name(package)?;
+
+  let package_dir = if package.starts_with('@') {
+    let parts: Vec<&str> = package.splitn(2, '/').collect();
+    if parts.len() == 2 {
+      std::path::Path::new(install_dir).join(parts[0]).join(parts[1])
+    } else {
+      std::path::Path::new(install_dir).join(package)
+    }
+  } else {
+    std::path::Path::new(install_dir).join(package)
+  };
+
+  if !replace {
+    extract_tarball(install_dir, &package_dir, tarball_bytes)?;
+    return Ok(package_dir);
+  }
+
+  // Package names never start with '.', so these can't clash with one, and
+  // listing skips them.
+  let name = package_dir
+    .file_name()
+    .and_then(|n| n.to_str())
+    .unwrap_or(package);
+  let parent = package_dir.parent().unwrap_or(Path::new(install_dir));
+  let staging = parent.join(format!(".{}.tpm-staging", name));
+  let previous = parent.join(format!(".{}.tpm-previous", name));
+  // Left behind by an update that was interrupted.
+  for leftover in [&staging, &previous] {
+    if leftover.exists() {
+      assert_path_contained(Path::new(install_dir), leftover)?;
+      std::fs::remove_dir_all(leftover).map_err(|e| {
+        NpmError::Other(format!(
+          "Failed to remove {}: {}",
+          leftover.display(),
+          e
+        ))
+      })?;
+    }
+  }
+
+  if let Err(e) = extract_tarball(install_dir, &staging, tarball_bytes) {
+    let _ = std::fs::remove_dir_all(&staging);
+    return Err(e);
+  }
+  if package_dir.exists() {
+    assert_path_contained(Path::new(install_dir), &package_dir)?;
+    if let Err(e) = std::fs::rename(&package_dir, &previous) {
+      let _ = std::fs::remove_dir_all(&staging);
+      return Err(NpmError::Other(format!(
+        "Failed to move aside {}: {}",
+        package_dir.display(),
+        e
+      )));
+    }
+  }
+  if let Err(e) = std::fs::rename(&staging, &package_dir) {
+    if previous.exists() {
+      let _ = std::fs::rename(&previous, &package_dir);
+    }
+    let _ = std::fs::remove_dir_all(&staging);
+    return Err(NpmError::Other(format!(
+      "Failed to install {}: {}",
+      package_dir.display(),
+      e
+    )));
+  }
+  // The new version is in place; a failure here only leaves a hidden
+  // directory for the next update to clear.
+  let _ = std::fs::remove_dir_all(&previous);
+  Ok(package_dir)
+}
+
+// Unpack the `package/` contents of a tarball into `dest`, which must lie
+// within `install_dir`.
+fn extract_tarball(
+  install_dir: &str,
+  dest: &Path,
+  tarball_bytes: Vec<u8>,
+) -> NpmResult<()> {
+  std::fs::create_dir_all(dest).map_err(|e| {
+    NpmError::Other(format!("Failed to create install directory: {}", e))
+  })?;
+
+  // After mkdir we can canonicalize and confirm containment within install_dir.
+  assert_path_contained(std::path::Path::new(install_dir), dest)?;
+
+  use flate2::read::GzDecoder;
+  use std::io::Cursor;
+
+  let cursor = Cursor::new(tarball_bytes);
+  let decoder = GzDecoder::new(cursor);
+  let mut archive = tar::Archive::new(decoder);
+
+  archive.set_preserve_permissions(true);
+  archive.set_preserve_mtime(true);
+  archive.set_unpack_xattrs(false);
+
+  for entry in archive
+    .entries()
+    .map_err(|e| NpmError::Other(e.to_string()))?
+  {
+    let mut entry = entry.map_err(|e| NpmError::Other(e.to_string()))?;
+    let path = entry.path().map_err(|e| NpmError::Other(e.to_string()))?;
+
+    let stripped_path = path.strip_prefix("package").unwrap_or(&path);
+
+    // Reject any tar entry that would escape dest: absolute paths,
+    // parent components, or root/prefix components. The tar crate's
+    // Entry::unpack() does NOT validate this on its own (per its docs).
+    if stripped_path.is_absolute()
+      || stripped_path.components().any(|c| {
+        matches!(
+          c,
+          Component::ParentDir | Component::Prefix(_) | Component::RootDir
+        )
+      })
+    {
+      return Err(NpmError::Other(format!(
+        "Refusing tar entry with unsafe path: {}",
+        stripped_path.display()
+      )));
+    }
+
+    let dest_path = dest.join(stripped_path);
+
+    if let Some(parent) = dest_path.parent() {
+      if let Err(e) = std::fs::create_dir_all(parent) {
+        if e.kind() != std::io::ErrorKind::AlreadyExists {
+          return Err(NpmError::Other(format!(
+            "Failed to create directory {}: {}",
+            parent.display(),
+            e
+          )));
+        }
+      }
+    }
+
+    if let Err(e) = entry.unpack(&dest_path) {
+      if e.kind() == std::io::ErrorKind::NotFound {
+        use std::io::{Read, Write};
+
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content).map_err(|e| {
+          NpmError::Other(format!("Failed to read entry content: {}", e))
+        })?;
+
+        let mut file = std::fs::File::create(&dest_path).map_err(|e| {
+          NpmError::Other(format!(
+            "Failed to create file {}: {}",
+            dest_path.display(),
+            e
+          ))
+        })?;
+
+        file.write_all(&content).map_err(|e| {
+          NpmError::Other(format!(
+            "Failed to write file {}: {}",
+            dest_path.display(),
+            e
+          ))
+        })?;
+      } else {
+        return Err(NpmError::Other(format!(
+          "Failed to extract file {}: {}",
+          dest_path.display(),
+          e
+        )));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+// Whether `version` satisfies an npm range. npm separates alternatives with
+// `||` and comparators with spaces, where the semver crate wants commas.
+// None when the range can't be read.
+pub(super) fn npm_range_matches(range: &str, version: &Version) -> Option<bool> {
+  let mut matched = false;
+  for alternative in range.split("||") {
+    let comparators: Vec<&str> = alternative.split_whitespace().collect();
+    let req = match comparators.as_slice() {
+      [] | ["*"] | ["x"] | ["latest"] => return Some(true),
+      [low, "-", high] => format!(">={}, <={}", low, high),
+      // npm reads a bare `1.2` as `1.2.x`; the semver crate as `^1.2`.
+      [partial] if is_major_minor(partial) => format!("~{}", partial),
+      _ => comparators.join(", "),
+    };
+    matched |= VersionReq::parse(&req).ok()?.matches(version);
+  }
+  Some(matched)
+}
+
+fn is_major_minor(s: &str) -> bool {
+  let mut parts = s.split('.');
+  matches!(
+    (parts.next(), parts.next(), parts.next()),
+    (Some(major), Some(minor), None)
+      if [major, minor]
+        .iter()
+        .all(|p| !p.is_empty() && p.chars().all(|c| c.is_ascii_digit()))
+  )
+}
+
 pub struct NpmRegistry {
   client: Client,
   registry_url: String,
+  cache: RegistryCache,
+  offline: bool,
 }
 
 impl NpmRegistry {
@@ -116,15 +350,37 @@ impl NpmRegistry {
       .build()
       .map_err(|e| NpmError::Network(e.to_string()))?;
 
+    let cache = RegistryCache::from_env(&registry_url).ok_or_else(|| {
+      NpmError::Other(format!("Invalid registry URL: {}", registry_url))
+    })?;
+
     Ok(Self {
       client,
       registry_url,
+      cache,
+      offline: offline_from_env(),
     })
   }
 
-  pub fn get_package_info(&self, name: &str) -> NpmResult<PackageInfoResponse> {
-    let url = format!("{}/{}", self.registry_url, name);
+  fn not_cached(&self, what: String) -> NpmError {
+    NpmError::Other(format!(
+      "{} is not in the offline cache at {} (TPM_OFFLINE is set)",
+      what,
+      self.cache.dir().display()
+    ))
+  }
 
+  // The registry's metadata document for `name`. Online it is fetched and
+  // cached; offline only the cached copy is used.
+  fn fetch_metadata(&self, name: &str) -> NpmResult<String> {
+    if self.offline {
+      return self
+        .cache
+        .read_metadata(name)
+        .ok_or_else(|| self.not_cached(format!("Package {}", name)));
+    }
+
+    let url = format!("{}/{}", self.registry_url, name);
     let response = self.client.get(&url).send()?;
 
     if response.status() == 404 {
@@ -140,6 +396,12 @@ impl NpmRegistry {
     }
 
     let text = response.text()?;
+    self.cache.write_metadata(name, &text);
+    Ok(text)
+  }
+
+  pub fn get_package_info(&self, name: &str) -> NpmResult<PackageInfoResponse> {
+    let text = self.fetch_metadata(name)?;
     let metadata: NpmPackageMetadata = serde_json::from_str(&text)?;
 
     let latest_version = metadata.dist_tags.get("latest").cloned();
@@ -196,55 +458,27 @@ impl NpmRegistry {
     &self,
     package_spec: &str,
   ) -> NpmResult<ResolveResponse> {
-    let (name, version_req) = if let Some(pos) = package_spec.rfind('@') {
-      if pos == 0 {
-        (package_spec, "latest")
-      } else {
-        let (n, v) = package_spec.split_at(pos);
-        (n, &v[1..])
-      }
-    } else {
-      (package_spec, "latest")
-    };
-
-    let url = format!("{}/{}", self.registry_url, name);
-    let response = self.client.get(&url).send()?;
-
-    if response.status() == 404 {
-      return Err(NpmError::PackageNotFound(name.to_string()));
-    }
-
-    if !response.status().is_success() {
-      return Err(NpmError::Network(format!(
-        "HTTP {} for package {}",
-        response.status(),
-        name
-      )));
-    }
+    let (name, version_req) = split_package_spec(package_spec);
 
-    let text = response.text()?;
+    let text = self.fetch_metadata(name)?;
     let metadata: NpmPackageMetadata = serde_json::from_str(&text)?;
 
-    let resolved_version = if version_req == "latest" || version_req.is_empty()
+    let version_req = if version_req.is_empty() {
+      "latest"
+    } else {
+      version_req
+    };
+
+    // A dist-tag (`latest`, `beta`, `next`, ...) names a version outright;
+    // tags take precedence, as in npm.
+    let resolved_version = if let Some(tagged) =
+      metadata.dist_tags.get(version_req)
     {
-      metadata
-        .dist_tags
-        .get("latest")
-        .ok_or_else(|| NpmError::Other("No latest tag found".to_string()))?
-        .clone()
-    } else if version_req.starts_with('^')
-      || version_req.starts_with('~')
-      || version_req.contains('*')
-      || version_req.contains('>')
-      || version_req.contains('<')
+      tagged.clone()
+    } else if Version::parse(version_req).is_ok() {
+      version_req.to_string()
+    } else if npm_range_matches(version_req, &Version::new(0, 0, 0)).is_some()
     {
-      let req = VersionReq::parse(version_req).map_err(|e| {
-        NpmError::Other(format!(
-          "Invalid semver requirement '{}': {}",
-          version_req, e
-        ))
-      })?;
-
       let mut versions: Vec<(Version, String)> = metadata
         .versions
         .keys()
@@ -257,7 +491,7 @@ impl NpmRegistry {
 
       versions
         .into_iter()
-        .find(|(v, _)| req.matches(v))
+        .find(|(v, _)| npm_range_matches(version_req, v) == Some(true))
         .map(|(_, s)| s)
         .ok_or_else(|| {
           NpmError::Other(format!(
@@ -266,7 +500,15 @@ impl NpmRegistry {
           ))
         })?
     } else {
-      version_req.to_string()
+      let mut tags: Vec<&str> =
+        metadata.dist_tags.keys().map(String::as_str).collect();
+      tags.sort();
+      return Err(NpmError::Other(format!(
+        "'{}' is neither a dist-tag of {} ({}) nor a semver version or range",
+        version_req,
+        name,
+        tags.join(", ")
+      )));
     };
 
     let version_meta =
@@ -289,6 +531,7 @@ impl NpmRegistry {
       resolved_version: version_meta.version.clone(),
       tarball_url: dist.tarball.clone(),
       dependencies: version_meta.dependencies.clone(),
+      peer_dependencies: required_peers(version_meta),
       shasum: Some(dist.shasum.clone()),
     })
   }
@@ -300,165 +543,129 @@ impl NpmRegistry {
   ) -> NpmResult<InstallResponse> {
     let resolved = self.resolve_package(package_spec)?;
 
-    let shasum = resolved.shasum.clone();
-
-    let tarball_response = self.client.get(&resolved.tarball_url).send()?;
-
-    if !tarball_response.status().is_success() {
-      return Ok(InstallResponse {
-        package: resolved.package.clone(),
-        version: resolved.resolved_version.clone(),
-        install_path: String::new(),
-        success: false,
-        error: Some(format!(
-          "Failed to download tarball: HTTP {}",
-          tarball_response.status()
-        )),
-      });
-    }
-
-    let tarball_bytes = tarball_response.bytes()?;
-
-    if let Some(ref expected_shasum) = shasum {
-      if let Err(e) = self.verify_integrity(&tarball_bytes, expected_shasum) {
+    let tarball_bytes = match self.download_verified(&resolved)? {
+      Ok(bytes) => bytes,
+      Err(error) => {
         return Ok(InstallResponse {
           package: resolved.package.clone(),
           version: resolved.resolved_version.clone(),
           install_path: String::new(),
           success: false,
-          error: Some(e.to_string()),
+          error: Some(error),
+          peer_warnings: Vec::new(),
         });
       }
-    }
-
-    // Even though `resolved.package` came from a registry lookup, validate it
-    // before mixing it into a filesystem path — the registry URL is itself
-    // configurable (TPM_REGISTRY_URL) and could be attacker-controlled.
-    validate_package_name(&resolved.package)?;
-
-    let package_dir = if resolved.package.starts_with('@') {
-      let parts: Vec<&str> = resolved.package.splitn(2, '/').collect();
-      if parts.len() == 2 {
-        std::path::Path::new(install_dir).join(parts[0]).join(parts[1])
-      } else {
-        std::path::Path::new(install_dir).join(&resolved.package)
-      }
-    } else {
-      std::path::Path::new(install_dir).join(&resolved.package)
     };
 
-    std::fs::create_dir_all(&package_dir).map_err(|e| {
-      NpmError::Other(format!("Failed to create install directory: {}", e))
-    })?;
-
-    // After mkdir we can canonicalize and confirm containment within install_dir.
-    assert_path_contained(std::path::Path::new(install_dir), &package_dir)?;
-
-    use flate2::read::GzDecoder;
-    use std::io::Cursor;
+    let package_dir =
+      unpack_package(&resolved.package, install_dir, tarball_bytes, false)?;
 
-    let cursor = Cursor::new(tarball_bytes);
-    let decoder = GzDecoder::new(cursor);
-    let mut archive = tar::Archive::new(decoder);
+    let (_, requested) = split_package_spec(package_spec);
+    let mut lock = Lockfile::load(install_dir)?;
+    lock.packages.insert(
+      resolved.package.clone(),
+      LockedPackage {
+        version: resolved.resolved_version.clone(),
+        range: recorded_range(requested, &resolved.resolved_version),
+        shasum: resolved.shasum.clone(),
+      },
+    );
+    lock.save(install_dir)?;
 
-    archive.set_preserve_permissions(true);
-    archive.set_preserve_mtime(true);
-    archive.set_unpack_xattrs(false);
+    Ok(InstallResponse {
+      package: resolved.package,
+      version: resolved.resolved_version,
+      install_path: package_dir.to_string_lossy().to_string(),
+      success: true,
+      error: None,
+      peer_warnings: Vec::new(),
+    })
+  }
 
-    for entry in archive
-      .entries()
-      .map_err(|e| NpmError::Other(e.to_string()))?
+  // The tarball of `resolved` with its shasum checked, from the cache when
+  // it holds a copy that verifies and downloaded (and cached) otherwise. The
+  // inner `Err` is a failed download or integrity check, reported per
+  // package.
+  fn download_verified(
+    &self,
+    resolved: &ResolveResponse,
+  ) -> NpmResult<Result<Vec<u8>, String>> {
+    let verifies = |bytes: &[u8]| match resolved.shasum {
+      Some(ref expected_shasum) => {
+        self.verify_integrity(bytes, expected_shasum).is_ok()
+      }
+      None => true,
+    };
+    if let Some(bytes) = self
+      .cache
+      .read_tarball(&resolved.package, &resolved.resolved_version)
     {
-      let mut entry = entry.map_err(|e| NpmError::Other(e.to_string()))?;
-      let path = entry.path().map_err(|e| NpmError::Other(e.to_string()))?;
-
-      let stripped_path = path.strip_prefix("package").unwrap_or(&path);
-
-      // Reject any tar entry that would escape package_dir: absolute paths,
-      // parent components, or root/prefix components. The tar crate's
-      // Entry::unpack() does NOT validate this on its own (per its docs).
-      if stripped_path.is_absolute()
-        || stripped_path.components().any(|c| {
-          matches!(
-            c,
-            Component::ParentDir | Component::Prefix(_) | Component::RootDir
-          )
-        })
-      {
-        return Err(NpmError::Other(format!(
-          "Refusing tar entry with unsafe path: {}",
-          stripped_path.display()
-        )));
+      if verifies(&bytes) {
+        return Ok(Ok(bytes));
       }
+    }
+    if self.offline {
+      return Ok(Err(
+        self
+          .not_cached(format!(
+            "Tarball of {}@{}",
+            resolved.package, resolved.resolved_version
+          ))
+          .to_string(),
+      ));
+    }
+
+    let tarball_response = self.client.get(&resolved.tarball_url).send()?;
 
-      let dest_path = package_dir.join(stripped_path);
+    if !tarball_response.status().is_success() {
+      return Ok(Err(format!(
+        "Failed to download tarball: HTTP {}",
+        tarball_response.status()
+      )));
+    }
 
-      if let Some(parent) = dest_path.parent() {
-        if let Err(e) = std::fs::create_dir_all(parent) {
-          if e.kind() != std::io::ErrorKind::AlreadyExists {
-            return Err(NpmError::Other(format!(
-              "Failed to create directory {}: {}",
-              parent.display(),
-              e
-            )));
-          }
-        }
-      }
+    let tarball_bytes = tarball_response.bytes()?;
 
-      if let Err(e) = entry.unpack(&dest_path) {
-        if e.kind() == std::io::ErrorKind::NotFound {
-          use std::io::{Read, Write};
-
-          let mut content = Vec::new();
-          entry.read_to_end(&mut content).map_err(|e| {
-            NpmError::Other(format!("Failed to read entry content: {}", e))
-          })?;
-
-          let mut file = std::fs::File::create(&dest_path).map_err(|e| {
-            NpmError::Other(format!(
-              "Failed to create file {}: {}",
-              dest_path.display(),
-              e
-            ))
-          })?;
-
-          file.write_all(&content).map_err(|e| {
-            NpmError::Other(format!(
-              "Failed to write file {}: {}",
-              dest_path.display(),
-              e
-            ))
-          })?;
-        } else {
-          return Err(NpmError::Other(format!(
-            "Failed to extract file {}: {}",
-            dest_path.display(),
-            e
-          )));
-        }
+    if let Some(ref expected_shasum) = resolved.shasum {
+      if let Err(e) = self.verify_integrity(&tarball_bytes, expected_shasum) {
+        return Ok(Err(e.to_string()));
       }
     }
 
-    Ok(InstallResponse {
-      package: resolved.package,
-      version: resolved.resolved_version,
-      install_path: package_dir.to_string_lossy().to_string(),
-      success: true,
-      error: None,
-    })
+    self.cache.write_tarball(
+      &resolved.package,
+      &resolved.resolved_version,
+      &tarball_bytes,
+    );
+    Ok(Ok(tarball_bytes.to_vec()))
   }
 
+  // Peers are not installed unless `install_peers` is set; either way each
+  // installed package lists the peers it requires that the install dir
+  // doesn't satisfy once everything is in place.
   pub fn install_package_with_deps(
     &self,
     package_spec: &str,
     install_dir: &str,
+    install_peers: bool,
   ) -> NpmResult<Vec<InstallResponse>> {
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
+
+    let installed_versions = |dir: &str| -> NpmResult<HashMap<String, String>> {
+      Ok(
+        Self::list_installed_packages(dir)?
+          .into_iter()
+          .map(|p| (p.package, p.version))
+          .collect(),
+      )
+    };
+    let already_installed = installed_versions(install_dir)?;
 
     let mut results = Vec::new();
     let mut to_install: Vec<(String, usize)> =
       vec![(package_spec.to_string(), 0)];
     let mut installed: HashSet<String> = HashSet::new();
+    let mut peers: HashMap<String, HashMap<String, String>> = HashMap::new();
 
     while let Some((spec, depth)) = to_install.pop() {
       let (name, _) = if let Some(pos) = spec.rfind('@') {
@@ -488,6 +695,19 @@ impl NpmRegistry {
                   to_install.push((dep_spec, depth + 1));
                 }
               }
+              if install_peers && depth < 10 {
+                // Peers the install dir already satisfies are kept as is.
+                for (peer_name, peer_range, _) in
+                  unmet_peers(&resolved.peer_dependencies, &already_installed)
+                {
+                  to_install
+                    .push((format!("{}@{}", peer_name, peer_range), depth + 1));
+                }
+              }
+              peers.insert(
+                install_result.package.clone(),
+                resolved.peer_dependencies,
+              );
             }
           }
           results.push(install_result);
@@ -499,11 +719,20 @@ impl NpmRegistry {
             install_path: String::new(),
             success: false,
             error: Some(e.to_string()),
+            peer_warnings: Vec::new(),
           });
         }
       }
     }
 
+    let available = installed_versions(install_dir)?;
+    for result in &mut results {
+      if let Some(required) = peers.get(&result.package) {
+        result.peer_warnings =
+          peer_warnings(&result.package, required, &available);
+      }
+    }
+
     Ok(results)
   }
 
@@ -511,9 +740,10 @@ impl NpmRegistry {
     &self,
     package_spec: &str,
   ) -> NpmResult<Vec<DependencyTreeResponse>> {
-    use std::collections::{HashSet, VecDeque};
+    use std::collections::{HashMap, HashSet, VecDeque};
 
     let mut result = Vec::new();
+    let mut peers: Vec<HashMap<String, String>> = Vec::new();
     let mut to_process: VecDeque<(String, usize, Option<String>)> =
       VecDeque::new();
     let mut processed: HashSet<String> = HashSet::new();
@@ -556,7 +786,9 @@ impl NpmRegistry {
             depth,
             parent: parent.clone(),
             tree_line,
+            peer_warnings: Vec::new(),
           });
+          peers.push(resolved.peer_dependencies.clone());
 
           if depth < 5 && !resolved.dependencies.is_empty() {
             for (dep_name, dep_version) in resolved.dependencies.iter() {
@@ -575,6 +807,15 @@ impl NpmRegistry {
       }
     }
 
+    // Peers are checked against the whole tree, as they would be installed.
+    let available: HashMap<String, String> = result
+      .iter()
+      .map(|node| (node.package.clone(), node.version.clone()))
+      .collect();
+    for (node, required) in result.iter_mut().zip(&peers) {
+      node.peer_warnings = peer_warnings(&node.package, required, &available);
+    }
+
     Ok(result)
   }
 
@@ -627,11 +868,16 @@ impl NpmRegistry {
         }
 
         if let Ok(dir_name) = entry.file_name().into_string() {
+          if dir_name.starts_with('.') {
+            continue;
+          }
           if dir_name.starts_with('@') {
             if let Ok(scoped_entries) = fs::read_dir(&entry_path) {
               for scoped_entry in scoped_entries.flatten() {
                 let scoped_path = scoped_entry.path();
-                if scoped_path.is_dir() {
+                let hidden =
+                  scoped_entry.file_name().to_string_lossy().starts_with('.');
+                if scoped_path.is_dir() && !hidden {
                   try_read_package(&scoped_path, &mut results);
                 }
               }
@@ -684,6 +930,13 @@ impl NpmRegistry {
 
     match std::fs::remove_dir_all(&package_dir) {
       Ok(_) => {
+        // The package is gone either way; a stale lockfile entry only keeps
+        // its recorded range around for a reinstall.
+        if let Ok(mut lock) = Lockfile::load(install_dir) {
+          if lock.packages.remove(package_name).is_some() {
+            let _ = lock.save(install_dir);
+          }
+        }
         if let Some(parent) = package_dir.parent() {
           if parent != std::path::Path::new(install_dir) {
             if let Ok(mut entries) = std::fs::read_dir(parent) {
@@ -711,6 +964,107 @@ impl NpmRegistry {
     }
   }
 
+  // Upgrade the packages installed in `install_dir`, or just `package`, to
+  // the newest version within the range in the lockfile. Packages already at
+  // that version, or pinned to an exact one, are left as they are.
+  pub fn update_packages(
+    &self,
+    install_dir: &str,
+    package: Option<&str>,
+  ) -> NpmResult<Vec<UpdateResponse>> {
+    let mut installed = Self::list_installed_packages(install_dir)?;
+    if let Some(name) = package {
+      validate_package_name(name)?;
+      installed.retain(|p| p.package == name);
+      if installed.is_empty() {
+        return Ok(vec![UpdateResponse {
+          package: name.to_string(),
+          old_version: String::new(),
+          new_version: String::new(),
+          range: String::new(),
+          updated: false,
+          error: Some(format!("Package not installed: {}", name)),
+        }]);
+      }
+    }
+
+    let mut lock = Lockfile::load(install_dir)?;
+    let mut results = Vec::new();
+
+    for pkg in installed {
+      let range = lock.range_for(&pkg.package, &pkg.version);
+      let mut result = UpdateResponse {
+        package: pkg.package.clone(),
+        old_version: pkg.version.clone(),
+        new_version: pkg.version.clone(),
+        range: range.clone(),
+        updated: false,
+        error: None,
+      };
+
+      if is_pinned(&range) {
+        results.push(result);
+        continue;
+      }
+
+      let resolved =
+        match self.resolve_package(&format!("{}@{}", pkg.package, range)) {
+          Ok(resolved) => resolved,
+          Err(e) => {
+            result.error = Some(e.to_string());
+            results.push(result);
+            continue;
+          }
+        };
+
+      let newer = match (
+        Version::parse(&resolved.resolved_version),
+        Version::parse(&pkg.version),
+      ) {
+        (Ok(best), Ok(current)) => best > current,
+        _ => resolved.resolved_version != pkg.version,
+      };
+      if !newer {
+        results.push(result);
+        continue;
+      }
+
+      let tarball_bytes = match self.download_verified(&resolved)? {
+        Ok(bytes) => bytes,
+        Err(error) => {
+          result.error = Some(error);
+          results.push(result);
+          continue;
+        }
+      };
+      if let Err(e) =
+        unpack_package(&resolved.package, install_dir, tarball_bytes, true)
+      {
+        result.error = Some(e.to_string());
+        results.push(result);
+        continue;
+      }
+
+      lock.packages.insert(
+        resolved.package.clone(),
+        LockedPackage {
+          version: resolved.resolved_version.clone(),
+          range,
+          shasum: resolved.shasum.clone(),
+        },
+      );
+      // Saved after every upgrade so the lockfile matches the install dir
+      // even if a later package fails.
+      lock.save(install_dir)?;
+
+      result.new_version = resolved.resolved_version;
+      result.updated = true;
+      results.push(result);
+    }
+
+    Ok(results)
+  }
+
   pub fn seed_packages(
     &self,
     install_dir: &str,
@@ -799,3 +1153,44 @@ impl NpmRegistry {
     Ok(results)
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::path::PathBuf;
+
+  fn offline_registry() -> NpmRegistry {
+    NpmRegistry {
+      client: Client::new(),
+      registry_url: "https://registry.npmjs.org".to_string(),
+      cache: RegistryCache::new(PathBuf::from("/nonexistent/tpm-cache")),
+      offline: true,
+    }
+  }
+
+  #[test]
+  fn offline_misses_name_the_package_and_cache() {
+    let registry = offline_registry();
+    let err = registry.resolve_package("@trex/my-plugin@1.0.0").unwrap_err();
+    assert_eq!(
+      err.to_string(),
+      "Error: Package @trex/my-plugin is not in the offline cache at \
+       /nonexistent/tpm-cache (TPM_OFFLINE is set)"
+    );
+
+    let resolved = ResolveResponse {
+      package: "@trex/my-plugin".to_string(),
+      resolved_version: "1.0.0".to_string(),
+      tarball_url: "https://registry.npmjs.org/unused.tgz".to_string(),
+      dependencies: Default::default(),
+      peer_dependencies: Default::default(),
+      shasum: None,
+    };
+    let err = registry.download_verified(&resolved).unwrap().unwrap_err();
+    assert!(
+      err.starts_with("Error: Tarball of @trex/my-plugin@1.0.0 is not in the"),
+      "{}",
+      err
+    );
+  }
+}
@@ -59,10 +59,20 @@ pub struct NpmVersionMetadata {
   pub dependencies: HashMap<String, String>,
   #[serde(rename = "devDependencies", default)]
   pub dev_dependencies: HashMap<String, String>,
+  #[serde(rename = "peerDependencies", default)]
+  pub peer_dependencies: HashMap<String, String>,
+  #[serde(rename = "peerDependenciesMeta", default)]
+  pub peer_dependencies_meta: HashMap<String, PeerDependencyMeta>,
   #[serde(default)]
   pub dist: Option<DistInfo>,
 }
 
+#[derive(Debug, Deserialize, Default)]
+pub struct PeerDependencyMeta {
+  #[serde(default)]
+  pub optional: bool,
+}
+
 #[derive(Debug, Serialize)]
 pub struct PackageInfoResponse {
   pub name: String,
@@ -98,6 +108,9 @@ pub struct ResolveResponse {
   pub resolved_version: String,
   pub tarball_url: String,
   pub dependencies: HashMap<String, String>,
+  // Required peers only; optional ones never produce warnings.
+  #[serde(skip_serializing_if = "HashMap::is_empty")]
+  pub peer_dependencies: HashMap<String, String>,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub shasum: Option<String>,
 }
@@ -110,6 +123,8 @@ pub struct InstallResponse {
   pub success: bool,
   #[serde(skip_serializing_if = "Option::is_none")]
   pub error: Option<String>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub peer_warnings: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -129,6 +144,7 @@ pub struct DependencyTreeResponse {
   pub depth: usize,
   pub parent: Option<String>,
   pub tree_line: String,
+  pub peer_warnings: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -148,6 +164,17 @@ pub struct SeedResponse {
   pub error: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct UpdateResponse {
+  pub package: String,
+  pub old_version: String,
+  pub new_version: String,
+  pub range: String,
+  pub updated: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct DeleteResponse {
   pub package: String,